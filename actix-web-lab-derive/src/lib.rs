@@ -3,6 +3,9 @@
 use quote::{format_ident, quote};
 use syn::{parse_macro_input, punctuated::Punctuated, token::Comma, DeriveInput, Ident};
 
+mod multipart_form;
+mod route;
+
 /// Derive a `FromRequest` implementation for an aggregate struct extractor.
 ///
 /// All fields of the struct need to implement `FromRequest` unless they are marked with annotations
@@ -153,3 +156,84 @@ pub fn derive_from_request(input: proc_macro::TokenStream) -> proc_macro::TokenS
 
     proc_macro::TokenStream::from(output)
 }
+
+/// Derive a `MultipartForm` extractor implementation for a struct of form fields.
+///
+/// Each field of the struct is read from the form field of the same name. Fields can be annotated
+/// with `#[multipart(rename = "name")]` to read a differently named form field and with
+/// `#[multipart(limit = "1MiB")]` to limit its size. The struct can be annotated with
+/// `#[multipart(deny_unknown_fields)]` to reject forms containing other fields.
+///
+/// See the `MultipartForm` extractor docs in `actix-web-lab` for supported field types.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, Responder};
+/// use actix_web_lab::extract::{MultipartForm, MultipartText, TempFile};
+///
+/// #[derive(MultipartForm)]
+/// #[multipart(deny_unknown_fields)]
+/// struct Avatar {
+///     #[multipart(rename = "user")]
+///     user_id: MultipartText<u64>,
+///
+///     #[multipart(limit = "2MiB")]
+///     image: TempFile,
+/// }
+///
+/// #[post("/avatar")]
+/// async fn upload(form: MultipartForm<Avatar>) -> impl Responder {
+///     format!("received {} bytes for user {}", form.image.size(), *form.user_id)
+/// }
+/// ```
+#[proc_macro_derive(MultipartForm, attributes(multipart))]
+pub fn derive_multipart_form(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    multipart_form::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+macro_rules! route_macro {
+    ($name:ident, $method:literal, $guard:literal) => {
+        #[doc = concat!("Creates a resource handler for `", $method, "` requests, with optional body size and timeout policies.")]
+        ///
+        /// Accepts the path of the resource followed by any of these options:
+        /// - `body_limit = "10MB"`: maximum request body size. Decimal (`KB`, `MB`, `GB`) and binary
+        ///   (`KiB`, `MiB`, `GiB`) units are supported, as are integer byte counts. Also sets the
+        ///   limits of the `Bytes`, `String`, `Json`, and `Form` extractors for this route.
+        /// - `timeout = "30s"`: maximum time for the handler to respond. Supports `ms`, `s`, and
+        ///   `m` units.
+        ///
+        /// Policies are enforced by the `RoutePolicy` middleware, which responds with
+        /// `413 Payload Too Large` and `503 Service Unavailable`, respectively.
+        ///
+        /// # Examples
+        /// ```
+        /// use actix_web::{web, App, Responder};
+        #[doc = concat!("use actix_web_lab::", stringify!($name), ";")]
+        ///
+        #[doc = concat!("#[", stringify!($name), r#"("/upload", body_limit = "10MB", timeout = "30s")]"#)]
+        /// async fn upload(body: web::Bytes) -> impl Responder {
+        ///     format!("received {} bytes", body.len())
+        /// }
+        ///
+        /// App::new().service(upload)
+        /// # ;
+        /// ```
+        #[proc_macro_attribute]
+        pub fn $name(
+            args: proc_macro::TokenStream,
+            item: proc_macro::TokenStream,
+        ) -> proc_macro::TokenStream {
+            route::expand($guard, args.into(), item.into()).into()
+        }
+    };
+}
+
+route_macro!(get, "GET", "Get");
+route_macro!(post, "POST", "Post");
+route_macro!(put, "PUT", "Put");
+route_macro!(patch, "PATCH", "Patch");
+route_macro!(delete, "DELETE", "Delete");
@@ -0,0 +1,145 @@
+//! Derive macro for typed multipart forms.
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{DeriveInput, LitInt, LitStr};
+
+use crate::route::parse_size;
+
+/// Options of a struct field, from its `#[multipart(...)]` attributes.
+struct FieldOpts {
+    name: String,
+    limit: Option<u64>,
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = input.ident;
+
+    let mut deny_unknown_fields = false;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("multipart"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("deny_unknown_fields") {
+                deny_unknown_fields = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported multipart attribute, expected `deny_unknown_fields`"))
+            }
+        })?;
+    }
+
+    let fields = match input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "Deriving MultipartForm is only supported on structs with named fields.",
+            ))
+        }
+    };
+
+    let mut read_arms = Vec::with_capacity(fields.len());
+    let mut take_fields = Vec::with_capacity(fields.len());
+
+    for field in &fields {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+
+        let mut opts = FieldOpts {
+            name: ident.to_string(),
+            limit: None,
+        };
+
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("multipart"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    opts.name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else if meta.path.is_ident("limit") {
+                    let value = meta.value()?;
+
+                    let limit = if value.peek(LitInt) {
+                        value.parse::<LitInt>()?.base10_parse()?
+                    } else {
+                        let lit = value.parse::<LitStr>()?;
+                        parse_size(&lit.value()).ok_or_else(|| {
+                            syn::Error::new(
+                                lit.span(),
+                                r#"invalid limit, expected a size such as "512KiB" or "10MB""#,
+                            )
+                        })?
+                    };
+
+                    opts.limit = Some(limit);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported multipart attribute, expected `rename` or `limit`"))
+                }
+            })?;
+        }
+
+        let field_name = &opts.name;
+
+        let limit = match opts.limit {
+            Some(limit) => {
+                let limit = Literal::u64_unsuffixed(limit);
+                quote! { ::std::option::Option::Some(#limit) }
+            }
+            None => quote! { ::std::option::Option::None },
+        };
+
+        read_arms.push(quote! {
+            #field_name => ::std::boxed::Box::pin(
+                __mp::read_field::<#ty>(req, state, field, limits, #limit)
+            ),
+        });
+
+        take_fields.push(quote! {
+            #ident: __mp::take_field::<#ty>(&mut state, #field_name)?,
+        });
+    }
+
+    Ok(quote! {
+        impl ::actix_web_lab::__reexports::multipart::MultipartCollect for #name {
+            fn handle_field<'t>(
+                req: &'t ::actix_web_lab::__reexports::actix_web::HttpRequest,
+                field: ::actix_web_lab::__reexports::multipart::Field,
+                limits: &'t mut ::actix_web_lab::__reexports::multipart::FieldLimits,
+                state: &'t mut ::actix_web_lab::__reexports::multipart::MultipartState,
+            ) -> ::actix_web_lab::__reexports::multipart::LocalBoxFuture<
+                't,
+                ::std::result::Result<(), ::actix_web_lab::__reexports::multipart::MultipartFormError>,
+            > {
+                use ::actix_web_lab::__reexports::multipart as __mp;
+
+                let name = __mp::field_name(&field).to_owned();
+
+                match name.as_str() {
+                    #(#read_arms)*
+                    _ => ::std::boxed::Box::pin(__mp::skip_field(field, limits, #deny_unknown_fields)),
+                }
+            }
+
+            fn from_state(
+                mut state: ::actix_web_lab::__reexports::multipart::MultipartState,
+            ) -> ::std::result::Result<Self, ::actix_web_lab::__reexports::multipart::MultipartFormError> {
+                use ::actix_web_lab::__reexports::multipart as __mp;
+
+                ::std::result::Result::Ok(Self {
+                    #(#take_fields)*
+                })
+            }
+        }
+    })
+}
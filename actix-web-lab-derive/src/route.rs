@@ -0,0 +1,202 @@
+//! Route attribute macros with per-route policies.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens as _};
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, ItemFn, LitInt, LitStr, Token,
+};
+
+/// Arguments of a route macro, e.g. `"/path", body_limit = "10MB", timeout = "30s"`.
+pub(crate) struct RouteArgs {
+    path: LitStr,
+    body_limit: Option<u64>,
+    timeout_ms: Option<u64>,
+}
+
+impl Parse for RouteArgs {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let path = input.parse::<LitStr>().map_err(|err| {
+            syn::Error::new(
+                err.span(),
+                r#"invalid route definition, expected #[<method>("<path>")]"#,
+            )
+        })?;
+
+        let mut args = Self {
+            path,
+            body_limit: None,
+            timeout_ms: None,
+        };
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+
+            // allow trailing comma
+            if input.is_empty() {
+                break;
+            }
+
+            let name = input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+
+            if name == "body_limit" {
+                let limit = if input.peek(LitInt) {
+                    input.parse::<LitInt>()?.base10_parse()?
+                } else {
+                    let lit = input.parse::<LitStr>()?;
+                    parse_size(&lit.value()).ok_or_else(|| {
+                        syn::Error::new(
+                            lit.span(),
+                            r#"invalid body limit, expected a size such as "512KiB" or "10MB""#,
+                        )
+                    })?
+                };
+
+                args.body_limit = Some(limit);
+            } else if name == "timeout" {
+                let lit = input.parse::<LitStr>()?;
+                let timeout_ms = parse_duration_ms(&lit.value()).ok_or_else(|| {
+                    syn::Error::new(
+                        lit.span(),
+                        r#"invalid timeout, expected a duration such as "500ms", "30s", or "2m""#,
+                    )
+                })?;
+
+                args.timeout_ms = Some(timeout_ms);
+            } else {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "unknown route option, expected `body_limit` or `timeout`",
+                ));
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Parses a size with an optional decimal (`KB`, `MB`, `GB`) or binary (`KiB`, `MiB`, `GiB`) unit.
+pub(crate) fn parse_size(val: &str) -> Option<u64> {
+    let val = val.trim();
+    let split = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    let (num, unit) = val.split_at(split);
+
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "KB" | "kB" => 1_000,
+        "KiB" => 1 << 10,
+        "MB" => 1_000_000,
+        "MiB" => 1 << 20,
+        "GB" => 1_000_000_000,
+        "GiB" => 1 << 30,
+        _ => return None,
+    };
+
+    num.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Parses a duration in milliseconds (`ms`), seconds (`s`), or minutes (`m`).
+fn parse_duration_ms(val: &str) -> Option<u64> {
+    let val = val.trim();
+    let split = val.find(|c: char| !c.is_ascii_digit())?;
+    let (num, unit) = val.split_at(split);
+
+    let multiplier = match unit.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        _ => return None,
+    };
+
+    num.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+pub(crate) fn expand(guard: &str, args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match syn::parse2::<RouteArgs>(args) {
+        Ok(args) => args,
+        Err(err) => return input_and_compile_error(item, err),
+    };
+
+    let mut func = match syn::parse2::<ItemFn>(item.clone()) {
+        Ok(func) => func,
+        Err(err) => return input_and_compile_error(item, err),
+    };
+
+    if func.sig.asyncness.is_none() {
+        let err =
+            syn::Error::new_spanned(func.sig.fn_token, "only support async fn as route handlers");
+        return input_and_compile_error(item, err);
+    }
+
+    let name = func.sig.ident.clone();
+    let vis = func.vis.clone();
+    let path = &args.path;
+    let guard = Ident::new(guard, Span::call_site());
+
+    // doc comments describe the generated service, other attributes stay on the handler
+    let (doc_attrs, fn_attrs) = func
+        .attrs
+        .into_iter()
+        .partition::<Vec<_>, _>(|attr| attr.path().is_ident("doc"));
+    func.attrs = fn_attrs;
+
+    let web = quote! { ::actix_web_lab::__reexports::actix_web::web };
+
+    let body_limit_data = args.body_limit.map(|limit| {
+        let limit = limit as usize;
+
+        quote! {
+            .app_data(#web::PayloadConfig::new(#limit))
+            .app_data(#web::JsonConfig::default().limit(#limit))
+            .app_data(#web::FormConfig::default().limit(#limit))
+        }
+    });
+
+    let policy = (args.body_limit.is_some() || args.timeout_ms.is_some()).then(|| {
+        let body_limit = args.body_limit.map(|limit| {
+            let limit = limit as usize;
+            quote! { .body_limit(#limit) }
+        });
+
+        let timeout = args.timeout_ms.map(|ms| {
+            quote! { .timeout(::std::time::Duration::from_millis(#ms)) }
+        });
+
+        quote! {
+            .wrap(::actix_web_lab::middleware::RoutePolicy::new() #body_limit #timeout)
+        }
+    });
+
+    let func = func.into_token_stream();
+
+    quote! {
+        #(#doc_attrs)*
+        #[allow(non_camel_case_types, missing_docs)]
+        #vis struct #name;
+
+        impl ::actix_web_lab::__reexports::actix_web::dev::HttpServiceFactory for #name {
+            fn register(self, config: &mut ::actix_web_lab::__reexports::actix_web::dev::AppService) {
+                #func
+
+                let resource = ::actix_web_lab::__reexports::actix_web::Resource::new(#path)
+                    .name(::std::stringify!(#name))
+                    .guard(::actix_web_lab::__reexports::actix_web::guard::#guard())
+                    #body_limit_data
+                    #policy
+                    .to(#name);
+
+                ::actix_web_lab::__reexports::actix_web::dev::HttpServiceFactory::register(
+                    resource, config,
+                );
+            }
+        }
+    }
+}
+
+/// Emits the original item alongside the error so that uses of the handler do not produce
+/// additional, confusing errors.
+fn input_and_compile_error(mut item: TokenStream, err: syn::Error) -> TokenStream {
+    item.extend(err.to_compile_error());
+    item
+}
@@ -0,0 +1,217 @@
+use actix_web::{http::StatusCode, test, web, App, HttpResponse, Responder};
+use actix_web_lab::{
+    extract::{MultipartForm, MultipartFormConfig, MultipartText, TempFile},
+    test::MultipartBuilder,
+};
+use tokio::io::AsyncReadExt as _;
+
+#[derive(MultipartForm)]
+struct Upload {
+    #[multipart(limit = "8B")]
+    title: MultipartText<String>,
+
+    count: Option<MultipartText<u32>>,
+
+    #[multipart(rename = "tag")]
+    tags: Vec<String>,
+
+    #[multipart(limit = 64)]
+    file: TempFile,
+}
+
+#[derive(MultipartForm)]
+#[multipart(deny_unknown_fields)]
+struct Strict {
+    #[allow(dead_code)]
+    title: MultipartText<String>,
+}
+
+#[derive(MultipartForm)]
+struct Attachments {
+    files: Vec<TempFile>,
+}
+
+async fn upload(MultipartForm(form): MultipartForm<Upload>) -> impl Responder {
+    let mut contents = String::new();
+    let mut file = form.file.open().await.unwrap();
+    file.read_to_string(&mut contents).await.unwrap();
+
+    HttpResponse::Ok().body(format!(
+        "{} {:?} {:?} {} {} {}",
+        *form.title,
+        form.count.map(MultipartText::into_inner),
+        form.tags,
+        form.file.file_name().unwrap(),
+        form.file.content_type().unwrap(),
+        contents,
+    ))
+}
+
+async fn strict(_form: MultipartForm<Strict>) -> impl Responder {
+    HttpResponse::Ok()
+}
+
+async fn call(form: MultipartBuilder, path: &str) -> (StatusCode, String) {
+    let app = test::init_service(
+        App::new()
+            .app_data(MultipartFormConfig::default().limit(128))
+            .route("/upload", web::post().to(upload))
+            .route("/strict", web::post().to(strict)),
+    )
+    .await;
+
+    let req = form
+        .into_test_request(test::TestRequest::post().uri(path))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    let status = res.status();
+    let body = test::read_body(res).await;
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[actix_web::test]
+async fn extracts_fields() {
+    let form = MultipartBuilder::new()
+        .text("title", "hello")
+        .text("tag", "a")
+        .text("ignored", "?")
+        .text("tag", "b")
+        .file("file", "notes.txt", "text/plain", "file contents");
+    let (status, body) = call(form, "/upload").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        r#"hello None ["a", "b"] notes.txt text/plain file contents"#
+    );
+
+    let form = MultipartBuilder::new()
+        .text("title", "hello")
+        .text("count", "3")
+        .file("file", "notes.txt", "text/plain", "");
+    let (status, body) = call(form, "/upload").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"hello Some(3) [] notes.txt text/plain "#);
+}
+
+#[actix_web::test]
+async fn field_errors() {
+    let cases = [
+        (
+            MultipartBuilder::new().file("file", "a.txt", "text/plain", ""),
+            "/upload",
+            StatusCode::BAD_REQUEST,
+            "Field `title` is missing.",
+        ),
+        (
+            MultipartBuilder::new().text("title", "far too long").file(
+                "file",
+                "a.txt",
+                "text/plain",
+                "",
+            ),
+            "/upload",
+            StatusCode::BAD_REQUEST,
+            "Field `title` has exceeded limit (8 bytes).",
+        ),
+        (
+            MultipartBuilder::new()
+                .text("title", "a")
+                .text("title", "b"),
+            "/upload",
+            StatusCode::BAD_REQUEST,
+            "Field `title` was sent more than once.",
+        ),
+        (
+            MultipartBuilder::new()
+                .text("title", "a")
+                .text("count", "three"),
+            "/upload",
+            StatusCode::BAD_REQUEST,
+            "Field `count` is invalid: invalid digit found in string",
+        ),
+        (
+            MultipartBuilder::new()
+                .text("title", "a")
+                .text("extra", "b"),
+            "/strict",
+            StatusCode::BAD_REQUEST,
+            "Field `extra` is not expected.",
+        ),
+        (
+            MultipartBuilder::new()
+                .text("title", "a")
+                .text("tag", "x".repeat(200)),
+            "/upload",
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Multipart payload has exceeded limit (128 bytes).",
+        ),
+    ];
+
+    for (form, path, status, message) in cases {
+        let (res_status, body) = call(form, path).await;
+        assert_eq!(res_status, status, "{body}");
+        assert_eq!(body, message);
+    }
+}
+
+#[actix_web::test]
+async fn spools_files_to_disk() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dest_dir = tempfile::tempdir_in(temp_dir.path()).unwrap();
+    let dest = dest_dir.path().join("kept.txt");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(
+                MultipartFormConfig::default()
+                    .temp_dir(temp_dir.path())
+                    .file_limit(16),
+            )
+            .app_data(web::Data::new(dest.clone()))
+            .route(
+                "/",
+                web::post().to(
+                    |MultipartForm(form): MultipartForm<Attachments>,
+                     dest: web::Data<std::path::PathBuf>| async move {
+                        let mut files = form.files.into_iter();
+
+                        let kept = files.next().unwrap();
+                        kept.persist(dest.as_path()).await.unwrap();
+
+                        // remaining temporary files are deleted when dropped
+                        let dropped = files.next().unwrap();
+                        let dropped_path = dropped.path().to_owned();
+                        drop(dropped);
+                        assert!(!dropped_path.exists());
+
+                        HttpResponse::Ok()
+                    },
+                ),
+            ),
+    )
+    .await;
+
+    let req = MultipartBuilder::new()
+        .file("files", "a.txt", "text/plain", "kept")
+        .file("files", "b.txt", "text/plain", "dropped")
+        .into_test_request(test::TestRequest::post())
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(std::fs::read_to_string(&dest).unwrap(), "kept");
+
+    let req = MultipartBuilder::new()
+        .file("files", "big.txt", "text/plain", "x".repeat(17))
+        .into_test_request(test::TestRequest::post())
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        test::read_body(res).await,
+        "Field `files` has exceeded limit (16 bytes).",
+    );
+
+    // only the persisted file and its directory remain
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
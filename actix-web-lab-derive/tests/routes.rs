@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use actix_web::{http::StatusCode, test, web, App, HttpResponse, Responder};
+use actix_web_lab::{get, post};
+
+/// Echoes the request body.
+#[post("/echo", body_limit = "4B")]
+async fn echo(body: web::Bytes) -> impl Responder {
+    HttpResponse::Ok().body(body)
+}
+
+#[post("/json", body_limit = 16)]
+async fn json(body: web::Json<serde_json::Value>) -> impl Responder {
+    HttpResponse::Ok().json(body.into_inner())
+}
+
+#[get("/slow", timeout = "10ms")]
+async fn slow() -> impl Responder {
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    HttpResponse::Ok()
+}
+
+#[get("/plain")]
+async fn plain() -> impl Responder {
+    HttpResponse::Ok()
+}
+
+#[actix_web::test]
+async fn route_policies() {
+    let app = test::init_service(
+        App::new()
+            .service(echo)
+            .service(json)
+            .service(slow)
+            .service(plain),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/echo")
+        .set_payload("abcd")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let req = test::TestRequest::post()
+        .uri("/echo")
+        .set_payload("abcde")
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let req = test::TestRequest::post()
+        .uri("/json")
+        .set_json(serde_json::json!({ "a": "0123456789abcdef" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let req = test::TestRequest::get().uri("/slow").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // method guard
+    let req = test::TestRequest::get().uri("/echo").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::get().uri("/plain").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
@@ -0,0 +1,141 @@
+use actix_web::{
+    body::BoxBody,
+    test::{self, TestRequest},
+    web, App, HttpRequest, HttpResponse,
+};
+use actix_web_lab::{
+    bench_support::{self, ChunkPattern},
+    middleware::map_response_body,
+    ndjson,
+    respond::NdJson,
+    sse::{self, Sse},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BODY_SIZES: &[usize] = &[1024, 64 * 1024, 1024 * 1024];
+const ITEM_COUNTS: &[usize] = &[10, 1_000];
+const STREAM_SIZES: &[usize] = &[1024 * 1024, 8 * 1024 * 1024];
+
+fn map_response_body_passthrough(c: &mut Criterion) {
+    let rt = actix_web::rt::System::new();
+
+    let mut group = c.benchmark_group("map_response_body");
+
+    for &size in BODY_SIZES {
+        let app = rt.block_on(test::init_service(
+            App::new()
+                .wrap(map_response_body(
+                    |_req: HttpRequest, body: BoxBody| async move { Ok(body) },
+                ))
+                .default_service(web::to(move || async move {
+                    HttpResponse::Ok().body(bench_support::body(size, ChunkPattern::Uniform(4096)))
+                })),
+        ));
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| {
+                rt.block_on(async {
+                    let res = test::call_service(&app, TestRequest::default().to_request()).await;
+                    bench_support::drain(res.into_body()).await
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn ndjson_encoding(c: &mut Criterion) {
+    let rt = actix_web::rt::System::new();
+
+    let mut group = c.benchmark_group("ndjson");
+
+    for &count in ITEM_COUNTS {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter(|| {
+                let body = NdJson::new(bench_support::json_items(count, 64)).into_body_stream();
+                rt.block_on(bench_support::drain(body))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn ndjson_decoding(c: &mut Criterion) {
+    let rt = actix_web::rt::System::new();
+
+    let mut group = c.benchmark_group("ndjson_decoder");
+
+    for &size in STREAM_SIZES {
+        // ~100 byte lines, split into chunks which do not align with line boundaries
+        let count = size / 100;
+        let body = rt.block_on(actix_web::body::to_bytes(
+            NdJson::new(bench_support::json_items(count, 80)).into_body_stream(),
+        ));
+        let body = body.unwrap_or_else(|_| unreachable!());
+
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &body, |b, body| {
+            b.iter(|| {
+                let mut decoder = ndjson::Decoder::<serde_json::Value>::new();
+                let mut items = 0;
+
+                for chunk in body.chunks(16 * 1024 - 1) {
+                    decoder.feed(chunk);
+
+                    while let Some(item) = decoder.next_item() {
+                        item.unwrap();
+                        items += 1;
+                    }
+                }
+
+                assert_eq!(items, count);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn sse_encoding(c: &mut Criterion) {
+    let rt = actix_web::rt::System::new();
+
+    let mut group = c.benchmark_group("sse");
+
+    for &count in ITEM_COUNTS {
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            b.iter(|| {
+                let body = Sse::from_stream(bench_support::sse_events(count, 64));
+                rt.block_on(bench_support::drain(body))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn sse_encoder(c: &mut Criterion) {
+    let event: sse::Event =
+        sse::Data::new(String::from_utf8(bench_support::bytes(64).to_vec()).unwrap())
+            .id("42")
+            .event("tick")
+            .into();
+
+    let mut encoder = sse::Encoder::new();
+
+    c.bench_function("sse_encoder", |b| b.iter(|| encoder.encode(&event)));
+}
+
+criterion_group!(
+    benches,
+    map_response_body_passthrough,
+    ndjson_encoding,
+    ndjson_decoding,
+    sse_encoding,
+    sse_encoder
+);
+criterion_main!(benches);
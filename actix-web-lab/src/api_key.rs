@@ -0,0 +1,245 @@
+//! API key extractor.
+//!
+//! See [`ApiKey`] docs.
+
+use std::fmt;
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::Payload,
+    http::{header::HeaderName, StatusCode},
+    web, FromRequest, HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+use crate::uri::decode_query_component;
+
+/// Default header from which API keys are read.
+const X_API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Extractor for API keys sent in a request header or query parameter.
+///
+/// Where keys are read from is configured by registering an [`ApiKeyConfig`]. Without one, keys
+/// are read from the `X-Api-Key` header.
+///
+/// Only the presence of a key is checked; handlers are responsible for validating it.
+///
+/// # Extractor
+/// Extraction fails with an [`ApiKeyError`] if no non-empty key is found in any of the configured
+/// locations. Use `Option<ApiKey>` for endpoints where keys are optional.
+///
+/// The key is omitted from `Debug` output to avoid leaking it into logs.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::header::HeaderName, App, Responder};
+/// use actix_web_lab::extract::{ApiKey, ApiKeyConfig};
+///
+/// #[get("/")]
+/// async fn index(key: ApiKey) -> impl Responder {
+///     format!("key is {} bytes long", key.as_str().len())
+/// }
+///
+/// App::new()
+///     .app_data(
+///         ApiKeyConfig::new()
+///             .header(HeaderName::from_static("x-api-key"))
+///             .query_param("api_key"),
+///     )
+///     .service(index)
+/// # ;
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey(String);
+
+impl ApiKey {
+    /// Returns API key as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps into inner API key.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ApiKey").field(&"..").finish()
+    }
+}
+
+impl FromRequest for ApiKey {
+    type Error = ApiKeyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            ApiKeyConfig::from_req(req)
+                .find(req)
+                .map(Self)
+                .ok_or_else(|| {
+                    debug!(
+                        "Failed to extract `ApiKey` for `{}` handler. No API key found in request.",
+                        req.match_name().unwrap_or_else(|| req.path())
+                    );
+
+                    ApiKeyError::Missing
+                }),
+        )
+    }
+}
+
+/// Errors that can occur when extracting an [`ApiKey`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ApiKeyError {
+    /// Request has no API key.
+    #[display(fmt = "API key is missing.")]
+    Missing,
+}
+
+impl ResponseError for ApiKeyError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+/// Location of an API key in a request.
+#[derive(Debug, Clone)]
+enum Location {
+    Header(HeaderName),
+    QueryParam(String),
+}
+
+/// Configuration for the [`ApiKey`] extractor.
+///
+/// Locations are searched in the order they are added. Adding any location replaces the default
+/// `X-Api-Key` header.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyConfig {
+    locations: Vec<Location>,
+}
+
+static DEFAULT_CONFIG: ApiKeyConfig = ApiKeyConfig {
+    locations: Vec::new(),
+};
+
+impl ApiKeyConfig {
+    /// Constructs new config which reads keys from the `X-Api-Key` header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header from which keys are read.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        self.locations.push(Location::Header(name));
+        self
+    }
+
+    /// Adds a query parameter from which keys are read.
+    ///
+    /// Keys in query strings are more likely to be leaked through logs and browser history than
+    /// those in headers, so prefer headers where clients support them.
+    pub fn query_param(mut self, name: impl Into<String>) -> Self {
+        self.locations.push(Location::QueryParam(name.into()));
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    /// Returns first non-empty key found in configured locations.
+    fn find(&self, req: &HttpRequest) -> Option<String> {
+        if self.locations.is_empty() {
+            return header_key(req, &X_API_KEY);
+        }
+
+        self.locations.iter().find_map(|location| match location {
+            Location::Header(name) => header_key(req, name),
+            Location::QueryParam(name) => query_key(req, name),
+        })
+    }
+}
+
+fn header_key(req: &HttpRequest, name: &HeaderName) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|val| val.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_owned)
+}
+
+fn query_key(req: &HttpRequest, name: &str) -> Option<String> {
+    req.query_string().split('&').find_map(|pair| {
+        let (key, val) = pair.split_once('=')?;
+
+        if decode_query_component(key).ok()? != name {
+            return None;
+        }
+
+        let val = decode_query_component(val).ok()?;
+        (!val.is_empty()).then(|| val.into_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn default_header() {
+        let req = TestRequest::default()
+            .insert_header(("x-api-key", "s3cr3t"))
+            .to_http_request();
+        let key = ApiKey::extract(&req).await.unwrap();
+        assert_eq!(key.as_str(), "s3cr3t");
+        assert_eq!(format!("{key:?}"), r#"ApiKey("..")"#);
+
+        let req = TestRequest::default()
+            .uri("/?api_key=s3cr3t")
+            .to_http_request();
+        let err = ApiKey::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn configured_locations() {
+        let config = ApiKeyConfig::new()
+            .header(HeaderName::from_static("x-key"))
+            .query_param("api_key");
+
+        let req = TestRequest::default()
+            .uri("/?api_key=from%20query")
+            .insert_header(("x-key", "from-header"))
+            .app_data(config.clone())
+            .to_http_request();
+        let key = ApiKey::extract(&req).await.unwrap();
+        assert_eq!(key.into_inner(), "from-header");
+
+        let req = TestRequest::default()
+            .uri("/?other=1&api_key=from%20query")
+            .insert_header(("x-key", ""))
+            .app_data(web::Data::new(config.clone()))
+            .to_http_request();
+        let key = ApiKey::extract(&req).await.unwrap();
+        assert_eq!(key.into_inner(), "from query");
+
+        let req = TestRequest::default()
+            .uri("/?api_key=")
+            .insert_header(("x-api-key", "default"))
+            .app_data(config)
+            .to_http_request();
+        assert!(ApiKey::extract(&req).await.is_err());
+    }
+}
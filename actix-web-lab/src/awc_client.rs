@@ -0,0 +1,756 @@
+//! Outgoing request builder with context propagation, retries, and circuit breaking.
+//!
+//! See [`LabClient`] docs.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use actix_http::BoxedPayloadStream;
+use actix_web::{
+    http::{
+        header::{HeaderMap, HeaderName, TryIntoHeaderPair},
+        Method, StatusCode,
+    },
+    web::Bytes,
+    HttpMessage as _, HttpRequest, ResponseError,
+};
+use awc::error::SendRequestError;
+use derive_more::{Display, Error};
+use tracing::debug;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    request_context::RequestContext,
+    request_id::RequestId,
+};
+
+/// Response type returned by [`OutgoingRequest`].
+pub type ClientResponse = awc::ClientResponse;
+
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+const TRACESTATE: HeaderName = HeaderName::from_static("tracestate");
+
+/// An `awc` client wrapper which applies retry and circuit breaker policies to outgoing requests.
+///
+/// Requests built with [`propagate()`](OutgoingRequest::propagate) carry the request ID, trace
+/// context, and deadline of the incoming request they are made on behalf of, mirroring the
+/// crate's inbound middleware:
+/// - the `X-Request-Id` header is set from the [`RequestContext`] or [`RequestId`] if either is
+///   available, otherwise it is copied from the incoming request;
+/// - `traceparent` and `tracestate` headers are copied from the incoming request; and
+/// - the [`RequestContext`] deadline bounds the timeout of each attempt and of retry backoff.
+///
+/// Counts of requests, attempts, and failures are recorded in [`ClientMetrics`], shared by all
+/// clones of the client.
+///
+/// # Examples
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use actix_web::{web, HttpRequest, HttpResponse};
+/// use actix_web_lab::client::{CircuitBreaker, ClientError, LabClient, RetryPolicy};
+///
+/// async fn profile(
+///     req: HttpRequest,
+///     client: web::Data<LabClient>,
+/// ) -> Result<HttpResponse, ClientError> {
+///     let mut res = client
+///         .get("http://users.internal/profile")
+///         .propagate(&req)
+///         .send()
+///         .await?;
+///
+///     let body = res.body().await.unwrap_or_default();
+///     Ok(HttpResponse::build(res.status()).body(body))
+/// }
+///
+/// let client = LabClient::new(awc::Client::new())
+///     .timeout(Duration::from_secs(2))
+///     .retry(RetryPolicy::idempotent())
+///     .circuit_breaker(CircuitBreaker::new(5, Duration::from_secs(30)));
+/// ```
+#[derive(Clone)]
+pub struct LabClient {
+    client: awc::Client,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+    breaker: Option<CircuitBreaker>,
+    metrics: ClientMetrics,
+}
+
+impl LabClient {
+    /// Wraps `client`, with no retries or circuit breaker.
+    pub fn new(client: awc::Client) -> Self {
+        Self {
+            client,
+            timeout: None,
+            retry: RetryPolicy::none(),
+            breaker: None,
+            metrics: ClientMetrics::default(),
+        }
+    }
+
+    /// Sets timeout of each attempt.
+    ///
+    /// When a request has a deadline, attempts are given at most the time remaining.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets retry policy.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets circuit breaker, which is shared by all clones of this client.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Returns metrics for requests made using this client and its clones.
+    pub fn metrics(&self) -> &ClientMetrics {
+        &self.metrics
+    }
+
+    /// Starts building a request.
+    pub fn request(&self, method: Method, url: impl Into<String>) -> OutgoingRequest {
+        OutgoingRequest {
+            client: self.clone(),
+            method,
+            url: url.into(),
+            headers: HeaderMap::new(),
+            deadline: None,
+        }
+    }
+
+    /// Starts building a `GET` request.
+    pub fn get(&self, url: impl Into<String>) -> OutgoingRequest {
+        self.request(Method::GET, url)
+    }
+
+    /// Starts building a `POST` request.
+    pub fn post(&self, url: impl Into<String>) -> OutgoingRequest {
+        self.request(Method::POST, url)
+    }
+
+    /// Starts building a `PUT` request.
+    pub fn put(&self, url: impl Into<String>) -> OutgoingRequest {
+        self.request(Method::PUT, url)
+    }
+
+    /// Starts building a `DELETE` request.
+    pub fn delete(&self, url: impl Into<String>) -> OutgoingRequest {
+        self.request(Method::DELETE, url)
+    }
+}
+
+impl fmt::Debug for LabClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LabClient")
+            .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
+            .field("breaker", &self.breaker)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An outgoing request builder created by [`LabClient`].
+#[derive(Debug)]
+pub struct OutgoingRequest {
+    client: LabClient,
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    deadline: Option<Instant>,
+}
+
+impl OutgoingRequest {
+    /// Inserts a header, replacing any that were set with an equivalent field name.
+    ///
+    /// Invalid headers are ignored.
+    pub fn insert_header(mut self, header: impl TryIntoHeaderPair) -> Self {
+        match header.try_into_pair() {
+            Ok((name, value)) => {
+                self.headers.insert(name, value);
+            }
+            Err(_) => debug!("invalid header ignored in outgoing request"),
+        }
+
+        self
+    }
+
+    /// Sets deadline by which the request, including retries, must complete.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Propagates request ID, trace context, and deadline from the incoming request `req`.
+    pub fn propagate(mut self, req: &HttpRequest) -> Self {
+        let ctx = req.extensions().get::<RequestContext>().cloned();
+
+        let request_id = ctx
+            .as_ref()
+            .and_then(|ctx| ctx.request_id())
+            .map(str::to_owned)
+            .or_else(|| {
+                req.extensions()
+                    .get::<RequestId>()
+                    .map(|id| id.as_str().to_owned())
+            })
+            .and_then(|id| id.parse().ok())
+            .or_else(|| req.headers().get(X_REQUEST_ID).cloned());
+
+        if let Some(request_id) = request_id {
+            self.headers.insert(X_REQUEST_ID, request_id);
+        }
+
+        for name in [TRACEPARENT, TRACESTATE] {
+            if let Some(value) = req.headers().get(&name) {
+                self.headers.insert(name, value.clone());
+            }
+        }
+
+        if let Some(deadline) = ctx.and_then(|ctx| ctx.deadline()) {
+            self.deadline = Some(self.deadline.map_or(deadline, |own| own.min(deadline)));
+        }
+
+        self
+    }
+
+    /// Sends request with an empty body.
+    pub async fn send(self) -> Result<ClientResponse, ClientError> {
+        self.send_body(Bytes::new()).await
+    }
+
+    /// Sends request with `body`, which is re-sent on retries.
+    pub async fn send_body(self, body: impl Into<Bytes>) -> Result<ClientResponse, ClientError> {
+        let body = body.into();
+        let client = &self.client;
+        let metrics = &client.metrics.inner;
+
+        metrics.requests.fetch_add(1, Ordering::Relaxed);
+        let start = Instant::now();
+
+        let mut retries = 0;
+
+        let res = loop {
+            if let Some(breaker) = &client.breaker {
+                if !breaker.try_acquire() {
+                    metrics.short_circuited.fetch_add(1, Ordering::Relaxed);
+                    break Err(ClientError::CircuitOpen);
+                }
+            }
+
+            let remaining = self
+                .deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+            if remaining == Some(Duration::ZERO) {
+                break Err(ClientError::DeadlineExceeded);
+            }
+
+            let mut req = client.client.request(self.method.clone(), &self.url);
+
+            for (name, value) in &self.headers {
+                req = req.insert_header((name.clone(), value.clone()));
+            }
+
+            let timeout = match (client.timeout, remaining) {
+                (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+                (timeout, remaining) => timeout.or(remaining),
+            };
+
+            if let Some(timeout) = timeout {
+                req = req.timeout(timeout);
+            }
+
+            // a timeout is caused by the deadline if the deadline was the tighter bound
+            let deadline_bound = remaining.is_some() && timeout == remaining;
+
+            metrics.attempts.fetch_add(1, Ordering::Relaxed);
+
+            let res = req.send_body(body.clone()).await;
+
+            let failed = match &res {
+                Ok(res) => res.status().is_server_error(),
+                Err(_) => true,
+            };
+
+            if let Some(breaker) = &client.breaker {
+                breaker.record(!failed);
+            }
+
+            let backoff = client.retry.backoff_for(retries);
+            let within_deadline = self
+                .deadline
+                .map_or(true, |deadline| Instant::now() + backoff < deadline);
+
+            if client.retry.should_retry(&self.method, &res, retries) && within_deadline {
+                debug!(
+                    "retrying {} {} after attempt {} failed",
+                    self.method,
+                    self.url,
+                    retries + 1
+                );
+
+                retries += 1;
+                metrics.retries.fetch_add(1, Ordering::Relaxed);
+                actix_web::rt::time::sleep(backoff).await;
+                continue;
+            }
+
+            break res
+                .map(|res| {
+                    // erase decoder type so response type does not depend on awc's features
+                    res.map_body(|_, payload| {
+                        let payload: BoxedPayloadStream = Box::pin(payload);
+                        actix_http::Payload::Stream { payload }
+                    })
+                })
+                .map_err(|err| match err {
+                    SendRequestError::Timeout if deadline_bound => ClientError::DeadlineExceeded,
+                    err => ClientError::Send(err),
+                });
+        };
+
+        match &res {
+            Ok(res) if !res.status().is_server_error() => {
+                metrics.successes.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {
+                metrics.failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        debug!(
+            method = %self.method,
+            url = %self.url,
+            status = ?res.as_ref().ok().map(|res| res.status()),
+            retries,
+            elapsed = ?start.elapsed(),
+            "outgoing request completed"
+        );
+
+        res
+    }
+}
+
+/// Retry policy for [`LabClient`] requests.
+///
+/// Requests are retried after connection errors, timeouts, and `502 Bad Gateway`,
+/// `503 Service Unavailable`, or `504 Gateway Timeout` responses. Backoff doubles after each
+/// retry, up to a maximum of 10 seconds.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    backoff: Duration,
+    idempotent_only: bool,
+}
+
+impl RetryPolicy {
+    /// Never retries requests.
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Retries requests with idempotent methods up to 2 times, starting with a 100ms backoff.
+    pub fn idempotent() -> Self {
+        Self::new(2)
+    }
+
+    /// Retries requests with idempotent methods up to `max_retries` times, starting with a 100ms
+    /// backoff.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            backoff: Duration::from_millis(100),
+            idempotent_only: true,
+        }
+    }
+
+    /// Sets backoff before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Allows retrying requests with non-idempotent methods, such as `POST`.
+    pub fn any_method(mut self) -> Self {
+        self.idempotent_only = false;
+        self
+    }
+
+    fn backoff_for(&self, retries: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2_u32.saturating_pow(retries))
+            .min(Duration::from_secs(10))
+    }
+
+    fn should_retry<T>(
+        &self,
+        method: &Method,
+        res: &Result<awc::ClientResponse<T>, SendRequestError>,
+        retries: u32,
+    ) -> bool {
+        if retries >= self.max_retries {
+            return false;
+        }
+
+        let idempotent = matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        );
+
+        if self.idempotent_only && !idempotent {
+            return false;
+        }
+
+        match res {
+            Ok(res) => matches!(
+                res.status(),
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Err(err) => matches!(
+                err,
+                SendRequestError::Connect(_)
+                    | SendRequestError::Send(_)
+                    | SendRequestError::Timeout
+            ),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+/// A circuit breaker which stops [`LabClient`] requests after repeated failures.
+///
+/// After `failure_threshold` consecutive failed attempts, where failures are errors and 5xx
+/// responses, the circuit opens and requests fail immediately with [`ClientError::CircuitOpen`].
+/// Once `cooldown` has elapsed, a single trial request is allowed through; the circuit closes if it
+/// succeeds and re-opens otherwise.
+///
+/// Cheap to clone; all clones share the same state.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    clock: Arc<dyn Clock>,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    /// Constructs new circuit breaker.
+    ///
+    /// # Panics
+    /// Panics if `failure_threshold` is zero.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        assert!(failure_threshold > 0, "failure threshold must be non-zero");
+
+        Self {
+            failure_threshold,
+            cooldown,
+            clock: Arc::new(SystemClock),
+            state: Arc::new(Mutex::new(BreakerState::default())),
+        }
+    }
+
+    /// Sets the clock used to determine when the cooldown has elapsed.
+    ///
+    /// Defaults to [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns true if the circuit is open, and requests are being rejected.
+    pub fn is_open(&self) -> bool {
+        self.state.lock().unwrap().opened_at.is_some()
+    }
+
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        match state.opened_at {
+            None => true,
+            Some(opened_at) if self.clock.now() - opened_at >= self.cooldown => {
+                !std::mem::replace(&mut state.trial_in_flight, true)
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+
+        if success {
+            *state = BreakerState::default();
+            return;
+        }
+
+        state.consecutive_failures += 1;
+
+        if state.trial_in_flight || state.consecutive_failures >= self.failure_threshold {
+            debug!("circuit breaker opened");
+            state.opened_at = Some(self.clock.now());
+            state.trial_in_flight = false;
+        }
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("cooldown", &self.cooldown)
+            .field("state", &*self.state.lock().unwrap())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    requests: AtomicU64,
+    attempts: AtomicU64,
+    retries: AtomicU64,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    short_circuited: AtomicU64,
+}
+
+/// Counts of requests made using a [`LabClient`].
+///
+/// Cheap to clone; all clones share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct ClientMetrics {
+    inner: Arc<MetricsInner>,
+}
+
+impl ClientMetrics {
+    /// Returns number of requests sent, not counting retries.
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns number of attempts made, including retries.
+    pub fn attempts(&self) -> u64 {
+        self.inner.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Returns number of retries made.
+    pub fn retries(&self) -> u64 {
+        self.inner.retries.load(Ordering::Relaxed)
+    }
+
+    /// Returns number of requests which completed with a non-5xx response.
+    pub fn successes(&self) -> u64 {
+        self.inner.successes.load(Ordering::Relaxed)
+    }
+
+    /// Returns number of requests which failed with an error or 5xx response.
+    pub fn failures(&self) -> u64 {
+        self.inner.failures.load(Ordering::Relaxed)
+    }
+
+    /// Returns number of attempts rejected because the circuit breaker was open.
+    pub fn short_circuited(&self) -> u64 {
+        self.inner.short_circuited.load(Ordering::Relaxed)
+    }
+}
+
+/// Errors that can occur when sending requests with [`LabClient`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// Failed to send request or receive response.
+    #[display(fmt = "Failed to send request: {_0}")]
+    Send(SendRequestError),
+
+    /// Request deadline was reached before a response was received.
+    #[display(fmt = "Request deadline exceeded.")]
+    DeadlineExceeded,
+
+    /// Circuit breaker is open.
+    #[display(fmt = "Circuit breaker is open.")]
+    CircuitOpen,
+}
+
+impl ResponseError for ClientError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Send(_) => StatusCode::BAD_GATEWAY,
+            Self::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            Self::CircuitOpen => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use actix_web::{test::TestRequest, web, App, HttpResponse};
+
+    use super::*;
+    use crate::{middleware::RequestContextMiddleware, test::MockClock};
+
+    #[actix_web::test]
+    async fn retries_and_propagates_context() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let srv = actix_test::start({
+            let calls = Arc::clone(&calls);
+
+            move || {
+                let calls = Arc::clone(&calls);
+
+                App::new().default_service(web::to(move |req: HttpRequest| {
+                    let calls = Arc::clone(&calls);
+
+                    async move {
+                        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                            return HttpResponse::ServiceUnavailable().finish();
+                        }
+
+                        let header = |name| {
+                            req.headers()
+                                .get(name)
+                                .map_or("", |val| val.to_str().unwrap())
+                                .to_owned()
+                        };
+
+                        HttpResponse::Ok().body(format!(
+                            "{} {}",
+                            header("x-request-id"),
+                            header("traceparent")
+                        ))
+                    }
+                }))
+            }
+        });
+
+        let client = LabClient::new(awc::Client::new())
+            .retry(RetryPolicy::idempotent().backoff(Duration::from_millis(1)));
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc"))
+            .insert_header((
+                "traceparent",
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            ))
+            .to_http_request();
+
+        let mut res = client
+            .get(srv.url("/"))
+            .propagate(&req)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.body().await.unwrap(),
+            "abc 00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+        );
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.requests(), 1);
+        assert_eq!(metrics.attempts(), 2);
+        assert_eq!(metrics.retries(), 1);
+        assert_eq!(metrics.successes(), 1);
+        assert_eq!(metrics.failures(), 0);
+
+        // non-idempotent requests are not retried
+        calls.store(0, Ordering::SeqCst);
+        let res = client.post(srv.url("/")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(metrics.failures(), 1);
+    }
+
+    #[actix_web::test]
+    async fn propagates_deadline() {
+        let srv = actix_test::start(|| App::new().default_service(web::to(HttpResponse::Ok)));
+
+        let app = actix_web::test::init_service(
+            App::new()
+                .wrap(RequestContextMiddleware::new().timeout(Duration::ZERO))
+                .default_service(web::to(|req: HttpRequest| async move {
+                    let client = LabClient::new(awc::Client::new());
+                    let url = req.app_data::<String>().unwrap().clone();
+
+                    match client.get(url).propagate(&req).send().await {
+                        Err(ClientError::DeadlineExceeded) => HttpResponse::GatewayTimeout(),
+                        _ => HttpResponse::Ok(),
+                    }
+                }))
+                .app_data(srv.url("/")),
+        )
+        .await;
+
+        let res = actix_web::test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[test]
+    fn circuit_breaker() {
+        let clock = MockClock::new();
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10)).clock(clock.clone());
+
+        assert!(breaker.try_acquire());
+        breaker.record(false);
+        assert!(!breaker.is_open());
+        breaker.record(true);
+        breaker.record(false);
+        assert!(!breaker.is_open());
+        breaker.record(false);
+        assert!(breaker.is_open());
+        assert!(!breaker.try_acquire());
+
+        clock.advance(Duration::from_secs(10));
+
+        // only a single trial request is allowed
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        breaker.record(false);
+        assert!(breaker.is_open());
+        assert!(!breaker.try_acquire());
+
+        clock.advance(Duration::from_secs(10));
+        assert!(breaker.try_acquire());
+        breaker.record(true);
+        assert!(!breaker.is_open());
+        assert!(breaker.try_acquire());
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        let retry = RetryPolicy::new(10).backoff(Duration::from_millis(100));
+        assert_eq!(retry.backoff_for(0), Duration::from_millis(100));
+        assert_eq!(retry.backoff_for(1), Duration::from_millis(200));
+        assert_eq!(retry.backoff_for(3), Duration::from_millis(800));
+        assert_eq!(retry.backoff_for(20), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,460 @@
+//! Basic authentication extractors.
+//!
+//! See [`BasicAuth`] and [`VerifiedBasicAuth`] docs.
+
+use std::{fmt, sync::Arc};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::Payload,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use base64::Engine as _;
+use derive_more::Display;
+use sha2::{Digest as _, Sha256};
+use tracing::debug;
+
+use crate::bearer_auth::escape;
+
+/// Extractor for credentials sent using the `Basic` authentication scheme, as defined in
+/// [RFC 7617].
+///
+/// Credentials are not verified; handlers can use [`verify()`](Self::verify) to compare them in
+/// constant time, or use the [`VerifiedBasicAuth`] extractor instead.
+///
+/// # Extractor
+/// Extraction fails with a [`BasicAuthError`] if the header is missing, uses a different
+/// authentication scheme, or is malformed. Error responses for missing credentials include a
+/// `WWW-Authenticate` challenge with the realm from the registered [`BasicAuthConfig`], if any.
+///
+/// The password is omitted from `Debug` output to avoid leaking it into logs.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::extract::BasicAuth;
+///
+/// #[get("/")]
+/// async fn index(auth: BasicAuth) -> impl Responder {
+///     format!("Hello, {}!", auth.user_id())
+/// }
+/// ```
+///
+/// [RFC 7617]: https://datatracker.ietf.org/doc/html/rfc7617
+#[derive(Clone, PartialEq, Eq)]
+pub struct BasicAuth {
+    user_id: String,
+    password: String,
+}
+
+impl BasicAuth {
+    /// Returns user ID.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns password, which may be empty.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Returns true if credentials match the given user ID and password.
+    ///
+    /// Comparisons take the same amount of time regardless of where, or whether, the credentials
+    /// differ.
+    pub fn verify(&self, user_id: &str, password: &str) -> bool {
+        // evaluate both comparisons to avoid revealing which one failed
+        let user_id_eq = constant_time_eq(self.user_id.as_bytes(), user_id.as_bytes());
+        let password_eq = constant_time_eq(self.password.as_bytes(), password.as_bytes());
+
+        user_id_eq & password_eq
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("user_id", &self.user_id)
+            .field("password", &"..")
+            .finish()
+    }
+}
+
+impl FromRequest for BasicAuth {
+    type Error = BasicAuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(parse_basic(req).map_err(|kind| {
+            debug!(
+                "Failed to extract `BasicAuth` for `{}` handler: {kind}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            BasicAuthError::new(kind, req)
+        }))
+    }
+}
+
+/// Extractor for `Basic` authentication credentials which are verified against the credential
+/// store registered using [`BasicAuthConfig::credentials()`].
+///
+/// Passwords are compared in constant time.
+///
+/// # Extractor
+/// Extraction fails with a [`BasicAuthError`] if the credentials are missing, malformed, or do
+/// not match the store. If no credential store is registered, extraction fails with a
+/// `500 Internal Server Error` response.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App, Responder};
+/// use actix_web_lab::extract::{BasicAuthConfig, VerifiedBasicAuth};
+///
+/// #[get("/admin")]
+/// async fn admin(auth: VerifiedBasicAuth) -> impl Responder {
+///     format!("Welcome, {}!", auth.user_id())
+/// }
+///
+/// App::new()
+///     .app_data(
+///         BasicAuthConfig::new()
+///             .realm("admin")
+///             .credentials(|user_id| (user_id == "admin").then(|| "hunter2".to_owned())),
+///     )
+///     .service(admin)
+/// # ;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedBasicAuth(BasicAuth);
+
+impl_more::impl_deref!(VerifiedBasicAuth => BasicAuth);
+
+impl VerifiedBasicAuth {
+    /// Unwraps into inner credentials.
+    pub fn into_inner(self) -> BasicAuth {
+        self.0
+    }
+}
+
+impl FromRequest for VerifiedBasicAuth {
+    type Error = BasicAuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let res = parse_basic(req).and_then(|auth| {
+            let Some(credentials) = &BasicAuthConfig::from_req(req).credentials else {
+                return Err(BasicAuthErrorKind::CredentialStoreNotConfigured);
+            };
+
+            // compare against an empty password for unknown users, to avoid revealing which
+            // users exist through response timing
+            let password = credentials(auth.user_id());
+            let password_eq = constant_time_eq(
+                auth.password.as_bytes(),
+                password.as_deref().unwrap_or_default().as_bytes(),
+            );
+
+            if password.is_some() && password_eq {
+                Ok(Self(auth))
+            } else {
+                Err(BasicAuthErrorKind::InvalidCredentials)
+            }
+        });
+
+        ready(res.map_err(|kind| {
+            debug!(
+                "Failed to extract `VerifiedBasicAuth` for `{}` handler: {kind}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            BasicAuthError::new(kind, req)
+        }))
+    }
+}
+
+/// Returns credentials from the `Authorization` header, if it uses the basic scheme.
+fn parse_basic(req: &HttpRequest) -> Result<BasicAuth, BasicAuthErrorKind> {
+    let Some(auth) = req.headers().get(header::AUTHORIZATION) else {
+        return Err(BasicAuthErrorKind::Missing);
+    };
+
+    let auth = auth.to_str().map_err(|_| BasicAuthErrorKind::Malformed)?;
+    let (scheme, credentials) = auth.split_once(' ').unwrap_or((auth, ""));
+
+    if !scheme.eq_ignore_ascii_case("Basic") {
+        return Err(BasicAuthErrorKind::Missing);
+    }
+
+    let credentials = base64::engine::general_purpose::STANDARD
+        .decode(credentials.trim_start_matches(' '))
+        .ok()
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+        .ok_or(BasicAuthErrorKind::Malformed)?;
+
+    let (user_id, password) = credentials
+        .split_once(':')
+        .ok_or(BasicAuthErrorKind::Malformed)?;
+
+    Ok(BasicAuth {
+        user_id: user_id.to_owned(),
+        password: password.to_owned(),
+    })
+}
+
+/// Returns true if `a` and `b` are equal, in time independent of their contents.
+///
+/// Inputs are hashed first so that timing does not depend on their lengths either.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let a = Sha256::digest(a);
+    let b = Sha256::digest(b);
+
+    a.iter().zip(b.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Kinds of [`BasicAuthError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[non_exhaustive]
+pub enum BasicAuthErrorKind {
+    /// Request has no basic authentication credentials.
+    #[display(fmt = "Credentials are missing.")]
+    Missing,
+
+    /// Authorization header is malformed.
+    #[display(fmt = "Authorization header is malformed.")]
+    Malformed,
+
+    /// Credentials do not match the credential store.
+    #[display(fmt = "Credentials are invalid.")]
+    InvalidCredentials,
+
+    /// No credential store is registered for [`VerifiedBasicAuth`].
+    #[display(fmt = "Credential store is not configured.")]
+    CredentialStoreNotConfigured,
+}
+
+/// Errors that can occur when authenticating requests using basic authentication.
+///
+/// Responds with a `401 Unauthorized` response with a `WWW-Authenticate` challenge for missing
+/// or invalid credentials.
+#[derive(Debug, Display)]
+#[display(fmt = "{}", kind)]
+pub struct BasicAuthError {
+    kind: BasicAuthErrorKind,
+    challenge: String,
+}
+
+impl BasicAuthError {
+    /// Constructs new error of given kind, with a challenge using the request's
+    /// [`BasicAuthConfig`].
+    pub fn new(kind: BasicAuthErrorKind, req: &HttpRequest) -> Self {
+        let challenge = match &BasicAuthConfig::from_req(req).realm {
+            Some(realm) => format!("Basic realm=\"{}\", charset=\"UTF-8\"", escape(realm)),
+            None => "Basic charset=\"UTF-8\"".to_owned(),
+        };
+
+        Self { kind, challenge }
+    }
+
+    /// Returns kind of error.
+    pub fn kind(&self) -> BasicAuthErrorKind {
+        self.kind
+    }
+}
+
+impl std::error::Error for BasicAuthError {}
+
+impl ResponseError for BasicAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self.kind {
+            BasicAuthErrorKind::Missing | BasicAuthErrorKind::InvalidCredentials => {
+                StatusCode::UNAUTHORIZED
+            }
+            BasicAuthErrorKind::Malformed => StatusCode::BAD_REQUEST,
+            BasicAuthErrorKind::CredentialStoreNotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+
+        if self.status_code() == StatusCode::UNAUTHORIZED {
+            // challenge is only invalid if realm contains control characters
+            if let Ok(challenge) = HeaderValue::try_from(&self.challenge) {
+                res.insert_header((header::WWW_AUTHENTICATE, challenge));
+            }
+        }
+
+        res.body(self.to_string())
+    }
+}
+
+type CredentialStore = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// Configuration for the [`BasicAuth`] and [`VerifiedBasicAuth`] extractors.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Clone, Default)]
+pub struct BasicAuthConfig {
+    realm: Option<String>,
+    credentials: Option<Arc<CredentialStore>>,
+}
+
+const DEFAULT_CONFIG: BasicAuthConfig = BasicAuthConfig {
+    realm: None,
+    credentials: None,
+};
+
+impl BasicAuthConfig {
+    /// Constructs new config without a realm or credential store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets realm included in challenges.
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// Sets credential store used by [`VerifiedBasicAuth`].
+    ///
+    /// The store is called with the user ID from the request and returns that user's password,
+    /// or `None` if the user is unknown.
+    pub fn credentials(
+        mut self,
+        store: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.credentials = Some(Arc::new(store));
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl fmt::Debug for BasicAuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuthConfig")
+            .field("realm", &self.realm)
+            .field("credentials", &self.credentials.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn basic(credentials: &str) -> String {
+        format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        )
+    }
+
+    fn config() -> BasicAuthConfig {
+        BasicAuthConfig::new()
+            .realm("example")
+            .credentials(|user_id| (user_id == "admin").then(|| "hunter2".to_owned()))
+    }
+
+    #[actix_web::test]
+    async fn extracts_credentials() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, basic("aladdin:open:sesame")))
+            .to_http_request();
+        let auth = BasicAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.user_id(), "aladdin");
+        assert_eq!(auth.password(), "open:sesame");
+        assert!(auth.verify("aladdin", "open:sesame"));
+        assert!(!auth.verify("aladdin", "open"));
+        assert!(!format!("{auth:?}").contains("sesame"));
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, basic("guest:")))
+            .to_http_request();
+        let auth = BasicAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.password(), "");
+    }
+
+    #[actix_web::test]
+    async fn missing_and_malformed() {
+        for auth in [None, Some("Bearer abc")] {
+            let mut req = TestRequest::default().app_data(config());
+
+            if let Some(auth) = auth {
+                req = req.insert_header((header::AUTHORIZATION, auth));
+            }
+
+            let err = BasicAuth::extract(&req.to_http_request())
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), BasicAuthErrorKind::Missing);
+
+            let res = err.error_response();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+                r#"Basic realm="example", charset="UTF-8""#,
+            );
+        }
+
+        let no_colon = basic("no-colon");
+
+        for auth in ["Basic", "Basic !!!", no_colon.as_str()] {
+            let req = TestRequest::default()
+                .insert_header((header::AUTHORIZATION, auth))
+                .to_http_request();
+            let err = BasicAuth::extract(&req).await.unwrap_err();
+            assert_eq!(err.kind(), BasicAuthErrorKind::Malformed, "{auth}");
+            assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn verified() {
+        let req = TestRequest::default()
+            .app_data(web::Data::new(config()))
+            .insert_header((header::AUTHORIZATION, basic("admin:hunter2")))
+            .to_http_request();
+        let auth = VerifiedBasicAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.user_id(), "admin");
+
+        for credentials in ["admin:hunter3", "root:hunter2", "root:"] {
+            let req = TestRequest::default()
+                .app_data(config())
+                .insert_header((header::AUTHORIZATION, basic(credentials)))
+                .to_http_request();
+            let err = VerifiedBasicAuth::extract(&req).await.unwrap_err();
+            assert_eq!(err.kind(), BasicAuthErrorKind::InvalidCredentials);
+
+            let res = err.error_response();
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+            assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, basic("admin:hunter2")))
+            .to_http_request();
+        let err = VerifiedBasicAuth::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn constant_time_comparison() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+    }
+}
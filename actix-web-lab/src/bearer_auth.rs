@@ -0,0 +1,375 @@
+//! Bearer token extractor.
+//!
+//! See [`BearerAuth`] docs.
+
+use std::fmt;
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::Payload,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use derive_more::Display;
+use tracing::debug;
+
+/// Extractor for bearer tokens sent in the `Authorization` header, as defined in [RFC 6750].
+///
+/// Only the syntax of the header is checked; handlers are responsible for validating the token
+/// itself, and can use [`BearerAuthError::invalid_token()`] and
+/// [`BearerAuthError::insufficient_scope()`] to reject it with the appropriate challenge.
+///
+/// # Extractor
+/// Extraction fails with a [`BearerAuthError`] if the header is missing, uses a different
+/// authentication scheme, or is malformed. Error responses include a `WWW-Authenticate` challenge
+/// with the realm and scope from the registered [`BearerAuthConfig`], if any.
+///
+/// The token is omitted from `Debug` output to avoid leaking it into logs.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App, HttpRequest, Responder};
+/// use actix_web_lab::extract::{BearerAuth, BearerAuthConfig, BearerAuthError};
+///
+/// #[get("/")]
+/// async fn index(req: HttpRequest, auth: BearerAuth) -> Result<impl Responder, BearerAuthError> {
+///     if auth.token() != "mF_9.B5f-4.1JqM" {
+///         return Err(BearerAuthError::invalid_token(&req));
+///     }
+///
+///     Ok("Hello!")
+/// }
+///
+/// App::new()
+///     .app_data(BearerAuthConfig::new().realm("example"))
+///     .service(index)
+/// # ;
+/// ```
+///
+/// [RFC 6750]: https://datatracker.ietf.org/doc/html/rfc6750
+#[derive(Clone, PartialEq, Eq)]
+pub struct BearerAuth(String);
+
+impl BearerAuth {
+    /// Returns bearer token.
+    pub fn token(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps into inner bearer token.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Debug for BearerAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BearerAuth").field(&"..").finish()
+    }
+}
+
+impl FromRequest for BearerAuth {
+    type Error = BearerAuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(parse_bearer(req).map(Self).map_err(|kind| {
+            debug!(
+                "Failed to extract `BearerAuth` for `{}` handler: {kind}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            BearerAuthError::new(kind, req)
+        }))
+    }
+}
+
+/// Returns token from the `Authorization` header, if it uses the bearer scheme.
+pub(crate) fn parse_bearer(req: &HttpRequest) -> Result<String, BearerAuthErrorKind> {
+    let Some(auth) = req.headers().get(header::AUTHORIZATION) else {
+        return Err(BearerAuthErrorKind::Missing);
+    };
+
+    let auth = auth
+        .to_str()
+        .map_err(|_| BearerAuthErrorKind::InvalidRequest)?;
+
+    let (scheme, token) = auth.split_once(' ').unwrap_or((auth, ""));
+
+    // requests using other schemes are treated as lacking authentication, per RFC 6750 §3.1
+    if !scheme.eq_ignore_ascii_case("Bearer") {
+        return Err(BearerAuthErrorKind::Missing);
+    }
+
+    let token = token.trim_start_matches(' ');
+
+    if is_b64token(token) {
+        Ok(token.to_owned())
+    } else {
+        Err(BearerAuthErrorKind::InvalidRequest)
+    }
+}
+
+/// Returns true if `token` matches the `b64token` syntax of RFC 6750 §2.1.
+fn is_b64token(token: &str) -> bool {
+    let token = token.trim_end_matches('=');
+
+    !token.is_empty()
+        && token.bytes().all(|b| {
+            b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/')
+        })
+}
+
+/// Kinds of [`BearerAuthError`], corresponding to the error codes of RFC 6750 §3.1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[non_exhaustive]
+pub enum BearerAuthErrorKind {
+    /// Request has no bearer token.
+    #[display(fmt = "Bearer token is missing.")]
+    Missing,
+
+    /// Authorization header is malformed.
+    #[display(fmt = "Authorization header is malformed.")]
+    InvalidRequest,
+
+    /// Bearer token is expired, revoked, or otherwise invalid.
+    #[display(fmt = "Bearer token is invalid.")]
+    InvalidToken,
+
+    /// Bearer token does not grant the scope required by the resource.
+    #[display(fmt = "Bearer token has insufficient scope.")]
+    InsufficientScope,
+}
+
+impl BearerAuthErrorKind {
+    fn error_code(self) -> Option<&'static str> {
+        match self {
+            Self::Missing => None,
+            Self::InvalidRequest => Some("invalid_request"),
+            Self::InvalidToken => Some("invalid_token"),
+            Self::InsufficientScope => Some("insufficient_scope"),
+        }
+    }
+}
+
+/// Errors that can occur when authenticating requests using bearer tokens.
+///
+/// Responds with the status code appropriate for the [kind](Self::kind) of error and a
+/// `WWW-Authenticate` challenge.
+#[derive(Debug, Display)]
+#[display(fmt = "{}", kind)]
+pub struct BearerAuthError {
+    kind: BearerAuthErrorKind,
+    challenge: String,
+}
+
+impl BearerAuthError {
+    /// Constructs new error of given kind, with a challenge using the request's
+    /// [`BearerAuthConfig`].
+    pub fn new(kind: BearerAuthErrorKind, req: &HttpRequest) -> Self {
+        let config = BearerAuthConfig::from_req(req);
+
+        let params = [
+            ("realm", config.realm.as_deref()),
+            ("scope", config.scope.as_deref()),
+            ("error", kind.error_code()),
+        ];
+
+        let params = params
+            .into_iter()
+            .filter_map(|(name, val)| Some(format!("{name}=\"{}\"", escape(val?))))
+            .collect::<Vec<_>>();
+
+        let challenge = if params.is_empty() {
+            "Bearer".to_owned()
+        } else {
+            format!("Bearer {}", params.join(", "))
+        };
+
+        Self { kind, challenge }
+    }
+
+    /// Constructs new error for a token which is expired, revoked, or otherwise invalid.
+    pub fn invalid_token(req: &HttpRequest) -> Self {
+        Self::new(BearerAuthErrorKind::InvalidToken, req)
+    }
+
+    /// Constructs new error for a token which does not grant the required scope.
+    pub fn insufficient_scope(req: &HttpRequest) -> Self {
+        Self::new(BearerAuthErrorKind::InsufficientScope, req)
+    }
+
+    /// Returns kind of error.
+    pub fn kind(&self) -> BearerAuthErrorKind {
+        self.kind
+    }
+}
+
+impl std::error::Error for BearerAuthError {}
+
+impl ResponseError for BearerAuthError {
+    fn status_code(&self) -> StatusCode {
+        match self.kind {
+            BearerAuthErrorKind::Missing | BearerAuthErrorKind::InvalidToken => {
+                StatusCode::UNAUTHORIZED
+            }
+            BearerAuthErrorKind::InvalidRequest => StatusCode::BAD_REQUEST,
+            BearerAuthErrorKind::InsufficientScope => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+
+        // challenge is only invalid if realm or scope contain control characters
+        if let Ok(challenge) = HeaderValue::try_from(&self.challenge) {
+            res.insert_header((header::WWW_AUTHENTICATE, challenge));
+        }
+
+        res.body(self.to_string())
+    }
+}
+
+/// Escapes quotes and backslashes for use in a quoted string.
+pub(crate) fn escape(val: &str) -> String {
+    val.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Configuration for the [`BearerAuth`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, Default)]
+pub struct BearerAuthConfig {
+    realm: Option<String>,
+    scope: Option<String>,
+}
+
+const DEFAULT_CONFIG: BearerAuthConfig = BearerAuthConfig {
+    realm: None,
+    scope: None,
+};
+
+impl BearerAuthConfig {
+    /// Constructs new config without a realm or scope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets realm included in challenges.
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+
+    /// Sets space-delimited list of scopes required by the resource, included in challenges.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn challenge(err: &BearerAuthError) -> String {
+        let res = err.error_response();
+        res.headers()
+            .get(header::WWW_AUTHENTICATE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[actix_web::test]
+    async fn extracts_token() {
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer mF_9.B5f-4.1JqM=="))
+            .to_http_request();
+        let auth = BearerAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.token(), "mF_9.B5f-4.1JqM==");
+        assert_eq!(format!("{auth:?}"), r#"BearerAuth("..")"#);
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "bearer  abc"))
+            .to_http_request();
+        let auth = BearerAuth::extract(&req).await.unwrap();
+        assert_eq!(auth.into_inner(), "abc");
+    }
+
+    #[actix_web::test]
+    async fn missing_token() {
+        for auth in [None, Some("Basic dXNlcjpwYXNz")] {
+            let mut req = TestRequest::default().app_data(BearerAuthConfig::new().realm("example"));
+
+            if let Some(auth) = auth {
+                req = req.insert_header((header::AUTHORIZATION, auth));
+            }
+
+            let err = BearerAuth::extract(&req.to_http_request())
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), BearerAuthErrorKind::Missing);
+            assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+            assert_eq!(challenge(&err), r#"Bearer realm="example""#);
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let err = BearerAuth::extract(&req).await.unwrap_err();
+        assert_eq!(challenge(&err), "Bearer");
+    }
+
+    #[actix_web::test]
+    async fn malformed_token() {
+        for auth in [
+            "Bearer",
+            "Bearer ",
+            "Bearer a b",
+            "Bearer =abc",
+            "Bearer a=b",
+        ] {
+            let req = TestRequest::default()
+                .insert_header((header::AUTHORIZATION, auth))
+                .to_http_request();
+
+            let err = BearerAuth::extract(&req).await.unwrap_err();
+            assert_eq!(err.kind(), BearerAuthErrorKind::InvalidRequest, "{auth}");
+            assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+            assert_eq!(challenge(&err), r#"Bearer error="invalid_request""#);
+        }
+    }
+
+    #[actix_web::test]
+    async fn handler_errors() {
+        let req = TestRequest::default()
+            .app_data(web::Data::new(
+                BearerAuthConfig::new()
+                    .realm(r#"say "hi""#)
+                    .scope("read write"),
+            ))
+            .to_http_request();
+
+        let err = BearerAuthError::invalid_token(&req);
+        assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            challenge(&err),
+            r#"Bearer realm="say \"hi\"", scope="read write", error="invalid_token""#
+        );
+
+        let err = BearerAuthError::insufficient_scope(&req);
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert!(challenge(&err).ends_with(r#"error="insufficient_scope""#));
+    }
+}
@@ -0,0 +1,181 @@
+//! Synthetic bodies and streams for benchmarking.
+//!
+//! Generators are deterministic so that results are comparable between runs. They are used by
+//! this crate's own benchmarks and can be used to measure the performance of custom body mappers
+//! and responders.
+//!
+//! # Examples
+//! ```
+//! use actix_web_lab::bench_support::{self, ChunkPattern};
+//!
+//! # actix_web::rt::System::new().block_on(async {
+//! let body = bench_support::body(64 * 1024, ChunkPattern::Uniform(1024));
+//! assert_eq!(bench_support::drain(body).await, 64 * 1024);
+//! # });
+//! ```
+
+use std::{convert::Infallible, pin::pin};
+
+use actix_web::body::{BodyStream, MessageBody};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::{future::poll_fn, stream};
+
+use crate::sse;
+
+/// Describes how a body is split into chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChunkPattern {
+    /// Every chunk has the given size, except possibly the last.
+    Uniform(usize),
+
+    /// Chunk sizes alternate between the two given sizes.
+    Alternating(usize, usize),
+
+    /// Chunks start at the given size and double until the second size is reached.
+    Doubling(usize, usize),
+}
+
+impl ChunkPattern {
+    /// Returns the sizes of chunks needed to make up `total` bytes.
+    ///
+    /// # Panics
+    /// Panics if any configured chunk size is zero.
+    pub fn chunk_sizes(self, total: usize) -> impl Iterator<Item = usize> {
+        let (first, second) = match self {
+            Self::Uniform(size) => (size, size),
+            Self::Alternating(a, b) => (a, b),
+            Self::Doubling(start, max) => (start, max),
+        };
+
+        assert!(first > 0 && second > 0, "chunk sizes must be non-zero");
+
+        let mut remaining = total;
+        let mut next = first;
+
+        std::iter::from_fn(move || {
+            if remaining == 0 {
+                return None;
+            }
+
+            let size = next;
+
+            next = match self {
+                Self::Uniform(_) => size,
+                Self::Alternating(a, b) => {
+                    if size == a {
+                        b
+                    } else {
+                        a
+                    }
+                }
+                Self::Doubling(_, max) => size.saturating_mul(2).min(max),
+            };
+
+            let size = size.min(remaining);
+            remaining -= size;
+            Some(size)
+        })
+    }
+}
+
+/// Returns a buffer of `size` printable ASCII bytes.
+pub fn bytes(size: usize) -> Bytes {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+    ALPHABET.iter().copied().cycle().take(size).collect()
+}
+
+/// Returns a stream of chunks totalling `total` bytes, split according to `pattern`.
+pub fn chunk_stream(
+    total: usize,
+    pattern: ChunkPattern,
+) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let buf = bytes(pattern.chunk_sizes(total).max().unwrap_or(0));
+    stream::iter(
+        pattern
+            .chunk_sizes(total)
+            .map(move |size| Ok(buf.slice(..size))),
+    )
+}
+
+/// Returns a streaming body totalling `total` bytes, split according to `pattern`.
+pub fn body(total: usize, pattern: ChunkPattern) -> impl MessageBody {
+    BodyStream::new(chunk_stream(total, pattern))
+}
+
+/// Returns a stream of `count` JSON objects, each containing a string of `data_len` bytes.
+///
+/// Suitable for use with [`NdJson`](crate::respond::NdJson).
+pub fn json_items(
+    count: usize,
+    data_len: usize,
+) -> impl Stream<Item = Result<serde_json::Value, Infallible>> {
+    let data = String::from_utf8(bytes(data_len).to_vec()).unwrap();
+
+    stream::iter((0..count).map(move |id| {
+        Ok(serde_json::json!({
+            "id": id,
+            "data": data,
+        }))
+    }))
+}
+
+/// Returns a stream of `count` SSE data events, each containing `data_len` bytes of data.
+///
+/// Suitable for use with [`Sse`](crate::sse::Sse).
+pub fn sse_events(
+    count: usize,
+    data_len: usize,
+) -> impl Stream<Item = Result<sse::Event, Infallible>> {
+    let data = String::from_utf8(bytes(data_len).to_vec()).unwrap();
+
+    stream::iter((0..count).map(move |id| {
+        Ok(sse::Data::new(data.clone())
+            .id(id.to_string())
+            .event("bench")
+            .into())
+    }))
+}
+
+/// Polls `body` to completion, returning the number of bytes it produced.
+///
+/// Unlike [`to_bytes`](actix_web::body::to_bytes), chunks are not collected.
+///
+/// # Panics
+/// Panics if the body yields an error.
+pub async fn drain(body: impl MessageBody) -> usize {
+    let mut body = pin!(body);
+    let mut len = 0;
+
+    while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+        match chunk {
+            Ok(chunk) => len += chunk.len(),
+            Err(_) => panic!("body yielded an error"),
+        }
+    }
+
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_patterns() {
+        let sizes = |pattern: ChunkPattern, total| pattern.chunk_sizes(total).collect::<Vec<_>>();
+
+        assert_eq!(sizes(ChunkPattern::Uniform(4), 10), [4, 4, 2]);
+        assert_eq!(sizes(ChunkPattern::Alternating(1, 4), 10), [1, 4, 1, 4]);
+        assert_eq!(sizes(ChunkPattern::Doubling(1, 4), 12), [1, 2, 4, 4, 1]);
+        assert!(sizes(ChunkPattern::Uniform(4), 0).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn body_length() {
+        let body = body(1000, ChunkPattern::Alternating(7, 100));
+        assert_eq!(drain(body).await, 1000);
+    }
+}
@@ -0,0 +1,187 @@
+//! Blocking body stream.
+//!
+//! See [`block_stream`] docs.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_web::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+use crate::BoxError;
+
+/// Default number of chunks that can be buffered before the producer is blocked.
+const DEFAULT_BUFFER: usize = 16;
+
+/// Runs a blocking closure on the blocking thread pool, streaming the chunks it produces as a
+/// response body.
+///
+/// The closure is given a [`BlockStreamSender`] which it should use to submit body chunks. The
+/// channel between the closure and the response body is bounded, so if the client is reading
+/// slower than the closure is producing data, [`send`](BlockStreamSender::send) will block the
+/// producer thread until there is space available.
+///
+/// If the closure returns an error, it will be forwarded to the body stream and the response will
+/// be aborted.
+///
+/// # Examples
+/// ```
+/// # use actix_web::{HttpResponse, web::Bytes};
+/// use std::io;
+///
+/// use actix_web_lab::web;
+///
+/// # async fn index() {
+/// let body = web::block_stream(|mut tx| {
+///     for i in 0..100 {
+///         // e.g., read next row from a synchronous database cursor
+///         let row = format!("row {i}\n");
+///
+///         if tx.send(Bytes::from(row)).is_err() {
+///             // client has disconnected
+///             break;
+///         }
+///     }
+///
+///     Ok::<_, io::Error>(())
+/// });
+///
+/// HttpResponse::Ok().body(body)
+/// # ;}
+/// ```
+pub fn block_stream<F, E>(f: F) -> impl MessageBody
+where
+    F: FnOnce(BlockStreamSender<E>) -> Result<(), E> + Send + 'static,
+    E: Into<BoxError> + Send + 'static,
+{
+    block_stream_with_buffer(DEFAULT_BUFFER, f)
+}
+
+/// Same as [`block_stream`] but with a custom chunk buffer size.
+///
+/// # Panics
+/// Panics if `buffer` is zero.
+pub fn block_stream_with_buffer<F, E>(buffer: usize, f: F) -> impl MessageBody
+where
+    F: FnOnce(BlockStreamSender<E>) -> Result<(), E> + Send + 'static,
+    E: Into<BoxError> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer);
+
+    actix_web::rt::task::spawn_blocking(move || {
+        let err_tx = tx.clone();
+
+        if let Err(err) = f(BlockStreamSender { tx }) {
+            // body may have already been dropped so send result can be ignored
+            let _ = err_tx.blocking_send(Err(err));
+        }
+    });
+
+    BlockStreamBody { rx }
+}
+
+/// A blocking sender for body chunks.
+///
+/// See [`block_stream`] docs.
+#[derive(Debug)]
+pub struct BlockStreamSender<E> {
+    tx: mpsc::Sender<Result<Bytes, E>>,
+}
+
+impl<E> BlockStreamSender<E> {
+    /// Submits a chunk of bytes to the response body stream, blocking the current thread if the
+    /// buffer is full.
+    ///
+    /// This method must not be called from within an async context.
+    ///
+    /// # Errors
+    /// Errors if the response body was dropped, returning `chunk`. This typically means that the
+    /// client has disconnected and the producer should stop.
+    pub fn send(&mut self, chunk: Bytes) -> Result<(), Bytes> {
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|mpsc::error::SendError(res)| match res {
+                Ok(chunk) => chunk,
+                Err(_) => unreachable!(),
+            })
+    }
+
+    /// Returns true if the response body was dropped.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+#[derive(Debug)]
+struct BlockStreamBody<E> {
+    rx: mpsc::Receiver<Result<Bytes, E>>,
+}
+
+impl<E> MessageBody for BlockStreamBody<E>
+where
+    E: Into<BoxError>,
+{
+    type Error = E;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use actix_web::body;
+
+    use super::*;
+
+    static_assertions::assert_impl_all!(BlockStreamSender<io::Error>: Send, Sync, Unpin);
+    static_assertions::assert_impl_all!(BlockStreamBody<io::Error>: Send, Sync, Unpin, MessageBody);
+
+    #[actix_web::test]
+    async fn streams_chunks_from_blocking_thread() {
+        let body = block_stream(|mut tx| {
+            for chunk in ["foo", "bar", "baz"] {
+                tx.send(Bytes::from_static(chunk.as_bytes())).unwrap();
+            }
+
+            Ok::<_, io::Error>(())
+        });
+
+        assert_eq!(body::to_bytes(body).await.ok().unwrap(), "foobarbaz");
+    }
+
+    #[actix_web::test]
+    async fn backpressure_with_small_buffer() {
+        let body = block_stream_with_buffer(1, |mut tx| {
+            for _ in 0..100 {
+                tx.send(Bytes::from_static(b"a")).unwrap();
+            }
+
+            Ok::<_, io::Error>(())
+        });
+
+        assert_eq!(body::to_bytes(body).await.ok().unwrap().len(), 100);
+    }
+
+    #[actix_web::test]
+    async fn forwards_producer_error() {
+        let body = block_stream(|mut tx| {
+            tx.send(Bytes::from_static(b"foo")).unwrap();
+            Err(io::Error::new(io::ErrorKind::Other, "cursor failed"))
+        });
+
+        body::to_bytes(body).await.unwrap_err();
+    }
+}
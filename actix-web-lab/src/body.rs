@@ -3,7 +3,39 @@
 //! Analogous to the `body` module in Actix Web.
 
 pub use crate::{
-    body_async_write::{writer, Writer},
-    body_channel::{channel, Sender},
+    body_async_write::{writer, writer_coalesced, Writer},
+    body_catch_panic::catch_panic,
+    body_channel::{channel, channel_coalesced, Sender},
+    body_coalesce::Coalescing,
+    body_progress::{with_progress, Progress, ProgressStatus, WithProgress},
+    body_spill::{collect_with_spill, CollectError, CollectedBody},
     infallible_body_stream::{new_infallible_body_stream, new_infallible_sized_stream},
+    replayable_body::ReplayStream,
 };
+
+pub mod combinators {
+    //! Combinators for composing response bodies without buffering them.
+    //!
+    //! Each combinator reports an accurate [`size()`](actix_web::body::MessageBody::size) where
+    //! possible so that `Content-Length` headers are still sent for composed bodies of known size.
+    //!
+    //! # Examples
+    //! ```
+    //! # #[actix_web::main] async fn test() {
+    //! use actix_web::body;
+    //! use actix_web_lab::body::combinators;
+    //!
+    //! let body = combinators::prefix("<!DOCTYPE html>\n", "<p>Hello</p>");
+    //! let body = combinators::suffix(body, "\n<!-- served by actix -->");
+    //!
+    //! assert_eq!(
+    //!     body::to_bytes(body).await.unwrap(),
+    //!     "<!DOCTYPE html>\n<p>Hello</p>\n<!-- served by actix -->",
+    //! );
+    //! # }; test();
+    //! ```
+
+    pub use crate::body_combinators::{
+        chain, limit, map_chunks, prefix, suffix, Chain, Limit, MapChunks,
+    };
+}
@@ -11,6 +11,8 @@ use tokio::{
     sync::mpsc::{UnboundedReceiver, UnboundedSender},
 };
 
+use crate::body_coalesce::{Coalescer, Coalescing};
+
 /// Returns an `AsyncWrite` response body writer and its associated body type.
 ///
 /// # Examples
@@ -28,8 +30,43 @@ use tokio::{
 /// # ;}
 /// ```
 pub fn writer() -> (Writer, impl MessageBody) {
+    writer_coalesced(Coalescing::default())
+}
+
+/// Returns an `AsyncWrite` response body writer and its associated body type, combining small
+/// writes into larger chunks according to `coalescing`.
+///
+/// See [`writer()`] and [`Coalescing`] for more.
+///
+/// # Examples
+/// ```
+/// # use actix_web::{HttpResponse, web};
+/// use actix_web_lab::body::{self, Coalescing};
+/// use tokio::io::AsyncWriteExt as _;
+///
+/// # async fn index() {
+/// let (mut wrt, body) = body::writer_coalesced(Coalescing::new(8 * 1024));
+///
+/// let _ = tokio::spawn(async move {
+///     for n in 0..1000 {
+///         wrt.write_all(format!("{n}\n").as_bytes()).await?;
+///     }
+///
+///     Ok::<_, std::io::Error>(())
+/// });
+///
+/// HttpResponse::Ok().body(body)
+/// # ;}
+/// ```
+pub fn writer_coalesced(coalescing: Coalescing) -> (Writer, impl MessageBody) {
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-    (Writer { tx }, BodyStream { rx })
+
+    let body = BodyStream {
+        rx,
+        coalescer: Coalescer::new(coalescing),
+    };
+
+    (Writer { tx }, body)
 }
 
 /// An `AsyncWrite` response body writer.
@@ -63,6 +100,7 @@ impl AsyncWrite for Writer {
 #[derive(Debug)]
 struct BodyStream {
     rx: UnboundedReceiver<Bytes>,
+    coalescer: Coalescer<io::Error>,
 }
 
 impl MessageBody for BodyStream {
@@ -76,7 +114,8 @@ impl MessageBody for BodyStream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
-        Poll::Ready(ready!(self.rx.poll_recv(cx)).map(Ok))
+        let Self { rx, coalescer } = &mut *self;
+        coalescer.poll_next(cx, |cx| Poll::Ready(ready!(rx.poll_recv(cx)).map(Ok)))
     }
 }
 
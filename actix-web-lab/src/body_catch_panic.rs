@@ -0,0 +1,143 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_web::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use pin_project_lite::pin_project;
+use tracing::error;
+
+use crate::BoxError;
+
+/// Wraps a body so that panics which occur while it is polled are caught.
+///
+/// Panics raised from inside a streaming body (e.g., in user-provided stream adapters) happen
+/// after the response has been started and are not covered by the
+/// [`CatchPanic`](crate::middleware::CatchPanic) middleware. With this wrapper, a panic is logged
+/// and converted into a body stream error, which causes the connection to be closed cleanly
+/// instead of tearing down the worker mid-response. The wrapped body is not polled again after
+/// it has panicked.
+///
+/// # Examples
+/// ```
+/// use actix_web::{body::BodyStream, get, web, HttpResponse, Responder};
+/// use actix_web_lab::body;
+/// use futures_util::stream;
+///
+/// #[get("/")]
+/// async fn index() -> impl Responder {
+///     let chunks = stream::iter([Ok::<_, std::io::Error>(web::Bytes::from("hello"))]);
+///     HttpResponse::Ok().body(body::catch_panic(BodyStream::new(chunks)))
+/// }
+/// ```
+pub fn catch_panic<B: MessageBody>(body: B) -> impl MessageBody {
+    CatchPanicBody {
+        body,
+        panicked: false,
+    }
+}
+
+pin_project! {
+    struct CatchPanicBody<B> {
+        #[pin]
+        body: B,
+        panicked: bool,
+    }
+}
+
+impl<B: MessageBody> MessageBody for CatchPanicBody<B> {
+    type Error = BoxError;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        if *this.panicked {
+            return Poll::Ready(None);
+        }
+
+        let body = this.body;
+
+        match panic::catch_unwind(AssertUnwindSafe(|| body.poll_next(cx))) {
+            Ok(poll) => poll.map_err(Into::into),
+
+            Err(panic_err) => {
+                *this.panicked = true;
+
+                error!(
+                    "response body panicked while being polled: {}",
+                    panic_message(&panic_err)
+                );
+
+                Poll::Ready(Some(Err("response body panicked".into())))
+            }
+        }
+    }
+}
+
+fn panic_message(panic_err: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = panic_err.downcast_ref::<&'static str>() {
+        msg
+    } else if let Some(msg) = panic_err.downcast_ref::<String>() {
+        msg
+    } else {
+        "<non-string panic payload>"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::{self, BodyStream};
+    use futures_util::{future::poll_fn, stream, StreamExt as _};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn passes_through_chunks() {
+        let chunks = stream::iter([
+            Ok::<_, BoxError>(Bytes::from("foo")),
+            Ok(Bytes::from("bar")),
+        ]);
+        let body = catch_panic(BodyStream::new(chunks));
+
+        assert_eq!(body::to_bytes(body).await.ok().unwrap(), "foobar");
+    }
+
+    #[actix_web::test]
+    async fn panic_becomes_error() {
+        let chunks = stream::iter([1, 2]).map(|n| {
+            if n == 2 {
+                panic!("the disco");
+            }
+
+            Ok::<_, BoxError>(Bytes::from(n.to_string()))
+        });
+
+        let mut body = Box::pin(catch_panic(BodyStream::new(chunks)));
+
+        assert_eq!(
+            poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .ok()
+                .unwrap(),
+            "1"
+        );
+
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .is_err());
+
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+    }
+}
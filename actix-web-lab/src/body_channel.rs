@@ -7,7 +7,10 @@ use actix_web::body::{BodySize, MessageBody};
 use bytes::Bytes;
 use tokio::sync::mpsc::{error::SendError, UnboundedReceiver, UnboundedSender};
 
-use crate::BoxError;
+use crate::{
+    body_coalesce::{Coalescer, Coalescing},
+    BoxError,
+};
 
 /// Returns a sender half and a receiver half that can be used as a body type.
 ///
@@ -31,8 +34,38 @@ use crate::BoxError;
 /// # ;}
 /// ```
 pub fn channel<E: Into<BoxError>>() -> (Sender<E>, impl MessageBody) {
+    channel_coalesced(Coalescing::default())
+}
+
+/// Returns a sender half and a receiver half that can be used as a body type, combining small
+/// chunks into larger ones according to `coalescing`.
+///
+/// See [`channel()`] and [`Coalescing`] for more.
+///
+/// # Examples
+/// ```
+/// # use actix_web::{HttpResponse, web};
+/// use std::convert::Infallible;
+///
+/// use actix_web_lab::body::{self, Coalescing};
+///
+/// # async fn index() {
+/// let (mut body_tx, body) = body::channel_coalesced::<Infallible>(Coalescing::new(8 * 1024));
+///
+/// let _ = web::block(move || {
+///     for n in 0..1000 {
+///         body_tx.send(web::Bytes::from(format!("{n}\n"))).unwrap();
+///     }
+/// });
+///
+/// HttpResponse::Ok().body(body)
+/// # ;}
+/// ```
+pub fn channel_coalesced<E: Into<BoxError>>(
+    coalescing: Coalescing,
+) -> (Sender<E>, impl MessageBody) {
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-    (Sender::new(tx), Receiver::new(rx))
+    (Sender::new(tx), Receiver::new(rx, coalescing))
 }
 
 /// A channel-like sender for body chunks.
@@ -76,11 +109,15 @@ impl<E> Sender<E> {
 #[derive(Debug)]
 struct Receiver<E> {
     rx: UnboundedReceiver<Result<Bytes, E>>,
+    coalescer: Coalescer<E>,
 }
 
 impl<E> Receiver<E> {
-    fn new(rx: UnboundedReceiver<Result<Bytes, E>>) -> Self {
-        Self { rx }
+    fn new(rx: UnboundedReceiver<Result<Bytes, E>>, coalescing: Coalescing) -> Self {
+        Self {
+            rx,
+            coalescer: Coalescer::new(coalescing),
+        }
     }
 }
 
@@ -98,16 +135,64 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
-        self.rx.poll_recv(cx)
+        let Self { rx, coalescer } = &mut *self;
+        coalescer.poll_next(cx, |cx| rx.poll_recv(cx))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io;
+    use std::{io, time::Duration};
+
+    use futures_util::future::poll_fn;
 
     use super::*;
 
     static_assertions::assert_impl_all!(Sender<io::Error>: Send, Sync, Unpin);
     static_assertions::assert_impl_all!(Receiver<io::Error>: Send, Sync, Unpin, MessageBody);
+
+    #[actix_web::test]
+    async fn coalesces_available_chunks() {
+        let (mut tx, body) = channel_coalesced::<io::Error>(Coalescing::new(8));
+        let mut body = Box::pin(body);
+
+        for chunk in ["a", "b", "c"] {
+            tx.send(Bytes::from(chunk)).unwrap();
+        }
+
+        // partially filled buffer is yielded when no more chunks are available
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok().unwrap(), "abc");
+
+        for chunk in ["12345", "67890", "x"] {
+            tx.send(Bytes::from(chunk)).unwrap();
+        }
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok().unwrap(), "1234567890");
+
+        tx.close(Some(io::Error::new(io::ErrorKind::Other, "test")))
+            .unwrap();
+
+        // buffered chunks are yielded before error
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok().unwrap(), "x");
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert!(chunk.unwrap().is_err());
+    }
+
+    #[actix_web::test]
+    async fn coalesces_within_max_delay() {
+        let (mut tx, body) =
+            channel_coalesced::<io::Error>(Coalescing::new(8).max_delay(Duration::from_millis(20)));
+
+        actix_web::rt::spawn(async move {
+            for chunk in ["a", "b", "c"] {
+                tx.send(Bytes::from(chunk)).unwrap();
+                actix_web::rt::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+
+        assert_eq!(actix_web::body::to_bytes(body).await.ok().unwrap(), "abc");
+    }
 }
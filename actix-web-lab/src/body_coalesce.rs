@@ -0,0 +1,135 @@
+use std::{
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_web::rt::time::{sleep, Sleep};
+use bytes::{Bytes, BytesMut};
+
+/// Configuration for combining small body chunks into larger ones.
+///
+/// Used with [`body::channel_coalesced()`](crate::body::channel_coalesced) and
+/// [`body::writer_coalesced()`](crate::body::writer_coalesced). Chunks that are already available
+/// when the body is polled are accumulated until `threshold` bytes have been collected. This
+/// reduces the number of writes to the socket when producers send many small chunks.
+///
+/// By default, a partially filled buffer is yielded as soon as no more chunks are immediately
+/// available. Use [`max_delay`](Self::max_delay) to wait a bounded amount of time for more chunks
+/// instead, trading latency for fewer, larger writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coalescing {
+    threshold: usize,
+    max_delay: Option<Duration>,
+}
+
+impl Coalescing {
+    /// Constructs a new coalescing configuration which accumulates up to `threshold` bytes.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            max_delay: None,
+        }
+    }
+
+    /// Sets the maximum time to wait for more chunks before yielding a partially filled buffer.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+}
+
+/// Chunk accumulator used by channel-like body types.
+#[derive(Debug)]
+pub(crate) struct Coalescer<E> {
+    config: Coalescing,
+    buf: BytesMut,
+    deadline: Option<Pin<Box<Sleep>>>,
+    pending_err: Option<E>,
+}
+
+// errors are never pinned
+impl<E> Unpin for Coalescer<E> {}
+
+impl<E> Coalescer<E> {
+    pub(crate) fn new(config: Coalescing) -> Self {
+        Self {
+            config,
+            buf: BytesMut::new(),
+            deadline: None,
+            pending_err: None,
+        }
+    }
+
+    fn flush(&mut self) -> Poll<Option<Result<Bytes, E>>> {
+        self.deadline = None;
+        Poll::Ready(Some(Ok(self.buf.split().freeze())))
+    }
+
+    /// Polls `poll_recv` for chunks, combining them according to the configured threshold.
+    pub(crate) fn poll_next(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut poll_recv: impl FnMut(&mut Context<'_>) -> Poll<Option<Result<Bytes, E>>>,
+    ) -> Poll<Option<Result<Bytes, E>>> {
+        if let Some(err) = self.pending_err.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        loop {
+            if !self.buf.is_empty() && self.buf.len() >= self.config.threshold {
+                return self.flush();
+            }
+
+            match poll_recv(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    // avoid copying chunks that are large enough by themselves
+                    if self.buf.is_empty() && chunk.len() >= self.config.threshold {
+                        return Poll::Ready(Some(Ok(chunk)));
+                    }
+
+                    self.buf.extend_from_slice(&chunk);
+                }
+
+                Poll::Ready(Some(Err(err))) => {
+                    if self.buf.is_empty() {
+                        return Poll::Ready(Some(Err(err)));
+                    }
+
+                    // yield buffered chunks before the error
+                    self.pending_err = Some(err);
+                    return self.flush();
+                }
+
+                Poll::Ready(None) => {
+                    if self.buf.is_empty() {
+                        return Poll::Ready(None);
+                    }
+
+                    return self.flush();
+                }
+
+                Poll::Pending => {
+                    if self.buf.is_empty() {
+                        return Poll::Pending;
+                    }
+
+                    let Some(max_delay) = self.config.max_delay else {
+                        return self.flush();
+                    };
+
+                    let deadline = self
+                        .deadline
+                        .get_or_insert_with(|| Box::pin(sleep(max_delay)));
+
+                    if deadline.as_mut().poll(cx).is_ready() {
+                        return self.flush();
+                    }
+
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
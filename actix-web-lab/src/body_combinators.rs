@@ -0,0 +1,265 @@
+//! Body combinators.
+//!
+//! See [`chain()`], [`map_chunks()`], and [`limit()`] docs.
+
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use pin_project_lite::pin_project;
+
+use crate::BoxError;
+
+/// Creates a body which yields all chunks of `first` followed by all chunks of `second`.
+pub fn chain<A, B>(first: A, second: B) -> Chain<A, B>
+where
+    A: MessageBody,
+    B: MessageBody,
+{
+    Chain {
+        first,
+        second,
+        first_done: false,
+    }
+}
+
+/// Creates a body which yields `prefix` followed by all chunks of `body`.
+pub fn prefix<B>(prefix: impl Into<Bytes>, body: B) -> Chain<Bytes, B>
+where
+    B: MessageBody,
+{
+    chain(prefix.into(), body)
+}
+
+/// Creates a body which yields all chunks of `body` followed by `suffix`.
+pub fn suffix<B>(body: B, suffix: impl Into<Bytes>) -> Chain<B, Bytes>
+where
+    B: MessageBody,
+{
+    chain(body, suffix.into())
+}
+
+/// Creates a body which transforms each chunk of `body` using `map`.
+///
+/// Since chunk lengths may change, the resulting body always has a streaming size unless `body`
+/// is empty.
+pub fn map_chunks<B, F>(body: B, map: F) -> MapChunks<B, F>
+where
+    B: MessageBody,
+    F: FnMut(Bytes) -> Bytes,
+{
+    MapChunks { body, map }
+}
+
+/// Creates a body which yields an error if `body` produces more than `limit` bytes.
+///
+/// If the size of `body` is known to exceed the limit, the error is yielded before any chunks are.
+pub fn limit<B>(body: B, limit: u64) -> Limit<B>
+where
+    B: MessageBody,
+{
+    Limit {
+        body,
+        limit,
+        seen: 0,
+    }
+}
+
+pin_project! {
+    /// Body returned from [`chain()`], [`prefix()`], and [`suffix()`].
+    #[derive(Debug)]
+    pub struct Chain<A, B> {
+        #[pin]
+        first: A,
+        #[pin]
+        second: B,
+        first_done: bool,
+    }
+}
+
+impl<A, B> MessageBody for Chain<A, B>
+where
+    A: MessageBody,
+    B: MessageBody,
+{
+    type Error = BoxError;
+
+    fn size(&self) -> BodySize {
+        if self.first_done {
+            return self.second.size();
+        }
+
+        match (self.first.size(), self.second.size()) {
+            (BodySize::None, size) | (size, BodySize::None) => size,
+            (BodySize::Sized(a), BodySize::Sized(b)) => BodySize::Sized(a + b),
+            _ => BodySize::Stream,
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        if !*this.first_done {
+            match ready!(this.first.poll_next(cx)) {
+                Some(res) => return Poll::Ready(Some(res.map_err(Into::into))),
+                None => *this.first_done = true,
+            }
+        }
+
+        this.second.poll_next(cx).map_err(Into::into)
+    }
+}
+
+pin_project! {
+    /// Body returned from [`map_chunks()`].
+    pub struct MapChunks<B, F> {
+        #[pin]
+        body: B,
+        map: F,
+    }
+}
+
+impl<B, F> MessageBody for MapChunks<B, F>
+where
+    B: MessageBody,
+    F: FnMut(Bytes) -> Bytes,
+{
+    type Error = B::Error;
+
+    fn size(&self) -> BodySize {
+        match self.body.size() {
+            BodySize::None => BodySize::None,
+            _ => BodySize::Stream,
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        this.body
+            .poll_next(cx)
+            .map(|chunk| chunk.map(|res| res.map(this.map)))
+    }
+}
+
+pin_project! {
+    /// Body returned from [`limit()`].
+    #[derive(Debug)]
+    pub struct Limit<B> {
+        #[pin]
+        body: B,
+        limit: u64,
+        seen: u64,
+    }
+}
+
+impl<B> MessageBody for Limit<B>
+where
+    B: MessageBody,
+{
+    type Error = BoxError;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        if let BodySize::Sized(size) = self.body.size() {
+            if size > self.limit {
+                return Poll::Ready(Some(Err(limit_error(self.limit))));
+            }
+        }
+
+        let this = self.project();
+
+        match ready!(this.body.poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                *this.seen += chunk.len() as u64;
+
+                if *this.seen > *this.limit {
+                    return Poll::Ready(Some(Err(limit_error(*this.limit))));
+                }
+
+                Poll::Ready(Some(Ok(chunk)))
+            }
+
+            Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+fn limit_error(limit: u64) -> BoxError {
+    format!("body exceeded limit of {limit} bytes").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::body::{self, BodyStream};
+    use futures_util::stream;
+
+    use super::*;
+
+    fn stream_body(chunks: &'static [&'static str]) -> impl MessageBody {
+        BodyStream::new(stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))),
+        ))
+    }
+
+    #[actix_web::test]
+    async fn chain_sizes() {
+        assert_eq!(chain("foo", "bar").size(), BodySize::Sized(6));
+        assert_eq!(chain(body::None::new(), "bar").size(), BodySize::Sized(3));
+        assert_eq!(chain("foo", body::None::new()).size(), BodySize::Sized(3));
+        assert_eq!(
+            chain(body::None::new(), body::None::new()).size(),
+            BodySize::None
+        );
+        assert_eq!(chain("foo", stream_body(&[])).size(), BodySize::Stream);
+
+        let body = chain(stream_body(&["a", "b"]), "c");
+        assert_eq!(body::to_bytes(body).await.unwrap(), "abc");
+    }
+
+    #[actix_web::test]
+    async fn prefix_and_suffix() {
+        let body = suffix(prefix("[", stream_body(&["1", ",", "2"])), "]");
+        assert_eq!(body::to_bytes(body).await.unwrap(), "[1,2]");
+    }
+
+    #[actix_web::test]
+    async fn map_chunks_transforms() {
+        let body = map_chunks("foo", |chunk| Bytes::from(chunk.to_ascii_uppercase()));
+        assert_eq!(body.size(), BodySize::Stream);
+        assert_eq!(body::to_bytes(body).await.unwrap(), "FOO");
+    }
+
+    #[actix_web::test]
+    async fn limit_enforced() {
+        let body = limit(stream_body(&["abc", "def"]), 6);
+        assert_eq!(body::to_bytes(body).await.unwrap(), "abcdef");
+
+        let body = limit(stream_body(&["abc", "def"]), 5);
+        assert!(body::to_bytes(body).await.is_err());
+
+        let body = limit("abcdef", 5);
+        assert!(body::to_bytes(body).await.is_err());
+    }
+}
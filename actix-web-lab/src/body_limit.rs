@@ -11,12 +11,13 @@ use std::{
 
 use actix_web::{
     dev::{self, Payload},
-    FromRequest, HttpMessage as _, HttpRequest, ResponseError,
+    http::header::ContentType,
+    web, FromRequest, HttpMessage as _, HttpRequest, HttpResponse, ResponseError,
 };
 use derive_more::Display;
 use futures_core::Stream as _;
 
-use crate::header::ContentLength;
+use crate::{error::ErrorChain, header::ContentLength, uploads::UploadReporter};
 
 /// Default body size limit of 2MiB.
 pub const DEFAULT_BODY_LIMIT: usize = 2_097_152;
@@ -37,6 +38,30 @@ pub const DEFAULT_BODY_LIMIT: usize = 2_097_152;
 ///     body
 /// }
 /// ```
+///
+/// The limit can also be set at runtime by registering a [`BodyLimitConfig`] as app data, which
+/// takes precedence over the `LIMIT` parameter. This allows limits to differ per environment or per
+/// scope without recompiling.
+///
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::extract::{BodyLimit, BodyLimitConfig};
+///
+/// async fn upload(body: BodyLimit<web::Bytes>) -> String {
+///     format!("received {} bytes", body.into_inner().len())
+/// }
+///
+/// let limit = std::env::var("UPLOAD_LIMIT")
+///     .ok()
+///     .and_then(|limit| limit.parse().ok())
+///     .unwrap_or(8_388_608);
+///
+/// App::new().service(
+///     web::scope("/uploads")
+///         .app_data(BodyLimitConfig::default().limit(limit))
+///         .route("", web::post().to(upload)),
+/// );
+/// ```
 #[derive(Debug, PartialEq, Eq)]
 pub struct BodyLimit<T, const LIMIT: usize = DEFAULT_BODY_LIMIT> {
     inner: T,
@@ -71,6 +96,38 @@ impl<T, const LIMIT: usize> BodyLimit<T, LIMIT> {
     }
 }
 
+/// Runtime configuration for the [`BodyLimit`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). When registered, the
+/// configured limit is used instead of the extractor's `LIMIT` parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyLimitConfig {
+    limit: usize,
+}
+
+impl BodyLimitConfig {
+    /// Sets maximum accepted payload size, in bytes.
+    ///
+    /// The default limit is 2MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> Option<&Self> {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+    }
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_BODY_LIMIT,
+        }
+    }
+}
+
 impl<T, const LIMIT: usize> FromRequest for BodyLimit<T, LIMIT>
 where
     T: FromRequest + 'static,
@@ -80,10 +137,15 @@ where
     type Future = BodyLimitFut<T, LIMIT>;
 
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // dropping the reporter on any error path marks the upload as aborted
+        let progress = UploadReporter::for_request(req);
+
+        let limit = BodyLimitConfig::from_req(req).map_or(LIMIT, |config| config.limit);
+
         // fast check of Content-Length header
         match req.get_header::<ContentLength>() {
             // CL header indicated that payload would be too large
-            Some(len) if len > LIMIT => return BodyLimitFut::new_error(BodyLimitError::Overflow),
+            Some(len) if len > limit => return BodyLimitFut::new_error(BodyLimitError::Overflow),
             _ => {}
         }
 
@@ -94,6 +156,8 @@ where
                 fut: Box::pin(T::from_request(req, payload)),
                 counter_pl: counter,
                 size: 0,
+                limit,
+                progress,
             },
         }
     }
@@ -137,6 +201,12 @@ where
 
         /// Running payload size count.
         size: usize,
+
+        /// Effective payload size limit.
+        limit: usize,
+
+        /// Upload progress reporter, if upload tracking is enabled.
+        progress: Option<UploadReporter>,
     },
 }
 
@@ -164,20 +234,32 @@ where
                 fut,
                 counter_pl,
                 size,
+                limit,
+                progress,
             } => {
                 // poll inner extractor first which also polls original payload stream
-                let res = ready!(fut.as_mut().poll(cx).map_err(BodyLimitError::Extractor)?);
+                let res = fut.as_mut().poll(cx).map_err(BodyLimitError::Extractor)?;
 
                 // catch up with payload length counter checks
                 while let Poll::Ready(Some(Ok(chunk))) = Pin::new(&mut *counter_pl).poll_next(cx) {
                     // update running size
                     *size += chunk.len();
 
-                    if *size > LIMIT {
+                    if *size > *limit {
                         return Poll::Ready(Err(BodyLimitError::Overflow));
                     }
                 }
 
+                if let Some(progress) = progress {
+                    progress.update(*size as u64);
+                }
+
+                let res = ready!(res);
+
+                if let Some(progress) = progress.take() {
+                    progress.complete();
+                }
+
                 let ret = BodyLimit { inner: res };
 
                 Poll::Ready(Ok(ret))
@@ -221,6 +303,20 @@ where
     T: FromRequest + 'static,
     T::Error: fmt::Debug + fmt::Display,
 {
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code())
+            .insert_header(ContentType::plaintext())
+            .body(self.to_string());
+
+        let messages = match self {
+            Self::Extractor(err) => vec![self.to_string(), err.to_string()],
+            Self::Overflow => vec![self.to_string()],
+        };
+
+        ErrorChain::from_messages(messages).insert_into(&mut res);
+
+        res
+    }
 }
 
 #[cfg(test)]
@@ -277,4 +373,30 @@ mod tests {
         let body = BodyLimit::<Bytes, 4>::from_request(&req, &mut pl).await;
         assert!(matches!(body.unwrap_err(), BodyLimitError::Overflow));
     }
+
+    #[actix_web::test]
+    async fn config_overrides_limit() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BodyLimitConfig::default().limit(4))
+            .set_payload(Bytes::from_static(b"0123456789"))
+            .to_http_parts();
+
+        let body = BodyLimit::<Bytes, 100>::from_request(&req, &mut pl).await;
+        assert!(matches!(body.unwrap_err(), BodyLimitError::Overflow));
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::Data::new(BodyLimitConfig::default().limit(100)))
+            .insert_header((
+                header::CONTENT_LENGTH,
+                header::HeaderValue::from_static("10"),
+            ))
+            .set_payload(Bytes::from_static(b"0123456789"))
+            .to_http_parts();
+
+        let body = BodyLimit::<Bytes, 4>::from_request(&req, &mut pl).await;
+        assert_eq!(
+            body.ok().unwrap().into_inner(),
+            Bytes::from_static(b"0123456789")
+        );
+    }
 }
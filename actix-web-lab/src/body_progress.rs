@@ -0,0 +1,279 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_web::body::{BodySize, MessageBody};
+use bytes::Bytes;
+use pin_project_lite::pin_project;
+
+/// State of a body when a [`Progress`] report was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProgressStatus {
+    /// Body is still being streamed.
+    Streaming,
+
+    /// Body has been streamed to completion.
+    Completed,
+
+    /// Body yielded an error or was dropped before completion (e.g., the client disconnected).
+    Aborted,
+}
+
+/// Progress report passed to [`with_progress()`] callbacks.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    bytes: u64,
+    elapsed: Duration,
+    status: ProgressStatus,
+}
+
+impl Progress {
+    /// Returns the cumulative number of bytes yielded by the body.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the time elapsed since the body was first polled.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the state of the body at the time of this report.
+    pub fn status(&self) -> ProgressStatus {
+        self.status
+    }
+}
+
+/// Wraps a body so that `callback` is invoked with the progress of streaming it.
+///
+/// By default, the callback is invoked after every chunk. Use [`WithProgress::interval()`] to
+/// limit how often in-progress reports are made. A final report is always made when the body
+/// completes, errors, or is dropped early; this makes the wrapper suitable for bandwidth accounting
+/// as well as progress tracking.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{get, HttpResponse, Responder};
+/// use actix_web_lab::body::{self, ProgressStatus};
+///
+/// #[get("/download")]
+/// async fn download() -> impl Responder {
+///     let body = body::with_progress("file contents", |progress| {
+///         if progress.status() != ProgressStatus::Streaming {
+///             tracing::info!(bytes = progress.bytes(), "download finished");
+///         }
+///     })
+///     .interval(Duration::from_secs(1));
+///
+///     HttpResponse::Ok().body(body)
+/// }
+/// ```
+pub fn with_progress<B>(body: B, callback: impl FnMut(Progress) + 'static) -> WithProgress<B>
+where
+    B: MessageBody,
+{
+    WithProgress {
+        body,
+        reporter: Reporter {
+            callback: Box::new(callback),
+            interval: Duration::ZERO,
+            bytes: 0,
+            started: None,
+            last_report: None,
+            finished: false,
+        },
+    }
+}
+
+pin_project! {
+    /// Body returned from [`with_progress()`].
+    pub struct WithProgress<B> {
+        #[pin]
+        body: B,
+        reporter: Reporter,
+    }
+}
+
+impl<B> WithProgress<B> {
+    /// Sets the minimum time between in-progress reports.
+    ///
+    /// Final reports are made regardless of this interval.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.reporter.interval = interval;
+        self
+    }
+}
+
+impl<B> MessageBody for WithProgress<B>
+where
+    B: MessageBody,
+{
+    type Error = B::Error;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        if this.reporter.finished {
+            return Poll::Ready(None);
+        }
+
+        let now = Instant::now();
+        this.reporter.started.get_or_insert(now);
+
+        let chunk = ready!(this.body.poll_next(cx));
+
+        match &chunk {
+            Some(Ok(bytes)) => {
+                this.reporter.bytes += bytes.len() as u64;
+                this.reporter.maybe_report();
+            }
+            Some(Err(_)) => this.reporter.finish(ProgressStatus::Aborted),
+            None => this.reporter.finish(ProgressStatus::Completed),
+        }
+
+        Poll::Ready(chunk)
+    }
+}
+
+struct Reporter {
+    callback: Box<dyn FnMut(Progress)>,
+    interval: Duration,
+    bytes: u64,
+    started: Option<Instant>,
+    last_report: Option<Instant>,
+    finished: bool,
+}
+
+impl Reporter {
+    fn report(&mut self, now: Instant, status: ProgressStatus) {
+        let elapsed = self
+            .started
+            .map_or(Duration::ZERO, |started| now.duration_since(started));
+
+        self.last_report = Some(now);
+
+        (self.callback)(Progress {
+            bytes: self.bytes,
+            elapsed,
+            status,
+        });
+    }
+
+    fn maybe_report(&mut self) {
+        let now = Instant::now();
+
+        let due = self
+            .last_report
+            .map_or(true, |last| now.duration_since(last) >= self.interval);
+
+        if due {
+            self.report(now, ProgressStatus::Streaming);
+        }
+    }
+
+    fn finish(&mut self, status: ProgressStatus) {
+        self.finished = true;
+        self.report(Instant::now(), status);
+    }
+}
+
+impl Drop for Reporter {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish(ProgressStatus::Aborted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, convert::Infallible, rc::Rc};
+
+    use actix_web::body::{self, BodyStream};
+    use futures_util::stream;
+
+    use super::*;
+
+    fn stream_body(chunks: &'static [&'static str]) -> impl MessageBody {
+        BodyStream::new(stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))),
+        ))
+    }
+
+    fn recorder() -> (Rc<RefCell<Vec<Progress>>>, impl FnMut(Progress) + 'static) {
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let reports2 = Rc::clone(&reports);
+        (reports, move |progress| {
+            reports2.borrow_mut().push(progress)
+        })
+    }
+
+    #[actix_web::test]
+    async fn reports_each_chunk_and_completion() {
+        let (reports, callback) = recorder();
+
+        let body = with_progress(stream_body(&["foo", "barbaz"]), callback);
+        assert_eq!(body::to_bytes(body).await.ok().unwrap(), "foobarbaz");
+
+        let reports = reports.borrow();
+        let reports = reports
+            .iter()
+            .map(|progress| (progress.bytes(), progress.status()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reports,
+            [
+                (3, ProgressStatus::Streaming),
+                (9, ProgressStatus::Streaming),
+                (9, ProgressStatus::Completed),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn interval_limits_reports() {
+        let (reports, callback) = recorder();
+
+        let body = with_progress(stream_body(&["a", "b", "c"]), callback)
+            .interval(Duration::from_secs(60));
+        body::to_bytes(body).await.ok().unwrap();
+
+        let statuses = reports
+            .borrow()
+            .iter()
+            .map(Progress::status)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            [ProgressStatus::Streaming, ProgressStatus::Completed]
+        );
+    }
+
+    #[actix_web::test]
+    async fn reports_abort_on_drop() {
+        let (reports, callback) = recorder();
+
+        drop(with_progress("foo", callback));
+
+        let reports = reports.borrow();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].bytes(), 0);
+        assert_eq!(reports[0].status(), ProgressStatus::Aborted);
+    }
+}
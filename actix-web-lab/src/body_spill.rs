@@ -0,0 +1,243 @@
+use std::{error::Error as StdError, fmt, io, pin::pin};
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody, SizedStream},
+    rt::task::spawn_blocking,
+};
+use bytes::{Bytes, BytesMut};
+use futures_util::{future::poll_fn, stream};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt as _, AsyncSeekExt as _, AsyncWriteExt as _},
+};
+
+/// Size of chunks read back from a spilled body.
+pub(crate) const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A fully collected body, returned from [`collect_with_spill()`].
+#[derive(Debug)]
+pub enum CollectedBody {
+    /// Body was small enough to be buffered in memory.
+    Memory(Bytes),
+
+    /// Body exceeded the memory limit and was written to an anonymous temporary file.
+    ///
+    /// The file is positioned at its start and is deleted when dropped.
+    File {
+        /// Temporary file containing the body.
+        file: File,
+
+        /// Length of the body, in bytes.
+        len: u64,
+    },
+}
+
+impl CollectedBody {
+    /// Returns length of the collected body, in bytes.
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Memory(bytes) => bytes.len() as u64,
+            Self::File { len, .. } => *len,
+        }
+    }
+
+    /// Returns true if the collected body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the buffered body if it was not spilled to disk.
+    pub fn as_bytes(&self) -> Option<&Bytes> {
+        match self {
+            Self::Memory(bytes) => Some(bytes),
+            Self::File { .. } => None,
+        }
+    }
+
+    /// Converts the collected body back into a sized body, suitable for re-attaching to a request
+    /// or response.
+    pub fn into_body(self) -> BoxBody {
+        match self {
+            Self::Memory(bytes) => BoxBody::new(bytes),
+
+            Self::File { file, len } => {
+                let chunks = stream::unfold(file, |mut file| async move {
+                    let mut buf = BytesMut::with_capacity(READ_CHUNK_SIZE);
+
+                    match file.read_buf(&mut buf).await {
+                        Ok(0) => None,
+                        Ok(_) => Some((Ok(buf.freeze()), file)),
+                        Err(err) => Some((Err(err), file)),
+                    }
+                });
+
+                BoxBody::new(SizedStream::new(len, Box::pin(chunks)))
+            }
+        }
+    }
+}
+
+/// Error returned from [`collect_with_spill()`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CollectError<E> {
+    /// Body yielded an error.
+    Body(E),
+
+    /// Temporary file could not be created, written to, or rewound.
+    Io(io::Error),
+}
+
+impl<E: fmt::Display> fmt::Display for CollectError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(err) => write!(f, "body error: {err}"),
+            Self::Io(err) => write!(f, "failed to spill body to disk: {err}"),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for CollectError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Body(err) => Some(err),
+            Self::Io(err) => Some(err),
+        }
+    }
+}
+
+impl<E> From<io::Error> for CollectError<E> {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Collects `body`, buffering up to `mem_limit` bytes in memory before spilling to a temporary
+/// file.
+///
+/// Useful for middleware which need to see a whole body (e.g., to compute an ETag or verify a
+/// signature) without risking unbounded memory use. Bodies of known size which exceed the limit
+/// are written to disk without first being buffered.
+///
+/// # Examples
+/// ```
+/// # actix_web::rt::System::new().block_on(async {
+/// use actix_web_lab::body::{collect_with_spill, CollectedBody};
+///
+/// let body = collect_with_spill("hello world", 1024).await.unwrap();
+/// assert!(matches!(body, CollectedBody::Memory(_)));
+///
+/// let body = collect_with_spill("hello world", 4).await.unwrap();
+/// assert!(matches!(body, CollectedBody::File { len: 11, .. }));
+/// # });
+/// ```
+pub async fn collect_with_spill<B: MessageBody>(
+    body: B,
+    mem_limit: usize,
+) -> Result<CollectedBody, CollectError<B::Error>> {
+    let mut body = pin!(body);
+
+    let mut buf = BytesMut::new();
+    let mut file = None;
+    let mut len = 0;
+
+    match body.size() {
+        BodySize::Sized(size) if size > mem_limit as u64 => file = Some(spill_file().await?),
+        BodySize::Sized(size) => buf.reserve(size as usize),
+        _ => {}
+    }
+
+    while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+        let chunk = chunk.map_err(CollectError::Body)?;
+        len += chunk.len() as u64;
+
+        match file.as_mut() {
+            Some(file) => file.write_all(&chunk).await?,
+
+            None if buf.len() + chunk.len() > mem_limit => {
+                let mut spilled = spill_file().await?;
+                spilled.write_all(&buf).await?;
+                spilled.write_all(&chunk).await?;
+
+                buf = BytesMut::new();
+                file = Some(spilled);
+            }
+
+            None => buf.extend_from_slice(&chunk),
+        }
+    }
+
+    match file {
+        None => Ok(CollectedBody::Memory(buf.freeze())),
+
+        Some(mut file) => {
+            file.flush().await?;
+            file.rewind().await?;
+            Ok(CollectedBody::File { file, len })
+        }
+    }
+}
+
+async fn spill_file() -> io::Result<File> {
+    let file = spawn_blocking(tempfile::tempfile).await.map_err(|_| {
+        io::Error::new(io::ErrorKind::Other, "temporary file creation was canceled")
+    })??;
+
+    Ok(File::from_std(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::body::{self, BodyStream};
+
+    use super::*;
+
+    fn stream_body(chunks: &'static [&'static str]) -> impl MessageBody {
+        BodyStream::new(stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))),
+        ))
+    }
+
+    #[actix_web::test]
+    async fn small_body_in_memory() {
+        let body = collect_with_spill(stream_body(&["foo", "bar"]), 6)
+            .await
+            .ok()
+            .unwrap();
+
+        assert_eq!(body.len(), 6);
+        assert_eq!(body.as_bytes().unwrap(), "foobar");
+        assert_eq!(
+            body::to_bytes(body.into_body()).await.ok().unwrap(),
+            "foobar"
+        );
+    }
+
+    #[actix_web::test]
+    async fn large_body_spilled() {
+        let body = collect_with_spill(stream_body(&["foo", "bar", "baz"]), 5)
+            .await
+            .ok()
+            .unwrap();
+
+        assert_eq!(body.len(), 9);
+        assert!(body.as_bytes().is_none());
+
+        let body = body.into_body();
+        assert_eq!(body.size(), BodySize::Sized(9));
+        assert_eq!(body::to_bytes(body).await.ok().unwrap(), "foobarbaz");
+    }
+
+    #[actix_web::test]
+    async fn sized_body_spilled_eagerly() {
+        let body = collect_with_spill(Bytes::from_static(b"foobarbaz"), 5)
+            .await
+            .unwrap();
+
+        assert!(matches!(body, CollectedBody::File { len: 9, .. }));
+    }
+}
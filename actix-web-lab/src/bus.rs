@@ -0,0 +1,345 @@
+//! In-process publish/subscribe message bus.
+//!
+//! Each message type `T` is its own topic. A [`Bus<T>`] is registered as app data and handlers can
+//! then use the [`Publisher<T>`] and [`Subscriber<T>`] extractors to send and receive messages.
+//!
+//! Channels are bounded. Subscribers that fall too far behind will miss the oldest messages; how
+//! this is handled is controlled by the bus' [`LagPolicy`].
+//!
+//! # Examples
+//! ```
+//! use actix_web::{get, post, App, Responder};
+//! use actix_web_lab::{
+//!     bus::{Bus, Publisher, Subscriber},
+//!     sse,
+//! };
+//!
+//! #[derive(Debug, Clone)]
+//! struct ChatMsg(String);
+//!
+//! #[post("/send")]
+//! async fn send(body: String, publisher: Publisher<ChatMsg>) -> impl Responder {
+//!     let subscribers = publisher.publish(ChatMsg(body));
+//!     format!("delivered to {subscribers} subscribers")
+//! }
+//!
+//! #[get("/events")]
+//! async fn events(subscriber: Subscriber<ChatMsg>) -> impl Responder {
+//!     subscriber.into_sse(|ChatMsg(msg)| sse::Data::new(msg).event("chat_msg").into())
+//! }
+//!
+//! let bus = Bus::<ChatMsg>::new(32);
+//!
+//! App::new().app_data(bus).service(send).service(events)
+//! # ;
+//! ```
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{dev, Error, FromRequest, HttpRequest};
+use futures_core::Stream;
+use futures_util::stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+use crate::{
+    error::LabError,
+    sse::{Event, Sse},
+    util::InfallibleStream,
+};
+
+/// Policy for subscribers that have fallen behind and missed messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum LagPolicy {
+    /// Skip missed messages, log a warning, and continue from the oldest retained message.
+    #[default]
+    Skip,
+
+    /// Close the subscription.
+    Close,
+}
+
+/// Typed message bus.
+///
+/// Cheap to clone; all clones share the same underlying channel.
+#[derive(Debug)]
+pub struct Bus<T> {
+    tx: broadcast::Sender<T>,
+    lag_policy: LagPolicy,
+}
+
+impl<T: Clone> Bus<T> {
+    /// Constructs a new bus that retains up to `capacity` messages for slow subscribers.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+
+        Self {
+            tx,
+            lag_policy: LagPolicy::default(),
+        }
+    }
+
+    /// Sets the policy used by subscribers when they have missed messages.
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Returns a new publisher handle.
+    pub fn publisher(&self) -> Publisher<T> {
+        Publisher {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// Returns a new subscription which will receive messages published from now on.
+    pub fn subscribe(&self) -> Subscriber<T> {
+        Subscriber {
+            rx: self.tx.subscribe(),
+            lag_policy: self.lag_policy,
+        }
+    }
+
+    /// Returns the number of active subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+impl<T> Clone for Bus<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            lag_policy: self.lag_policy,
+        }
+    }
+}
+
+/// Publishing handle for a [`Bus<T>`].
+///
+/// Can be used as an extractor when a `Bus<T>` is registered as app data.
+#[derive(Debug)]
+pub struct Publisher<T> {
+    tx: broadcast::Sender<T>,
+}
+
+impl<T> Publisher<T> {
+    /// Publishes a message to all current subscribers.
+    ///
+    /// Returns the number of subscribers the message was delivered to. Publishing with no
+    /// subscribers is not an error; the message is simply dropped.
+    pub fn publish(&self, msg: T) -> usize {
+        self.tx.send(msg).unwrap_or(0)
+    }
+}
+
+impl<T> Clone for Publisher<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<T: Clone + 'static> FromRequest for Publisher<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        ready(bus_from_app_data::<T>(req, "Publisher").map(|bus| bus.publisher()))
+    }
+}
+
+/// Subscription to a [`Bus<T>`].
+///
+/// Can be used as an extractor when a `Bus<T>` is registered as app data.
+#[derive(Debug)]
+pub struct Subscriber<T> {
+    rx: broadcast::Receiver<T>,
+    lag_policy: LagPolicy,
+}
+
+impl<T: Clone> Subscriber<T> {
+    /// Receives the next message.
+    ///
+    /// Returns `None` if the bus has been dropped or, when using [`LagPolicy::Close`], if this
+    /// subscriber has missed messages.
+    pub async fn recv(&mut self) -> Option<T> {
+        recv_with_lag_policy(&mut self.rx, self.lag_policy).await
+    }
+
+    /// Converts subscription into a stream of messages.
+    pub fn into_stream(self) -> impl Stream<Item = T>
+    where
+        T: 'static,
+    {
+        stream::unfold(self, |mut sub| async move {
+            let msg = sub.recv().await?;
+            Some((msg, sub))
+        })
+    }
+
+    /// Converts subscription into a server-sent events responder, mapping each message to an
+    /// event using `map`.
+    ///
+    /// See also [`sse::from_broadcast()`](crate::sse::from_broadcast).
+    pub fn into_sse<F>(self, map: F) -> Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>
+    where
+        T: 'static,
+        F: FnMut(T) -> Event + 'static,
+    {
+        crate::sse::from_broadcast(self.rx, self.lag_policy, map)
+    }
+}
+
+impl<T: Clone + 'static> FromRequest for Subscriber<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        ready(bus_from_app_data::<T>(req, "Subscriber").map(Bus::subscribe))
+    }
+}
+
+/// Receives next message from `rx`, handling lagged receivers according to `lag_policy`.
+pub(crate) async fn recv_with_lag_policy<T: Clone>(
+    rx: &mut broadcast::Receiver<T>,
+    lag_policy: LagPolicy,
+) -> Option<T> {
+    loop {
+        match rx.recv().await {
+            Ok(msg) => return Some(msg),
+
+            Err(RecvError::Lagged(missed)) => match lag_policy {
+                LagPolicy::Skip => {
+                    warn!("broadcast receiver lagged behind; skipped {missed} messages");
+                }
+                LagPolicy::Close => {
+                    warn!("broadcast receiver lagged behind by {missed} messages; closing");
+                    return None;
+                }
+            },
+
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+fn bus_from_app_data<'a, T: 'static>(
+    req: &'a HttpRequest,
+    extractor: &str,
+) -> Result<&'a Bus<T>, Error> {
+    req.app_data::<Bus<T>>().ok_or_else(|| {
+        debug!(
+            "Failed to extract `{extractor}<{}>` for `{}` handler. For the {extractor} extractor \
+            to work correctly, construct a `Bus::<{}>::new()` and pass it to `App::app_data()`.",
+            core::any::type_name::<T>(),
+            req.match_name().unwrap_or_else(|| req.path()),
+            core::any::type_name::<T>(),
+        );
+
+        LabError::AppDataNotConfigured {
+            type_name: core::any::type_name::<Bus<T>>(),
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest, Responder as _};
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::sse;
+
+    #[actix_web::test]
+    async fn publish_and_receive() {
+        let bus = Bus::<u32>::new(4);
+        let mut sub = bus.subscribe();
+
+        assert_eq!(bus.publisher().publish(1), 1);
+        assert_eq!(bus.publisher().publish(2), 1);
+
+        assert_eq!(sub.recv().await, Some(1));
+        assert_eq!(sub.recv().await, Some(2));
+    }
+
+    #[actix_web::test]
+    async fn publish_without_subscribers() {
+        let bus = Bus::<u32>::new(4);
+        assert_eq!(bus.publisher().publish(1), 0);
+    }
+
+    #[actix_web::test]
+    async fn lagged_subscriber_skips() {
+        let bus = Bus::<u32>::new(2);
+        let mut sub = bus.subscribe();
+        let publisher = bus.publisher();
+
+        for i in 0..5 {
+            publisher.publish(i);
+        }
+
+        assert_eq!(sub.recv().await, Some(3));
+        assert_eq!(sub.recv().await, Some(4));
+    }
+
+    #[actix_web::test]
+    async fn lagged_subscriber_closes() {
+        let bus = Bus::<u32>::new(2).lag_policy(LagPolicy::Close);
+        let mut sub = bus.subscribe();
+        let publisher = bus.publisher();
+
+        for i in 0..5 {
+            publisher.publish(i);
+        }
+
+        assert_eq!(sub.recv().await, None);
+    }
+
+    #[actix_web::test]
+    async fn extractors() {
+        let bus = Bus::<u32>::new(4);
+        let req = TestRequest::default().app_data(bus).to_http_request();
+
+        let mut sub = Subscriber::<u32>::extract(&req).await.unwrap();
+        let publisher = Publisher::<u32>::extract(&req).await.unwrap();
+        publisher.publish(42);
+        assert_eq!(sub.recv().await, Some(42));
+
+        let req = TestRequest::default().to_http_request();
+        Publisher::<u32>::extract(&req).await.unwrap_err();
+        Subscriber::<u32>::extract(&req).await.unwrap_err();
+    }
+
+    #[actix_web::test]
+    async fn sse_bridge() {
+        let bus = Bus::<u32>::new(4);
+        let sub = bus.subscribe();
+        let publisher = bus.publisher();
+
+        publisher.publish(1);
+        publisher.publish(2);
+        drop(publisher);
+        drop(bus);
+
+        let stream = sub.into_stream().collect::<Vec<_>>().await;
+        assert_eq!(stream, [1, 2]);
+
+        let bus = Bus::<u32>::new(4);
+        let sub = bus.subscribe();
+        bus.publisher().publish(1);
+        drop(bus);
+
+        let sse = sub.into_sse(|n| sse::Data::new(n.to_string()).into());
+        let res = sse.respond_to(&TestRequest::default().to_http_request());
+        assert_eq!(
+            body::to_bytes(res.into_body()).await.unwrap(),
+            "data: 1\n\n"
+        );
+    }
+}
@@ -31,6 +31,12 @@ pub const DEFAULT_BYTES_LIMIT: usize = 4_194_304;
 /// - Supports const-generic size limits.
 /// - Will not automatically decompress request bodies.
 ///
+/// # Content Type Allowlist
+/// By default, requests with any `Content-Type` are accepted. Registering a [`BytesConfig`] with
+/// allowed media types as app data causes requests with other, or missing, content types to be
+/// rejected with a `415 Unsupported Media Type` response. This is useful for raw-body endpoints,
+/// such as webhook receivers, that expect a specific format.
+///
 /// # Examples
 /// ```
 /// use actix_web::{post, App};
@@ -50,6 +56,22 @@ pub const DEFAULT_BYTES_LIMIT: usize = 4_194_304;
 ///     format!("Payload up to 32MiB: {info:?}!")
 /// }
 /// ```
+///
+/// Only accept JSON payloads on a webhook endpoint:
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::extract::{Bytes, BytesConfig};
+///
+/// async fn webhook(body: Bytes) -> String {
+///     format!("received {} bytes", body.len())
+/// }
+///
+/// App::new().service(
+///     web::resource("/webhook")
+///         .app_data(BytesConfig::default().allow_content_type(mime::APPLICATION_JSON))
+///         .route(web::post().to(webhook)),
+/// );
+/// ```
 #[derive(Debug)]
 // #[derive(Debug, Deref, DerefMut, AsRef, AsMut)]
 pub struct Bytes<const LIMIT: usize = DEFAULT_BYTES_LIMIT>(pub web::Bytes);
@@ -98,13 +120,69 @@ impl<const LIMIT: usize> FromRequest for Bytes<LIMIT> {
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let fut = if BytesConfig::from_req(req).is_allowed(req) {
+            BytesBody::new(req, payload)
+        } else {
+            BytesBody::Error(Some(BytesPayloadError::ContentType))
+        };
+
         BytesExtractFut {
             req: Some(req.clone()),
-            fut: BytesBody::new(req, payload),
+            fut,
         }
     }
 }
 
+/// Configuration for the [`Bytes`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Extractors used
+/// without a registered config accept all content types.
+#[derive(Debug, Clone)]
+pub struct BytesConfig {
+    content_types: Vec<mime::Mime>,
+}
+
+static DEFAULT_CONFIG: BytesConfig = BytesConfig {
+    content_types: Vec::new(),
+};
+
+impl BytesConfig {
+    /// Adds a media type to the content type allowlist.
+    ///
+    /// Content types are compared without parameters, so allowing `application/json` also allows
+    /// `application/json; charset=utf-8`.
+    pub fn allow_content_type(mut self, mime: mime::Mime) -> Self {
+        self.content_types.push(mime);
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn is_allowed(&self, req: &HttpRequest) -> bool {
+        if self.content_types.is_empty() {
+            return true;
+        }
+
+        let Ok(Some(mime)) = req.mime_type() else {
+            return false;
+        };
+
+        self.content_types
+            .iter()
+            .any(|allowed| allowed.essence_str() == mime.essence_str())
+    }
+}
+
+impl Default for BytesConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG.clone()
+    }
+}
+
 pub struct BytesExtractFut<const LIMIT: usize> {
     req: Option<HttpRequest>,
     fut: BytesBody<LIMIT>,
@@ -224,6 +302,10 @@ pub enum BytesPayloadError {
     #[display(fmt = "Payload has exceeded limit ({limit} bytes).")]
     Overflow { limit: usize },
 
+    /// Content type of the request is not in the allowlist.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
     /// Payload error.
     #[display(fmt = "Error that occur during reading payload: {_0}")]
     Payload(actix_web::error::PayloadError),
@@ -240,6 +322,7 @@ impl ResponseError for BytesPayloadError {
         match self {
             Self::OverflowKnownLength { .. } => StatusCode::PAYLOAD_TOO_LARGE,
             Self::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
             Self::Payload(err) => err.status_code(),
         }
     }
@@ -270,6 +353,8 @@ mod tests {
                     l_limit == r_limit
                 }
 
+                (Self::ContentType, Self::ContentType) => true,
+
                 _ => false,
             }
         }
@@ -383,4 +468,46 @@ mod tests {
             "Payload (16 bytes) is larger than allowed (limit: 10 bytes).",
         );
     }
+
+    #[actix_web::test]
+    async fn content_type_allowlist() {
+        let config = BytesConfig::default()
+            .allow_content_type(mime::APPLICATION_JSON)
+            .allow_content_type(mime::TEXT_PLAIN);
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(config.clone())
+            .insert_header((header::CONTENT_TYPE, "application/json; charset=utf-8"))
+            .set_payload(web::Bytes::from_static(b"{}"))
+            .to_http_parts();
+        let bytes = Bytes::<DEFAULT_BYTES_LIMIT>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_ref(), "{}");
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::Data::new(config.clone()))
+            .insert_header(header::ContentType::form_url_encoded())
+            .set_payload(web::Bytes::from_static(b"a=b"))
+            .to_http_parts();
+        let err = Bytes::<DEFAULT_BYTES_LIMIT>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(config)
+            .set_payload(web::Bytes::from_static(b"{}"))
+            .to_http_parts();
+        let err = Bytes::<DEFAULT_BYTES_LIMIT>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+    }
 }
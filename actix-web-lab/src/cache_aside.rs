@@ -0,0 +1,333 @@
+//! Cache-aside helper with single-flight protection.
+//!
+//! See [`cache_aside()`] docs.
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_web::{
+    body::BoxBody,
+    http::header::{self, EntityTag, IfNoneMatch},
+    HttpMessage as _, HttpRequest, HttpResponse, Responder,
+};
+use ahash::AHashMap;
+use bytes::Bytes;
+use sha2::{Digest as _, Sha256};
+use tokio::sync::watch;
+
+use crate::util::ShardedMap;
+
+type InFlight = Arc<Mutex<AHashMap<String, watch::Receiver<Option<CachedBytes>>>>>;
+
+/// Storage backend for [`cache_aside()`].
+///
+/// Implemented for [`ShardedMap<String, CachedBytes>`](ShardedMap), which is a suitable in-memory
+/// store for most applications.
+pub trait CacheStore: Send + Sync {
+    /// Returns unexpired value for `key`, if present.
+    fn get(&self, key: &str) -> Option<CachedBytes>;
+
+    /// Inserts value for `key` which expires after `ttl`.
+    fn insert(&self, key: String, value: CachedBytes, ttl: Duration);
+}
+
+impl CacheStore for ShardedMap<String, CachedBytes> {
+    fn get(&self, key: &str) -> Option<CachedBytes> {
+        ShardedMap::get(self, key)
+    }
+
+    fn insert(&self, key: String, value: CachedBytes, ttl: Duration) {
+        self.insert_with_ttl(key, value, ttl);
+    }
+}
+
+/// A cached value and its entity tag.
+///
+/// # Responder
+/// Responds with the cached bytes and an `ETag` header. If the request's `If-None-Match` header
+/// matches the entity tag, responds with `304 Not Modified` and no body instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedBytes {
+    body: Bytes,
+    etag: EntityTag,
+}
+
+impl CachedBytes {
+    /// Constructs new cached value with a strong entity tag derived from a hash of `body`.
+    pub fn new(body: impl Into<Bytes>) -> Self {
+        let body = body.into();
+        let etag = EntityTag::new_strong(format!("{:x}", Sha256::digest(&body)));
+
+        Self { body, etag }
+    }
+
+    /// Returns cached bytes.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns entity tag of cached bytes.
+    pub fn etag(&self) -> &EntityTag {
+        &self.etag
+    }
+
+    /// Returns cached bytes, discarding the entity tag.
+    pub fn into_body(self) -> Bytes {
+        self.body
+    }
+}
+
+impl Responder for CachedBytes {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let not_modified = match req.get_header::<IfNoneMatch>() {
+            Some(IfNoneMatch::Any) => true,
+            Some(IfNoneMatch::Items(etags)) => etags.iter().any(|etag| etag.weak_eq(&self.etag)),
+            None => false,
+        };
+
+        let mut res = if not_modified {
+            HttpResponse::NotModified()
+        } else {
+            HttpResponse::Ok()
+        };
+
+        res.insert_header(header::ETag(self.etag));
+
+        if not_modified {
+            res.finish()
+        } else {
+            res.body(self.body)
+        }
+    }
+}
+
+/// Cache store with tracking of in-progress fetches, for use with [`cache_aside()`].
+///
+/// Clones share the same store, so a cache constructed outside of the `HttpServer` app factory is
+/// shared across workers.
+#[derive(Clone)]
+pub struct CacheAside {
+    store: Arc<dyn CacheStore>,
+    in_flight: InFlight,
+}
+
+impl CacheAside {
+    /// Constructs new cache-aside helper backed by `store`.
+    pub fn new(store: impl CacheStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            in_flight: InFlight::default(),
+        }
+    }
+
+    /// Constructs new cache-aside helper backed by an in-memory [`ShardedMap`] holding at most
+    /// (approximately) `capacity` entries.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::new(ShardedMap::<String, CachedBytes>::new(capacity))
+    }
+}
+
+impl fmt::Debug for CacheAside {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheAside").finish_non_exhaustive()
+    }
+}
+
+/// Returns cached value for `key`, or computes it using `fetch` and caches it for `ttl`.
+///
+/// Concurrent calls for the same key which miss the cache are coalesced so that only one of them
+/// runs its `fetch` future while the others wait for and share its result. If that fetch fails or
+/// is cancelled, the waiting calls each run their own `fetch`. Errors are returned to the caller
+/// and are not cached.
+///
+/// The returned [`CachedBytes`] can be used as a response directly, which sets an `ETag` header
+/// and handles conditional requests.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{get, web, Responder};
+/// use actix_web_lab::util::{cache_aside, CacheAside};
+///
+/// #[get("/leaderboard")]
+/// async fn leaderboard(cache: web::Data<CacheAside>) -> actix_web::Result<impl Responder> {
+///     let cached = cache_aside(&cache, "leaderboard", Duration::from_secs(30), || async {
+///         // expensive database query ...
+///         let scores = vec![("ferris", 9001)];
+///         serde_json::to_vec(&scores)
+///     })
+///     .await?;
+///
+///     Ok(cached.customize().insert_header(("content-type", "application/json")))
+/// }
+/// ```
+pub async fn cache_aside<K, F, Fut, B, E>(
+    cache: &CacheAside,
+    key: K,
+    ttl: Duration,
+    fetch: F,
+) -> Result<CachedBytes, E>
+where
+    K: Into<String>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<B, E>>,
+    B: Into<Bytes>,
+{
+    let key = key.into();
+
+    if let Some(cached) = cache.store.get(&key) {
+        return Ok(cached);
+    }
+
+    let flight = {
+        let mut in_flight = cache.in_flight.lock().unwrap();
+
+        match in_flight.get(&key) {
+            Some(rx) => Err(rx.clone()),
+            None => {
+                let (tx, rx) = watch::channel(None);
+                in_flight.insert(key.clone(), rx);
+
+                Ok(Flight {
+                    key,
+                    tx,
+                    in_flight: Arc::clone(&cache.in_flight),
+                })
+            }
+        }
+    };
+
+    let flight = match flight {
+        Ok(flight) => flight,
+
+        Err(mut rx) => {
+            // an error means the leader did not produce a value
+            let _ = rx.changed().await;
+
+            let cached = rx.borrow().clone();
+
+            return match cached {
+                Some(cached) => Ok(cached),
+                None => fetch().await.map(CachedBytes::new),
+            };
+        }
+    };
+
+    let cached = CachedBytes::new(fetch().await?);
+
+    cache.store.insert(flight.key.clone(), cached.clone(), ttl);
+    let _ = flight.tx.send(Some(cached.clone()));
+
+    Ok(cached)
+}
+
+/// Marks a call as the one fetching the value for its key.
+///
+/// When dropped, other calls for the same key are released; if no value was sent, they each run
+/// their own fetch.
+struct Flight {
+    key: String,
+    tx: watch::Sender<Option<CachedBytes>>,
+    in_flight: InFlight,
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use actix_web::{http::StatusCode, test::TestRequest};
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::test::MockClock;
+
+    #[actix_web::test]
+    async fn caches_until_expiry() {
+        let clock = MockClock::new();
+        let cache =
+            CacheAside::new(ShardedMap::<String, CachedBytes>::new(16).clock(clock.clone()));
+        let fetches = &AtomicUsize::new(0);
+
+        let fetch = || async move {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, Infallible>("value")
+        };
+
+        let ttl = Duration::from_secs(10);
+        let first = cache_aside(&cache, "key", ttl, fetch).await.unwrap();
+        let second = cache_aside(&cache, "key", ttl, fetch).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.body(), "value");
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::from_secs(11));
+        cache_aside(&cache, "key", ttl, fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn coalesces_concurrent_misses() {
+        let cache = CacheAside::in_memory(16);
+        let fetches = &AtomicUsize::new(0);
+
+        let results = join_all((0..5).map(|_| {
+            cache_aside(&cache, "key", Duration::from_secs(10), || async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                actix_web::rt::task::yield_now().await;
+                Ok::<_, Infallible>("value")
+            })
+        }))
+        .await;
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(results
+            .iter()
+            .all(|res| res.as_ref().unwrap().body() == "value"));
+    }
+
+    #[actix_web::test]
+    async fn errors_are_not_cached() {
+        let cache = CacheAside::in_memory(16);
+        let ttl = Duration::from_secs(10);
+
+        let res = cache_aside(&cache, "key", ttl, || async { Err::<&str, _>("failed") }).await;
+        assert_eq!(res.unwrap_err(), "failed");
+
+        let res = cache_aside(&cache, "key", ttl, || async { Ok::<_, &str>("value") }).await;
+        assert_eq!(res.unwrap().body(), "value");
+    }
+
+    #[actix_web::test]
+    async fn responds_with_etag() {
+        let cached = CachedBytes::new("value");
+        let etag = cached.etag().to_string();
+
+        let req = TestRequest::default().to_http_request();
+        let res = cached.clone().respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.as_str()))
+            .to_http_request();
+        let res = cached.respond_to(&req);
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag.as_str());
+    }
+}
@@ -0,0 +1,434 @@
+//! Sticky canary routing middleware.
+//!
+//! See [`Canary`] docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    guard::{Guard, GuardContext},
+    http::header::{self, HeaderName, HeaderValue},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use futures_core::future::LocalBoxFuture;
+use sha2::{Digest as _, Sha256};
+use tracing::debug;
+
+use crate::{
+    entropy::{Entropy, SystemEntropy},
+    error::LabError,
+};
+
+/// Name of the response header that [`Canary`] tags responses with.
+const CANARY_VARIANT: HeaderName = HeaderName::from_static("x-canary-variant");
+
+type StickyKeyFn = Rc<dyn Fn(&ServiceRequest) -> Option<String>>;
+
+/// Variant of a request routed by the [`Canary`] middleware.
+///
+/// As a [guard](Guard), matches requests assigned to this variant, which is how the canary version
+/// of a scope or resource is selected. Requests not seen by a `Canary` middleware are considered
+/// [`Stable`](Self::Stable).
+///
+/// As an extractor, returns the variant assigned to the request. Requires the `Canary` middleware
+/// to be registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanaryVariant {
+    /// The current, stable version.
+    Stable,
+
+    /// The canary version.
+    Canary,
+}
+
+impl CanaryVariant {
+    /// Returns variant name, as used in tag headers and sticky cookies.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Canary => "canary",
+        }
+    }
+
+    fn from_str(variant: &str) -> Option<Self> {
+        match variant {
+            "stable" => Some(Self::Stable),
+            "canary" => Some(Self::Canary),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CanaryVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Guard for CanaryVariant {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        let variant = ctx
+            .req_data()
+            .get::<CanaryVariant>()
+            .copied()
+            .unwrap_or(CanaryVariant::Stable);
+
+        variant == *self
+    }
+}
+
+impl FromRequest for CanaryVariant {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<CanaryVariant>()
+                .copied()
+                .ok_or_else(|| {
+                    debug!(
+                "Failed to extract `CanaryVariant` for `{}` handler. For the CanaryVariant \
+                extractor to work correctly, wrap the app or scope with the `Canary` middleware.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+                    LabError::MiddlewareNotRegistered {
+                        middleware: "Canary",
+                    }
+                    .into()
+                }),
+        )
+    }
+}
+
+/// A middleware that routes a percentage of traffic to canary versions of scopes and resources.
+///
+/// Each request is assigned a [`CanaryVariant`], stored in request extensions. Canary versions of
+/// scopes or resources are registered under the same path as their stable counterparts, before
+/// them, using the `CanaryVariant::Canary` guard. Responses are tagged with the variant in the
+/// `x-canary-variant` header so that metrics can be compared between variants.
+///
+/// # Stickiness
+/// Without stickiness, requests are assigned randomly and independently. To keep each client on
+/// the same variant, either:
+/// - use [`sticky_cookie`](Self::sticky_cookie) to remember the assigned variant in a cookie; or
+/// - use [`sticky_key`](Self::sticky_key) to derive the variant from a hash of a stable key, like
+///   a user ID, so that a client is assigned the same variant on any device or server instance.
+///
+/// When both are used, the cookie takes precedence and the key is used to assign new clients.
+/// Requests without a key or cookie are assigned randomly.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpResponse};
+/// use actix_web_lab::{extract::CanaryVariant, middleware::Canary};
+///
+/// App::new()
+///     .wrap(
+///         Canary::new(5.0)
+///             .sticky_cookie("checkout-variant")
+///             .sticky_key(|req| {
+///                 req.headers()
+///                     .get("x-user-id")
+///                     .and_then(|id| id.to_str().ok())
+///                     .map(str::to_owned)
+///             }),
+///     )
+///     // canary version must be registered first
+///     .service(
+///         web::scope("/checkout")
+///             .guard(CanaryVariant::Canary)
+///             .route("", web::post().to(|| async { HttpResponse::Ok().body("v2") })),
+///     )
+///     .service(
+///         web::scope("/checkout")
+///             .route("", web::post().to(|| async { HttpResponse::Ok().body("v1") })),
+///     )
+/// # ;
+/// ```
+#[derive(Clone)]
+pub struct Canary {
+    basis_points: u64,
+    cookie: Option<Rc<str>>,
+    cookie_max_age: Duration,
+    sticky_key: Option<StickyKeyFn>,
+    entropy: Arc<dyn Entropy>,
+}
+
+impl Canary {
+    /// Constructs new canary middleware which routes `percent` of traffic to canary versions.
+    ///
+    /// # Panics
+    /// Panics if `percent` is not in the range `0.0..=100.0`.
+    pub fn new(percent: f64) -> Self {
+        assert!(
+            (0.0..=100.0).contains(&percent),
+            "canary percentage must be between 0 and 100"
+        );
+
+        Self {
+            basis_points: (percent * 100.0).round() as u64,
+            cookie: None,
+            cookie_max_age: Duration::from_secs(24 * 60 * 60),
+            sticky_key: None,
+            entropy: Arc::new(SystemEntropy::new()),
+        }
+    }
+
+    /// Remembers assigned variants in a cookie named `name`.
+    pub fn sticky_cookie(mut self, name: impl AsRef<str>) -> Self {
+        self.cookie = Some(Rc::from(name.as_ref()));
+        self
+    }
+
+    /// Sets maximum age of sticky cookies.
+    ///
+    /// Defaults to one day.
+    pub fn cookie_max_age(mut self, max_age: Duration) -> Self {
+        self.cookie_max_age = max_age;
+        self
+    }
+
+    /// Derives variants from a hash of the key returned by `sticky_key`, when it returns one.
+    pub fn sticky_key(
+        mut self,
+        sticky_key: impl Fn(&ServiceRequest) -> Option<String> + 'static,
+    ) -> Self {
+        self.sticky_key = Some(Rc::new(sticky_key));
+        self
+    }
+
+    /// Sets the source of randomness used to assign variants to requests without a sticky key.
+    ///
+    /// Defaults to [`SystemEntropy`].
+    pub fn entropy(mut self, entropy: impl Entropy + 'static) -> Self {
+        self.entropy = Arc::new(entropy);
+        self
+    }
+
+    /// Returns variant for request, and whether a sticky cookie should be set.
+    fn assign(&self, req: &ServiceRequest) -> (CanaryVariant, bool) {
+        if let Some(name) = &self.cookie {
+            if let Some(variant) = cookie_variant(req, name) {
+                return (variant, false);
+            }
+        }
+
+        let bucket = match self.sticky_key.as_ref().and_then(|key| key(req)) {
+            Some(key) => {
+                let hash = Sha256::digest(key.as_bytes());
+                u64::from_be_bytes(hash[..8].try_into().unwrap()) % 10_000
+            }
+            None => self.entropy.next_u64() % 10_000,
+        };
+
+        let variant = if bucket < self.basis_points {
+            CanaryVariant::Canary
+        } else {
+            CanaryVariant::Stable
+        };
+
+        (variant, self.cookie.is_some())
+    }
+}
+
+impl fmt::Debug for Canary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Canary")
+            .field("basis_points", &self.basis_points)
+            .field("cookie", &self.cookie)
+            .field("cookie_max_age", &self.cookie_max_age)
+            .field(
+                "sticky_key",
+                &self.sticky_key.as_ref().map(|_| "<callback>"),
+            )
+            .field("entropy", &self.entropy)
+            .finish()
+    }
+}
+
+fn cookie_variant(req: &ServiceRequest, name: &str) -> Option<CanaryVariant> {
+    req.headers()
+        .get_all(header::COOKIE)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, val)| CanaryVariant::from_str(val))
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Canary
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = CanaryMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CanaryMiddleware {
+            service: Rc::new(service),
+            canary: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`Canary`] middleware.
+pub struct CanaryMiddleware<S> {
+    service: Rc<S>,
+    canary: Canary,
+}
+
+impl<S, B> Service<ServiceRequest> for CanaryMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let (variant, set_cookie) = self.canary.assign(&req);
+        req.extensions_mut().insert(variant);
+
+        let cookie = match &self.canary.cookie {
+            Some(name) if set_cookie => {
+                let max_age = self.canary.cookie_max_age.as_secs();
+                let cookie =
+                    format!("{name}={variant}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax");
+                HeaderValue::try_from(cookie).ok()
+            }
+            _ => None,
+        };
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            let headers = res.headers_mut();
+            headers.insert(CANARY_VARIANT, HeaderValue::from_static(variant.as_str()));
+
+            if let Some(cookie) = cookie {
+                headers.append(header::SET_COOKIE, cookie);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::to_bytes,
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+    use crate::test::SeededEntropy;
+
+    async fn v1(variant: CanaryVariant) -> HttpResponse {
+        assert_eq!(variant, CanaryVariant::Stable);
+        HttpResponse::Ok().body("v1")
+    }
+
+    async fn v2(variant: CanaryVariant) -> HttpResponse {
+        assert_eq!(variant, CanaryVariant::Canary);
+        HttpResponse::Ok().body("v2")
+    }
+
+    macro_rules! canary_app {
+        ($canary:expr) => {
+            test::init_service(
+                App::new()
+                    .wrap($canary)
+                    .service(web::resource("/").guard(CanaryVariant::Canary).to(v2))
+                    .service(web::resource("/").to(v1)),
+            )
+            .await
+        };
+    }
+
+    #[actix_web::test]
+    async fn routes_by_percentage() {
+        let app = canary_app!(Canary::new(100.0));
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.headers().get(CANARY_VARIANT).unwrap(), "canary");
+        assert!(!res.headers().contains_key(header::SET_COOKIE));
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "v2");
+
+        let app = canary_app!(Canary::new(0.0));
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.headers().get(CANARY_VARIANT).unwrap(), "stable");
+        assert_eq!(to_bytes(res.into_body()).await.unwrap(), "v1");
+
+        let app = canary_app!(Canary::new(50.0).entropy(SeededEntropy::new(42)));
+        let mut canaries = 0;
+        for _ in 0..200 {
+            let res = test::call_service(&app, TestRequest::default().to_request()).await;
+            if res.headers().get(CANARY_VARIANT).unwrap() == "canary" {
+                canaries += 1;
+            }
+        }
+        assert!((60..140).contains(&canaries), "{canaries} canary requests");
+    }
+
+    #[actix_web::test]
+    async fn sticky_cookie() {
+        let app = canary_app!(Canary::new(100.0).sticky_cookie("variant"));
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        let cookie = res.headers().get(header::SET_COOKIE).unwrap();
+        assert!(cookie.to_str().unwrap().starts_with("variant=canary;"));
+
+        // existing assignment is kept, even though all new clients are assigned the canary
+        let req = TestRequest::default()
+            .insert_header((header::COOKIE, "a=b; variant=stable"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(CANARY_VARIANT).unwrap(), "stable");
+        assert!(!res.headers().contains_key(header::SET_COOKIE));
+    }
+
+    #[actix_web::test]
+    async fn sticky_key() {
+        let canary = Canary::new(50.0).sticky_key(|req| {
+            req.headers()
+                .get("x-user-id")
+                .and_then(|id| id.to_str().ok())
+                .map(str::to_owned)
+        });
+        let app = canary_app!(canary);
+
+        for user in ["ana", "bo", "cy", "di"] {
+            let mut variants = Vec::new();
+
+            for _ in 0..3 {
+                let req = TestRequest::default()
+                    .insert_header(("x-user-id", user))
+                    .to_request();
+                let res = test::call_service(&app, req).await;
+                variants.push(res.headers().get(CANARY_VARIANT).unwrap().clone());
+            }
+
+            assert!(variants.windows(2).all(|pair| pair[0] == pair[1]));
+        }
+    }
+}
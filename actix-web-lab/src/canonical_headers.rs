@@ -0,0 +1,368 @@
+//! Request header canonicalization middleware and extractor.
+//!
+//! See [`NormalizeHeaders`] and [`CanonicalHeaders`] for docs.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, HeaderName},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use ahash::AHashMap;
+use tracing::debug;
+
+use crate::error::LabError;
+
+/// A middleware that canonicalizes selected list-valued request headers.
+///
+/// For each configured header, all occurrences are split into their comma-separated items, which
+/// are then trimmed, have whitespace around `;` and `=` removed, are optionally lowercased, and are
+/// deduplicated while preserving order. Commas inside quoted strings are not treated as
+/// separators.
+///
+/// The results are stored in request extensions as [`CanonicalHeaders`], which downstream
+/// extractors can take as an argument and guards can read using
+/// `ctx.req_data().get::<CanonicalHeaders>()`. The request's own headers are left unchanged.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::header, App, Responder};
+/// use actix_web_lab::{extract::CanonicalHeaders, middleware::NormalizeHeaders};
+///
+/// #[get("/")]
+/// async fn index(headers: CanonicalHeaders) -> impl Responder {
+///     // e.g., "Accept-Encoding: GZIP , br" and "Accept-Encoding: gzip" become "gzip, br"
+///     headers
+///         .value(&header::ACCEPT_ENCODING)
+///         .unwrap_or_default()
+///         .to_owned()
+/// }
+///
+/// App::new()
+///     .wrap(
+///         NormalizeHeaders::new()
+///             .header(header::ACCEPT_ENCODING)
+///             .header(header::CACHE_CONTROL)
+///             .header_preserving_case(header::ACCEPT),
+///     )
+///     .service(index)
+/// # ;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeHeaders {
+    headers: Rc<Vec<(HeaderName, bool)>>,
+}
+
+impl NormalizeHeaders {
+    /// Constructs new header canonicalization middleware with no headers selected.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects a header for canonicalization, lowercasing its items.
+    ///
+    /// Suitable for headers whose items are case-insensitive tokens, like `Accept-Encoding` and
+    /// `Cache-Control`.
+    pub fn header(self, name: HeaderName) -> Self {
+        self.add(name, true)
+    }
+
+    /// Selects a header for canonicalization without changing the case of its items.
+    pub fn header_preserving_case(self, name: HeaderName) -> Self {
+        self.add(name, false)
+    }
+
+    fn add(mut self, name: HeaderName, lowercase: bool) -> Self {
+        Rc::get_mut(&mut self.headers)
+            .expect("NormalizeHeaders instance should not be cloned before configuration")
+            .push((name, lowercase));
+
+        self
+    }
+
+    fn canonicalize(&self, headers: &HeaderMap) -> CanonicalHeaders {
+        let mut map = AHashMap::with_capacity(self.headers.len());
+
+        for (name, lowercase) in self.headers.iter() {
+            let mut items = Vec::<String>::new();
+
+            for val in headers.get_all(name) {
+                // values which are not visible ASCII cannot be canonicalized safely
+                let Ok(val) = val.to_str() else {
+                    continue;
+                };
+
+                for item in split_list(val) {
+                    let item = canonicalize_item(item, *lowercase);
+
+                    if !item.is_empty() && !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+            }
+
+            if !items.is_empty() {
+                let value = items.join(", ");
+                map.insert(name.clone(), Entry { items, value });
+            }
+        }
+
+        CanonicalHeaders {
+            headers: Rc::new(map),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for NormalizeHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = NormalizeHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(NormalizeHeadersMiddleware {
+            service,
+            config: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`NormalizeHeaders`] middleware.
+pub struct NormalizeHeadersMiddleware<S> {
+    service: S,
+    config: NormalizeHeaders,
+}
+
+impl<S, B> Service<ServiceRequest> for NormalizeHeadersMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let canonical = self.config.canonicalize(req.headers());
+        req.extensions_mut().insert(canonical);
+
+        self.service.call(req)
+    }
+}
+
+/// Splits a comma-separated header list, ignoring commas inside quoted strings.
+fn split_list(val: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    val.split(move |ch| {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => return true,
+            _ => {}
+        }
+
+        false
+    })
+}
+
+/// Trims item and removes whitespace around parameter delimiters outside of quoted strings.
+fn canonicalize_item(item: &str, lowercase: bool) -> String {
+    let mut out = String::with_capacity(item.len());
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut pending_space = false;
+
+    for ch in item.trim().chars() {
+        if quoted {
+            out.push(ch);
+
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match ch {
+            ' ' | '\t' => {
+                pending_space = !out.ends_with([';', '=']);
+                continue;
+            }
+            ';' | '=' => {}
+            _ if pending_space => out.push(' '),
+            _ => {}
+        }
+
+        pending_space = false;
+
+        if ch == '"' {
+            quoted = true;
+            out.push(ch);
+        } else if lowercase {
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+
+    out
+}
+
+#[derive(Debug)]
+struct Entry {
+    items: Vec<String>,
+    value: String,
+}
+
+/// Canonical values of request headers selected by the [`NormalizeHeaders`] middleware.
+///
+/// Extracting `CanonicalHeaders` without the middleware results in a
+/// [`LabError::MiddlewareNotRegistered`] error.
+///
+/// See [`NormalizeHeaders`] docs for an example.
+#[derive(Debug, Clone)]
+pub struct CanonicalHeaders {
+    headers: Rc<AHashMap<HeaderName, Entry>>,
+}
+
+impl CanonicalHeaders {
+    /// Returns canonical items of header `name`, or an empty slice if it was not present.
+    pub fn items(&self, name: &HeaderName) -> &[String] {
+        self.headers
+            .get(name)
+            .map(|entry| entry.items.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns canonical items of header `name` joined into a single value, if it was present.
+    pub fn value(&self, name: &HeaderName) -> Option<&str> {
+        self.headers.get(name).map(|entry| entry.value.as_str())
+    }
+
+    /// Returns true if header `name` contains `item`, compared case-insensitively.
+    pub fn contains(&self, name: &HeaderName, item: &str) -> bool {
+        self.items(name)
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(item))
+    }
+}
+
+impl FromRequest for CanonicalHeaders {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `CanonicalHeaders` for `{}` handler. For the CanonicalHeaders \
+                extractor to work correctly, wrap the app or scope with the `NormalizeHeaders` \
+                middleware.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "NormalizeHeaders",
+            }
+            .into()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::header::{ACCEPT, ACCEPT_ENCODING, CACHE_CONTROL},
+        test::{call_and_read_body, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[test]
+    fn canonicalizes_items() {
+        assert_eq!(canonicalize_item(" No-Cache ", true), "no-cache");
+        assert_eq!(canonicalize_item("max-age = 60", true), "max-age=60");
+        assert_eq!(
+            canonicalize_item("Text/HTML ; Q=0.9", false),
+            "Text/HTML;Q=0.9",
+        );
+        assert_eq!(
+            canonicalize_item(r#"foo; bar = "A, \" B""#, true),
+            r#"foo;bar="A, \" B""#,
+        );
+        assert_eq!(canonicalize_item("a  b", true), "a b");
+    }
+
+    #[test]
+    fn splits_outside_quotes() {
+        let items = split_list(r#"a, b="x,y", c"#).collect::<Vec<_>>();
+        assert_eq!(items, ["a", r#" b="x,y""#, " c"]);
+    }
+
+    #[test]
+    fn merges_and_dedupes() {
+        let mw = NormalizeHeaders::new()
+            .header(ACCEPT_ENCODING)
+            .header_preserving_case(ACCEPT);
+
+        let req = TestRequest::default()
+            .append_header((ACCEPT_ENCODING, "GZIP , br"))
+            .append_header((ACCEPT_ENCODING, "gzip,,deflate"))
+            .insert_header((ACCEPT, "text/HTML"))
+            .insert_header((CACHE_CONTROL, "No-Cache"))
+            .to_http_request();
+
+        let headers = mw.canonicalize(req.headers());
+
+        assert_eq!(headers.items(&ACCEPT_ENCODING), ["gzip", "br", "deflate"]);
+        assert_eq!(headers.value(&ACCEPT_ENCODING), Some("gzip, br, deflate"));
+        assert_eq!(headers.value(&ACCEPT), Some("text/HTML"));
+        assert!(headers.contains(&ACCEPT, "text/html"));
+
+        // not selected
+        assert_eq!(headers.value(&CACHE_CONTROL), None);
+        assert!(headers.items(&CACHE_CONTROL).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn extractor() {
+        let app = init_service(
+            App::new()
+                .wrap(NormalizeHeaders::new().header(CACHE_CONTROL))
+                .default_service(web::to(|headers: CanonicalHeaders| async move {
+                    HttpResponse::Ok().body(headers.value(&CACHE_CONTROL).unwrap().to_owned())
+                })),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header((CACHE_CONTROL, "No-Store,  MAX-AGE=0"))
+            .to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "no-store, max-age=0");
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let err = CanonicalHeaders::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_error::<LabError>().unwrap().code(),
+            "middleware_not_registered",
+        );
+    }
+}
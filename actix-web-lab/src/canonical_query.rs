@@ -0,0 +1,311 @@
+//! Query string canonicalization middleware.
+//!
+//! See [`CanonicalQuery`] docs.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header,
+        uri::{PathAndQuery, Uri},
+        Method, StatusCode,
+    },
+    Error, HttpResponse,
+};
+use futures_core::future::LocalBoxFuture;
+
+/// Query parameters commonly added by analytics and advertising platforms.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "mc_eid"];
+
+/// A middleware that rewrites request query strings into a canonical form.
+///
+/// # Canonicalization Steps
+/// - Removes configured parameters, such as `utm_*` tracking parameters.
+/// - Removes empty segments and exact duplicate `name=value` pairs.
+/// - Sorts parameters by name, preserving the relative order of values for the same name.
+///
+/// Parameters are compared in their percent-encoded form; no decoding or re-encoding is done.
+///
+/// By default, the request's URI is rewritten before being passed to inner services so that
+/// handlers and caches keyed on the URI see the canonical form. With
+/// [`redirect`](Self::redirect), `GET` and `HEAD` requests with non-canonical query strings are
+/// instead answered with a `301 Moved Permanently` redirect to the canonical URL, which helps
+/// search engines and shared caches consolidate equivalent URLs.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::middleware::CanonicalQuery;
+///
+/// // `/search?utm_source=x&q=rust&page=2` is handled as `/search?page=2&q=rust`
+/// App::new().wrap(CanonicalQuery::new().strip_tracking_params())
+/// # ;
+///
+/// App::new().wrap(
+///     CanonicalQuery::new()
+///         .strip_param("session")
+///         .redirect(),
+/// )
+/// # ;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalQuery {
+    config: Rc<Config>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Config {
+    strip_params: Vec<String>,
+    strip_prefixes: Vec<String>,
+    redirect: bool,
+}
+
+impl CanonicalQuery {
+    /// Constructs new query canonicalization middleware which does not strip any parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes parameters named `name`.
+    pub fn strip_param(mut self, name: impl Into<String>) -> Self {
+        self.config_mut().strip_params.push(name.into());
+        self
+    }
+
+    /// Removes parameters whose names start with `prefix`.
+    pub fn strip_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config_mut().strip_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Removes common tracking parameters.
+    ///
+    /// This includes `utm_*` parameters as well as `fbclid`, `gclid`, `dclid`, `msclkid`, and
+    /// `mc_eid`.
+    pub fn strip_tracking_params(mut self) -> Self {
+        let config = self.config_mut();
+        config.strip_prefixes.push("utm_".to_owned());
+        config
+            .strip_params
+            .extend(TRACKING_PARAMS.iter().map(|&name| name.to_owned()));
+        self
+    }
+
+    /// Redirects `GET` and `HEAD` requests with non-canonical query strings to the canonical URL
+    /// using a `301 Moved Permanently` response.
+    ///
+    /// Requests with other methods are rewritten in place, as without this option.
+    pub fn redirect(mut self) -> Self {
+        self.config_mut().redirect = true;
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut Config {
+        Rc::get_mut(&mut self.config)
+            .expect("CanonicalQuery instance should not be cloned before configuration")
+    }
+}
+
+impl Config {
+    fn is_stripped(&self, name: &str) -> bool {
+        self.strip_params.iter().any(|param| param == name)
+            || self
+                .strip_prefixes
+                .iter()
+                .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    /// Returns canonical form of `query`.
+    fn canonicalize(&self, query: &str) -> String {
+        let mut params = Vec::<(&str, &str)>::new();
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, _) = pair.split_once('=').unwrap_or((pair, ""));
+
+            if !self.is_stripped(name) && !params.iter().any(|(_, seen)| *seen == pair) {
+                params.push((name, pair));
+            }
+        }
+
+        // stable sort keeps order of repeated parameters, which can be significant
+        params.sort_by_key(|(name, _)| *name);
+
+        params
+            .into_iter()
+            .map(|(_, pair)| pair)
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CanonicalQuery
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, ()>>;
+    type Error = Error;
+    type Transform = CanonicalQueryMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CanonicalQueryMiddleware {
+            service,
+            config: Rc::clone(&self.config),
+        }))
+    }
+}
+
+/// Service for the [`CanonicalQuery`] middleware.
+pub struct CanonicalQueryMiddleware<S> {
+    service: S,
+    config: Rc<Config>,
+}
+
+impl<S, B> Service<ServiceRequest> for CanonicalQueryMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, ()>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let Some(query) = req.uri().query() else {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        };
+
+        let canonical = self.config.canonicalize(query);
+
+        if canonical != query {
+            let path = req.path();
+
+            let path_and_query = if canonical.is_empty() {
+                path.to_owned()
+            } else {
+                format!("{path}?{canonical}")
+            };
+
+            let redirect = self.config.redirect
+                && (req.method() == Method::GET || req.method() == Method::HEAD);
+
+            if redirect {
+                let mut res = HttpResponse::with_body(StatusCode::MOVED_PERMANENTLY, ());
+                res.headers_mut().insert(
+                    header::LOCATION,
+                    path_and_query
+                        .parse()
+                        .expect("canonical URI should be valid"),
+                );
+
+                let res = req.into_response(res).map_into_right_body();
+                return Box::pin(ready(Ok(res)));
+            }
+
+            let mut parts = req.uri().clone().into_parts();
+            parts.path_and_query = Some(
+                path_and_query
+                    .parse::<PathAndQuery>()
+                    .expect("canonical URI should be valid"),
+            );
+
+            let uri = Uri::from_parts(parts).expect("canonical URI should be valid");
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        test::{call_and_read_body, call_service, init_service, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    use super::*;
+
+    fn canonicalize(mw: CanonicalQuery, query: &str) -> String {
+        mw.config.canonicalize(query)
+    }
+
+    #[test]
+    fn sorts_and_dedupes() {
+        let mw = CanonicalQuery::new;
+
+        assert_eq!(canonicalize(mw(), "b=2&a=1"), "a=1&b=2");
+        assert_eq!(canonicalize(mw(), "b=2&a=1&b=1&b=2"), "a=1&b=2&b=1");
+        assert_eq!(canonicalize(mw(), "&&flag&a=%20"), "a=%20&flag");
+        assert_eq!(canonicalize(mw(), ""), "");
+    }
+
+    #[test]
+    fn strips_params() {
+        let mw = CanonicalQuery::new()
+            .strip_tracking_params()
+            .strip_param("sid");
+
+        assert_eq!(
+            canonicalize(mw, "utm_source=x&q=rust&sid=1&gclid=2&utm_medium"),
+            "q=rust",
+        );
+    }
+
+    #[actix_web::test]
+    async fn rewrites_uri() {
+        let app = init_service(
+            App::new()
+                .wrap(CanonicalQuery::new().strip_tracking_params())
+                .default_service(web::to(
+                    |req: HttpRequest| async move { req.uri().to_string() },
+                )),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/foo?utm_source=x&b=2&a=1").to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "/foo?a=1&b=2");
+
+        let req = TestRequest::with_uri("/foo?utm_source=x").to_request();
+        let body = call_and_read_body(&app, req).await;
+        assert_eq!(body, "/foo");
+    }
+
+    #[actix_web::test]
+    async fn redirects_get_requests() {
+        let app = init_service(
+            App::new()
+                .wrap(CanonicalQuery::new().redirect())
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/foo?b=2&a=1").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/foo?a=1&b=2",);
+
+        let req = TestRequest::with_uri("/foo?a=1&b=2").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post().uri("/foo?b=2&a=1").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -6,10 +6,30 @@ use derive_more::{Deref, DerefMut, Display};
 use mime::Mime;
 use once_cell::sync::Lazy;
 use serde::Serialize;
+use tracing::debug;
 
 static CBOR_MIME: Lazy<Mime> = Lazy::new(|| "application/cbor".parse().unwrap());
 
 /// CBOR responder.
+///
+/// Serializes the inner value as CBOR, with a `Content-Type` of `application/cbor`. To extract CBOR
+/// request bodies, use the [`Cbor`](crate::extract::Cbor) extractor.
+///
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::respond::Cbor;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Status {
+///     online: bool,
+/// }
+///
+/// #[get("/status")]
+/// async fn index() -> impl Responder {
+///     Cbor(Status { online: true })
+/// }
+/// ```
 #[derive(Debug, Deref, DerefMut, Display)]
 pub struct Cbor<T>(pub T);
 
@@ -17,11 +37,19 @@ impl<T: Serialize> Responder for Cbor<T> {
     type Body = Bytes;
 
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        let body = Bytes::from(serde_cbor_2::to_vec(&self.0).unwrap());
+        match serde_cbor_2::to_vec(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(CBOR_MIME.clone())
+                .message_body(Bytes::from(body))
+                .unwrap(),
 
-        HttpResponse::Ok()
-            .content_type(CBOR_MIME.clone())
-            .message_body(body)
-            .unwrap()
+            Err(err) => {
+                debug!("Failed to serialize CBOR response: {err}");
+
+                HttpResponse::InternalServerError()
+                    .message_body(Bytes::new())
+                    .unwrap()
+            }
+        }
     }
 }
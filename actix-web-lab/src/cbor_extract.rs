@@ -0,0 +1,285 @@
+//! CBOR extractor.
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, FromRequest, HttpMessage as _,
+    HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::bytes::{BytesBody, BytesPayloadError};
+
+/// Default CBOR payload size limit of 2MiB.
+pub const DEFAULT_CBOR_LIMIT: usize = 2_097_152;
+
+/// CBOR extractor with const-generic payload size limit.
+///
+/// To extract typed data from a request body, the inner type `T` must implement the
+/// [`serde::Deserialize`] trait. The request must have a `Content-Type` of `application/cbor` or
+/// a type with a `+cbor` suffix.
+///
+/// Use the `LIMIT` const generic parameter to control the payload size limit. The default limit
+/// that is exported (`DEFAULT_CBOR_LIMIT`) is 2MiB.
+///
+/// To respond with CBOR, use the [`Cbor`](crate::respond::Cbor) responder.
+///
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::Cbor;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Reading {
+///     sensor: String,
+///     value: f64,
+/// }
+///
+/// #[post("/readings")]
+/// async fn index(reading: Cbor<Reading, 4096>) -> String {
+///     format!("{}: {}", reading.sensor, reading.value)
+/// }
+/// # App::new().service(index);
+/// ```
+#[derive(Debug)]
+pub struct Cbor<T, const LIMIT: usize = DEFAULT_CBOR_LIMIT>(pub T);
+
+mod waiting_on_derive_more_to_start_using_syn_2_due_to_proc_macro_panic {
+    use super::*;
+
+    impl<T, const LIMIT: usize> std::ops::Deref for Cbor<T, LIMIT> {
+        type Target = T;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T, const LIMIT: usize> std::ops::DerefMut for Cbor<T, LIMIT> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl<T: fmt::Display, const LIMIT: usize> fmt::Display for Cbor<T, LIMIT> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T, const LIMIT: usize> Cbor<T, LIMIT> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned, const LIMIT: usize> FromRequest for Cbor<T, LIMIT> {
+    type Error = CborPayloadError;
+    type Future = CborExtractFut<T, LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let can_parse_cbor = match req.mime_type() {
+            Ok(Some(mime)) => {
+                mime.subtype().as_str() == "cbor"
+                    || mime.suffix().map(|s| s.as_str()) == Some("cbor")
+            }
+            _ => false,
+        };
+
+        CborExtractFut {
+            req: Some(req.clone()),
+            fut: can_parse_cbor.then(|| BytesBody::new(req, payload)),
+            _res: PhantomData,
+        }
+    }
+}
+
+/// Future for the [`Cbor`] extractor.
+pub struct CborExtractFut<T, const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    fut: Option<BytesBody<LIMIT>>,
+    _res: PhantomData<T>,
+}
+
+impl<T, const LIMIT: usize> Unpin for CborExtractFut<T, LIMIT> {}
+
+impl<T: DeserializeOwned, const LIMIT: usize> Future for CborExtractFut<T, LIMIT> {
+    type Output = Result<Cbor<T, LIMIT>, CborPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match &mut this.fut {
+            None => Err(CborPayloadError::ContentType),
+            Some(fut) => ready!(Pin::new(fut).poll(cx))
+                .map_err(CborPayloadError::from)
+                .and_then(|body| {
+                    serde_cbor_2::from_slice(&body).map_err(CborPayloadError::Deserialize)
+                }),
+        };
+
+        if res.is_err() {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to deserialize Cbor<{}> from payload in handler: {}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        Poll::Ready(res.map(Cbor))
+    }
+}
+
+/// Errors that can occur when extracting a [`Cbor`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum CborPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(
+        fmt = "CBOR payload ({length} bytes) is larger than allowed (limit: {limit} bytes)."
+    )]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "CBOR payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not CBOR.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload could not be deserialized.
+    #[display(fmt = "CBOR deserialize error: {_0}")]
+    Deserialize(serde_cbor_2::Error),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<BytesPayloadError> for CborPayloadError {
+    fn from(err: BytesPayloadError) -> Self {
+        match err {
+            BytesPayloadError::OverflowKnownLength { length, limit } => {
+                Self::OverflowKnownLength { length, limit }
+            }
+            BytesPayloadError::Overflow { limit } => Self::Overflow { limit },
+            BytesPayloadError::ContentType => Self::ContentType,
+            BytesPayloadError::Payload(err) => Self::Payload(err),
+        }
+    }
+}
+
+impl ResponseError for CborPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Deserialize(_) => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest, Responder as _};
+    use bytes::Bytes;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: u32,
+    }
+
+    fn reading() -> Reading {
+        Reading {
+            sensor: "temp".to_owned(),
+            value: 21,
+        }
+    }
+
+    #[actix_web::test]
+    async fn round_trip() {
+        let req = TestRequest::default().to_http_request();
+        let res = crate::respond::Cbor(reading()).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/cbor",
+        );
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/cbor"))
+            .set_payload(body.clone())
+            .to_http_parts();
+        let extracted = Cbor::<Reading>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(extracted.into_inner(), reading());
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/vnd.sensor+cbor"))
+            .set_payload(body)
+            .to_http_parts();
+        let extracted = Cbor::<Reading>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(extracted.sensor, "temp");
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let body = Bytes::from(serde_cbor_2::to_vec(&reading()).unwrap());
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(body.clone())
+            .to_http_parts();
+        let err = Cbor::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/cbor"))
+            .insert_header((header::CONTENT_LENGTH, body.len()))
+            .set_payload(body)
+            .to_http_parts();
+        let err = Cbor::<Reading, 4>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/cbor"))
+            .set_payload(Bytes::from_static(b"\xff\xff"))
+            .to_http_parts();
+        let err = Cbor::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
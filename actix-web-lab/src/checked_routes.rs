@@ -0,0 +1,520 @@
+//! Route table with conflict detection.
+//!
+//! See [`CheckedRoutes`] docs.
+
+use std::fmt;
+
+use actix_web::{
+    dev::{AppService, HttpServiceFactory},
+    guard::Guard,
+    http::Method,
+    web, FromRequest, Handler, Responder, Route,
+};
+use derive_more::{Display, Error};
+
+/// A set of routes which can be checked for conflicts before being registered.
+///
+/// Actix Web matches resources in registration order, so a route can be made unreachable by an
+/// earlier, more general pattern; by an earlier route with the same pattern, method, and guard; or,
+/// when paths are normalized, by a pattern that differs only in its trailing slash. These mistakes
+/// otherwise only show up as unexpected 404 or 405 responses.
+///
+/// Routes with the same pattern are grouped into a single resource, in the order their pattern
+/// was first added. Guards other than the method guard are opaque, so routes with extra guards are
+/// given a name which is used to compare them.
+///
+/// Conflicts can be inspected using [`conflicts()`](Self::conflicts) or
+/// [`validate()`](Self::validate). Alternatively, use [`deny_conflicts()`](Self::deny_conflicts)
+/// to panic with the full list of conflicts when the app is built.
+///
+/// # Examples
+/// ```
+/// use actix_web::{guard, http::Method, App, HttpResponse};
+/// use actix_web_lab::web::{CheckedRoutes, RouteConflict};
+///
+/// let routes = CheckedRoutes::new()
+///     .route("/users/{id}", Method::GET, HttpResponse::Ok)
+///     .route("/users/me", Method::GET, HttpResponse::Ok)
+///     .guarded_route(
+///         "/users/{id}",
+///         Method::DELETE,
+///         "admin",
+///         guard::Header("x-admin", "1"),
+///         HttpResponse::NoContent,
+///     );
+///
+/// assert_eq!(
+///     routes.conflicts(),
+///     [RouteConflict::Shadowed {
+///         pattern: "/users/me".to_owned(),
+///         shadowed_by: "/users/{id}".to_owned(),
+///     }],
+/// );
+///
+/// let routes = CheckedRoutes::new()
+///     .route("/users/me", Method::GET, HttpResponse::Ok)
+///     .route("/users/{id}", Method::GET, HttpResponse::Ok);
+///
+/// App::new().service(routes.deny_conflicts())
+/// # ;
+/// ```
+#[derive(Default)]
+pub struct CheckedRoutes {
+    routes: Vec<CheckedRoute>,
+    deny_conflicts: bool,
+}
+
+struct CheckedRoute {
+    pattern: String,
+    method: Method,
+    guard: Option<String>,
+    route: Route,
+}
+
+impl CheckedRoutes {
+    /// Constructs new, empty set of routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route for `method` requests matching `pattern`.
+    pub fn route<F, Args>(self, pattern: impl Into<String>, method: Method, handler: F) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        let route = web::method(method.clone()).to(handler);
+        self.push(pattern.into(), method, None, route)
+    }
+
+    /// Adds a route for `method` requests matching `pattern` which also pass `guard`.
+    ///
+    /// The guard's `name` is used to detect routes with identical guards.
+    pub fn guarded_route<F, Args>(
+        self,
+        pattern: impl Into<String>,
+        method: Method,
+        name: impl Into<String>,
+        guard: impl Guard + 'static,
+        handler: F,
+    ) -> Self
+    where
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        let route = web::method(method.clone()).guard(guard).to(handler);
+        self.push(pattern.into(), method, Some(name.into()), route)
+    }
+
+    /// Panics when registered if any conflicts are detected.
+    pub fn deny_conflicts(mut self) -> Self {
+        self.deny_conflicts = true;
+        self
+    }
+
+    /// Returns all detected conflicts, in registration order.
+    pub fn conflicts(&self) -> Vec<RouteConflict> {
+        let patterns = self.patterns();
+        let mut conflicts = Vec::new();
+
+        for (idx, pattern) in patterns.iter().enumerate() {
+            if let Some(earlier) = patterns[..idx]
+                .iter()
+                .find(|earlier| covers(earlier, pattern))
+            {
+                conflicts.push(RouteConflict::Shadowed {
+                    pattern: (*pattern).to_owned(),
+                    shadowed_by: (*earlier).to_owned(),
+                });
+            }
+
+            if let Some(twin) = patterns[..idx]
+                .iter()
+                .find(|earlier| is_trailing_slash_twin(earlier, pattern))
+            {
+                conflicts.push(RouteConflict::TrailingSlash {
+                    pattern: (*pattern).to_owned(),
+                    twin: (*twin).to_owned(),
+                });
+            }
+
+            let routes = self
+                .routes
+                .iter()
+                .filter(|route| route.pattern == *pattern)
+                .collect::<Vec<_>>();
+
+            for (idx, route) in routes.iter().enumerate() {
+                let duplicate = routes[..idx].iter().any(|earlier| {
+                    earlier.method == route.method
+                        && (earlier.guard.is_none() || earlier.guard == route.guard)
+                });
+
+                if duplicate {
+                    conflicts.push(RouteConflict::Duplicate {
+                        pattern: route.pattern.clone(),
+                        method: route.method.clone(),
+                        guard: route.guard.clone(),
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Returns error listing all detected conflicts, if there are any.
+    pub fn validate(&self) -> Result<(), RouteConflictError> {
+        let conflicts = self.conflicts();
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(RouteConflictError { conflicts })
+        }
+    }
+
+    fn push(
+        mut self,
+        pattern: String,
+        method: Method,
+        guard: Option<String>,
+        route: Route,
+    ) -> Self {
+        self.routes.push(CheckedRoute {
+            pattern,
+            method,
+            guard,
+            route,
+        });
+        self
+    }
+
+    /// Returns distinct patterns, in the order they were first added.
+    fn patterns(&self) -> Vec<&str> {
+        let mut patterns = Vec::<&str>::new();
+
+        for route in &self.routes {
+            if !patterns.contains(&route.pattern.as_str()) {
+                patterns.push(&route.pattern);
+            }
+        }
+
+        patterns
+    }
+}
+
+impl fmt::Debug for CheckedRoutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckedRoutes")
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|route| (&route.pattern, &route.method, &route.guard))
+                    .collect::<Vec<_>>(),
+            )
+            .field("deny_conflicts", &self.deny_conflicts)
+            .finish()
+    }
+}
+
+impl HttpServiceFactory for CheckedRoutes {
+    fn register(self, config: &mut AppService) {
+        if self.deny_conflicts {
+            if let Err(err) = self.validate() {
+                panic!("{err}");
+            }
+        }
+
+        let patterns = self
+            .patterns()
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+
+        let mut routes = self.routes;
+
+        for pattern in patterns {
+            let (matching, rest) = routes
+                .into_iter()
+                .partition::<Vec<_>, _>(|route| route.pattern == pattern);
+            routes = rest;
+
+            let resource = matching
+                .into_iter()
+                .fold(web::resource(pattern), |resource, route| {
+                    resource.route(route.route)
+                });
+
+            resource.register(config);
+        }
+    }
+}
+
+/// A conflict between routes detected by [`CheckedRoutes`].
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RouteConflict {
+    /// Every path matching `pattern` is matched by an earlier pattern.
+    #[display(fmt = "`{pattern}` is shadowed by `{shadowed_by}`")]
+    Shadowed {
+        /// Unreachable pattern.
+        pattern: String,
+
+        /// Earlier pattern which matches the same paths.
+        shadowed_by: String,
+    },
+
+    /// Route has the same pattern, method, and guard as an earlier route.
+    #[display(fmt = "{method} `{pattern}` is registered more than once")]
+    Duplicate {
+        /// Route pattern.
+        pattern: String,
+
+        /// Route method.
+        method: Method,
+
+        /// Name of route's guard, if it has one.
+        guard: Option<String>,
+    },
+
+    /// Pattern differs from an earlier pattern only by a trailing slash.
+    #[display(fmt = "`{pattern}` differs from `{twin}` only by a trailing slash")]
+    TrailingSlash {
+        /// Later pattern.
+        pattern: String,
+
+        /// Earlier pattern.
+        twin: String,
+    },
+}
+
+/// Error listing route conflicts detected by [`CheckedRoutes`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct RouteConflictError {
+    conflicts: Vec<RouteConflict>,
+}
+
+impl RouteConflictError {
+    /// Returns detected conflicts, in registration order.
+    pub fn conflicts(&self) -> &[RouteConflict] {
+        &self.conflicts
+    }
+}
+
+impl fmt::Display for RouteConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Route conflicts detected:")?;
+
+        for conflict in &self.conflicts {
+            write!(f, "\n- {conflict}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment<'a> {
+    /// Literal text, or a segment mixing text and parameters, which only matches itself.
+    Exact(&'a str),
+
+    /// A `{name}` parameter, which matches any non-empty segment.
+    Param,
+
+    /// A `{name}*` or `{name:.*}` tail parameter, which matches the rest of the path.
+    Tail,
+}
+
+fn segments(pattern: &str) -> Vec<Segment<'_>> {
+    pattern.split('/').map(segment).collect()
+}
+
+fn segment(segment: &str) -> Segment<'_> {
+    let is_name = |name: &str| !name.is_empty() && !name.contains(['{', '}', ':']);
+
+    if let Some(inner) = segment.strip_prefix('{') {
+        if inner.strip_suffix("}*").is_some_and(is_name) {
+            return Segment::Tail;
+        }
+
+        if let Some(inner) = inner.strip_suffix('}') {
+            if inner.strip_suffix(":.*").is_some_and(is_name) {
+                return Segment::Tail;
+            }
+
+            if is_name(inner) {
+                return Segment::Param;
+            }
+        }
+    }
+
+    Segment::Exact(segment)
+}
+
+/// Returns true if every path matched by `pattern` is also matched by `earlier`.
+fn covers(earlier: &str, pattern: &str) -> bool {
+    let earlier = segments(earlier);
+    let pattern = segments(pattern);
+
+    for (idx, segment) in earlier.iter().enumerate() {
+        match (segment, pattern.get(idx)) {
+            (Segment::Tail, _) => return true,
+            (_, None) => return false,
+            (Segment::Param, Some(Segment::Param)) => {}
+            (Segment::Param, Some(Segment::Exact(exact))) if !exact.is_empty() => {}
+            (Segment::Exact(a), Some(Segment::Exact(b))) if a == b => {}
+            _ => return false,
+        }
+    }
+
+    earlier.len() == pattern.len()
+}
+
+fn is_trailing_slash_twin(earlier: &str, pattern: &str) -> bool {
+    earlier != pattern && earlier.trim_end_matches('/') == pattern.trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        guard,
+        http::StatusCode,
+        test::{self, TestRequest},
+        App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[test]
+    fn pattern_coverage() {
+        assert!(covers("/users/{id}", "/users/me"));
+        assert!(covers("/users/{id}", "/users/{name}"));
+        assert!(covers("/files/{path}*", "/files/a/b"));
+        assert!(covers("/files/{path:.*}", "/files/{id}/raw"));
+        assert!(covers("/", "/"));
+
+        assert!(!covers("/users/me", "/users/{id}"));
+        assert!(!covers("/users/{id}", "/users/{id}/posts"));
+        assert!(!covers("/users/{id:\\d+}", "/users/me"));
+        assert!(!covers("/users/{id}", "/users/"));
+        assert!(!covers("/users/{id}.json", "/users/me.json"));
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let routes = CheckedRoutes::new()
+            .route("/users", Method::GET, HttpResponse::Ok)
+            .route("/users/{id}", Method::GET, HttpResponse::Ok)
+            .route("/users/me", Method::GET, HttpResponse::Ok)
+            .route("/users/", Method::POST, HttpResponse::Ok)
+            .route("/users", Method::GET, HttpResponse::Ok)
+            .guarded_route("/users", Method::POST, "a", guard::Header("a", "1"), || {
+                HttpResponse::Ok()
+            })
+            .guarded_route("/users", Method::POST, "b", guard::Header("b", "1"), || {
+                HttpResponse::Ok()
+            })
+            .guarded_route("/users", Method::POST, "a", guard::Header("a", "1"), || {
+                HttpResponse::Ok()
+            });
+
+        let err = routes.validate().unwrap_err();
+        assert_eq!(
+            err.conflicts(),
+            [
+                RouteConflict::Duplicate {
+                    pattern: "/users".to_owned(),
+                    method: Method::GET,
+                    guard: None,
+                },
+                RouteConflict::Duplicate {
+                    pattern: "/users".to_owned(),
+                    method: Method::POST,
+                    guard: Some("a".to_owned()),
+                },
+                RouteConflict::Shadowed {
+                    pattern: "/users/me".to_owned(),
+                    shadowed_by: "/users/{id}".to_owned(),
+                },
+                RouteConflict::TrailingSlash {
+                    pattern: "/users/".to_owned(),
+                    twin: "/users".to_owned(),
+                },
+            ],
+        );
+        assert_eq!(
+            err.to_string(),
+            "Route conflicts detected:\n\
+            - GET `/users` is registered more than once\n\
+            - POST `/users` is registered more than once\n\
+            - `/users/me` is shadowed by `/users/{id}`\n\
+            - `/users/` differs from `/users` only by a trailing slash",
+        );
+
+        let routes = CheckedRoutes::new()
+            .route("/users/me", Method::GET, HttpResponse::Ok)
+            .route("/users/{id}", Method::GET, HttpResponse::Ok)
+            .route("/users/{id}", Method::DELETE, HttpResponse::Ok);
+        assert!(routes.validate().is_ok());
+    }
+
+    #[actix_web::test]
+    async fn registers_routes() {
+        let app = test::init_service(
+            App::new().service(
+                CheckedRoutes::new()
+                    .route("/users/me", Method::GET, || async {
+                        HttpResponse::Ok().body("me")
+                    })
+                    .route("/users/{id}", Method::GET, || async {
+                        HttpResponse::Ok().body("id")
+                    })
+                    .guarded_route(
+                        "/users/{id}",
+                        Method::DELETE,
+                        "admin",
+                        guard::Header("x-admin", "1"),
+                        HttpResponse::NoContent,
+                    )
+                    .deny_conflicts(),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/users/me").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "me");
+
+        let req = TestRequest::with_uri("/users/1").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "id");
+
+        let req = TestRequest::delete()
+            .uri("/users/1")
+            .insert_header(("x-admin", "1"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        let req = TestRequest::delete().uri("/users/1").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_web::test]
+    #[should_panic = "`/users/me` is shadowed by `/users/{id}`"]
+    async fn deny_conflicts_panics() {
+        test::init_service(
+            App::new().service(
+                CheckedRoutes::new()
+                    .route("/users/{id}", Method::GET, HttpResponse::Ok)
+                    .route("/users/me", Method::GET, HttpResponse::Ok)
+                    .deny_conflicts(),
+            ),
+        )
+        .await;
+    }
+}
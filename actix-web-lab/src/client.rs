@@ -0,0 +1,8 @@
+//! Experimental HTTP client utilities, built on `awc`.
+//!
+//! See [`LabClient`] docs.
+
+pub use crate::awc_client::{
+    CircuitBreaker, ClientError, ClientMetrics, ClientResponse, LabClient, OutgoingRequest,
+    RetryPolicy,
+};
@@ -0,0 +1,281 @@
+//! Client IP address extractor.
+//!
+//! See [`ClientIp`] docs.
+
+use std::net::{IpAddr, SocketAddr};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::Payload, http::header::Header as _, http::StatusCode, web, FromRequest, HttpRequest,
+    ResponseError,
+};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+use crate::{
+    extract::ConnectData, header::Forwarded, proxy_protocol::ProxyHeader, util::TrustedProxies,
+};
+
+/// Client IP address, resolved through trusted proxies.
+///
+/// # Extractor
+/// Resolution starts at the source address of the connection's PROXY protocol header, if one was
+/// made available as [`ConnectData<ProxyHeader>`](ConnectData), or otherwise at the peer address
+/// of the connection. While that address belongs to a trusted proxy, the chain of addresses in the `Forwarded` header, or the `X-Forwarded-For`
+/// header if there is no `Forwarded` header, is walked from right to left, stepping to the address
+/// that the proxy received the request from. The first untrusted address is the client IP. If the
+/// chain ends or contains an identifier which is not an IP address, such as `unknown`, the last
+/// address reached is used.
+///
+/// Trusted proxies are configured by registering a [`ClientIpConfig`] as app data. Without a
+/// registered config no proxies are trusted, so the peer address is always used and forwarding
+/// headers can not be used to spoof the client IP.
+///
+/// Extraction fails with a [`ClientIpError`] if the connection has no PROXY protocol source address
+/// or peer address, such as when using Unix domain sockets. Use `Option<ClientIp>` to handle this
+/// case.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::{
+///     extract::{ClientIp, ClientIpConfig},
+///     util::TrustedProxies,
+/// };
+///
+/// async fn index(ip: ClientIp) -> String {
+///     format!("your address is {ip}")
+/// }
+///
+/// let proxies = TrustedProxies::parse(["10.0.0.0/8", "fd00::/8"]).unwrap();
+///
+/// App::new()
+///     .app_data(ClientIpConfig::new().trust_proxies(proxies))
+///     .route("/", web::get().to(index))
+/// # ;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientIp(pub IpAddr);
+
+impl_more::impl_deref!(ClientIp => IpAddr);
+impl_more::forward_display!(ClientIp);
+
+impl ClientIp {
+    /// Unwraps into inner IP address.
+    pub fn into_inner(self) -> IpAddr {
+        self.0
+    }
+}
+
+impl FromRequest for ClientIp {
+    type Error = ClientIpError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let proxied = req
+            .conn_data::<ConnectData<ProxyHeader>>()
+            .and_then(|header| header.source());
+
+        let Some(peer) = proxied.or_else(|| req.peer_addr()) else {
+            debug!(
+                "Failed to extract `ClientIp` for `{}` handler. Connection has no peer address.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            return ready(Err(ClientIpError::NoPeerAddr));
+        };
+
+        ready(Ok(ClientIp(
+            ClientIpConfig::from_req(req).resolve(req, peer.ip()),
+        )))
+    }
+}
+
+/// Errors that can occur when extracting a [`ClientIp`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ClientIpError {
+    /// Connection has no peer address.
+    #[display(fmt = "Client IP address is not available.")]
+    NoPeerAddr,
+}
+
+impl ResponseError for ClientIpError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// Trusted proxy configuration for the [`ClientIp`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientIpConfig {
+    trusted: TrustedProxies,
+}
+
+static DEFAULT_CONFIG: ClientIpConfig = ClientIpConfig {
+    trusted: TrustedProxies::new(),
+};
+
+impl ClientIpConfig {
+    /// Constructs new config which trusts no proxies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets trusted proxy address ranges.
+    pub fn trust_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted = proxies;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.contains(ip)
+    }
+
+    fn resolve(&self, req: &HttpRequest, peer: IpAddr) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let chain = match Forwarded::parse(req) {
+            Ok(fwd) => fwd.for_chain().map(str::to_owned).collect::<Vec<_>>(),
+            Err(_) => req
+                .headers()
+                .get_all("x-forwarded-for")
+                .filter_map(|val| val.to_str().ok())
+                .flat_map(|val| val.split(','))
+                .map(|ident| ident.trim().to_owned())
+                .collect(),
+        };
+
+        let mut client = peer;
+
+        for ident in chain.iter().rev() {
+            if !self.is_trusted(client) {
+                break;
+            }
+
+            match parse_node(ident) {
+                Some(ip) => client = ip,
+                None => break,
+            }
+        }
+
+        client
+    }
+}
+
+/// Parses IP address from a node identifier, ignoring any port.
+fn parse_node(ident: &str) -> Option<IpAddr> {
+    let ident = ident.trim().trim_matches('"');
+
+    ident
+        .parse::<IpAddr>()
+        .or_else(|_| ident.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .or_else(|_| {
+            ident
+                .strip_prefix('[')
+                .and_then(|ident| ident.strip_suffix(']'))
+                .unwrap_or(ident)
+                .parse::<IpAddr>()
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn node_identifiers() {
+        assert_eq!(parse_node("192.0.2.1"), Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(
+            parse_node("192.0.2.1:80"),
+            Some("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            parse_node("2001:db8::1"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(
+            parse_node("\"[2001:db8::1]:80\""),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(
+            parse_node("[2001:db8::1]"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+    }
+
+    fn client_ip(req: TestRequest) -> IpAddr {
+        let req = req
+            .app_data(
+                ClientIpConfig::new().trust_proxies(TrustedProxies::parse(["10.0.0.0/8"]).unwrap()),
+            )
+            .to_http_request();
+
+        ClientIp::extract(&req).into_inner().unwrap().into_inner()
+    }
+
+    #[test]
+    fn resolves_client_ip() {
+        let proxy = "10.0.0.1:1234".parse().unwrap();
+        let client = "192.0.2.1:1234".parse().unwrap();
+
+        // untrusted peers can not spoof addresses
+        let req = TestRequest::default()
+            .peer_addr(client)
+            .insert_header(("x-forwarded-for", "203.0.113.7"));
+        assert_eq!(client_ip(req), client.ip());
+
+        let req = TestRequest::default()
+            .peer_addr(proxy)
+            .insert_header(("x-forwarded-for", "203.0.113.7, 192.0.2.1, 10.0.0.2"));
+        assert_eq!(client_ip(req), client.ip());
+
+        let req = TestRequest::default()
+            .peer_addr(proxy)
+            .append_header(("x-forwarded-for", "10.0.0.3"))
+            .append_header(("x-forwarded-for", "10.0.0.2"));
+        assert_eq!(client_ip(req), "10.0.0.3".parse::<IpAddr>().unwrap());
+
+        let req = TestRequest::default()
+            .peer_addr(proxy)
+            .insert_header(("forwarded", r#"for="[2001:db8::1]:80", for=10.0.0.2"#))
+            .insert_header(("x-forwarded-for", "203.0.113.7"));
+        assert_eq!(client_ip(req), "2001:db8::1".parse::<IpAddr>().unwrap());
+
+        let req = TestRequest::default()
+            .peer_addr(proxy)
+            .insert_header(("forwarded", "for=unknown, for=10.0.0.2"));
+        assert_eq!(client_ip(req), "10.0.0.2".parse::<IpAddr>().unwrap());
+
+        let req = TestRequest::default().peer_addr(proxy);
+        assert_eq!(client_ip(req), proxy.ip());
+    }
+
+    #[test]
+    fn untrusted_by_default() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("x-forwarded-for", "203.0.113.7"))
+            .to_http_request();
+        let ip = ClientIp::extract(&req).into_inner().unwrap();
+        assert_eq!(ip.to_string(), "10.0.0.1");
+
+        let req = TestRequest::default().to_http_request();
+        assert!(ClientIp::extract(&req).into_inner().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Time sources.
+//!
+//! See [`Clock`] docs.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A source of the current time.
+///
+/// Time-dependent components in this crate, like [`ShardedMap`](crate::util::ShardedMap) entry
+/// expiry, read the time through a `Clock` so that tests can substitute a
+/// [`MockClock`](crate::test::MockClock) and advance time deterministically instead of sleeping.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current monotonic time, used for measuring durations.
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time, used for timestamps sent to clients (e.g., in
+    /// `Retry-After` or `Expires` headers).
+    fn system_time(&self) -> SystemTime;
+}
+
+/// Clock which reads the system's time.
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        (**self).system_time()
+    }
+}
+
+/// Clock which only moves forward when told to.
+///
+/// Cheap to clone; all clones share the same time, so a clone can be given to the component under
+/// test while the test itself keeps a handle for advancing it.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web_lab::{test::MockClock, util::ShardedMap};
+///
+/// let clock = MockClock::new();
+/// let map = ShardedMap::new(16)
+///     .default_ttl(Duration::from_secs(60))
+///     .clock(clock.clone());
+///
+/// map.insert("key", "value");
+///
+/// clock.advance(Duration::from_secs(59));
+/// assert!(map.contains_key("key"));
+///
+/// clock.advance(Duration::from_secs(1));
+/// assert!(!map.contains_key("key"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    start_system: SystemTime,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    /// Constructs a new mock clock, starting at the current system time.
+    pub fn new() -> Self {
+        Self::starting_at(SystemTime::now())
+    }
+
+    /// Constructs a new mock clock whose wall-clock time starts at `system_time`.
+    pub fn starting_at(system_time: SystemTime) -> Self {
+        Self {
+            start: Instant::now(),
+            start_system: system_time,
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Returns the total time this clock has been advanced by.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static_assertions::assert_obj_safe!(Clock);
+
+    #[test]
+    fn mock_clock_advances() {
+        let clock = MockClock::starting_at(SystemTime::UNIX_EPOCH);
+        let clock2 = clock.clone();
+
+        let now = clock.now();
+        assert_eq!(clock.system_time(), SystemTime::UNIX_EPOCH);
+
+        clock2.advance(Duration::from_secs(5));
+        assert_eq!(clock.now() - now, Duration::from_secs(5));
+        assert_eq!(
+            clock.system_time(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(5),
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Lab items under the paths of their upstream counterparts.
+//!
+//! Each module here mirrors a module in Actix Web and re-exports the lab items which have, or are
+//! expected to have, an equivalent there under the same name. Importing these modules instead of
+//! their Actix Web counterparts lets apps adopt lab APIs now and later migrate by changing one
+//! import; for example, from `actix_web_lab::compat::web` to `actix_web::web`.
+//!
+//! Lab APIs are not always identical to their upstream counterparts, so check the Actix Web docs
+//! when migrating. Items with an upstream counterpart note it in their docs; lab items which have
+//! graduated will be deprecated, as described in the [crate docs](crate).
+//!
+//! # Examples
+//! ```
+//! use actix_web::{App, Responder};
+//! // after migrating: `use actix_web::web;`
+//! use actix_web_lab::compat::web;
+//!
+//! async fn echo(body: web::Json<serde_json::Value>) -> impl Responder {
+//!     web::Html::new(format!("<pre>{}</pre>", *body))
+//! }
+//!
+//! App::new().route("/", actix_web::web::post().to(echo))
+//! # ;
+//! ```
+
+/// Lab counterparts of items in `actix_web::web`.
+pub mod web {
+    /// Upstream counterpart: `actix_web::web::Json`. Upstream, payload limits are set with
+    /// `JsonConfig` rather than a const generic parameter.
+    #[doc(inline)]
+    pub use crate::extract::Json;
+    /// Upstream counterpart: `actix_web::web::Path`.
+    #[doc(inline)]
+    pub use crate::extract::Path;
+    /// Upstream counterpart: `actix_web::web::Query`.
+    #[doc(inline)]
+    pub use crate::extract::Query;
+    /// Upstream counterpart: `actix_web::web::Form`.
+    #[doc(inline)]
+    pub use crate::extract::UrlEncodedForm as Form;
+    /// Upstream counterpart: `actix_web::web::Html`, available in recent Actix Web releases.
+    #[doc(inline)]
+    pub use crate::respond::Html;
+}
+
+/// Lab counterparts of items in `actix_web::middleware`.
+pub mod middleware {
+    /// Upstream counterpart: `actix_web::middleware::ErrorHandlers`.
+    #[doc(inline)]
+    pub use crate::middleware::ErrorHandlers;
+    /// Upstream counterpart: `actix_web::middleware::NormalizePath`.
+    #[doc(inline)]
+    pub use crate::middleware::NormalizePath;
+    /// Upstream counterpart: `actix_web::middleware::from_fn`, available in recent Actix Web
+    /// releases.
+    #[doc(inline)]
+    pub use crate::middleware::{from_fn, Next};
+}
+
+/// Lab counterparts of items in `actix_web::http`.
+pub mod http {
+    /// Lab counterparts of items in `actix_web::http::header`.
+    pub mod header {
+        /// Upstream counterpart: `actix_web::http::header::ContentLength`.
+        #[doc(inline)]
+        pub use crate::header::ContentLength;
+        /// Upstream counterpart: `actix_web::http::header::CacheControl`.
+        #[doc(inline)]
+        pub use crate::header::{CacheControl, CacheDirective};
+    }
+}
@@ -0,0 +1,255 @@
+//! Typed connection data.
+//!
+//! See [`ConnectInfoPlugin`] and [`ConnectData`] docs.
+
+use std::{any::Any, fmt, marker::PhantomData, ops::Deref, rc::Rc, sync::Arc};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::{self, Extensions},
+    Error, FromRequest, HttpRequest,
+};
+use tracing::debug;
+
+use crate::error::LabError;
+
+type ExtractFn<T> = Arc<dyn Fn(&dyn Any) -> Option<T> + Send + Sync>;
+
+/// Typed builder for [`HttpServer::on_connect()`] callbacks.
+///
+/// Registers functions which extract data of type `T` from specific connection stream types (e.g.,
+/// client certificates from TLS streams). The first function whose stream type matches the
+/// connection and which returns `Some` provides the data, which handlers can then access using the
+/// [`ConnectData<T>`] extractor.
+///
+/// # Examples
+/// ```no_run
+/// use std::net::SocketAddr;
+///
+/// use actix_web::{get, rt::net::TcpStream, App, HttpServer, Responder};
+/// use actix_web_lab::{extract::ConnectData, util::ConnectInfoPlugin};
+///
+/// #[derive(Debug)]
+/// struct LocalAddr(SocketAddr);
+///
+/// #[get("/")]
+/// async fn index(addr: ConnectData<LocalAddr>) -> impl Responder {
+///     format!("connected to {:?}", addr.0)
+/// }
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let plugin = ConnectInfoPlugin::new()
+///     .stream(|tcp: &TcpStream| tcp.local_addr().ok().map(LocalAddr));
+///
+/// HttpServer::new(|| App::new().service(index))
+///     .on_connect(plugin.into_callback())
+///     .bind(("127.0.0.1", 8080))?
+///     .run()
+///     .await
+/// # }
+/// ```
+///
+/// [`HttpServer::on_connect()`]: actix_web::HttpServer::on_connect
+pub struct ConnectInfoPlugin<T> {
+    extractors: Vec<ExtractFn<T>>,
+}
+
+impl<T: 'static> ConnectInfoPlugin<T> {
+    /// Constructs a new plugin with no registered stream types.
+    pub fn new() -> Self {
+        Self {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Registers a function which extracts data from connections with stream type `S`.
+    ///
+    /// Functions are tried in the order they were registered.
+    pub fn stream<S: 'static>(
+        mut self,
+        extract: impl Fn(&S) -> Option<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.extractors.push(Arc::new(move |conn: &dyn Any| {
+            conn.downcast_ref::<S>().and_then(&extract)
+        }));
+
+        self
+    }
+
+    /// Runs registered extraction functions against `conn`, inserting the result into `data`.
+    ///
+    /// Use this method to combine multiple plugins in a single `on_connect` callback.
+    pub fn on_connect(&self, conn: &dyn Any, data: &mut Extensions) {
+        match self.extractors.iter().find_map(|extract| extract(conn)) {
+            Some(val) => {
+                data.insert(ConnectData(Rc::new(val)));
+            }
+            None => {
+                data.insert(Unmatched::<T>(PhantomData));
+            }
+        }
+    }
+
+    /// Converts plugin into a callback for passing to [`HttpServer::on_connect()`].
+    ///
+    /// [`HttpServer::on_connect()`]: actix_web::HttpServer::on_connect
+    pub fn into_callback(self) -> impl Fn(&dyn Any, &mut Extensions) + Send + Sync + 'static {
+        move |conn, data| self.on_connect(conn, data)
+    }
+}
+
+impl<T: 'static> Default for ConnectInfoPlugin<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ConnectInfoPlugin<T> {
+    fn clone(&self) -> Self {
+        Self {
+            extractors: self.extractors.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ConnectInfoPlugin<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectInfoPlugin")
+            .field("type", &core::any::type_name::<T>())
+            .field("extractors", &self.extractors.len())
+            .finish()
+    }
+}
+
+/// Marker inserted when a plugin ran but none of its functions produced data.
+struct Unmatched<T>(PhantomData<T>);
+
+/// Connection data extractor.
+///
+/// Provides access to data inserted by a [`ConnectInfoPlugin<T>`]. Extraction fails with a
+/// [`LabError::ConnectDataNotConfigured`] error if no data is available; use
+/// `Option<ConnectData<T>>` if data is only available for some connections.
+///
+/// See [`ConnectInfoPlugin`] docs for an example.
+#[derive(Debug)]
+pub struct ConnectData<T>(Rc<T>);
+
+impl<T> ConnectData<T> {
+    /// Returns reference-counted pointer to the inner connection data.
+    pub fn into_inner(self) -> Rc<T> {
+        self.0
+    }
+}
+
+impl<T> Clone for ConnectData<T> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<T> Deref for ConnectData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: 'static> FromRequest for ConnectData<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut dev::Payload) -> Self::Future {
+        if let Some(data) = req.conn_data::<Self>() {
+            return ready(Ok(data.clone()));
+        }
+
+        let type_name = core::any::type_name::<T>();
+        let handler = req.match_name().unwrap_or_else(|| req.path());
+
+        if req.conn_data::<Unmatched<T>>().is_some() {
+            debug!(
+                "Failed to extract `ConnectData<{type_name}>` for `{handler}` handler. The \
+                `ConnectInfoPlugin<{type_name}>` did not produce data for this connection; either \
+                its stream type was not registered or the extraction function returned `None`. \
+                Use `Option<ConnectData<{type_name}>>` if data is not available for all \
+                connections.",
+            );
+        } else {
+            debug!(
+                "Failed to extract `ConnectData<{type_name}>` for `{handler}` handler. For the \
+                ConnectData extractor to work correctly, construct a \
+                `ConnectInfoPlugin::<{type_name}>::new()` and pass it to \
+                `HttpServer::on_connect()`.",
+            );
+        }
+
+        ready(Err(LabError::ConnectDataNotConfigured {
+            type_name: core::any::type_name::<Self>(),
+        }
+        .into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Port(u16);
+
+    struct FakeTcp(u16);
+    struct FakeTls(FakeTcp);
+
+    fn plugin() -> ConnectInfoPlugin<Port> {
+        ConnectInfoPlugin::new()
+            .stream(|tcp: &FakeTcp| Some(Port(tcp.0)))
+            .stream(|tls: &FakeTls| (tls.0 .0 != 0).then_some(Port(tls.0 .0)))
+    }
+
+    #[test]
+    fn extracts_from_registered_streams() {
+        let plugin = plugin();
+
+        let mut data = Extensions::new();
+        plugin.on_connect(&FakeTcp(80), &mut data);
+        assert_eq!(*data.get::<ConnectData<Port>>().unwrap().0, Port(80));
+
+        let mut data = Extensions::new();
+        plugin.on_connect(&FakeTls(FakeTcp(443)), &mut data);
+        assert_eq!(*data.get::<ConnectData<Port>>().unwrap().0, Port(443));
+    }
+
+    #[test]
+    fn marks_unmatched_streams() {
+        let plugin = plugin();
+
+        let mut data = Extensions::new();
+        plugin.on_connect(&FakeTls(FakeTcp(0)), &mut data);
+        assert!(data.get::<ConnectData<Port>>().is_none());
+        assert!(data.get::<Unmatched<Port>>().is_some());
+
+        let mut data = Extensions::new();
+        plugin.on_connect(&(), &mut data);
+        assert!(data.get::<Unmatched<Port>>().is_some());
+    }
+
+    #[actix_web::test]
+    async fn missing_conn_data() {
+        let (req, mut pl) = actix_web::test::TestRequest::default().to_http_parts();
+
+        let err = ConnectData::<Port>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.as_error::<LabError>().unwrap().code(),
+            "connect_data_not_configured",
+        );
+
+        let opt = Option::<ConnectData<Port>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert!(opt.is_none());
+    }
+}
@@ -0,0 +1,257 @@
+//! Connection metadata extractor.
+//!
+//! See [`ConnectionMeta`] docs.
+
+use std::{any::Any, cell::Cell, net::SocketAddr};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::{self, Extensions},
+    http::Version,
+    rt::net::TcpStream,
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use tracing::debug;
+
+use crate::error::LabError;
+
+/// Transport details of the connection a request was received on.
+///
+/// Populated from connection data registered by [`ConnectionMeta::on_connect()`], which must be
+/// passed to [`HttpServer::on_connect()`]. Extracting `ConnectionMeta` without it results in a
+/// [`LabError::ConnectDataNotConfigured`] error.
+///
+/// TLS details are only available for Rustls connections when the `rustls-0_21` crate feature is
+/// enabled.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{get, App, HttpServer, Responder};
+/// use actix_web_lab::extract::ConnectionMeta;
+///
+/// #[get("/")]
+/// async fn index(conn: ConnectionMeta) -> impl Responder {
+///     tracing::info!(
+///         protocol = ?conn.protocol(),
+///         peer = ?conn.peer_addr(),
+///         requests = conn.requests_on_connection(),
+///     );
+///
+///     "Hello!"
+/// }
+///
+/// # async fn run() -> std::io::Result<()> {
+/// HttpServer::new(|| App::new().service(index))
+///     .on_connect(ConnectionMeta::on_connect)
+///     .bind(("127.0.0.1", 8080))?
+///     .run()
+///     .await
+/// # }
+/// ```
+///
+/// [`HttpServer::on_connect()`]: actix_web::HttpServer::on_connect
+#[derive(Debug, Clone)]
+pub struct ConnectionMeta {
+    protocol: Version,
+    local_addr: Option<SocketAddr>,
+    peer_addr: Option<SocketAddr>,
+    tls: Option<TlsInfo>,
+    requests: u64,
+}
+
+impl ConnectionMeta {
+    /// Connection callback which records transport details for use by this extractor.
+    ///
+    /// Pass this function to [`HttpServer::on_connect()`](actix_web::HttpServer::on_connect).
+    pub fn on_connect(conn: &dyn Any, data: &mut Extensions) {
+        if let Some(conn_data) = ConnData::from_stream(conn) {
+            data.insert(conn_data);
+        }
+    }
+
+    /// Returns HTTP protocol version of the connection.
+    pub fn protocol(&self) -> Version {
+        self.protocol
+    }
+
+    /// Returns local (server) address of the connection.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Returns peer address of the connection.
+    ///
+    /// This is the address of the immediate peer, which may be a proxy.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Returns TLS session details, if the connection is encrypted and they are available.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.as_ref()
+    }
+
+    /// Returns number of requests on this connection which have extracted `ConnectionMeta`,
+    /// including the current one.
+    ///
+    /// Values greater than 1 indicate that the connection has been reused.
+    pub fn requests_on_connection(&self) -> u64 {
+        self.requests
+    }
+}
+
+impl FromRequest for ConnectionMeta {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut dev::Payload) -> Self::Future {
+        // avoid counting the same request twice when extracted more than once
+        if let Some(meta) = req.extensions().get::<Self>() {
+            return ready(Ok(meta.clone()));
+        }
+
+        let Some(conn_data) = req.conn_data::<ConnData>() else {
+            debug!(
+                "Failed to extract `ConnectionMeta` for `{}` handler. For the ConnectionMeta \
+                extractor to work correctly, pass `ConnectionMeta::on_connect` to \
+                `HttpServer::on_connect()`.",
+                req.match_name().unwrap_or_else(|| req.path()),
+            );
+
+            return ready(Err(LabError::ConnectDataNotConfigured {
+                type_name: core::any::type_name::<Self>(),
+            }
+            .into()));
+        };
+
+        conn_data.requests.set(conn_data.requests.get() + 1);
+
+        let meta = Self {
+            protocol: req.version(),
+            local_addr: conn_data.local_addr,
+            peer_addr: conn_data.peer_addr,
+            tls: conn_data.tls.clone(),
+            requests: conn_data.requests.get(),
+        };
+
+        req.extensions_mut().insert(meta.clone());
+
+        ready(Ok(meta))
+    }
+}
+
+/// TLS session details of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsInfo {
+    version: Option<&'static str>,
+    cipher_suite: Option<&'static str>,
+    alpn_protocol: Option<Vec<u8>>,
+}
+
+impl TlsInfo {
+    /// Returns negotiated TLS protocol version (e.g., `TLSv1_3`).
+    pub fn version(&self) -> Option<&'static str> {
+        self.version
+    }
+
+    /// Returns negotiated cipher suite (e.g., `TLS13_AES_128_GCM_SHA256`).
+    pub fn cipher_suite(&self) -> Option<&'static str> {
+        self.cipher_suite
+    }
+
+    /// Returns protocol negotiated using ALPN (e.g., `h2`), if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+}
+
+/// Connection data inserted by [`ConnectionMeta::on_connect()`].
+#[derive(Debug)]
+struct ConnData {
+    local_addr: Option<SocketAddr>,
+    peer_addr: Option<SocketAddr>,
+    tls: Option<TlsInfo>,
+    requests: Cell<u64>,
+}
+
+impl ConnData {
+    fn from_stream(conn: &dyn Any) -> Option<Self> {
+        if let Some(tcp) = conn.downcast_ref::<TcpStream>() {
+            return Some(Self::from_tcp(tcp, None));
+        }
+
+        #[cfg(feature = "rustls-0_21")]
+        if let Some(tls) =
+            conn.downcast_ref::<actix_tls::accept::rustls_0_21::TlsStream<TcpStream>>()
+        {
+            let (tcp, session) = tls.get_ref();
+
+            let tls = TlsInfo {
+                version: session.protocol_version().and_then(|ver| ver.as_str()),
+                cipher_suite: session
+                    .negotiated_cipher_suite()
+                    .and_then(|suite| suite.suite().as_str()),
+                alpn_protocol: session.alpn_protocol().map(<[u8]>::to_vec),
+            };
+
+            return Some(Self::from_tcp(tcp, Some(tls)));
+        }
+
+        None
+    }
+
+    fn from_tcp(tcp: &TcpStream, tls: Option<TlsInfo>) -> Self {
+        Self {
+            local_addr: tcp.local_addr().ok(),
+            peer_addr: tcp.peer_addr().ok(),
+            tls,
+            requests: Cell::new(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode, rt::net::TcpListener, test::TestRequest, ResponseError as _,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn on_connect_records_tcp_addrs() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = TcpStream::connect(addr).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        let mut ext = Extensions::new();
+        ConnectionMeta::on_connect(&server, &mut ext);
+
+        let conn_data = ext.get::<ConnData>().unwrap();
+        assert_eq!(conn_data.local_addr, Some(addr));
+        assert_eq!(conn_data.peer_addr, client.local_addr().ok());
+        assert!(conn_data.tls.is_none());
+    }
+
+    #[actix_web::test]
+    async fn on_connect_ignores_unknown_streams() {
+        let mut ext = Extensions::new();
+        ConnectionMeta::on_connect(&(), &mut ext);
+        assert!(ext.get::<ConnData>().is_none());
+    }
+
+    #[actix_web::test]
+    async fn missing_conn_data() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+
+        let err = ConnectionMeta::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+
+        let err = err.as_error::<LabError>().unwrap();
+        assert_eq!(err.code(), "connect_data_not_configured");
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
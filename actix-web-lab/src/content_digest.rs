@@ -0,0 +1,671 @@
+//! Content-Digest header and body digest middleware.
+//!
+//! See [`ContentDigest`] and [`BodyDigest`] docs.
+
+use std::{
+    fmt,
+    future::poll_fn,
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{ready, Context, Poll},
+};
+
+use actix_http::{
+    error::ParseError,
+    header::{Header, HeaderName, HeaderValue, InvalidHeaderValue, TryIntoHeaderValue},
+    BoxedPayloadStream, HttpMessage,
+};
+use actix_web::{
+    body::{BodySize, EitherBody, MessageBody as _},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error::{self, PayloadError},
+    http::StatusCode,
+    web::{Bytes, BytesMut},
+    ResponseError,
+};
+use derive_more::{Display, Error};
+use futures_core::{future::LocalBoxFuture, Stream};
+use pin_project_lite::pin_project;
+use sha2::{Digest as _, Sha256, Sha512};
+use tracing::debug;
+
+use crate::{
+    body::combinators,
+    structured_field::{self, BareItem, Item, Member, Params},
+    BoxError,
+};
+
+/// The `Content-Digest` header name.
+#[allow(clippy::declare_interior_mutable_const)]
+pub const CONTENT_DIGEST: HeaderName = HeaderName::from_static("content-digest");
+
+/// Default maximum size of response bodies which are digested by [`BodyDigest`].
+const DEFAULT_RESPONSE_DIGEST_LIMIT: usize = 1_048_576; // 1MiB
+
+/// Hash algorithms supported for content digests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// SHA-256; the `sha-256` algorithm key.
+    Sha256,
+
+    /// SHA-512; the `sha-512` algorithm key.
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Returns algorithm's key, as used in the `Content-Digest` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha-256",
+            Self::Sha512 => "sha-512",
+        }
+    }
+
+    fn hasher(self) -> Hasher {
+        match self {
+            Self::Sha256 => Hasher::Sha256(Sha256::new()),
+            Self::Sha512 => Hasher::Sha512(Sha512::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha512(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+/// The `Content-Digest` header, defined in [RFC 9530 §2].
+///
+/// Contains one or more digests of a message's content, each keyed by the hash algorithm used to
+/// compute it. Digests using algorithms not in [`DigestAlgorithm`] are preserved when parsing but
+/// cannot be verified.
+///
+/// # Example Values
+/// - `sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:`
+/// - `sha-256=:X48E9q...=:, sha-512=:WZDPaV...==:`
+///
+/// # Examples
+/// ```
+/// use actix_web::HttpResponse;
+/// use actix_web_lab::header::{ContentDigest, DigestAlgorithm};
+///
+/// let body = r#"{"hello": "world"}"#;
+/// let digest = ContentDigest::compute(DigestAlgorithm::Sha256, body.as_bytes());
+/// assert!(digest.verify(body.as_bytes()).is_ok());
+///
+/// let mut builder = HttpResponse::Ok();
+/// builder.insert_header(digest);
+/// ```
+///
+/// [RFC 9530 §2]: https://datatracker.ietf.org/doc/html/rfc9530#section-2
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentDigest {
+    digests: Vec<(String, Vec<u8>)>,
+}
+
+impl ContentDigest {
+    /// Computes digest of `content` using `algorithm`.
+    pub fn compute(algorithm: DigestAlgorithm, content: &[u8]) -> Self {
+        let mut hasher = algorithm.hasher();
+        hasher.update(content);
+        Self::from_hasher(algorithm, hasher)
+    }
+
+    fn from_hasher(algorithm: DigestAlgorithm, hasher: Hasher) -> Self {
+        Self {
+            digests: vec![(algorithm.as_str().to_owned(), hasher.finalize())],
+        }
+    }
+
+    /// Returns digest computed using `algorithm`, if present.
+    pub fn get(&self, algorithm: DigestAlgorithm) -> Option<&[u8]> {
+        self.get_by_key(algorithm.as_str())
+    }
+
+    /// Returns iterator over algorithm keys and their digests, in header order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.digests
+            .iter()
+            .map(|(key, digest)| (key.as_str(), digest.as_slice()))
+    }
+
+    /// Checks `content` against the strongest supported digest in this header.
+    ///
+    /// # Errors
+    /// Returns [`ContentDigestError::UnsupportedAlgorithm`] if no digest uses a supported
+    /// algorithm, or [`ContentDigestError::Mismatch`] if the digest does not match `content`.
+    pub fn verify(&self, content: &[u8]) -> Result<(), ContentDigestError> {
+        let (algorithm, expected) = self
+            .strongest()
+            .ok_or(ContentDigestError::UnsupportedAlgorithm)?;
+
+        let mut hasher = algorithm.hasher();
+        hasher.update(content);
+
+        if hasher.finalize() == expected {
+            Ok(())
+        } else {
+            Err(ContentDigestError::Mismatch)
+        }
+    }
+
+    fn get_by_key(&self, key: &str) -> Option<&[u8]> {
+        self.digests
+            .iter()
+            .find_map(|(name, digest)| (name == key).then_some(digest.as_slice()))
+    }
+
+    /// Returns strongest supported algorithm and its digest.
+    fn strongest(&self) -> Option<(DigestAlgorithm, &[u8])> {
+        [DigestAlgorithm::Sha512, DigestAlgorithm::Sha256]
+            .into_iter()
+            .find_map(|algorithm| Some((algorithm, self.get(algorithm)?)))
+    }
+}
+
+impl fmt::Display for ContentDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (key, digest)) in self.digests.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+
+            let digest = Member::Item(Item {
+                bare: BareItem::ByteSeq(digest.clone()),
+                params: Params::new(),
+            });
+
+            write!(f, "{key}={}", digest.serialize())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryIntoHeaderValue for ContentDigest {
+    type Error = InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<HeaderValue, Self::Error> {
+        HeaderValue::try_from(self.to_string())
+    }
+}
+
+impl Header for ContentDigest {
+    fn name() -> HeaderName {
+        CONTENT_DIGEST
+    }
+
+    fn parse<M: HttpMessage>(msg: &M) -> Result<Self, ParseError> {
+        let values = msg
+            .headers()
+            .get_all(Self::name())
+            .map(|val| val.to_str().map_err(|_| ParseError::Header))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let dict =
+            structured_field::parse_dictionary(&values.join(", ")).ok_or(ParseError::Header)?;
+
+        let digests = dict
+            .into_iter()
+            .map(|(key, member)| match member {
+                Member::Item(Item {
+                    bare: BareItem::ByteSeq(digest),
+                    ..
+                }) => Ok((key, digest)),
+                _ => Err(ParseError::Header),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if digests.is_empty() {
+            return Err(ParseError::Header);
+        }
+
+        Ok(Self { digests })
+    }
+}
+
+/// Errors that can occur when verifying content digests.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ContentDigestError {
+    /// Request has no `Content-Digest` header, but one is required.
+    #[display(fmt = "Content-Digest header is missing.")]
+    Missing,
+
+    /// `Content-Digest` header could not be parsed.
+    #[display(fmt = "Content-Digest header is malformed.")]
+    Malformed,
+
+    /// `Content-Digest` header has no digests using supported algorithms.
+    #[display(fmt = "Content-Digest header uses unsupported algorithms.")]
+    UnsupportedAlgorithm,
+
+    /// Content does not match its digest.
+    #[display(fmt = "Content does not match Content-Digest header.")]
+    Mismatch,
+}
+
+impl ResponseError for ContentDigestError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// A middleware that verifies request body digests and adds digests to responses.
+///
+/// Request bodies with a `Content-Digest` header are hashed as they are read, using the strongest
+/// supported algorithm in the header. If the body does not match, reading it fails at the end of
+/// the body with a [`PayloadError`], so body extractors reject the request before handlers see
+/// any of it. Requests with malformed headers are rejected before the wrapped service is called,
+/// as are requests without a usable digest if [required](Self::require_request_digest).
+///
+/// When [enabled](Self::response_digest), response bodies are hashed chunk by chunk as they are
+/// produced and a `Content-Digest` header is added before the response is sent. Since headers
+/// precede the body, chunks are held in memory until the body ends, up to a
+/// [limit](Self::response_digest_limit). This includes streaming bodies of unknown size. If a body
+/// grows past the limit, the chunks read so far are sent, followed by the rest of the body, without
+/// a digest. Bodies with a known size over the limit are not read at all, and responses which
+/// already have the header are left unchanged.
+///
+/// Digests cover the content as it is sent, so this middleware should be registered after (i.e.,
+/// outside) any compression middleware.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::{header::DigestAlgorithm, middleware::BodyDigest};
+///
+/// App::new().wrap(
+///     BodyDigest::new()
+///         .require_request_digest(true)
+///         .response_digest(DigestAlgorithm::Sha256),
+/// )
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct BodyDigest {
+    require_request_digest: bool,
+    response_digest: Option<DigestAlgorithm>,
+    response_digest_limit: usize,
+}
+
+impl BodyDigest {
+    /// Constructs new body digest middleware which verifies request body digests, when present.
+    pub fn new() -> Self {
+        Self {
+            require_request_digest: false,
+            response_digest: None,
+            response_digest_limit: DEFAULT_RESPONSE_DIGEST_LIMIT,
+        }
+    }
+
+    /// Sets whether requests must have a `Content-Digest` header using a supported algorithm.
+    ///
+    /// Defaults to false.
+    pub fn require_request_digest(mut self, require: bool) -> Self {
+        self.require_request_digest = require;
+        self
+    }
+
+    /// Adds `Content-Digest` headers, computed using `algorithm`, to responses.
+    pub fn response_digest(mut self, algorithm: DigestAlgorithm) -> Self {
+        self.response_digest = Some(algorithm);
+        self
+    }
+
+    /// Sets maximum size, in bytes, of response bodies which are digested, including streaming
+    /// bodies.
+    ///
+    /// The default is 1MiB.
+    pub fn response_digest_limit(mut self, limit: usize) -> Self {
+        self.response_digest_limit = limit;
+        self
+    }
+
+    /// Wraps request payload in a verifying stream, or returns why the request is rejected.
+    fn verify_request(&self, req: &mut ServiceRequest) -> Result<(), ContentDigestError> {
+        let digest = match ContentDigest::parse(req) {
+            Ok(digest) => digest,
+            Err(_) if req.headers().contains_key(CONTENT_DIGEST) => {
+                return Err(ContentDigestError::Malformed)
+            }
+            Err(_) if self.require_request_digest => return Err(ContentDigestError::Missing),
+            Err(_) => return Ok(()),
+        };
+
+        let Some((algorithm, expected)) = digest.strongest() else {
+            if self.require_request_digest {
+                return Err(ContentDigestError::UnsupportedAlgorithm);
+            }
+
+            return Ok(());
+        };
+
+        let payload: BoxedPayloadStream = Box::pin(VerifyDigest {
+            stream: req.take_payload(),
+            hasher: Some(algorithm.hasher()),
+            expected: expected.to_vec(),
+        });
+        req.set_payload(Payload::from(payload));
+
+        Ok(())
+    }
+}
+
+impl Default for BodyDigest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BodyDigest
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = BodyDigestMiddleware<S>;
+    type InitError = ();
+    type Future = std::future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(BodyDigestMiddleware {
+            service: Rc::new(service),
+            config: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`BodyDigest`] middleware.
+pub struct BodyDigestMiddleware<S> {
+    service: Rc<S>,
+    config: BodyDigest,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyDigestMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: actix_web::body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if let Err(err) = self.config.verify_request(&mut req) {
+            debug!("Rejected request to `{}`: {err}", req.path());
+            return Box::pin(std::future::ready(Ok(req
+                .error_response(err)
+                .map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        let algorithm = self.config.response_digest;
+        let limit = self.config.response_digest_limit;
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let Some(algorithm) = algorithm else {
+                return Ok(res.map_into_left_body());
+            };
+
+            let within_limit = match res.response().body().size() {
+                BodySize::None => false,
+                BodySize::Sized(size) => size <= limit as u64,
+                BodySize::Stream => true,
+            };
+
+            if !within_limit || res.headers().contains_key(CONTENT_DIGEST) {
+                return Ok(res.map_into_left_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let (mut res, body) = res.into_parts();
+
+            let mut body = Box::pin(body);
+            let mut hasher = algorithm.hasher();
+            let mut buf = BytesMut::new();
+
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                let chunk = chunk.map_err(|err| {
+                    let err: BoxError = err.into();
+                    error::ErrorInternalServerError(err.to_string())
+                })?;
+
+                hasher.update(&chunk);
+                buf.extend_from_slice(&chunk);
+
+                if buf.len() > limit {
+                    debug!("Response body from `{}` is too large to digest", req.path());
+
+                    let body = combinators::prefix(buf.freeze(), body);
+                    let res = res.set_body(body).map_into_boxed_body();
+                    return Ok(ServiceResponse::new(req, res).map_into_right_body());
+                }
+            }
+
+            let digest = ContentDigest::from_hasher(algorithm, hasher);
+            res.headers_mut().insert(
+                CONTENT_DIGEST,
+                digest
+                    .try_into_value()
+                    .map_err(error::ErrorInternalServerError)?,
+            );
+
+            let res = res.set_body(buf.freeze()).map_into_boxed_body();
+            Ok(ServiceResponse::new(req, res).map_into_right_body())
+        })
+    }
+}
+
+pin_project! {
+    /// Payload stream which checks the digest of the payload once it has been read.
+    struct VerifyDigest<S> {
+        #[pin]
+        stream: S,
+        hasher: Option<Hasher>,
+        expected: Vec<u8>,
+    }
+}
+
+impl<S> Stream for VerifyDigest<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        match ready!(this.stream.poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                if let Some(hasher) = this.hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+
+                Poll::Ready(Some(Ok(chunk)))
+            }
+
+            Some(Err(err)) => Poll::Ready(Some(Err(err))),
+
+            None => {
+                let mismatch = this
+                    .hasher
+                    .take()
+                    .is_some_and(|hasher| hasher.finalize() != *this.expected);
+
+                if mismatch {
+                    Poll::Ready(Some(Err(PayloadError::Io(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        ContentDigestError::Mismatch,
+                    )))))
+                } else {
+                    Poll::Ready(None)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::{
+        http::header,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+    use base64::Engine as _;
+
+    use super::*;
+
+    const BODY: &str = r#"{"hello": "world"}"#;
+    const SHA_256: &str = "sha-256=:X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=:";
+    const SHA_512: &str = "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:";
+
+    #[test]
+    fn header_round_trip() {
+        let digest = ContentDigest::compute(DigestAlgorithm::Sha256, BODY.as_bytes());
+        assert_eq!(digest.to_string(), SHA_256);
+
+        let req = TestRequest::default()
+            .append_header((CONTENT_DIGEST, SHA_256))
+            .append_header((CONTENT_DIGEST, format!("{SHA_512}, md5=:AAAA:")))
+            .to_http_request();
+        let digest = ContentDigest::parse(&req).unwrap();
+
+        assert_eq!(digest.iter().count(), 3);
+        assert_eq!(
+            digest.get(DigestAlgorithm::Sha512).unwrap(),
+            base64::engine::general_purpose::STANDARD
+                .decode(&SHA_512[9..SHA_512.len() - 1])
+                .unwrap()
+        );
+        assert!(digest.verify(BODY.as_bytes()).is_ok());
+        assert!(matches!(
+            digest.verify(b"{}"),
+            Err(ContentDigestError::Mismatch)
+        ));
+
+        let req = TestRequest::default()
+            .insert_header((CONTENT_DIGEST, "sha-256=abc"))
+            .to_http_request();
+        assert!(ContentDigest::parse(&req).is_err());
+    }
+
+    #[actix_web::test]
+    async fn verifies_requests_and_digests_responses() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    BodyDigest::new()
+                        .require_request_digest(true)
+                        .response_digest(DigestAlgorithm::Sha256),
+                )
+                .route(
+                    "/",
+                    web::post().to(|body: web::Bytes| async move {
+                        HttpResponse::Ok()
+                            .content_type(mime::APPLICATION_JSON)
+                            .body(body)
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .insert_header((CONTENT_DIGEST, SHA_512))
+            .set_payload(BODY)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(CONTENT_DIGEST).unwrap(), SHA_256);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert_eq!(test::read_body(res).await, BODY);
+
+        let req = TestRequest::post()
+            .insert_header((CONTENT_DIGEST, SHA_256))
+            .set_payload("{}")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post().set_payload(BODY).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post()
+            .insert_header((CONTENT_DIGEST, "md5=:AAAA:"))
+            .set_payload(BODY)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn digests_streaming_responses() {
+        fn streaming(chunks: &'static [&'static str]) -> HttpResponse {
+            HttpResponse::Ok().streaming(futures_util::stream::iter(
+                chunks
+                    .iter()
+                    .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))),
+            ))
+        }
+
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    BodyDigest::new()
+                        .response_digest(DigestAlgorithm::Sha256)
+                        .response_digest_limit(BODY.len()),
+                )
+                .route(
+                    "/",
+                    web::get().to(|| async { streaming(&["{\"hello\"", ": ", "\"world\"}"]) }),
+                )
+                .route(
+                    "/large",
+                    web::get().to(|| async { streaming(&[BODY, "\n"]) }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(CONTENT_DIGEST).unwrap(), SHA_256);
+        assert_eq!(test::read_body(res).await, BODY);
+
+        // bodies which grow past the limit are passed through intact, without a digest
+        let req = TestRequest::with_uri("/large").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(!res.headers().contains_key(CONTENT_DIGEST));
+        assert_eq!(test::read_body(res).await, format!("{BODY}\n"));
+    }
+}
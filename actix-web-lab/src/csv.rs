@@ -94,7 +94,7 @@ impl Csv<Infallible> {
     }
 }
 
-fn serialize_csv_row(item: impl Serialize) -> Bytes {
+pub(crate) fn serialize_csv_row(item: impl Serialize) -> Bytes {
     let mut buf = BytesMut::new();
     let wrt = MutWriter(&mut buf);
 
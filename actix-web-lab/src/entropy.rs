@@ -0,0 +1,163 @@
+//! Random sources.
+//!
+//! See [`Entropy`] docs.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher as _, Hasher as _},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// A source of random numbers for non-cryptographic purposes.
+///
+/// Components which need randomness, like request ID generators, experiment bucketing, and
+/// samplers, read it through an `Entropy` so that tests can substitute a
+/// [`SeededEntropy`](crate::test::SeededEntropy) and get reproducible results.
+///
+/// Implementations are not required to be cryptographically secure and must not be used for
+/// generating secrets.
+pub trait Entropy: fmt::Debug + Send + Sync {
+    /// Returns the next random `u64`.
+    fn next_u64(&self) -> u64;
+
+    /// Returns a random `f64` in the range `[0, 1)`.
+    fn next_f64(&self) -> f64 {
+        // use the top 53 bits, the precision of an f64 mantissa
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Returns true with the given `probability`.
+    ///
+    /// Probabilities at or below `0.0` always return false and those at or above `1.0` always
+    /// return true.
+    fn sample(&self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Fills `buf` with random bytes.
+    fn fill_bytes(&self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+impl<E: Entropy + ?Sized> Entropy for Arc<E> {
+    fn next_u64(&self) -> u64 {
+        (**self).next_u64()
+    }
+}
+
+/// Entropy source seeded randomly by the operating system.
+///
+/// Each instance is seeded independently. Values are derived by hashing an incrementing counter
+/// with a randomly keyed SipHash.
+#[derive(Debug, Default)]
+pub struct SystemEntropy {
+    keys: RandomState,
+    counter: AtomicU64,
+}
+
+impl SystemEntropy {
+    /// Constructs a new, randomly seeded entropy source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Entropy for SystemEntropy {
+    fn next_u64(&self) -> u64 {
+        let mut hasher = self.keys.build_hasher();
+        hasher.write_u64(self.counter.fetch_add(1, Ordering::Relaxed));
+        hasher.finish()
+    }
+}
+
+/// Deterministic entropy source which produces the same sequence for the same seed.
+///
+/// Cheap to clone; clones continue to share the same sequence.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::{test::SeededEntropy, util::Entropy as _};
+///
+/// let a = SeededEntropy::new(42);
+/// let b = SeededEntropy::new(42);
+///
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SeededEntropy {
+    state: Arc<AtomicU64>,
+}
+
+impl SeededEntropy {
+    /// Constructs a new entropy source from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Arc::new(AtomicU64::new(seed)),
+        }
+    }
+}
+
+impl Entropy for SeededEntropy {
+    fn next_u64(&self) -> u64 {
+        // SplitMix64
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static_assertions::assert_obj_safe!(Entropy);
+
+    #[test]
+    fn seeded_is_reproducible() {
+        let a = SeededEntropy::new(1);
+        let b = SeededEntropy::new(1);
+        let c = SeededEntropy::new(2);
+
+        let seq = |entropy: &SeededEntropy| (0..4).map(|_| entropy.next_u64()).collect::<Vec<_>>();
+
+        let seq_a = seq(&a);
+        assert_eq!(seq_a, seq(&b));
+        assert_ne!(seq_a, seq(&c));
+    }
+
+    #[test]
+    fn system_produces_distinct_values() {
+        let entropy = SystemEntropy::new();
+        assert_ne!(entropy.next_u64(), entropy.next_u64());
+    }
+
+    #[test]
+    fn provided_methods() {
+        let entropy = SeededEntropy::new(0);
+
+        for _ in 0..100 {
+            let n = entropy.next_f64();
+            assert!((0.0..1.0).contains(&n));
+        }
+
+        assert!(!entropy.sample(0.0));
+        assert!(entropy.sample(1.0));
+
+        let mut buf = [0; 13];
+        entropy.fill_bytes(&mut buf);
+        assert_ne!(buf, [0; 13]);
+    }
+}
@@ -0,0 +1,8 @@
+//! Experimental errors.
+//!
+//! Analogous to the `error` module in Actix Web.
+
+pub use crate::{
+    error_chain::ErrorChain,
+    lab_error::{ErrorRegistry, LabError},
+};
@@ -0,0 +1,118 @@
+//! Error source-chain capture.
+//!
+//! See [`ErrorChain`] for docs.
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    error::Error as StdError,
+    fmt,
+};
+
+use actix_web::HttpResponse;
+
+/// The source chain of an error, captured when it was converted into a response.
+///
+/// Error responses produced by this crate's extractors and middleware have this type inserted into
+/// their extensions so that logging middleware can report the underlying causes, which are
+/// otherwise lost once an error becomes an `HttpResponse`. For example, the
+/// [`RequestSpan`](crate::middleware::RequestSpan) middleware includes it in its access log.
+///
+/// # Examples
+/// ```
+/// use actix_web::dev::ServiceResponse;
+/// use actix_web_lab::error::ErrorChain;
+///
+/// fn log_error_chain<B>(res: &ServiceResponse<B>) {
+///     if let Some(chain) = res.response().extensions().get::<ErrorChain>() {
+///         tracing::error!("request failed: {chain}");
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ErrorChain {
+    messages: Vec<String>,
+    backtrace: Option<Backtrace>,
+}
+
+impl ErrorChain {
+    /// Captures the display messages of `err` and each of its sources.
+    ///
+    /// A backtrace of the current location is also captured if enabled through the
+    /// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables.
+    pub fn capture(err: &(dyn StdError + 'static)) -> Self {
+        let mut messages = vec![err.to_string()];
+
+        let mut source = err.source();
+        while let Some(err) = source {
+            messages.push(err.to_string());
+            source = err.source();
+        }
+
+        Self::from_messages(messages)
+    }
+
+    /// Constructs a chain from pre-rendered messages, outermost first.
+    pub(crate) fn from_messages(messages: Vec<String>) -> Self {
+        let backtrace = Backtrace::capture();
+
+        Self {
+            messages,
+            backtrace: (backtrace.status() == BacktraceStatus::Captured).then_some(backtrace),
+        }
+    }
+
+    /// Returns the error messages in the chain, outermost first.
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.messages.iter().map(String::as_str)
+    }
+
+    /// Returns the backtrace captured alongside the error chain, if enabled.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+
+    /// Inserts this chain into the extensions of `res`.
+    pub(crate) fn insert_into(self, res: &mut HttpResponse) {
+        res.extensions_mut().insert(self);
+    }
+}
+
+impl fmt::Display for ErrorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut messages = self.messages();
+
+        if let Some(msg) = messages.next() {
+            f.write_str(msg)?;
+        }
+
+        for msg in messages {
+            write!(f, ": {msg}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use derive_more::{Display, Error};
+
+    use super::*;
+
+    #[derive(Debug, Display, Error)]
+    #[display(fmt = "outer")]
+    struct Outer {
+        source: Inner,
+    }
+
+    #[derive(Debug, Display, Error)]
+    #[display(fmt = "inner")]
+    struct Inner;
+
+    #[test]
+    fn captures_source_chain() {
+        let chain = ErrorChain::capture(&Outer { source: Inner });
+        assert_eq!(chain.messages().collect::<Vec<_>>(), ["outer", "inner"]);
+        assert_eq!(chain.to_string(), "outer: inner");
+    }
+}
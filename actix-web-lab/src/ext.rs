@@ -0,0 +1,188 @@
+use std::{
+    any::type_name,
+    future::{ready, Ready},
+    ops::{Deref, DerefMut},
+};
+
+use actix_web::{
+    dev::{Payload, ServiceRequest},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use tracing::debug;
+
+use crate::error::LabError;
+
+/// Extractor for a value inserted into request extensions, typically by middleware.
+///
+/// The value is cloned out of the request's extensions, so types which are expensive to clone
+/// should be wrapped in an `Rc` or `Arc` when inserted.
+///
+/// Extracting a type which has not been inserted results in a [`LabError::ExtensionNotFound`]
+/// error. The missing type is named in debug logs but not in the response.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     body::MessageBody,
+///     dev::{ServiceRequest, ServiceResponse},
+///     get, App, Error, Responder,
+/// };
+/// use actix_web_lab::{
+///     extract::{Ext, InsertExt as _},
+///     middleware::{from_fn, Next},
+/// };
+///
+/// #[derive(Debug, Clone)]
+/// struct Tenant(String);
+///
+/// async fn resolve_tenant(
+///     req: ServiceRequest,
+///     next: Next<impl MessageBody>,
+/// ) -> Result<ServiceResponse<impl MessageBody>, Error> {
+///     req.insert_ext(Tenant("acme".to_owned()));
+///     next.call(req).await
+/// }
+///
+/// #[get("/")]
+/// async fn handler(Ext(tenant): Ext<Tenant>) -> impl Responder {
+///     tenant.0
+/// }
+///
+/// App::new().wrap(from_fn(resolve_tenant)).service(handler)
+/// # ;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ext<T>(pub T);
+
+impl<T> Ext<T> {
+    /// Unwraps into inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Ext<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Ext<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Clone + 'static> FromRequest for Ext<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(val) = req.extensions().get::<T>() {
+            return ready(Ok(Ext(val.clone())));
+        }
+
+        debug!(
+            "Failed to extract `Ext<{}>` for `{}` handler. For the Ext extractor to work correctly, \
+            the value must be inserted into request extensions, usually by middleware, before the \
+            handler is called. Ensure that the middleware is registered for this route and that \
+            types align in both the insert and extract calls.",
+            type_name::<T>(),
+            req.match_name().unwrap_or_else(|| req.path())
+        );
+
+        ready(Err(LabError::ExtensionNotFound {
+            type_name: type_name::<T>(),
+        }
+        .into()))
+    }
+}
+
+/// Extension trait for inserting values which can be extracted using [`Ext`].
+///
+/// See [`Ext`] docs for an example.
+pub trait InsertExt {
+    /// Inserts `val` into request extensions, returning any previously inserted value of the same
+    /// type.
+    fn insert_ext<T: 'static>(&self, val: T) -> Option<T>;
+}
+
+impl InsertExt for HttpRequest {
+    fn insert_ext<T: 'static>(&self, val: T) -> Option<T> {
+        self.extensions_mut().insert(val)
+    }
+}
+
+impl InsertExt for ServiceRequest {
+    fn insert_ext<T: 'static>(&self, val: T) -> Option<T> {
+        self.extensions_mut().insert(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::{self, MessageBody},
+        dev::ServiceResponse,
+        http::StatusCode,
+        test, web, App,
+    };
+
+    use super::*;
+    use crate::middleware::{from_fn, Next};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct UserId(u64);
+
+    async fn insert_user(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, Error> {
+        assert!(req.insert_ext(UserId(1)).is_none());
+        assert_eq!(req.insert_ext(UserId(42)), Some(UserId(1)));
+        next.call(req).await
+    }
+
+    async fn handler(Ext(user): Ext<UserId>) -> String {
+        user.0.to_string()
+    }
+
+    #[actix_web::test]
+    async fn extracts_inserted_value() {
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::scope("/with")
+                        .wrap(from_fn(insert_user))
+                        .default_service(web::to(handler)),
+                )
+                .default_service(web::to(handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::with_uri("/with").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "42");
+
+        let req = test::TestRequest::with_uri("/without").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("extension_not_found"));
+        assert!(!body.contains("UserId"));
+    }
+
+    #[actix_web::test]
+    async fn insert_into_http_request() {
+        let req = test::TestRequest::default().to_http_request();
+        req.insert_ext(UserId(7));
+
+        let Ext(user) = Ext::<UserId>::extract(&req).await.unwrap();
+        assert_eq!(user, UserId(7));
+    }
+}
@@ -4,16 +4,104 @@
 pub type SharedData<T> = actix_web::web::Data<T>;
 
 pub use crate::{
-    body_limit::{BodyLimit, DEFAULT_BODY_LIMIT},
-    bytes::{Bytes, DEFAULT_BYTES_LIMIT},
-    host::Host,
+    api_key::{ApiKey, ApiKeyConfig, ApiKeyError},
+    basic_auth::{
+        BasicAuth, BasicAuthConfig, BasicAuthError, BasicAuthErrorKind, VerifiedBasicAuth,
+    },
+    bearer_auth::{BearerAuth, BearerAuthConfig, BearerAuthError, BearerAuthErrorKind},
+    body_limit::{BodyLimit, BodyLimitConfig, DEFAULT_BODY_LIMIT},
+    bytes::{Bytes, BytesConfig, DEFAULT_BYTES_LIMIT},
+    canary::CanaryVariant,
+    canonical_headers::CanonicalHeaders,
+    client_ip::{ClientIp, ClientIpConfig, ClientIpError},
+    connect_data::ConnectData,
+    connection_meta::{ConnectionMeta, TlsInfo},
+    ext::{Ext, InsertExt},
+    host::{Host, HostConfig},
     json::{Json, DEFAULT_JSON_LIMIT},
+    json_or_form::{JsonOrForm, JsonOrFormPayloadError, DEFAULT_JSON_OR_FORM_LIMIT},
+    lab_config::LabConfig,
     lazy_data::LazyData,
     local_data::LocalData,
+    odata::{ODataQuery, ODataQueryError},
+    partial_response::{FieldSelector, FieldSelectorError},
     path::Path,
-    query::Query,
+    preferred_language::{PreferredLanguage, PreferredLanguageConfig, PreferredLanguageError},
+    query::{Query, QueryConfig},
+    replayable_body::{
+        ReplayableBody, ReplayableBodyConfig, ReplayableBodyError, DEFAULT_REPLAYABLE_LIMIT,
+        DEFAULT_REPLAYABLE_MEMORY_LIMIT,
+    },
+    request_context::RequestContext,
+    request_id::RequestId,
     request_signature::{RequestSignature, RequestSignatureError, RequestSignatureScheme},
+    request_snapshot::RequestSnapshot,
+    root_span::RootSpan,
+    rsql::{Rsql, RsqlConfig, RsqlError},
+    server_stats::ServerStats,
     swap_data::SwapData,
+    text::{InvalidSequences, Text, TextConfig, TextPayloadError, DEFAULT_TEXT_LIMIT},
+    transactional::Tx,
     url_encoded_form::{UrlEncodedForm, DEFAULT_URL_ENCODED_FORM_LIMIT},
+    user_agent::UserAgent,
     x_forwarded_prefix::ReconstructedPath,
 };
+
+#[cfg(feature = "cbor")]
+pub use crate::cbor_extract::{Cbor, CborPayloadError, DEFAULT_CBOR_LIMIT};
+#[cfg(feature = "jsonapi")]
+pub use crate::json_api::{JsonApi, JsonApiPayloadError, JsonApiResource, SparseFieldsets};
+#[cfg(feature = "jwt")]
+pub use crate::jwt::{Jwks, JwksError, Jwt, JwtConfig, JwtError};
+#[cfg(feature = "msgpack")]
+pub use crate::msgpack::{MsgPack, MsgPackConfig, MsgPackPayloadError, DEFAULT_MSGPACK_LIMIT};
+#[cfg(feature = "multipart")]
+pub use crate::multipart_form::{
+    FieldGroup, FieldLimits, FieldReader, MultipartForm, MultipartFormConfig, MultipartFormError,
+    MultipartText, TempFile, DEFAULT_MULTIPART_FORM_LIMIT,
+};
+#[cfg(feature = "protobuf")]
+pub use crate::protobuf_extract::{Protobuf, ProtobufPayloadError, DEFAULT_PROTOBUF_LIMIT};
+#[cfg(feature = "qs")]
+pub use crate::qs_query::{QsQuery, QsQueryConfig, QsQueryError};
+#[cfg(feature = "arena")]
+pub use crate::request_arena::RequestArena;
+#[cfg(any(feature = "ed25519", feature = "hmac-sha256"))]
+pub use crate::signature_schemes::SignatureSchemeError;
+#[cfg(feature = "ed25519")]
+pub use crate::signature_schemes::{Ed25519Config, Ed25519Scheme};
+#[cfg(feature = "hmac-sha256")]
+pub use crate::signature_schemes::{HmacSha256Config, HmacSha256Scheme};
+#[cfg(feature = "garde")]
+pub use crate::validated::{Validatable, Validated, ValidatedError};
+#[cfg(feature = "validator")]
+pub use crate::validated_json::{ValidatedJson, ValidatedJsonError};
+#[cfg(feature = "xml")]
+pub use crate::xml::{Xml, XmlConfig, XmlPayloadError, DEFAULT_XML_LIMIT};
+#[cfg(feature = "yaml")]
+pub use crate::yaml::{Yaml, YamlPayloadError, DEFAULT_YAML_LIMIT};
+#[cfg(all(feature = "derive", feature = "multipart"))]
+pub use actix_web_lab_derive::MultipartForm;
+
+/// Types for working with [`Jwt`] headers and key sets.
+#[cfg(feature = "jwt")]
+pub mod jwt {
+    pub use jsonwebtoken::{jwk::JwkSet, Algorithm, Header};
+}
+
+/// Types for working with [`ODataQuery`] filter and ordering options.
+pub mod odata {
+    pub use crate::odata::{
+        CompareOp, FilterExpr, FilterVisitor, Literal, OrderBy, SortDirection, StringFunction,
+    };
+}
+
+/// Types for working with [`Rsql`] expressions.
+pub mod rsql {
+    pub use crate::rsql::{Comparison, Expr, Operator};
+}
+
+/// Types for working with [`UserAgent`] product tokens.
+pub mod user_agent {
+    pub use crate::user_agent::Product;
+}
@@ -0,0 +1,292 @@
+//! Structured fallback service.
+//!
+//! See [`Fallback`] docs.
+
+use std::{
+    fmt,
+    future::{ready, Future, Ready},
+    rc::Rc,
+};
+
+use actix_service::{always_ready, Service, ServiceFactory};
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    Error, HttpRequest, HttpResponse,
+};
+use futures_core::future::LocalBoxFuture;
+
+type RenderFn = Rc<dyn Fn(HttpRequest) -> LocalBoxFuture<'static, HttpResponse>>;
+
+/// Reason a request was handled by a [`Fallback`] service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FallbackKind {
+    /// No resource matches the request path.
+    NotFound,
+
+    /// A resource matches the request path but none of its routes accept the request.
+    ///
+    /// Only detected when the fallback is registered as a resource's default service.
+    MethodNotAllowed,
+
+    /// A resource matches the request path but its guards rejected the request.
+    ///
+    /// Since method routing macros such as `#[get]` guard the whole resource, this includes
+    /// requests with the wrong method for resources defined by those macros.
+    GuardRejected,
+}
+
+impl FallbackKind {
+    /// Classifies a request which reached a default service.
+    fn of(req: &HttpRequest) -> Self {
+        if req.match_info().unprocessed().is_empty() {
+            // the path is only fully consumed when a resource matched it, which means this is that
+            // resource's default service
+            Self::MethodNotAllowed
+        } else if req.resource_map().has_resource(req.path()) {
+            Self::GuardRejected
+        } else {
+            Self::NotFound
+        }
+    }
+}
+
+/// A default service which renders distinct responses for unmatched paths, unmatched methods, and
+/// guard rejections.
+///
+/// Actix Web routes all of these cases to a single default service; a `Fallback` classifies each
+/// request (see [`FallbackKind`]) and calls the corresponding renderer. Register it as the default
+/// service of an app or scope to handle unmatched paths and guard rejections, and of individual
+/// resources to handle unmatched methods.
+///
+/// By default, unmatched paths and guard rejections result in empty `404 Not Found` responses and
+/// unmatched methods result in empty `405 Method Not Allowed` responses. Guard rejections are
+/// rendered using the not-found renderer unless a [specific one](Self::guard_rejected) is set, so
+/// that the existence of guarded resources is not revealed.
+///
+/// Actix Web does not expose the methods a resource accepts to its default service, so renderers
+/// of 405 responses should set an `Allow` header themselves where one is needed.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header, web, App, HttpResponse};
+/// use actix_web_lab::web as web_lab;
+///
+/// let fallback = web_lab::fallback()
+///     .not_found(|req| async move {
+///         HttpResponse::NotFound().body(format!("nothing at {}", req.path()))
+///     })
+///     .method_not_allowed(|_req| async {
+///         HttpResponse::MethodNotAllowed()
+///             .insert_header((header::ALLOW, "GET"))
+///             .finish()
+///     });
+///
+/// App::new()
+///     .service(
+///         web::resource("/items")
+///             .route(web::get().to(HttpResponse::Ok))
+///             .default_service(fallback.clone()),
+///     )
+///     .default_service(fallback)
+/// # ;
+/// ```
+#[derive(Clone)]
+pub struct Fallback {
+    not_found: RenderFn,
+    method_not_allowed: RenderFn,
+    guard_rejected: Option<RenderFn>,
+}
+
+impl Fallback {
+    /// Constructs a new fallback service with default renderers.
+    pub fn new() -> Self {
+        Self {
+            not_found: render_fn(|_| async { HttpResponse::NotFound().finish() }),
+            method_not_allowed: render_fn(|_| async { HttpResponse::MethodNotAllowed().finish() }),
+            guard_rejected: None,
+        }
+    }
+
+    /// Sets renderer for requests whose path does not match any resource.
+    pub fn not_found<F, Fut>(mut self, render: F) -> Self
+    where
+        F: Fn(HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = HttpResponse> + 'static,
+    {
+        self.not_found = render_fn(render);
+        self
+    }
+
+    /// Sets renderer for requests whose method is not accepted by the matched resource.
+    pub fn method_not_allowed<F, Fut>(mut self, render: F) -> Self
+    where
+        F: Fn(HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = HttpResponse> + 'static,
+    {
+        self.method_not_allowed = render_fn(render);
+        self
+    }
+
+    /// Sets renderer for requests rejected by the guards of a resource matching their path.
+    pub fn guard_rejected<F, Fut>(mut self, render: F) -> Self
+    where
+        F: Fn(HttpRequest) -> Fut + 'static,
+        Fut: Future<Output = HttpResponse> + 'static,
+    {
+        self.guard_rejected = Some(render_fn(render));
+        self
+    }
+
+    fn renderer(&self, kind: FallbackKind) -> &RenderFn {
+        match kind {
+            FallbackKind::NotFound => &self.not_found,
+            FallbackKind::MethodNotAllowed => &self.method_not_allowed,
+            FallbackKind::GuardRejected => self.guard_rejected.as_ref().unwrap_or(&self.not_found),
+        }
+    }
+}
+
+impl Default for Fallback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Fallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallback")
+            .field("guard_rejected", &self.guard_rejected.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+fn render_fn<F, Fut>(render: F) -> RenderFn
+where
+    F: Fn(HttpRequest) -> Fut + 'static,
+    Fut: Future<Output = HttpResponse> + 'static,
+{
+    Rc::new(move |req| Box::pin(render(req)))
+}
+
+impl ServiceFactory<ServiceRequest> for Fallback {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = FallbackService;
+    type InitError = ();
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ready(Ok(FallbackService {
+            fallback: self.clone(),
+        }))
+    }
+}
+
+/// Service for [`Fallback`].
+pub struct FallbackService {
+    fallback: Fallback,
+}
+
+impl Service<ServiceRequest> for FallbackService {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    always_ready!();
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let (req, _) = req.into_parts();
+        let render = Rc::clone(self.fallback.renderer(FallbackKind::of(&req)));
+
+        Box::pin(async move {
+            let res = render(req.clone()).await;
+            Ok(ServiceResponse::new(req, res))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        guard,
+        http::{Method, StatusCode},
+        test, web, App,
+    };
+
+    use super::*;
+
+    fn labelled(
+        kind: &'static str,
+        status: StatusCode,
+    ) -> impl Fn(HttpRequest) -> Ready<HttpResponse> {
+        move |_| ready(HttpResponse::build(status).body(kind))
+    }
+
+    #[actix_web::test]
+    async fn classifies_requests() {
+        let fallback = Fallback::new()
+            .not_found(labelled("not found", StatusCode::NOT_FOUND))
+            .method_not_allowed(labelled("method", StatusCode::METHOD_NOT_ALLOWED))
+            .guard_rejected(labelled("guard", StatusCode::NOT_FOUND));
+
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::resource("/items")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .default_service(fallback.clone()),
+                )
+                .service(
+                    web::resource("/admin")
+                        .guard(guard::Header("x-admin", "1"))
+                        .to(HttpResponse::Ok),
+                )
+                .default_service(fallback),
+        )
+        .await;
+
+        let cases = [
+            (Method::GET, "/items", "", StatusCode::OK),
+            (
+                Method::POST,
+                "/items",
+                "method",
+                StatusCode::METHOD_NOT_ALLOWED,
+            ),
+            (Method::GET, "/admin", "guard", StatusCode::NOT_FOUND),
+            (Method::GET, "/nope", "not found", StatusCode::NOT_FOUND),
+        ];
+
+        for (method, path, body, status) in cases {
+            let req = test::TestRequest::with_uri(path)
+                .method(method)
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), status, "{path}");
+            assert_eq!(test::read_body(res).await, body, "{path}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn guard_rejections_render_not_found_by_default() {
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::resource("/admin")
+                        .guard(guard::Header("x-admin", "1"))
+                        .to(HttpResponse::Ok),
+                )
+                .default_service(
+                    Fallback::new().not_found(labelled("not found", StatusCode::NOT_FOUND)),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::with_uri("/admin").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(test::read_body(res).await, "not found");
+    }
+}
@@ -0,0 +1,321 @@
+//! Forwarding headers for proxied requests.
+//!
+//! See [`ForwardHeaders`] docs.
+
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::{
+    http::header::{self, HeaderMap, HeaderName, HeaderValue},
+    HttpRequest,
+};
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// How the client is identified in forwarding headers.
+///
+/// See [RFC 7239 §6](https://datatracker.ietf.org/doc/html/rfc7239#section-6).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum NodeIdentifier {
+    /// The peer's IP address.
+    #[default]
+    Ip,
+
+    /// The peer's IP address and port.
+    IpAndPort,
+
+    /// The `unknown` identifier, which hides the peer's address.
+    Unknown,
+
+    /// An obfuscated identifier, such as `_hidden`, which hides the peer's address.
+    ///
+    /// Obfuscated identifiers must start with an underscore and contain only ASCII alphanumerics,
+    /// `.`, `_`, and `-`.
+    Obfuscated(String),
+}
+
+impl NodeIdentifier {
+    fn identify(&self, req: &HttpRequest) -> String {
+        let peer = req.peer_addr();
+
+        match (self, peer) {
+            (Self::Ip, Some(SocketAddr::V4(peer))) => peer.ip().to_string(),
+            (Self::Ip, Some(SocketAddr::V6(peer))) => format!("[{}]", peer.ip()),
+            (Self::IpAndPort, Some(peer)) => peer.to_string(),
+            (Self::Ip | Self::IpAndPort | Self::Unknown, _) => "unknown".to_owned(),
+            (Self::Obfuscated(ident), _) => ident.clone(),
+        }
+    }
+}
+
+/// Builds forwarding headers for requests sent on to upstream servers by proxy handlers.
+///
+/// The current hop is appended to any `Forwarded` and `X-Forwarded-For` headers on the incoming
+/// request, so the upstream server sees the full chain. `X-Forwarded-Proto` and `X-Forwarded-Host`
+/// are kept if present, since they describe the original request, and are otherwise set from the
+/// incoming request.
+///
+/// The peer is identified using its IP address by default. Use
+/// [`identify_for()`](Self::identify_for) to obfuscate it, for example when the upstream server is
+/// not trusted with client addresses.
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "awc")] {
+/// use actix_web::{web, HttpRequest, HttpResponse};
+/// use actix_web_lab::util::{ForwardHeaders, NodeIdentifier};
+///
+/// async fn proxy(req: HttpRequest, client: web::Data<awc::Client>) -> HttpResponse {
+///     let forward = ForwardHeaders::new()
+///         .identify_for(NodeIdentifier::Obfuscated("_client".to_owned()))
+///         .by("_gateway");
+///
+///     let upstream = forward.apply(&req, client.get("http://upstream.internal/"));
+///
+///     // send request and stream response back to the client ...
+///     # let _ = upstream;
+///     # HttpResponse::Ok().finish()
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ForwardHeaders {
+    for_ident: NodeIdentifier,
+    by: Option<String>,
+}
+
+impl ForwardHeaders {
+    /// Constructs new forwarding header builder which identifies peers by IP address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the peer is identified in `Forwarded` and `X-Forwarded-For` headers.
+    ///
+    /// # Panics
+    /// Panics if an obfuscated identifier is invalid.
+    pub fn identify_for(mut self, ident: NodeIdentifier) -> Self {
+        if let NodeIdentifier::Obfuscated(ident) = &ident {
+            assert_obfuscated(ident);
+        }
+
+        self.for_ident = ident;
+        self
+    }
+
+    /// Sets the `by` identifier of this proxy in `Forwarded` headers.
+    ///
+    /// # Panics
+    /// Panics if the identifier is neither an IP address nor a valid obfuscated identifier.
+    pub fn by(mut self, ident: impl Into<String>) -> Self {
+        let ident = ident.into();
+
+        if ident.parse::<IpAddr>().is_err() {
+            assert_obfuscated(&ident);
+        }
+
+        self.by = Some(ident);
+        self
+    }
+
+    /// Returns forwarding headers for a request proxying `req`.
+    pub fn headers(&self, req: &HttpRequest) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        let r#for = self.for_ident.identify(req);
+        let proto = if req.app_config().secure() {
+            "https"
+        } else {
+            "http"
+        };
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(str::to_owned)
+            .or_else(|| req.uri().authority().map(|authority| authority.to_string()));
+
+        let mut hop = self
+            .by
+            .iter()
+            .map(|by| format!("by=\"{by}\""))
+            .chain([format!("for=\"{for}\"")])
+            .chain(host.iter().map(|host| format!("host=\"{host}\"")))
+            .chain([format!("proto=\"{proto}\"")])
+            .collect::<Vec<_>>()
+            .join(";");
+
+        if let Some(prev) = joined(req, &header::FORWARDED) {
+            hop = format!("{prev}, {hop}");
+        }
+        insert(&mut headers, header::FORWARDED, hop);
+
+        // X-Forwarded-For uses bare IP addresses, without ports or IPv6 brackets
+        let xff_for = match (&self.for_ident, req.peer_addr()) {
+            (NodeIdentifier::Ip | NodeIdentifier::IpAndPort, Some(peer)) => peer.ip().to_string(),
+            _ => r#for.clone(),
+        };
+        let xff = match joined(req, &X_FORWARDED_FOR) {
+            Some(prev) => format!("{prev}, {xff_for}"),
+            None => xff_for,
+        };
+        insert(&mut headers, X_FORWARDED_FOR, xff);
+
+        let proto = joined(req, &X_FORWARDED_PROTO).unwrap_or_else(|| proto.to_owned());
+        insert(&mut headers, X_FORWARDED_PROTO, proto);
+
+        if let Some(host) = joined(req, &X_FORWARDED_HOST).or(host) {
+            insert(&mut headers, X_FORWARDED_HOST, host);
+        }
+
+        headers
+    }
+
+    /// Inserts forwarding headers for a request proxying `req` into an outgoing `awc` request.
+    ///
+    /// Any forwarding headers already set on the outgoing request are replaced.
+    #[cfg(feature = "awc")]
+    pub fn apply(
+        &self,
+        req: &HttpRequest,
+        mut client_req: awc::ClientRequest,
+    ) -> awc::ClientRequest {
+        for (name, value) in self.headers(req) {
+            client_req.headers_mut().insert(name, value);
+        }
+
+        client_req
+    }
+}
+
+/// Returns all values of header `name` joined with commas, if there are any.
+fn joined(req: &HttpRequest, name: &HeaderName) -> Option<String> {
+    let values = req
+        .headers()
+        .get_all(name)
+        .filter_map(|val| val.to_str().ok())
+        .map(str::trim)
+        .filter(|val| !val.is_empty())
+        .collect::<Vec<_>>();
+
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+fn insert(headers: &mut HeaderMap, name: HeaderName, value: String) {
+    // values are built from valid header values and identifiers so are always valid
+    if let Ok(value) = HeaderValue::try_from(value) {
+        headers.insert(name, value);
+    }
+}
+
+fn assert_obfuscated(ident: &str) {
+    let valid = ident.len() > 1
+        && ident.starts_with('_')
+        && ident
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'));
+
+    assert!(valid, "`{ident}` is not a valid obfuscated identifier");
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn first_hop() {
+        let req = TestRequest::default()
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .insert_header(("host", "example.com"))
+            .to_http_request();
+
+        let headers = ForwardHeaders::new().headers(&req);
+        assert_eq!(
+            header(&headers, "forwarded"),
+            r#"for="192.0.2.1";host="example.com";proto="http""#,
+        );
+        assert_eq!(header(&headers, "x-forwarded-for"), "192.0.2.1");
+        assert_eq!(header(&headers, "x-forwarded-proto"), "http");
+        assert_eq!(header(&headers, "x-forwarded-host"), "example.com");
+    }
+
+    #[test]
+    fn appends_hop() {
+        let req = TestRequest::default()
+            .peer_addr("[2001:db8::1]:1234".parse().unwrap())
+            .insert_header(("host", "internal"))
+            .insert_header(("forwarded", "for=192.0.2.1;proto=https"))
+            .insert_header(("x-forwarded-for", "192.0.2.1"))
+            .insert_header(("x-forwarded-proto", "https"))
+            .insert_header(("x-forwarded-host", "example.com"))
+            .to_http_request();
+
+        let headers = ForwardHeaders::new()
+            .identify_for(NodeIdentifier::IpAndPort)
+            .by("_gateway")
+            .headers(&req);
+        assert_eq!(
+            header(&headers, "forwarded"),
+            concat!(
+                r#"for=192.0.2.1;proto=https, "#,
+                r#"by="_gateway";for="[2001:db8::1]:1234";host="internal";proto="http""#,
+            ),
+        );
+        assert_eq!(
+            header(&headers, "x-forwarded-for"),
+            "192.0.2.1, 2001:db8::1"
+        );
+        assert_eq!(header(&headers, "x-forwarded-proto"), "https");
+        assert_eq!(header(&headers, "x-forwarded-host"), "example.com");
+    }
+
+    #[test]
+    fn obfuscated_identifiers() {
+        let req = TestRequest::default()
+            .peer_addr("192.0.2.1:1234".parse().unwrap())
+            .to_http_request();
+
+        let headers = ForwardHeaders::new()
+            .identify_for(NodeIdentifier::Obfuscated("_hidden".to_owned()))
+            .headers(&req);
+        assert_eq!(
+            header(&headers, "forwarded"),
+            r#"for="_hidden";proto="http""#
+        );
+        assert_eq!(header(&headers, "x-forwarded-for"), "_hidden");
+        assert!(headers.get("x-forwarded-host").is_none());
+
+        let headers = ForwardHeaders::new()
+            .identify_for(NodeIdentifier::Unknown)
+            .headers(&req);
+        assert_eq!(header(&headers, "x-forwarded-for"), "unknown");
+    }
+
+    #[test]
+    fn ipv6_identifiers() {
+        let req = TestRequest::default()
+            .peer_addr("[2001:db8::1]:1234".parse().unwrap())
+            .to_http_request();
+
+        let headers = ForwardHeaders::new().headers(&req);
+        assert_eq!(
+            header(&headers, "forwarded"),
+            r#"for="[2001:db8::1]";proto="http""#
+        );
+        assert_eq!(header(&headers, "x-forwarded-for"), "2001:db8::1");
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_obfuscated_identifier() {
+        let _ = ForwardHeaders::new().identify_for(NodeIdentifier::Obfuscated("hidden".to_owned()));
+    }
+}
@@ -1,3 +1,5 @@
 //! Experimental route guards.
 //!
 //! Analogous to the `guard` module in Actix Web.
+
+pub use crate::canary::CanaryVariant;
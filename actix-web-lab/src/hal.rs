@@ -0,0 +1,510 @@
+//! HAL hypermedia responders.
+//!
+//! See [`Hal`] and [`HalCollection`] for docs.
+
+use actix_web::{
+    body::BoxBody,
+    http::header::{self, HeaderValue},
+    HttpRequest, HttpResponse, Responder,
+};
+use derive_more::Display;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::url_for::{LabUrl, UrlForError};
+
+/// Media type of HAL documents.
+const HAL_JSON: &str = "application/hal+json";
+
+#[derive(Debug, Clone)]
+enum Href {
+    Route(LabUrl),
+    Raw(String),
+}
+
+#[derive(Debug, Clone)]
+struct Links {
+    links: Vec<(String, Href)>,
+    absolute: bool,
+}
+
+impl Links {
+    fn new() -> Self {
+        Self {
+            links: Vec::new(),
+            absolute: false,
+        }
+    }
+
+    fn push(&mut self, rel: String, href: Href) {
+        self.links.push((rel, href));
+    }
+
+    fn to_value(&self, req: &HttpRequest) -> Result<Value, UrlForError> {
+        let mut links = Map::new();
+
+        for (rel, href) in &self.links {
+            let href = match href {
+                Href::Route(url) if self.absolute => url.absolute(req)?,
+                Href::Route(url) => url.relative(req)?,
+                Href::Raw(href) => href.clone(),
+            };
+
+            let link = serde_json::json!({ "href": href });
+
+            // repeated relations are serialized as an array of link objects
+            match links.get_mut(rel) {
+                None => {
+                    links.insert(rel.clone(), link);
+                }
+                Some(Value::Array(arr)) => arr.push(link),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), link]),
+            }
+        }
+
+        Ok(Value::Object(links))
+    }
+}
+
+/// A [HAL] resource responder.
+///
+/// Serializes the inner resource as JSON, with a `_links` member containing links generated from
+/// [`LabUrl`] builders when responding. The resource must serialize to a JSON object. Responses
+/// have a `Content-Type` of `application/hal+json`.
+///
+/// Links are relative unless [`absolute_links()`](Self::absolute_links) is called. If a link can
+/// not be generated, such as when its route is not in the [`RouteTable`](crate::web::RouteTable),
+/// a `500 Internal Server Error` response is returned instead.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::{
+///     respond::Hal,
+///     web::{LabUrl, RouteTable},
+/// };
+/// use derive_more::Display;
+use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     id: u64,
+///     name: String,
+/// }
+///
+/// async fn user(id: web::Path<u64>) -> Hal<User> {
+///     let id = id.into_inner();
+///
+///     Hal::new(User { id, name: "Ferris".to_owned() })
+///         .link("self", LabUrl::for_route("user_detail").param("id", id))
+///         .link("collection", LabUrl::for_route("users"))
+/// }
+///
+/// let routes = RouteTable::new()
+///     .route("users", "/users")
+///     .route("user_detail", "/users/{id}");
+///
+/// App::new()
+///     .app_data(routes.clone())
+///     .service(routes.resource("user_detail").to(user))
+/// # ;
+/// ```
+///
+/// [HAL]: https://datatracker.ietf.org/doc/html/draft-kelly-json-hal
+#[derive(Debug, Clone)]
+pub struct Hal<T> {
+    resource: T,
+    links: Links,
+}
+
+impl<T> Hal<T> {
+    /// Constructs new HAL responder for `resource`, with no links.
+    pub fn new(resource: T) -> Self {
+        Self {
+            resource,
+            links: Links::new(),
+        }
+    }
+
+    /// Adds link to a named route.
+    ///
+    /// Adding multiple links with the same relation results in an array of links.
+    pub fn link(mut self, rel: impl Into<String>, url: LabUrl) -> Self {
+        self.links.push(rel.into(), Href::Route(url));
+        self
+    }
+
+    /// Adds link with a literal `href`, such as one to an external resource.
+    pub fn link_href(mut self, rel: impl Into<String>, href: impl Into<String>) -> Self {
+        self.links.push(rel.into(), Href::Raw(href.into()));
+        self
+    }
+
+    /// Generates absolute links, using the request's scheme and host, instead of relative ones.
+    pub fn absolute_links(mut self) -> Self {
+        self.links.absolute = true;
+        self
+    }
+
+    /// Returns a reference to the inner resource.
+    pub fn resource(&self) -> &T {
+        &self.resource
+    }
+
+    /// Unwraps into inner resource.
+    pub fn into_inner(self) -> T {
+        self.resource
+    }
+}
+
+impl<T: Serialize> Hal<T> {
+    fn to_document(&self, req: &HttpRequest, absolute: bool) -> Result<Value, HalError> {
+        let mut doc = match serde_json::to_value(&self.resource).map_err(HalError::Serialize)? {
+            Value::Object(doc) => doc,
+            _ => return Err(HalError::NotAnObject),
+        };
+
+        let links = Links {
+            absolute: absolute || self.links.absolute,
+            ..self.links.clone()
+        };
+
+        doc.insert("_links".to_owned(), links.to_value(req)?);
+
+        Ok(Value::Object(doc))
+    }
+}
+
+impl<T> From<T> for Hal<T> {
+    fn from(resource: T) -> Self {
+        Self::new(resource)
+    }
+}
+
+impl<T: Serialize> Responder for Hal<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        hal_response(req, self.to_document(req, false))
+    }
+}
+
+/// A [HAL] collection responder with optional pagination links.
+///
+/// Items are embedded in the `_embedded` member under the given relation. Items can be plain
+/// serializable values or [`Hal`] resources with their own links.
+///
+/// When [paginated](Self::paginate), `self`, `first`, `last`, and, where applicable, `prev` and
+/// `next` links are added using `page` and `per_page` query parameters, and the `page`,
+/// `per_page`, and `total` members are included in the document.
+///
+/// # Examples
+/// ```
+/// use actix_web::web;
+/// use actix_web_lab::{
+///     respond::{Hal, HalCollection},
+///     web::LabUrl,
+/// };
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     id: u64,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Page {
+///     page: u64,
+/// }
+///
+/// async fn users(page: web::Query<Page>) -> HalCollection<User> {
+///     let users = (1..=10).map(|id| {
+///         Hal::new(User { id }).link("self", LabUrl::for_route("user_detail").param("id", id))
+///     });
+///
+///     HalCollection::new("users", users).paginate(LabUrl::for_route("users"), page.page, 10, 95)
+/// }
+/// ```
+///
+/// [HAL]: https://datatracker.ietf.org/doc/html/draft-kelly-json-hal
+#[derive(Debug, Clone)]
+pub struct HalCollection<T> {
+    rel: String,
+    items: Vec<Hal<T>>,
+    links: Links,
+    pagination: Option<Pagination>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pagination {
+    page: u64,
+    per_page: u64,
+    total: u64,
+}
+
+impl<T> HalCollection<T> {
+    /// Constructs new HAL collection responder with items embedded under `rel`.
+    ///
+    /// Plain resources can be embedded without links by mapping them with [`Hal::new`].
+    pub fn new(rel: impl Into<String>, items: impl IntoIterator<Item = Hal<T>>) -> Self {
+        Self {
+            rel: rel.into(),
+            items: items.into_iter().collect(),
+            links: Links::new(),
+            pagination: None,
+        }
+    }
+
+    /// Adds link to a named route.
+    pub fn link(mut self, rel: impl Into<String>, url: LabUrl) -> Self {
+        self.links.push(rel.into(), Href::Route(url));
+        self
+    }
+
+    /// Adds link with a literal `href`.
+    pub fn link_href(mut self, rel: impl Into<String>, href: impl Into<String>) -> Self {
+        self.links.push(rel.into(), Href::Raw(href.into()));
+        self
+    }
+
+    /// Adds pagination links and members.
+    ///
+    /// `url` is the collection's URL, to which the `page` and `per_page` query parameters are
+    /// appended. Pages are numbered from 1; a `page` of 0 is treated as 1.
+    ///
+    /// # Panics
+    /// Panics if `per_page` is 0.
+    pub fn paginate(mut self, url: LabUrl, page: u64, per_page: u64, total: u64) -> Self {
+        assert!(per_page > 0, "per_page must be greater than 0");
+
+        let page = page.max(1);
+        let last = (total / per_page + u64::from(total % per_page != 0)).max(1);
+
+        let page_url =
+            |page: u64| Href::Route(url.clone().query("page", page).query("per_page", per_page));
+
+        self.links.push("self".to_owned(), page_url(page));
+        self.links.push("first".to_owned(), page_url(1));
+
+        if page > 1 {
+            self.links
+                .push("prev".to_owned(), page_url((page - 1).min(last)));
+        }
+
+        if page < last {
+            self.links.push("next".to_owned(), page_url(page + 1));
+        }
+
+        self.links.push("last".to_owned(), page_url(last));
+
+        self.pagination = Some(Pagination {
+            page,
+            per_page,
+            total,
+        });
+
+        self
+    }
+
+    /// Generates absolute links, for the collection and its items, using the request's scheme and
+    /// host instead of relative ones.
+    pub fn absolute_links(mut self) -> Self {
+        self.links.absolute = true;
+        self
+    }
+}
+
+impl<T: Serialize> HalCollection<T> {
+    fn to_document(&self, req: &HttpRequest) -> Result<Value, HalError> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| item.to_document(req, self.links.absolute))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut doc = Map::new();
+
+        if let Some(Pagination {
+            page,
+            per_page,
+            total,
+        }) = self.pagination
+        {
+            doc.insert("page".to_owned(), page.into());
+            doc.insert("per_page".to_owned(), per_page.into());
+            doc.insert("total".to_owned(), total.into());
+        }
+
+        doc.insert("_links".to_owned(), self.links.to_value(req)?);
+
+        let mut embedded = Map::new();
+        embedded.insert(self.rel.clone(), Value::Array(items));
+        doc.insert("_embedded".to_owned(), Value::Object(embedded));
+
+        Ok(Value::Object(doc))
+    }
+}
+
+impl<T: Serialize> Responder for HalCollection<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        hal_response(req, self.to_document(req))
+    }
+}
+
+#[derive(Debug, Display)]
+enum HalError {
+    #[display(fmt = "resource could not be serialized: {_0}")]
+    Serialize(serde_json::Error),
+
+    #[display(fmt = "resource does not serialize to a JSON object")]
+    NotAnObject,
+
+    #[display(fmt = "link could not be generated: {_0}")]
+    Link(UrlForError),
+}
+
+impl From<UrlForError> for HalError {
+    fn from(err: UrlForError) -> Self {
+        Self::Link(err)
+    }
+}
+
+fn hal_response(req: &HttpRequest, doc: Result<Value, HalError>) -> HttpResponse {
+    match doc {
+        Ok(doc) => {
+            let mut res = HttpResponse::Ok().json(doc);
+            res.headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(HAL_JSON));
+            res
+        }
+
+        Err(err) => {
+            debug!(
+                "Failed to build HAL document in `{}` handler: {err}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            match err {
+                HalError::Link(err) => HttpResponse::from_error(err),
+                _ => HttpResponse::InternalServerError().finish(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::StatusCode, test::TestRequest};
+    use serde_json::json;
+
+    use super::*;
+    use crate::url_for::RouteTable;
+
+    #[derive(Debug, Clone, Serialize)]
+    struct User {
+        id: u64,
+    }
+
+    fn routes() -> RouteTable {
+        RouteTable::new()
+            .route("users", "/users")
+            .route("user_detail", "/users/{id}")
+    }
+
+    async fn document(res: HttpResponse) -> Value {
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/hal+json",
+        );
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn resource_links() {
+        let req = TestRequest::default()
+            .app_data(routes())
+            .insert_header(("host", "example.com"))
+            .to_http_request();
+
+        let hal = Hal::new(User { id: 1 })
+            .link("self", LabUrl::for_route("user_detail").param("id", 1))
+            .link("related", LabUrl::for_route("users"))
+            .link_href("related", "https://example.org/friends");
+
+        let doc = document(hal.clone().respond_to(&req)).await;
+        assert_eq!(
+            doc,
+            json!({
+                "id": 1,
+                "_links": {
+                    "self": { "href": "/users/1" },
+                    "related": [
+                        { "href": "/users" },
+                        { "href": "https://example.org/friends" },
+                    ],
+                },
+            }),
+        );
+
+        let doc = document(hal.absolute_links().respond_to(&req)).await;
+        assert_eq!(doc["_links"]["self"]["href"], "http://example.com/users/1");
+    }
+
+    #[actix_web::test]
+    async fn collection_pagination() {
+        let req = TestRequest::default().app_data(routes()).to_http_request();
+
+        let items = (1..=2).map(|id| {
+            Hal::new(User { id }).link("self", LabUrl::for_route("user_detail").param("id", id))
+        });
+
+        let res = HalCollection::new("users", items)
+            .paginate(LabUrl::for_route("users"), 2, 2, 5)
+            .respond_to(&req);
+        let doc = document(res).await;
+
+        assert_eq!(doc["total"], 5);
+        assert_eq!(doc["page"], 2);
+        assert_eq!(
+            doc["_links"],
+            json!({
+                "self": { "href": "/users?page=2&per_page=2" },
+                "first": { "href": "/users?page=1&per_page=2" },
+                "prev": { "href": "/users?page=1&per_page=2" },
+                "next": { "href": "/users?page=3&per_page=2" },
+                "last": { "href": "/users?page=3&per_page=2" },
+            }),
+        );
+        assert_eq!(
+            doc["_embedded"]["users"][1]["_links"]["self"]["href"],
+            "/users/2"
+        );
+
+        let res = HalCollection::new("users", Vec::<Hal<User>>::new())
+            .paginate(LabUrl::for_route("users"), 1, 10, 0)
+            .respond_to(&req);
+        let doc = document(res).await;
+        assert!(doc["_links"].get("prev").is_none());
+        assert!(doc["_links"].get("next").is_none());
+        assert_eq!(doc["_links"]["last"]["href"], "/users?page=1&per_page=10");
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let req = TestRequest::default().app_data(routes()).to_http_request();
+
+        let res = Hal::new(User { id: 1 })
+            .link("self", LabUrl::for_route("nope"))
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let res = Hal::new(vec![1, 2, 3]).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -4,6 +4,7 @@
 pub(crate) use self::header_test_helpers::{assert_parse_eq, assert_parse_fail};
 pub use crate::{
     cache_control::{CacheControl, CacheDirective},
+    content_digest::{ContentDigest, DigestAlgorithm, CONTENT_DIGEST},
     content_length::ContentLength,
     forwarded::Forwarded,
     strict_transport_security::StrictTransportSecurity,
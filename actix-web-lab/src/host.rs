@@ -1,11 +1,51 @@
+//! Effective host extractor.
+//!
+//! See [`Host`] docs.
+
 use std::convert::Infallible;
 
 use actix_utils::future::{ok, Ready};
-use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use actix_web::{
+    dev::Payload,
+    http::header::{self, Header as _},
+    web, FromRequest, HttpRequest,
+};
+
+use crate::{header::Forwarded, util::TrustedProxies};
 
-/// Host information.
+/// Effective host of the request.
+///
+/// # Extractor
+/// The host is resolved from, in order:
+/// 1. the `host` parameter of the `Forwarded` header;
+/// 1. the first value of the `X-Forwarded-Host` header;
+/// 1. the `Host` header, or the URI authority for HTTP/2 requests;
+/// 1. the app's configured [host](actix_web::dev::AppConfig::host).
+///
+/// The forwarding headers are only used if they are trusted according to the [`HostConfig`]
+/// registered as app data. Without a registered config, they are always trusted, matching
+/// [`ConnectionInfo::host()`](actix_web::dev::ConnectionInfo::host).
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::{
+///     extract::{Host, HostConfig},
+///     util::TrustedProxies,
+/// };
 ///
-/// See [`ConnectionInfo::host()`](actix_web::dev::ConnectionInfo::host) for more.
+/// async fn tenant(host: Host) -> String {
+///     let tenant = host.as_ref().split('.').next().unwrap_or_default();
+///     format!("tenant: {tenant}")
+/// }
+///
+/// let proxies = TrustedProxies::parse(["10.0.0.1"]).unwrap();
+///
+/// App::new()
+///     .app_data(HostConfig::trust_proxies(proxies))
+///     .route("/", web::get().to(tenant))
+/// # ;
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Host(String);
 
@@ -26,10 +66,96 @@ impl FromRequest for Host {
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        ok(Host(req.connection_info().host().to_owned()))
+        ok(Host(HostConfig::from_req(req).resolve(req)))
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Trust {
+    All,
+    Proxies(TrustedProxies),
+}
+
+/// Trust policy for forwarding headers used by the [`Host`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostConfig {
+    trust: Trust,
+}
+
+const DEFAULT_CONFIG: HostConfig = HostConfig { trust: Trust::All };
+
+impl HostConfig {
+    /// Trusts forwarding headers on all requests.
+    ///
+    /// Only use this when the app can not be reached without going through a proxy that
+    /// overwrites these headers, otherwise clients can choose the resolved host.
+    pub fn trust_all() -> Self {
+        DEFAULT_CONFIG
+    }
+
+    /// Ignores forwarding headers on all requests.
+    pub fn trust_none() -> Self {
+        Self::trust_proxies(TrustedProxies::new())
+    }
+
+    /// Trusts forwarding headers only on requests from the given proxies.
+    pub fn trust_proxies(proxies: TrustedProxies) -> Self {
+        Self {
+            trust: Trust::Proxies(proxies),
+        }
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn is_trusted(&self, req: &HttpRequest) -> bool {
+        match &self.trust {
+            Trust::All => true,
+            Trust::Proxies(proxies) => req
+                .peer_addr()
+                .is_some_and(|peer| proxies.contains(peer.ip())),
+        }
+    }
+
+    fn resolve(&self, req: &HttpRequest) -> String {
+        let forwarded = if self.is_trusted(req) {
+            forwarded_host(req)
+        } else {
+            None
+        };
+
+        forwarded
+            .or_else(|| {
+                req.headers()
+                    .get(header::HOST)
+                    .and_then(|host| host.to_str().ok())
+                    .map(str::to_owned)
+            })
+            .or_else(|| req.uri().authority().map(|authority| authority.to_string()))
+            .unwrap_or_else(|| req.app_config().host().to_owned())
+    }
+}
+
+/// Returns host from `Forwarded` or `X-Forwarded-Host` headers, if present.
+fn forwarded_host(req: &HttpRequest) -> Option<String> {
+    Forwarded::parse(req)
+        .ok()
+        .and_then(|fwd| fwd.host().map(str::to_owned))
+        .or_else(|| {
+            req.headers()
+                .get("x-forwarded-host")
+                .and_then(|val| val.to_str().ok())
+                .and_then(|val| val.split(',').next())
+                .map(|host| host.trim().to_owned())
+        })
+        .filter(|host| !host.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use actix_web::{
@@ -65,4 +191,42 @@ mod tests {
         assert_eq!(res.status(), StatusCode::OK);
         assert_eq!(test::read_body(res).await, b"localhost:8080".as_ref());
     }
+
+    #[test]
+    fn trust_policy() {
+        let proxy = "10.0.0.1:1234".parse().unwrap();
+        let client = "192.0.2.1:1234".parse().unwrap();
+
+        let forwarded = |config: HostConfig, peer| {
+            let req = TestRequest::default()
+                .app_data(config)
+                .peer_addr(peer)
+                .insert_header(("host", "internal"))
+                .insert_header(("forwarded", "for=192.0.2.1; host=forwarded.com"))
+                .insert_header(("x-forwarded-host", "x-forwarded.com, other.com"))
+                .to_http_request();
+
+            Host::extract(&req).into_inner().unwrap().into_inner()
+        };
+
+        let x_forwarded = |config: HostConfig, peer| {
+            let req = TestRequest::default()
+                .app_data(web::Data::new(config))
+                .peer_addr(peer)
+                .insert_header(("host", "internal"))
+                .insert_header(("x-forwarded-host", "x-forwarded.com, other.com"))
+                .to_http_request();
+
+            Host::extract(&req).into_inner().unwrap().into_inner()
+        };
+
+        assert_eq!(forwarded(HostConfig::trust_all(), client), "forwarded.com");
+        assert_eq!(forwarded(HostConfig::trust_none(), proxy), "internal");
+
+        let trusted = HostConfig::trust_proxies(TrustedProxies::from_iter([proxy.ip()]));
+        assert_eq!(forwarded(trusted.clone(), proxy), "forwarded.com");
+        assert_eq!(forwarded(trusted.clone(), client), "internal");
+        assert_eq!(x_forwarded(trusted.clone(), proxy), "x-forwarded.com");
+        assert_eq!(x_forwarded(trusted, client), "internal");
+    }
 }
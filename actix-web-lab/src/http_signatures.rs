@@ -0,0 +1,838 @@
+//! HTTP Message Signatures (RFC 9421) verification middleware.
+//!
+//! See [`HttpSignatures`] docs.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_http::Request;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode, Uri,
+    },
+    ResponseError,
+};
+use async_trait::async_trait;
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+use hmac::{Mac as _, SimpleHmac};
+use sha2::Sha256;
+use tracing::debug;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    structured_field::{self, param, BareItem, Item, Member, Params},
+};
+
+const SIGNATURE: HeaderName = HeaderName::from_static("signature");
+const SIGNATURE_INPUT: HeaderName = HeaderName::from_static("signature-input");
+
+/// Default maximum age of signatures.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// How far in the future signature creation times may be, to account for clock skew.
+const CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// A middleware that verifies HTTP message signatures, as defined in RFC 9421.
+///
+/// Requests must include `Signature-Input` and `Signature` headers. Each signature is checked by
+/// reconstructing its signature base from the covered components of the request and verifying it
+/// using the key returned by the [`SignatureKeyResolver`] for the signature's `keyid` parameter.
+/// Requests are only passed to the wrapped service if every signature verifies; otherwise, they
+/// are rejected with an [`HttpSignatureError`] response.
+///
+/// The derived components `@method`, `@target-uri`, `@authority`, `@scheme`, `@request-target`,
+/// `@path`, and `@query` are supported, as are header fields without parameters. The request body
+/// is not covered directly; cover the `Content-Digest` header for that.
+///
+/// By default, signatures must cover `@method` and `@path`, and must have been created within the
+/// last 5 minutes.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashMap;
+///
+/// use actix_web::App;
+/// use actix_web_lab::middleware::{HttpSignatureKey, HttpSignatures};
+///
+/// let keys = HashMap::from([(
+///     "client-1".to_owned(),
+///     HttpSignatureKey::HmacSha256(b"shared secret".to_vec()),
+/// )]);
+///
+/// App::new().wrap(
+///     HttpSignatures::new(keys).required_components(["@method", "@target-uri", "content-digest"]),
+/// )
+/// # ;
+/// ```
+pub struct HttpSignatures<R> {
+    resolver: Rc<R>,
+    required_components: Vec<String>,
+    max_age: Option<Duration>,
+    label: Option<String>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: SignatureKeyResolver> HttpSignatures<R> {
+    /// Constructs new signature verification middleware which looks up keys using `resolver`.
+    pub fn new(resolver: R) -> Self {
+        Self {
+            resolver: Rc::new(resolver),
+            required_components: vec!["@method".to_owned(), "@path".to_owned()],
+            max_age: Some(DEFAULT_MAX_AGE),
+            label: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets components which every signature must cover.
+    ///
+    /// Defaults to `@method` and `@path`.
+    pub fn required_components<I, S>(mut self, components: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.required_components = components.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets maximum age of signatures, measured from their `created` parameter.
+    ///
+    /// Defaults to 5 minutes. Setting `None` allows signatures without a `created` parameter;
+    /// signatures with an `expires` parameter in the past are always rejected.
+    pub fn max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Only verifies the signature with the given label, ignoring any others.
+    ///
+    /// By default, all signatures in a request are verified.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets the clock used to check signature creation and expiry times.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    async fn verify(&self, req: &ServiceRequest) -> Result<(), HttpSignatureError> {
+        let inputs = dictionary_header(req.headers(), &SIGNATURE_INPUT)?;
+        let signatures = dictionary_header(req.headers(), &SIGNATURE)?;
+        let parts = MessageParts::from_service_request(req);
+
+        let mut verified = 0;
+
+        for (label, input) in &inputs {
+            if self.label.as_ref().is_some_and(|only| only != label) {
+                continue;
+            }
+
+            let Member::InnerList(items, params) = input else {
+                return Err(HttpSignatureError::Malformed);
+            };
+
+            let signature = match signatures.iter().find(|(name, _)| name == label) {
+                Some((
+                    _,
+                    Member::Item(Item {
+                        bare: BareItem::ByteSeq(signature),
+                        ..
+                    }),
+                )) => signature,
+                Some(_) => return Err(HttpSignatureError::Malformed),
+                None => return Err(HttpSignatureError::Missing),
+            };
+
+            let components = items
+                .iter()
+                .map(|item| match (&item.bare, item.params.is_empty()) {
+                    (BareItem::String(name), true) => Ok(name.as_str()),
+                    _ => Err(HttpSignatureError::Malformed),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(component) = self
+                .required_components
+                .iter()
+                .find(|required| !components.contains(&required.as_str()))
+            {
+                return Err(HttpSignatureError::MissingComponent {
+                    component: component.clone(),
+                });
+            }
+
+            self.check_times(params)?;
+
+            let key_id = param(params, "keyid")
+                .and_then(BareItem::as_str)
+                .ok_or(HttpSignatureError::Malformed)?;
+
+            let key = self
+                .resolver
+                .resolve(key_id)
+                .await
+                .ok_or(HttpSignatureError::UnknownKey)?;
+
+            if let Some(alg) = param(params, "alg") {
+                if alg.as_str() != Some(key.algorithm()) {
+                    return Err(HttpSignatureError::Invalid);
+                }
+            }
+
+            let base = signature_base(&parts, &components, &input.serialize())?;
+            key.verify(base.as_bytes(), signature)?;
+
+            verified += 1;
+        }
+
+        if verified == 0 {
+            return Err(HttpSignatureError::Missing);
+        }
+
+        Ok(())
+    }
+
+    fn check_times(&self, params: &Params) -> Result<(), HttpSignatureError> {
+        let timestamp = |name: &str| {
+            param(params, name)
+                .map(|val| val.as_integer().ok_or(HttpSignatureError::Malformed))
+                .transpose()
+        };
+
+        let now = unix_time(self.clock.system_time());
+
+        if let Some(max_age) = self.max_age {
+            let created = timestamp("created")?.ok_or(HttpSignatureError::Expired)?;
+
+            if created < now - max_age.as_secs() as i64
+                || created > now + CLOCK_SKEW.as_secs() as i64
+            {
+                return Err(HttpSignatureError::Expired);
+            }
+        }
+
+        if let Some(expires) = timestamp("expires")? {
+            if expires < now {
+                return Err(HttpSignatureError::Expired);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Clone for HttpSignatures<R> {
+    fn clone(&self) -> Self {
+        Self {
+            resolver: Rc::clone(&self.resolver),
+            required_components: self.required_components.clone(),
+            max_age: self.max_age,
+            label: self.label.clone(),
+            clock: Arc::clone(&self.clock),
+        }
+    }
+}
+
+impl<R> fmt::Debug for HttpSignatures<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpSignatures")
+            .field("required_components", &self.required_components)
+            .field("max_age", &self.max_age)
+            .field("label", &self.label)
+            .field("clock", &self.clock)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B, R> Transform<S, ServiceRequest> for HttpSignatures<R>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+    R: SignatureKeyResolver,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = HttpSignaturesMiddleware<S, R>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpSignaturesMiddleware {
+            service: Rc::new(service),
+            config: Rc::new(self.clone()),
+        }))
+    }
+}
+
+/// Service for the [`HttpSignatures`] middleware.
+pub struct HttpSignaturesMiddleware<S, R> {
+    service: Rc<S>,
+    config: Rc<HttpSignatures<R>>,
+}
+
+impl<S, B, R> Service<ServiceRequest> for HttpSignaturesMiddleware<S, R>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+    R: SignatureKeyResolver,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let config = Rc::clone(&self.config);
+
+        Box::pin(async move {
+            if let Err(err) = config.verify(&req).await {
+                debug!(
+                    "Rejected request to `{}` with invalid HTTP message signature: {err}",
+                    req.path()
+                );
+
+                return Ok(req.error_response(err).map_into_right_body());
+            }
+
+            Ok(service.call(req).await?.map_into_left_body())
+        })
+    }
+}
+
+/// A source of keys for verifying HTTP message signatures.
+///
+/// Implemented for maps of key IDs to keys.
+#[async_trait(?Send)]
+pub trait SignatureKeyResolver: 'static {
+    /// Returns key identified by the `keyid` signature parameter, if it is known.
+    async fn resolve(&self, key_id: &str) -> Option<HttpSignatureKey>;
+}
+
+#[async_trait(?Send)]
+impl SignatureKeyResolver for HashMap<String, HttpSignatureKey> {
+    async fn resolve(&self, key_id: &str) -> Option<HttpSignatureKey> {
+        self.get(key_id).cloned()
+    }
+}
+
+/// A key used to verify HTTP message signatures.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum HttpSignatureKey {
+    /// Shared secret for the `hmac-sha256` algorithm.
+    HmacSha256(Vec<u8>),
+
+    /// Public key for the `ed25519` algorithm.
+    #[cfg(feature = "ed25519")]
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl HttpSignatureKey {
+    /// Returns name of key's algorithm, as used in the `alg` signature parameter.
+    fn algorithm(&self) -> &'static str {
+        match self {
+            Self::HmacSha256(_) => "hmac-sha256",
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(_) => "ed25519",
+        }
+    }
+
+    fn verify(&self, base: &[u8], signature: &[u8]) -> Result<(), HttpSignatureError> {
+        match self {
+            Self::HmacSha256(key) => {
+                let mut hmac = SimpleHmac::<Sha256>::new_from_slice(key)
+                    .expect("HMAC can take key of any size");
+                hmac.update(base);
+
+                hmac.verify_slice(signature)
+                    .map_err(|_| HttpSignatureError::Invalid)
+            }
+
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(signature)
+                    .map_err(|_| HttpSignatureError::Invalid)?;
+
+                key.verify_strict(base, &signature)
+                    .map_err(|_| HttpSignatureError::Invalid)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for HttpSignatureKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HmacSha256(_) => f.debug_tuple("HmacSha256").field(&"..").finish(),
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => f.debug_tuple("Ed25519").field(key).finish(),
+        }
+    }
+}
+
+/// Errors that can occur when verifying HTTP message signatures.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum HttpSignatureError {
+    /// Request has no signature.
+    #[display(fmt = "Signature is missing.")]
+    Missing,
+
+    /// Signature headers could not be parsed or use unsupported features.
+    #[display(fmt = "Signature is malformed.")]
+    Malformed,
+
+    /// Signature does not cover a required component.
+    #[display(fmt = "Signature does not cover required component `{}`.", component)]
+    MissingComponent {
+        /// Name of the component.
+        component: String,
+    },
+
+    /// Signature has expired, is too old, or lacks a creation time.
+    #[display(fmt = "Signature has expired.")]
+    Expired,
+
+    /// Signature's key ID is not known.
+    #[display(fmt = "Signature key is unknown.")]
+    UnknownKey,
+
+    /// Signature does not match request.
+    #[display(fmt = "Signature is invalid.")]
+    Invalid,
+}
+
+impl ResponseError for HttpSignatureError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Malformed => StatusCode::BAD_REQUEST,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+/// A key used to sign requests with [`sign_request()`].
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum HttpSigningKey {
+    /// Shared secret for the `hmac-sha256` algorithm.
+    HmacSha256(Vec<u8>),
+
+    /// Private key for the `ed25519` algorithm.
+    #[cfg(feature = "ed25519")]
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl HttpSigningKey {
+    fn algorithm(&self) -> &'static str {
+        match self {
+            Self::HmacSha256(_) => "hmac-sha256",
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(_) => "ed25519",
+        }
+    }
+
+    fn sign(&self, base: &[u8]) -> Vec<u8> {
+        match self {
+            Self::HmacSha256(key) => {
+                let mut hmac = SimpleHmac::<Sha256>::new_from_slice(key)
+                    .expect("HMAC can take key of any size");
+                hmac.update(base);
+                hmac.finalize().into_bytes().to_vec()
+            }
+
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(key) => ed25519_dalek::Signer::sign(key, base).to_bytes().to_vec(),
+        }
+    }
+}
+
+impl fmt::Debug for HttpSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HmacSha256(_) => f.debug_tuple("HmacSha256").field(&"..").finish(),
+            #[cfg(feature = "ed25519")]
+            Self::Ed25519(_) => f.debug_tuple("Ed25519").field(&"..").finish(),
+        }
+    }
+}
+
+/// Signs test request with an HTTP message signature covering `components`.
+///
+/// Adds `Signature-Input` and `Signature` headers for a signature labelled `sig1`, created at the
+/// current time, with the given key ID. The `@authority` component is taken from the request's
+/// URI or `Host` header, falling back to the test server's default of `localhost:8080`.
+///
+/// # Panics
+/// Panics if a component is not supported by [`HttpSignatures`] or is not present in the request.
+///
+/// # Examples
+/// ```
+/// use actix_web::{test::TestRequest, HttpMessage as _};
+/// use actix_web_lab::test::{sign_request, HttpSigningKey};
+///
+/// let mut req = TestRequest::post().uri("/orders").to_request();
+///
+/// sign_request(
+///     &mut req,
+///     "client-1",
+///     &HttpSigningKey::HmacSha256(b"shared secret".to_vec()),
+///     &["@method", "@path"],
+/// );
+///
+/// assert!(req.headers().contains_key("signature"));
+/// ```
+pub fn sign_request(req: &mut Request, key_id: &str, key: &HttpSigningKey, components: &[&str]) {
+    let items = components
+        .iter()
+        .map(|&component| Item {
+            bare: BareItem::String(component.to_owned()),
+            params: Params::new(),
+        })
+        .collect();
+
+    let params = vec![
+        (
+            "created".to_owned(),
+            BareItem::Integer(unix_time(SystemTime::now())),
+        ),
+        ("keyid".to_owned(), BareItem::String(key_id.to_owned())),
+        (
+            "alg".to_owned(),
+            BareItem::String(key.algorithm().to_owned()),
+        ),
+    ];
+
+    let input = Member::InnerList(items, params).serialize();
+
+    let parts = MessageParts::from_request(req);
+    let base = signature_base(&parts, components, &input)
+        .expect("signed components should be supported and present in request");
+    let signature = Member::Item(Item {
+        bare: BareItem::ByteSeq(key.sign(base.as_bytes())),
+        params: Params::new(),
+    })
+    .serialize();
+
+    let headers = req.headers_mut();
+    headers.insert(
+        SIGNATURE_INPUT,
+        HeaderValue::from_str(&format!("sig1={input}")).unwrap(),
+    );
+    headers.insert(
+        SIGNATURE,
+        HeaderValue::from_str(&format!("sig1={signature}")).unwrap(),
+    );
+}
+
+/// Parts of a request which signatures can cover.
+struct MessageParts<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    headers: &'a HeaderMap,
+    scheme: String,
+    authority: String,
+}
+
+impl<'a> MessageParts<'a> {
+    fn from_service_request(req: &'a ServiceRequest) -> Self {
+        let conn_info = req.connection_info();
+
+        Self {
+            method: req.method(),
+            uri: req.uri(),
+            headers: req.headers(),
+            scheme: conn_info.scheme().to_owned(),
+            authority: conn_info.host().to_owned(),
+        }
+    }
+
+    fn from_request(req: &'a Request) -> Self {
+        let head = req.head();
+
+        let authority = head
+            .uri
+            .authority()
+            .map(|authority| authority.as_str())
+            .or_else(|| {
+                head.headers
+                    .get(actix_web::http::header::HOST)
+                    .and_then(|host| host.to_str().ok())
+            })
+            .unwrap_or("localhost:8080");
+
+        Self {
+            method: &head.method,
+            uri: &head.uri,
+            headers: &head.headers,
+            scheme: head.uri.scheme_str().unwrap_or("http").to_owned(),
+            authority: authority.to_owned(),
+        }
+    }
+
+    /// Returns canonical value of `component`.
+    fn component(&self, component: &str) -> Result<String, HttpSignatureError> {
+        let request_target = || {
+            self.uri
+                .path_and_query()
+                .map_or_else(|| self.uri.path().to_owned(), |target| target.to_string())
+        };
+
+        Ok(match component {
+            "@method" => self.method.as_str().to_owned(),
+            "@target-uri" => format!("{}://{}{}", self.scheme, self.authority, request_target()),
+            "@authority" => self.authority.to_ascii_lowercase(),
+            "@scheme" => self.scheme.to_ascii_lowercase(),
+            "@request-target" => request_target(),
+            "@path" => match self.uri.path() {
+                "" => "/".to_owned(),
+                path => path.to_owned(),
+            },
+            "@query" => format!("?{}", self.uri.query().unwrap_or_default()),
+
+            // other derived components, including `@signature-params`, cannot be covered
+            name if name.starts_with('@') => return Err(HttpSignatureError::Malformed),
+
+            name => {
+                let name = HeaderName::from_lowercase(name.as_bytes())
+                    .map_err(|_| HttpSignatureError::Malformed)?;
+
+                let values = self
+                    .headers
+                    .get_all(name)
+                    .map(|val| val.to_str().map(str::trim))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| HttpSignatureError::Invalid)?;
+
+                if values.is_empty() {
+                    return Err(HttpSignatureError::Invalid);
+                }
+
+                values.join(", ")
+            }
+        })
+    }
+}
+
+/// Constructs signature base from covered `components` and serialized signature parameters.
+fn signature_base(
+    parts: &MessageParts<'_>,
+    components: &[&str],
+    signature_params: &str,
+) -> Result<String, HttpSignatureError> {
+    let mut base = String::new();
+
+    for (idx, component) in components.iter().enumerate() {
+        if components[..idx].contains(component) {
+            return Err(HttpSignatureError::Malformed);
+        }
+
+        let val = parts.component(component)?;
+        base.push_str(&format!("\"{component}\": {val}\n"));
+    }
+
+    base.push_str(&format!("\"@signature-params\": {signature_params}"));
+
+    Ok(base)
+}
+
+/// Parses dictionary from all values of header `name`.
+fn dictionary_header(
+    headers: &HeaderMap,
+    name: &HeaderName,
+) -> Result<Vec<(String, Member)>, HttpSignatureError> {
+    let values = headers
+        .get_all(name)
+        .map(HeaderValue::to_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| HttpSignatureError::Malformed)?;
+
+    if values.is_empty() {
+        return Err(HttpSignatureError::Missing);
+    }
+
+    structured_field::parse_dictionary(&values.join(", ")).ok_or(HttpSignatureError::Malformed)
+}
+
+fn unix_time(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::header,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+    use base64::Engine as _;
+
+    use super::*;
+    use crate::test::MockClock;
+
+    fn keys() -> HashMap<String, HttpSignatureKey> {
+        HashMap::from([(
+            "test-key".to_owned(),
+            HttpSignatureKey::HmacSha256(b"secret".to_vec()),
+        )])
+    }
+
+    #[actix_web::test]
+    async fn rfc_9421_hmac_example() {
+        let key = base64::engine::general_purpose::STANDARD
+            .decode(
+                "uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbmHhIDi6pcl8jsasjlTMtDQ==",
+            )
+            .unwrap();
+        let keys = HashMap::from([(
+            "test-shared-secret".to_owned(),
+            HttpSignatureKey::HmacSha256(key),
+        )]);
+
+        let clock = MockClock::starting_at(UNIX_EPOCH + Duration::from_secs(1618884473));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    HttpSignatures::new(keys)
+                        .required_components(["@authority"])
+                        .clock(clock),
+                )
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/foo?param=Value&Pet=dog")
+            .insert_header((header::HOST, "example.com"))
+            .insert_header((header::DATE, "Tue, 20 Apr 2021 02:07:55 GMT"))
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .insert_header((
+                "signature-input",
+                r#"sig-b25=("date" "@authority" "content-type");created=1618884473;keyid="test-shared-secret""#,
+            ))
+            .insert_header((
+                "signature",
+                "sig-b25=:pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=:",
+            ))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn verifies_signed_requests() {
+        let clock = MockClock::new();
+
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    HttpSignatures::new(keys())
+                        .required_components(["@method", "@target-uri"])
+                        .clock(clock.clone()),
+                )
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let key = HttpSigningKey::HmacSha256(b"secret".to_vec());
+        let signed = |method: Method, uri: &str, components: &[&str]| {
+            let mut req = TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .insert_header(("x-tenant", "acme"))
+                .to_request();
+            sign_request(&mut req, "test-key", &key, components);
+            req
+        };
+
+        let req = signed(
+            Method::POST,
+            "/orders?id=1",
+            &["@method", "@target-uri", "x-tenant"],
+        );
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // tampered method
+        let mut req = signed(Method::POST, "/orders", &["@method", "@target-uri"]);
+        req.head_mut().method = Method::DELETE;
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // unsigned
+        let req = TestRequest::post().uri("/orders").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // required component not covered
+        let req = signed(Method::POST, "/orders", &["@method", "@path"]);
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // unknown key
+        let mut req = TestRequest::post().uri("/orders").to_request();
+        sign_request(&mut req, "other-key", &key, &["@method", "@target-uri"]);
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // malformed
+        let req = TestRequest::post()
+            .uri("/orders")
+            .insert_header(("signature-input", "sig1=(@method)"))
+            .insert_header(("signature", "sig1=:AAAA:"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        // expired
+        let req = signed(Method::POST, "/orders", &["@method", "@target-uri"]);
+        clock.advance(Duration::from_secs(6 * 60));
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[actix_web::test]
+    async fn ed25519() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let keys = HashMap::from([(
+            "ed-key".to_owned(),
+            HttpSignatureKey::Ed25519(signing_key.verifying_key()),
+        )]);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(HttpSignatures::new(keys))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let mut req = TestRequest::get().uri("/").to_request();
+        sign_request(
+            &mut req,
+            "ed-key",
+            &HttpSigningKey::Ed25519(signing_key),
+            &["@method", "@path"],
+        );
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -1,6 +1,7 @@
 //! JSON extractor with const-generic payload size limit.
 
 use std::{
+    collections::BTreeMap,
     future::Future,
     marker::PhantomData,
     pin::Pin,
@@ -13,9 +14,15 @@ use actix_web::{
     dev::Payload, error::JsonPayloadError, http::header, web, FromRequest, HttpMessage, HttpRequest,
 };
 use futures_core::Stream as _;
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use tracing::debug;
 
+use crate::{
+    json_de,
+    lab_config::LabConfig,
+    strict::{self, UnknownFields},
+};
+
 /// Default JSON payload size limit of 2MiB.
 pub const DEFAULT_JSON_LIMIT: usize = 2_097_152;
 
@@ -165,11 +172,14 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for JsonExtractFut<T, LIMIT
 /// - `Content-Type` is not `application/json`.
 /// - `Content-Length` is greater than `LIMIT`.
 /// - The payload, when consumed, is not valid JSON.
+/// - A [`LabConfig`] in strict mode is registered and the payload has unknown fields.
 pub enum JsonBody<T, const LIMIT: usize> {
     Error(Option<JsonPayloadError>),
     Body {
         /// Length as reported by `Content-Length` header, if present.
         length: Option<usize>,
+        /// Known fields of `T`, if unknown fields should be rejected.
+        strict_fields: Option<&'static [&'static str]>,
         // #[cfg(feature = "__compress")]
         // payload: Decompress<Payload>,
         // #[cfg(not(feature = "__compress"))]
@@ -222,8 +232,15 @@ impl<T: DeserializeOwned, const LIMIT: usize> JsonBody<T, LIMIT> {
             }
         }
 
+        let strict_fields = if LabConfig::from_req(req).is_strict() {
+            strict::struct_fields::<T>()
+        } else {
+            None
+        };
+
         JsonBody::Body {
             length,
+            strict_fields,
             payload,
             buf: web::BytesMut::with_capacity(8192),
             _res: PhantomData,
@@ -238,7 +255,12 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for JsonBody<T, LIMIT> {
         let this = self.get_mut();
 
         match this {
-            JsonBody::Body { buf, payload, .. } => loop {
+            JsonBody::Body {
+                buf,
+                payload,
+                strict_fields,
+                ..
+            } => loop {
                 let res = ready!(Pin::new(&mut *payload).poll_next(cx));
 
                 match res {
@@ -253,8 +275,23 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for JsonBody<T, LIMIT> {
                     }
 
                     None => {
-                        let json = serde_json::from_slice::<T>(buf)
-                            .map_err(JsonPayloadError::Deserialize)?;
+                        if let Some(unknown) = (*strict_fields).and_then(|fields| {
+                            // non-object payloads are left for `T` to reject
+                            let obj = serde_json::from_slice::<BTreeMap<String, IgnoredAny>>(buf);
+                            UnknownFields::find(obj.ok()?.keys(), fields)
+                        }) {
+                            return Poll::Ready(Err(JsonPayloadError::Deserialize(
+                                unknown.into_error(),
+                            )));
+                        }
+
+                        let json = json_de::from_slice::<T>(buf).map_err(|err| {
+                            if let Some(offset) = json_de::error_offset(buf, &err) {
+                                debug!("JSON payload is invalid at byte offset {offset}: {err}");
+                            }
+
+                            JsonPayloadError::Deserialize(err)
+                        })?;
                         return Poll::Ready(Ok(json));
                     }
                 }
@@ -406,6 +443,39 @@ mod tests {
         );
     }
 
+    #[actix_web::test]
+    async fn strict_mode() {
+        let payload = Bytes::from_static(b"{\"name\": \"test\", \"nmae\": 1, \"extra\": 2}");
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(payload.clone())
+            .to_http_parts();
+        let json = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(json.name, "test");
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .app_data(LabConfig::new().strict())
+            .set_payload(payload)
+            .to_http_parts();
+        let err = Json::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Json deserialize error: unknown fields `extra`, `nmae` (did you mean `name`?)"
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .app_data(LabConfig::new().strict())
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+        let json = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(json.name, "test");
+    }
+
     #[actix_web::test]
     async fn test_with_json_and_bad_content_type() {
         let (req, mut pl) = TestRequest::default()
@@ -0,0 +1,860 @@
+//! JSON:API extractor, responders, and error objects.
+//!
+//! See [`JsonApi`] for docs.
+
+use std::{collections::HashSet, fmt};
+
+use actix_web::{
+    body::BoxBody,
+    dev::Payload,
+    error::PayloadError,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    FromRequest, HttpMessage as _, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use ahash::AHashMap;
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::debug;
+
+use crate::{
+    bytes::{BytesBody, BytesPayloadError},
+    json::DEFAULT_JSON_LIMIT,
+};
+
+/// Media type of JSON:API documents.
+const JSON_API: &str = "application/vnd.api+json";
+
+/// A type that can be represented as a JSON:API resource object.
+///
+/// The type must (de)serialize as a map. Its `id` field is used as the resource's ID and fields
+/// named in [`RELATIONSHIPS`](Self::RELATIONSHIPS) are represented as relationships, with all
+/// other fields becoming attributes.
+///
+/// Relationship fields hold the ID of the related resource for to-one relationships, a sequence of
+/// IDs for to-many relationships, or null for empty to-one relationships. IDs of resources
+/// extracted from requests are always strings.
+///
+/// See [`JsonApi`] docs for an example.
+pub trait JsonApiResource {
+    /// Resource type name.
+    const TYPE: &'static str;
+
+    /// Relationship field names and the resource type they refer to.
+    const RELATIONSHIPS: &'static [(&'static str, &'static str)] = &[];
+}
+
+/// JSON:API extractor and responder for single resource objects.
+///
+/// # Extractor
+/// Extracts a resource from a request body containing a JSON:API document with a single resource
+/// object as its primary data. The request must have a `Content-Type` of
+/// `application/vnd.api+json`. The resource object's ID, if any, and relationship linkage are
+/// merged with its attributes before being deserialized into `T`.
+///
+/// The payload size limit is 2MiB. Extraction errors respond with JSON:API error documents; a
+/// resource object whose type does not match [`T::TYPE`](JsonApiResource::TYPE) is rejected with
+/// `409 Conflict`, as required by the specification.
+///
+/// # Responder
+/// Responds with a JSON:API document containing `T` as a single resource object. Sparse fieldsets
+/// requested using `fields[TYPE]` query parameters are applied automatically. Use
+/// [`JsonApiCollection`] for collections of resources.
+///
+/// # Examples
+/// ```
+/// use actix_web::post;
+/// use actix_web_lab::{extract::JsonApiResource, respond::JsonApi};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Article {
+///     id: Option<String>,
+///     title: String,
+///     author: String,
+/// }
+///
+/// impl JsonApiResource for Article {
+///     const TYPE: &'static str = "articles";
+///     const RELATIONSHIPS: &'static [(&'static str, &'static str)] = &[("author", "people")];
+/// }
+///
+/// #[post("/articles")]
+/// async fn create(JsonApi(mut article): JsonApi<Article>) -> JsonApi<Article> {
+///     article.id = Some("1".to_owned());
+///     JsonApi(article)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct JsonApi<T>(pub T);
+
+impl<T> JsonApi<T> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for JsonApi<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for JsonApi<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: JsonApiResource + Serialize> Responder for JsonApi<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let fieldsets = SparseFieldsets::from_query(req.query_string());
+
+        match resource_object(&self.0, &fieldsets) {
+            Ok(data) => json_api_response(StatusCode::OK, &json!({ "data": data })),
+            Err(err) => resource_error(req, err),
+        }
+    }
+}
+
+impl<T: JsonApiResource + DeserializeOwned + 'static> FromRequest for JsonApi<T> {
+    type Error = JsonApiPayloadError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        let is_json_api = matches!(
+            req.mime_type(),
+            Ok(Some(mime)) if mime.essence_str() == JSON_API
+        );
+
+        let body = is_json_api.then(|| BytesBody::<DEFAULT_JSON_LIMIT>::new(&req, payload));
+
+        Box::pin(async move {
+            let res = match body {
+                None => Err(JsonApiPayloadError::ContentType),
+                Some(body) => match body.await {
+                    Ok(body) => from_document::<T>(&body),
+                    Err(err) => Err(err.into()),
+                },
+            };
+
+            if res.is_err() {
+                debug!(
+                    "Failed to deserialize JsonApi<{}> from payload in handler: {}",
+                    core::any::type_name::<T>(),
+                    req.match_name().unwrap_or_else(|| req.path())
+                );
+            }
+
+            res.map(JsonApi)
+        })
+    }
+}
+
+/// JSON:API responder for collections of resource objects.
+///
+/// Sparse fieldsets requested using `fields[TYPE]` query parameters are applied automatically.
+///
+/// # Examples
+/// ```
+/// use actix_web::get;
+/// use actix_web_lab::{extract::JsonApiResource, respond::JsonApiCollection};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Tag {
+///     id: u64,
+///     name: &'static str,
+/// }
+///
+/// impl JsonApiResource for Tag {
+///     const TYPE: &'static str = "tags";
+/// }
+///
+/// #[get("/tags")]
+/// async fn tags() -> JsonApiCollection<Tag> {
+///     JsonApiCollection(vec![Tag { id: 1, name: "rust" }])
+/// }
+/// ```
+#[derive(Debug)]
+pub struct JsonApiCollection<T>(pub Vec<T>);
+
+impl<T: JsonApiResource + Serialize> Responder for JsonApiCollection<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let fieldsets = SparseFieldsets::from_query(req.query_string());
+
+        let data = self
+            .0
+            .iter()
+            .map(|item| resource_object(item, &fieldsets))
+            .collect::<Result<Vec<_>, _>>();
+
+        match data {
+            Ok(data) => json_api_response(StatusCode::OK, &json!({ "data": data })),
+            Err(err) => resource_error(req, err),
+        }
+    }
+}
+
+/// Sparse fieldsets requested using `fields[TYPE]=a,b` query parameters.
+///
+/// The [`JsonApi`] and [`JsonApiCollection`] responders apply requested fieldsets automatically;
+/// this extractor is useful for avoiding loading fields that will not be included in responses.
+///
+/// # Examples
+/// ```
+/// use actix_web::get;
+/// use actix_web_lab::extract::SparseFieldsets;
+///
+/// #[get("/articles")]
+/// async fn articles(fields: SparseFieldsets) -> String {
+///     if fields.includes("articles", "body") {
+///         "full articles".to_owned()
+///     } else {
+///         "article summaries".to_owned()
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SparseFieldsets {
+    fieldsets: AHashMap<String, HashSet<String>>,
+}
+
+impl SparseFieldsets {
+    /// Parses sparse fieldsets from a query string, ignoring other parameters.
+    pub fn from_query(query: &str) -> Self {
+        let params = serde_html_form::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+        let mut fieldsets = AHashMap::new();
+
+        for (key, val) in params {
+            let Some(ty) = key
+                .strip_prefix("fields[")
+                .and_then(|key| key.strip_suffix(']'))
+            else {
+                continue;
+            };
+
+            let fields = val
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(str::to_owned)
+                .collect();
+
+            fieldsets.insert(ty.to_owned(), fields);
+        }
+
+        Self { fieldsets }
+    }
+
+    /// Returns requested fields for resource type `ty`, or `None` if all fields were requested.
+    pub fn fields(&self, ty: &str) -> Option<&HashSet<String>> {
+        self.fieldsets.get(ty)
+    }
+
+    /// Returns true if `field` of resource type `ty` should be included in responses.
+    pub fn includes(&self, ty: &str, field: &str) -> bool {
+        self.fields(ty)
+            .map_or(true, |fields| fields.contains(field))
+    }
+}
+
+impl FromRequest for SparseFieldsets {
+    type Error = actix_web::Error;
+    type Future = std::future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        std::future::ready(Ok(Self::from_query(req.query_string())))
+    }
+}
+
+/// A JSON:API error object.
+///
+/// Implements [`ResponseError`], responding with an error document containing this error, so can
+/// be returned from handlers directly or using the `?` operator.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::StatusCode, web};
+/// use actix_web_lab::respond::{JsonApi, JsonApiError};
+/// # use actix_web_lab::extract::JsonApiResource;
+/// # #[derive(serde::Serialize)]
+/// # struct Article { id: u64 }
+/// # impl JsonApiResource for Article { const TYPE: &'static str = "articles"; }
+///
+/// #[get("/articles/{id}")]
+/// async fn article(id: web::Path<u64>) -> Result<JsonApi<Article>, JsonApiError> {
+///     Err(JsonApiError::new(StatusCode::NOT_FOUND)
+///         .code("article_not_found")
+///         .detail(format!("Article {id} does not exist."))
+///         .parameter("id"))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonApiError {
+    status: StatusCode,
+    code: Option<String>,
+    title: Option<String>,
+    detail: Option<String>,
+    pointer: Option<String>,
+    parameter: Option<String>,
+}
+
+impl JsonApiError {
+    /// Constructs new error object with the given status code.
+    ///
+    /// The title defaults to the status's reason phrase.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            code: None,
+            title: None,
+            detail: None,
+            pointer: None,
+            parameter: None,
+        }
+    }
+
+    /// Sets application-specific error code.
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Sets short, human-readable summary of the problem.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets human-readable explanation specific to this occurrence of the problem.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets JSON pointer to the value in the request document that caused the error.
+    pub fn pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.pointer = Some(pointer.into());
+        self
+    }
+
+    /// Sets name of the query parameter that caused the error.
+    pub fn parameter(mut self, parameter: impl Into<String>) -> Self {
+        self.parameter = Some(parameter.into());
+        self
+    }
+
+    fn to_value(&self) -> Value {
+        let mut obj = Map::new();
+
+        obj.insert("status".to_owned(), self.status.as_str().into());
+
+        if let Some(code) = &self.code {
+            obj.insert("code".to_owned(), code.as_str().into());
+        }
+
+        let title = self
+            .title
+            .as_deref()
+            .or_else(|| self.status.canonical_reason());
+
+        if let Some(title) = title {
+            obj.insert("title".to_owned(), title.into());
+        }
+
+        if let Some(detail) = &self.detail {
+            obj.insert("detail".to_owned(), detail.as_str().into());
+        }
+
+        let mut source = Map::new();
+
+        if let Some(pointer) = &self.pointer {
+            source.insert("pointer".to_owned(), pointer.as_str().into());
+        }
+
+        if let Some(parameter) = &self.parameter {
+            source.insert("parameter".to_owned(), parameter.as_str().into());
+        }
+
+        if !source.is_empty() {
+            obj.insert("source".to_owned(), Value::Object(source));
+        }
+
+        Value::Object(obj)
+    }
+}
+
+impl fmt::Display for JsonApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let title = self
+            .title
+            .as_deref()
+            .or_else(|| self.status.canonical_reason())
+            .unwrap_or("error");
+
+        match &self.detail {
+            Some(detail) => write!(f, "{title}: {detail}"),
+            None => f.write_str(title),
+        }
+    }
+}
+
+impl std::error::Error for JsonApiError {}
+
+impl ResponseError for JsonApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        json_api_response(self.status, &json!({ "errors": [self.to_value()] }))
+    }
+}
+
+/// Errors that can occur when extracting a [`JsonApi`] payload.
+///
+/// Responds with a JSON:API error document.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum JsonApiPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(
+        fmt = "JSON:API payload ({length} bytes) is larger than allowed (limit: {limit} bytes)."
+    )]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "JSON:API payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not `application/vnd.api+json`.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload is not a JSON:API document with a single resource object as its primary data.
+    #[display(fmt = "Invalid JSON:API document: {reason}")]
+    InvalidDocument {
+        /// Description of the problem.
+        reason: &'static str,
+    },
+
+    /// Type of the resource object does not match the expected type.
+    #[display(fmt = "Resource type `{found}` does not match expected type `{expected}`.")]
+    TypeMismatch {
+        /// Expected resource type.
+        expected: &'static str,
+
+        /// Resource type in the document.
+        found: String,
+    },
+
+    /// Payload could not be deserialized.
+    #[display(fmt = "JSON:API deserialize error: {_0}")]
+    Deserialize(serde_json::Error),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<BytesPayloadError> for JsonApiPayloadError {
+    fn from(err: BytesPayloadError) -> Self {
+        match err {
+            BytesPayloadError::OverflowKnownLength { length, limit } => {
+                Self::OverflowKnownLength { length, limit }
+            }
+            BytesPayloadError::Overflow { limit } => Self::Overflow { limit },
+            BytesPayloadError::ContentType => Self::ContentType,
+            BytesPayloadError::Payload(err) => Self::Payload(err),
+        }
+    }
+}
+
+impl ResponseError for JsonApiPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::InvalidDocument { .. } | Self::Deserialize(_) => StatusCode::BAD_REQUEST,
+            Self::TypeMismatch { .. } => StatusCode::CONFLICT,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut err = JsonApiError::new(self.status_code()).detail(self.to_string());
+
+        if let Self::TypeMismatch { .. } = self {
+            err = err.pointer("/data/type");
+        }
+
+        err.error_response()
+    }
+}
+
+#[derive(Debug, Display)]
+enum ResourceError {
+    #[display(fmt = "resource could not be serialized: {_0}")]
+    Serialize(serde_json::Error),
+
+    #[display(fmt = "resource does not serialize to a JSON object")]
+    NotAnObject,
+
+    #[display(fmt = "resource ID is not a string or number")]
+    InvalidId,
+}
+
+/// Converts a resource ID or relationship linkage value to a string ID.
+fn id_string(val: Value) -> Result<String, ResourceError> {
+    match val {
+        Value::String(id) => Ok(id),
+        Value::Number(id) => Ok(id.to_string()),
+        _ => Err(ResourceError::InvalidId),
+    }
+}
+
+fn resource_object<T: JsonApiResource + Serialize>(
+    resource: &T,
+    fieldsets: &SparseFieldsets,
+) -> Result<Value, ResourceError> {
+    let mut attributes = match serde_json::to_value(resource).map_err(ResourceError::Serialize)? {
+        Value::Object(obj) => obj,
+        _ => return Err(ResourceError::NotAnObject),
+    };
+
+    let mut obj = Map::new();
+    obj.insert("type".to_owned(), T::TYPE.into());
+
+    match attributes.remove("id") {
+        None | Some(Value::Null) => {}
+        Some(id) => {
+            obj.insert("id".to_owned(), id_string(id)?.into());
+        }
+    }
+
+    let mut relationships = Map::new();
+
+    for &(name, ty) in T::RELATIONSHIPS {
+        let Some(linkage) = attributes.remove(name) else {
+            continue;
+        };
+
+        if !fieldsets.includes(T::TYPE, name) {
+            continue;
+        }
+
+        let identifier = |id| -> Result<Value, ResourceError> {
+            Ok(json!({ "type": ty, "id": id_string(id)? }))
+        };
+
+        let data = match linkage {
+            Value::Null => Value::Null,
+            Value::Array(ids) => ids
+                .into_iter()
+                .map(identifier)
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+            id => identifier(id)?,
+        };
+
+        relationships.insert(name.to_owned(), json!({ "data": data }));
+    }
+
+    attributes.retain(|name, _| fieldsets.includes(T::TYPE, name));
+
+    if !attributes.is_empty() {
+        obj.insert("attributes".to_owned(), Value::Object(attributes));
+    }
+
+    if !relationships.is_empty() {
+        obj.insert("relationships".to_owned(), Value::Object(relationships));
+    }
+
+    Ok(Value::Object(obj))
+}
+
+fn from_document<T: JsonApiResource + DeserializeOwned>(
+    body: &[u8],
+) -> Result<T, JsonApiPayloadError> {
+    let invalid = |reason| JsonApiPayloadError::InvalidDocument { reason };
+
+    let doc = serde_json::from_slice::<Value>(body).map_err(JsonApiPayloadError::Deserialize)?;
+
+    let Some(Value::Object(mut data)) = doc.get("data").cloned() else {
+        return Err(invalid("primary data must be a single resource object"));
+    };
+
+    match data.remove("type") {
+        Some(Value::String(ty)) if ty == T::TYPE => {}
+        Some(Value::String(found)) => {
+            return Err(JsonApiPayloadError::TypeMismatch {
+                expected: T::TYPE,
+                found,
+            })
+        }
+        _ => return Err(invalid("resource object must have a string `type` member")),
+    }
+
+    let mut fields = match data.remove("attributes") {
+        None => Map::new(),
+        Some(Value::Object(attributes)) => attributes,
+        Some(_) => return Err(invalid("`attributes` must be an object")),
+    };
+
+    match data.remove("id") {
+        None => {}
+        Some(Value::String(id)) => {
+            fields.insert("id".to_owned(), id.into());
+        }
+        Some(_) => return Err(invalid("resource object `id` must be a string")),
+    }
+
+    let relationships = match data.remove("relationships") {
+        None => Map::new(),
+        Some(Value::Object(relationships)) => relationships,
+        Some(_) => return Err(invalid("`relationships` must be an object")),
+    };
+
+    for (name, relationship) in relationships {
+        let linkage_id = |identifier: &Value| {
+            identifier
+                .get("id")
+                .and_then(Value::as_str)
+                .map(Value::from)
+                .ok_or_else(|| invalid("resource identifier must have a string `id` member"))
+        };
+
+        let linkage = match relationship.get("data") {
+            Some(Value::Null) => Value::Null,
+            Some(Value::Array(identifiers)) => identifiers
+                .iter()
+                .map(&linkage_id)
+                .collect::<Result<Vec<_>, _>>()?
+                .into(),
+            Some(identifier @ Value::Object(_)) => linkage_id(identifier)?,
+            _ => return Err(invalid("relationship must have a `data` member")),
+        };
+
+        fields.insert(name, linkage);
+    }
+
+    serde_json::from_value(Value::Object(fields)).map_err(JsonApiPayloadError::Deserialize)
+}
+
+fn json_api_response(status: StatusCode, doc: &Value) -> HttpResponse {
+    let mut res = HttpResponse::build(status).json(doc);
+    res.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(JSON_API));
+    res
+}
+
+fn resource_error(req: &HttpRequest, err: ResourceError) -> HttpResponse {
+    debug!(
+        "Failed to build JSON:API resource object in `{}` handler: {err}",
+        req.match_name().unwrap_or_else(|| req.path())
+    );
+
+    JsonApiError::new(StatusCode::INTERNAL_SERVER_ERROR).error_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest, web::Bytes};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Article {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        title: String,
+        body: String,
+        author: Option<String>,
+        tags: Vec<String>,
+    }
+
+    impl JsonApiResource for Article {
+        const TYPE: &'static str = "articles";
+        const RELATIONSHIPS: &'static [(&'static str, &'static str)] =
+            &[("author", "people"), ("tags", "tags")];
+    }
+
+    fn article() -> Article {
+        Article {
+            id: Some("1".to_owned()),
+            title: "Hello".to_owned(),
+            body: "World".to_owned(),
+            author: Some("9".to_owned()),
+            tags: vec!["2".to_owned(), "3".to_owned()],
+        }
+    }
+
+    async fn document(res: HttpResponse) -> Value {
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/vnd.api+json",
+        );
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn responds_with_resource_objects() {
+        let req = TestRequest::default().to_http_request();
+        let doc = document(JsonApi(article()).respond_to(&req)).await;
+
+        assert_eq!(
+            doc,
+            json!({
+                "data": {
+                    "type": "articles",
+                    "id": "1",
+                    "attributes": { "title": "Hello", "body": "World" },
+                    "relationships": {
+                        "author": { "data": { "type": "people", "id": "9" } },
+                        "tags": { "data": [
+                            { "type": "tags", "id": "2" },
+                            { "type": "tags", "id": "3" },
+                        ] },
+                    },
+                },
+            }),
+        );
+
+        let req =
+            TestRequest::with_uri("/?fields%5Barticles%5D=title,author&fields%5Bpeople%5D=name")
+                .to_http_request();
+        let doc = document(JsonApiCollection(vec![article()]).respond_to(&req)).await;
+
+        assert_eq!(doc["data"][0]["attributes"], json!({ "title": "Hello" }));
+        assert_eq!(
+            doc["data"][0]["relationships"],
+            json!({ "author": { "data": { "type": "people", "id": "9" } } }),
+        );
+    }
+
+    #[test]
+    fn parses_sparse_fieldsets() {
+        let fields = SparseFieldsets::from_query("fields[a]=x,%20y&fields[b]=&sort=x");
+
+        assert!(fields.includes("a", "x"));
+        assert!(fields.includes("a", "y"));
+        assert!(!fields.includes("a", "z"));
+        assert!(!fields.includes("b", "x"));
+        assert!(fields.includes("c", "x"));
+    }
+
+    #[actix_web::test]
+    async fn extracts_resource_objects() {
+        let payload = json!({
+            "data": {
+                "type": "articles",
+                "attributes": { "title": "Hello", "body": "World" },
+                "relationships": {
+                    "author": { "data": { "type": "people", "id": "9" } },
+                    "tags": { "data": [] },
+                },
+            },
+        });
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, JSON_API))
+            .set_payload(payload.to_string())
+            .to_http_parts();
+        let JsonApi(extracted) = JsonApi::<Article>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            extracted,
+            Article {
+                id: None,
+                author: Some("9".to_owned()),
+                tags: vec![],
+                ..article()
+            },
+        );
+    }
+
+    #[actix_web::test]
+    async fn extraction_errors() {
+        async fn extract(content_type: &str, payload: Value) -> JsonApiPayloadError {
+            let (req, mut pl) = TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload(Bytes::from(payload.to_string()))
+                .to_http_parts();
+
+            JsonApi::<Article>::from_request(&req, &mut pl)
+                .await
+                .unwrap_err()
+        }
+
+        let valid = json!({ "data": { "type": "articles", "attributes": {} } });
+
+        let err = extract("application/json", valid).await;
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let err = extract(JSON_API, json!({ "data": { "type": "people" } })).await;
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+
+        let doc = document(err.error_response()).await;
+        assert_eq!(doc["errors"][0]["status"], "409");
+        assert_eq!(doc["errors"][0]["source"]["pointer"], "/data/type");
+
+        let err = extract(JSON_API, json!({ "data": [] })).await;
+        assert!(matches!(err, JsonApiPayloadError::InvalidDocument { .. }));
+
+        let err = extract(JSON_API, json!({ "data": { "type": "articles" } })).await;
+        assert!(matches!(err, JsonApiPayloadError::Deserialize(_)));
+    }
+
+    #[actix_web::test]
+    async fn error_objects() {
+        let err = JsonApiError::new(StatusCode::NOT_FOUND)
+            .code("missing")
+            .detail("Gone fishing.")
+            .parameter("id");
+        assert_eq!(err.to_string(), "Not Found: Gone fishing.");
+
+        let doc = document(err.error_response()).await;
+        assert_eq!(
+            doc,
+            json!({
+                "errors": [{
+                    "status": "404",
+                    "code": "missing",
+                    "title": "Not Found",
+                    "detail": "Gone fishing.",
+                    "source": { "parameter": "id" },
+                }],
+            }),
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! JSON deserialization helpers shared by JSON-based extractors and decoders.
+
+use serde::de::DeserializeOwned;
+
+/// Deserializes `T` from a JSON buffer.
+///
+/// When the `simd-json` crate feature is enabled, the buffer is first parsed using `simd-json`.
+/// Since `simd-json` can only report errors with less detail, buffers which it fails to parse are
+/// re-parsed using `serde_json` so that errors always include line and column information.
+pub(crate) fn from_slice<T: DeserializeOwned>(buf: &[u8]) -> Result<T, serde_json::Error> {
+    #[cfg(feature = "simd-json")]
+    {
+        // simd-json parses in place so needs a scratch copy to keep the original for the fallback
+        let mut scratch = buf.to_vec();
+
+        if let Ok(item) = simd_json::serde::from_slice(&mut scratch) {
+            return Ok(item);
+        }
+    }
+
+    serde_json::from_slice(buf)
+}
+
+/// Returns the byte offset in `input` at which a deserialization error occurred.
+///
+/// Returns `None` if the error does not correspond to a position in the input.
+pub(crate) fn error_offset(input: &[u8], err: &serde_json::Error) -> Option<usize> {
+    let (line, column) = (err.line(), err.column());
+
+    if line == 0 {
+        return None;
+    }
+
+    let line_start = match line {
+        1 => 0,
+        _ => memchr::memchr_iter(b'\n', input).nth(line - 2)? + 1,
+    };
+
+    // column is 1-based but is 0 when error occurs at start of line (e.g., unexpected EOF)
+    Some((line_start + column.saturating_sub(1)).min(input.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserializes() {
+        let val = from_slice::<Vec<u32>>(b"[1, 2, 3]").unwrap();
+        assert_eq!(val, [1, 2, 3]);
+
+        let err = from_slice::<Vec<u32>>(b"[1,\n 2,\n x]").unwrap_err();
+        assert_eq!(err.line(), 3);
+    }
+
+    #[test]
+    fn error_offsets() {
+        let input = b"[1,\n 2,\n x]";
+        let err = serde_json::from_slice::<serde_json::Value>(input).unwrap_err();
+        assert_eq!(error_offset(input, &err), Some(9));
+
+        let input = b"{\"a\": tru}";
+        let err = serde_json::from_slice::<serde_json::Value>(input).unwrap_err();
+        assert_eq!(input[error_offset(input, &err).unwrap()], b'}');
+
+        let input = b"[1, 2";
+        let err = serde_json::from_slice::<serde_json::Value>(input).unwrap_err();
+        assert!(error_offset(input, &err).unwrap() <= input.len());
+    }
+}
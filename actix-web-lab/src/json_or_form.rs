@@ -0,0 +1,318 @@
+//! JSON or URL-encoded form extractor.
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, FromRequest, HttpMessage as _,
+    HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::bytes::{BytesBody, BytesPayloadError};
+
+/// Default JSON or URL-encoded form payload size limit of 2MiB.
+pub const DEFAULT_JSON_OR_FORM_LIMIT: usize = 2_097_152;
+
+/// Extractor for payloads that are either JSON or URL-encoded forms.
+///
+/// The body is deserialized according to the request's `Content-Type`: as JSON for
+/// `application/json` or types with a `+json` suffix, and as a URL-encoded form for
+/// `application/x-www-form-urlencoded`. Other content types are rejected with a
+/// `415 Unsupported Media Type` response.
+///
+/// This is useful for endpoints that accept both HTML form submissions from browsers and JSON
+/// payloads from API clients. The inner type `T` must implement [`serde::Deserialize`]; note that
+/// URL-encoded forms can only represent flat structures of strings, numbers, and booleans.
+///
+/// Use the `LIMIT` const generic parameter to control the payload size limit. The default limit
+/// that is exported (`DEFAULT_JSON_OR_FORM_LIMIT`) is 2MiB.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::JsonOrForm;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Signup {
+///     email: String,
+/// }
+///
+/// #[post("/signup")]
+/// async fn signup(JsonOrForm(signup): JsonOrForm<Signup>) -> String {
+///     format!("Welcome {}!", signup.email)
+/// }
+/// # App::new().service(signup);
+/// ```
+#[doc(alias = "form", alias = "json")]
+#[derive(Debug)]
+pub struct JsonOrForm<T, const LIMIT: usize = DEFAULT_JSON_OR_FORM_LIMIT>(pub T);
+
+mod waiting_on_derive_more_to_start_using_syn_2_due_to_proc_macro_panic {
+    use super::*;
+
+    impl<T, const LIMIT: usize> std::ops::Deref for JsonOrForm<T, LIMIT> {
+        type Target = T;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T, const LIMIT: usize> std::ops::DerefMut for JsonOrForm<T, LIMIT> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl<T: fmt::Display, const LIMIT: usize> fmt::Display for JsonOrForm<T, LIMIT> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T, const LIMIT: usize> JsonOrForm<T, LIMIT> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Payload formats accepted by [`JsonOrForm`].
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Json,
+    Form,
+}
+
+impl<T: DeserializeOwned, const LIMIT: usize> FromRequest for JsonOrForm<T, LIMIT> {
+    type Error = JsonOrFormPayloadError;
+    type Future = JsonOrFormExtractFut<T, LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let format = match req.mime_type() {
+            Ok(Some(mime))
+                if (mime.type_() == mime::APPLICATION && mime.subtype() == mime::JSON)
+                    || mime.suffix() == Some(mime::JSON) =>
+            {
+                Some(Format::Json)
+            }
+            Ok(Some(mime))
+                if mime.type_() == mime::APPLICATION
+                    && mime.subtype() == mime::WWW_FORM_URLENCODED =>
+            {
+                Some(Format::Form)
+            }
+            _ => None,
+        };
+
+        JsonOrFormExtractFut {
+            req: Some(req.clone()),
+            fut: format.map(|format| (format, BytesBody::new(req, payload))),
+            _res: PhantomData,
+        }
+    }
+}
+
+/// Future for the [`JsonOrForm`] extractor.
+pub struct JsonOrFormExtractFut<T, const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    fut: Option<(Format, BytesBody<LIMIT>)>,
+    _res: PhantomData<T>,
+}
+
+impl<T, const LIMIT: usize> Unpin for JsonOrFormExtractFut<T, LIMIT> {}
+
+impl<T: DeserializeOwned, const LIMIT: usize> Future for JsonOrFormExtractFut<T, LIMIT> {
+    type Output = Result<JsonOrForm<T, LIMIT>, JsonOrFormPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match &mut this.fut {
+            None => Err(JsonOrFormPayloadError::ContentType),
+            Some((format, fut)) => {
+                let format = *format;
+
+                ready!(Pin::new(fut).poll(cx))
+                    .map_err(JsonOrFormPayloadError::from)
+                    .and_then(|body| deserialize(format, &body))
+            }
+        };
+
+        if res.is_err() {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to deserialize JsonOrForm<{}> from payload in handler: {}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        Poll::Ready(res.map(JsonOrForm))
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(
+    format: Format,
+    body: &[u8],
+) -> Result<T, JsonOrFormPayloadError> {
+    match format {
+        Format::Json => serde_json::from_slice(body).map_err(JsonOrFormPayloadError::Json),
+        Format::Form => serde_html_form::from_bytes(body).map_err(JsonOrFormPayloadError::Form),
+    }
+}
+
+/// Errors that can occur when extracting a [`JsonOrForm`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum JsonOrFormPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(fmt = "Payload ({length} bytes) is larger than allowed (limit: {limit} bytes).")]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "Payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is neither JSON nor a URL-encoded form.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// JSON payload could not be deserialized.
+    #[display(fmt = "JSON deserialize error: {_0}")]
+    Json(serde_json::Error),
+
+    /// URL-encoded form payload could not be deserialized.
+    #[display(fmt = "URL-encoded form deserialize error: {_0}")]
+    Form(serde::de::value::Error),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<BytesPayloadError> for JsonOrFormPayloadError {
+    fn from(err: BytesPayloadError) -> Self {
+        match err {
+            BytesPayloadError::OverflowKnownLength { length, limit } => {
+                Self::OverflowKnownLength { length, limit }
+            }
+            BytesPayloadError::Overflow { limit } => Self::Overflow { limit },
+            BytesPayloadError::ContentType => Self::ContentType,
+            BytesPayloadError::Payload(err) => Self::Payload(err),
+        }
+    }
+}
+
+impl ResponseError for JsonOrFormPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Json(_) | Self::Form(_) => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Signup {
+        email: String,
+        age: u8,
+    }
+
+    fn signup() -> Signup {
+        Signup {
+            email: "ana@example.com".to_owned(),
+            age: 30,
+        }
+    }
+
+    #[actix_web::test]
+    async fn extracts_json_and_form() {
+        for (content_type, payload) in [
+            (
+                "application/json",
+                r#"{"email":"ana@example.com","age":30}"#,
+            ),
+            (
+                "application/vnd.signup+json; charset=utf-8",
+                r#"{"email":"ana@example.com","age":30}"#,
+            ),
+            (
+                "application/x-www-form-urlencoded",
+                "email=ana%40example.com&age=30",
+            ),
+        ] {
+            let (req, mut pl) = TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload(payload)
+                .to_http_parts();
+
+            let JsonOrForm(extracted) = JsonOrForm::<Signup>::from_request(&req, &mut pl)
+                .await
+                .unwrap();
+            assert_eq!(extracted, signup());
+        }
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::plaintext())
+            .set_payload("email=ana%40example.com&age=30")
+            .to_http_parts();
+        let err = JsonOrForm::<Signup>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::form_url_encoded())
+            .set_payload("email=ana%40example.com&age=old")
+            .to_http_parts();
+        let err = JsonOrForm::<Signup>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JsonOrFormPayloadError::Form(_)));
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(r#"{"email":"ana@example.com","age":30}"#)
+            .to_http_parts();
+        let err = JsonOrForm::<Signup, 8>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
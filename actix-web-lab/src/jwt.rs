@@ -0,0 +1,575 @@
+//! JSON Web Token extractor with JWKS key sets.
+//!
+//! See [`Jwt`] and [`Jwks`] docs.
+
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    dev::Payload, http::StatusCode, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use arc_swap::ArcSwap;
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Header, Validation};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::bearer_auth::{parse_bearer, BearerAuthError};
+
+/// Default interval after which key sets are refreshed in the background.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum interval between refreshes triggered by tokens with unknown key IDs.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Extractor for claims of a JSON Web Token sent as a bearer token.
+///
+/// Tokens are read from the `Authorization` header using the `Bearer` scheme, their signatures
+/// are verified using the key from the configured [`Jwks`] whose ID matches the token's `kid`
+/// header, and their claims are validated and deserialized into `T`.
+///
+/// Requires a [`JwtConfig`] to be registered as app data, which sets the key set and the
+/// validation rules. By default, the `exp` claim is required and checked, and the `nbf` claim is
+/// checked when present; `aud` and `iss` claims are only checked when configured.
+///
+/// # Extractor
+/// Extraction fails with a [`JwtError`]. Requests with missing, malformed, or invalid tokens
+/// result in RFC 6750 challenge responses, like those of the [`BearerAuth`] extractor.
+///
+/// [`BearerAuth`]: crate::extract::BearerAuth
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App, Responder};
+/// use actix_web_lab::extract::{jwt::Algorithm, Jwks, Jwt, JwtConfig};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Claims {
+///     sub: String,
+/// }
+///
+/// #[get("/")]
+/// async fn index(jwt: Jwt<Claims>) -> impl Responder {
+///     format!("Hello, {}!", jwt.sub)
+/// }
+///
+/// let jwks = Jwks::from_url("https://auth.example.com/.well-known/jwks.json");
+///
+/// App::new()
+///     .app_data(
+///         JwtConfig::new(jwks)
+///             .algorithms([Algorithm::RS256])
+///             .issuer(["https://auth.example.com/"])
+///             .audience(["my-api"]),
+///     )
+///     .service(index)
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Jwt<T> {
+    header: Header,
+    claims: T,
+}
+
+impl<T> Jwt<T> {
+    /// Returns token header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns token claims.
+    pub fn claims(&self) -> &T {
+        &self.claims
+    }
+
+    /// Unwraps into inner claims.
+    pub fn into_claims(self) -> T {
+        self.claims
+    }
+}
+
+impl<T> Deref for Jwt<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.claims
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Jwt<T> {
+    type Error = JwtError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+
+        Box::pin(async move {
+            let token = parse_bearer(&req)
+                .map_err(|kind| JwtError::Unauthorized(BearerAuthError::new(kind, &req)))?;
+
+            let Some(config) = JwtConfig::from_req(&req) else {
+                debug!(
+                    "Failed to extract `Jwt` for `{}` handler. For the Jwt extractor to work \
+                    correctly, register a `JwtConfig` as app data.",
+                    req.match_name().unwrap_or_else(|| req.path())
+                );
+
+                return Err(JwtError::NotConfigured);
+            };
+
+            let header = jsonwebtoken::decode_header(&token).map_err(|err| invalid(&req, err))?;
+
+            let key = match config.jwks.decoding_key(header.kid.as_deref()).await {
+                Ok(key) => key,
+                Err(JwksError::KeyNotFound) => return Err(invalid(&req, JwksError::KeyNotFound)),
+                Err(err) => return Err(JwtError::KeySet(err)),
+            };
+
+            let data = jsonwebtoken::decode::<T>(&token, &key, &config.validation)
+                .map_err(|err| invalid(&req, err))?;
+
+            Ok(Self {
+                header: data.header,
+                claims: data.claims,
+            })
+        })
+    }
+}
+
+/// Logs reason a token was rejected and returns an `invalid_token` error.
+fn invalid(req: &HttpRequest, err: impl fmt::Display) -> JwtError {
+    debug!(
+        "Failed to extract `Jwt` for `{}` handler: {err}",
+        req.match_name().unwrap_or_else(|| req.path())
+    );
+
+    JwtError::Unauthorized(BearerAuthError::invalid_token(req))
+}
+
+/// Configuration for the [`Jwt`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](actix_web::web::Data).
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    jwks: Jwks,
+    validation: Validation,
+}
+
+impl JwtConfig {
+    /// Constructs new config which verifies tokens using keys from `jwks`.
+    ///
+    /// Accepts tokens signed using the RS256 algorithm, by default.
+    pub fn new(jwks: Jwks) -> Self {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.validate_nbf = true;
+        validation.validate_aud = false;
+
+        Self { jwks, validation }
+    }
+
+    /// Sets algorithms which tokens may be signed with.
+    ///
+    /// Keys are only used with algorithms of their own type, regardless of this setting.
+    pub fn algorithms(mut self, algorithms: impl IntoIterator<Item = Algorithm>) -> Self {
+        self.validation.algorithms = algorithms.into_iter().collect();
+        self
+    }
+
+    /// Requires tokens to have an `aud` claim matching one of `audience`.
+    pub fn audience<S: ToString>(mut self, audience: impl IntoIterator<Item = S>) -> Self {
+        let audience = audience.into_iter().collect::<Vec<_>>();
+        self.validation.set_audience(&audience);
+        self.validation
+            .required_spec_claims
+            .insert("aud".to_owned());
+        self.validation.validate_aud = true;
+        self
+    }
+
+    /// Requires tokens to have an `iss` claim matching one of `issuer`.
+    pub fn issuer<S: ToString>(mut self, issuer: impl IntoIterator<Item = S>) -> Self {
+        let issuer = issuer.into_iter().collect::<Vec<_>>();
+        self.validation.set_issuer(&issuer);
+        self
+    }
+
+    /// Sets leeway allowed when checking `exp` and `nbf` claims, to account for clock skew.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.validation.leeway = leeway.as_secs();
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> Option<&Self> {
+        req.app_data::<Self>().or_else(|| {
+            req.app_data::<actix_web::web::Data<Self>>()
+                .map(|data| data.get_ref())
+        })
+    }
+}
+
+type ClientFactory = Arc<dyn Fn() -> awc::Client + Send + Sync>;
+
+/// A JSON Web Key Set, used to verify [`Jwt`] signatures.
+///
+/// Key sets fetched from a URL are fetched when first used, and are then refreshed in the
+/// background once they are older than the [refresh interval](Self::refresh_interval). Tokens
+/// with key IDs not in the set also cause it to be refreshed, at most once every 30 seconds, to
+/// pick up rotated keys promptly.
+///
+/// Cloning a `Jwks` is cheap; clones share the same keys.
+#[derive(Clone)]
+pub struct Jwks {
+    url: Option<String>,
+    client: ClientFactory,
+    refresh_interval: Duration,
+    state: Arc<JwksState>,
+}
+
+struct JwksState {
+    keys: ArcSwap<JwkSet>,
+    last_fetch: Mutex<Option<Instant>>,
+    refreshing: AtomicBool,
+}
+
+impl Jwks {
+    /// Constructs key set which is fetched from `url`.
+    ///
+    /// HTTPS URLs require a TLS feature of `awc` to be enabled, or a client to be set using
+    /// [`client()`](Self::client).
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Self::new(Some(url.into()), JwkSet { keys: Vec::new() })
+    }
+
+    /// Constructs key set from fixed keys, which are never refreshed.
+    pub fn from_set(keys: JwkSet) -> Self {
+        Self::new(None, keys)
+    }
+
+    fn new(url: Option<String>, keys: JwkSet) -> Self {
+        Self {
+            url,
+            client: Arc::new(awc::Client::default),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            state: Arc::new(JwksState {
+                keys: ArcSwap::from_pointee(keys),
+                last_fetch: Mutex::new(None),
+                refreshing: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Sets interval after which the key set is refreshed in the background.
+    ///
+    /// Defaults to 1 hour.
+    pub fn refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Sets factory for clients used to fetch the key set.
+    ///
+    /// Defaults to [`awc::Client::default()`].
+    pub fn client(mut self, client: impl Fn() -> awc::Client + Send + Sync + 'static) -> Self {
+        self.client = Arc::new(client);
+        self
+    }
+
+    /// Returns current keys.
+    pub fn keys(&self) -> Arc<JwkSet> {
+        self.state.keys.load_full()
+    }
+
+    /// Fetches key set from its URL, replacing the current keys.
+    ///
+    /// Does nothing for fixed key sets.
+    pub async fn refresh(&self) -> Result<(), JwksError> {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+
+        *self.state.last_fetch.lock().unwrap() = Some(Instant::now());
+
+        let mut res = (self.client)()
+            .get(url.as_str())
+            .send()
+            .await
+            .map_err(|err| JwksError::Fetch(err.to_string()))?;
+
+        if !res.status().is_success() {
+            return Err(JwksError::Fetch(format!(
+                "unexpected response status {}",
+                res.status()
+            )));
+        }
+
+        let keys = res
+            .json::<JwkSet>()
+            .await
+            .map_err(|err| JwksError::Fetch(err.to_string()))?;
+
+        self.state.keys.store(Arc::new(keys));
+
+        Ok(())
+    }
+
+    /// Returns decoding key with ID `kid`, or the only key if the token has no key ID.
+    async fn decoding_key(&self, kid: Option<&str>) -> Result<DecodingKey, JwksError> {
+        if self.url.is_some() {
+            let last_fetch = *self.state.last_fetch.lock().unwrap();
+
+            match last_fetch {
+                None => self.refresh().await?,
+                Some(fetched) if fetched.elapsed() >= self.refresh_interval => self.spawn_refresh(),
+                Some(_) => {}
+            }
+        }
+
+        if let Some(key) = self.find(kid)? {
+            return Ok(key);
+        }
+
+        let can_refresh = self.url.is_some()
+            && self
+                .state
+                .last_fetch
+                .lock()
+                .unwrap()
+                .map_or(true, |fetched| fetched.elapsed() >= MIN_REFRESH_INTERVAL);
+
+        if can_refresh {
+            self.refresh().await?;
+
+            if let Some(key) = self.find(kid)? {
+                return Ok(key);
+            }
+        }
+
+        Err(JwksError::KeyNotFound)
+    }
+
+    fn find(&self, kid: Option<&str>) -> Result<Option<DecodingKey>, JwksError> {
+        let keys = self.state.keys.load();
+
+        let jwk = match kid {
+            Some(kid) => keys.find(kid),
+            None if keys.keys.len() == 1 => keys.keys.first(),
+            None => None,
+        };
+
+        jwk.map(|jwk| DecodingKey::from_jwk(jwk).map_err(JwksError::InvalidKey))
+            .transpose()
+    }
+
+    /// Refreshes key set in a background task, unless a refresh is already in progress.
+    fn spawn_refresh(&self) {
+        if self.state.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let jwks = self.clone();
+
+        actix_web::rt::spawn(async move {
+            if let Err(err) = jwks.refresh().await {
+                debug!("Failed to refresh JWKS in background: {err}");
+            }
+
+            jwks.state.refreshing.store(false, Ordering::Release);
+        });
+    }
+}
+
+impl fmt::Debug for Jwks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jwks")
+            .field("url", &self.url)
+            .field("refresh_interval", &self.refresh_interval)
+            .field("keys", &self.state.keys.load().keys.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Errors that can occur when fetching or using a [`Jwks`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum JwksError {
+    /// Key set could not be fetched.
+    #[display(fmt = "Failed to fetch key set: {_0}")]
+    Fetch(#[error(not(source))] String),
+
+    /// Key set contains no key matching the token.
+    #[display(fmt = "No key matches the token's key ID.")]
+    KeyNotFound,
+
+    /// Key is invalid or of an unsupported type.
+    #[display(fmt = "Key is invalid: {_0}")]
+    InvalidKey(jsonwebtoken::errors::Error),
+}
+
+/// Errors that can occur when extracting a [`Jwt`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum JwtError {
+    /// Token is missing, malformed, or invalid.
+    ///
+    /// Responds with the status code and challenge of the inner error.
+    #[display(fmt = "{_0}")]
+    Unauthorized(BearerAuthError),
+
+    /// Key set could not be fetched or contains an invalid key.
+    #[display(fmt = "{_0}")]
+    KeySet(JwksError),
+
+    /// No [`JwtConfig`] is registered.
+    #[display(fmt = "JWT extractor is not configured.")]
+    NotConfigured,
+}
+
+impl ResponseError for JwtError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unauthorized(err) => err.status_code(),
+            Self::KeySet(_) | Self::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::Unauthorized(err) => err.error_response(),
+            _ => HttpResponse::new(self.status_code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest};
+    use jsonwebtoken::{get_current_timestamp, EncodingKey};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Claims {
+        sub: String,
+    }
+
+    fn config() -> JwtConfig {
+        let keys = serde_json::from_value(json!({
+            "keys": [
+                { "kty": "oct", "kid": "k1", "k": "c2VjcmV0" },
+                { "kty": "oct", "kid": "k2", "k": "b3RoZXI" },
+            ]
+        }))
+        .unwrap();
+
+        JwtConfig::new(Jwks::from_set(keys))
+            .algorithms([Algorithm::HS256])
+            .audience(["api"])
+    }
+
+    fn token(kid: Option<&str>, secret: &[u8], claims: serde_json::Value) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = kid.map(str::to_owned);
+
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_secret(secret)).unwrap()
+    }
+
+    async fn extract(token: &str) -> Result<Jwt<Claims>, JwtError> {
+        let req = TestRequest::default()
+            .app_data(config())
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+            .to_http_request();
+
+        Jwt::<Claims>::extract(&req).await
+    }
+
+    #[actix_web::test]
+    async fn valid_token() {
+        let exp = get_current_timestamp() + 60;
+        let token = token(
+            Some("k1"),
+            b"secret",
+            json!({ "sub": "alice", "aud": "api", "exp": exp }),
+        );
+
+        let jwt = extract(&token).await.unwrap();
+        assert_eq!(jwt.sub, "alice");
+        assert_eq!(jwt.header().kid.as_deref(), Some("k1"));
+    }
+
+    #[actix_web::test]
+    async fn invalid_tokens() {
+        let exp = get_current_timestamp() + 60;
+        let expired = get_current_timestamp() - 3600;
+
+        let tokens = [
+            // wrong key
+            token(
+                Some("k2"),
+                b"secret",
+                json!({ "sub": "a", "aud": "api", "exp": exp }),
+            ),
+            // unknown key ID
+            token(
+                Some("k3"),
+                b"secret",
+                json!({ "sub": "a", "aud": "api", "exp": exp }),
+            ),
+            // no key ID with multiple keys
+            token(
+                None,
+                b"secret",
+                json!({ "sub": "a", "aud": "api", "exp": exp }),
+            ),
+            // expired
+            token(
+                Some("k1"),
+                b"secret",
+                json!({ "sub": "a", "aud": "api", "exp": expired }),
+            ),
+            // wrong audience
+            token(
+                Some("k1"),
+                b"secret",
+                json!({ "sub": "a", "aud": "web", "exp": exp }),
+            ),
+            // missing expiry
+            token(Some("k1"), b"secret", json!({ "sub": "a", "aud": "api" })),
+            "not-a-jwt".to_owned(),
+        ];
+
+        for token in tokens {
+            let err = extract(&token).await.unwrap_err();
+            assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED, "{token}");
+
+            let res = err.error_response();
+            let challenge = res.headers().get(header::WWW_AUTHENTICATE).unwrap();
+            assert_eq!(challenge, r#"Bearer error="invalid_token""#);
+        }
+    }
+
+    #[actix_web::test]
+    async fn missing_token_and_config() {
+        let req = TestRequest::default().app_data(config()).to_http_request();
+        let err = Jwt::<Claims>::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer abc"))
+            .to_http_request();
+        let err = Jwt::<Claims>::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,68 @@
+//! Crate-wide extractor configuration.
+//!
+//! See [`LabConfig`] docs.
+
+use actix_web::{web, HttpRequest};
+
+/// Configuration shared by this crate's extractors.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data), on an app, scope, or
+/// resource. The most specific registered config is used.
+///
+/// # Strict Mode
+/// In [strict mode](Self::strict), the [`Json`], [`UrlEncodedForm`], and [`Query`] extractors
+/// reject payloads containing fields that the target type does not declare, instead of ignoring
+/// them. This catches typos in client requests early. The `400 Bad Request` response lists the
+/// unknown fields along with the most similar known field names, e.g.:
+///
+/// ```text
+/// Json deserialize error: unknown fields `nmae` (did you mean `name`?), `extra`
+/// ```
+///
+/// Fields are checked against the names, including aliases, that the target type declares to
+/// serde. Only top-level fields are checked. Types that are deserialized from a map, including
+/// structs with flattened fields, are not checked.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::extract::LabConfig;
+///
+/// App::new().service(web::scope("/api").app_data(LabConfig::new().strict()))
+/// # ;
+/// ```
+///
+/// [`Json`]: crate::extract::Json
+/// [`UrlEncodedForm`]: crate::extract::UrlEncodedForm
+/// [`Query`]: crate::extract::Query
+#[derive(Debug, Clone, Default)]
+pub struct LabConfig {
+    strict: bool,
+}
+
+const DEFAULT_CONFIG: LabConfig = LabConfig { strict: false };
+
+impl LabConfig {
+    /// Constructs new config with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables strict mode, where extractors reject unknown fields.
+    ///
+    /// By default, unknown fields are ignored.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    pub(crate) fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        self.strict
+    }
+}
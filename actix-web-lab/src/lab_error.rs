@@ -0,0 +1,308 @@
+//! Crate-wide error type and error code registry.
+//!
+//! See [`LabError`] and [`ErrorRegistry`] for docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    time::Duration,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::StatusCode,
+    Error, HttpResponse, ResponseError,
+};
+use ahash::AHashMap;
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+
+use crate::error::ErrorChain;
+
+type RemapFn = Rc<dyn Fn(&LabError) -> HttpResponse>;
+
+/// Errors produced by this crate's extractors and middleware when they are misconfigured or reject
+/// a request.
+///
+/// Payload extractors (e.g., [`Json`](crate::extract::Json)) keep their own error types, and
+/// errors from wrapped services and bodies are passed through unchanged.
+///
+/// Each variant has a stable [code](Self::code) which is included in its JSON error response and
+/// which can be used to customize responses using an [`ErrorRegistry`].
+///
+/// Error responses have the form:
+///
+/// ```json
+/// { "code": "app_data_not_configured", "message": "Requested application data is ..." }
+/// ```
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum LabError {
+    /// Application data required by an extractor was not registered.
+    ///
+    /// The type name is not included in the response but is logged at debug level.
+    #[display(fmt = "Requested application data is not configured correctly. \
+        View/enable debug logs for more details.")]
+    AppDataNotConfigured {
+        /// Type name of the missing app data.
+        type_name: &'static str,
+    },
+
+    /// Middleware required by an extractor was not registered.
+    #[display(
+        fmt = "Required middleware is not registered. View/enable debug logs for more details."
+    )]
+    MiddlewareNotRegistered {
+        /// Name of the missing middleware.
+        middleware: &'static str,
+    },
+
+    /// A request extension required by the [`Ext`](crate::extract::Ext) extractor was not found.
+    ///
+    /// This usually means that the middleware which inserts it is not registered for the route.
+    /// The type name is not included in the response but is logged at debug level.
+    #[display(fmt = "Requested extension was not found. \
+        View/enable debug logs for more details.")]
+    ExtensionNotFound {
+        /// Type name of the missing extension.
+        type_name: &'static str,
+    },
+
+    /// Connection data required by an extractor was not registered.
+    ///
+    /// The type name is not included in the response but is logged at debug level.
+    #[display(fmt = "Requested connection data is not configured correctly. \
+        View/enable debug logs for more details.")]
+    ConnectDataNotConfigured {
+        /// Type name of the missing connection data.
+        type_name: &'static str,
+    },
+
+    /// Request was rejected by the [`StrictHttp`](crate::middleware::StrictHttp) middleware.
+    #[display(fmt = "Request was rejected: {reason}.")]
+    SuspiciousRequest {
+        /// Reason the request was rejected.
+        reason: &'static str,
+    },
+
+    /// Handler did not respond within the timeout set by the
+    /// [`RoutePolicy`](crate::middleware::RoutePolicy) middleware.
+    #[display(fmt = "Request handler did not respond in time.")]
+    HandlerTimeout {
+        /// Configured timeout.
+        timeout: Duration,
+    },
+}
+
+impl LabError {
+    /// Returns the stable error code for this error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::AppDataNotConfigured { .. } => "app_data_not_configured",
+            Self::MiddlewareNotRegistered { .. } => "middleware_not_registered",
+            Self::ExtensionNotFound { .. } => "extension_not_found",
+            Self::ConnectDataNotConfigured { .. } => "connect_data_not_configured",
+            Self::SuspiciousRequest { .. } => "suspicious_request",
+            Self::HandlerTimeout { .. } => "handler_timeout",
+        }
+    }
+}
+
+impl ResponseError for LabError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::AppDataNotConfigured { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::MiddlewareNotRegistered { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ExtensionNotFound { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ConnectDataNotConfigured { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::SuspiciousRequest { .. } => StatusCode::BAD_REQUEST,
+            Self::HandlerTimeout { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+        }));
+
+        ErrorChain::capture(self).insert_into(&mut res);
+
+        res
+    }
+}
+
+/// A middleware for remapping [`LabError`] responses by error code.
+///
+/// Responses resulting from a `LabError` whose code has been registered are replaced; all other
+/// responses are passed through unchanged.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::StatusCode, App, HttpResponse};
+/// use actix_web_lab::error::ErrorRegistry;
+///
+/// App::new().wrap(
+///     ErrorRegistry::new()
+///         .status("middleware_not_registered", StatusCode::SERVICE_UNAVAILABLE)
+///         .handler("app_data_not_configured", |err| {
+///             HttpResponse::InternalServerError().body(format!("oops: {}", err.code()))
+///         }),
+/// )
+/// # ;
+/// ```
+#[derive(Clone, Default)]
+pub struct ErrorRegistry {
+    remaps: Rc<AHashMap<&'static str, RemapFn>>,
+}
+
+impl ErrorRegistry {
+    /// Constructs a new registry with no remapped codes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler that produces the response for errors with the given `code`.
+    pub fn handler(
+        mut self,
+        code: &'static str,
+        handler: impl Fn(&LabError) -> HttpResponse + 'static,
+    ) -> Self {
+        Rc::get_mut(&mut self.remaps)
+            .expect("ErrorRegistry instance should not be cloned before configuration")
+            .insert(code, Rc::new(handler));
+
+        self
+    }
+
+    /// Overrides the status code of responses for errors with the given `code`.
+    ///
+    /// The default JSON body is kept.
+    pub fn status(self, code: &'static str, status: StatusCode) -> Self {
+        self.handler(code, move |err| {
+            let mut res = err.error_response();
+            *res.status_mut() = status;
+            res
+        })
+    }
+}
+
+impl fmt::Debug for ErrorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorRegistry")
+            .field("codes", &self.remaps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ErrorRegistry
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ErrorRegistryMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ErrorRegistryMiddleware {
+            service: Rc::new(service),
+            remaps: Rc::clone(&self.remaps),
+        }))
+    }
+}
+
+pub struct ErrorRegistryMiddleware<S> {
+    service: Rc<S>,
+    remaps: Rc<AHashMap<&'static str, RemapFn>>,
+}
+
+impl<S, B> Service<ServiceRequest> for ErrorRegistryMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let remaps = Rc::clone(&self.remaps);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let remapped = res
+                .response()
+                .error()
+                .and_then(|err| err.as_error::<LabError>())
+                .and_then(|err| {
+                    let remap = remaps.get(err.code())?;
+
+                    let mut res = remap(err);
+                    ErrorChain::capture(err).insert_into(&mut res);
+                    Some(res)
+                });
+
+            Ok(match remapped {
+                Some(new_res) => res.into_response(new_res).map_into_right_body(),
+                None => res.map_into_left_body(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    use super::*;
+    use crate::extract::SwapData;
+
+    #[actix_web::test]
+    async fn json_error_response() {
+        let res = LabError::AppDataNotConfigured { type_name: "u32" }.error_response();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+        assert_eq!(body["code"], "app_data_not_configured");
+        assert!(!body["message"].as_str().unwrap().contains("u32"));
+
+        let res = LabError::AppDataNotConfigured { type_name: "u32" }.error_response();
+        assert!(res.extensions().get::<ErrorChain>().is_some());
+    }
+
+    #[actix_web::test]
+    async fn registry_remaps_codes() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    ErrorRegistry::new().handler("app_data_not_configured", |_| {
+                        HttpResponse::ServiceUnavailable().body("not ready")
+                    }),
+                )
+                .route("/", web::to(|_: SwapData<u32>| async { "" }))
+                .route("/other", web::to(|| async { HttpResponse::BadRequest() })),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(test::read_body(res).await, "not ready");
+
+        let req = TestRequest::default().uri("/other").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -5,11 +5,13 @@ use std::{
     rc::Rc,
 };
 
-use actix_web::{dev, error, Error, FromRequest, HttpRequest};
+use actix_web::{dev, Error, FromRequest, HttpRequest};
 use futures_core::future::LocalBoxFuture;
 use tokio::sync::OnceCell;
 use tracing::debug;
 
+use crate::error::LabError;
+
 /// A lazy extractor for thread-local data.
 ///
 /// Using `LazyData` as an extractor will not initialize the data; [`get`](Self::get) must be used.
@@ -87,10 +89,9 @@ impl<T: 'static> FromRequest for LazyData<T> {
                 req.match_name().unwrap_or_else(|| req.path())
             );
 
-            ready(Err(error::ErrorInternalServerError(
-                "Requested application data is not configured correctly. \
-                View/enable debug logs for more details.",
-            )))
+            ready(Err(Error::from(LabError::AppDataNotConfigured {
+                type_name: core::any::type_name::<T>(),
+            })))
         }
     }
 }
@@ -17,6 +17,8 @@
 //! - Items that graduate to Actix Web crate will be marked deprecated here for a reasonable amount
 //!   of time so you can migrate.
 //! - Migrating will often be as easy as dropping the `_lab` suffix from imports when migrating.
+//! - Items with upstream counterparts are also available under the same paths in [`compat`], so
+//!   migrating can be done by changing a single import. See also the [`prelude`].
 //!
 //! [examples]: https://github.com/robjtede/actix-web-lab/tree/HEAD/actix-web-lab/examples
 
@@ -24,66 +26,183 @@
 #![warn(future_incompatible, missing_docs)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+mod api_key;
+#[cfg(feature = "awc")]
+mod awc_client;
+mod basic_auth;
+mod bearer_auth;
+mod block_stream;
 mod body_async_write;
+mod body_catch_panic;
 mod body_channel;
+mod body_coalesce;
+mod body_combinators;
 mod body_limit;
+mod body_progress;
+mod body_spill;
 mod bytes;
+mod cache_aside;
 mod cache_control;
+mod canary;
+mod canonical_headers;
+mod canonical_query;
 mod catch_panic;
 #[cfg(feature = "cbor")]
 mod cbor;
+#[cfg(feature = "cbor")]
+mod cbor_extract;
+mod checked_routes;
+mod client_ip;
+mod clock;
+mod connect_data;
+mod connection_meta;
+mod content_digest;
 mod content_length;
 mod csv;
 mod display_stream;
+mod entropy;
 mod err_handler;
+mod error_chain;
+mod ext;
+mod fallback;
+mod forward_headers;
 mod forwarded;
+mod hal;
 mod host;
 mod html;
+mod http_signatures;
 mod infallible_body_stream;
 mod json;
+#[cfg(feature = "jsonapi")]
+mod json_api;
+mod json_de;
+mod json_or_form;
+#[cfg(feature = "jwt")]
+mod jwt;
+mod lab_config;
+mod lab_error;
 mod lazy_data;
 mod load_shed;
 mod local_data;
+mod micro_cache;
 mod middleware_from_fn;
 mod middleware_map_response;
 mod middleware_map_response_body;
+mod middleware_stack;
+mod middleware_trace;
+mod middleware_when;
 #[cfg(feature = "msgpack")]
 mod msgpack;
-mod ndjson;
+#[cfg(feature = "multipart")]
+mod multipart_form;
+mod ndjson_decoder;
 mod normalize_path;
+mod odata;
 mod panic_reporter;
+mod partial_response;
 mod path;
+mod preferred_language;
+mod problem_details;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+#[cfg(feature = "protobuf")]
+mod protobuf_extract;
+#[cfg(feature = "qs")]
+mod qs_query;
 mod query;
+mod redact_json;
 mod redirect_to_https;
 mod redirect_to_non_www;
 mod redirect_to_www;
+mod replayable_body;
+#[cfg(feature = "arena")]
+mod request_arena;
+mod request_context;
+mod request_id;
 mod request_signature;
+mod request_snapshot;
+mod response_ext;
+mod root_span;
+mod route_policy;
+mod rsql;
+mod scrub;
+mod server_stats;
+mod sharded_map;
+#[cfg(any(feature = "ed25519", feature = "hmac-sha256"))]
+mod signature_schemes;
 #[cfg(feature = "spa")]
 mod spa;
+#[cfg(feature = "awc")]
+mod sse_client;
+mod sse_compress;
+#[cfg(feature = "postgres")]
+mod sse_postgres;
+#[cfg(feature = "fs-watch")]
+mod sse_watch_path;
+mod streamed;
+mod strict;
+mod strict_http;
 mod strict_transport_security;
+mod structured_field;
 mod swap_data;
+mod switch_service;
+#[cfg(feature = "proptest")]
+mod test_arbitrary;
 #[cfg(test)]
 mod test_header_macros;
+mod test_http_file;
+mod test_multipart;
 mod test_request_macros;
 mod test_response_macros;
 mod test_services;
+mod test_streaming;
+mod text;
+#[cfg(feature = "tower")]
+mod tower_interop;
+mod transactional;
+mod trusted_proxies;
+mod uri;
 mod url_encoded_form;
+mod url_for;
+mod user_agent;
+#[cfg(feature = "garde")]
+mod validated;
+#[cfg(feature = "validator")]
+mod validated_json;
+mod warmup;
+mod with_cleanup;
 mod x_forwarded_prefix;
+#[cfg(feature = "xml")]
+mod xml;
+#[cfg(feature = "yaml")]
+mod yaml;
 
 // public API
+pub mod bench_support;
 pub mod body;
+pub mod bus;
+#[cfg(feature = "awc")]
+pub mod client;
+pub mod compat;
+pub mod error;
 pub mod extract;
 pub mod guard;
 pub mod header;
 pub mod middleware;
+pub mod ndjson;
+pub mod prelude;
+pub mod proxy_protocol;
 pub mod respond;
+pub mod scheduler;
 pub mod sse;
+pub mod stream_bridge;
 pub mod test;
+pub mod uploads;
 pub mod util;
 pub mod web;
 
 #[cfg(feature = "derive")]
-pub use actix_web_lab_derive::FromRequest;
+pub use actix_web_lab_derive::{delete, get, patch, post, put, FromRequest};
 
 // private re-exports for macros
 #[doc(hidden)]
@@ -93,6 +212,19 @@ pub mod __reexports {
     pub use ::serde_json;
     pub use ::tokio;
     pub use ::tracing;
+
+    pub use crate::scrub::span_target;
+
+    #[cfg(feature = "multipart")]
+    pub mod multipart {
+        pub use actix_multipart::Field;
+        pub use futures_core::future::LocalBoxFuture;
+
+        pub use crate::multipart_form::{
+            field_name, read_field, skip_field, take_field, FieldLimits, MultipartCollect,
+            MultipartFormError, MultipartState,
+        };
+    }
 }
 
 pub(crate) type BoxError = Box<dyn std::error::Error>;
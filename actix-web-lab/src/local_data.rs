@@ -1,9 +1,11 @@
 use std::{any::type_name, ops::Deref, rc::Rc};
 
 use actix_utils::future::{err, ok, Ready};
-use actix_web::{dev::Payload, error, Error, FromRequest, HttpRequest};
+use actix_web::{dev::Payload, Error, FromRequest, HttpRequest};
 use tracing::debug;
 
+use crate::error::LabError;
+
 /// A thread-local equivalent to [`SharedData`](crate::extract::SharedData).
 #[doc(alias = "state")]
 #[derive(Debug)]
@@ -53,10 +55,9 @@ impl<T: ?Sized + 'static> FromRequest for LocalData<T> {
                 req.match_name().unwrap_or_else(|| req.path())
             );
 
-            err(error::ErrorInternalServerError(
-                "Requested application data is not configured correctly. \
-                View/enable debug logs for more details.",
-            ))
+            err(Error::from(LabError::AppDataNotConfigured {
+                type_name: type_name::<T>(),
+            }))
         }
     }
 }
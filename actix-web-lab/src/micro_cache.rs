@@ -0,0 +1,579 @@
+//! Micro-caching middleware.
+//!
+//! See [`MicroCache`] docs.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_web::{
+    body::{self, BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error,
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    Error, HttpRequest, HttpResponse,
+};
+use ahash::AHashMap;
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+use tokio::sync::watch;
+
+use crate::{
+    util::{Clock, ShardedMap, SystemClock},
+    BoxError,
+};
+
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+const DEFAULT_MAX_BODY_SIZE: u64 = 64 * 1024;
+
+type InFlight = Arc<Mutex<AHashMap<String, watch::Receiver<Option<Arc<CachedResponse>>>>>>;
+
+/// A middleware that caches successful `GET` responses for a very short time.
+///
+/// Micro-caching absorbs bursts of identical requests to hot endpoints: with a TTL of even one
+/// second, an endpoint receiving thousands of requests per second is only computed once per
+/// second. Concurrent requests which miss the cache are coalesced so that only one of them calls
+/// the inner service while the others wait for and share its response.
+///
+/// # Cacheability
+/// Only `GET` requests without `Authorization` or `Cookie` headers are cached, keyed by `Host`
+/// header (or URI authority), path, and query string. Forwarding headers such as
+/// `X-Forwarded-Host` do not affect the key. A response is cached only if:
+/// - its status is `200 OK`;
+/// - it does not set cookies;
+/// - its `Cache-Control` header does not contain `no-store`, `no-cache`, or `private`;
+/// - its `Vary` header is not `*`;
+/// - its body has a known size no larger than the [limit](Self::max_body_size).
+///
+/// Headers listed in a cached response's `Vary` header must have the same values in later
+/// requests for them to be served from the cache. To keep memory use low, only the most recent
+/// variant of each URL is kept.
+///
+/// Clones share the same storage, so a cache constructed outside of the `HttpServer` app factory
+/// is shared across workers.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{web, App, HttpServer};
+/// use actix_web_lab::middleware::MicroCache;
+///
+/// # fn run() -> std::io::Result<()> {
+/// let cache = MicroCache::new(Duration::from_secs(1)).max_entries(256);
+///
+/// HttpServer::new(move || {
+///     App::new().service(
+///         web::resource("/leaderboard")
+///             .wrap(cache.clone())
+///             .to(|| async { "expensive to compute" }),
+///     )
+/// })
+/// # ; Ok(()) }
+/// ```
+#[derive(Clone)]
+pub struct MicroCache {
+    ttl: Duration,
+    max_entries: usize,
+    max_body_size: u64,
+    clock: Arc<dyn Clock>,
+    entries: ShardedMap<String, Arc<CachedResponse>>,
+    in_flight: InFlight,
+}
+
+impl MicroCache {
+    /// Constructs new micro-cache middleware which caches responses for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+
+        Self {
+            ttl,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            entries: Self::storage(ttl, DEFAULT_MAX_ENTRIES, &clock),
+            clock,
+            in_flight: InFlight::default(),
+        }
+    }
+
+    /// Sets maximum number of cached URLs, after which the least recently used are evicted.
+    ///
+    /// Defaults to 1024. Discards any cached responses.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self.entries = Self::storage(self.ttl, max_entries, &self.clock);
+        self
+    }
+
+    /// Sets maximum size of response bodies which will be cached.
+    ///
+    /// Defaults to 64 KiB.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets the clock used to determine when cached responses expire.
+    ///
+    /// Defaults to [`SystemClock`]. Discards any cached responses.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self.entries = Self::storage(self.ttl, self.max_entries, &self.clock);
+        self
+    }
+
+    fn storage(
+        ttl: Duration,
+        max_entries: usize,
+        clock: &Arc<dyn Clock>,
+    ) -> ShardedMap<String, Arc<CachedResponse>> {
+        ShardedMap::new(max_entries)
+            .default_ttl(ttl)
+            .clock(Arc::clone(clock))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MicroCache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = MicroCacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MicroCacheMiddleware {
+            service: Rc::new(service),
+            cache: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`MicroCache`] middleware.
+pub struct MicroCacheMiddleware<S> {
+    service: Rc<S>,
+    cache: MicroCache,
+}
+
+impl<S, B> Service<ServiceRequest> for MicroCacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let cache = self.cache.clone();
+
+        Box::pin(async move {
+            let personal = req.headers().contains_key(header::AUTHORIZATION)
+                || req.headers().contains_key(header::COOKIE);
+
+            if req.method() != Method::GET || personal {
+                return passthrough(&service, req).await;
+            }
+
+            // forwarding headers are not used since any client could set them to poison the
+            // entries of other hosts
+            let key = {
+                let host = req
+                    .headers()
+                    .get(header::HOST)
+                    .and_then(|host| host.to_str().ok())
+                    .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
+                    .unwrap_or_else(|| req.app_config().host());
+                let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+                format!("{host}{path_and_query}")
+            };
+
+            if let Some(cached) = cache.entries.get(&key) {
+                if cached.matches(req.headers()) {
+                    return Ok(cached.respond(req.into_parts().0));
+                }
+            }
+
+            // coalesce concurrent misses; waiting requests for a different variant call the service
+            let flight = {
+                let mut in_flight = cache.in_flight.lock().unwrap();
+
+                match in_flight.get(&key) {
+                    Some(rx) => Err(rx.clone()),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        in_flight.insert(key.clone(), rx);
+
+                        Ok(Flight {
+                            key,
+                            tx,
+                            in_flight: Arc::clone(&cache.in_flight),
+                        })
+                    }
+                }
+            };
+
+            let flight = match flight {
+                Ok(flight) => flight,
+
+                Err(mut rx) => {
+                    // an error means the leader did not produce a cacheable response
+                    let _ = rx.changed().await;
+
+                    let cached = rx.borrow().clone();
+
+                    return match cached {
+                        Some(cached) if cached.matches(req.headers()) => {
+                            Ok(cached.respond(req.into_parts().0))
+                        }
+                        _ => passthrough(&service, req).await,
+                    };
+                }
+            };
+
+            let vary_req_headers = req.headers().clone();
+            let res = service.call(req).await?;
+
+            let Some(vary) = cacheable_vary(&res, cache.max_body_size) else {
+                return Ok(res.map_into_left_body());
+            };
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+
+            let body = body::to_bytes(body).await.map_err(|err| {
+                let err: BoxError = err.into();
+                error::ErrorInternalServerError(err.to_string())
+            })?;
+
+            let cached = Arc::new(CachedResponse {
+                status: res.status(),
+                headers: res.headers().clone(),
+                body,
+                vary: vary
+                    .into_iter()
+                    .map(|name| {
+                        let val = vary_req_headers.get(&name).cloned();
+                        (name, val)
+                    })
+                    .collect(),
+            });
+
+            cache
+                .entries
+                .insert(flight.key.clone(), Arc::clone(&cached));
+            let _ = flight.tx.send(Some(Arc::clone(&cached)));
+
+            Ok(cached.respond(req))
+        })
+    }
+}
+
+async fn passthrough<S, B>(
+    service: &S,
+    req: ServiceRequest,
+) -> Result<ServiceResponse<EitherBody<B, Bytes>>, Error>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    Ok(service.call(req).await?.map_into_left_body())
+}
+
+/// Returns names of headers the response varies on, if it is cacheable.
+fn cacheable_vary<B: MessageBody>(
+    res: &ServiceResponse<B>,
+    max_body_size: u64,
+) -> Option<Vec<HeaderName>> {
+    if res.status() != StatusCode::OK || res.headers().contains_key(header::SET_COOKIE) {
+        return None;
+    }
+
+    match res.response().body().size() {
+        BodySize::Sized(size) if size <= max_body_size => {}
+        _ => return None,
+    }
+
+    let uncacheable = res
+        .headers()
+        .get_all(header::CACHE_CONTROL)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(|directive| directive.trim().split('=').next().unwrap_or_default())
+        .any(|directive| {
+            ["no-store", "no-cache", "private"]
+                .iter()
+                .any(|uncacheable| directive.eq_ignore_ascii_case(uncacheable))
+        });
+
+    if uncacheable {
+        return None;
+    }
+
+    let mut vary = Vec::new();
+
+    for name in res
+        .headers()
+        .get_all(header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if name == "*" {
+            return None;
+        }
+
+        vary.push(HeaderName::try_from(name).ok()?);
+    }
+
+    Some(vary)
+}
+
+/// Marks a request as the one computing the response for its key.
+///
+/// When dropped, other requests for the same key are released; if no response was sent, they
+/// each call the inner service.
+struct Flight {
+    key: String,
+    tx: watch::Sender<Option<Arc<CachedResponse>>>,
+    in_flight: InFlight,
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        self.in_flight.lock().unwrap().remove(&self.key);
+    }
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl CachedResponse {
+    /// Returns true if request headers match those this response varies on.
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, val)| headers.get(name) == val.as_ref())
+    }
+
+    fn respond<B>(&self, req: HttpRequest) -> ServiceResponse<EitherBody<B, Bytes>> {
+        let mut res = HttpResponse::with_body(self.status, self.body.clone());
+        *res.headers_mut() = self.headers.clone();
+
+        ServiceResponse::new(req, res.map_into_right_body())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_web::{test, web, App, Route};
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::test::MockClock;
+
+    fn counting_handler(hits: &Arc<AtomicUsize>, make_res: fn() -> HttpResponse) -> Route {
+        let hits = Arc::clone(hits);
+
+        web::to(move || {
+            let hits = Arc::clone(&hits);
+
+            async move {
+                hits.fetch_add(1, Ordering::SeqCst);
+                actix_web::rt::task::yield_now().await;
+                make_res()
+            }
+        })
+    }
+
+    #[actix_web::test]
+    async fn caches_until_expiry() {
+        let clock = MockClock::new();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1)).clock(clock.clone());
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cache)
+                .default_service(counting_handler(&hits, || HttpResponse::Ok().body("hot"))),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let req = test::TestRequest::with_uri("/hot").to_request();
+            assert_eq!(test::call_and_read_body(&app, req).await, "hot");
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::from_secs(2));
+
+        let req = test::TestRequest::with_uri("/hot").to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // other URLs and non-GET requests are not served from the cache
+        let req = test::TestRequest::with_uri("/hot?page=2").to_request();
+        test::call_service(&app, req).await;
+        let req = test::TestRequest::post().uri("/hot").to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[actix_web::test]
+    async fn coalesces_concurrent_misses() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cache)
+                .default_service(counting_handler(&hits, || HttpResponse::Ok().body("hot"))),
+        )
+        .await;
+
+        let responses = join_all((0..5).map(|_| {
+            let req = test::TestRequest::with_uri("/hot").to_request();
+            test::call_service(&app, req)
+        }))
+        .await;
+
+        assert!(responses.iter().all(|res| res.status() == StatusCode::OK));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn skips_uncacheable_responses() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1));
+
+        let app = test::init_service(App::new().wrap(cache).default_service(counting_handler(
+            &hits,
+            || {
+                HttpResponse::Ok()
+                    .insert_header((header::CACHE_CONTROL, "private, max-age=60"))
+                    .body("personal")
+            },
+        )))
+        .await;
+
+        for _ in 0..2 {
+            let req = test::TestRequest::with_uri("/me").to_request();
+            test::call_service(&app, req).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn skips_cookies() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cache)
+                .route(
+                    "/session",
+                    counting_handler(&hits, || {
+                        HttpResponse::Ok()
+                            .insert_header((header::SET_COOKIE, "session=abc"))
+                            .body("welcome")
+                    }),
+                )
+                .default_service(counting_handler(&hits, || HttpResponse::Ok().body("hot"))),
+        )
+        .await;
+
+        // responses which set cookies are not cached
+        for _ in 0..2 {
+            let req = test::TestRequest::with_uri("/session").to_request();
+            test::call_service(&app, req).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // requests with cookies neither read from nor populate the cache
+        for _ in 0..2 {
+            let req = test::TestRequest::with_uri("/hot")
+                .insert_header((header::COOKIE, "session=abc"))
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        let req = test::TestRequest::with_uri("/hot").to_request();
+        test::call_service(&app, req).await;
+        let req = test::TestRequest::with_uri("/hot")
+            .insert_header((header::COOKIE, "session=abc"))
+            .to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 6);
+    }
+
+    #[actix_web::test]
+    async fn ignores_forwarded_host() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cache)
+                .default_service(counting_handler(&hits, || HttpResponse::Ok().body("hot"))),
+        )
+        .await;
+
+        // a client can not populate the entry of another host using forwarding headers
+        let req = test::TestRequest::with_uri("/")
+            .insert_header((header::HOST, "attacker.example"))
+            .insert_header(("x-forwarded-host", "victim.example"))
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::with_uri("/")
+            .insert_header((header::HOST, "victim.example"))
+            .to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn respects_vary() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let cache = MicroCache::new(Duration::from_secs(1));
+
+        let app = test::init_service(App::new().wrap(cache).default_service(counting_handler(
+            &hits,
+            || {
+                HttpResponse::Ok()
+                    .insert_header((header::VARY, "accept-language"))
+                    .body("hello")
+            },
+        )))
+        .await;
+
+        for lang in ["en", "en", "fr"] {
+            let req = test::TestRequest::with_uri("/")
+                .insert_header((header::ACCEPT_LANGUAGE, lang))
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+}
@@ -3,15 +3,41 @@
 //! Analogous to the `middleware` module in Actix Web.
 
 pub use crate::{
+    canary::{Canary, CanaryMiddleware},
+    canonical_headers::NormalizeHeaders,
+    canonical_query::CanonicalQuery,
     catch_panic::CatchPanic,
+    content_digest::{BodyDigest, BodyDigestMiddleware, ContentDigestError},
     err_handler::ErrorHandlers,
+    http_signatures::{
+        HttpSignatureError, HttpSignatureKey, HttpSignatures, HttpSignaturesMiddleware,
+        SignatureKeyResolver,
+    },
     load_shed::LoadShed,
+    micro_cache::MicroCache,
     middleware_from_fn::{from_fn, MiddlewareFn, Next},
     middleware_map_response::{map_response, MapResMiddleware},
     middleware_map_response_body::{map_response_body, MapResBodyMiddleware},
+    middleware_stack::{Identity, MiddlewareStack, Stacked},
+    middleware_trace::{traced, MiddlewareLayer, MiddlewareTrace, Traced, TracedMiddleware},
+    middleware_when::{when, SharedService, When, WhenMiddleware},
     normalize_path::NormalizePath,
     panic_reporter::PanicReporter,
+    redact_json::{RedactJson, RedactJsonMiddleware},
     redirect_to_https::RedirectHttps,
     redirect_to_non_www::redirect_to_non_www,
     redirect_to_www::redirect_to_www,
+    request_context::RequestContextMiddleware,
+    request_id::{request_id, RequestIdMiddleware, RequestIdService},
+    root_span::RequestSpan,
+    route_policy::{RoutePolicy, RoutePolicyMiddleware},
+    server_stats::{ServerStats, ServerStatsMiddleware, WorkerStats},
+    strict_http::StrictHttp,
+    transactional::{Transactional, TransactionalMiddleware, TxProvider},
+    warmup::{Warmup, WarmupMiddleware},
 };
+
+#[cfg(feature = "arena")]
+pub use crate::request_arena::{ArenaStats, RequestArenaMiddleware, RequestArenaService};
+#[cfg(feature = "tower")]
+pub use crate::tower_interop::{FromTowerService, IntoTowerService, TowerLayer};
@@ -0,0 +1,239 @@
+//! Middleware composition.
+//!
+//! See [`MiddlewareStack`] docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_service::Transform;
+use futures_core::future::LocalBoxFuture;
+
+/// Composes several middleware into a single, reusable unit.
+///
+/// Middleware added to a stack with [`wrap`](Self::wrap) are ordered in the same way as they
+/// would be if they were added to an `App` or `Scope` directly: the last middleware added is the
+/// outermost and is the first to see incoming requests.
+///
+/// A stack is itself a middleware, so a group of middleware that is commonly used together can be
+/// defined once and then wrapped around any number of scopes with a single `.wrap()` call.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{DefaultHeaders, Logger},
+///     web, App, HttpResponse,
+/// };
+/// use actix_web_lab::middleware::{CatchPanic, MiddlewareStack, RoutePolicy};
+///
+/// let api_defaults = MiddlewareStack::new()
+///     .wrap(RoutePolicy::new().body_limit(64 * 1024))
+///     .wrap(DefaultHeaders::new().add(("x-api-version", "1")))
+///     .wrap(CatchPanic::default());
+///
+/// App::new()
+///     .wrap(Logger::default())
+///     .service(
+///         web::scope("/api")
+///             .wrap(api_defaults)
+///             .route("/", web::to(HttpResponse::Ok)),
+///     )
+/// # ;
+/// ```
+///
+/// Stacks can be cloned, sharing their middleware, to wrap several scopes:
+///
+/// ```
+/// use actix_web::{middleware::DefaultHeaders, web, App};
+/// use actix_web_lab::middleware::MiddlewareStack;
+///
+/// let headers = MiddlewareStack::new()
+///     .wrap(DefaultHeaders::new().add(("x-frame-options", "DENY")))
+///     .wrap(DefaultHeaders::new().add(("x-content-type-options", "nosniff")));
+///
+/// App::new()
+///     .service(web::scope("/a").wrap(headers.clone()))
+///     .service(web::scope("/b").wrap(headers))
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct MiddlewareStack<T = Identity> {
+    transform: T,
+}
+
+impl MiddlewareStack {
+    /// Constructs new, empty middleware stack.
+    pub fn new() -> Self {
+        Self {
+            transform: Identity,
+        }
+    }
+}
+
+impl Default for MiddlewareStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> MiddlewareStack<T> {
+    /// Adds middleware to the stack, outside of all previously added middleware.
+    pub fn wrap<M>(self, mw: M) -> MiddlewareStack<Stacked<T, M>> {
+        MiddlewareStack {
+            transform: Stacked {
+                inner: self.transform,
+                outer: Rc::new(mw),
+            },
+        }
+    }
+}
+
+impl<S, Req, T> Transform<S, Req> for MiddlewareStack<T>
+where
+    T: Transform<S, Req>,
+{
+    type Response = T::Response;
+    type Error = T::Error;
+    type Transform = T::Transform;
+    type InitError = T::InitError;
+    type Future = T::Future;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        self.transform.new_transform(service)
+    }
+}
+
+/// Middleware which passes requests through unchanged; the base of an empty [`MiddlewareStack`].
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct Identity;
+
+impl<S, Req> Transform<S, Req> for Identity
+where
+    S: actix_service::Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Transform = S;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(service))
+    }
+}
+
+/// Pair of middleware in a [`MiddlewareStack`], where `outer` wraps `inner`.
+pub struct Stacked<Inner, Outer> {
+    inner: Inner,
+    outer: Rc<Outer>,
+}
+
+impl<Inner: Clone, Outer> Clone for Stacked<Inner, Outer> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            outer: Rc::clone(&self.outer),
+        }
+    }
+}
+
+impl<Inner: fmt::Debug, Outer: fmt::Debug> fmt::Debug for Stacked<Inner, Outer> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stacked")
+            .field("inner", &self.inner)
+            .field("outer", &self.outer)
+            .finish()
+    }
+}
+
+impl<S, Req, Inner, Outer> Transform<S, Req> for Stacked<Inner, Outer>
+where
+    Inner: Transform<S, Req>,
+    Inner::Future: 'static,
+    Outer: Transform<Inner::Transform, Req, InitError = Inner::InitError> + 'static,
+{
+    type Response = Outer::Response;
+    type Error = Outer::Error;
+    type Transform = Outer::Transform;
+    type InitError = Outer::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let inner_fut = self.inner.new_transform(service);
+        let outer = Rc::clone(&self.outer);
+
+        Box::pin(async move {
+            let inner = inner_fut.await?;
+            outer.new_transform(inner).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::header::HeaderValue,
+        middleware::DefaultHeaders,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn applies_in_wrap_order() {
+        let stack = MiddlewareStack::new()
+            .wrap(DefaultHeaders::new().add(("x-order", "inner")))
+            .wrap(
+                DefaultHeaders::new()
+                    .add(("x-order", "outer"))
+                    .add(("x-outer", "1")),
+            );
+
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::scope("/a")
+                        .wrap(stack.clone())
+                        .default_service(web::to(HttpResponse::Ok)),
+                )
+                .service(
+                    web::scope("/b")
+                        .wrap(stack)
+                        .default_service(web::to(HttpResponse::Ok)),
+                )
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for path in ["/a", "/b"] {
+            let res = test::call_service(&app, TestRequest::with_uri(path).to_request()).await;
+
+            // inner middleware sets header first so outer default is not applied
+            assert_eq!(
+                res.headers().get("x-order"),
+                Some(&HeaderValue::from_static("inner")),
+            );
+            assert!(res.headers().contains_key("x-outer"));
+        }
+
+        let res = test::call_service(&app, TestRequest::with_uri("/c").to_request()).await;
+        assert!(!res.headers().contains_key("x-order"));
+    }
+
+    #[actix_web::test]
+    async fn empty_stack() {
+        let app = test::init_service(
+            App::new()
+                .wrap(MiddlewareStack::new())
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert!(res.status().is_success());
+    }
+}
@@ -0,0 +1,361 @@
+//! Middleware ordering diagnostics.
+//!
+//! See [`traced`] docs.
+
+use std::{
+    cell::RefCell,
+    fmt::{self, Write as _},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage as _,
+};
+use futures_core::future::LocalBoxFuture;
+
+/// Response header containing the middleware trace, when exposed.
+const MIDDLEWARE_TRACE: HeaderName = HeaderName::from_static("x-middleware-trace");
+
+/// Wraps middleware so that its position and latency are recorded in a [`MiddlewareTrace`].
+///
+/// Each traced layer records when it is entered and how long the wrapped middleware, including
+/// everything inside it, took to produce a response. Once the outermost traced layer completes, the
+/// [`MiddlewareTrace`] is inserted into the response's extensions and, if [exposed], into an
+/// `X-Middleware-Trace` header listing layers from outermost to innermost along with the time
+/// spent in each one.
+///
+/// Untraced middleware is not recorded, and timings do not include streaming the response body.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{DefaultHeaders, Logger, NormalizePath},
+///     App,
+/// };
+/// use actix_web_lab::middleware::traced;
+///
+/// App::new()
+///     .wrap(traced("DefaultHeaders", DefaultHeaders::new()))
+///     .wrap(traced("NormalizePath", NormalizePath::trim()))
+///     .wrap(traced("Logger", Logger::default()).expose_header(true))
+/// # ;
+/// ```
+///
+/// With this configuration, responses include the header:
+///
+/// ```plain
+/// x-middleware-trace: Logger;dur=0.061, NormalizePath;dur=0.020, DefaultHeaders;dur=0.734
+/// ```
+///
+/// [exposed]: Traced::expose_header()
+pub fn traced<T>(name: &'static str, transform: T) -> Traced<T> {
+    Traced {
+        name,
+        transform,
+        expose_header: false,
+    }
+}
+
+/// Middleware wrapper which records a layer in the [`MiddlewareTrace`].
+///
+/// Constructed using [`traced()`].
+#[derive(Debug, Clone)]
+pub struct Traced<T> {
+    name: &'static str,
+    transform: T,
+    expose_header: bool,
+}
+
+impl<T> Traced<T> {
+    /// Sets whether the recorded trace is added to responses in an `X-Middleware-Trace` header.
+    ///
+    /// Only has an effect on the outermost traced layer. Since the header reveals application
+    /// internals, it should only be enabled in development; e.g., using
+    /// `.expose_header(cfg!(debug_assertions))`.
+    pub fn expose_header(mut self, expose_header: bool) -> Self {
+        self.expose_header = expose_header;
+        self
+    }
+}
+
+impl<S, T, B> Transform<S, ServiceRequest> for Traced<T>
+where
+    T: Transform<S, ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    T::Future: 'static,
+    T::Transform: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TracedMiddleware<T::Transform>;
+    type InitError = T::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let fut = self.transform.new_transform(service);
+        let name = self.name;
+        let expose_header = self.expose_header;
+
+        Box::pin(async move {
+            Ok(TracedMiddleware {
+                service: fut.await?,
+                name,
+                expose_header,
+            })
+        })
+    }
+}
+
+/// Service for the [`Traced`] middleware wrapper.
+pub struct TracedMiddleware<S> {
+    service: S,
+    name: &'static str,
+    expose_header: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for TracedMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let existing = req.extensions().get::<MiddlewareTrace>().cloned();
+        let trace = existing.unwrap_or_else(|| {
+            let trace = MiddlewareTrace::default();
+            req.extensions_mut().insert(trace.clone());
+            trace
+        });
+
+        let idx = trace.enter(self.name);
+        let start = Instant::now();
+        let expose_header = self.expose_header;
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+            trace.exit(idx, start.elapsed());
+
+            let mut res = res?;
+
+            if idx == 0 {
+                if expose_header {
+                    if let Ok(val) = HeaderValue::try_from(trace.header_value()) {
+                        res.headers_mut().insert(MIDDLEWARE_TRACE, val);
+                    }
+                }
+
+                res.response_mut().extensions_mut().insert(trace);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: &'static str,
+    elapsed: Option<Duration>,
+}
+
+/// The traced middleware layers which handled a request, from outermost to innermost.
+///
+/// Found in response extensions after passing through middleware wrapped with [`traced()`].
+///
+/// # Examples
+/// ```
+/// use actix_web::dev::ServiceResponse;
+/// use actix_web_lab::middleware::MiddlewareTrace;
+///
+/// fn log_trace(res: &ServiceResponse) {
+///     if let Some(trace) = res.response().extensions().get::<MiddlewareTrace>() {
+///         for layer in trace.layers() {
+///             println!("{}: {:?}", layer.name(), layer.self_elapsed());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MiddlewareTrace {
+    entries: Rc<RefCell<Vec<Entry>>>,
+}
+
+impl MiddlewareTrace {
+    fn enter(&self, name: &'static str) -> usize {
+        let mut entries = self.entries.borrow_mut();
+        entries.push(Entry {
+            name,
+            elapsed: None,
+        });
+        entries.len() - 1
+    }
+
+    fn exit(&self, idx: usize, elapsed: Duration) {
+        self.entries.borrow_mut()[idx].elapsed = Some(elapsed);
+    }
+
+    /// Returns names of recorded layers, from outermost to innermost.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    /// Returns recorded layers, from outermost to innermost.
+    pub fn layers(&self) -> Vec<MiddlewareLayer> {
+        let entries = self.entries.borrow();
+
+        entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                let inner = entries.get(idx + 1).map(|inner| inner.elapsed);
+
+                let self_elapsed = match (entry.elapsed, inner) {
+                    (Some(elapsed), Some(Some(inner))) => Some(elapsed.saturating_sub(inner)),
+                    (Some(elapsed), None) => Some(elapsed),
+                    _ => None,
+                };
+
+                MiddlewareLayer {
+                    name: entry.name,
+                    elapsed: entry.elapsed,
+                    self_elapsed,
+                }
+            })
+            .collect()
+    }
+
+    /// Formats layers like `Logger;dur=0.061, NormalizePath;dur=0.020`, with self times in ms.
+    fn header_value(&self) -> String {
+        let mut val = String::new();
+
+        for layer in self.layers() {
+            if !val.is_empty() {
+                val.push_str(", ");
+            }
+
+            val.push_str(layer.name);
+
+            if let Some(dur) = layer.self_elapsed {
+                let _ = write!(val, ";dur={:.3}", dur.as_secs_f64() * 1000.0);
+            }
+        }
+
+        val
+    }
+}
+
+/// A traced middleware layer. See [`MiddlewareTrace`].
+#[derive(Debug, Clone)]
+pub struct MiddlewareLayer {
+    name: &'static str,
+    elapsed: Option<Duration>,
+    self_elapsed: Option<Duration>,
+}
+
+impl MiddlewareLayer {
+    /// Returns name given to [`traced()`].
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns time taken by this layer and all layers inside it, if it has completed.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// Returns time taken by this layer excluding the next traced layer, if both have completed.
+    pub fn self_elapsed(&self) -> Option<Duration> {
+        self.self_elapsed
+    }
+}
+
+impl fmt::Display for MiddlewareLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.self_elapsed {
+            Some(dur) => write!(f, "{} ({dur:?})", self.name),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        middleware::{Compat, DefaultHeaders},
+        test, web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn records_order() {
+        let app = test::init_service(
+            App::new()
+                .wrap(traced("inner", DefaultHeaders::new().add(("x-inner", "1"))))
+                .wrap(traced("compat", Compat::new(DefaultHeaders::new())))
+                .wrap(traced("outer", DefaultHeaders::new()).expose_header(true))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("x-inner").unwrap(), "1");
+
+        let trace = res
+            .response()
+            .extensions()
+            .get::<MiddlewareTrace>()
+            .cloned()
+            .unwrap();
+        assert_eq!(trace.names(), ["outer", "compat", "inner"]);
+
+        let layers = trace.layers();
+        assert!(layers.iter().all(|layer| layer.elapsed().is_some()));
+        assert!(layers[0].elapsed() >= layers[1].elapsed());
+
+        let header = res
+            .headers()
+            .get(MIDDLEWARE_TRACE)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(header.starts_with("outer;dur="));
+        assert!(header.contains(", compat;dur="));
+        assert!(header.contains(", inner;dur="));
+    }
+
+    #[actix_web::test]
+    async fn header_not_exposed_by_default() {
+        let app = test::init_service(
+            App::new()
+                .wrap(traced("outer", DefaultHeaders::new()))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(!res.headers().contains_key(MIDDLEWARE_TRACE));
+        assert!(res
+            .response()
+            .extensions()
+            .get::<MiddlewareTrace>()
+            .is_some());
+    }
+}
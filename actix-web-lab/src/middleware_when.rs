@@ -0,0 +1,215 @@
+//! Conditional middleware wrapper.
+//!
+//! See [`when`] docs.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::{
+    future::{Either, MapOk},
+    TryFutureExt as _,
+};
+
+/// Applies middleware only to requests which match a predicate.
+///
+/// Requests for which `predicate` returns false skip the middleware `mw` entirely and are passed
+/// directly to the wrapped service. Unlike Actix Web's [`Condition`] middleware, which is decided
+/// once when the app is built, the predicate is evaluated for every request; it should be cheap,
+/// e.g., a check of the request's path, method, or headers.
+///
+/// [`Condition`]: actix_web::middleware::Condition
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::Logger, App};
+/// use actix_web_lab::middleware::{when, CatchPanic};
+///
+/// App::new()
+///     // skip request logging for health checks
+///     .wrap(when(
+///         |req| req.path() != "/health",
+///         Logger::default(),
+///     ))
+///     // only catch panics in admin area
+///     .wrap(when(
+///         |req| req.path().starts_with("/admin/"),
+///         CatchPanic::default(),
+///     ))
+/// # ;
+/// ```
+pub fn when<P, T>(predicate: P, mw: T) -> When<P, T>
+where
+    P: Fn(&ServiceRequest) -> bool,
+{
+    When {
+        predicate: Rc::new(predicate),
+        mw,
+    }
+}
+
+/// Middleware which applies inner middleware conditionally.
+///
+/// Constructed using [`when()`].
+#[derive(Debug)]
+pub struct When<P, T> {
+    predicate: Rc<P>,
+    mw: T,
+}
+
+impl<S, P, T, B, B2> Transform<S, ServiceRequest> for When<P, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    P: Fn(&ServiceRequest) -> bool + 'static,
+    T: Transform<SharedService<S>, ServiceRequest, Response = ServiceResponse<B2>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B2, B>>;
+    type Error = Error;
+    type Transform = WhenMiddleware<S, P, T::Transform>;
+    type InitError = T::InitError;
+    type Future = WhenFuture<S, P, T::Future>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let service = Rc::new(service);
+
+        WhenFuture {
+            fut: self.mw.new_transform(SharedService(Rc::clone(&service))),
+            service: Some(service),
+            predicate: Some(Rc::clone(&self.predicate)),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    #[doc(hidden)]
+    pub struct WhenFuture<S, P, Fut> {
+        #[pin]
+        fut: Fut,
+        service: Option<Rc<S>>,
+        predicate: Option<Rc<P>>,
+    }
+}
+
+impl<S, P, Fut, M, E> Future for WhenFuture<S, P, Fut>
+where
+    Fut: Future<Output = Result<M, E>>,
+{
+    type Output = Result<WhenMiddleware<S, P, M>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let wrapped = std::task::ready!(this.fut.poll(cx))?;
+
+        Poll::Ready(Ok(WhenMiddleware {
+            service: this.service.take().unwrap(),
+            predicate: this.predicate.take().unwrap(),
+            wrapped,
+        }))
+    }
+}
+
+/// A service shared between a [`When`] middleware and its conditional inner middleware.
+pub struct SharedService<S>(Rc<S>);
+
+impl<S, Req> Service<Req> for SharedService<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&self, req: Req) -> Self::Future {
+        self.0.call(req)
+    }
+}
+
+type MapLeft<B2, B> = fn(ServiceResponse<B2>) -> ServiceResponse<EitherBody<B2, B>>;
+type MapRight<B2, B> = fn(ServiceResponse<B>) -> ServiceResponse<EitherBody<B2, B>>;
+
+/// Service for the [`When`] middleware.
+pub struct WhenMiddleware<S, P, M> {
+    service: Rc<S>,
+    predicate: Rc<P>,
+    wrapped: M,
+}
+
+impl<S, P, M, B, B2> Service<ServiceRequest> for WhenMiddleware<S, P, M>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    P: Fn(&ServiceRequest) -> bool,
+    M: Service<ServiceRequest, Response = ServiceResponse<B2>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B2, B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Either<MapOk<M::Future, MapLeft<B2, B>>, MapOk<S::Future, MapRight<B2, B>>>;
+
+    // the wrapped middleware is expected to poll readiness of the shared service
+    forward_ready!(wrapped);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if (self.predicate)(&req) {
+            Either::Left(
+                self.wrapped
+                    .call(req)
+                    .map_ok(ServiceResponse::map_into_left_body as MapLeft<B2, B>),
+            )
+        } else {
+            Either::Right(
+                self.service
+                    .call(req)
+                    .map_ok(ServiceResponse::map_into_right_body as MapRight<B2, B>),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::Method,
+        middleware::DefaultHeaders,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn applies_conditionally() {
+        let app = test::init_service(
+            App::new()
+                .wrap(when(
+                    |req| req.path().starts_with("/api/") && req.method() != Method::GET,
+                    DefaultHeaders::new().add(("x-applied", "1")),
+                ))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let cases = [
+            (Method::POST, "/api/items", true),
+            (Method::GET, "/api/items", false),
+            (Method::POST, "/items", false),
+        ];
+
+        for (method, path, applied) in cases {
+            let req = TestRequest::with_uri(path).method(method).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.headers().contains_key("x-applied"), applied, "{path}");
+        }
+    }
+}
@@ -1,14 +1,23 @@
-//! MessagePack responder.
+//! MessagePack extractor and responders.
 
-use actix_web::{HttpRequest, HttpResponse, Responder};
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, web, FromRequest, HttpMessage as _,
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
 use bytes::Bytes;
-use derive_more::{Deref, DerefMut, Display};
+use derive_more::{Deref, DerefMut, Display, Error};
+use futures_core::future::LocalBoxFuture;
+use futures_util::StreamExt as _;
 use mime::Mime;
 use once_cell::sync::Lazy;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::debug;
 
 static MSGPACK_MIME: Lazy<Mime> = Lazy::new(|| "application/msgpack".parse().unwrap());
 
+/// Default MessagePack payload size limit of 2MiB.
+pub const DEFAULT_MSGPACK_LIMIT: usize = 2_097_152;
+
 /// MessagePack responder.
 ///
 /// If you require the fields to be named, use [`MessagePackNamed`].
@@ -44,3 +53,366 @@ impl<T: Serialize> Responder for MessagePackNamed<T> {
             .unwrap()
     }
 }
+
+/// MessagePack extractor and responder.
+///
+/// # Extractor
+/// To extract typed data from a request body, the inner type `T` must implement the
+/// [`serde::Deserialize`] trait. The request must have a `Content-Type` of `application/msgpack`,
+/// `application/x-msgpack`, `application/vnd.msgpack`, or a type with a `+msgpack` suffix. Structs
+/// can be encoded either as maps or as arrays.
+///
+/// The payload size limit defaults to 2MiB and can be changed by registering a [`MsgPackConfig`]
+/// as app data. Deserialization errors include the path to the field that failed to deserialize.
+///
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::{MsgPack, MsgPackConfig};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Reading {
+///     sensor: String,
+///     value: f64,
+/// }
+///
+/// #[post("/readings")]
+/// async fn index(reading: MsgPack<Reading>) -> String {
+///     format!("{}: {}", reading.sensor, reading.value)
+/// }
+///
+/// App::new()
+///     .app_data(MsgPackConfig::default().limit(4096))
+///     .service(index)
+/// # ;
+/// ```
+///
+/// # Responder
+/// Serializes the inner value as MessagePack, with struct fields encoded as maps, and a
+/// `Content-Type` of `application/msgpack`. Responds with an empty `500 Internal Server Error`
+/// response if serialization fails.
+///
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::respond::MsgPack;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Status {
+///     online: bool,
+/// }
+///
+/// #[get("/status")]
+/// async fn index() -> impl Responder {
+///     MsgPack(Status { online: true })
+/// }
+/// ```
+#[derive(Debug, Deref, DerefMut, Display)]
+pub struct MsgPack<T>(pub T);
+
+impl<T> MsgPack<T> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Responder for MsgPack<T> {
+    type Body = Bytes;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        match rmp_serde::to_vec_named(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(MSGPACK_MIME.clone())
+                .message_body(Bytes::from(body))
+                .unwrap(),
+
+            Err(err) => {
+                debug!("Failed to serialize MessagePack response: {err}");
+
+                HttpResponse::InternalServerError()
+                    .message_body(Bytes::new())
+                    .unwrap()
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for MsgPack<T> {
+    type Error = MsgPackPayloadError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let limit = MsgPackConfig::from_req(&req).limit;
+        let payload = payload.take();
+
+        Box::pin(async move {
+            let res = read_msgpack(&req, payload, limit).await;
+
+            if let Err(err) = &res {
+                debug!(
+                    "Failed to deserialize MsgPack<{}> from payload in handler: {}",
+                    core::any::type_name::<T>(),
+                    req.match_name().unwrap_or_else(|| req.path())
+                );
+
+                if let MsgPackPayloadError::Deserialize { path, .. } = err {
+                    debug!("MessagePack payload is invalid at `{path}`");
+                }
+            }
+
+            res.map(MsgPack)
+        })
+    }
+}
+
+async fn read_msgpack<T: DeserializeOwned>(
+    req: &HttpRequest,
+    mut payload: Payload,
+    limit: usize,
+) -> Result<T, MsgPackPayloadError> {
+    let can_parse_msgpack = match req.mime_type() {
+        Ok(Some(mime)) => {
+            matches!(
+                mime.subtype().as_str(),
+                "msgpack" | "x-msgpack" | "vnd.msgpack"
+            ) || mime.suffix().map(|s| s.as_str()) == Some("msgpack")
+        }
+        _ => false,
+    };
+
+    if !can_parse_msgpack {
+        return Err(MsgPackPayloadError::ContentType);
+    }
+
+    let length = req
+        .get_header::<crate::header::ContentLength>()
+        .map(|cl| cl.into_inner());
+
+    if let Some(length) = length {
+        if length > limit {
+            return Err(MsgPackPayloadError::OverflowKnownLength { length, limit });
+        }
+    }
+
+    let mut buf = web::BytesMut::with_capacity(length.unwrap_or(8192).min(limit));
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if buf.len() + chunk.len() > limit {
+            return Err(MsgPackPayloadError::Overflow { limit });
+        }
+
+        buf.extend_from_slice(&chunk);
+    }
+
+    let mut de = rmp_serde::Deserializer::from_read_ref(&buf);
+
+    serde_path_to_error::deserialize(&mut de).map_err(|err| MsgPackPayloadError::Deserialize {
+        path: err.path().to_string(),
+        source: err.into_inner(),
+    })
+}
+
+/// Configuration for the [`MsgPack`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Extractors used
+/// without a registered config use the defaults.
+#[derive(Debug, Clone)]
+pub struct MsgPackConfig {
+    limit: usize,
+}
+
+const DEFAULT_CONFIG: MsgPackConfig = MsgPackConfig {
+    limit: DEFAULT_MSGPACK_LIMIT,
+};
+
+impl MsgPackConfig {
+    /// Sets maximum accepted payload size, in bytes.
+    ///
+    /// The default limit is 2MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl Default for MsgPackConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+/// Errors that can occur when extracting a [`MsgPack`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum MsgPackPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(
+        fmt = "MessagePack payload ({length} bytes) is larger than allowed (limit: {limit} bytes)."
+    )]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "MessagePack payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not MessagePack.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload could not be deserialized.
+    #[display(fmt = "MessagePack deserialize error at `{path}`: {source}")]
+    Deserialize {
+        /// Path to the value that failed to deserialize, using `.` for fields and `[n]` for
+        /// sequence elements.
+        path: String,
+
+        /// Underlying deserialization error.
+        source: rmp_serde::decode::Error,
+    },
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<PayloadError> for MsgPackPayloadError {
+    fn from(err: PayloadError) -> Self {
+        Self::Payload(err)
+    }
+}
+
+impl ResponseError for MsgPackPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Deserialize { .. } => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Reading {
+        sensor: String,
+        values: Vec<u32>,
+    }
+
+    fn reading() -> Reading {
+        Reading {
+            sensor: "temp".to_owned(),
+            values: vec![21, 22],
+        }
+    }
+
+    #[actix_web::test]
+    async fn round_trip() {
+        let req = TestRequest::default().to_http_request();
+        let res = MsgPack(reading()).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/msgpack",
+        );
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/msgpack"))
+            .set_payload(body)
+            .to_http_parts();
+        let extracted = MsgPack::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(extracted.into_inner(), reading());
+
+        // compact encoding, with structs as arrays
+        let body = rmp_serde::to_vec(&reading()).unwrap();
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/vnd.sensor+msgpack"))
+            .set_payload(body)
+            .to_http_parts();
+        let extracted = MsgPack::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(extracted.sensor, "temp");
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let body = Bytes::from(rmp_serde::to_vec_named(&reading()).unwrap());
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(body.clone())
+            .to_http_parts();
+        let err = MsgPack::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let length = body.len();
+        let (req, mut pl) = TestRequest::default()
+            .app_data(MsgPackConfig::default().limit(4))
+            .insert_header((header::CONTENT_TYPE, "application/msgpack"))
+            .set_payload(body)
+            .to_http_parts();
+        let err = MsgPack::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            MsgPackPayloadError::OverflowKnownLength { length: len, limit: 4 } if len == length
+        ));
+
+        #[derive(Serialize)]
+        struct BadReading {
+            sensor: &'static str,
+            values: Vec<&'static str>,
+        }
+
+        let body = rmp_serde::to_vec_named(&BadReading {
+            sensor: "temp",
+            values: vec!["warm"],
+        })
+        .unwrap();
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-msgpack"))
+            .set_payload(body)
+            .to_http_parts();
+        let err = MsgPack::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert!(
+            matches!(&err, MsgPackPayloadError::Deserialize { path, .. } if path == "values[0]"),
+            "unexpected error: {err}",
+        );
+    }
+}
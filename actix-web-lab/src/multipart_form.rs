@@ -0,0 +1,759 @@
+//! Typed multipart form extractor.
+//!
+//! See [`MultipartForm`] docs.
+
+use std::{
+    any::Any,
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use actix_multipart::{Field, Multipart, MultipartError};
+use actix_web::{dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, ResponseError};
+use ahash::AHashMap;
+use bytes::{Bytes, BytesMut};
+use derive_more::{Deref, DerefMut, Display, Error};
+use futures_core::future::LocalBoxFuture;
+use futures_util::TryStreamExt as _;
+use mime::Mime;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt as _;
+use tracing::debug;
+
+/// Default multipart form payload size limit of 50MiB.
+pub const DEFAULT_MULTIPART_FORM_LIMIT: usize = 52_428_800;
+
+type ErrorHandler = Arc<dyn Fn(MultipartFormError, &HttpRequest) -> actix_web::Error + Send + Sync>;
+
+/// Typed `multipart/form-data` extractor.
+///
+/// Maps the fields of a multipart form onto the fields of a struct which derives
+/// [`MultipartForm`](macro@crate::extract::MultipartForm). Each struct field is read from the form
+/// field of the same name using its type's [`FieldReader`] implementation:
+/// - [`MultipartText<T>`] parses text fields using `T`'s [`FromStr`] implementation;
+/// - [`String`] reads text fields as-is;
+/// - [`Bytes`] reads fields into memory;
+/// - [`TempFile`] streams fields, usually file uploads, to a temporary file.
+///
+/// Wrap the type in an `Option` to make a field optional, or a `Vec` to accept a field any number
+/// of times. Form fields which do not correspond to a struct field are ignored, unless the struct is
+/// annotated with `#[multipart(deny_unknown_fields)]`.
+///
+/// # Limits
+/// The total size of all fields is limited to 50MiB by default, which can be changed by registering
+/// a [`MultipartFormConfig`]. Individual fields can be limited further using the
+/// `#[multipart(limit = "1MiB")]` attribute; decimal (`KB`, `MB`, `GB`) and binary (`KiB`, `MiB`,
+/// `GiB`) units are supported, as are integer byte counts.
+///
+/// # Errors
+/// Forms with missing, duplicated, invalid, or oversized fields are rejected with a
+/// `400 Bad Request` response naming the offending field. Forms exceeding the total limit are
+/// rejected with a `413 Payload Too Large` response.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, App, Responder};
+/// use actix_web_lab::extract::{MultipartForm, MultipartText, TempFile};
+///
+/// #[derive(MultipartForm)]
+/// struct Upload {
+///     #[multipart(limit = "256B")]
+///     title: MultipartText<String>,
+///
+///     #[multipart(rename = "tag")]
+///     tags: Vec<MultipartText<String>>,
+///
+///     #[multipart(limit = "10MiB")]
+///     file: TempFile,
+/// }
+///
+/// #[post("/upload")]
+/// async fn upload(MultipartForm(form): MultipartForm<Upload>) -> impl Responder {
+///     format!(
+///         "uploaded {} bytes as {} with {} tags",
+///         form.file.size(),
+///         *form.title,
+///         form.tags.len(),
+///     )
+/// }
+/// # App::new().service(upload);
+/// ```
+#[derive(Debug, Deref, DerefMut)]
+pub struct MultipartForm<T>(pub T);
+
+impl<T> MultipartForm<T> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: MultipartCollect + 'static> FromRequest for MultipartForm<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let config = MultipartFormConfig::from_req(&req).clone();
+        let multipart = Multipart::new(req.headers(), payload.take());
+
+        Box::pin(async move {
+            match collect::<T>(&req, multipart, config.limit).await {
+                Ok(form) => Ok(MultipartForm(form)),
+
+                Err(err) => {
+                    debug!(
+                        "Failed to extract MultipartForm<{}> from payload in handler: {}: {err}",
+                        core::any::type_name::<T>(),
+                        req.match_name().unwrap_or_else(|| req.path())
+                    );
+
+                    Err(match &config.err_handler {
+                        Some(err_handler) => (err_handler)(err, &req),
+                        None => err.into(),
+                    })
+                }
+            }
+        })
+    }
+}
+
+async fn collect<T: MultipartCollect>(
+    req: &HttpRequest,
+    mut multipart: Multipart,
+    limit: usize,
+) -> Result<T, MultipartFormError> {
+    let mut limits = FieldLimits::new(limit);
+    let mut state = MultipartState::default();
+
+    while let Some(field) = multipart.try_next().await? {
+        T::handle_field(req, field, &mut limits, &mut state).await?;
+    }
+
+    T::from_state(state)
+}
+
+/// Configuration for the [`MultipartForm`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Extractors used
+/// without a registered config use the defaults.
+#[derive(Clone)]
+pub struct MultipartFormConfig {
+    limit: usize,
+    file_limit: Option<usize>,
+    temp_dir: Option<PathBuf>,
+    err_handler: Option<ErrorHandler>,
+}
+
+const DEFAULT_CONFIG: MultipartFormConfig = MultipartFormConfig {
+    limit: DEFAULT_MULTIPART_FORM_LIMIT,
+    file_limit: None,
+    temp_dir: None,
+    err_handler: None,
+};
+
+impl MultipartFormConfig {
+    /// Sets maximum accepted total size of all fields, in bytes.
+    ///
+    /// The default limit is 50MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets maximum size of each [`TempFile`] field, in bytes.
+    ///
+    /// Fields with their own limit, set using the `#[multipart(limit = "...")]` attribute, are not
+    /// affected. By default, temporary files are only limited by the total limit.
+    pub fn file_limit(mut self, limit: usize) -> Self {
+        self.file_limit = Some(limit);
+        self
+    }
+
+    /// Sets directory in which [`TempFile`] fields are spooled.
+    ///
+    /// Defaults to the system's temporary directory. Using a directory on the same filesystem as
+    /// the final location of uploaded files allows [`TempFile::persist()`] to move, rather than
+    /// copy, them.
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets custom error handler, used to convert extraction errors into responses.
+    pub fn error_handler<F>(mut self, err_handler: F) -> Self
+    where
+        F: Fn(MultipartFormError, &HttpRequest) -> actix_web::Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(err_handler));
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl Default for MultipartFormConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+impl fmt::Debug for MultipartFormConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultipartFormConfig")
+            .field("limit", &self.limit)
+            .field("file_limit", &self.file_limit)
+            .field("temp_dir", &self.temp_dir)
+            .field("err_handler", &self.err_handler.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// Size limits applying to the multipart field being read.
+#[derive(Debug)]
+pub struct FieldLimits {
+    field: String,
+    field_limit: Option<usize>,
+    field_read: usize,
+    total_limit: usize,
+    total_read: usize,
+}
+
+impl FieldLimits {
+    fn new(total_limit: usize) -> Self {
+        Self {
+            field: String::new(),
+            field_limit: None,
+            field_read: 0,
+            total_limit,
+            total_read: 0,
+        }
+    }
+
+    fn start_field(&mut self, field: String, limit: Option<usize>) {
+        self.field = field;
+        self.field_limit = limit;
+        self.field_read = 0;
+    }
+
+    /// Sets `limit` as the limit of the field being read, unless it has its own.
+    fn default_field_limit(&mut self, limit: Option<usize>) {
+        if self.field_limit.is_none() {
+            self.field_limit = limit;
+        }
+    }
+
+    /// Returns name of the field being read.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Records that `len` more bytes of the field were read.
+    ///
+    /// Returns an error if either the field's limit or the form's total limit has been exceeded.
+    pub fn consume(&mut self, len: usize) -> Result<(), MultipartFormError> {
+        self.field_read += len;
+        self.total_read += len;
+
+        if let Some(limit) = self.field_limit {
+            if self.field_read > limit {
+                return Err(MultipartFormError::FieldOverflow {
+                    field: self.field.clone(),
+                    limit,
+                });
+            }
+        }
+
+        if self.total_read > self.total_limit {
+            return Err(MultipartFormError::Overflow {
+                limit: self.total_limit,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// A type that can be read from a single multipart field.
+///
+/// Implementations must read the field to completion, calling [`FieldLimits::consume()`] for each
+/// chunk read.
+pub trait FieldReader: Sized + 'static {
+    /// Reads value from `field`.
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut FieldLimits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartFormError>>;
+}
+
+async fn read_bytes(
+    mut field: Field,
+    limits: &mut FieldLimits,
+) -> Result<BytesMut, MultipartFormError> {
+    let mut buf = BytesMut::new();
+
+    while let Some(chunk) = field.try_next().await? {
+        limits.consume(chunk.len())?;
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(buf)
+}
+
+fn read_string(buf: BytesMut, limits: &FieldLimits) -> Result<String, MultipartFormError> {
+    String::from_utf8(buf.to_vec()).map_err(|_| MultipartFormError::InvalidField {
+        field: limits.field().to_owned(),
+        reason: "field is not valid UTF-8".to_owned(),
+    })
+}
+
+impl FieldReader for Bytes {
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut FieldLimits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartFormError>> {
+        Box::pin(async move { Ok(read_bytes(field, limits).await?.freeze()) })
+    }
+}
+
+impl FieldReader for String {
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut FieldLimits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartFormError>> {
+        Box::pin(async move {
+            let buf = read_bytes(field, limits).await?;
+            read_string(buf, limits)
+        })
+    }
+}
+
+/// A text field, parsed using `T`'s [`FromStr`] implementation.
+#[derive(Debug, Clone, PartialEq, Eq, Deref, DerefMut)]
+pub struct MultipartText<T>(pub T);
+
+impl<T> MultipartText<T> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> FieldReader for MultipartText<T>
+where
+    T: FromStr + 'static,
+    T::Err: fmt::Display,
+{
+    fn read_field<'t>(
+        _req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut FieldLimits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartFormError>> {
+        Box::pin(async move {
+            let buf = read_bytes(field, limits).await?;
+            let text = read_string(buf, limits)?;
+
+            text.parse().map(MultipartText).map_err(|err: T::Err| {
+                MultipartFormError::InvalidField {
+                    field: limits.field().to_owned(),
+                    reason: err.to_string(),
+                }
+            })
+        })
+    }
+}
+
+/// A field streamed to a temporary file, usually a file upload.
+///
+/// Fields are spooled to disk as they are received, so large uploads are never buffered in memory.
+/// The directory they are spooled to and a size limit applying to all temporary files can be set
+/// using [`MultipartFormConfig`].
+///
+/// The file is deleted when this value is dropped, including when extraction of the form fails,
+/// unless it has been moved to a permanent location using [`persist()`](Self::persist) or the
+/// [`NamedTempFile`] returned by [`into_file()`](Self::into_file).
+#[derive(Debug)]
+pub struct TempFile {
+    file: NamedTempFile,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+    size: usize,
+}
+
+impl TempFile {
+    /// Returns the temporary file.
+    pub fn file(&self) -> &NamedTempFile {
+        &self.file
+    }
+
+    /// Returns path of the temporary file.
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Opens the temporary file for asynchronous reading.
+    pub async fn open(&self) -> io::Result<tokio::fs::File> {
+        Ok(tokio::fs::File::from_std(self.file.reopen()?))
+    }
+
+    /// Moves the temporary file to `path`, so that it is not deleted when dropped.
+    ///
+    /// Fails if `path` is on a different filesystem than the temporary file, in which case the
+    /// temporary file is deleted.
+    pub async fn persist(self, path: impl AsRef<Path>) -> io::Result<()> {
+        let temp_path = self.file.into_temp_path();
+        tokio::fs::rename(&temp_path, path).await?;
+
+        // file no longer exists at the temporary path so there is nothing left to clean up
+        let _ = temp_path.keep();
+
+        Ok(())
+    }
+
+    /// Returns file name sent by the client, if any.
+    ///
+    /// This is untrusted input and should not be used as a path without sanitization.
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns content type sent by the client, if any.
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// Returns size of the file, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Unwraps into the temporary file.
+    pub fn into_file(self) -> NamedTempFile {
+        self.file
+    }
+}
+
+impl FieldReader for TempFile {
+    fn read_field<'t>(
+        req: &'t HttpRequest,
+        mut field: Field,
+        limits: &'t mut FieldLimits,
+    ) -> LocalBoxFuture<'t, Result<Self, MultipartFormError>> {
+        Box::pin(async move {
+            let file_name = field
+                .content_disposition()
+                .get_filename()
+                .map(str::to_owned);
+            let content_type = field.content_type().cloned();
+
+            let config = MultipartFormConfig::from_req(req);
+            limits.default_field_limit(config.file_limit);
+
+            let file = match &config.temp_dir {
+                Some(dir) => NamedTempFile::new_in(dir)?,
+                None => NamedTempFile::new()?,
+            };
+            let mut writer = tokio::fs::File::from_std(file.reopen()?);
+            let mut size = 0;
+
+            while let Some(chunk) = field.try_next().await? {
+                limits.consume(chunk.len())?;
+                writer.write_all(&chunk).await?;
+                size += chunk.len();
+            }
+
+            writer.flush().await?;
+
+            Ok(TempFile {
+                file,
+                file_name,
+                content_type,
+                size,
+            })
+        })
+    }
+}
+
+/// A struct field type, made up of any number of values read from form fields of the same name.
+///
+/// Implemented for any [`FieldReader`] (exactly one value), `Option`s of them (at most one value),
+/// and `Vec`s of them (any number of values).
+pub trait FieldGroup: Sized {
+    /// Type of the values making up this group.
+    type Item: FieldReader;
+
+    #[doc(hidden)]
+    const REPEATABLE: bool;
+
+    #[doc(hidden)]
+    fn from_items(field: &str, items: Vec<Self::Item>) -> Result<Self, MultipartFormError>;
+}
+
+impl<T: FieldReader> FieldGroup for T {
+    type Item = T;
+
+    const REPEATABLE: bool = false;
+
+    fn from_items(field: &str, items: Vec<T>) -> Result<Self, MultipartFormError> {
+        items
+            .into_iter()
+            .next()
+            .ok_or_else(|| MultipartFormError::MissingField {
+                field: field.to_owned(),
+            })
+    }
+}
+
+impl<T: FieldReader> FieldGroup for Option<T> {
+    type Item = T;
+
+    const REPEATABLE: bool = false;
+
+    fn from_items(_field: &str, items: Vec<T>) -> Result<Self, MultipartFormError> {
+        Ok(items.into_iter().next())
+    }
+}
+
+impl<T: FieldReader> FieldGroup for Vec<T> {
+    type Item = T;
+
+    const REPEATABLE: bool = true;
+
+    fn from_items(_field: &str, items: Vec<T>) -> Result<Self, MultipartFormError> {
+        Ok(items)
+    }
+}
+
+/// A struct which can be extracted from a multipart form.
+///
+/// Implemented using the [`MultipartForm`](macro@crate::extract::MultipartForm) derive macro.
+#[doc(hidden)]
+pub trait MultipartCollect: Sized {
+    /// Reads `field` into `state`.
+    fn handle_field<'t>(
+        req: &'t HttpRequest,
+        field: Field,
+        limits: &'t mut FieldLimits,
+        state: &'t mut MultipartState,
+    ) -> LocalBoxFuture<'t, Result<(), MultipartFormError>>;
+
+    /// Constructs struct from collected fields.
+    fn from_state(state: MultipartState) -> Result<Self, MultipartFormError>;
+}
+
+/// Values read so far, keyed by field name.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct MultipartState {
+    fields: AHashMap<String, Box<dyn Any>>,
+}
+
+/// Returns name of a multipart field.
+#[doc(hidden)]
+pub fn field_name(field: &Field) -> &str {
+    field.content_disposition().get_name().unwrap_or_default()
+}
+
+/// Reads `field` as an item of the field group `G`, enforcing the field's `limit`, if any.
+#[doc(hidden)]
+pub async fn read_field<G: FieldGroup>(
+    req: &HttpRequest,
+    state: &mut MultipartState,
+    field: Field,
+    limits: &mut FieldLimits,
+    limit: Option<usize>,
+) -> Result<(), MultipartFormError> {
+    let name = field_name(&field).to_owned();
+    limits.start_field(name.clone(), limit);
+
+    let items = state
+        .fields
+        .entry(name)
+        .or_insert_with(|| Box::<Vec<G::Item>>::default())
+        .downcast_mut::<Vec<G::Item>>()
+        .unwrap();
+
+    if !G::REPEATABLE && !items.is_empty() {
+        return Err(MultipartFormError::DuplicateField {
+            field: limits.field().to_owned(),
+        });
+    }
+
+    items.push(G::Item::read_field(req, field, limits).await?);
+
+    Ok(())
+}
+
+/// Reads and discards a field which does not correspond to any struct field.
+#[doc(hidden)]
+pub async fn skip_field(
+    mut field: Field,
+    limits: &mut FieldLimits,
+    deny_unknown_fields: bool,
+) -> Result<(), MultipartFormError> {
+    let name = field_name(&field).to_owned();
+
+    if deny_unknown_fields {
+        return Err(MultipartFormError::UnknownField { field: name });
+    }
+
+    limits.start_field(name, None);
+
+    // skipped fields still count towards the total limit
+    while let Some(chunk) = field.try_next().await? {
+        limits.consume(chunk.len())?;
+    }
+
+    Ok(())
+}
+
+/// Takes the values read for `field` out of `state`, as the field group `G`.
+#[doc(hidden)]
+pub fn take_field<G: FieldGroup>(
+    state: &mut MultipartState,
+    field: &str,
+) -> Result<G, MultipartFormError> {
+    let items = state
+        .fields
+        .remove(field)
+        .and_then(|items| items.downcast::<Vec<G::Item>>().ok())
+        .map(|items| *items)
+        .unwrap_or_default();
+
+    G::from_items(field, items)
+}
+
+/// Errors that can occur when extracting a [`MultipartForm`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum MultipartFormError {
+    /// Total size of fields is bigger than allowed.
+    #[display(fmt = "Multipart payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured total size limit.
+        limit: usize,
+    },
+
+    /// Size of a field is bigger than allowed.
+    #[display(fmt = "Field `{field}` has exceeded limit ({limit} bytes).")]
+    FieldOverflow {
+        /// Name of the field.
+        field: String,
+
+        /// Configured field size limit.
+        limit: usize,
+    },
+
+    /// A required field is missing.
+    #[display(fmt = "Field `{field}` is missing.")]
+    MissingField {
+        /// Name of the field.
+        field: String,
+    },
+
+    /// A field which is accepted at most once was sent more than once.
+    #[display(fmt = "Field `{field}` was sent more than once.")]
+    DuplicateField {
+        /// Name of the field.
+        field: String,
+    },
+
+    /// A field was sent which does not correspond to any struct field.
+    #[display(fmt = "Field `{field}` is not expected.")]
+    UnknownField {
+        /// Name of the field.
+        field: String,
+    },
+
+    /// A field could not be read into its struct field type.
+    #[display(fmt = "Field `{field}` is invalid: {reason}")]
+    InvalidField {
+        /// Name of the field.
+        field: String,
+
+        /// Description of the problem.
+        reason: String,
+    },
+
+    /// Payload is not a valid multipart form.
+    #[display(fmt = "Multipart error: {_0}")]
+    Multipart(MultipartError),
+
+    /// Field could not be written to a temporary file.
+    #[display(fmt = "Failed to write field to temporary file: {_0}")]
+    Io(io::Error),
+}
+
+impl From<MultipartError> for MultipartFormError {
+    fn from(err: MultipartError) -> Self {
+        Self::Multipart(err)
+    }
+}
+
+impl From<io::Error> for MultipartFormError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl ResponseError for MultipartFormError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::FieldOverflow { .. }
+            | Self::MissingField { .. }
+            | Self::DuplicateField { .. }
+            | Self::UnknownField { .. }
+            | Self::InvalidField { .. } => StatusCode::BAD_REQUEST,
+            Self::Multipart(err) => err.status_code(),
+            Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_limits() {
+        let mut limits = FieldLimits::new(10);
+
+        limits.start_field("a".to_owned(), Some(4));
+        limits.consume(4).unwrap();
+        let err = limits.consume(1).unwrap_err();
+        assert_eq!(err.to_string(), "Field `a` has exceeded limit (4 bytes).");
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        limits.start_field("b".to_owned(), None);
+        limits.consume(5).unwrap();
+        let err = limits.consume(1).unwrap_err();
+        assert!(matches!(err, MultipartFormError::Overflow { limit: 10 }));
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn field_groups() {
+        let mut state = MultipartState::default();
+        state
+            .fields
+            .insert("tag".to_owned(), Box::new(vec![Bytes::from("a")]));
+
+        let err = take_field::<Bytes>(&mut state, "title").unwrap_err();
+        assert_eq!(err.to_string(), "Field `title` is missing.");
+        assert_eq!(
+            take_field::<Option<Bytes>>(&mut state, "title").unwrap(),
+            None
+        );
+        assert_eq!(take_field::<Vec<Bytes>>(&mut state, "tag").unwrap(), ["a"]);
+    }
+}
@@ -1,3 +1,7 @@
+//! NDJSON responder and decoder.
+//!
+//! See [`NdJson`] and [`Decoder`] for docs.
+
 use std::{convert::Infallible, error::Error as StdError, io::Write as _};
 
 use actix_web::{
@@ -12,6 +16,7 @@ use once_cell::sync::Lazy;
 use pin_project_lite::pin_project;
 use serde::Serialize;
 
+pub use crate::ndjson_decoder::{DecodeError, Decoder};
 use crate::util::{InfallibleStream, MutWriter};
 
 static NDJSON_MIME: Lazy<Mime> = Lazy::new(|| "application/x-ndjson".parse().unwrap());
@@ -99,7 +104,7 @@ impl NdJson<Infallible> {
     }
 }
 
-fn serialize_json_line(item: impl Serialize) -> Bytes {
+pub(crate) fn serialize_json_line(item: impl Serialize) -> Bytes {
     let mut buf = BytesMut::new();
     let mut wrt = MutWriter(&mut buf);
 
@@ -0,0 +1,312 @@
+use std::{collections::VecDeque, error::Error as StdError, fmt, marker::PhantomData};
+
+use bytes::{Bytes, BytesMut};
+use derive_more::{Display, Error};
+use futures_core::Stream;
+use futures_util::{stream, StreamExt as _};
+use serde::de::DeserializeOwned;
+
+use crate::json_de;
+
+/// Default maximum line length.
+const DEFAULT_MAX_LINE_LENGTH: usize = 1024 * 1024; // 1MiB
+
+/// Errors produced while decoding NDJSON.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// A line could not be deserialized.
+    #[display(fmt = "Failed to deserialize NDJSON line: {_0}")]
+    Json(serde_json::Error),
+
+    /// A line was longer than the maximum line length and was skipped.
+    #[display(fmt = "NDJSON line exceeded maximum length (limit: {limit} bytes).")]
+    LineTooLong {
+        /// Configured maximum line length.
+        limit: usize,
+    },
+
+    /// The underlying byte stream failed.
+    #[display(fmt = "Failed to read NDJSON stream: {_0}")]
+    Stream(#[error(not(source))] Box<dyn StdError>),
+}
+
+/// Incremental [NDJSON] decoder.
+///
+/// Bytes are fed into the decoder in chunks, which can be split at arbitrary points, and items are
+/// deserialized from each complete line. Blank lines are skipped and `\r\n` line endings are
+/// accepted.
+///
+/// Lines longer than the [maximum line length](Self::max_line_length) are skipped without being
+/// buffered, producing a [`DecodeError::LineTooLong`] error in their place. A deserialization
+/// error does not affect subsequent lines.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::ndjson::Decoder;
+///
+/// let mut decoder = Decoder::<u32>::new();
+///
+/// decoder.feed(b"1\n2");
+/// assert_eq!(decoder.next_item().unwrap().unwrap(), 1);
+/// assert!(decoder.next_item().is_none());
+///
+/// decoder.feed(b"3\n");
+/// assert_eq!(decoder.next_item().unwrap().unwrap(), 23);
+/// ```
+///
+/// Decoding a response body from `awc`, or any other stream of byte chunks:
+///
+/// ```
+/// use actix_web_lab::ndjson::Decoder;
+/// use futures_util::{stream, StreamExt as _};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let body = stream::iter([
+///     Ok::<_, std::convert::Infallible>(&b"{\"x\":1,\"y\":2}\n{\"x\":"[..]),
+///     Ok(&b"3,\"y\":4}"[..]),
+/// ])
+/// .map(|chunk| chunk.map(bytes::Bytes::from_static));
+///
+/// let points = Decoder::<Point>::new()
+///     .decode_stream(body)
+///     .map(Result::unwrap)
+///     .collect::<Vec<_>>()
+///     .await;
+///
+/// assert_eq!(points.len(), 2);
+/// assert_eq!(points[1].y, 4);
+/// # });
+/// ```
+///
+/// [NDJSON]: https://ndjson.org/
+pub struct Decoder<T> {
+    buf: BytesMut,
+    max_line_length: usize,
+
+    /// Whether the remainder of an overlong line is being discarded.
+    discarding: bool,
+
+    /// Offsets into `buf` at which overlong lines were skipped.
+    skipped_at: VecDeque<usize>,
+
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T> Decoder<T> {
+    /// Constructs a new decoder with the default maximum line length of 1MiB.
+    pub fn new() -> Self {
+        Self {
+            buf: BytesMut::new(),
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            discarding: false,
+            skipped_at: VecDeque::new(),
+            _item: PhantomData,
+        }
+    }
+
+    /// Sets maximum line length, in bytes.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// Feeds a chunk of bytes into the decoder.
+    pub fn feed(&mut self, mut chunk: &[u8]) {
+        if self.discarding {
+            match memchr::memchr(b'\n', chunk) {
+                Some(idx) => {
+                    chunk = &chunk[idx + 1..];
+                    self.discarding = false;
+                }
+                None => return,
+            }
+        }
+
+        self.buf.extend_from_slice(chunk);
+
+        let partial_start = memchr::memrchr(b'\n', &self.buf).map_or(0, |idx| idx + 1);
+
+        if self.buf.len() - partial_start > self.max_line_length {
+            self.buf.truncate(partial_start);
+            self.skipped_at.push_back(partial_start);
+            self.discarding = true;
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Decoder<T> {
+    /// Removes and returns the next item decoded from a complete line, if any.
+    pub fn next_item(&mut self) -> Option<Result<T, DecodeError>> {
+        loop {
+            if self.skipped_at.front() == Some(&0) {
+                self.skipped_at.pop_front();
+                return Some(Err(DecodeError::LineTooLong {
+                    limit: self.max_line_length,
+                }));
+            }
+
+            let idx = memchr::memchr(b'\n', &self.buf)?;
+            let line = self.take_line(idx + 1);
+
+            if let Some(item) = parse_line(&line) {
+                return Some(item);
+            }
+        }
+    }
+
+    /// Decodes any remaining partial line, for use once the input has ended.
+    pub fn finish(&mut self) -> Option<Result<T, DecodeError>> {
+        if let Some(item) = self.next_item() {
+            return Some(item);
+        }
+
+        if self.discarding {
+            self.discarding = false;
+            return None;
+        }
+
+        let line = self.take_line(self.buf.len());
+        parse_line(&line)
+    }
+
+    /// Removes the first `len` bytes from the buffer, adjusting skipped line offsets.
+    fn take_line(&mut self, len: usize) -> Bytes {
+        for offset in &mut self.skipped_at {
+            *offset -= len;
+        }
+
+        self.buf.split_to(len).freeze()
+    }
+
+    /// Decodes a stream of byte chunks into a stream of items.
+    pub fn decode_stream<S, E>(self, stream: S) -> impl Stream<Item = Result<T, DecodeError>>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: Into<Box<dyn StdError>>,
+    {
+        stream::unfold(
+            (self, Some(Box::pin(stream))),
+            |(mut decoder, mut stream)| async move {
+                loop {
+                    if let Some(item) = decoder.next_item() {
+                        return Some((item, (decoder, stream)));
+                    }
+
+                    let Some(inner) = &mut stream else {
+                        return decoder.finish().map(|item| (item, (decoder, None)));
+                    };
+
+                    match inner.next().await {
+                        Some(Ok(chunk)) => decoder.feed(&chunk),
+                        Some(Err(err)) => {
+                            // the partial line, if any, is truncated and should not be decoded
+                            decoder.discarding = true;
+                            return Some((Err(DecodeError::Stream(err.into())), (decoder, None)));
+                        }
+                        None => stream = None,
+                    }
+                }
+            },
+        )
+    }
+}
+
+impl<T> Default for Decoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Decoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decoder")
+            .field("buffered", &self.buf.len())
+            .field("max_line_length", &self.max_line_length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Deserializes a line, returning `None` if it is blank.
+fn parse_line<T: DeserializeOwned>(mut line: &[u8]) -> Option<Result<T, DecodeError>> {
+    while let [rest @ .., b'\n' | b'\r'] = line {
+        line = rest;
+    }
+
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return None;
+    }
+
+    Some(json_de::from_slice(line).map_err(DecodeError::Json))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    #[test]
+    fn decodes_split_lines() {
+        let mut decoder = Decoder::<serde_json::Value>::new();
+
+        decoder.feed(b"{\"a\":");
+        assert!(decoder.next_item().is_none());
+
+        decoder.feed(b"1}\r\n\n  \n[1,2]\nnot json\n\"tail\"");
+        assert_eq!(
+            decoder.next_item().unwrap().unwrap(),
+            serde_json::json!({ "a": 1 }),
+        );
+        assert_eq!(
+            decoder.next_item().unwrap().unwrap(),
+            serde_json::json!([1, 2]),
+        );
+        assert!(matches!(
+            decoder.next_item().unwrap().unwrap_err(),
+            DecodeError::Json(_),
+        ));
+        assert!(decoder.next_item().is_none());
+
+        assert_eq!(decoder.finish().unwrap().unwrap(), "tail");
+        assert!(decoder.finish().is_none());
+    }
+
+    #[test]
+    fn skips_long_lines() {
+        let mut decoder = Decoder::<u32>::new().max_line_length(4);
+
+        decoder.feed(b"1\n123");
+        decoder.feed(b"456");
+        decoder.feed(b"789\n2\n");
+
+        assert_eq!(decoder.next_item().unwrap().unwrap(), 1);
+        assert!(matches!(
+            decoder.next_item().unwrap().unwrap_err(),
+            DecodeError::LineTooLong { limit: 4 },
+        ));
+        assert_eq!(decoder.next_item().unwrap().unwrap(), 2);
+        assert!(decoder.next_item().is_none());
+        assert!(decoder.buf.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn decodes_streams() {
+        let chunks = ["1\n2", "\n", "3"].map(|chunk| Ok::<_, Infallible>(Bytes::from(chunk)));
+
+        let items = Decoder::<u32>::new()
+            .decode_stream(stream::iter(chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items, [1, 2, 3]);
+    }
+}
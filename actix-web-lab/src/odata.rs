@@ -0,0 +1,942 @@
+//! OData-style query options extractor.
+//!
+//! See [`ODataQuery`] for docs.
+
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, http::StatusCode, FromRequest, HttpRequest, ResponseError};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+/// OData-style query options extractor.
+///
+/// Parses the `$filter`, `$orderby`, `$top`, `$skip`, and `$select` system query options into a
+/// typed representation. Query parameters not starting with `$` are ignored so they can be used
+/// alongside this extractor; unrecognized or repeated system query options are rejected.
+///
+/// Filter expressions support a subset of the OData grammar:
+/// - comparisons of a property to a literal using `eq`, `ne`, `gt`, `ge`, `lt`, and `le`;
+/// - the `contains`, `startswith`, and `endswith` string functions;
+/// - `and`, `or`, and `not`, with the usual precedence, and parentheses;
+/// - string (`'O''Neil'`), integer, decimal, `true`, `false`, and `null` literals.
+///
+/// Use a [`FilterVisitor`] to translate filters into database queries. Since property names come
+/// from the client, check them against an allow-list using
+/// [`check_properties()`](Self::check_properties) before use.
+///
+/// Invalid query options result in a `400 Bad Request` response.
+///
+/// # Examples
+/// ```
+/// use actix_web::get;
+/// use actix_web_lab::extract::{
+///     odata::{CompareOp, FilterVisitor, Literal, StringFunction},
+///     ODataQuery,
+/// };
+///
+/// /// Builds an SQL `WHERE` clause with positional parameters.
+/// struct SqlWhere {
+///     params: Vec<Literal>,
+/// }
+///
+/// impl FilterVisitor for SqlWhere {
+///     type Output = String;
+///
+///     fn visit_compare(&mut self, property: &str, op: CompareOp, value: &Literal) -> String {
+///         self.params.push(value.clone());
+///         format!("{property} {} ${}", op.as_sql(), self.params.len())
+///     }
+///
+///     fn visit_function(&mut self, func: StringFunction, property: &str, value: &Literal) -> String {
+///         self.params.push(value.clone());
+///         let n = self.params.len();
+///
+///         match func {
+///             StringFunction::Contains => format!("{property} LIKE '%' || ${n} || '%'"),
+///             StringFunction::StartsWith => format!("{property} LIKE ${n} || '%'"),
+///             StringFunction::EndsWith => format!("{property} LIKE '%' || ${n}"),
+///         }
+///     }
+///
+///     fn visit_and(&mut self, left: String, right: String) -> String {
+///         format!("({left} AND {right})")
+///     }
+///
+///     fn visit_or(&mut self, left: String, right: String) -> String {
+///         format!("({left} OR {right})")
+///     }
+///
+///     fn visit_not(&mut self, inner: String) -> String {
+///         format!("NOT {inner}")
+///     }
+/// }
+///
+/// #[get("/people")]
+/// async fn people(query: ODataQuery) -> actix_web::Result<String> {
+///     query.check_properties(&["name", "age"])?;
+///
+///     let mut visitor = SqlWhere { params: Vec::new() };
+///     let clause = query.filter().map(|filter| filter.accept(&mut visitor));
+///
+///     Ok(format!("{clause:?} limit={:?}", query.top()))
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ODataQuery {
+    filter: Option<FilterExpr>,
+    orderby: Vec<OrderBy>,
+    top: Option<u64>,
+    skip: Option<u64>,
+    select: Vec<String>,
+}
+
+impl ODataQuery {
+    /// Parses query options from a URL query string.
+    pub fn from_query(query: &str) -> Result<Self, ODataQueryError> {
+        let params = serde_html_form::from_str::<Vec<(String, String)>>(query)
+            .map_err(ODataQueryError::Query)?;
+
+        let mut seen = Vec::new();
+        let mut odata = Self::default();
+
+        for (key, val) in params {
+            if !key.starts_with('$') {
+                continue;
+            }
+
+            if seen.contains(&key) {
+                return Err(ODataQueryError::DuplicateOption { option: key });
+            }
+
+            match key.as_str() {
+                "$filter" => odata.filter = Some(parse_filter(&val)?),
+                "$orderby" => odata.orderby = parse_orderby(&val)?,
+                "$top" => odata.top = Some(parse_count("$top", &val)?),
+                "$skip" => odata.skip = Some(parse_count("$skip", &val)?),
+                "$select" => odata.select = parse_select(&val)?,
+                _ => return Err(ODataQueryError::UnknownOption { option: key }),
+            }
+
+            seen.push(key);
+        }
+
+        Ok(odata)
+    }
+
+    /// Returns parsed `$filter` expression, if any.
+    pub fn filter(&self) -> Option<&FilterExpr> {
+        self.filter.as_ref()
+    }
+
+    /// Returns parsed `$orderby` items, in order of precedence.
+    pub fn orderby(&self) -> &[OrderBy] {
+        &self.orderby
+    }
+
+    /// Returns `$top` value, if any.
+    pub fn top(&self) -> Option<u64> {
+        self.top
+    }
+
+    /// Returns `$skip` value, if any.
+    pub fn skip(&self) -> Option<u64> {
+        self.skip
+    }
+
+    /// Returns properties listed in `$select`, or an empty slice if all properties were selected.
+    pub fn select(&self) -> &[String] {
+        &self.select
+    }
+
+    /// Checks that all properties referenced in `$filter`, `$orderby`, and `$select` are in
+    /// `allowed`.
+    pub fn check_properties(&self, allowed: &[&str]) -> Result<(), ODataQueryError> {
+        let mut properties = Vec::new();
+
+        if let Some(filter) = &self.filter {
+            filter.collect_properties(&mut properties);
+        }
+
+        properties.extend(self.orderby.iter().map(|item| item.property.as_str()));
+        properties.extend(self.select.iter().map(String::as_str));
+
+        match properties.into_iter().find(|prop| !allowed.contains(prop)) {
+            Some(property) => Err(ODataQueryError::UnknownProperty {
+                property: property.to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromRequest for ODataQuery {
+    type Error = ODataQueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_query(req.query_string()).map_err(|err| {
+            debug!(
+                "Failed to parse OData query options for `{}` handler: {err}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            err
+        }))
+    }
+}
+
+/// A parsed `$filter` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    /// Both expressions must match.
+    And(Box<FilterExpr>, Box<FilterExpr>),
+
+    /// Either expression must match.
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+
+    /// Expression must not match.
+    Not(Box<FilterExpr>),
+
+    /// Comparison of a property to a literal value.
+    Compare {
+        /// Property name.
+        property: String,
+
+        /// Comparison operator.
+        op: CompareOp,
+
+        /// Value to compare to.
+        value: Literal,
+    },
+
+    /// String function applied to a property and a literal value.
+    Function {
+        /// Function name.
+        function: StringFunction,
+
+        /// Property name.
+        property: String,
+
+        /// Function argument.
+        value: Literal,
+    },
+}
+
+impl FilterExpr {
+    /// Walks the expression tree depth-first, combining the results of visiting each node.
+    pub fn accept<V: FilterVisitor>(&self, visitor: &mut V) -> V::Output {
+        match self {
+            Self::And(left, right) => {
+                let left = left.accept(visitor);
+                let right = right.accept(visitor);
+                visitor.visit_and(left, right)
+            }
+
+            Self::Or(left, right) => {
+                let left = left.accept(visitor);
+                let right = right.accept(visitor);
+                visitor.visit_or(left, right)
+            }
+
+            Self::Not(inner) => {
+                let inner = inner.accept(visitor);
+                visitor.visit_not(inner)
+            }
+
+            Self::Compare {
+                property,
+                op,
+                value,
+            } => visitor.visit_compare(property, *op, value),
+
+            Self::Function {
+                function,
+                property,
+                value,
+            } => visitor.visit_function(*function, property, value),
+        }
+    }
+
+    fn collect_properties<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.collect_properties(out);
+                right.collect_properties(out);
+            }
+            Self::Not(inner) => inner.collect_properties(out),
+            Self::Compare { property, .. } | Self::Function { property, .. } => out.push(property),
+        }
+    }
+}
+
+/// Visitor for translating [`FilterExpr`] trees, used with [`FilterExpr::accept`].
+///
+/// Child expressions are visited before their parents so that, for example, a SQL visitor can
+/// combine the clauses generated for each side of an `and` expression.
+pub trait FilterVisitor {
+    /// Result of visiting an expression.
+    type Output;
+
+    /// Visits a comparison.
+    fn visit_compare(&mut self, property: &str, op: CompareOp, value: &Literal) -> Self::Output;
+
+    /// Visits a string function call.
+    fn visit_function(
+        &mut self,
+        function: StringFunction,
+        property: &str,
+        value: &Literal,
+    ) -> Self::Output;
+
+    /// Visits a conjunction of two visited expressions.
+    fn visit_and(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+
+    /// Visits a disjunction of two visited expressions.
+    fn visit_or(&mut self, left: Self::Output, right: Self::Output) -> Self::Output;
+
+    /// Visits a negation of a visited expression.
+    fn visit_not(&mut self, inner: Self::Output) -> Self::Output;
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    /// `eq`
+    Eq,
+
+    /// `ne`
+    Ne,
+
+    /// `gt`
+    Gt,
+
+    /// `ge`
+    Ge,
+
+    /// `lt`
+    Lt,
+
+    /// `le`
+    Le,
+}
+
+impl CompareOp {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        Some(match keyword {
+            "eq" => Self::Eq,
+            "ne" => Self::Ne,
+            "gt" => Self::Gt,
+            "ge" => Self::Ge,
+            "lt" => Self::Lt,
+            "le" => Self::Le,
+            _ => return None,
+        })
+    }
+
+    /// Returns equivalent SQL operator.
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "<>",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
+}
+
+/// String functions usable in filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StringFunction {
+    /// `contains(property, value)`
+    Contains,
+
+    /// `startswith(property, value)`
+    StartsWith,
+
+    /// `endswith(property, value)`
+    EndsWith,
+}
+
+impl StringFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "contains" => Self::Contains,
+            "startswith" => Self::StartsWith,
+            "endswith" => Self::EndsWith,
+            _ => return None,
+        })
+    }
+}
+
+/// Literal values in filters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// `null`
+    Null,
+
+    /// `true` or `false`.
+    Bool(bool),
+
+    /// Integer literal.
+    Integer(i64),
+
+    /// Decimal literal.
+    Decimal(f64),
+
+    /// String literal, with quotes removed and escaped quotes unescaped.
+    String(String),
+}
+
+/// An item of the `$orderby` option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderBy {
+    /// Property name.
+    pub property: String,
+
+    /// Sort direction.
+    pub direction: SortDirection,
+}
+
+/// Sort direction of an [`OrderBy`] item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    /// Ascending order; the default.
+    #[default]
+    Asc,
+
+    /// Descending order.
+    Desc,
+}
+
+/// Errors that can occur when parsing [`ODataQuery`] options.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ODataQueryError {
+    /// Query string could not be parsed.
+    #[display(fmt = "Query string could not be parsed: {_0}")]
+    Query(serde::de::value::Error),
+
+    /// Query contains an unsupported system query option.
+    #[display(fmt = "Query option `{option}` is not supported.")]
+    UnknownOption {
+        /// Option name.
+        option: String,
+    },
+
+    /// Query contains the same system query option more than once.
+    #[display(fmt = "Query option `{option}` is repeated.")]
+    DuplicateOption {
+        /// Option name.
+        option: String,
+    },
+
+    /// Value of a system query option is invalid.
+    #[display(fmt = "Query option `{option}` is invalid: {reason}")]
+    InvalidOption {
+        /// Option name.
+        option: &'static str,
+
+        /// Description of the problem.
+        reason: &'static str,
+    },
+
+    /// `$filter` expression is invalid.
+    #[display(fmt = "Query option `$filter` is invalid at position {position}: {reason}")]
+    InvalidFilter {
+        /// Byte offset in the filter expression at which the problem was found.
+        position: usize,
+
+        /// Description of the problem.
+        reason: &'static str,
+    },
+
+    /// Query references a property that is not allowed.
+    #[display(fmt = "Property `{property}` is not supported.")]
+    UnknownProperty {
+        /// Property name.
+        property: String,
+    },
+}
+
+impl ResponseError for ODataQueryError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+fn is_identifier(ident: &str) -> bool {
+    let mut chars = ident.chars();
+
+    matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '/' | '.'))
+}
+
+fn parse_count(option: &'static str, val: &str) -> Result<u64, ODataQueryError> {
+    val.parse().map_err(|_| ODataQueryError::InvalidOption {
+        option,
+        reason: "expected a non-negative integer",
+    })
+}
+
+fn parse_select(val: &str) -> Result<Vec<String>, ODataQueryError> {
+    val.split(',')
+        .map(str::trim)
+        .map(|prop| match prop {
+            "*" => Ok(None),
+            prop if is_identifier(prop) => Ok(Some(prop.to_owned())),
+            _ => Err(ODataQueryError::InvalidOption {
+                option: "$select",
+                reason: "expected a comma-separated list of properties",
+            }),
+        })
+        .filter_map(Result::transpose)
+        .collect()
+}
+
+fn parse_orderby(val: &str) -> Result<Vec<OrderBy>, ODataQueryError> {
+    let invalid = ODataQueryError::InvalidOption {
+        option: "$orderby",
+        reason: "expected comma-separated properties, each optionally followed by `asc` or `desc`",
+    };
+
+    val.split(',')
+        .map(|item| {
+            let mut parts = item.split_whitespace();
+
+            let property = parts.next().filter(|prop| is_identifier(prop));
+
+            let direction = match parts.next() {
+                None | Some("asc") => SortDirection::Asc,
+                Some("desc") => SortDirection::Desc,
+                Some(_) => return Err(()),
+            };
+
+            match (property, parts.next()) {
+                (Some(property), None) => Ok(OrderBy {
+                    property: property.to_owned(),
+                    direction,
+                }),
+                _ => Err(()),
+            }
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(Literal),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ODataQueryError> {
+    let invalid = |position, reason| ODataQueryError::InvalidFilter { position, reason };
+
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        match ch {
+            ch if ch.is_whitespace() => {
+                chars.next();
+            }
+
+            '(' | ')' | ',' => {
+                chars.next();
+
+                let token = match ch {
+                    '(' => Token::OpenParen,
+                    ')' => Token::CloseParen,
+                    _ => Token::Comma,
+                };
+
+                tokens.push((start, token));
+            }
+
+            '\'' => {
+                chars.next();
+                let mut val = String::new();
+
+                loop {
+                    match chars.next() {
+                        // doubled quotes are escaped quotes
+                        Some((_, '\'')) if matches!(chars.peek(), Some((_, '\''))) => {
+                            chars.next();
+                            val.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, ch)) => val.push(ch),
+                        None => return Err(invalid(start, "unterminated string literal")),
+                    }
+                }
+
+                tokens.push((start, Token::Literal(Literal::String(val))));
+            }
+
+            ch if ch.is_ascii_digit() || ch == '-' => {
+                let mut end = start;
+
+                while let Some(&(idx, ch)) = chars.peek() {
+                    if !(ch.is_ascii_digit() || matches!(ch, '-' | '.' | 'e' | 'E' | '+')) {
+                        break;
+                    }
+
+                    end = idx + ch.len_utf8();
+                    chars.next();
+                }
+
+                let num = &input[start..end];
+
+                let literal = if let Ok(int) = num.parse() {
+                    Literal::Integer(int)
+                } else if let Ok(dec) = num.parse() {
+                    Literal::Decimal(dec)
+                } else {
+                    return Err(invalid(start, "invalid number"));
+                };
+
+                tokens.push((start, Token::Literal(literal)));
+            }
+
+            ch if ch.is_ascii_alphabetic() || ch == '_' => {
+                let mut end = start;
+
+                while let Some(&(idx, ch)) = chars.peek() {
+                    if !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '/' | '.')) {
+                        break;
+                    }
+
+                    end = idx + ch.len_utf8();
+                    chars.next();
+                }
+
+                let token = match &input[start..end] {
+                    "null" => Token::Literal(Literal::Null),
+                    "true" => Token::Literal(Literal::Bool(true)),
+                    "false" => Token::Literal(Literal::Bool(false)),
+                    ident => Token::Ident(ident.to_owned()),
+                };
+
+                tokens.push((start, token));
+            }
+
+            _ => return Err(invalid(start, "unexpected character")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_filter(input: &str) -> Result<FilterExpr, ODataQueryError> {
+    let mut parser = FilterParser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        len: input.len(),
+    };
+
+    let expr = parser.parse_or()?;
+
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(_) => Err(parser.error("unexpected token after expression")),
+    }
+}
+
+/// Recursive descent parser for filter expressions.
+struct FilterParser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+}
+
+impl FilterParser {
+    fn error(&self, reason: &'static str) -> ODataQueryError {
+        let position = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.len, |(position, _)| *position);
+
+        ODataQueryError::InvalidFilter { position, reason }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword);
+
+        if found {
+            self.pos += 1;
+        }
+
+        found
+    }
+
+    fn expect(&mut self, token: Token, reason: &'static str) -> Result<(), ODataQueryError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, ODataQueryError> {
+        let mut expr = self.parse_and()?;
+
+        while self.eat_keyword("or") {
+            expr = FilterExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, ODataQueryError> {
+        let mut expr = self.parse_unary()?;
+
+        while self.eat_keyword("and") {
+            expr = FilterExpr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr, ODataQueryError> {
+        if self.eat_keyword("not") {
+            return Ok(FilterExpr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        if self.peek() == Some(&Token::OpenParen) {
+            self.pos += 1;
+            let expr = self.parse_or()?;
+            self.expect(Token::CloseParen, "expected `)`")?;
+            return Ok(expr);
+        }
+
+        let property = match self.peek() {
+            Some(Token::Ident(ident)) => ident.clone(),
+            _ => return Err(self.error("expected property or function")),
+        };
+        self.pos += 1;
+
+        if let Some(function) = StringFunction::from_name(&property) {
+            self.expect(Token::OpenParen, "expected `(`")?;
+            let property = self.parse_property()?;
+            self.expect(Token::Comma, "expected `,`")?;
+            let value = self.parse_literal()?;
+            self.expect(Token::CloseParen, "expected `)`")?;
+
+            return Ok(FilterExpr::Function {
+                function,
+                property,
+                value,
+            });
+        }
+
+        let op = match self.peek() {
+            Some(Token::Ident(keyword)) => CompareOp::from_keyword(keyword),
+            _ => None,
+        };
+        let op = op.ok_or_else(|| self.error("expected comparison operator"))?;
+        self.pos += 1;
+
+        let value = self.parse_literal()?;
+
+        Ok(FilterExpr::Compare {
+            property,
+            op,
+            value,
+        })
+    }
+
+    fn parse_property(&mut self) -> Result<String, ODataQueryError> {
+        match self.peek() {
+            Some(Token::Ident(_)) => match self.next() {
+                Some(Token::Ident(ident)) => Ok(ident),
+                _ => unreachable!(),
+            },
+            _ => Err(self.error("expected property")),
+        }
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ODataQueryError> {
+        match self.peek() {
+            Some(Token::Literal(_)) => match self.next() {
+                Some(Token::Literal(literal)) => Ok(literal),
+                _ => unreachable!(),
+            },
+            _ => Err(self.error("expected literal value")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn compare(property: &str, op: CompareOp, value: Literal) -> FilterExpr {
+        FilterExpr::Compare {
+            property: property.to_owned(),
+            op,
+            value,
+        }
+    }
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(
+            parse_filter("Name eq 'O''Neil' and not (Age lt 18 or Score ge -1.5e2)").unwrap(),
+            FilterExpr::And(
+                Box::new(compare(
+                    "Name",
+                    CompareOp::Eq,
+                    Literal::String("O'Neil".to_owned())
+                )),
+                Box::new(FilterExpr::Not(Box::new(FilterExpr::Or(
+                    Box::new(compare("Age", CompareOp::Lt, Literal::Integer(18))),
+                    Box::new(compare("Score", CompareOp::Ge, Literal::Decimal(-150.0))),
+                )))),
+            ),
+        );
+
+        // and binds tighter than or
+        assert_eq!(
+            parse_filter("a eq 1 or b eq true and contains(c/d, 'x')").unwrap(),
+            FilterExpr::Or(
+                Box::new(compare("a", CompareOp::Eq, Literal::Integer(1))),
+                Box::new(FilterExpr::And(
+                    Box::new(compare("b", CompareOp::Eq, Literal::Bool(true))),
+                    Box::new(FilterExpr::Function {
+                        function: StringFunction::Contains,
+                        property: "c/d".to_owned(),
+                        value: Literal::String("x".to_owned()),
+                    }),
+                )),
+            ),
+        );
+
+        let err = |input| match parse_filter(input).unwrap_err() {
+            ODataQueryError::InvalidFilter { position, .. } => position,
+            err => panic!("unexpected error: {err}"),
+        };
+
+        assert_eq!(err("Name eq 'abc"), 8);
+        assert_eq!(err("Name is 1"), 5);
+        assert_eq!(err("(Name eq 1"), 10);
+        assert_eq!(err("Name eq 1 Age"), 10);
+        assert_eq!(err("Name eq 1 & 2"), 10);
+        assert_eq!(err("startswith(Name 'a')"), 16);
+    }
+
+    #[test]
+    fn parses_options() {
+        let query = ODataQuery::from_query(
+            "%24filter=Age%20gt%2030&$orderby=Name,%20Age%20desc&$top=10&$skip=20&$select=Name,Age&page=1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.filter(),
+            Some(&compare("Age", CompareOp::Gt, Literal::Integer(30)))
+        );
+        assert_eq!(
+            query.orderby(),
+            [
+                OrderBy {
+                    property: "Name".to_owned(),
+                    direction: SortDirection::Asc,
+                },
+                OrderBy {
+                    property: "Age".to_owned(),
+                    direction: SortDirection::Desc,
+                },
+            ],
+        );
+        assert_eq!(query.top(), Some(10));
+        assert_eq!(query.skip(), Some(20));
+        assert_eq!(query.select(), ["Name", "Age"]);
+
+        query.check_properties(&["Name", "Age"]).unwrap();
+        assert!(matches!(
+            query.check_properties(&["Name"]),
+            Err(ODataQueryError::UnknownProperty { property }) if property == "Age"
+        ));
+
+        for invalid in [
+            "$top=-1",
+            "$top=1&$top=2",
+            "$expand=Friends",
+            "$orderby=Name%20up",
+            "$orderby=",
+            "$select=a;b",
+        ] {
+            assert!(ODataQuery::from_query(invalid).is_err(), "{invalid}");
+        }
+
+        assert!(ODataQuery::from_query("$select=*")
+            .unwrap()
+            .select()
+            .is_empty());
+    }
+
+    #[actix_web::test]
+    async fn extracts() {
+        let req = TestRequest::with_uri("/?$top=5").to_http_request();
+        let query = ODataQuery::extract(&req).await.unwrap();
+        assert_eq!(query.top(), Some(5));
+
+        let req = TestRequest::with_uri("/?$top=five").to_http_request();
+        let err = ODataQuery::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn visits_filters() {
+        struct Count(usize);
+
+        impl FilterVisitor for Count {
+            type Output = usize;
+
+            fn visit_compare(&mut self, _: &str, _: CompareOp, _: &Literal) -> usize {
+                self.0 += 1;
+                1
+            }
+
+            fn visit_function(&mut self, _: StringFunction, _: &str, _: &Literal) -> usize {
+                self.0 += 1;
+                1
+            }
+
+            fn visit_and(&mut self, left: usize, right: usize) -> usize {
+                left + right
+            }
+
+            fn visit_or(&mut self, left: usize, right: usize) -> usize {
+                left.max(right)
+            }
+
+            fn visit_not(&mut self, inner: usize) -> usize {
+                inner
+            }
+        }
+
+        let filter = parse_filter("a eq 1 and (b eq 2 or not endswith(c, 'x'))").unwrap();
+        let mut visitor = Count(0);
+        assert_eq!(filter.accept(&mut visitor), 2);
+        assert_eq!(visitor.0, 3);
+    }
+}
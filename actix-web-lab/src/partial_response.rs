@@ -0,0 +1,464 @@
+//! Partial response field selection.
+
+use std::{
+    collections::BTreeMap,
+    future::{ready, Ready},
+};
+
+use actix_web::{
+    body::BoxBody, dev::Payload, http::StatusCode, FromRequest, HttpRequest, HttpResponse,
+    Responder, ResponseError,
+};
+use derive_more::{Display, Error};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+
+/// Partial response field selection, parsed from the `fields` query parameter.
+///
+/// Selectors use the format popularized by Google APIs: a comma-separated list of field names,
+/// where `a/b` selects field `b` of object `a` and `a(b,c)` selects fields `b` and `c` of object
+/// `a`. Selections apply to each element of arrays. For example,
+/// `fields=id,author/name,comments(id,body)`.
+///
+/// When no `fields` parameter is present, all fields are selected. An invalid selector causes a
+/// `400 Bad Request` response.
+///
+/// The [`PartialJson`](crate::respond::PartialJson) responder applies the selection
+/// automatically; this extractor is useful for avoiding loading fields that will not be included
+/// in responses.
+///
+/// # Examples
+/// ```
+/// use actix_web::get;
+/// use actix_web_lab::extract::FieldSelector;
+///
+/// #[get("/books")]
+/// async fn books(fields: FieldSelector) -> String {
+///     if fields.includes("author/biography") {
+///         "books with author biographies".to_owned()
+///     } else {
+///         "books".to_owned()
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelector {
+    selection: Option<Selection>,
+}
+
+impl FieldSelector {
+    /// Parses a field selector, such as `id,author(name,email)`.
+    pub fn parse(selector: &str) -> Result<Self, FieldSelectorError> {
+        let mut parser = Parser {
+            input: selector,
+            pos: 0,
+        };
+
+        let selection = parser.selection()?;
+
+        if parser.pos < selector.len() {
+            return Err(parser.error("unexpected character"));
+        }
+
+        Ok(Self {
+            selection: Some(selection),
+        })
+    }
+
+    /// Parses field selection from the `fields` parameter of a query string.
+    pub fn from_query(query: &str) -> Result<Self, FieldSelectorError> {
+        let params = serde_html_form::from_str::<Vec<(String, String)>>(query)
+            .map_err(FieldSelectorError::Query)?;
+
+        match params.iter().find(|(key, _)| key == "fields") {
+            Some((_, selector)) => Self::parse(selector),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Returns true if all fields are selected.
+    pub fn is_all(&self) -> bool {
+        self.selection.is_none()
+    }
+
+    /// Returns true if the field at `path` (using `/` as the separator) will be included in
+    /// responses, either in full or in part.
+    pub fn includes(&self, path: &str) -> bool {
+        let Some(mut selection) = self.selection.as_ref() else {
+            return true;
+        };
+
+        for name in path.split('/') {
+            match selection.fields.get(name) {
+                None => return false,
+                Some(Node::All) => return true,
+                Some(Node::Partial(inner)) => selection = inner,
+            }
+        }
+
+        true
+    }
+
+    /// Removes unselected fields from a JSON value.
+    pub fn apply(&self, value: &mut Value) {
+        if let Some(selection) = &self.selection {
+            selection.prune(value);
+        }
+    }
+}
+
+impl FromRequest for FieldSelector {
+    type Error = FieldSelectorError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::from_query(req.query_string()).map_err(|err| {
+            debug!(
+                "Failed to parse field selector for `{}` handler: {err}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            err
+        }))
+    }
+}
+
+/// Selected fields of an object.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Selection {
+    fields: BTreeMap<String, Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    /// Field is included in full.
+    All,
+
+    /// Only some sub-fields of the field are included.
+    Partial(Selection),
+}
+
+impl Selection {
+    fn insert(&mut self, path: &[String], sub: Option<Selection>) {
+        let (name, rest) = path.split_first().expect("paths are never empty");
+
+        if rest.is_empty() {
+            match sub {
+                None => {
+                    self.fields.insert(name.clone(), Node::All);
+                }
+
+                Some(sub) => match self.fields.get_mut(name) {
+                    Some(Node::All) => {}
+                    Some(Node::Partial(existing)) => existing.merge(sub),
+                    None => {
+                        self.fields.insert(name.clone(), Node::Partial(sub));
+                    }
+                },
+            }
+
+            return;
+        }
+
+        let node = self
+            .fields
+            .entry(name.clone())
+            .or_insert_with(|| Node::Partial(Selection::default()));
+
+        if let Node::Partial(inner) = node {
+            inner.insert(rest, sub);
+        }
+    }
+
+    fn merge(&mut self, other: Selection) {
+        for (name, node) in other.fields {
+            match node {
+                Node::All => self.insert(&[name], None),
+                Node::Partial(sub) => self.insert(&[name], Some(sub)),
+            }
+        }
+    }
+
+    fn prune(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|key, _| self.fields.contains_key(key));
+
+                for (key, val) in map.iter_mut() {
+                    if let Some(Node::Partial(inner)) = self.fields.get(key) {
+                        inner.prune(val);
+                    }
+                }
+            }
+
+            Value::Array(items) => {
+                for item in items {
+                    self.prune(item);
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Errors that can occur when parsing a [`FieldSelector`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum FieldSelectorError {
+    /// Query string could not be parsed.
+    #[display(fmt = "Query string could not be parsed: {_0}")]
+    Query(serde::de::value::Error),
+
+    /// Field selector is not valid.
+    #[display(fmt = "Invalid field selector at position {position}: {reason}.")]
+    Syntax {
+        /// Byte offset in the selector at which the problem was found.
+        position: usize,
+
+        /// Description of the problem.
+        reason: &'static str,
+    },
+}
+
+impl ResponseError for FieldSelectorError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Recursive descent parser for field selectors.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &'static str) -> FieldSelectorError {
+        FieldSelectorError::Syntax {
+            position: self.pos,
+            reason,
+        }
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.input[self.pos..].starts_with(ch);
+
+        if found {
+            self.pos += ch.len_utf8();
+        }
+
+        found
+    }
+
+    fn selection(&mut self) -> Result<Selection, FieldSelectorError> {
+        let mut selection = Selection::default();
+
+        loop {
+            let mut path = vec![self.name()?];
+
+            while self.eat('/') {
+                path.push(self.name()?);
+            }
+
+            let sub = if self.eat('(') {
+                let sub = self.selection()?;
+
+                if !self.eat(')') {
+                    return Err(self.error("expected `)`"));
+                }
+
+                Some(sub)
+            } else {
+                None
+            };
+
+            selection.insert(&path, sub);
+
+            if !self.eat(',') {
+                return Ok(selection);
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, FieldSelectorError> {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|ch: char| matches!(ch, ',' | '/' | '(' | ')') || ch.is_whitespace())
+            .unwrap_or(rest.len());
+
+        if len == 0 {
+            return Err(self.error("expected field name"));
+        }
+
+        self.pos += len;
+        Ok(rest[..len].to_owned())
+    }
+}
+
+/// JSON responder that applies partial response field selection.
+///
+/// The inner value is serialized as JSON and pruned according to the request's `fields` query
+/// parameter (see [`FieldSelector`](crate::extract::FieldSelector) for the selector format),
+/// allowing clients to request only the fields they need without changes to handler logic.
+///
+/// Pruning requires buffering the serialized value into a [`serde_json::Value`] tree; when no
+/// `fields` parameter is present, the value is serialized directly.
+///
+/// Responds with `400 Bad Request` if the selector is invalid and an empty
+/// `500 Internal Server Error` if serialization fails.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::respond::PartialJson;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Book {
+///     id: u64,
+///     title: String,
+///     summary: String,
+/// }
+///
+/// // `GET /books?fields=id,title` omits the summary
+/// #[get("/books")]
+/// async fn books() -> impl Responder {
+///     PartialJson(vec![Book {
+///         id: 1,
+///         title: "Dune".to_owned(),
+///         summary: "Spice and sand.".to_owned(),
+///     }])
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PartialJson<T>(pub T);
+
+impl<T: Serialize> Responder for PartialJson<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let selector = match FieldSelector::from_query(req.query_string()) {
+            Ok(selector) => selector,
+            Err(err) => return HttpResponse::from_error(err),
+        };
+
+        let body = if selector.is_all() {
+            serde_json::to_vec(&self.0)
+        } else {
+            serde_json::to_value(&self.0).and_then(|mut value| {
+                selector.apply(&mut value);
+                serde_json::to_vec(&value)
+            })
+        };
+
+        match body {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(mime::APPLICATION_JSON)
+                .body(body),
+
+            Err(err) => {
+                debug!("Failed to serialize partial JSON response: {err}");
+                HttpResponse::new(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest};
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "id": 1,
+            "title": "Dune",
+            "author": { "name": "Frank Herbert", "born": 1920 },
+            "comments": [
+                { "id": 1, "body": "Great", "likes": 3 },
+                { "id": 2, "body": "Long", "likes": 0 },
+            ],
+        })
+    }
+
+    #[test]
+    fn parse_and_apply() {
+        let selector = FieldSelector::parse("id,author/name,comments(id,body)").unwrap();
+        let mut value = document();
+        selector.apply(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "id": 1,
+                "author": { "name": "Frank Herbert" },
+                "comments": [{ "id": 1, "body": "Great" }, { "id": 2, "body": "Long" }],
+            }),
+        );
+
+        // full selection takes precedence over partial selection
+        let selector = FieldSelector::parse("author/name,author").unwrap();
+        assert_eq!(selector, FieldSelector::parse("author").unwrap());
+
+        // nested selections are merged
+        let selector = FieldSelector::parse("author(name),author/born").unwrap();
+        assert_eq!(selector, FieldSelector::parse("author(born,name)").unwrap());
+    }
+
+    #[test]
+    fn includes() {
+        let selector = FieldSelector::from_query("fields=id,author(name)").unwrap();
+        assert!(selector.includes("id"));
+        assert!(selector.includes("author"));
+        assert!(selector.includes("author/name"));
+        assert!(!selector.includes("author/born"));
+        assert!(!selector.includes("title"));
+
+        let selector = FieldSelector::from_query("page=2").unwrap();
+        assert!(selector.is_all());
+        assert!(selector.includes("anything/at/all"));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for (selector, position) in [
+            ("", 0),
+            ("id,", 3),
+            ("a/", 2),
+            ("a(b", 3),
+            ("a()", 2),
+            ("a)", 1),
+        ] {
+            match FieldSelector::parse(selector) {
+                Err(FieldSelectorError::Syntax { position: pos, .. }) => {
+                    assert_eq!(pos, position, "selector: {selector:?}")
+                }
+                res => panic!("unexpected result for {selector:?}: {res:?}"),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn responder() {
+        let req = TestRequest::with_uri("/?fields=title,comments/likes").to_http_request();
+        let res = PartialJson(document()).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({ "title": "Dune", "comments": [{ "likes": 3 }, { "likes": 0 }] }),
+        );
+
+        let req = TestRequest::default().to_http_request();
+        let res = PartialJson(document()).respond_to(&req);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), document());
+
+        let req = TestRequest::with_uri("/?fields=a(b").to_http_request();
+        let res = PartialJson(document()).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,290 @@
+//! Accept-Language negotiation extractor.
+//!
+//! See [`PreferredLanguage`] docs.
+
+use std::cmp::Ordering;
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::Payload,
+    http::{header, StatusCode},
+    web, FromRequest, HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+/// Language negotiated from the request's `Accept-Language` header.
+///
+/// The client's preferences are matched, in order of their quality values, against the languages
+/// supported by the server, which are set using a [`PreferredLanguageConfig`]. A preference
+/// matches a supported language if they are equal, ignoring case, or if one is a prefix of the
+/// other in whole subtags; e.g., a preference for `en` matches `en-GB`, and a preference for
+/// `fr-CA` matches `fr`. Exact matches are preferred over prefix matches.
+///
+/// If no preference matches, the first supported language is used as a fallback, unless the
+/// config [requires a match](PreferredLanguageConfig::require_match).
+///
+/// If no config is registered, any language is accepted and the client's most preferred language
+/// is used.
+///
+/// # Extractor
+/// Extraction fails with a [`PreferredLanguageError`] if no language could be negotiated. Use
+/// `Option<PreferredLanguage>` to handle this case.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App};
+/// use actix_web_lab::extract::{PreferredLanguage, PreferredLanguageConfig};
+///
+/// #[get("/")]
+/// async fn greeting(lang: PreferredLanguage) -> &'static str {
+///     match lang.as_str() {
+///         "fr" => "Bonjour",
+///         "de" => "Hallo",
+///         _ => "Hello",
+///     }
+/// }
+///
+/// App::new()
+///     .app_data(PreferredLanguageConfig::new(["en", "fr", "de"]))
+///     .service(greeting);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreferredLanguage(String);
+
+impl_more::impl_deref!(PreferredLanguage => String);
+impl_more::forward_display!(PreferredLanguage);
+
+impl PreferredLanguage {
+    /// Returns negotiated language tag.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps into inner language tag.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromRequest for PreferredLanguage {
+    type Error = PreferredLanguageError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = PreferredLanguageConfig::from_req(req);
+        let preferences = parse_accept_language(req);
+
+        ready(config.negotiate(&preferences).map(Self).ok_or_else(|| {
+            debug!(
+                "Failed to extract `PreferredLanguage` for `{}` handler. No acceptable language \
+                found.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            PreferredLanguageError::NotAcceptable
+        }))
+    }
+}
+
+/// Errors that can occur when extracting a [`PreferredLanguage`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum PreferredLanguageError {
+    /// None of the client's preferred languages are supported.
+    #[display(fmt = "None of the requested languages are supported.")]
+    NotAcceptable,
+}
+
+impl ResponseError for PreferredLanguageError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::NOT_ACCEPTABLE
+    }
+}
+
+/// Configuration for the [`PreferredLanguage`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, Default)]
+pub struct PreferredLanguageConfig {
+    supported: Vec<String>,
+    require_match: bool,
+}
+
+static DEFAULT_CONFIG: PreferredLanguageConfig = PreferredLanguageConfig {
+    supported: Vec::new(),
+    require_match: false,
+};
+
+impl PreferredLanguageConfig {
+    /// Constructs new config which negotiates against `supported` languages.
+    ///
+    /// The first supported language is used when no preference matches.
+    pub fn new(supported: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            supported: supported.into_iter().map(Into::into).collect(),
+            require_match: false,
+        }
+    }
+
+    /// Causes extraction to fail, rather than falling back to the first supported language, when
+    /// no preference matches.
+    pub fn require_match(mut self) -> Self {
+        self.require_match = true;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn negotiate(&self, preferences: &[&str]) -> Option<String> {
+        if self.supported.is_empty() {
+            return preferences
+                .iter()
+                .find(|pref| **pref != "*")
+                .map(|pref| (*pref).to_owned());
+        }
+
+        let matched = preferences.iter().find_map(|pref| {
+            if *pref == "*" {
+                return self.supported.first();
+            }
+
+            self.supported
+                .iter()
+                .find(|lang| lang.eq_ignore_ascii_case(pref))
+                .or_else(|| {
+                    self.supported
+                        .iter()
+                        .find(|lang| is_prefix(lang, pref) || is_prefix(pref, lang))
+                })
+        });
+
+        match matched {
+            Some(lang) => Some(lang.clone()),
+            None if self.require_match => None,
+            None => self.supported.first().cloned(),
+        }
+    }
+}
+
+/// Returns true if `prefix` is a prefix of `tag` in whole subtags, ignoring case.
+fn is_prefix(prefix: &str, tag: &str) -> bool {
+    tag.len() > prefix.len()
+        && tag.as_bytes()[prefix.len()] == b'-'
+        && tag[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+/// Returns language ranges from `Accept-Language` headers, most preferred first.
+///
+/// Ranges with a quality of zero or an invalid quality are excluded. Ranges of equal quality keep
+/// the order they were sent in.
+fn parse_accept_language(req: &HttpRequest) -> Vec<&str> {
+    let mut ranges = req
+        .headers()
+        .get_all(header::ACCEPT_LANGUAGE)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let tag = parts.next().filter(|tag| !tag.is_empty())?;
+
+            let quality = match parts.find_map(|param| param.strip_prefix("q=")) {
+                Some(q) => q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?,
+                None => 1.0,
+            };
+
+            (quality > 0.0).then_some((tag, quality))
+        })
+        .collect::<Vec<_>>();
+
+    // stable sort keeps order of ranges with equal quality
+    ranges.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+    ranges.into_iter().map(|(tag, _)| tag).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    async fn negotiate(config: PreferredLanguageConfig, accept: &str) -> Option<String> {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT_LANGUAGE, accept))
+            .app_data(config)
+            .to_http_request();
+
+        PreferredLanguage::extract(&req)
+            .await
+            .ok()
+            .map(PreferredLanguage::into_inner)
+    }
+
+    #[actix_web::test]
+    async fn negotiates_by_quality() {
+        let config = PreferredLanguageConfig::new(["en", "fr", "de"]);
+
+        let lang = negotiate(config.clone(), "de;q=0.5, fr;q=0.9, en;q=0.1").await;
+        assert_eq!(lang.as_deref(), Some("fr"));
+
+        let lang = negotiate(config.clone(), "es, de").await;
+        assert_eq!(lang.as_deref(), Some("de"));
+
+        let lang = negotiate(config.clone(), "fr;q=0, de;q=0.2").await;
+        assert_eq!(lang.as_deref(), Some("de"));
+
+        let lang = negotiate(config, "es, *;q=0.1").await;
+        assert_eq!(lang.as_deref(), Some("en"));
+    }
+
+    #[actix_web::test]
+    async fn prefix_matches() {
+        let config = PreferredLanguageConfig::new(["en-GB", "fr", "pt-BR", "pt"]);
+
+        let lang = negotiate(config.clone(), "en").await;
+        assert_eq!(lang.as_deref(), Some("en-GB"));
+
+        let lang = negotiate(config.clone(), "FR-ca").await;
+        assert_eq!(lang.as_deref(), Some("fr"));
+
+        let lang = negotiate(config.clone(), "pt").await;
+        assert_eq!(lang.as_deref(), Some("pt"));
+
+        let lang = negotiate(config, "fry").await;
+        assert_eq!(lang.as_deref(), Some("en-GB"));
+    }
+
+    #[actix_web::test]
+    async fn fallback_and_require_match() {
+        let config = PreferredLanguageConfig::new(["en", "fr"]);
+        let lang = negotiate(config.clone(), "ja").await;
+        assert_eq!(lang.as_deref(), Some("en"));
+
+        let req = TestRequest::default()
+            .app_data(web::Data::new(config.clone()))
+            .to_http_request();
+        let lang = PreferredLanguage::extract(&req).await.unwrap();
+        assert_eq!(lang.as_str(), "en");
+
+        let lang = negotiate(config.require_match(), "ja").await;
+        assert_eq!(lang, None);
+    }
+
+    #[actix_web::test]
+    async fn without_config() {
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT_LANGUAGE, "*, nl-BE;q=0.8"))
+            .to_http_request();
+        let lang = PreferredLanguage::extract(&req).await.unwrap();
+        assert_eq!(lang.as_str(), "nl-BE");
+
+        let req = TestRequest::default().to_http_request();
+        let err = PreferredLanguage::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::NOT_ACCEPTABLE);
+    }
+}
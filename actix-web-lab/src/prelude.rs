@@ -0,0 +1,23 @@
+//! Commonly used traits and functions, for glob importing.
+//!
+//! Extension traits are imported anonymously so that their names do not conflict with other
+//! items in scope; import them from their own modules if they need to be named.
+//!
+//! # Examples
+//! ```
+//! use actix_web::HttpRequest;
+//! use actix_web_lab::prelude::*;
+//!
+//! fn tag_request(req: &HttpRequest) {
+//!     // `InsertExt` is in scope
+//!     req.insert_ext(42_u32);
+//! }
+//! ```
+
+pub use crate::{
+    ext::InsertExt as _,
+    extract::{Ext, RequestSignatureScheme},
+    middleware::{from_fn, Next, SignatureKeyResolver, TxProvider},
+    response_ext::ServiceResponseExt as _,
+    util::{Clock, Entropy},
+};
@@ -0,0 +1,405 @@
+//! Problem details responder and API result adapter.
+//!
+//! See [`ProblemDetails`] and [`ApiResult`] for docs.
+
+use std::{borrow::Cow, fmt, sync::Arc};
+
+use actix_web::{
+    body::BoxBody,
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, HttpRequest, HttpResponse, Responder,
+};
+use ahash::AHashMap;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::request_snapshot::RequestSnapshot;
+
+/// Media type of problem details JSON documents.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An [RFC 9457] problem details responder.
+///
+/// Problems either have an explicit status or a domain error [code](Self::from_code) whose status
+/// is looked up in the [`ProblemStatusMap`] registered as app data when responding. Problems with
+/// neither, or with an unmapped code, respond with `500 Internal Server Error`.
+///
+/// The code, if any, is included in the document as the `code` extension member. If a
+/// [`RequestSnapshot`] was captured for the request, its ID is included as the `snapshot_id`
+/// extension member.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::StatusCode, App};
+/// use actix_web_lab::respond::{ProblemDetails, ProblemStatusMap};
+///
+/// #[get("/")]
+/// async fn index() -> ProblemDetails {
+///     ProblemDetails::from_code("out_of_stock")
+///         .title("Item is out of stock")
+///         .extension("restock_eta_days", 3)
+/// }
+///
+/// App::new()
+///     .app_data(ProblemStatusMap::new().code("out_of_stock", StatusCode::CONFLICT))
+///     .service(index)
+/// # ;
+/// ```
+///
+/// [RFC 9457]: https://www.rfc-editor.org/rfc/rfc9457
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemDetails {
+    status: Option<StatusCode>,
+    code: Option<Cow<'static, str>>,
+    type_uri: Option<String>,
+    title: Option<String>,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl ProblemDetails {
+    /// Constructs a new problem with an explicit status code.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status: Some(status),
+            ..Self::empty()
+        }
+    }
+
+    /// Constructs a new problem with a domain error code, whose status is resolved using the
+    /// [`ProblemStatusMap`] app data.
+    pub fn from_code(code: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            code: Some(code.into()),
+            ..Self::empty()
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            status: None,
+            code: None,
+            type_uri: None,
+            title: None,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Sets URI reference identifying the problem type.
+    ///
+    /// Defaults to `about:blank`, in which case the title defaults to the status's reason phrase.
+    pub fn type_uri(mut self, type_uri: impl Into<String>) -> Self {
+        self.type_uri = Some(type_uri.into());
+        self
+    }
+
+    /// Sets short, human-readable summary of the problem type.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets human-readable explanation specific to this occurrence of the problem.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets URI reference identifying this occurrence of the problem.
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Adds an extension member.
+    ///
+    /// # Panics
+    /// Panics if `value` fails to serialize.
+    pub fn extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("problem extension should serialize");
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// Returns explicit status code, if set.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// Returns domain error code, if set.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Resolves status code for this problem using the mapping registered on `req`.
+    fn resolve_status(&self, req: &HttpRequest) -> StatusCode {
+        self.status
+            .or_else(|| {
+                let code = self.code.as_deref()?;
+                let map = req.app_data::<ProblemStatusMap>().or_else(|| {
+                    req.app_data::<web::Data<ProblemStatusMap>>()
+                        .map(web::Data::get_ref)
+                })?;
+                map.get(code)
+            })
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn to_document(&self, status: StatusCode) -> Value {
+        let mut doc = self.extensions.clone();
+
+        if let Some(code) = &self.code {
+            doc.insert("code".to_owned(), code.as_ref().into());
+        }
+
+        let title = match (&self.title, &self.type_uri) {
+            (Some(title), _) => Some(title.clone()),
+            (None, None) => status.canonical_reason().map(str::to_owned),
+            (None, Some(_)) => None,
+        };
+
+        let members = [
+            ("type", self.type_uri.clone()),
+            ("title", title),
+            ("detail", self.detail.clone()),
+            ("instance", self.instance.clone()),
+        ];
+
+        for (name, value) in members {
+            if let Some(value) = value {
+                doc.insert(name.to_owned(), value.into());
+            }
+        }
+
+        doc.insert("status".to_owned(), status.as_u16().into());
+
+        Value::Object(doc)
+    }
+}
+
+impl fmt::Display for ProblemDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self
+            .title
+            .as_deref()
+            .or(self.code.as_deref())
+            .unwrap_or("problem");
+
+        match &self.detail {
+            Some(detail) => write!(f, "{summary}: {detail}"),
+            None => f.write_str(summary),
+        }
+    }
+}
+
+impl Responder for ProblemDetails {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let status = self.resolve_status(req);
+        let mut doc = self.to_document(status);
+
+        if let (Value::Object(doc), Some(id)) = (&mut doc, RequestSnapshot::captured_id(req)) {
+            doc.entry("snapshot_id").or_insert_with(|| id.into());
+        }
+
+        let mut res = HttpResponse::build(status).json(doc);
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        res
+    }
+}
+
+/// Mapping from domain error codes to HTTP status codes, used by [`ProblemDetails`] responses.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Cheap to clone.
+///
+/// See [`ProblemDetails`] docs for an example.
+#[derive(Debug, Clone, Default)]
+pub struct ProblemStatusMap {
+    codes: Arc<AHashMap<Cow<'static, str>, StatusCode>>,
+}
+
+impl ProblemStatusMap {
+    /// Constructs a new, empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps domain error `code` to `status`.
+    pub fn code(mut self, code: impl Into<Cow<'static, str>>, status: StatusCode) -> Self {
+        Arc::make_mut(&mut self.codes).insert(code.into(), status);
+        self
+    }
+
+    /// Returns status mapped to `code`, if any.
+    pub fn get(&self, code: &str) -> Option<StatusCode> {
+        self.codes.get(code).copied()
+    }
+}
+
+/// A responder for fallible API handlers.
+///
+/// `Ok` values are serialized as JSON with a `200 OK` status and errors are converted into
+/// [`ProblemDetails`] responses, with status codes resolved using the [`ProblemStatusMap`] app
+/// data. Any error type which converts into `ProblemDetails` can be used with the `?` operator
+/// through [`ApiResult::from`]. Problem details are boxed to keep successful results small.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+/// use actix_web_lab::respond::{ApiResult, ProblemDetails};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Account {
+///     balance: u64,
+/// }
+///
+/// enum AccountError {
+///     Frozen,
+/// }
+///
+/// impl From<AccountError> for ProblemDetails {
+///     fn from(err: AccountError) -> Self {
+///         match err {
+///             AccountError::Frozen => ProblemDetails::from_code("account_frozen"),
+///         }
+///     }
+/// }
+///
+/// fn load_account(id: u64) -> Result<Account, AccountError> {
+///     # let _ = id;
+///     Err(AccountError::Frozen)
+/// }
+///
+/// #[get("/accounts/{id}")]
+/// async fn account(id: web::Path<u64>) -> ApiResult<Account> {
+///     load_account(id.into_inner()).into()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ApiResult<T>(pub Result<T, Box<ProblemDetails>>);
+
+impl<T> ApiResult<T> {
+    /// Unwraps into the inner result.
+    pub fn into_inner(self) -> Result<T, Box<ProblemDetails>> {
+        self.0
+    }
+}
+
+impl<T, E: Into<ProblemDetails>> From<Result<T, E>> for ApiResult<T> {
+    fn from(res: Result<T, E>) -> Self {
+        Self(res.map_err(|err| Box::new(err.into())))
+    }
+}
+
+impl<T: Serialize> Responder for ApiResult<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        match self.0 {
+            Ok(val) => HttpResponse::Ok().json(val),
+            Err(problem) => (*problem).respond_to(req),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest};
+
+    use super::*;
+
+    async fn to_json(res: HttpResponse) -> Value {
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn explicit_status() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = ProblemDetails::new(StatusCode::NOT_FOUND)
+            .detail("no such widget")
+            .instance("/widgets/7")
+            .respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            PROBLEM_JSON
+        );
+
+        assert_eq!(
+            to_json(res).await,
+            serde_json::json!({
+                "title": "Not Found",
+                "status": 404,
+                "detail": "no such widget",
+                "instance": "/widgets/7",
+            }),
+        );
+    }
+
+    #[actix_web::test]
+    async fn mapped_code() {
+        let map = ProblemStatusMap::new().code("account_frozen", StatusCode::FORBIDDEN);
+
+        let req = TestRequest::default()
+            .app_data(map.clone())
+            .to_http_request();
+        let res = ProblemDetails::from_code("account_frozen").respond_to(&req);
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let doc = to_json(res).await;
+        assert_eq!(doc["code"], "account_frozen");
+        assert_eq!(doc["status"], 403);
+
+        let req = TestRequest::default()
+            .app_data(web::Data::new(map))
+            .to_http_request();
+        let res = ProblemDetails::from_code("account_frozen").respond_to(&req);
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // unmapped codes
+        let res = ProblemDetails::from_code("other")
+            .type_uri("https://example.com/probs/other")
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(to_json(res).await.get("title").is_none());
+    }
+
+    #[actix_web::test]
+    async fn api_result() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = ApiResult::<_>::from(Ok::<_, ProblemDetails>(vec![1, 2])).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(to_json(res).await, serde_json::json!([1, 2]));
+
+        let res = ApiResult::<u8>(Err(Box::new(ProblemDetails::new(StatusCode::CONFLICT))))
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn snapshot_id() {
+        let req = TestRequest::default().to_http_request();
+        let snapshot = RequestSnapshot::capture(&req);
+
+        let res = ProblemDetails::new(StatusCode::BAD_GATEWAY).respond_to(&req);
+        assert_eq!(to_json(res).await["snapshot_id"], snapshot.id());
+
+        let res = ProblemDetails::new(StatusCode::BAD_GATEWAY)
+            .extension("snapshot_id", "custom")
+            .respond_to(&req);
+        assert_eq!(to_json(res).await["snapshot_id"], "custom");
+    }
+}
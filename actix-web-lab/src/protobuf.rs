@@ -0,0 +1,44 @@
+//! Protocol Buffers responder.
+
+use actix_web::{HttpRequest, HttpResponse, Responder};
+use bytes::Bytes;
+use derive_more::{Deref, DerefMut, Display};
+use mime::Mime;
+use once_cell::sync::Lazy;
+use prost::Message;
+
+static PROTOBUF_MIME: Lazy<Mime> = Lazy::new(|| "application/x-protobuf".parse().unwrap());
+
+/// Protocol Buffers responder, using [`prost`] messages.
+///
+/// Encodes the inner message, with a `Content-Type` of `application/x-protobuf`. To extract
+/// Protobuf request bodies, use the [`Protobuf`](crate::extract::Protobuf) extractor.
+///
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::respond::Protobuf;
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Status {
+///     #[prost(bool, tag = "1")]
+///     online: bool,
+/// }
+///
+/// #[get("/status")]
+/// async fn index() -> impl Responder {
+///     Protobuf(Status { online: true })
+/// }
+/// ```
+#[derive(Debug, Deref, DerefMut, Display)]
+pub struct Protobuf<T>(pub T);
+
+impl<T: Message> Responder for Protobuf<T> {
+    type Body = Bytes;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .content_type(PROTOBUF_MIME.clone())
+            .message_body(Bytes::from(self.0.encode_to_vec()))
+            .unwrap()
+    }
+}
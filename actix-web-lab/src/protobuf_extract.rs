@@ -0,0 +1,282 @@
+//! Protocol Buffers extractor.
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, FromRequest, HttpMessage as _,
+    HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use prost::Message;
+use tracing::debug;
+
+use crate::bytes::{BytesBody, BytesPayloadError};
+
+/// Default Protobuf payload size limit of 2MiB.
+pub const DEFAULT_PROTOBUF_LIMIT: usize = 2_097_152;
+
+/// Protocol Buffers extractor with const-generic payload size limit, using [`prost`] messages.
+///
+/// To extract a message from a request body, the inner type `T` must implement
+/// [`prost::Message`] and [`Default`]. The request must have a `Content-Type` of
+/// `application/x-protobuf` or `application/protobuf`.
+///
+/// Use the `LIMIT` const generic parameter to control the payload size limit. The default limit
+/// that is exported (`DEFAULT_PROTOBUF_LIMIT`) is 2MiB.
+///
+/// To respond with Protobuf, use the [`Protobuf`](crate::respond::Protobuf) responder.
+///
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::Protobuf;
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Reading {
+///     #[prost(string, tag = "1")]
+///     sensor: String,
+///     #[prost(double, tag = "2")]
+///     value: f64,
+/// }
+///
+/// #[post("/readings")]
+/// async fn index(reading: Protobuf<Reading, 4096>) -> String {
+///     format!("{}: {}", reading.sensor, reading.value)
+/// }
+/// # App::new().service(index);
+/// ```
+#[derive(Debug)]
+pub struct Protobuf<T, const LIMIT: usize = DEFAULT_PROTOBUF_LIMIT>(pub T);
+
+mod waiting_on_derive_more_to_start_using_syn_2_due_to_proc_macro_panic {
+    use super::*;
+
+    impl<T, const LIMIT: usize> std::ops::Deref for Protobuf<T, LIMIT> {
+        type Target = T;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T, const LIMIT: usize> std::ops::DerefMut for Protobuf<T, LIMIT> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl<T: fmt::Display, const LIMIT: usize> fmt::Display for Protobuf<T, LIMIT> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T, const LIMIT: usize> Protobuf<T, LIMIT> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Message + Default, const LIMIT: usize> FromRequest for Protobuf<T, LIMIT> {
+    type Error = ProtobufPayloadError;
+    type Future = ProtobufExtractFut<T, LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let can_parse_protobuf = match req.mime_type() {
+            Ok(Some(mime)) => {
+                mime.type_() == mime::APPLICATION
+                    && matches!(mime.subtype().as_str(), "x-protobuf" | "protobuf")
+            }
+            _ => false,
+        };
+
+        ProtobufExtractFut {
+            req: Some(req.clone()),
+            fut: can_parse_protobuf.then(|| BytesBody::new(req, payload)),
+            _res: PhantomData,
+        }
+    }
+}
+
+/// Future for the [`Protobuf`] extractor.
+pub struct ProtobufExtractFut<T, const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    fut: Option<BytesBody<LIMIT>>,
+    _res: PhantomData<T>,
+}
+
+impl<T, const LIMIT: usize> Unpin for ProtobufExtractFut<T, LIMIT> {}
+
+impl<T: Message + Default, const LIMIT: usize> Future for ProtobufExtractFut<T, LIMIT> {
+    type Output = Result<Protobuf<T, LIMIT>, ProtobufPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match &mut this.fut {
+            None => Err(ProtobufPayloadError::ContentType),
+            Some(fut) => ready!(Pin::new(fut).poll(cx))
+                .map_err(ProtobufPayloadError::from)
+                .and_then(|body| T::decode(body).map_err(ProtobufPayloadError::Decode)),
+        };
+
+        if res.is_err() {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to decode Protobuf<{}> from payload in handler: {}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        Poll::Ready(res.map(Protobuf))
+    }
+}
+
+/// Errors that can occur when extracting a [`Protobuf`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ProtobufPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(
+        fmt = "Protobuf payload ({length} bytes) is larger than allowed (limit: {limit} bytes)."
+    )]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "Protobuf payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not Protobuf.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload could not be decoded.
+    #[display(fmt = "Protobuf decode error: {_0}")]
+    Decode(prost::DecodeError),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<BytesPayloadError> for ProtobufPayloadError {
+    fn from(err: BytesPayloadError) -> Self {
+        match err {
+            BytesPayloadError::OverflowKnownLength { length, limit } => {
+                Self::OverflowKnownLength { length, limit }
+            }
+            BytesPayloadError::Overflow { limit } => Self::Overflow { limit },
+            BytesPayloadError::ContentType => Self::ContentType,
+            BytesPayloadError::Payload(err) => Self::Payload(err),
+        }
+    }
+}
+
+impl ResponseError for ProtobufPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Decode(_) => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest, Responder as _};
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Reading {
+        #[prost(string, tag = "1")]
+        sensor: String,
+        #[prost(uint32, tag = "2")]
+        value: u32,
+    }
+
+    fn reading() -> Reading {
+        Reading {
+            sensor: "temp".to_owned(),
+            value: 21,
+        }
+    }
+
+    #[actix_web::test]
+    async fn round_trip() {
+        let req = TestRequest::default().to_http_request();
+        let res = crate::respond::Protobuf(reading()).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-protobuf",
+        );
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+
+        for content_type in ["application/x-protobuf", "application/protobuf"] {
+            let (req, mut pl) = TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload(body.clone())
+                .to_http_parts();
+            let extracted = Protobuf::<Reading>::from_request(&req, &mut pl)
+                .await
+                .unwrap();
+            assert_eq!(extracted.into_inner(), reading());
+        }
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let body = Bytes::from(reading().encode_to_vec());
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(body.clone())
+            .to_http_parts();
+        let err = Protobuf::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .insert_header((header::CONTENT_LENGTH, body.len()))
+            .set_payload(body)
+            .to_http_parts();
+        let err = Protobuf::<Reading, 4>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .set_payload(Bytes::from_static(b"\x0a\xff"))
+            .to_http_parts();
+        let err = Protobuf::<Reading>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
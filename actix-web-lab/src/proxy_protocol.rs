@@ -0,0 +1,522 @@
+//! HAProxy PROXY protocol support.
+//!
+//! TCP load balancers which do not terminate HTTP can prepend a [PROXY protocol] header to each
+//! connection in order to convey the original client's address. This module contains a parser for
+//! both the text (v1) and binary (v2) header formats, an [`acceptor()`] service which reads the
+//! header from incoming connections, and a [`ProxiedStream`] type which carries the parsed header
+//! alongside the connection.
+//!
+//! Since the header must be read before any HTTP data, the acceptor is used in a custom
+//! `actix-server` pipeline in front of `actix-http`'s `HttpService`, in the same way as TLS
+//! acceptors. The parsed header can then be made available to handlers as connection data by
+//! calling [`ProxyHeader::plugin()`]'s `on_connect` method from `HttpService::on_connect_ext()`,
+//! after which it is available through the [`ConnectData`](crate::extract::ConnectData) extractor.
+//!
+//! Only enable PROXY protocol on listeners which are exclusively reachable through a trusted load
+//! balancer; otherwise, clients are able to spoof their address.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    str,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_service::{fn_service, ServiceFactory};
+use actix_web::rt::{
+    net::{ActixStream, Ready, TcpStream},
+    time::timeout,
+};
+use derive_more::{Display, Error, From};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, ReadBuf};
+
+use crate::util::ConnectInfoPlugin;
+
+/// Binary (v2) header signature.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Maximum length of a text (v1) header, including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Errors that can occur when reading a PROXY protocol header.
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum ProxyProtocolError {
+    /// Connection did not start with a PROXY protocol header.
+    #[display(fmt = "connection did not start with a PROXY protocol header")]
+    #[from(ignore)]
+    Missing,
+
+    /// PROXY protocol header was malformed.
+    #[display(fmt = "malformed PROXY protocol header")]
+    #[from(ignore)]
+    Invalid,
+
+    /// Header was not received in time.
+    #[display(fmt = "timed out waiting for PROXY protocol header")]
+    #[from(ignore)]
+    Timeout,
+
+    /// I/O error while reading header.
+    #[display(fmt = "I/O error while reading PROXY protocol header: {_0}")]
+    Io(io::Error),
+}
+
+/// A parsed PROXY protocol header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyHeader {
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+
+impl ProxyHeader {
+    /// Returns address of the original client.
+    ///
+    /// Returns `None` for health-check connections from the proxy itself (v1 `UNKNOWN` and v2
+    /// `LOCAL` headers) and for address families other than IPv4 and IPv6.
+    pub fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Returns address that the original client connected to.
+    pub fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+
+    /// Parses a header from the start of `buf`, returning it along with its length in bytes.
+    ///
+    /// Returns `Ok(None)` if `buf` is the start of a header but is incomplete.
+    pub fn parse(buf: &[u8]) -> Result<Option<(Self, usize)>, ProxyProtocolError> {
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        if buf[0] == b'P' {
+            parse_v1(buf)
+        } else if buf[0] == V2_SIGNATURE[0] {
+            parse_v2(buf)
+        } else {
+            Err(ProxyProtocolError::Missing)
+        }
+    }
+
+    /// Returns a connection data plugin which makes headers read by [`acceptor()`] available
+    /// through the [`ConnectData<ProxyHeader>`](crate::extract::ConnectData) extractor.
+    pub fn plugin() -> ConnectInfoPlugin<Self> {
+        ConnectInfoPlugin::new()
+            .stream(|stream: &ProxiedStream<TcpStream>| Some(stream.header().clone()))
+    }
+}
+
+fn parse_v1(buf: &[u8]) -> Result<Option<(ProxyHeader, usize)>, ProxyProtocolError> {
+    const PREFIX: &[u8] = b"PROXY ";
+
+    let prefix_len = buf.len().min(PREFIX.len());
+    if buf[..prefix_len] != PREFIX[..prefix_len] {
+        return Err(ProxyProtocolError::Missing);
+    }
+
+    let Some(end) = buf
+        .windows(2)
+        .take(V1_MAX_LEN - 1)
+        .position(|w| w == b"\r\n")
+    else {
+        return if buf.len() < V1_MAX_LEN {
+            Ok(None)
+        } else {
+            Err(ProxyProtocolError::Invalid)
+        };
+    };
+
+    let line = str::from_utf8(&buf[PREFIX.len()..end]).map_err(|_| ProxyProtocolError::Invalid)?;
+    let mut parts = line.split(' ');
+
+    let header = match parts.next() {
+        Some("UNKNOWN") => ProxyHeader {
+            source: None,
+            destination: None,
+        },
+
+        Some(proto @ ("TCP4" | "TCP6")) => {
+            let mut next_ip = || -> Result<IpAddr, ProxyProtocolError> {
+                let ip = parts.next().ok_or(ProxyProtocolError::Invalid)?;
+
+                let ip = if proto == "TCP4" {
+                    IpAddr::V4(ip.parse().map_err(|_| ProxyProtocolError::Invalid)?)
+                } else {
+                    IpAddr::V6(ip.parse().map_err(|_| ProxyProtocolError::Invalid)?)
+                };
+
+                Ok(ip)
+            };
+
+            let src_ip = next_ip()?;
+            let dst_ip = next_ip()?;
+
+            let mut next_port = || -> Result<u16, ProxyProtocolError> {
+                parts
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .ok_or(ProxyProtocolError::Invalid)
+            };
+
+            let src_port = next_port()?;
+            let dst_port = next_port()?;
+
+            if parts.next().is_some() {
+                return Err(ProxyProtocolError::Invalid);
+            }
+
+            ProxyHeader {
+                source: Some(SocketAddr::new(src_ip, src_port)),
+                destination: Some(SocketAddr::new(dst_ip, dst_port)),
+            }
+        }
+
+        _ => return Err(ProxyProtocolError::Invalid),
+    };
+
+    Ok(Some((header, end + 2)))
+}
+
+fn parse_v2(buf: &[u8]) -> Result<Option<(ProxyHeader, usize)>, ProxyProtocolError> {
+    let sig_len = buf.len().min(V2_SIGNATURE.len());
+    if buf[..sig_len] != V2_SIGNATURE[..sig_len] {
+        return Err(ProxyProtocolError::Missing);
+    }
+
+    if buf.len() < 16 {
+        return Ok(None);
+    }
+
+    let ver_cmd = buf[12];
+    let family = buf[13];
+    let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+
+    if ver_cmd >> 4 != 2 {
+        return Err(ProxyProtocolError::Invalid);
+    }
+
+    let Some(addrs) = buf.get(16..16 + len) else {
+        return Ok(None);
+    };
+
+    let (source, destination) = match (ver_cmd & 0x0F, family >> 4) {
+        // LOCAL command; addresses are ignored
+        (0x0, _) => (None, None),
+
+        // PROXY command over IPv4
+        (0x1, 0x1) => {
+            let addrs = addrs.get(..12).ok_or(ProxyProtocolError::Invalid)?;
+            let ip = |at: usize| Ipv4Addr::from(<[u8; 4]>::try_from(&addrs[at..at + 4]).unwrap());
+            let port = |at: usize| u16::from_be_bytes([addrs[at], addrs[at + 1]]);
+
+            (
+                Some(SocketAddr::new(ip(0).into(), port(8))),
+                Some(SocketAddr::new(ip(4).into(), port(10))),
+            )
+        }
+
+        // PROXY command over IPv6
+        (0x1, 0x2) => {
+            let addrs = addrs.get(..36).ok_or(ProxyProtocolError::Invalid)?;
+            let ip = |at: usize| Ipv6Addr::from(<[u8; 16]>::try_from(&addrs[at..at + 16]).unwrap());
+            let port = |at: usize| u16::from_be_bytes([addrs[at], addrs[at + 1]]);
+
+            (
+                Some(SocketAddr::new(ip(0).into(), port(32))),
+                Some(SocketAddr::new(ip(16).into(), port(34))),
+            )
+        }
+
+        // PROXY command over UNIX sockets or unspecified families
+        (0x1, _) => (None, None),
+
+        _ => return Err(ProxyProtocolError::Invalid),
+    };
+
+    Ok(Some((
+        ProxyHeader {
+            source,
+            destination,
+        },
+        16 + len,
+    )))
+}
+
+/// Reads a PROXY protocol header from the start of `io`.
+///
+/// Exactly the bytes of the header are consumed so that `io` can subsequently be used to read the
+/// proxied connection's data.
+///
+/// Returns [`ProxyProtocolError::Missing`] as soon as the data read can no longer be the start of
+/// a header.
+pub async fn read_header<IO>(io: &mut IO) -> Result<ProxyHeader, ProxyProtocolError>
+where
+    IO: AsyncRead + Unpin,
+{
+    const V1_PREFIX: &[u8] = b"PROXY ";
+
+    let mut buf = vec![0; 16];
+    io.read_exact(&mut buf[..1]).await?;
+
+    let len = if buf[0] == V1_PREFIX[0] {
+        // text headers are read one byte at a time to avoid over-reading
+        let mut len = 1;
+
+        while !buf[..len].ends_with(b"\r\n") {
+            if len == V1_MAX_LEN {
+                return Err(ProxyProtocolError::Invalid);
+            }
+
+            if len == buf.len() {
+                buf.resize(V1_MAX_LEN, 0);
+            }
+
+            io.read_exact(&mut buf[len..len + 1]).await?;
+            len += 1;
+
+            let prefix_len = len.min(V1_PREFIX.len());
+            if buf[..prefix_len] != V1_PREFIX[..prefix_len] {
+                return Err(ProxyProtocolError::Missing);
+            }
+        }
+
+        len
+    } else if buf[0] == V2_SIGNATURE[0] {
+        // signature is also read one byte at a time so that other protocols are not over-read
+        for len in 1..V2_SIGNATURE.len() {
+            io.read_exact(&mut buf[len..len + 1]).await?;
+
+            if buf[len] != V2_SIGNATURE[len] {
+                return Err(ProxyProtocolError::Missing);
+            }
+        }
+
+        io.read_exact(&mut buf[V2_SIGNATURE.len()..16]).await?;
+
+        let len = 16 + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+        buf.resize(len, 0);
+        io.read_exact(&mut buf[16..]).await?;
+
+        len
+    } else {
+        return Err(ProxyProtocolError::Missing);
+    };
+
+    match ProxyHeader::parse(&buf[..len])? {
+        Some((header, _)) => Ok(header),
+        None => Err(ProxyProtocolError::Invalid),
+    }
+}
+
+/// Returns a service factory which reads PROXY protocol headers from incoming connections.
+///
+/// Connections which do not send a valid header within `header_timeout` are rejected.
+pub fn acceptor<IO>(
+    header_timeout: Duration,
+) -> impl ServiceFactory<
+    IO,
+    Config = (),
+    Response = ProxiedStream<IO>,
+    Error = ProxyProtocolError,
+    InitError = (),
+> + Clone
+where
+    IO: AsyncRead + Unpin + 'static,
+{
+    fn_service(move |mut io: IO| async move {
+        let header = timeout(header_timeout, read_header(&mut io))
+            .await
+            .map_err(|_| ProxyProtocolError::Timeout)??;
+
+        Ok(ProxiedStream { io, header })
+    })
+}
+
+/// A connection stream paired with its PROXY protocol header.
+#[derive(Debug)]
+pub struct ProxiedStream<IO> {
+    io: IO,
+    header: ProxyHeader,
+}
+
+impl<IO> ProxiedStream<IO> {
+    /// Returns the PROXY protocol header sent at the start of the connection.
+    pub fn header(&self) -> &ProxyHeader {
+        &self.header
+    }
+
+    /// Returns reference to the underlying stream.
+    pub fn get_ref(&self) -> &IO {
+        &self.io
+    }
+
+    /// Unwraps into the underlying stream and header.
+    pub fn into_parts(self) -> (IO, ProxyHeader) {
+        (self.io, self.header)
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for ProxiedStream<IO> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for ProxiedStream<IO> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+impl<IO: ActixStream> ActixStream for ProxiedStream<IO> {
+    fn poll_read_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
+        IO::poll_read_ready(&self.io, cx)
+    }
+
+    fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
+        IO::poll_write_ready(&self.io, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::Service as _;
+
+    use super::*;
+
+    const V1_TCP4: &[u8] = b"PROXY TCP4 192.0.2.1 198.51.100.2 56324 443\r\nGET / HTTP/1.1\r\n";
+
+    fn v2_tcp4() -> Vec<u8> {
+        let mut buf = V2_SIGNATURE.to_vec();
+        buf.extend_from_slice(&[0x21, 0x11, 0x00, 0x0C]);
+        buf.extend_from_slice(&[192, 0, 2, 1, 198, 51, 100, 2]);
+        buf.extend_from_slice(&56324_u16.to_be_bytes());
+        buf.extend_from_slice(&443_u16.to_be_bytes());
+        buf.extend_from_slice(b"GET / HTTP/1.1\r\n");
+        buf
+    }
+
+    #[test]
+    fn parse_v1() {
+        let (header, len) = ProxyHeader::parse(V1_TCP4).unwrap().unwrap();
+        assert_eq!(len, 45);
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("198.51.100.2:443".parse().unwrap()),
+        );
+
+        let (header, _) = ProxyHeader::parse(b"PROXY TCP6 ::1 ::2 1 2\r\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.source(), Some("[::1]:1".parse().unwrap()));
+
+        let (header, _) = ProxyHeader::parse(b"PROXY UNKNOWN\r\n").unwrap().unwrap();
+        assert_eq!(header.source(), None);
+
+        assert!(ProxyHeader::parse(b"PROX").unwrap().is_none());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 192.0.2.1")
+            .unwrap()
+            .is_none());
+
+        assert!(matches!(
+            ProxyHeader::parse(b"PROXY TCP4 ::1 ::2 1 2\r\n"),
+            Err(ProxyProtocolError::Invalid),
+        ));
+        assert!(matches!(
+            ProxyHeader::parse(b"GET / HTTP/1.1\r\n"),
+            Err(ProxyProtocolError::Missing),
+        ));
+    }
+
+    #[test]
+    fn parse_v2() {
+        let buf = v2_tcp4();
+
+        let (header, len) = ProxyHeader::parse(&buf).unwrap().unwrap();
+        assert_eq!(len, 28);
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("198.51.100.2:443".parse().unwrap()),
+        );
+
+        assert!(ProxyHeader::parse(&buf[..20]).unwrap().is_none());
+
+        // LOCAL command
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]);
+        let (header, len) = ProxyHeader::parse(&local).unwrap().unwrap();
+        assert_eq!(len, 16);
+        assert_eq!(header.source(), None);
+    }
+
+    #[actix_web::test]
+    async fn read_header_consumes_exact_bytes() {
+        for input in [V1_TCP4.to_vec(), v2_tcp4()] {
+            let mut io = input.as_slice();
+
+            let header = read_header(&mut io).await.unwrap();
+            assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+            assert_eq!(io, b"GET / HTTP/1.1\r\n");
+        }
+    }
+
+    #[actix_web::test]
+    async fn acceptor_wraps_stream() {
+        let acceptor = acceptor::<&[u8]>(Duration::from_secs(1))
+            .new_service(())
+            .await
+            .unwrap();
+
+        let mut stream = acceptor.call(V1_TCP4).await.unwrap();
+        assert_eq!(stream.header().source().unwrap().port(), 56324);
+
+        let mut rest = String::new();
+        stream.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "GET / HTTP/1.1\r\n");
+
+        let err = acceptor.call(&b"GET / HTTP/1.1\r\n"[..]).await.unwrap_err();
+        assert!(matches!(err, ProxyProtocolError::Missing));
+
+        let err = acceptor.call(&b"\r\nGET"[..]).await.unwrap_err();
+        assert!(matches!(err, ProxyProtocolError::Missing));
+
+        let err = acceptor.call(&b"POST"[..]).await.unwrap_err();
+        assert!(matches!(err, ProxyProtocolError::Missing));
+    }
+}
@@ -0,0 +1,246 @@
+//! For nested query parameter extractor documentation, see [`QsQuery`].
+
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, ResponseError};
+use derive_more::{Display, Error};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+/// Extract typed information from the request's query, supporting nested and array parameters.
+///
+/// To extract typed data from the URL query string, the inner type `T` must implement the
+/// [`DeserializeOwned`] trait.
+///
+/// # Differences From [`Query`](crate::extract::Query)
+/// This extractor uses `serde_qs` under-the-hood which supports bracketed keys. Nested structs
+/// and maps can be decoded from parameters like `filter[status]=open` and sequences from
+/// parameters like `ids[]=1&ids[]=2` or `ids[0]=1&ids[1]=2`.
+///
+/// The maximum nesting depth and whether percent-encoded brackets are accepted are configured by
+/// registering a [`QsQueryConfig`] as app data.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::extract::QsQuery;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Filter {
+///     status: String,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct IssueParams {
+///     filter: Filter,
+///     ids: Vec<u64>,
+/// }
+///
+/// // A valid request path for this handler would be `/issues?filter[status]=open&ids[]=1&ids[]=2`.
+/// #[get("/issues")]
+/// async fn index(QsQuery(params): QsQuery<IssueParams>) -> impl Responder {
+///     format!("{} issues in {:?}", params.filter.status, params.ids)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QsQuery<T>(pub T);
+
+impl_more::impl_deref_and_mut!(<T> in QsQuery<T> => T);
+impl_more::forward_display!(<T> in QsQuery<T>);
+
+impl<T> QsQuery<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned> QsQuery<T> {
+    /// Deserialize a `T` from the query string using the default configuration.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use actix_web_lab::extract::QsQuery;
+    /// type Ranges = HashMap<String, HashMap<String, u32>>;
+    /// let filter = QsQuery::<Ranges>::from_query("range[min]=1&range[max]=9").unwrap();
+    ///
+    /// assert_eq!(filter["range"]["min"], 1);
+    /// assert_eq!(filter["range"]["max"], 9);
+    /// ```
+    pub fn from_query(query_str: &str) -> Result<Self, QsQueryError> {
+        QsQueryConfig::default().deserialize(query_str).map(Self)
+    }
+}
+
+/// See [here](#examples) for example of usage as an extractor.
+impl<T: DeserializeOwned> FromRequest for QsQuery<T> {
+    type Error = QsQueryError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let res = QsQueryConfig::from_req(req).deserialize(req.query_string());
+
+        if let Err(err) = &res {
+            debug!(
+                "Failed to deserialize QsQuery<{}> for `{}` handler: {err}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        ready(res.map(QsQuery))
+    }
+}
+
+/// Configuration for the [`QsQuery`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Extractors used
+/// without a registered config use the defaults.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::extract::QsQueryConfig;
+///
+/// // accept percent-encoded brackets, as sent by HTML forms
+/// App::new().app_data(QsQueryConfig::default().max_depth(3).strict(false))
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct QsQueryConfig {
+    max_depth: usize,
+    strict: bool,
+}
+
+const DEFAULT_CONFIG: QsQueryConfig = QsQueryConfig {
+    max_depth: 5,
+    strict: true,
+};
+
+impl QsQueryConfig {
+    /// Sets maximum depth of nested parameters.
+    ///
+    /// Brackets nested deeper than this are treated as part of the key. The default is 5.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets whether strict mode is enabled.
+    ///
+    /// In strict mode, brackets in keys must not be percent-encoded. Disabling strict mode allows
+    /// encoded brackets (e.g., `filter%5Bstatus%5D=open`) at the cost of ambiguity for keys that
+    /// genuinely contain brackets. Enabled by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, query_str: &str) -> Result<T, QsQueryError> {
+        serde_qs::Config::new(self.max_depth, self.strict)
+            .deserialize_str(query_str)
+            .map_err(QsQueryError::Deserialize)
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl Default for QsQueryConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+/// Errors that can occur when extracting a [`QsQuery`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum QsQueryError {
+    /// Query string could not be deserialized.
+    #[display(fmt = "Query deserialize error: {_0}")]
+    Deserialize(serde_qs::Error),
+}
+
+impl ResponseError for QsQueryError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Filter {
+        status: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Params {
+        filter: Filter,
+        ids: Vec<u32>,
+    }
+
+    fn params() -> Params {
+        Params {
+            filter: Filter {
+                status: "open".to_owned(),
+            },
+            ids: vec![1, 2],
+        }
+    }
+
+    #[actix_web::test]
+    async fn nested_and_arrays() {
+        let (req, mut pl) = TestRequest::default()
+            .uri("/?filter%5Bstatus%5D=open&ids%5B%5D=1&ids%5B%5D=2")
+            .to_http_parts();
+
+        // strict mode (the default) does not decode encoded brackets
+        assert!(QsQuery::<Params>::from_request(&req, &mut pl)
+            .await
+            .is_err());
+
+        let (req, mut pl) = TestRequest::default()
+            .uri("/?filter%5Bstatus%5D=open&ids%5B%5D=1&ids%5B%5D=2")
+            .app_data(QsQueryConfig::default().strict(false))
+            .to_http_parts();
+        let QsQuery(extracted) = QsQuery::<Params>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(extracted, params());
+
+        let extracted = QsQuery::<Params>::from_query("filter[status]=open&ids[0]=1&ids[1]=2")
+            .unwrap()
+            .into_inner();
+        assert_eq!(extracted, params());
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let err = QsQuery::<Params>::from_query("filter[status]=open&ids[]=one").unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        // nesting beyond max depth is not decoded into structs
+        let config = QsQueryConfig::default().max_depth(0);
+        assert!(config
+            .deserialize::<Params>("filter[status]=open&ids[]=1")
+            .is_err());
+
+        let (req, mut pl) = TestRequest::default()
+            .uri("/?filter%5Bstatus%5D=open&ids%5B%5D=1")
+            .app_data(web::Data::new(config.strict(false)))
+            .to_http_parts();
+        assert!(QsQuery::<Params>::from_request(&req, &mut pl)
+            .await
+            .is_err());
+    }
+}
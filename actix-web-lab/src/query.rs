@@ -1,11 +1,21 @@
 //! For query parameter extractor documentation, see [`Query`].
 
-use std::future::{ready, Ready};
+use std::{
+    borrow::Cow,
+    fmt,
+    future::{ready, Ready},
+    sync::Arc,
+};
 
-use actix_web::{dev::Payload, error::QueryPayloadError, Error, FromRequest, HttpRequest};
+use actix_web::{dev::Payload, error::QueryPayloadError, web, Error, FromRequest, HttpRequest};
 use serde::de::DeserializeOwned;
 use tracing::debug;
 
+use crate::{
+    lab_config::LabConfig,
+    strict::{self, UnknownFields},
+};
+
 /// Extract typed information from the request's query.
 ///
 /// To extract typed data from the URL query string, the inner type `T` must implement the
@@ -15,8 +25,15 @@ use tracing::debug;
 /// This extractor uses `serde_html_form` under-the-hood which supports multi-value items. These are
 /// sent by HTML select inputs when multiple options are chosen and can be collected into a `Vec`.
 ///
-/// This version also removes the custom error handler config; users should instead prefer to handle
-/// errors using the explicit `Result<Query<T>, E>` extractor in their handlers.
+/// Errors can be handled using the explicit `Result<Query<T>, E>` extractor in handlers or, to
+/// convert them consistently across an app or scope, by registering a [`QueryConfig`] with a
+/// custom error handler.
+///
+/// # Defaults, Aliases, and Unknown Parameters
+/// Field-level defaults and aliases are supported using serde's `#[serde(default)]` and
+/// `#[serde(alias = "...")]` attributes. Unknown parameters are ignored unless a [`QueryConfig`] or
+/// [`LabConfig`] in strict mode is registered, and keys can be matched case-insensitively using
+/// [`QueryConfig::case_insensitive_keys()`].
 ///
 /// # Panics
 /// A query string consists of unordered `key=value` pairs, therefore it cannot be decoded into any
@@ -99,25 +116,170 @@ impl<T: DeserializeOwned> FromRequest for Query<T> {
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        serde_html_form::from_str::<T>(req.query_string())
-            .map(|val| ready(Ok(Query(val))))
-            .unwrap_or_else(move |e| {
-                let err = QueryPayloadError::Deserialize(e);
+        let config = QueryConfig::from_req(req);
+        let query = config.normalize_keys(req.query_string());
 
+        config
+            .check_unknown::<T>(req, &query)
+            .and_then(|()| {
+                serde_html_form::from_str::<T>(&query).map_err(QueryPayloadError::Deserialize)
+            })
+            .map(|val| ready(Ok(Query(val))))
+            .unwrap_or_else(move |err| {
                 debug!(
                     "Failed during Query extractor deserialization. \
                      Request path: {:?}",
                     req.path()
                 );
 
-                ready(Err(err.into()))
+                let err = match &config.err_handler {
+                    Some(err_handler) => (err_handler)(err, req),
+                    None => err.into(),
+                };
+
+                ready(Err(err))
             })
     }
 }
 
+type ErrorHandler = Arc<dyn Fn(QueryPayloadError, &HttpRequest) -> Error + Send + Sync>;
+
+/// Configuration for the [`Query`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data), on an app, scope, or
+/// resource. The most specific registered config is used; extractors used without a registered
+/// config respond with a plain-text `400 Bad Request` on failure.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error, web, App, HttpResponse};
+/// use actix_web_lab::extract::QueryConfig;
+/// use serde_json::json;
+///
+/// let api_config = QueryConfig::default().error_handler(|err, _req| {
+///     let res = HttpResponse::BadRequest().json(json!({ "error": err.to_string() }));
+///     error::InternalError::from_response(err, res).into()
+/// });
+///
+/// App::new().service(web::scope("/api").app_data(api_config))
+/// # ;
+/// ```
+///
+/// Rejecting unknown parameters and matching keys case-insensitively:
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::extract::QueryConfig;
+///
+/// App::new().service(
+///     web::scope("/api").app_data(QueryConfig::default().strict().case_insensitive_keys()),
+/// )
+/// # ;
+/// ```
+#[derive(Clone, Default)]
+pub struct QueryConfig {
+    err_handler: Option<ErrorHandler>,
+    strict: bool,
+    case_insensitive_keys: bool,
+}
+
+const DEFAULT_CONFIG: QueryConfig = QueryConfig {
+    err_handler: None,
+    strict: false,
+    case_insensitive_keys: false,
+};
+
+impl QueryConfig {
+    /// Sets custom error handler, used to convert deserialization errors into responses.
+    pub fn error_handler<F>(mut self, err_handler: F) -> Self
+    where
+        F: Fn(QueryPayloadError, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(err_handler));
+        self
+    }
+
+    /// Enables strict mode, where parameters that do not match a field of the target type are
+    /// rejected with a deserialization error.
+    ///
+    /// Parameters are checked against the field names, including aliases, that the type declares
+    /// to serde. Types that are deserialized from a map, including those with flattened fields, are
+    /// not checked.
+    ///
+    /// By default, unknown parameters are ignored. Strict mode can also be enabled for all of this
+    /// crate's extractors using [`LabConfig::strict()`].
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Lowercases parameter keys before deserialization, so that they match fields regardless of
+    /// case.
+    ///
+    /// Field names and aliases of the target type should be lowercase for them to match.
+    pub fn case_insensitive_keys(mut self) -> Self {
+        self.case_insensitive_keys = true;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+
+    fn normalize_keys<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        if !self.case_insensitive_keys || !query.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Cow::Borrowed(query);
+        }
+
+        // percent-encoded bytes are unaffected by lowercasing, apart from their hex digits
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, val)) => format!("{}={val}", key.to_ascii_lowercase()),
+                None => pair.to_ascii_lowercase(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        Cow::Owned(query)
+    }
+
+    fn check_unknown<T: DeserializeOwned>(
+        &self,
+        req: &HttpRequest,
+        query: &str,
+    ) -> Result<(), QueryPayloadError> {
+        if !self.strict && !LabConfig::from_req(req).is_strict() {
+            return Ok(());
+        }
+
+        let Some(fields) = strict::struct_fields::<T>() else {
+            return Ok(());
+        };
+
+        match UnknownFields::find(strict::urlencoded_keys(query), fields) {
+            Some(unknown) => Err(QueryPayloadError::Deserialize(unknown.into_error())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for QueryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryConfig")
+            .field("err_handler", &self.err_handler.as_ref().map(|_| ".."))
+            .field("strict", &self.strict)
+            .field("case_insensitive_keys", &self.case_insensitive_keys)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use actix_web::test::TestRequest;
+    use actix_web::{
+        body, error::InternalError, http::StatusCode, test::TestRequest, HttpResponse,
+    };
     use derive_more::Display;
     use serde::Deserialize;
 
@@ -177,6 +339,111 @@ mod tests {
         assert_eq!(s.id, "test1");
     }
 
+    #[actix_web::test]
+    async fn custom_error_handler() {
+        let config = QueryConfig::default().error_handler(|err, _req| {
+            let res = HttpResponse::UnprocessableEntity().body("bad query");
+            InternalError::from_response(err, res).into()
+        });
+
+        let (req, mut pl) = TestRequest::with_uri("/name/user1/")
+            .app_data(web::Data::new(config))
+            .to_srv_request()
+            .into_parts();
+        let err = Query::<Id>::from_request(&req, &mut pl).await.unwrap_err();
+
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "bad query");
+    }
+
+    #[actix_web::test]
+    async fn defaults_and_aliases() {
+        #[derive(Debug, Deserialize)]
+        struct Params {
+            #[serde(default = "default_page")]
+            page: u32,
+
+            #[serde(alias = "q")]
+            search: Option<String>,
+        }
+
+        fn default_page() -> u32 {
+            1
+        }
+
+        let req = TestRequest::with_uri("/?q=rust").to_http_request();
+        let params = Query::<Params>::extract(&req).await.unwrap();
+        assert_eq!(params.page, 1);
+        assert_eq!(params.search.as_deref(), Some("rust"));
+    }
+
+    #[actix_web::test]
+    async fn strict_mode() {
+        #[derive(Debug, Deserialize)]
+        struct Params {
+            #[serde(alias = "identifier")]
+            id: String,
+        }
+
+        let lenient = TestRequest::with_uri("/?id=1&extra=2").to_http_request();
+        assert!(Query::<Params>::extract(&lenient).await.is_ok());
+
+        let config = QueryConfig::default().strict();
+
+        let req = TestRequest::with_uri("/?identifier=1")
+            .app_data(config.clone())
+            .to_http_request();
+        assert!(Query::<Params>::extract(&req).await.is_ok());
+
+        let req = TestRequest::with_uri("/?id=1&extra=2")
+            .app_data(config.clone())
+            .to_http_request();
+        let err = Query::<Params>::extract(&req).await.unwrap_err();
+        assert!(err.to_string().contains("unknown field `extra`"));
+
+        // maps are not checked
+        let req = TestRequest::with_uri("/?id=1&extra=2")
+            .app_data(config)
+            .to_http_request();
+        let map = Query::<std::collections::HashMap<String, String>>::extract(&req)
+            .await
+            .unwrap();
+        assert_eq!(map.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn case_insensitive_keys() {
+        let req = TestRequest::with_uri("/?ID=test").to_http_request();
+        assert!(Query::<Id>::extract(&req).await.is_err());
+
+        let config = QueryConfig::default().strict().case_insensitive_keys();
+
+        let req = TestRequest::with_uri("/?ID=Test")
+            .app_data(config.clone())
+            .to_http_request();
+        let id = Query::<Id>::extract(&req).await.unwrap();
+        assert_eq!(id.id, "Test");
+
+        let req = TestRequest::with_uri("/?Id=test&Other=1")
+            .app_data(config)
+            .to_http_request();
+        assert!(Query::<Id>::extract(&req).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn lab_config_strict_mode() {
+        let req = TestRequest::with_uri("/?id=1&idd=2&extra=3")
+            .app_data(LabConfig::new().strict())
+            .to_http_request();
+        let err = Query::<Id>::extract(&req).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Query deserialize error: unknown fields `idd` (did you mean `id`?), `extra`"
+        );
+    }
+
     #[actix_web::test]
     #[should_panic]
     async fn test_tuple_panic() {
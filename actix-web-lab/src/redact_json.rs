@@ -0,0 +1,355 @@
+//! JSON response redaction middleware.
+//!
+//! See [`RedactJson`] docs.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+};
+
+use actix_web::{
+    body::{self, BodySize, EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error,
+    http::header,
+    Error,
+};
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+use mime::Mime;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::BoxError;
+
+const DEFAULT_MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// A middleware that masks values at configured key paths in JSON response bodies.
+///
+/// Key paths are dot-separated object keys, matched from the root of the document. A `*` segment
+/// matches any key or array element. Named segments are applied to each element when they meet
+/// an array, so `users.ssn` masks the `ssn` field of every object in a `users` array.
+///
+/// | Path        | Masks                                                |
+/// |-------------|------------------------------------------------------|
+/// | `password`  | the top-level `password` field                       |
+/// | `*.ssn`     | the `ssn` field of any top-level object or element   |
+/// | `user.card` | the `card` field of the top-level `user` object      |
+///
+/// Masked values are replaced with the string `"[REDACTED]"` by default; use
+/// [`mask`](Self::mask) to change this. Fields that are not present are left alone.
+///
+/// # Limitations
+/// Only responses with a JSON content type (`application/json` or a `+json` suffix) whose body
+/// has a known size no larger than the [limit](Self::max_body_size) are redacted. Streaming
+/// responses, oversized responses, and bodies that are not valid JSON are passed through
+/// unchanged, with a warning logged for oversized and invalid bodies.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::middleware::RedactJson;
+///
+/// App::new().wrap(
+///     RedactJson::new()
+///         .redact("password")
+///         .redact("*.ssn")
+///         .mask("***"),
+/// )
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactJson {
+    paths: Arc<Vec<Vec<String>>>,
+    mask: Value,
+    max_body_size: u64,
+}
+
+impl RedactJson {
+    /// Constructs new redaction middleware with no key paths configured.
+    pub fn new() -> Self {
+        Self {
+            paths: Arc::default(),
+            mask: Value::from("[REDACTED]"),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    /// Adds a dot-separated key path whose values should be masked.
+    pub fn redact(mut self, path: impl AsRef<str>) -> Self {
+        let path = path.as_ref().split('.').map(str::to_owned).collect();
+        Arc::make_mut(&mut self.paths).push(path);
+        self
+    }
+
+    /// Sets the value that masked values are replaced with.
+    ///
+    /// Defaults to the string `"[REDACTED]"`.
+    pub fn mask(mut self, mask: impl Into<Value>) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    /// Sets maximum size of response bodies which will be redacted.
+    ///
+    /// Defaults to 1 MiB.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Masks values at configured key paths in `value`, returning true if any were masked.
+    fn apply(&self, value: &mut Value) -> bool {
+        let mut masked = false;
+
+        for path in self.paths.iter() {
+            masked |= visit_path(value, path, &mut |val| *val = self.mask.clone());
+        }
+
+        masked
+    }
+}
+
+impl Default for RedactJson {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calls `f` with each value at a key path, returning true if any were found.
+///
+/// See [`RedactJson`] for key path syntax.
+pub(crate) fn visit_path(
+    value: &mut Value,
+    path: &[String],
+    f: &mut dyn FnMut(&mut Value),
+) -> bool {
+    let Some((segment, rest)) = path.split_first() else {
+        return false;
+    };
+
+    let mut found = false;
+
+    let mut visit = |child: &mut Value| {
+        if rest.is_empty() {
+            f(child);
+            found = true;
+        } else {
+            found |= visit_path(child, rest, f);
+        }
+    };
+
+    match value {
+        Value::Object(map) if segment == "*" => map.values_mut().for_each(&mut visit),
+        Value::Object(map) => {
+            if let Some(child) = map.get_mut(segment) {
+                visit(child);
+            }
+        }
+
+        Value::Array(items) if segment == "*" => items.iter_mut().for_each(&mut visit),
+        Value::Array(items) => {
+            for item in items {
+                found |= visit_path(item, path, f);
+            }
+        }
+
+        _ => {}
+    }
+
+    found
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RedactJson
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = RedactJsonMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RedactJsonMiddleware {
+            service: Rc::new(service),
+            redact: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`RedactJson`] middleware.
+pub struct RedactJsonMiddleware<S> {
+    service: Rc<S>,
+    redact: RedactJson,
+}
+
+impl<S, B> Service<ServiceRequest> for RedactJsonMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let redact = self.redact.clone();
+
+        Box::pin(async move {
+            let res = service.call(req).await?;
+
+            let is_json = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|ct| ct.to_str().ok())
+                .and_then(|ct| ct.parse::<Mime>().ok())
+                .is_some_and(|mime| {
+                    (mime.type_() == mime::APPLICATION && mime.subtype() == mime::JSON)
+                        || mime.suffix() == Some(mime::JSON)
+                });
+
+            if !is_json || redact.paths.is_empty() {
+                return Ok(res.map_into_left_body());
+            }
+
+            match res.response().body().size() {
+                BodySize::Sized(size) if size <= redact.max_body_size => {}
+
+                BodySize::Sized(size) => {
+                    warn!(
+                        "JSON response body ({size} bytes) exceeds redaction limit of {} bytes; \
+                         passing through unredacted",
+                        redact.max_body_size
+                    );
+                    return Ok(res.map_into_left_body());
+                }
+
+                _ => return Ok(res.map_into_left_body()),
+            }
+
+            let (req, res) = res.into_parts();
+            let (mut res, body) = res.into_parts();
+
+            let body = body::to_bytes(body).await.map_err(|err| {
+                let err: BoxError = err.into();
+                error::ErrorInternalServerError(err.to_string())
+            })?;
+
+            let body = match serde_json::from_slice::<Value>(&body) {
+                Ok(mut value) => {
+                    if redact.apply(&mut value) {
+                        debug!("redacted JSON response body for {}", req.path());
+
+                        res.headers_mut().remove(header::CONTENT_LENGTH);
+                        Bytes::from(serde_json::to_vec(&value)?)
+                    } else {
+                        body
+                    }
+                }
+
+                Err(err) => {
+                    warn!("JSON response body could not be parsed for redaction: {err}");
+                    body
+                }
+            };
+
+            let res = res.set_body(body).map_into_right_body();
+            Ok(ServiceResponse::new(req, res))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::to_bytes,
+        http::header::ContentType,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "password": "hunter2",
+            "user": { "name": "ana", "ssn": "123" },
+            "users": [{ "name": "bo", "ssn": "456" }, { "name": "cy" }],
+        })
+    }
+
+    async fn json() -> HttpResponse {
+        HttpResponse::Ok().json(document())
+    }
+
+    async fn text() -> HttpResponse {
+        HttpResponse::Ok()
+            .insert_header(ContentType::plaintext())
+            .body(document().to_string())
+    }
+
+    #[test]
+    fn redacts_paths() {
+        let redact = RedactJson::new()
+            .redact("password")
+            .redact("*.ssn")
+            .redact("users.ssn")
+            .redact("missing.key");
+
+        let mut value = document();
+        assert!(redact.apply(&mut value));
+        assert_eq!(
+            value,
+            json!({
+                "password": "[REDACTED]",
+                "user": { "name": "ana", "ssn": "[REDACTED]" },
+                "users": [{ "name": "bo", "ssn": "[REDACTED]" }, { "name": "cy" }],
+            }),
+        );
+
+        let mut value = json!({ "name": "ana" });
+        assert!(!redact.apply(&mut value));
+    }
+
+    #[actix_web::test]
+    async fn redacts_json_responses() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RedactJson::new().redact("password").mask(0))
+                .route("/json", web::get().to(json))
+                .route("/text", web::get().to(text)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let res = test::call_service(&app, req).await;
+        let body = to_bytes(res.into_body()).await.unwrap();
+        let value = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(value["password"], 0);
+        assert_eq!(value["user"]["ssn"], "123");
+
+        let req = TestRequest::with_uri("/text").to_request();
+        let res = test::call_service(&app, req).await;
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, document().to_string());
+    }
+
+    #[actix_web::test]
+    async fn skips_oversized_bodies() {
+        let redact = RedactJson::new().redact("password").max_body_size(8);
+        let app =
+            test::init_service(App::new().wrap(redact).route("/json", web::get().to(json))).await;
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let res = test::call_service(&app, req).await;
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, document().to_string());
+    }
+}
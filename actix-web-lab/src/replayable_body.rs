@@ -0,0 +1,434 @@
+//! Replayable request body extractor.
+//!
+//! See [`ReplayableBody`] docs.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use actix_web::{
+    body::{BodySize, MessageBody},
+    dev,
+    error::PayloadError,
+    http::StatusCode,
+    rt::task::spawn_blocking,
+    web::{self, Bytes, BytesMut},
+    FromRequest, HttpRequest, ResponseError,
+};
+use derive_more::{Display, Error};
+use futures_core::{future::LocalBoxFuture, Stream};
+use futures_util::{stream, StreamExt as _};
+use tempfile::NamedTempFile;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+};
+use tracing::debug;
+
+use crate::body_spill::READ_CHUNK_SIZE;
+
+/// Default limit on the size of replayable bodies buffered in memory, 256KiB.
+pub const DEFAULT_REPLAYABLE_MEMORY_LIMIT: usize = 262_144;
+
+/// Default limit on the total size of replayable bodies, 16MiB.
+pub const DEFAULT_REPLAYABLE_LIMIT: usize = 16_777_216;
+
+/// A request body that can be streamed any number of times.
+///
+/// Useful for handlers and proxies which need to retry an upstream call with the same body after
+/// a connection-level failure, since a request's payload can otherwise only be read once.
+///
+/// # Extractor
+/// Reads the whole request body, buffering it in memory up to the [memory
+/// limit](ReplayableBodyConfig::memory_limit) and spilling it to a temporary file beyond that.
+/// Extraction fails with a [`ReplayableBodyError`] if the body is larger than the [total
+/// limit](ReplayableBodyConfig::limit) or cannot be read or spilled.
+///
+/// Limits are configured by registering a [`ReplayableBodyConfig`] as app data. The defaults are
+/// 256KiB in memory and 16MiB in total.
+///
+/// Cloning a `ReplayableBody` is cheap and shares its buffer. Temporary files are deleted when
+/// the last clone, and the last stream reading from it, are dropped.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, Responder};
+/// use actix_web_lab::extract::ReplayableBody;
+///
+/// # async fn send_upstream(body: actix_web_lab::body::ReplayStream) -> std::io::Result<()> {
+/// #     Ok(())
+/// # }
+/// #[post("/forward")]
+/// async fn forward(body: ReplayableBody) -> impl Responder {
+///     for _attempt in 0..3 {
+///         if send_upstream(body.clone_stream()).await.is_ok() {
+///             return "forwarded";
+///         }
+///     }
+///
+///     "upstream unavailable"
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayableBody {
+    buf: Buffer,
+    len: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Buffer {
+    Memory(Bytes),
+    File(Arc<NamedTempFile>),
+}
+
+impl ReplayableBody {
+    /// Returns length of the body, in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the body is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the buffered body if it was not spilled to disk.
+    pub fn as_bytes(&self) -> Option<&Bytes> {
+        match &self.buf {
+            Buffer::Memory(bytes) => Some(bytes),
+            Buffer::File(_) => None,
+        }
+    }
+
+    /// Returns a new stream of the whole body.
+    ///
+    /// Streams are independent of each other; each one starts from the beginning of the body.
+    pub fn clone_stream(&self) -> ReplayStream {
+        let chunks = match &self.buf {
+            Buffer::Memory(bytes) => {
+                let bytes = bytes.clone();
+                stream::iter((!bytes.is_empty()).then_some(Ok(bytes))).boxed()
+            }
+
+            Buffer::File(file) => {
+                stream::unfold(ReadState::Open(Arc::clone(file)), read_chunk).boxed()
+            }
+        };
+
+        ReplayStream {
+            chunks,
+            len: self.len,
+        }
+    }
+
+    async fn collect(
+        mut payload: dev::Payload,
+        config: ReplayableBodyConfig,
+    ) -> Result<Self, ReplayableBodyError> {
+        let mut buf = BytesMut::new();
+        let mut spilled = None::<(NamedTempFile, File)>;
+        let mut len = 0;
+
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk?;
+            len += chunk.len();
+
+            if len > config.limit {
+                return Err(ReplayableBodyError::Overflow {
+                    limit: config.limit,
+                });
+            }
+
+            match spilled.as_mut() {
+                Some((_, file)) => file.write_all(&chunk).await?,
+
+                None if len > config.memory_limit => {
+                    let (named, mut file) = spill_file().await?;
+                    file.write_all(&buf).await?;
+                    file.write_all(&chunk).await?;
+
+                    buf = BytesMut::new();
+                    spilled = Some((named, file));
+                }
+
+                None => buf.extend_from_slice(&chunk),
+            }
+        }
+
+        let buf = match spilled {
+            None => Buffer::Memory(buf.freeze()),
+
+            Some((named, mut file)) => {
+                file.flush().await?;
+                Buffer::File(Arc::new(named))
+            }
+        };
+
+        Ok(Self {
+            buf,
+            len: len as u64,
+        })
+    }
+}
+
+impl FromRequest for ReplayableBody {
+    type Error = ReplayableBodyError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let config = ReplayableBodyConfig::from_req(req).clone();
+        let payload = payload.take();
+        let req = req.clone();
+
+        Box::pin(async move {
+            Self::collect(payload, config).await.map_err(|err| {
+                debug!(
+                    "Failed to extract `ReplayableBody` for `{}` handler: {err}",
+                    req.match_name().unwrap_or_else(|| req.path())
+                );
+
+                err
+            })
+        })
+    }
+}
+
+/// Creates a named temporary file, returning it along with a handle for writing to it.
+async fn spill_file() -> io::Result<(NamedTempFile, File)> {
+    let (named, file) = spawn_blocking(|| {
+        let named = NamedTempFile::new()?;
+        let file = named.reopen()?;
+        Ok::<_, io::Error>((named, file))
+    })
+    .await
+    .map_err(|_| io::Error::new(io::ErrorKind::Other, "temporary file creation was canceled"))??;
+
+    Ok((named, File::from_std(file)))
+}
+
+enum ReadState {
+    Open(Arc<NamedTempFile>),
+    Reading(Arc<NamedTempFile>, File),
+    Done,
+}
+
+async fn read_chunk(state: ReadState) -> Option<(io::Result<Bytes>, ReadState)> {
+    let (named, mut file) = match state {
+        ReadState::Open(named) => {
+            // reopening gives each stream its own read position
+            let path = Arc::clone(&named);
+            let file = spawn_blocking(move || path.reopen()).await.map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    "temporary file reopening was canceled",
+                )
+            });
+
+            match file {
+                Ok(Ok(file)) => (named, File::from_std(file)),
+                Ok(Err(err)) | Err(err) => return Some((Err(err), ReadState::Done)),
+            }
+        }
+
+        ReadState::Reading(named, file) => (named, file),
+        ReadState::Done => return None,
+    };
+
+    let mut buf = BytesMut::with_capacity(READ_CHUNK_SIZE);
+
+    match file.read_buf(&mut buf).await {
+        Ok(0) => None,
+        Ok(_) => Some((Ok(buf.freeze()), ReadState::Reading(named, file))),
+        Err(err) => Some((Err(err), ReadState::Done)),
+    }
+}
+
+/// A stream of a [`ReplayableBody`], returned from [`ReplayableBody::clone_stream()`].
+///
+/// Can be used as a [`Stream`] of bytes, e.g., as the body of an upstream request, or directly as
+/// a sized [`MessageBody`].
+pub struct ReplayStream {
+    chunks: stream::BoxStream<'static, io::Result<Bytes>>,
+    len: u64,
+}
+
+impl std::fmt::Debug for ReplayStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayStream")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for ReplayStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.chunks.poll_next_unpin(cx)
+    }
+}
+
+impl MessageBody for ReplayStream {
+    type Error = io::Error;
+
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.len)
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Stream::poll_next(self, cx)
+    }
+}
+
+/// Configuration for the [`ReplayableBody`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone)]
+pub struct ReplayableBodyConfig {
+    memory_limit: usize,
+    limit: usize,
+}
+
+const DEFAULT_CONFIG: ReplayableBodyConfig = ReplayableBodyConfig {
+    memory_limit: DEFAULT_REPLAYABLE_MEMORY_LIMIT,
+    limit: DEFAULT_REPLAYABLE_LIMIT,
+};
+
+impl ReplayableBodyConfig {
+    /// Sets maximum number of bytes buffered in memory before the body is spilled to disk.
+    ///
+    /// Defaults to 256KiB.
+    pub fn memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Sets maximum size of bodies, in bytes.
+    ///
+    /// Defaults to 16MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl Default for ReplayableBodyConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+/// Errors that can occur when extracting a [`ReplayableBody`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ReplayableBodyError {
+    /// Payload size is bigger than allowed.
+    #[display(fmt = "Payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured limit, in bytes.
+        limit: usize,
+    },
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+
+    /// Temporary file could not be created or written to.
+    #[display(fmt = "Failed to spill payload to disk: {_0}")]
+    Io(io::Error),
+}
+
+impl From<PayloadError> for ReplayableBodyError {
+    fn from(err: PayloadError) -> Self {
+        Self::Payload(err)
+    }
+}
+
+impl From<io::Error> for ReplayableBodyError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl ResponseError for ReplayableBodyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Payload(err) => err.status_code(),
+            Self::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest};
+
+    use super::*;
+
+    async fn extract(config: ReplayableBodyConfig, body: &'static str) -> ReplayableBody {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(config)
+            .set_payload(body)
+            .to_http_parts();
+
+        ReplayableBody::from_request(&req, &mut pl).await.unwrap()
+    }
+
+    #[actix_web::test]
+    async fn replays_from_memory() {
+        let body = extract(ReplayableBodyConfig::default(), "foobar").await;
+        assert_eq!(body.len(), 6);
+        assert_eq!(body.as_bytes().unwrap(), "foobar");
+
+        for _ in 0..2 {
+            let stream = body.clone_stream();
+            assert_eq!(stream.size(), BodySize::Sized(6));
+            assert_eq!(body::to_bytes(stream).await.unwrap(), "foobar");
+        }
+
+        let body = extract(ReplayableBodyConfig::default(), "").await;
+        assert!(body.is_empty());
+        assert_eq!(body.clone_stream().count().await, 0);
+    }
+
+    #[actix_web::test]
+    async fn replays_spilled_body() {
+        let config = ReplayableBodyConfig::default().memory_limit(4);
+        let body = extract(config, "foobarbaz").await;
+        assert_eq!(body.len(), 9);
+        assert!(body.as_bytes().is_none());
+
+        let first = body.clone_stream();
+        let second = body.clone().clone_stream();
+        drop(body);
+
+        assert_eq!(body::to_bytes(first).await.unwrap(), "foobarbaz");
+        assert_eq!(body::to_bytes(second).await.unwrap(), "foobarbaz");
+    }
+
+    #[actix_web::test]
+    async fn limit() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::Data::new(ReplayableBodyConfig::default().limit(4)))
+            .set_payload("foobar")
+            .to_http_parts();
+
+        let err = ReplayableBody::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
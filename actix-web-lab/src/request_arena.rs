@@ -0,0 +1,399 @@
+//! Per-request bump allocation middleware and extractor.
+//!
+//! See [`RequestArenaMiddleware`] and [`RequestArena`] for docs.
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use bumpalo::{collections, Bump};
+use futures_core::future::LocalBoxFuture;
+use tracing::{debug, trace};
+
+use crate::error::LabError;
+
+/// Default initial capacity of each arena.
+const DEFAULT_CAPACITY: usize = 4 * 1024; // 4KiB
+
+/// Default number of idle arenas kept for reuse by each worker.
+const DEFAULT_POOL_SIZE: usize = 64;
+
+/// A middleware that provides a [`RequestArena`] to each request.
+///
+/// Arenas are taken from a per-worker pool and, once the response has been produced, are reset
+/// and returned to the pool. Since resetting an arena keeps its largest memory chunk, requests
+/// handled by a warm worker typically make no calls to the global allocator for data placed in the
+/// arena.
+///
+/// Aggregate allocation statistics are available from the [`ArenaStats`] handle returned by
+/// [`stats()`](Self::stats) and are also logged for each request at trace level.
+///
+/// This is an experiment: arena memory is only reclaimed when a request completes, so it is not
+/// suitable for large or long-lived allocations, such as those made while streaming a response.
+///
+/// # Examples
+/// ```
+/// use std::fmt::Write as _;
+///
+/// use actix_web::{get, App, HttpResponse, Responder};
+/// use actix_web_lab::{extract::RequestArena, middleware::RequestArenaMiddleware};
+///
+/// #[get("/")]
+/// async fn index(arena: RequestArena) -> impl Responder {
+///     let mut csv = arena.string();
+///
+///     for n in 0..10 {
+///         let _ = writeln!(csv, "{n},{}", n * n);
+///     }
+///
+///     HttpResponse::Ok().body(csv.as_str().to_owned())
+/// }
+///
+/// let arena = RequestArenaMiddleware::new().capacity(16 * 1024);
+/// let stats = arena.stats();
+///
+/// App::new().wrap(arena).service(index)
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestArenaMiddleware {
+    capacity: usize,
+    pool_size: usize,
+    stats: ArenaStats,
+}
+
+impl RequestArenaMiddleware {
+    /// Constructs new request arena middleware with default settings.
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            pool_size: DEFAULT_POOL_SIZE,
+            stats: ArenaStats::default(),
+        }
+    }
+
+    /// Sets initial capacity, in bytes, of newly created arenas.
+    ///
+    /// The default is 4KiB.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets maximum number of idle arenas kept for reuse by each worker.
+    ///
+    /// The default is 64. Setting this to 0 disables reuse.
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = pool_size;
+        self
+    }
+
+    /// Returns a handle to the allocation statistics recorded by this middleware.
+    pub fn stats(&self) -> ArenaStats {
+        self.stats.clone()
+    }
+}
+
+impl Default for RequestArenaMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestArenaMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestArenaService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestArenaService {
+            service,
+            config: self.clone(),
+            pool: Rc::new(RefCell::new(Vec::new())),
+        }))
+    }
+}
+
+/// Service for the [`RequestArenaMiddleware`].
+pub struct RequestArenaService<S> {
+    service: S,
+    config: RequestArenaMiddleware,
+    pool: Rc<RefCell<Vec<Bump>>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestArenaService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let pooled = self.pool.borrow_mut().pop();
+        let reused = pooled.is_some();
+        let bump = pooled.unwrap_or_else(|| Bump::with_capacity(self.config.capacity));
+
+        req.extensions_mut().insert(RequestArena {
+            inner: Rc::new(Inner {
+                bump,
+                allocations: Cell::new(0),
+            }),
+        });
+
+        let fut = self.service.call(req);
+        let pool = Rc::clone(&self.pool);
+        let pool_size = self.config.pool_size;
+        let stats = self.config.stats.clone();
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let arena = res.request().extensions_mut().remove::<RequestArena>();
+
+            if let Some(arena) = arena {
+                let allocations = arena.allocations();
+                let bytes = arena.allocated_bytes();
+
+                stats.record(allocations, bytes, reused);
+                trace!(allocations, bytes, reused, "request arena released");
+
+                // the arena can only be reused if no clones of it are held elsewhere
+                if let Ok(mut inner) = Rc::try_unwrap(arena.inner) {
+                    let mut pool = pool.borrow_mut();
+
+                    if pool.len() < pool_size {
+                        inner.bump.reset();
+                        pool.push(inner.bump);
+                    }
+                }
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// Aggregate allocation statistics recorded by a [`RequestArenaMiddleware`].
+///
+/// Cheap to clone; all clones share the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct ArenaStats {
+    inner: Rc<StatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct StatsInner {
+    requests: Cell<u64>,
+    allocations: Cell<u64>,
+    allocated_bytes: Cell<u64>,
+    reused: Cell<u64>,
+}
+
+impl ArenaStats {
+    fn record(&self, allocations: usize, bytes: usize, reused: bool) {
+        let inner = &self.inner;
+
+        inner.requests.set(inner.requests.get() + 1);
+        inner
+            .allocations
+            .set(inner.allocations.get() + allocations as u64);
+        inner
+            .allocated_bytes
+            .set(inner.allocated_bytes.get() + bytes as u64);
+
+        if reused {
+            inner.reused.set(inner.reused.get() + 1);
+        }
+    }
+
+    /// Returns number of completed requests.
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.get()
+    }
+
+    /// Returns number of allocations made in arenas using [`RequestArena`] methods.
+    ///
+    /// Each of these would otherwise have been a call to the global allocator.
+    pub fn allocations(&self) -> u64 {
+        self.inner.allocations.get()
+    }
+
+    /// Returns total memory, in bytes, reserved by arenas at the end of each request.
+    pub fn allocated_bytes(&self) -> u64 {
+        self.inner.allocated_bytes.get()
+    }
+
+    /// Returns number of requests that were given a reused arena from the pool.
+    pub fn reused_arenas(&self) -> u64 {
+        self.inner.reused.get()
+    }
+}
+
+struct Inner {
+    bump: Bump,
+    allocations: Cell<usize>,
+}
+
+/// Request-scoped bump allocator provided by the [`RequestArenaMiddleware`].
+///
+/// Useful for short-lived buffers which are built and discarded while handling a request, such as
+/// when assembling strings or header values. Memory allocated in the arena is only released once
+/// the request completes and, like all bump allocators, destructors of values allocated with
+/// [`alloc`](Self::alloc) are not run.
+///
+/// Extracting `RequestArena` without the middleware results in a
+/// [`LabError::MiddlewareNotRegistered`] error.
+///
+/// See [`RequestArenaMiddleware`] docs for an example.
+#[derive(Clone)]
+pub struct RequestArena {
+    inner: Rc<Inner>,
+}
+
+impl RequestArena {
+    fn count(&self) {
+        self.inner.allocations.set(self.inner.allocations.get() + 1);
+    }
+
+    /// Allocates `val` in the arena, returning a mutable reference to it.
+    pub fn alloc<T>(&self, val: T) -> &mut T {
+        self.count();
+        self.inner.bump.alloc(val)
+    }
+
+    /// Copies `src` into the arena.
+    pub fn alloc_str(&self, src: &str) -> &str {
+        self.count();
+        self.inner.bump.alloc_str(src)
+    }
+
+    /// Copies `src` into the arena.
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &[T] {
+        self.count();
+        self.inner.bump.alloc_slice_copy(src)
+    }
+
+    /// Creates an empty, growable string backed by the arena.
+    pub fn string(&self) -> collections::String<'_> {
+        self.count();
+        collections::String::new_in(&self.inner.bump)
+    }
+
+    /// Creates an empty, growable vector backed by the arena.
+    pub fn vec<T>(&self) -> collections::Vec<'_, T> {
+        self.count();
+        collections::Vec::new_in(&self.inner.bump)
+    }
+
+    /// Returns number of allocations made using this arena's methods so far.
+    ///
+    /// Allocations made directly through [`bump()`](Self::bump) are not counted.
+    pub fn allocations(&self) -> usize {
+        self.inner.allocations.get()
+    }
+
+    /// Returns memory, in bytes, currently reserved by the arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.inner.bump.allocated_bytes()
+    }
+
+    /// Returns the underlying bump allocator.
+    pub fn bump(&self) -> &Bump {
+        &self.inner.bump
+    }
+}
+
+impl fmt::Debug for RequestArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestArena")
+            .field("allocations", &self.allocations())
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+impl FromRequest for RequestArena {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `RequestArena` for `{}` handler. For the RequestArena \
+                extractor to work correctly, wrap the app or scope with the \
+                `RequestArenaMiddleware`.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "RequestArenaMiddleware",
+            }
+            .into()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    use super::*;
+
+    async fn handler(arena: RequestArena) -> HttpResponse {
+        let mut body = arena.string();
+        let _ = write!(body, "{}-{}", arena.alloc_str("hello"), arena.alloc(42));
+        HttpResponse::Ok().body(body.as_str().to_owned())
+    }
+
+    #[actix_web::test]
+    async fn reuses_arenas() {
+        let mw = RequestArenaMiddleware::new().capacity(1024);
+        let stats = mw.stats();
+
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::scope("/arena")
+                        .wrap(mw)
+                        .default_service(web::to(handler)),
+                )
+                .default_service(web::to(handler)),
+        )
+        .await;
+
+        for _ in 0..3 {
+            let req = test::TestRequest::with_uri("/arena").to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(test::read_body(res).await, "hello-42");
+        }
+
+        assert_eq!(stats.requests(), 3);
+        assert_eq!(stats.allocations(), 9);
+        assert_eq!(stats.reused_arenas(), 2);
+        assert!(stats.allocated_bytes() > 0);
+
+        let req = test::TestRequest::with_uri("/no-arena").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
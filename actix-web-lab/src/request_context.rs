@@ -0,0 +1,371 @@
+//! Aggregated request context middleware and extractor.
+//!
+//! See [`RequestContextMiddleware`] and [`RequestContext`] for docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    net::IpAddr,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use tracing::debug;
+
+use crate::{error::LabError, request_id::RequestId};
+
+type ResolveFn<T> = Rc<dyn Fn(&ServiceRequest) -> Option<T>>;
+
+/// Header used to resolve request IDs by default.
+const REQUEST_ID: &str = "x-request-id";
+
+/// A middleware that resolves a [`RequestContext`] once per request.
+///
+/// Each part of the context is produced by a resolver function which runs before inner services.
+/// By default:
+/// - the request ID is the one assigned by the [`request_id()`](crate::middleware::request_id)
+///   middleware, if registered outside of this one, otherwise it is read from the `X-Request-Id`
+///   header;
+/// - the client IP is the connection's peer address;
+/// - the locale is the first language range of the `Accept-Language` header;
+/// - the identity and tenant are not resolved; and
+/// - there is no deadline.
+///
+/// Resolvers can read request extensions, so identity and tenant resolvers can use data inserted
+/// by authentication middleware registered outside of (i.e., after) this one.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{get, App, Responder};
+/// use actix_web_lab::{extract::RequestContext, middleware::RequestContextMiddleware};
+///
+/// #[get("/")]
+/// async fn index(ctx: RequestContext) -> impl Responder {
+///     format!(
+///         "tenant {:?} in locale {:?}; {:?} left",
+///         ctx.tenant(),
+///         ctx.locale(),
+///         ctx.remaining(),
+///     )
+/// }
+///
+/// App::new()
+///     .wrap(
+///         RequestContextMiddleware::new()
+///             .tenant(|req| {
+///                 let host = req.connection_info().host().to_owned();
+///                 host.split_once('.').map(|(tenant, _)| tenant.to_owned())
+///             })
+///             .timeout(Duration::from_secs(5)),
+///     )
+///     .service(index)
+/// # ;
+/// ```
+#[derive(Clone)]
+pub struct RequestContextMiddleware {
+    request_id: ResolveFn<String>,
+    client_ip: ResolveFn<IpAddr>,
+    identity: ResolveFn<String>,
+    tenant: ResolveFn<String>,
+    locale: ResolveFn<String>,
+    timeout: Option<Duration>,
+}
+
+impl RequestContextMiddleware {
+    /// Constructs new request context middleware with default resolvers.
+    pub fn new() -> Self {
+        Self {
+            request_id: Rc::new(|req| {
+                if let Some(id) = req.extensions().get::<RequestId>() {
+                    return Some(id.as_str().to_owned());
+                }
+
+                req.headers()
+                    .get(REQUEST_ID)
+                    .and_then(|val| val.to_str().ok())
+                    .map(str::to_owned)
+            }),
+            client_ip: Rc::new(|req| req.peer_addr().map(|addr| addr.ip())),
+            identity: Rc::new(|_| None),
+            tenant: Rc::new(|_| None),
+            locale: Rc::new(|req| {
+                req.headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|val| val.to_str().ok())
+                    .and_then(|val| val.split(',').next())
+                    .and_then(|range| range.split(';').next())
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty() && *tag != "*")
+                    .map(str::to_owned)
+            }),
+            timeout: None,
+        }
+    }
+
+    /// Sets function which resolves the request ID.
+    pub fn request_id(
+        mut self,
+        resolve: impl Fn(&ServiceRequest) -> Option<String> + 'static,
+    ) -> Self {
+        self.request_id = Rc::new(resolve);
+        self
+    }
+
+    /// Sets function which resolves the client IP address.
+    ///
+    /// The default uses the peer address, which is the address of the last proxy when deployed
+    /// behind one.
+    pub fn client_ip(
+        mut self,
+        resolve: impl Fn(&ServiceRequest) -> Option<IpAddr> + 'static,
+    ) -> Self {
+        self.client_ip = Rc::new(resolve);
+        self
+    }
+
+    /// Sets function which resolves the authenticated identity, e.g. a user ID.
+    pub fn identity(
+        mut self,
+        resolve: impl Fn(&ServiceRequest) -> Option<String> + 'static,
+    ) -> Self {
+        self.identity = Rc::new(resolve);
+        self
+    }
+
+    /// Sets function which resolves the tenant.
+    pub fn tenant(mut self, resolve: impl Fn(&ServiceRequest) -> Option<String> + 'static) -> Self {
+        self.tenant = Rc::new(resolve);
+        self
+    }
+
+    /// Sets function which resolves the locale.
+    pub fn locale(mut self, resolve: impl Fn(&ServiceRequest) -> Option<String> + 'static) -> Self {
+        self.locale = Rc::new(resolve);
+        self
+    }
+
+    /// Sets the time budget of each request, from which its deadline is computed.
+    ///
+    /// The deadline is informational; handlers are expected to check it using
+    /// [`RequestContext::remaining()`] before starting expensive work.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn resolve(&self, req: &ServiceRequest) -> RequestContext {
+        RequestContext {
+            inner: Rc::new(Inner {
+                request_id: (self.request_id)(req),
+                client_ip: (self.client_ip)(req),
+                identity: (self.identity)(req),
+                tenant: (self.tenant)(req),
+                locale: (self.locale)(req),
+                deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            }),
+        }
+    }
+}
+
+impl Default for RequestContextMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RequestContextMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestContextMiddleware")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestContextMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestContextService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestContextService {
+            service,
+            config: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`RequestContextMiddleware`].
+pub struct RequestContextService<S> {
+    service: S,
+    config: RequestContextMiddleware,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestContextService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let ctx = self.config.resolve(&req);
+        req.extensions_mut().insert(ctx);
+
+        self.service.call(req)
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    request_id: Option<String>,
+    client_ip: Option<IpAddr>,
+    identity: Option<String>,
+    tenant: Option<String>,
+    locale: Option<String>,
+    deadline: Option<Instant>,
+}
+
+/// Request-scoped context resolved by the [`RequestContextMiddleware`].
+///
+/// Groups commonly needed request metadata behind a single extractor. Cheap to clone.
+///
+/// Extracting `RequestContext` without the middleware results in a
+/// [`LabError::MiddlewareNotRegistered`] error.
+///
+/// See [`RequestContextMiddleware`] docs for an example.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    inner: Rc<Inner>,
+}
+
+impl RequestContext {
+    /// Returns request ID, if resolved.
+    pub fn request_id(&self) -> Option<&str> {
+        self.inner.request_id.as_deref()
+    }
+
+    /// Returns client IP address, if resolved.
+    pub fn client_ip(&self) -> Option<IpAddr> {
+        self.inner.client_ip
+    }
+
+    /// Returns authenticated identity, if resolved.
+    pub fn identity(&self) -> Option<&str> {
+        self.inner.identity.as_deref()
+    }
+
+    /// Returns tenant, if resolved.
+    pub fn tenant(&self) -> Option<&str> {
+        self.inner.tenant.as_deref()
+    }
+
+    /// Returns locale, if resolved.
+    pub fn locale(&self) -> Option<&str> {
+        self.inner.locale.as_deref()
+    }
+
+    /// Returns deadline by which the request should be completed, if a timeout is configured.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.inner.deadline
+    }
+
+    /// Returns time remaining until the deadline, if a timeout is configured.
+    ///
+    /// Returns `Some(Duration::ZERO)` once the deadline has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.inner
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+impl FromRequest for RequestContext {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `RequestContext` for `{}` handler. For the RequestContext \
+                extractor to work correctly, wrap the app or scope with the \
+                `RequestContextMiddleware`.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "RequestContextMiddleware",
+            }
+            .into()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App, HttpResponse};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn resolves_defaults() {
+        let req = test::TestRequest::default()
+            .insert_header((REQUEST_ID, "abc"))
+            .insert_header((header::ACCEPT_LANGUAGE, "fr-CH, fr;q=0.9, en;q=0.8"))
+            .peer_addr("203.0.113.7:1234".parse().unwrap())
+            .to_srv_request();
+
+        let ctx = RequestContextMiddleware::new().resolve(&req);
+
+        assert_eq!(ctx.request_id(), Some("abc"));
+        assert_eq!(ctx.client_ip(), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(ctx.locale(), Some("fr-CH"));
+        assert_eq!(ctx.identity(), None);
+        assert_eq!(ctx.tenant(), None);
+        assert_eq!(ctx.deadline(), None);
+    }
+
+    #[actix_web::test]
+    async fn extractor() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    RequestContextMiddleware::new()
+                        .tenant(|_| Some("acme".to_owned()))
+                        .timeout(Duration::from_secs(60)),
+                )
+                .default_service(web::to(|ctx: RequestContext| async move {
+                    assert!(ctx.remaining().unwrap() > Duration::from_secs(30));
+                    HttpResponse::Ok().body(ctx.tenant().unwrap().to_owned())
+                })),
+        )
+        .await;
+
+        let req = test::TestRequest::default().to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "acme");
+
+        let (req, mut pl) = test::TestRequest::default().to_http_parts();
+        let err = RequestContext::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_error::<LabError>().unwrap().code(),
+            "middleware_not_registered",
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Request ID middleware and extractor.
+//!
+//! See [`request_id()`] and [`RequestId`] for docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use futures_core::future::LocalBoxFuture;
+use tracing::debug;
+
+use crate::{
+    entropy::{Entropy, SystemEntropy},
+    error::LabError,
+};
+
+/// Default header used to propagate request IDs.
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Maximum length of incoming request IDs which are propagated.
+const MAX_INCOMING_LEN: usize = 200;
+
+/// Constructs a middleware that assigns each request an ID.
+///
+/// See [`RequestIdMiddleware`] for configuration options.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App, Responder};
+/// use actix_web_lab::{extract::RequestId, middleware::request_id};
+///
+/// #[get("/")]
+/// async fn index(id: RequestId) -> impl Responder {
+///     tracing::info!(request_id = %id, "handling request");
+///     "Hello!"
+/// }
+///
+/// App::new().wrap(request_id()).service(index)
+/// # ;
+/// ```
+pub fn request_id() -> RequestIdMiddleware {
+    RequestIdMiddleware::new()
+}
+
+/// A middleware that assigns each request an ID, for correlating logs across services.
+///
+/// If the request has an `X-Request-Id` header with a plausible value, that ID is propagated;
+/// otherwise, a random UUIDv4-formatted ID is generated. Incoming IDs must be at most 200
+/// characters of visible ASCII to be propagated.
+///
+/// The ID is stored in request extensions, where it can be read using the [`RequestId`]
+/// extractor, and is set on the response's `X-Request-Id` header. Responses from inner services
+/// which fail with an error do not have the header set.
+///
+/// When registered outside of a [`RequestContextMiddleware`] which uses the default request ID
+/// resolver, the context's request ID is the one assigned by this middleware.
+///
+/// [`RequestContextMiddleware`]: crate::middleware::RequestContextMiddleware
+#[derive(Clone)]
+pub struct RequestIdMiddleware {
+    header: HeaderName,
+    trust_incoming: bool,
+    entropy: Arc<dyn Entropy>,
+}
+
+impl RequestIdMiddleware {
+    /// Constructs new request ID middleware which propagates incoming IDs.
+    pub fn new() -> Self {
+        Self {
+            header: X_REQUEST_ID,
+            trust_incoming: true,
+            entropy: Arc::new(SystemEntropy::new()),
+        }
+    }
+
+    /// Sets header used to read incoming IDs and to send IDs in responses.
+    ///
+    /// Defaults to `X-Request-Id`.
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Always generates a new ID, ignoring any incoming one.
+    ///
+    /// Useful at the edge of a system, where incoming IDs are provided by untrusted clients.
+    pub fn ignore_incoming(mut self) -> Self {
+        self.trust_incoming = false;
+        self
+    }
+
+    /// Sets the source of randomness used to generate IDs.
+    ///
+    /// Defaults to [`SystemEntropy`].
+    pub fn entropy(mut self, entropy: impl Entropy + 'static) -> Self {
+        self.entropy = Arc::new(entropy);
+        self
+    }
+
+    fn assign(&self, req: &ServiceRequest) -> RequestId {
+        if self.trust_incoming {
+            let incoming = req
+                .headers()
+                .get(&self.header)
+                .and_then(|val| val.to_str().ok())
+                .filter(|id| is_plausible(id));
+
+            if let Some(id) = incoming {
+                return RequestId(id.to_owned());
+            }
+        }
+
+        RequestId(generate(&*self.entropy))
+    }
+}
+
+impl Default for RequestIdMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for RequestIdMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIdMiddleware")
+            .field("header", &self.header)
+            .field("trust_incoming", &self.trust_incoming)
+            .field("entropy", &self.entropy)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdService {
+            service,
+            config: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`RequestIdMiddleware`].
+pub struct RequestIdService<S> {
+    service: S,
+    config: RequestIdMiddleware,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = self.config.assign(&req);
+        let header = self.config.header.clone();
+
+        // generated and propagated IDs are always valid header values
+        let value = HeaderValue::from_str(id.as_str()).ok();
+
+        req.extensions_mut().insert(id);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            if let Some(value) = value {
+                res.headers_mut().insert(header, value);
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+/// ID assigned to a request by the [`request_id()`] middleware.
+///
+/// Extracting `RequestId` without the middleware results in a
+/// [`LabError::MiddlewareNotRegistered`] error.
+///
+/// See [`request_id()`] docs for an example.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl_more::impl_deref!(RequestId => String);
+impl_more::forward_display!(RequestId);
+
+impl RequestId {
+    /// Returns request ID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps into inner request ID.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `RequestId` for `{}` handler. For the RequestId extractor to \
+                work correctly, wrap the app or scope with the `request_id()` middleware.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "request_id",
+            }
+            .into()
+        }))
+    }
+}
+
+/// Returns true if an incoming ID is short and contains only visible ASCII.
+fn is_plausible(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_INCOMING_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Generates a random ID formatted as a version 4 UUID.
+pub(crate) fn generate(entropy: &dyn Entropy) -> String {
+    // set version (4) and variant (0b10) bits
+    let hi = (entropy.next_u64() & !(0xF << 12)) | (0x4 << 12);
+    let lo = (entropy.next_u64() & !(0b11 << 62)) | (0b10 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xFFFF,
+        hi & 0xFFFF,
+        lo >> 48,
+        lo & 0xFFFF_FFFF_FFFF,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    use super::*;
+    use crate::{
+        extract::RequestContext, middleware::RequestContextMiddleware, test::SeededEntropy,
+    };
+
+    async fn echo(id: RequestId) -> String {
+        id.into_inner()
+    }
+
+    #[actix_web::test]
+    async fn generates_ids() {
+        let app = test::init_service(
+            App::new()
+                .wrap(request_id().entropy(SeededEntropy::new(42)))
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        let header = res.headers().get("x-request-id").unwrap().clone();
+        let body = test::read_body(res).await;
+
+        assert_eq!(header.as_bytes(), body);
+        let id = header.to_str().unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_ne!(res.headers().get("x-request-id").unwrap(), &header);
+    }
+
+    #[actix_web::test]
+    async fn propagates_plausible_ids() {
+        let app = test::init_service(
+            App::new()
+                .wrap(request_id())
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc-123");
+        assert_eq!(test::read_body(res).await, "abc-123");
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "a".repeat(201)))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap().len(), 36);
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "has space"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_ne!(res.headers().get("x-request-id").unwrap(), "has space");
+    }
+
+    #[actix_web::test]
+    async fn custom_header_and_ignore_incoming() {
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    request_id()
+                        .header(HeaderName::from_static("x-correlation-id"))
+                        .ignore_incoming(),
+                )
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-correlation-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.headers().get("x-request-id").is_none());
+        let id = res.headers().get("x-correlation-id").unwrap().clone();
+        assert_ne!(id, "abc-123");
+        assert_eq!(test::read_body(res).await, id.as_bytes());
+    }
+
+    #[actix_web::test]
+    async fn request_context_uses_assigned_id() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestContextMiddleware::new())
+                .wrap(request_id().ignore_incoming())
+                .route(
+                    "/",
+                    web::get().to(|ctx: RequestContext| async move {
+                        ctx.request_id().unwrap().to_owned()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("x-request-id", "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let id = res.headers().get("x-request-id").unwrap().clone();
+        assert_ne!(id, "abc-123");
+        assert_eq!(test::read_body(res).await, id.as_bytes());
+    }
+
+    #[actix_web::test]
+    async fn extractor_without_middleware() {
+        let req = TestRequest::default().to_http_request();
+        let err = RequestId::extract(&req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
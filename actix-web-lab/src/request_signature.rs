@@ -30,6 +30,9 @@ use tracing::trace;
 /// for certain crypto ecosystems though many of the examples shown here will use types from
 /// [RustCrypto](https://github.com/RustCrypto).
 ///
+/// For common webhook formats, the `Ed25519Scheme` and `HmacSha256Scheme` implementations are
+/// available behind the `ed25519` and `hmac-sha256` crate features, respectively.
+///
 /// # `RequestSignature` Extractor
 /// Types that implement this trait can be used with the [`RequestSignature`] extractor to
 /// declaratively derive the request signature alongside the desired body extractor.
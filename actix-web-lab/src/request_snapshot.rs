@@ -0,0 +1,226 @@
+//! Request snapshot extractor.
+//!
+//! See [`RequestSnapshot`] docs.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage as _, HttpRequest};
+use serde::{ser::SerializeStruct as _, Serialize, Serializer};
+
+use crate::{entropy::SystemEntropy, extract::RequestId, request_id, scrub::Scrubber};
+
+/// A serializable summary of a request, for error reports and support tickets.
+///
+/// Captures the method, URI, matched route pattern, headers, and peer address of a request, along
+/// with an ID which can be shown to users and quoted back when reporting problems. The ID is the
+/// one assigned by the [`request_id()`](crate::middleware::request_id) middleware, if it is
+/// registered, or is otherwise randomly generated.
+///
+/// Sensitive headers and query parameters are scrubbed using the [`Scrubber`] registered as app
+/// data, either directly or wrapped in [`Data`](web::Data), or using [`Scrubber::new()`] if none
+/// is registered.
+///
+/// Snapshots are cached in request extensions, so capturing a request more than once returns the
+/// same snapshot. [`ProblemDetails`] responses for requests which have been captured include the
+/// snapshot's ID as the `snapshot_id` extension member.
+///
+/// # Extractor
+/// Extracting a `RequestSnapshot` is infallible.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::StatusCode, App};
+/// use actix_web_lab::{extract::RequestSnapshot, respond::ProblemDetails};
+///
+/// #[get("/")]
+/// async fn index(snapshot: RequestSnapshot) -> ProblemDetails {
+///     tracing::error!(snapshot = %serde_json::to_string(&snapshot).unwrap(), "lookup failed");
+///
+///     // response includes `snapshot_id`
+///     ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE)
+/// }
+///
+/// App::new().service(index)
+/// # ;
+/// ```
+///
+/// [`ProblemDetails`]: crate::respond::ProblemDetails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSnapshot {
+    id: String,
+    method: String,
+    uri: String,
+    match_pattern: Option<String>,
+    headers: Vec<(String, String)>,
+    peer_addr: Option<SocketAddr>,
+}
+
+impl RequestSnapshot {
+    /// Captures snapshot of `req`, or returns the snapshot already captured for it.
+    pub fn capture(req: &HttpRequest) -> Self {
+        if let Some(snapshot) = req.extensions().get::<Self>() {
+            return snapshot.clone();
+        }
+
+        let default_scrubber;
+        let scrubber = match req.app_data::<Scrubber>().or_else(|| {
+            req.app_data::<web::Data<Scrubber>>()
+                .map(|data| data.get_ref())
+        }) {
+            Some(scrubber) => scrubber,
+            None => {
+                default_scrubber = Scrubber::new();
+                &default_scrubber
+            }
+        };
+
+        let id = match req.extensions().get::<RequestId>() {
+            Some(id) => id.as_str().to_owned(),
+            None => request_id::generate(&SystemEntropy::new()),
+        };
+
+        let headers = scrubber
+            .scrub_headers(req.headers())
+            .into_iter()
+            .map(|(name, val)| (name.as_str().to_owned(), val))
+            .collect();
+
+        let snapshot = Self {
+            id,
+            method: req.method().to_string(),
+            uri: scrubber.scrub_uri(req.uri()),
+            match_pattern: req.match_pattern(),
+            headers,
+            peer_addr: req.peer_addr(),
+        };
+
+        req.extensions_mut().insert(snapshot.clone());
+
+        snapshot
+    }
+
+    /// Returns snapshot ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns request method.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns request URI, with sensitive query parameters scrubbed.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns pattern of the matched route, if any.
+    pub fn match_pattern(&self) -> Option<&str> {
+        self.match_pattern.as_deref()
+    }
+
+    /// Returns request headers, with sensitive values scrubbed.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns peer address of the connection, if known.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Returns ID of snapshot captured for `req`, if any.
+    pub(crate) fn captured_id(req: &HttpRequest) -> Option<String> {
+        req.extensions()
+            .get::<Self>()
+            .map(|snapshot| snapshot.id.clone())
+    }
+}
+
+impl Serialize for RequestSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut snapshot = serializer.serialize_struct("RequestSnapshot", 6)?;
+        snapshot.serialize_field("id", &self.id)?;
+        snapshot.serialize_field("method", &self.method)?;
+        snapshot.serialize_field("uri", &self.uri)?;
+        snapshot.serialize_field("match_pattern", &self.match_pattern)?;
+        snapshot.serialize_field("headers", &self.headers)?;
+        snapshot.serialize_field("peer_addr", &self.peer_addr)?;
+        snapshot.end()
+    }
+}
+
+impl FromRequest for RequestSnapshot {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Self::capture(req)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::header,
+        test::{self, TestRequest},
+        App,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::middleware::request_id;
+
+    #[actix_web::test]
+    async fn captures_request() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Scrubber::new().query_param("token"))
+                .wrap(request_id())
+                .route(
+                    "/items/{id}",
+                    web::post().to(|req: HttpRequest, snapshot: RequestSnapshot| async move {
+                        assert_eq!(RequestSnapshot::capture(&req), snapshot);
+                        web::Json(snapshot)
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/items/7?token=s3cr3t&lang=en")
+            .insert_header(("x-request-id", "abc-123"))
+            .insert_header((header::AUTHORIZATION, "Bearer s3cr3t"))
+            .peer_addr("127.0.0.1:8080".parse().unwrap())
+            .to_request();
+        let snapshot: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(snapshot["id"], "abc-123");
+        assert_eq!(snapshot["method"], "POST");
+        assert_eq!(snapshot["match_pattern"], "/items/{id}");
+        assert_eq!(snapshot["peer_addr"], "127.0.0.1:8080");
+
+        let uri = snapshot["uri"].as_str().unwrap();
+        assert!(uri.starts_with("/items/7?token="));
+        assert!(uri.ends_with("&lang=en"));
+        assert!(!uri.contains("s3cr3t"));
+
+        let headers = snapshot["headers"].as_array().unwrap();
+        assert!(headers.contains(&json!(["x-request-id", "abc-123"])));
+        assert!(headers.contains(&json!(["authorization", "[REDACTED]"])));
+    }
+
+    #[actix_web::test]
+    async fn generates_id() {
+        let req = TestRequest::default().to_http_request();
+        let snapshot = RequestSnapshot::extract(&req).await.unwrap();
+
+        assert_eq!(snapshot.id().len(), 36);
+        assert_eq!(snapshot.match_pattern(), None);
+        assert_eq!(
+            RequestSnapshot::captured_id(&req).as_deref(),
+            Some(snapshot.id())
+        );
+    }
+}
@@ -2,6 +2,21 @@
 
 #[cfg(feature = "cbor")]
 pub use crate::cbor::Cbor;
+#[cfg(feature = "jsonapi")]
+pub use crate::json_api::{JsonApi, JsonApiCollection, JsonApiError};
 #[cfg(feature = "msgpack")]
-pub use crate::msgpack::{MessagePack, MessagePackNamed};
-pub use crate::{csv::Csv, display_stream::DisplayStream, html::Html, ndjson::NdJson};
+pub use crate::msgpack::{MessagePack, MessagePackNamed, MsgPack};
+#[cfg(feature = "protobuf")]
+pub use crate::protobuf::Protobuf;
+#[cfg(feature = "xml")]
+pub use crate::xml::Xml;
+pub use crate::{
+    csv::Csv,
+    display_stream::DisplayStream,
+    hal::{Hal, HalCollection},
+    html::Html,
+    ndjson::NdJson,
+    partial_response::PartialJson,
+    problem_details::{ApiResult, ProblemDetails, ProblemStatusMap},
+    streamed::{CsvFmt, NdJsonFmt, Negotiate, SseFmt, StreamFormat, Streamed},
+};
@@ -0,0 +1,164 @@
+use actix_web::{
+    body::{BoxBody, EitherBody, MessageBody},
+    dev::ServiceResponse,
+};
+
+/// Extension methods for unifying the body types of [`ServiceResponse`]s in middleware.
+///
+/// Middleware in this crate which may respond without calling the wrapped service use
+/// [`EitherBody<B>`] as their body type, where the left variant is the wrapped service's body and
+/// the right variant is a boxed body. Using the same shape in custom middleware keeps response
+/// types nameable and lets them be stacked in any order, alongside the lab middleware, without
+/// resorting to [`Compat`].
+///
+/// [`Compat`]: actix_web::middleware::Compat
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     body::{EitherBody, MessageBody},
+///     dev::{ServiceRequest, ServiceResponse},
+///     http::StatusCode,
+///     Error, HttpResponse,
+/// };
+/// use actix_web_lab::{middleware::Next, util::ServiceResponseExt as _};
+///
+/// async fn require_header(
+///     req: ServiceRequest,
+///     next: Next<impl MessageBody + 'static>,
+/// ) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+///     if !req.headers().contains_key("x-tenant") {
+///         let res = HttpResponse::new(StatusCode::BAD_REQUEST);
+///         return Ok(req.into_response(res).map_into_lab_short_circuit_body());
+///     }
+///
+///     Ok(next.call(req).await?.map_into_lab_body())
+/// }
+/// # actix_web::App::new().wrap(actix_web_lab::middleware::from_fn(require_header));
+/// ```
+pub trait ServiceResponseExt<B> {
+    /// Maps body into the left variant of an [`EitherBody<B>`].
+    fn map_into_lab_body(self) -> ServiceResponse<EitherBody<B>>;
+
+    /// Boxes the response's body and maps it into the right variant of an [`EitherBody<L>`].
+    ///
+    /// Useful for returning responses that were not produced by the wrapped service, such as
+    /// responses created by another service or from a cache, when a middleware short-circuits.
+    fn map_into_lab_short_circuit_body<L>(self) -> ServiceResponse<EitherBody<L>>
+    where
+        B: MessageBody + 'static;
+}
+
+impl<B> ServiceResponseExt<B> for ServiceResponse<B> {
+    fn map_into_lab_body(self) -> ServiceResponse<EitherBody<B>> {
+        self.map_into_left_body::<BoxBody>()
+    }
+
+    fn map_into_lab_short_circuit_body<L>(self) -> ServiceResponse<EitherBody<L>>
+    where
+        B: MessageBody + 'static,
+    {
+        self.map_into_boxed_body().map_into_right_body()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::{
+        http::StatusCode,
+        middleware::{DefaultHeaders, Logger},
+        test, web, App, HttpResponse,
+    };
+
+    use super::*;
+    use crate::{
+        error::ErrorRegistry,
+        middleware::{
+            from_fn, CanonicalQuery, CatchPanic, ErrorHandlers, MicroCache, MiddlewareStack, Next,
+            NormalizeHeaders, NormalizePath, PanicReporter, RedirectHttps, RequestSpan,
+            RoutePolicy, StrictHttp,
+        },
+    };
+
+    async fn passthrough(
+        req: actix_web::dev::ServiceRequest,
+        next: Next<impl MessageBody + 'static>,
+    ) -> actix_web::Result<ServiceResponse<EitherBody<impl MessageBody>>> {
+        if req.path() == "/teapot" {
+            let res = req.into_response(HttpResponse::new(StatusCode::IM_A_TEAPOT));
+            return Ok(res.map_into_lab_short_circuit_body());
+        }
+
+        Ok(next.call(req).await?.map_into_lab_body())
+    }
+
+    #[actix_web::test]
+    async fn lab_middleware_stacks_without_compat() {
+        // each app stacks the same middleware in a different order; compiling is the main test
+        let _ = App::new()
+            .wrap(StrictHttp::new())
+            .wrap(CatchPanic::default())
+            .wrap(NormalizePath::trim())
+            .wrap(CanonicalQuery::new())
+            .wrap(NormalizeHeaders::new())
+            .wrap(MicroCache::new(Duration::from_secs(1)))
+            .wrap(RedirectHttps::default())
+            .wrap(ErrorHandlers::new())
+            .wrap(ErrorRegistry::new())
+            .wrap(RequestSpan::default())
+            .wrap(PanicReporter::new(|_| {}))
+            .wrap(RoutePolicy::new())
+            .wrap(from_fn(passthrough))
+            .wrap(Logger::default());
+
+        let _ = App::new()
+            .wrap(Logger::default())
+            .wrap(from_fn(passthrough))
+            .wrap(RoutePolicy::new())
+            .wrap(PanicReporter::new(|_| {}))
+            .wrap(RequestSpan::default())
+            .wrap(ErrorRegistry::new())
+            .wrap(ErrorHandlers::new())
+            .wrap(RedirectHttps::default())
+            .wrap(MicroCache::new(Duration::from_secs(1)))
+            .wrap(NormalizeHeaders::new())
+            .wrap(CanonicalQuery::new())
+            .wrap(NormalizePath::trim())
+            .wrap(CatchPanic::default())
+            .wrap(StrictHttp::new());
+
+        let _ = App::new().service(
+            web::scope("/api")
+                .wrap(ErrorHandlers::new())
+                .wrap(NormalizePath::trim())
+                .wrap(from_fn(passthrough))
+                .wrap(StrictHttp::new())
+                .wrap(MicroCache::new(Duration::from_secs(1)))
+                .wrap(CatchPanic::default()),
+        );
+
+        let stack = MiddlewareStack::new()
+            .wrap(from_fn(passthrough))
+            .wrap(CanonicalQuery::new())
+            .wrap(StrictHttp::new())
+            .wrap(DefaultHeaders::new());
+
+        let app = test::init_service(
+            App::new()
+                .wrap(stack)
+                .wrap(NormalizePath::trim())
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::with_uri("/teapot").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::IM_A_TEAPOT);
+
+        let req = test::TestRequest::with_uri("/other").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
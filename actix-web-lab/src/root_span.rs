@@ -0,0 +1,324 @@
+//! Request-scoped tracing span middleware and extractor.
+//!
+//! See [`RequestSpan`] and [`RootSpan`] for docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    time::Instant,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use futures_core::future::LocalBoxFuture;
+use tracing::{debug, Instrument as _, Span};
+
+use crate::{
+    error::{ErrorChain, LabError},
+    scrub::Scrubber,
+};
+
+type MakeSpanFn = Rc<dyn Fn(&ServiceRequest) -> Span>;
+
+/// A middleware that creates a root tracing span for each request.
+///
+/// The request's handler and inner middleware are instrumented with the span. When the response
+/// is ready, its status code is recorded on the span's `http.status_code` field (if declared) and
+/// an access-log event is emitted inside the span, including any [`ErrorChain`] found in the
+/// response's extensions. Handlers can record additional fields on the
+/// span using the [`RootSpan`](crate::extract::RootSpan) extractor.
+///
+/// Since [`tracing`] only records fields that were declared when a span was created, any fields
+/// that handlers will record must be declared up front. The default span declares `http.method`,
+/// `http.target`, and `http.status_code`. Use [`new`](Self::new) with the [`root_span!`] macro
+/// to declare more, or to take full control of span creation.
+///
+/// Query parameters recorded in the `http.target` field by [`root_span!`] can be scrubbed by
+/// registering a [`Scrubber`] using [`scrubber`](Self::scrubber).
+///
+/// [`root_span!`]: crate::root_span!
+/// [`Scrubber`]: crate::util::scrub::Scrubber
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App, Responder};
+/// use actix_web_lab::{extract::RootSpan, middleware::RequestSpan, root_span};
+///
+/// #[get("/")]
+/// async fn index(span: RootSpan) -> impl Responder {
+///     span.record("user_id", 42);
+///     "Hello World!"
+/// }
+///
+/// App::new()
+///     .wrap(RequestSpan::new(|req| root_span!(req, user_id, tenant)))
+///     .service(index)
+///     # ;
+/// ```
+#[derive(Clone)]
+pub struct RequestSpan {
+    make_span: MakeSpanFn,
+    scrubber: Option<Scrubber>,
+}
+
+impl RequestSpan {
+    /// Constructs new request span middleware which creates spans using `make_span`.
+    ///
+    /// Fields which are to be recorded later should be declared using [`tracing::field::Empty`].
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web_lab::middleware::RequestSpan;
+    /// use tracing::field::Empty;
+    ///
+    /// RequestSpan::new(|req| {
+    ///     tracing::info_span!(
+    ///         "request",
+    ///         method = %req.method(),
+    ///         path = req.path(),
+    ///         http.status_code = Empty,
+    ///         tenant = Empty,
+    ///     )
+    /// })
+    /// # ;
+    /// ```
+    pub fn new(make_span: impl Fn(&ServiceRequest) -> Span + 'static) -> Self {
+        Self {
+            make_span: Rc::new(make_span),
+            scrubber: None,
+        }
+    }
+
+    /// Sets scrubber used to remove sensitive query parameters from recorded request targets.
+    ///
+    /// The scrubber is made available to the span constructor through request extensions;
+    /// [`root_span!`](crate::root_span!) uses it automatically.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web_lab::{middleware::RequestSpan, util::scrub::Scrubber};
+    ///
+    /// RequestSpan::default().scrubber(Scrubber::new().query_param("token"))
+    /// # ;
+    /// ```
+    pub fn scrubber(mut self, scrubber: Scrubber) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+}
+
+impl Default for RequestSpan {
+    fn default() -> Self {
+        Self::new(|req| crate::root_span!(req))
+    }
+}
+
+impl fmt::Debug for RequestSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestSpan")
+            .field("make_span", &"<callback>")
+            .field("scrubber", &self.scrubber)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestSpan
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestSpanMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestSpanMiddleware {
+            service: Rc::new(service),
+            make_span: Rc::clone(&self.make_span),
+            scrubber: self.scrubber.clone(),
+        }))
+    }
+}
+
+pub struct RequestSpanMiddleware<S> {
+    service: Rc<S>,
+    make_span: MakeSpanFn,
+    scrubber: Option<Scrubber>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestSpanMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(scrubber) = &self.scrubber {
+            req.extensions_mut().insert(scrubber.clone());
+        }
+
+        let span = (self.make_span)(&req);
+        req.extensions_mut().insert(RootSpan(span.clone()));
+
+        let start = Instant::now();
+        let fut = self.service.call(req).instrument(span.clone());
+
+        Box::pin(async move {
+            let res = fut.await;
+
+            let status = match &res {
+                Ok(res) => res.response().status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+
+            span.record("http.status_code", status.as_u16());
+
+            let error_chain = res.as_ref().ok().and_then(|res| {
+                res.response()
+                    .extensions()
+                    .get::<ErrorChain>()
+                    .map(ToString::to_string)
+            });
+
+            span.in_scope(|| {
+                tracing::info!(
+                    status = status.as_u16(),
+                    elapsed_ms = start.elapsed().as_millis() as u64,
+                    error.chain = error_chain.as_deref(),
+                    "request completed",
+                );
+            });
+
+            res
+        })
+    }
+}
+
+/// Extractor for the root tracing span of the current request.
+///
+/// Requires the [`RequestSpan`](crate::middleware::RequestSpan) middleware to be registered.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::extract::RootSpan;
+///
+/// #[get("/")]
+/// async fn index(span: RootSpan) -> impl Responder {
+///     span.record("tenant", "acme");
+///     "Hello World!"
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RootSpan(Span);
+
+impl RootSpan {
+    /// Records a value on one of the span's declared fields.
+    ///
+    /// Recording a field which was not declared when the span was created has no effect.
+    pub fn record<V: tracing::Value>(&self, field: &str, value: V) -> &Self {
+        self.0.record(field, value);
+        self
+    }
+
+    /// Returns a reference to the underlying span.
+    pub fn span(&self) -> &Span {
+        &self.0
+    }
+
+    /// Unwraps into the underlying span.
+    pub fn into_inner(self) -> Span {
+        self.0
+    }
+}
+
+impl FromRequest for RootSpan {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<RootSpan>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `RootSpan` for `{}` handler. For the RootSpan extractor to \
+                work correctly, wrap the app or scope with the `RequestSpan` middleware.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "RequestSpan",
+            }
+            .into()
+        }))
+    }
+}
+
+/// Creates a root span for a request, declaring the default fields used by [`RequestSpan`] plus
+/// any additional (initially empty) fields.
+///
+/// [`RequestSpan`]: crate::middleware::RequestSpan
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::{middleware::RequestSpan, root_span};
+///
+/// RequestSpan::new(|req| root_span!(req, user_id, tenant))
+/// # ;
+/// ```
+#[macro_export]
+macro_rules! root_span {
+    ($req:expr $(, $field:ident)* $(,)?) => {
+        $crate::__reexports::tracing::info_span!(
+            "http_request",
+            http.method = %$req.method(),
+            http.target = %$crate::__reexports::span_target(&$req),
+            http.status_code = $crate::__reexports::tracing::field::Empty,
+            $($field = $crate::__reexports::tracing::field::Empty,)*
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn extracts_root_span() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestSpan::new(|req| root_span!(req, user_id)))
+                .default_service(web::to(|span: RootSpan| async move {
+                    span.record("user_id", 42);
+                    HttpResponse::Ok()
+                })),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn missing_middleware() {
+        let app = test::init_service(
+            App::new().default_service(web::to(|_span: RootSpan| async { HttpResponse::Ok() })),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
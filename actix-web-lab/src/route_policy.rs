@@ -0,0 +1,242 @@
+//! Per-route body size and timeout policy middleware.
+//!
+//! See [`RoutePolicy`] docs.
+
+use std::{
+    future::{ready, Ready},
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_http::BoxedPayloadStream;
+use actix_web::{
+    body::EitherBody,
+    dev::{self, forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::header,
+    rt::time::timeout,
+    web::Bytes,
+    Error, HttpMessage as _,
+};
+use futures_core::{future::LocalBoxFuture, Stream};
+
+use crate::error::LabError;
+
+/// A middleware that enforces a request body size limit and a handler timeout.
+///
+/// Requests which declare a `Content-Length` above the body limit are rejected with
+/// `413 Payload Too Large` without calling the inner service. Otherwise, the request payload is
+/// wrapped so that reading past the limit yields a [`PayloadError::Overflow`] error, which
+/// extractors report as `413 Payload Too Large`. This applies to all extractors, including those
+/// with their own, larger limits.
+///
+/// Handlers which do not produce a response within the timeout are cancelled and the error
+/// response for [`LabError::HandlerTimeout`] is returned instead.
+///
+/// This middleware is generated by the crate's route macros when `body_limit` or `timeout` is
+/// given, but can also be used directly.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{web, App, HttpResponse};
+/// use actix_web_lab::middleware::RoutePolicy;
+///
+/// App::new().service(
+///     web::resource("/upload")
+///         .wrap(
+///             RoutePolicy::new()
+///                 .body_limit(10 * 1024 * 1024)
+///                 .timeout(Duration::from_secs(30)),
+///         )
+///         .route(web::post().to(HttpResponse::Ok)),
+/// )
+/// # ;
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoutePolicy {
+    body_limit: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl RoutePolicy {
+    /// Constructs a new policy with no body limit or timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets maximum request body size, in bytes.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// Sets maximum time for the inner service to produce a response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RoutePolicy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RoutePolicyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RoutePolicyMiddleware {
+            service: Rc::new(service),
+            policy: *self,
+        }))
+    }
+}
+
+/// Service for the [`RoutePolicy`] middleware.
+pub struct RoutePolicyMiddleware<S> {
+    service: Rc<S>,
+    policy: RoutePolicy,
+}
+
+impl<S, B> Service<ServiceRequest> for RoutePolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if let Some(limit) = self.policy.body_limit {
+            let declared_len = req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|len| len.to_str().ok())
+                .and_then(|len| len.parse::<usize>().ok());
+
+            if declared_len.is_some_and(|len| len > limit) {
+                let res = req.error_response(PayloadError::Overflow);
+                return Box::pin(ready(Ok(res.map_into_right_body())));
+            }
+
+            let payload = req.take_payload();
+            let limited: BoxedPayloadStream = Box::pin(LimitedPayload {
+                payload,
+                remaining: limit,
+            });
+            req.set_payload(dev::Payload::from(limited));
+        }
+
+        let Some(dur) = self.policy.timeout else {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        };
+
+        let http_req = req.request().clone();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            match timeout(dur, fut).await {
+                Ok(res) => res.map(ServiceResponse::map_into_left_body),
+                Err(_) => {
+                    let err = LabError::HandlerTimeout { timeout: dur };
+                    Ok(ServiceResponse::from_err(err, http_req).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// Payload stream which errors once more than `remaining` bytes are read.
+struct LimitedPayload {
+    payload: dev::Payload,
+    remaining: usize,
+}
+
+impl Stream for LimitedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match Pin::new(&mut this.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => match this.remaining.checked_sub(chunk.len()) {
+                Some(remaining) => {
+                    this.remaining = remaining;
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+                None => Poll::Ready(Some(Err(PayloadError::Overflow))),
+            },
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn body_limit() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RoutePolicy::new().body_limit(4))
+                .default_service(web::to(|body: Bytes| async move {
+                    HttpResponse::Ok().body(body)
+                })),
+        )
+        .await;
+
+        let req = test::TestRequest::post().set_payload("abcd").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = test::TestRequest::post().set_payload("abcde").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // without Content-Length, the limit is enforced while reading
+        let mut req = test::TestRequest::post().set_payload("abcde").to_request();
+        req.headers_mut().remove(header::CONTENT_LENGTH);
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_web::test]
+    async fn handler_timeout() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RoutePolicy::new().timeout(Duration::from_millis(10)))
+                .route(
+                    "/slow",
+                    web::to(|| async {
+                        actix_web::rt::time::sleep(Duration::from_secs(10)).await;
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route("/fast", web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::with_uri("/fast").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = test::TestRequest::with_uri("/slow").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,707 @@
+//! RSQL/FIQL filter expression extractor.
+//!
+//! See [`Rsql`] for docs.
+
+use std::{
+    borrow::Cow,
+    fmt,
+    future::{ready, Ready},
+    str::FromStr,
+};
+
+use actix_web::{dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, ResponseError};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+/// Characters which may not appear in unquoted selectors and arguments.
+const RESERVED: &[char] = &['"', '\'', '(', ')', ';', ',', '=', '!', '~', '<', '>'];
+
+/// RSQL/FIQL filter expression extractor.
+///
+/// Parses an [RSQL] filter expression, such as `name=="Kill Bill";year=gt=2003`, from a query
+/// parameter into an [`Expr`] tree. Logical operators can be written as `;` and `,` or as ` and `
+/// and ` or `, and `and` binds tighter than `or`.
+///
+/// The query parameter name, allowed selectors, and allowed operators are configured by
+/// registering an [`RsqlConfig`] as app data. By default, the expression is read from the `filter`
+/// parameter, all selectors are allowed, and only the standard operators (`==`, `!=`, `=lt=`/`<`,
+/// `=le=`/`<=`, `=gt=`/`>`, `=ge=`/`>=`, `=in=`, and `=out=`) are allowed.
+///
+/// Invalid or disallowed expressions result in a `400 Bad Request` response whose body describes
+/// the problem, including its position in the expression for syntax errors.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, App};
+/// use actix_web_lab::extract::{
+///     rsql::{Expr, Operator},
+///     Rsql, RsqlConfig,
+/// };
+///
+/// fn to_sql(expr: &Expr) -> String {
+///     match expr {
+///         Expr::And(items) => items.iter().map(to_sql).collect::<Vec<_>>().join(" AND "),
+///         Expr::Or(items) => format!(
+///             "({})",
+///             items.iter().map(to_sql).collect::<Vec<_>>().join(" OR ")
+///         ),
+///         Expr::Comparison(cmp) => format!("{} {} ?", cmp.selector, cmp.operator),
+///     }
+/// }
+///
+/// #[get("/movies")]
+/// async fn movies(filter: Rsql) -> String {
+///     filter.expr().map(to_sql).unwrap_or_default()
+/// }
+///
+/// App::new()
+///     .app_data(
+///         RsqlConfig::default()
+///             .param("q")
+///             .allow_selectors(["title", "year", "genre"])
+///             .allow_operators([Operator::Eq, Operator::Gt, Operator::In]),
+///     )
+///     .service(movies)
+/// # ;
+/// ```
+///
+/// [RSQL]: https://github.com/jirutka/rsql-parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rsql(pub Option<Expr>);
+
+impl Rsql {
+    /// Returns parsed expression, or `None` if the query parameter was not present.
+    pub fn expr(&self) -> Option<&Expr> {
+        self.0.as_ref()
+    }
+
+    /// Unwraps into inner expression.
+    pub fn into_inner(self) -> Option<Expr> {
+        self.0
+    }
+}
+
+impl FromRequest for Rsql {
+    type Error = RsqlError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let config = RsqlConfig::from_req(req);
+
+        let res = serde_html_form::from_str::<Vec<(String, String)>>(req.query_string())
+            .map_err(RsqlError::Query)
+            .and_then(|params| {
+                params
+                    .into_iter()
+                    .find(|(key, _)| *key == config.param)
+                    .map(|(_, val)| config.parse(&val))
+                    .transpose()
+            });
+
+        if let Err(err) = &res {
+            debug!(
+                "Failed to parse RSQL expression for `{}` handler: {err}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        ready(res.map(Rsql))
+    }
+}
+
+/// Configuration for the [`Rsql`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone)]
+pub struct RsqlConfig {
+    param: Cow<'static, str>,
+    selectors: Option<Vec<String>>,
+    operators: Vec<Operator>,
+}
+
+impl RsqlConfig {
+    /// Sets name of the query parameter containing the expression.
+    ///
+    /// The default is `filter`.
+    pub fn param(mut self, param: impl Into<Cow<'static, str>>) -> Self {
+        self.param = param.into();
+        self
+    }
+
+    /// Restricts selectors that may be used in expressions.
+    pub fn allow_selectors<I>(mut self, selectors: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.selectors = Some(selectors.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets operators that may be used in expressions, replacing the default standard operators.
+    ///
+    /// Custom `=name=` operators are only accepted if allowed here.
+    pub fn allow_operators(mut self, operators: impl IntoIterator<Item = Operator>) -> Self {
+        self.operators = operators.into_iter().collect();
+        self
+    }
+
+    /// Parses and validates an expression using this configuration.
+    pub fn parse(&self, input: &str) -> Result<Expr, RsqlError> {
+        let mut parser = Parser {
+            input,
+            pos: 0,
+            config: self,
+        };
+
+        let expr = parser.parse_or()?;
+        parser.skip_whitespace();
+
+        if parser.pos < input.len() {
+            return Err(parser.error("unexpected character after expression"));
+        }
+
+        Ok(expr)
+    }
+
+    fn from_req(req: &HttpRequest) -> Cow<'_, Self> {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .map_or_else(|| Cow::Owned(Self::default()), Cow::Borrowed)
+    }
+}
+
+impl Default for RsqlConfig {
+    fn default() -> Self {
+        Self {
+            param: Cow::Borrowed("filter"),
+            selectors: None,
+            operators: vec![
+                Operator::Eq,
+                Operator::Ne,
+                Operator::Lt,
+                Operator::Le,
+                Operator::Gt,
+                Operator::Ge,
+                Operator::In,
+                Operator::Out,
+            ],
+        }
+    }
+}
+
+/// A parsed RSQL expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// All expressions must match.
+    And(Vec<Expr>),
+
+    /// Any expression must match.
+    Or(Vec<Expr>),
+
+    /// A single comparison.
+    Comparison(Comparison),
+}
+
+/// Parses an expression using the default [`RsqlConfig`].
+impl FromStr for Expr {
+    type Err = RsqlError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        RsqlConfig::default().parse(input)
+    }
+}
+
+/// A comparison of a selector to one or more arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    /// Selector, usually a field name.
+    pub selector: String,
+
+    /// Comparison operator.
+    pub operator: Operator,
+
+    /// Arguments, with quotes removed. Only set operators can have more than one argument.
+    pub arguments: Vec<String>,
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operator {
+    /// `==`
+    Eq,
+
+    /// `!=`
+    Ne,
+
+    /// `=lt=` or `<`
+    Lt,
+
+    /// `=le=` or `<=`
+    Le,
+
+    /// `=gt=` or `>`
+    Gt,
+
+    /// `=ge=` or `>=`
+    Ge,
+
+    /// `=in=`
+    In,
+
+    /// `=out=`
+    Out,
+
+    /// A custom `=name=` operator.
+    Custom(Cow<'static, str>),
+}
+
+impl Operator {
+    /// Returns true if this operator accepts a list of arguments.
+    ///
+    /// Custom operators are assumed to accept lists.
+    pub fn is_multi_valued(&self) -> bool {
+        matches!(self, Self::In | Self::Out | Self::Custom(_))
+    }
+}
+
+/// Formats operators using their FIQL form.
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eq => f.write_str("=="),
+            Self::Ne => f.write_str("!="),
+            Self::Lt => f.write_str("=lt="),
+            Self::Le => f.write_str("=le="),
+            Self::Gt => f.write_str("=gt="),
+            Self::Ge => f.write_str("=ge="),
+            Self::In => f.write_str("=in="),
+            Self::Out => f.write_str("=out="),
+            Self::Custom(name) => write!(f, "={name}="),
+        }
+    }
+}
+
+/// Errors that can occur when parsing [`Rsql`] expressions.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum RsqlError {
+    /// Query string could not be parsed.
+    #[display(fmt = "Query string could not be parsed: {_0}")]
+    Query(serde::de::value::Error),
+
+    /// Expression is not valid RSQL.
+    #[display(fmt = "Invalid RSQL expression at position {position}: {reason}.")]
+    Syntax {
+        /// Byte offset in the expression at which the problem was found.
+        position: usize,
+
+        /// Description of the problem.
+        reason: &'static str,
+    },
+
+    /// Expression uses a selector that is not allowed.
+    #[display(fmt = "Selector `{selector}` is not allowed.")]
+    SelectorNotAllowed {
+        /// Selector.
+        selector: String,
+    },
+
+    /// Expression uses an operator that is not allowed.
+    #[display(fmt = "Operator `{operator}` is not allowed.")]
+    OperatorNotAllowed {
+        /// Operator.
+        operator: Operator,
+    },
+}
+
+impl ResponseError for RsqlError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Recursive descent parser for RSQL expressions.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    config: &'a RsqlConfig,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> RsqlError {
+        RsqlError::Syntax {
+            position: self.pos,
+            reason,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+
+        if found {
+            self.pos += ch.len_utf8();
+        }
+
+        found
+    }
+
+    /// Skips whitespace, returning true if any was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+        self.pos > start
+    }
+
+    /// Consumes a logical operator, in symbol or keyword form.
+    fn eat_logical(&mut self, symbol: char, keyword: &str) -> bool {
+        let start = self.pos;
+        let had_whitespace = self.skip_whitespace();
+
+        if self.eat(symbol) {
+            return true;
+        }
+
+        if had_whitespace {
+            if let Some(after) = self.rest().strip_prefix(keyword) {
+                if after.starts_with(char::is_whitespace) {
+                    self.pos += keyword.len();
+                    return true;
+                }
+            }
+        }
+
+        self.pos = start;
+        false
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, RsqlError> {
+        let mut items = vec![self.parse_and()?];
+
+        while self.eat_logical(',', "or") {
+            items.push(self.parse_and()?);
+        }
+
+        Ok(match items.len() {
+            1 => items.pop().unwrap(),
+            _ => Expr::Or(items),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, RsqlError> {
+        let mut items = vec![self.parse_constraint()?];
+
+        while self.eat_logical(';', "and") {
+            items.push(self.parse_constraint()?);
+        }
+
+        Ok(match items.len() {
+            1 => items.pop().unwrap(),
+            _ => Expr::And(items),
+        })
+    }
+
+    fn parse_constraint(&mut self) -> Result<Expr, RsqlError> {
+        self.skip_whitespace();
+
+        if self.eat('(') {
+            let expr = self.parse_or()?;
+            self.skip_whitespace();
+
+            if !self.eat(')') {
+                return Err(self.error("expected `)`"));
+            }
+
+            return Ok(expr);
+        }
+
+        self.parse_comparison().map(Expr::Comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Comparison, RsqlError> {
+        let selector = self.parse_unreserved();
+
+        if selector.is_empty() {
+            return Err(self.error("expected selector"));
+        }
+
+        if let Some(selectors) = &self.config.selectors {
+            if !selectors.contains(&selector) {
+                return Err(RsqlError::SelectorNotAllowed { selector });
+            }
+        }
+
+        let operator = self.parse_operator()?;
+
+        if !self.config.operators.contains(&operator) {
+            return Err(RsqlError::OperatorNotAllowed { operator });
+        }
+
+        let arguments = if self.peek() == Some('(') {
+            if !operator.is_multi_valued() {
+                return Err(self.error("operator does not accept a list of arguments"));
+            }
+
+            self.pos += 1;
+            let mut arguments = Vec::new();
+
+            loop {
+                self.skip_whitespace();
+                arguments.push(self.parse_argument()?);
+                self.skip_whitespace();
+
+                if self.eat(')') {
+                    break;
+                }
+
+                if !self.eat(',') {
+                    return Err(self.error("expected `,` or `)`"));
+                }
+            }
+
+            arguments
+        } else {
+            vec![self.parse_argument()?]
+        };
+
+        Ok(Comparison {
+            selector,
+            operator,
+            arguments,
+        })
+    }
+
+    fn parse_operator(&mut self) -> Result<Operator, RsqlError> {
+        let symbols = [
+            ("==", Operator::Eq),
+            ("!=", Operator::Ne),
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+        ];
+
+        for (symbol, operator) in symbols {
+            if self.rest().starts_with(symbol) {
+                self.pos += symbol.len();
+                return Ok(operator);
+            }
+        }
+
+        let name = self
+            .rest()
+            .strip_prefix('=')
+            .map(|rest| {
+                let len = rest
+                    .find(|ch: char| !ch.is_ascii_alphabetic())
+                    .unwrap_or(rest.len());
+                &rest[..len]
+            })
+            .filter(|name| !name.is_empty() && self.rest()[name.len() + 1..].starts_with('='))
+            .ok_or_else(|| self.error("expected comparison operator"))?;
+
+        self.pos += name.len() + 2;
+
+        Ok(match name {
+            "lt" => Operator::Lt,
+            "le" => Operator::Le,
+            "gt" => Operator::Gt,
+            "ge" => Operator::Ge,
+            "in" => Operator::In,
+            "out" => Operator::Out,
+            name => Operator::Custom(Cow::Owned(name.to_owned())),
+        })
+    }
+
+    fn parse_argument(&mut self) -> Result<String, RsqlError> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let start = self.pos;
+                self.pos += 1;
+                let mut val = String::new();
+
+                loop {
+                    match self.peek() {
+                        Some(ch) if ch == quote => {
+                            self.pos += 1;
+                            return Ok(val);
+                        }
+                        Some('\\') => {
+                            self.pos += 1;
+
+                            match self.peek() {
+                                Some(ch) => {
+                                    val.push(ch);
+                                    self.pos += ch.len_utf8();
+                                }
+                                None => break,
+                            }
+                        }
+                        Some(ch) => {
+                            val.push(ch);
+                            self.pos += ch.len_utf8();
+                        }
+                        None => break,
+                    }
+                }
+
+                self.pos = start;
+                Err(self.error("unterminated quoted argument"))
+            }
+
+            _ => {
+                let val = self.parse_unreserved();
+
+                if val.is_empty() {
+                    Err(self.error("expected argument"))
+                } else {
+                    Ok(val)
+                }
+            }
+        }
+    }
+
+    fn parse_unreserved(&mut self) -> String {
+        let len = self
+            .rest()
+            .find(|ch: char| ch.is_whitespace() || RESERVED.contains(&ch))
+            .unwrap_or(self.rest().len());
+
+        let val = self.rest()[..len].to_owned();
+        self.pos += len;
+        val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn cmp(selector: &str, operator: Operator, arguments: &[&str]) -> Expr {
+        Expr::Comparison(Comparison {
+            selector: selector.to_owned(),
+            operator,
+            arguments: arguments.iter().map(|&arg| arg.to_owned()).collect(),
+        })
+    }
+
+    #[test]
+    fn parses_expressions() {
+        assert_eq!(
+            "name==\"Kill Bill\";year=gt=2003".parse::<Expr>().unwrap(),
+            Expr::And(vec![
+                cmp("name", Operator::Eq, &["Kill Bill"]),
+                cmp("year", Operator::Gt, &["2003"]),
+            ]),
+        );
+
+        assert_eq!(
+            "a<1 or b=in=( 'x\\'y' , z ) and c!=*"
+                .parse::<Expr>()
+                .unwrap(),
+            Expr::Or(vec![
+                cmp("a", Operator::Lt, &["1"]),
+                Expr::And(vec![
+                    cmp("b", Operator::In, &["x'y", "z"]),
+                    cmp("c", Operator::Ne, &["*"]),
+                ]),
+            ]),
+        );
+
+        assert_eq!(
+            "(a>=1,b=le=2);c==3".parse::<Expr>().unwrap(),
+            Expr::And(vec![
+                Expr::Or(vec![
+                    cmp("a", Operator::Ge, &["1"]),
+                    cmp("b", Operator::Le, &["2"]),
+                ]),
+                cmp("c", Operator::Eq, &["3"]),
+            ]),
+        );
+
+        // keyword-like values are not logical operators without surrounding whitespace
+        assert_eq!(
+            "genre==and".parse::<Expr>().unwrap(),
+            cmp("genre", Operator::Eq, &["and"])
+        );
+    }
+
+    #[test]
+    fn reports_positions() {
+        let position = |input: &str| match input.parse::<Expr>().unwrap_err() {
+            RsqlError::Syntax { position, .. } => position,
+            err => panic!("unexpected error: {err}"),
+        };
+
+        assert_eq!(position("==1"), 0);
+        assert_eq!(position("a~1"), 1);
+        assert_eq!(position("a==1;"), 5);
+        assert_eq!(position("(a==1"), 5);
+        assert_eq!(position("a==\"x"), 3);
+        assert_eq!(position("a==(1,2)"), 3);
+        assert_eq!(position("a=in=(1 2)"), 8);
+        assert_eq!(position("a==1 b==2"), 5);
+    }
+
+    #[test]
+    fn validates_config() {
+        let config = RsqlConfig::default()
+            .allow_selectors(["name"])
+            .allow_operators([Operator::Eq, Operator::Custom("like".into())]);
+
+        assert_eq!(
+            config.parse("name=like=abc*").unwrap(),
+            cmp("name", Operator::Custom("like".into()), &["abc*"]),
+        );
+        assert!(matches!(
+            config.parse("year==1"),
+            Err(RsqlError::SelectorNotAllowed { selector }) if selector == "year"
+        ));
+        assert!(matches!(
+            config.parse("name!=x"),
+            Err(RsqlError::OperatorNotAllowed {
+                operator: Operator::Ne
+            })
+        ));
+        assert!(matches!(
+            "a=like=x".parse::<Expr>(),
+            Err(RsqlError::OperatorNotAllowed { .. })
+        ));
+    }
+
+    #[actix_web::test]
+    async fn extracts() {
+        let req = TestRequest::with_uri("/?filter=year%3Dgt%3D2003").to_http_request();
+        let Rsql(expr) = Rsql::extract(&req).await.unwrap();
+        assert_eq!(expr, Some(cmp("year", Operator::Gt, &["2003"])));
+
+        let req = TestRequest::with_uri("/?q=a%3D%3D1")
+            .app_data(RsqlConfig::default().param("q"))
+            .to_http_request();
+        let rsql = Rsql::extract(&req).await.unwrap();
+        assert!(rsql.expr().is_some());
+
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(Rsql::extract(&req).await.unwrap(), Rsql(None));
+
+        let req = TestRequest::with_uri("/?filter=a%3D%3D").to_http_request();
+        let err = Rsql::extract(&req).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            err.to_string(),
+            "Invalid RSQL expression at position 3: expected argument."
+        );
+    }
+}
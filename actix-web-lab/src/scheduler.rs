@@ -0,0 +1,302 @@
+//! Periodic job scheduler tied to the server lifecycle.
+//!
+//! Jobs are registered on a [`Scheduler`] before the server is started. Calling
+//! [`start`](Scheduler::start) spawns each job on the current runtime and returns a
+//! [`SchedulerHandle`] which is used to stop the jobs gracefully when the server shuts down. This
+//! avoids ad-hoc `spawn`ed loops that outlive the server.
+//!
+//! # Examples
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use actix_web::{get, App, HttpServer, Responder};
+//! use actix_web_lab::{extract::SwapData, scheduler::Scheduler};
+//!
+//! #[get("/rates")]
+//! async fn get_rates(rates: SwapData<Vec<f64>>) -> impl Responder {
+//!     format!("{:?}", *rates.load())
+//! }
+//!
+//! #[actix_web::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let rates = SwapData::new(Vec::new());
+//!
+//!     let scheduler = Scheduler::new()
+//!         .interval(Duration::from_secs(60 * 60), || async {
+//!             // clean up expired sessions, etc.
+//!         })
+//!         .interval_publish(Duration::from_secs(60), rates.clone(), || async {
+//!             // fetch latest exchange rates
+//!             vec![1.0, 1.2]
+//!         })
+//!         .start();
+//!
+//!     let server = HttpServer::new(move || {
+//!         App::new().app_data(rates.clone()).service(get_rates)
+//!     })
+//!     .bind(("127.0.0.1", 8080))?
+//!     .run();
+//!
+//!     // stops scheduling jobs once the server has shut down
+//!     scheduler.run_until(server).await
+//! }
+//! ```
+
+use std::{fmt, future::Future, rc::Rc, time::Duration};
+
+use actix_web::rt::{self, task::JoinHandle};
+use futures_core::future::LocalBoxFuture;
+use tokio::sync::watch;
+use tracing::trace;
+
+use crate::extract::SwapData;
+
+type JobFn = Rc<dyn Fn() -> LocalBoxFuture<'static, ()>>;
+
+/// Schedule describing when a job should run.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Schedule {
+    /// Run once when the scheduler starts and then every period thereafter.
+    Interval(Duration),
+
+    /// Run according to a cron expression.
+    #[cfg(feature = "cron")]
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// Constructs an interval schedule.
+    pub fn interval(period: Duration) -> Self {
+        Self::Interval(period)
+    }
+
+    /// Constructs a schedule from a cron expression.
+    ///
+    /// See the [`cron` crate docs](https://docs.rs/cron) for supported syntax.
+    #[cfg(feature = "cron")]
+    pub fn cron(expr: &str) -> Result<Self, cron::error::Error> {
+        expr.parse().map(|schedule| Self::Cron(Box::new(schedule)))
+    }
+
+    /// Returns time to wait until the next run or `None` if the schedule is exhausted.
+    fn next_delay(&self, is_first: bool) -> Option<Duration> {
+        match self {
+            Schedule::Interval(_) if is_first => Some(Duration::ZERO),
+            Schedule::Interval(period) => Some(*period),
+
+            #[cfg(feature = "cron")]
+            Schedule::Cron(schedule) => {
+                let now = chrono::Utc::now();
+                let next = schedule.after(&now).next()?;
+                Some((next - now).to_std().unwrap_or(Duration::ZERO))
+            }
+        }
+    }
+}
+
+/// Builder for a set of scheduled jobs.
+///
+/// See [module docs](self) for usage.
+#[must_use]
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<(Schedule, JobFn)>,
+}
+
+impl Scheduler {
+    /// Constructs a new scheduler with no registered jobs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an async job to be run according to `schedule`.
+    pub fn job<F, Fut>(mut self, schedule: Schedule, job: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let job: JobFn = Rc::new(move || -> LocalBoxFuture<'static, ()> { Box::pin(job()) });
+        self.jobs.push((schedule, job));
+        self
+    }
+
+    /// Registers an async job to be run immediately and then every `period`.
+    pub fn interval<F, Fut>(self, period: Duration, job: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.job(Schedule::interval(period), job)
+    }
+
+    /// Registers an async job whose output is stored in `data` after each run.
+    ///
+    /// Handlers can read the most recent result using the [`SwapData`] extractor.
+    pub fn job_publish<T, F, Fut>(self, schedule: Schedule, data: SwapData<T>, job: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = T> + 'static,
+    {
+        let job = Rc::new(job);
+
+        self.job(schedule, move || {
+            let job = Rc::clone(&job);
+            let data = data.clone();
+
+            async move { data.store(job().await) }
+        })
+    }
+
+    /// Registers an async job, run immediately and then every `period`, whose output is stored in
+    /// `data` after each run.
+    pub fn interval_publish<T, F, Fut>(self, period: Duration, data: SwapData<T>, job: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = T> + 'static,
+    {
+        self.job_publish(Schedule::interval(period), data, job)
+    }
+
+    /// Spawns all registered jobs on the current runtime.
+    ///
+    /// # Panics
+    /// Panics if called outside of an Actix (or Tokio `LocalSet`) runtime context.
+    pub fn start(self) -> SchedulerHandle {
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        let tasks = self
+            .jobs
+            .into_iter()
+            .map(|(schedule, job)| rt::spawn(run_job(schedule, job, shutdown_rx.clone())))
+            .collect();
+
+        SchedulerHandle { shutdown_tx, tasks }
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("jobs", &self.jobs.len())
+            .finish()
+    }
+}
+
+async fn run_job(schedule: Schedule, job: JobFn, mut shutdown_rx: watch::Receiver<bool>) {
+    let mut is_first = true;
+
+    while let Some(delay) = schedule.next_delay(is_first) {
+        is_first = false;
+
+        tokio::select! {
+            _ = rt::time::sleep(delay) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        trace!("running scheduled job");
+
+        // in-flight runs are always completed; shutdown is only observed between runs
+        job().await;
+    }
+
+    trace!("scheduled job stopped");
+}
+
+/// Handle to a running set of jobs.
+///
+/// Dropping the handle without calling [`shutdown`](Self::shutdown) will also stop the jobs after
+/// any in-flight runs complete, but will not wait for them to do so.
+#[derive(Debug)]
+pub struct SchedulerHandle {
+    shutdown_tx: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    /// Stops scheduling new job runs and waits for any in-flight runs to complete.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(true);
+
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+
+    /// Drives `fut` (typically an [`actix_web::dev::Server`]) to completion and then gracefully
+    /// shuts down the scheduled jobs.
+    pub async fn run_until<F: Future>(self, fut: F) -> F::Output {
+        let output = fut.await;
+        self.shutdown().await;
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn interval_jobs_run_until_shutdown() {
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let handle = Scheduler::new()
+            .interval(Duration::from_millis(5), {
+                let runs = Arc::clone(&runs);
+                move || {
+                    let runs = Arc::clone(&runs);
+                    async move {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            })
+            .start();
+
+        rt::time::sleep(Duration::from_millis(40)).await;
+        handle.shutdown().await;
+
+        let runs_at_shutdown = runs.load(Ordering::SeqCst);
+        assert!(runs_at_shutdown > 1);
+
+        rt::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), runs_at_shutdown);
+    }
+
+    #[actix_web::test]
+    async fn publishes_job_output() {
+        let data = SwapData::new(0_u32);
+        let counter = Rc::new(Cell::new(0_u32));
+
+        let handle = Scheduler::new()
+            .interval_publish(Duration::from_millis(5), data.clone(), move || {
+                counter.set(counter.get() + 1);
+                let count = counter.get();
+                async move { count }
+            })
+            .start();
+
+        rt::time::sleep(Duration::from_millis(30)).await;
+        handle.shutdown().await;
+
+        assert!(**data.load() > 1);
+    }
+
+    #[actix_web::test]
+    async fn run_until_returns_future_output() {
+        let handle = Scheduler::new()
+            .interval(Duration::from_secs(60), || async {})
+            .start();
+
+        assert_eq!(handle.run_until(async { 42 }).await, 42);
+    }
+}
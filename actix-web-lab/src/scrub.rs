@@ -0,0 +1,386 @@
+//! Scrubbing of sensitive values before they are logged.
+//!
+//! See [`Scrubber`] docs.
+
+use std::{borrow::Cow, fmt, fmt::Write as _, sync::Arc};
+
+use actix_web::{
+    dev::ServiceRequest,
+    http::{
+        header::{self, HeaderMap, HeaderName},
+        Uri,
+    },
+    HttpMessage as _,
+};
+use hmac::{Mac as _, SimpleHmac};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::{
+    redact_json::visit_path,
+    uri::{decode_query_component, encode, EncodeSet},
+};
+
+/// A policy for transforming sensitive values before they are logged.
+///
+/// Implemented by [`Mask`], [`Hash`], and closures taking and returning strings.
+pub trait ScrubPolicy: Send + Sync {
+    /// Returns the value to log in place of the sensitive `value`.
+    fn scrub(&self, value: &str) -> String;
+}
+
+impl<F> ScrubPolicy for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn scrub(&self, value: &str) -> String {
+        (self)(value)
+    }
+}
+
+/// Scrub policy that replaces sensitive values with a fixed string.
+///
+/// The default replacement is `[REDACTED]`.
+#[derive(Debug, Clone)]
+pub struct Mask {
+    replacement: Cow<'static, str>,
+}
+
+impl Mask {
+    /// Constructs a new mask policy that replaces values with `replacement`.
+    pub fn new(replacement: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            replacement: replacement.into(),
+        }
+    }
+}
+
+impl Default for Mask {
+    fn default() -> Self {
+        Self::new("[REDACTED]")
+    }
+}
+
+impl ScrubPolicy for Mask {
+    fn scrub(&self, _value: &str) -> String {
+        self.replacement.clone().into_owned()
+    }
+}
+
+/// Scrub policy that replaces sensitive values with a keyed hash.
+///
+/// Values are replaced with a truncated, hex-encoded HMAC-SHA256 of the value, prefixed with
+/// `hmac:`. Equal values produce equal hashes, so occurrences of the same token or user can be
+/// correlated across log lines without logging the value itself. The key should be kept secret;
+/// without it, low-entropy values such as phone numbers could be recovered by brute force.
+#[derive(Clone)]
+pub struct Hash {
+    key: Arc<[u8]>,
+}
+
+impl Hash {
+    /// Constructs a new hash policy using `key`.
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: Arc::from(key.as_ref()),
+        }
+    }
+}
+
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hash").field("key", &"[REDACTED]").finish()
+    }
+}
+
+impl ScrubPolicy for Hash {
+    fn scrub(&self, value: &str) -> String {
+        let mut mac =
+            SimpleHmac::<Sha256>::new_from_slice(&self.key).expect("HMAC can take key of any size");
+        mac.update(value.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let mut hash = String::from("hmac:");
+
+        for byte in &digest[..8] {
+            let _ = write!(hash, "{byte:02x}");
+        }
+
+        hash
+    }
+}
+
+/// Scrubs sensitive headers, query parameters, and JSON body fields before they are logged.
+///
+/// By default, the `Authorization`, `Proxy-Authorization`, `Cookie`, and `Set-Cookie` headers are
+/// scrubbed using the [`Mask`] policy. Additional headers, query parameters, and body fields
+/// (using the key path syntax of [`RedactJson`](crate::middleware::RedactJson)) can be added, and
+/// the policy can be replaced.
+///
+/// Register a scrubber on the [`RequestSpan`](crate::middleware::RequestSpan) middleware to scrub
+/// query parameters in the request target recorded on root spans, or use it directly when writing
+/// audit logs.
+///
+/// # Examples
+/// ```
+/// use actix_web::http::{header, Uri};
+/// use actix_web_lab::util::scrub::{Hash, Scrubber};
+///
+/// let scrubber = Scrubber::new()
+///     .header(header::HeaderName::from_static("x-api-key"))
+///     .query_param("token")
+///     .body_field("user.password")
+///     .policy(Hash::new(b"log-correlation-key"));
+///
+/// let uri = Uri::from_static("/reset?token=s3cr3t&lang=en");
+/// assert!(!scrubber.scrub_uri(&uri).contains("s3cr3t"));
+/// ```
+#[derive(Clone)]
+pub struct Scrubber {
+    headers: Vec<HeaderName>,
+    query_params: Vec<String>,
+    body_fields: Vec<Vec<String>>,
+    policy: Arc<dyn ScrubPolicy>,
+}
+
+impl Scrubber {
+    /// Constructs a new scrubber for the default set of credential-bearing headers.
+    pub fn new() -> Self {
+        Self {
+            headers: vec![
+                header::AUTHORIZATION,
+                header::PROXY_AUTHORIZATION,
+                header::COOKIE,
+                header::SET_COOKIE,
+            ],
+            query_params: Vec::new(),
+            body_fields: Vec::new(),
+            policy: Arc::new(Mask::default()),
+        }
+    }
+
+    /// Adds a header whose values should be scrubbed.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        self.headers.push(name);
+        self
+    }
+
+    /// Adds a query parameter whose values should be scrubbed.
+    pub fn query_param(mut self, name: impl Into<String>) -> Self {
+        self.query_params.push(name.into());
+        self
+    }
+
+    /// Adds a dot-separated key path of JSON body fields whose values should be scrubbed.
+    pub fn body_field(mut self, path: impl AsRef<str>) -> Self {
+        let path = path.as_ref().split('.').map(str::to_owned).collect();
+        self.body_fields.push(path);
+        self
+    }
+
+    /// Sets the policy used to transform sensitive values.
+    ///
+    /// Defaults to [`Mask`].
+    pub fn policy(mut self, policy: impl ScrubPolicy + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Returns true if values of header `name` are scrubbed.
+    pub fn is_sensitive_header(&self, name: &HeaderName) -> bool {
+        self.headers.contains(name)
+    }
+
+    /// Returns all headers as strings, with sensitive values scrubbed.
+    ///
+    /// Header values which are not valid UTF-8 are lossily converted.
+    pub fn scrub_headers(&self, headers: &HeaderMap) -> Vec<(HeaderName, String)> {
+        headers
+            .iter()
+            .map(|(name, val)| {
+                let val = String::from_utf8_lossy(val.as_bytes());
+
+                let val = if self.is_sensitive_header(name) {
+                    self.policy.scrub(&val)
+                } else {
+                    val.into_owned()
+                };
+
+                (name.clone(), val)
+            })
+            .collect()
+    }
+
+    /// Returns query string with values of sensitive parameters scrubbed.
+    ///
+    /// Other parameters are left as-is, including their encoding and order.
+    pub fn scrub_query<'a>(&self, query: &'a str) -> Cow<'a, str> {
+        if self.query_params.is_empty() {
+            return Cow::Borrowed(query);
+        }
+
+        let mut scrubbed = String::with_capacity(query.len());
+        let mut changed = false;
+
+        for (idx, pair) in query.split('&').enumerate() {
+            if idx > 0 {
+                scrubbed.push('&');
+            }
+
+            let (key, val) = pair.split_once('=').unwrap_or((pair, ""));
+            let name = decode_query_component(key).unwrap_or(Cow::Borrowed(key));
+
+            if self.query_params.iter().any(|param| *param == name) {
+                let val = decode_query_component(val).unwrap_or(Cow::Borrowed(val));
+                let val = self.policy.scrub(&val);
+
+                scrubbed.push_str(key);
+                scrubbed.push('=');
+                scrubbed.push_str(&encode(&val, EncodeSet::QueryComponent));
+                changed = true;
+            } else {
+                scrubbed.push_str(pair);
+            }
+        }
+
+        if changed {
+            Cow::Owned(scrubbed)
+        } else {
+            Cow::Borrowed(query)
+        }
+    }
+
+    /// Returns path and query of `uri`, with values of sensitive query parameters scrubbed.
+    pub fn scrub_uri(&self, uri: &Uri) -> String {
+        match uri.query() {
+            Some(query) => format!("{}?{}", uri.path(), self.scrub_query(query)),
+            None => uri.path().to_owned(),
+        }
+    }
+
+    /// Scrubs sensitive fields in a JSON value, in place.
+    ///
+    /// Non-string values are converted to their JSON representation before being scrubbed.
+    pub fn scrub_json(&self, value: &mut Value) {
+        for path in &self.body_fields {
+            visit_path(value, path, &mut |val| {
+                let scrubbed = match &*val {
+                    Value::String(val) => self.policy.scrub(val),
+                    val => self.policy.scrub(&val.to_string()),
+                };
+
+                *val = Value::String(scrubbed);
+            });
+        }
+    }
+}
+
+impl Default for Scrubber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Scrubber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scrubber")
+            .field("headers", &self.headers)
+            .field("query_params", &self.query_params)
+            .field("body_fields", &self.body_fields)
+            .field("policy", &"<policy>")
+            .finish()
+    }
+}
+
+/// Returns request target to record on root spans, scrubbed if a scrubber was registered on the
+/// `RequestSpan` middleware.
+#[doc(hidden)]
+pub fn span_target(req: &ServiceRequest) -> String {
+    match req.extensions().get::<Scrubber>() {
+        Some(scrubber) => scrubber.scrub_uri(req.uri()),
+        None => req.uri().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header::HeaderValue, test::TestRequest};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn scrubs_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer abc"),
+        );
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+
+        let scrubbed = Scrubber::new().scrub_headers(&headers);
+        assert!(scrubbed.contains(&(header::AUTHORIZATION, "[REDACTED]".to_owned())));
+        assert!(scrubbed.contains(&(header::ACCEPT, "*/*".to_owned())));
+    }
+
+    #[test]
+    fn scrubs_query() {
+        let scrubber = Scrubber::new()
+            .query_param("token")
+            .policy(Mask::new("x y"));
+
+        assert_eq!(scrubber.scrub_query("a=1&b"), "a=1&b");
+        assert_eq!(
+            scrubber.scrub_query("token=abc&a=%20&tok%65n=def"),
+            "token=x%20y&a=%20&tok%65n=x%20y",
+        );
+
+        let uri = Uri::from_static("/reset?token=abc");
+        assert_eq!(scrubber.scrub_uri(&uri), "/reset?token=x%20y");
+    }
+
+    #[test]
+    fn scrubs_json() {
+        let scrubber = Scrubber::new()
+            .body_field("password")
+            .body_field("cards.number")
+            .policy(|val: &str| format!("<{} chars>", val.len()));
+
+        let mut value = json!({
+            "user": "ana",
+            "password": "hunter2",
+            "cards": [{ "number": 4111, "expiry": "01/30" }],
+        });
+        scrubber.scrub_json(&mut value);
+
+        assert_eq!(
+            value,
+            json!({
+                "user": "ana",
+                "password": "<7 chars>",
+                "cards": [{ "number": "<4 chars>", "expiry": "01/30" }],
+            }),
+        );
+    }
+
+    #[test]
+    fn hash_policy() {
+        let hash = Hash::new("key");
+        assert_eq!(hash.scrub("ana"), hash.scrub("ana"));
+        assert_ne!(hash.scrub("ana"), hash.scrub("bo"));
+        assert_ne!(hash.scrub("ana"), Hash::new("other key").scrub("ana"));
+        assert!(hash.scrub("ana").starts_with("hmac:"));
+        assert_eq!(hash.scrub("ana").len(), 21);
+        assert_eq!(format!("{hash:?}"), r#"Hash { key: "[REDACTED]" }"#);
+    }
+
+    #[test]
+    fn span_target_uses_registered_scrubber() {
+        let req = TestRequest::with_uri("/?token=abc").to_srv_request();
+        assert_eq!(span_target(&req), "/?token=abc");
+
+        req.extensions_mut()
+            .insert(Scrubber::new().query_param("token"));
+        assert_eq!(span_target(&req), "/?token=%5BREDACTED%5D");
+    }
+}
@@ -0,0 +1,347 @@
+//! Lightweight per-worker request counters.
+//!
+//! See [`ServerStats`] docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::StatusCode,
+    Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use futures_core::future::LocalBoxFuture;
+use tracing::debug;
+
+use crate::error::LabError;
+
+#[derive(Debug, Default)]
+struct WorkerCounters {
+    accepted: AtomicU64,
+    in_flight: AtomicU64,
+    completed: [AtomicU64; 5],
+}
+
+impl WorkerCounters {
+    fn complete(&self, status: StatusCode) {
+        self.completed[status_class(status)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> WorkerStats {
+        WorkerStats {
+            accepted: self.accepted.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            completed: std::array::from_fn(|class| self.completed[class].load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Decrements in-flight count when dropped, including when the request is cancelled.
+struct InFlightGuard(Arc<WorkerCounters>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A middleware that counts requests per worker, and an extractor for reading the counts.
+///
+/// Each worker keeps counts of accepted requests, requests currently in flight, and completed
+/// requests grouped by response status class. Counters are plain atomics updated with relaxed
+/// ordering, so the overhead is minimal and reads are approximate while requests are being
+/// handled. This is intended for custom status pages rather than as a replacement for a metrics
+/// system.
+///
+/// The middleware should be constructed outside the `HttpServer` factory closure, so that all
+/// workers share it, and should wrap the app only once since each wrapped service is counted as a
+/// separate worker.
+///
+/// # Extractor
+/// Handlers behind the middleware can extract `ServerStats` to read the counts. If the middleware
+/// is not registered, extraction fails with a [`LabError::MiddlewareNotRegistered`] error.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpServer};
+/// use actix_web_lab::middleware::ServerStats;
+///
+/// async fn status(stats: ServerStats) -> String {
+///     let total = stats.total();
+///
+///     format!(
+///         "{} workers, {} requests accepted, {} in flight",
+///         stats.workers().len(),
+///         total.accepted(),
+///         total.in_flight(),
+///     )
+/// }
+///
+/// # fn run() -> std::io::Result<()> {
+/// let stats = ServerStats::new();
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .wrap(stats.clone())
+///         .route("/status", web::get().to(status))
+/// })
+/// # ; Ok(()) }
+/// ```
+#[derive(Clone, Default)]
+pub struct ServerStats {
+    workers: Arc<Mutex<Vec<Arc<WorkerCounters>>>>,
+}
+
+impl ServerStats {
+    /// Constructs new request counting middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns current counts for each worker, in the order the workers started.
+    pub fn workers(&self) -> Vec<WorkerStats> {
+        self.workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|counters| counters.snapshot())
+            .collect()
+    }
+
+    /// Returns current counts summed across all workers.
+    pub fn total(&self) -> WorkerStats {
+        self.workers()
+            .into_iter()
+            .fold(WorkerStats::default(), |total, worker| WorkerStats {
+                accepted: total.accepted + worker.accepted,
+                in_flight: total.in_flight + worker.in_flight,
+                completed: std::array::from_fn(|class| {
+                    total.completed[class] + worker.completed[class]
+                }),
+            })
+    }
+}
+
+impl fmt::Debug for ServerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerStats")
+            .field("workers", &self.workers())
+            .finish()
+    }
+}
+
+impl FromRequest for ServerStats {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<ServerStats>()
+                .cloned()
+                .ok_or_else(|| {
+                    debug!(
+                        "Failed to extract `ServerStats` for `{}` handler. For the ServerStats \
+                        extractor to work correctly, wrap the app with the `ServerStats` \
+                        middleware.",
+                        req.match_name().unwrap_or_else(|| req.path())
+                    );
+
+                    LabError::MiddlewareNotRegistered {
+                        middleware: "ServerStats",
+                    }
+                    .into()
+                }),
+        )
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ServerStats
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ServerStatsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let counters = Arc::new(WorkerCounters::default());
+        self.workers.lock().unwrap().push(Arc::clone(&counters));
+
+        ready(Ok(ServerStatsMiddleware {
+            service: Rc::new(service),
+            stats: self.clone(),
+            counters,
+        }))
+    }
+}
+
+/// Service for the [`ServerStats`] middleware.
+pub struct ServerStatsMiddleware<S> {
+    service: Rc<S>,
+    stats: ServerStats,
+    counters: Arc<WorkerCounters>,
+}
+
+impl<S, B> Service<ServiceRequest> for ServerStatsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        self.counters.accepted.fetch_add(1, Ordering::Relaxed);
+        self.counters.in_flight.fetch_add(1, Ordering::Relaxed);
+        let guard = InFlightGuard(Arc::clone(&self.counters));
+
+        req.extensions_mut().insert(self.stats.clone());
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+            guard.0.complete(status);
+
+            res
+        })
+    }
+}
+
+/// Snapshot of request counts, for a single worker or summed across workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    accepted: u64,
+    in_flight: u64,
+    completed: [u64; 5],
+}
+
+impl WorkerStats {
+    /// Returns number of requests accepted.
+    pub fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    /// Returns number of requests currently being handled.
+    pub fn in_flight(&self) -> u64 {
+        self.in_flight
+    }
+
+    /// Returns number of requests completed.
+    pub fn completed(&self) -> u64 {
+        self.completed.iter().sum()
+    }
+
+    /// Returns number of requests completed with a response in the same status class as `status`.
+    ///
+    /// For example, passing [`StatusCode::NOT_FOUND`] returns the number of 4xx responses.
+    pub fn completed_with(&self, status: StatusCode) -> u64 {
+        self.completed[status_class(status)]
+    }
+}
+
+/// Returns index of status code's class, from 0 for 1xx to 4 for 5xx.
+fn status_class(status: StatusCode) -> usize {
+    usize::from(status.as_u16() / 100).clamp(1, 5) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        error,
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn counts_requests() {
+        let stats = ServerStats::new();
+
+        let app = test::init_service(
+            App::new()
+                .wrap(stats.clone())
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/err",
+                    web::get().to(|| async { Err::<HttpResponse, _>(error::ErrorBadGateway("")) }),
+                )
+                .route(
+                    "/stats",
+                    web::get().to(|stats: ServerStats| async move {
+                        stats.total().in_flight().to_string()
+                    }),
+                ),
+        )
+        .await;
+
+        for uri in ["/", "/", "/missing", "/err"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let req = TestRequest::with_uri("/stats").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "1");
+
+        assert_eq!(stats.workers().len(), 1);
+
+        let total = stats.total();
+        assert_eq!(total, stats.workers()[0]);
+        assert_eq!(total.accepted(), 5);
+        assert_eq!(total.in_flight(), 0);
+        assert_eq!(total.completed(), 5);
+        assert_eq!(total.completed_with(StatusCode::OK), 3);
+        assert_eq!(total.completed_with(StatusCode::NOT_FOUND), 1);
+        assert_eq!(total.completed_with(StatusCode::BAD_GATEWAY), 1);
+    }
+
+    #[actix_web::test]
+    async fn sums_workers() {
+        let stats = ServerStats::new();
+
+        for _ in 0..2 {
+            let app = test::init_service(
+                App::new()
+                    .wrap(stats.clone())
+                    .route("/", web::get().to(HttpResponse::Ok)),
+            )
+            .await;
+
+            test::call_service(&app, TestRequest::default().to_request()).await;
+        }
+
+        assert_eq!(stats.workers().len(), 2);
+        assert_eq!(stats.total().accepted(), 2);
+        assert_eq!(stats.total().completed_with(StatusCode::OK), 2);
+    }
+
+    #[actix_web::test]
+    async fn extractor_without_middleware() {
+        let req = TestRequest::default().to_http_request();
+        let err = ServerStats::extract(&req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
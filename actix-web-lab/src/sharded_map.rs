@@ -0,0 +1,454 @@
+//! Sharded, size-bounded map with expiry.
+//!
+//! See [`ShardedMap`] docs.
+
+use std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    fmt,
+    hash::{BuildHasher as _, Hash, Hasher as _},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use ahash::{AHashMap, RandomState};
+
+use crate::clock::{Clock, SystemClock};
+
+/// Default number of shards.
+const DEFAULT_SHARDS: usize = 16;
+
+/// Concurrent in-memory map with per-entry time-to-live and least-recently-used eviction.
+///
+/// Entries are spread across a number of independently locked shards to reduce contention when
+/// the map is shared between workers. Each shard holds an equal share of the total capacity; when
+/// a shard is full, its least recently used entry is evicted to make room for new ones. Expired
+/// entries are removed lazily when accessed or, in bulk, by [`purge_expired()`](Self::purge_expired).
+///
+/// This is a suitable backend for middleware that need to keep a bounded amount of per-client
+/// state, like rate limiters, idempotency key stores, and caches. It is cheap to clone; all clones
+/// share the same underlying storage.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web_lab::util::ShardedMap;
+///
+/// let hits = ShardedMap::<String, u32>::new(10_000).default_ttl(Duration::from_secs(60));
+///
+/// let count = hits.update("203.0.113.7".to_owned(), || 0, |count| {
+///     *count += 1;
+///     *count
+/// });
+///
+/// assert_eq!(count, 1);
+/// assert_eq!(hits.get("203.0.113.7"), Some(1));
+/// ```
+pub struct ShardedMap<K, V> {
+    shards: Arc<[Mutex<Shard<K, V>>]>,
+    hasher: RandomState,
+    shard_capacity: usize,
+    default_ttl: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K, V> ShardedMap<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Constructs a new map which holds at most (approximately) `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_shards(capacity, DEFAULT_SHARDS)
+    }
+
+    /// Constructs a new map which holds at most (approximately) `capacity` entries, split across
+    /// `shards` shards.
+    ///
+    /// # Panics
+    /// Panics if `shards` is zero.
+    pub fn with_shards(capacity: usize, shards: usize) -> Self {
+        assert!(shards > 0, "ShardedMap requires at least one shard");
+
+        Self {
+            shards: (0..shards).map(|_| Mutex::new(Shard::new())).collect(),
+            hasher: RandomState::new(),
+            shard_capacity: ((capacity + shards - 1) / shards).max(1),
+            default_ttl: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets time-to-live of entries inserted without an explicit TTL.
+    ///
+    /// By default, entries do not expire.
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the clock used to determine when entries expire.
+    ///
+    /// Defaults to [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    fn shard<Q>(&self, key: &Q) -> MutexGuard<'_, Shard<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+
+        let idx = (hasher.finish() % self.shards.len() as u64) as usize;
+        self.shards[idx].lock().unwrap()
+    }
+
+    /// Inserts an entry using the default TTL, returning the previous unexpired value, if any.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value, self.default_ttl)
+    }
+
+    /// Inserts an entry which expires after `ttl`, returning the previous unexpired value, if any.
+    pub fn insert_with_ttl(&self, key: K, value: V, ttl: Duration) -> Option<V> {
+        self.insert_entry(key, value, Some(ttl))
+    }
+
+    fn insert_entry(&self, key: K, value: V, ttl: Option<Duration>) -> Option<V> {
+        let now = self.clock.now();
+        let expires_at = ttl.map(|ttl| now + ttl);
+
+        self.shard(&key)
+            .insert(key, value, expires_at, now, self.shard_capacity)
+    }
+
+    /// Returns a clone of the value for `key`, if present and unexpired.
+    ///
+    /// Marks the entry as recently used.
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        let mut shard = self.shard(key);
+        shard.get_mut(key, self.clock.now()).cloned()
+    }
+
+    /// Returns true if an unexpired value for `key` is present.
+    ///
+    /// Does not affect the entry's recency.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+
+        self.shard(key)
+            .entries
+            .get(key)
+            .is_some_and(|entry| !entry.is_expired(now))
+    }
+
+    /// Atomically updates the value for `key` using `f`, first inserting the result of `init` using
+    /// the default TTL if no unexpired value is present.
+    ///
+    /// The shard containing `key` is locked while `init` and `f` are run, so they should be quick.
+    pub fn update<R>(&self, key: K, init: impl FnOnce() -> V, f: impl FnOnce(&mut V) -> R) -> R {
+        let now = self.clock.now();
+        let mut shard = self.shard(&key);
+
+        if let Some(value) = shard.get_mut(&key, now) {
+            return f(value);
+        }
+
+        let expires_at = self.default_ttl.map(|ttl| now + ttl);
+        let mut value = init();
+        let ret = f(&mut value);
+        shard.insert(key, value, expires_at, now, self.shard_capacity);
+
+        ret
+    }
+
+    /// Removes the entry for `key`, returning its value if it was present and unexpired.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+
+        self.shard(key)
+            .remove(key)
+            .filter(|entry| !entry.is_expired(now))
+            .map(|entry| entry.value)
+    }
+
+    /// Removes all expired entries, returning the number removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = self.clock.now();
+
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().purge_expired(now))
+            .sum()
+    }
+
+    /// Returns number of entries in the map, including any that have expired but not been removed.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().entries.len())
+            .sum()
+    }
+
+    /// Returns true if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock().unwrap();
+            shard.entries.clear();
+            shard.lru.clear();
+        }
+    }
+}
+
+impl<K, V> Clone for ShardedMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: Arc::clone(&self.shards),
+            hasher: self.hasher.clone(),
+            shard_capacity: self.shard_capacity,
+            default_ttl: self.default_ttl,
+            clock: Arc::clone(&self.clock),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for ShardedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedMap")
+            .field("shards", &self.shards.len())
+            .field("shard_capacity", &self.shard_capacity)
+            .field("default_ttl", &self.default_ttl)
+            .field("clock", &self.clock)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Entry<V> {
+    value: V,
+    expires_at: Option<Instant>,
+    last_used: u64,
+}
+
+impl<V> Entry<V> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+struct Shard<K, V> {
+    entries: AHashMap<K, Entry<V>>,
+
+    /// Keys ordered by last use, least recent first.
+    lru: BTreeMap<u64, K>,
+
+    /// Monotonic use counter.
+    tick: u64,
+}
+
+impl<K, V> Shard<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn new() -> Self {
+        Self {
+            entries: AHashMap::new(),
+            lru: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get_mut<Q>(&mut self, key: &Q, now: Instant) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.entries.get(key)?.is_expired(now) {
+            self.remove(key);
+            return None;
+        }
+
+        let tick = self.next_tick();
+        let entry = self.entries.get_mut(key)?;
+
+        let key = self.lru.remove(&entry.last_used)?;
+        self.lru.insert(tick, key);
+        entry.last_used = tick;
+
+        Some(&mut entry.value)
+    }
+
+    fn insert(
+        &mut self,
+        key: K,
+        value: V,
+        expires_at: Option<Instant>,
+        now: Instant,
+        capacity: usize,
+    ) -> Option<V> {
+        let prev = self.remove(&key).filter(|entry| !entry.is_expired(now));
+
+        while self.entries.len() >= capacity {
+            let Some((_, lru_key)) = self.lru.pop_first() else {
+                break;
+            };
+
+            self.entries.remove(&lru_key);
+        }
+
+        let tick = self.next_tick();
+        self.lru.insert(tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                expires_at,
+                last_used: tick,
+            },
+        );
+
+        prev.map(|entry| entry.value)
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<Entry<V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.lru.remove(&entry.last_used);
+        Some(entry)
+    }
+
+    fn purge_expired(&mut self, now: Instant) -> usize {
+        let before = self.entries.len();
+
+        let lru = &mut self.lru;
+        self.entries.retain(|_, entry| {
+            let expired = entry.is_expired(now);
+
+            if expired {
+                lru.remove(&entry.last_used);
+            }
+
+            !expired
+        });
+
+        before - self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::MockClock;
+
+    static_assertions::assert_impl_all!(ShardedMap<String, u32>: Send, Sync, Clone);
+
+    #[test]
+    fn insert_get_remove() {
+        let map = ShardedMap::<String, u32>::new(16);
+        assert!(map.is_empty());
+
+        assert_eq!(map.insert("a".to_owned(), 1), None);
+        assert_eq!(map.insert("a".to_owned(), 2), Some(1));
+        assert_eq!(map.get("a"), Some(2));
+        assert!(map.contains_key("a"));
+        assert_eq!(map.len(), 1);
+
+        assert_eq!(map.remove("a"), Some(2));
+        assert_eq!(map.get("a"), None);
+    }
+
+    #[test]
+    fn expired_entries_are_hidden() {
+        let clock = MockClock::new();
+        let map = ShardedMap::<&str, u32>::new(16).clock(clock.clone());
+
+        map.insert_with_ttl("a", 1, Duration::from_secs(1));
+        map.insert_with_ttl("b", 2, Duration::from_secs(1));
+        map.insert("c", 3);
+        clock.advance(Duration::from_secs(1));
+
+        assert_eq!(map.get("a"), None);
+        assert!(!map.contains_key("b"));
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.purge_expired(), 1);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn entries_expire_with_clock() {
+        let clock = MockClock::new();
+        let map = ShardedMap::<&str, u32>::new(16)
+            .default_ttl(Duration::from_secs(10))
+            .clock(clock.clone());
+
+        map.insert("a", 1);
+        map.insert_with_ttl("b", 2, Duration::from_secs(20));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.get("b"), Some(2));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(map.get("b"), None);
+    }
+
+    #[test]
+    fn least_recently_used_evicted() {
+        let map = ShardedMap::<&str, u32>::with_shards(2, 1);
+
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        // mark "a" as recently used
+        map.get("a");
+
+        map.insert("c", 3);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(1));
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.get("c"), Some(3));
+    }
+
+    #[test]
+    fn update_initializes_and_modifies() {
+        let map = ShardedMap::<&str, u32>::new(16);
+
+        let incr = |count: &mut u32| {
+            *count += 1;
+            *count
+        };
+
+        assert_eq!(map.update("a", || 10, incr), 11);
+        assert_eq!(map.update("a", || 10, incr), 12);
+        assert_eq!(map.get("a"), Some(12));
+    }
+}
@@ -0,0 +1,446 @@
+//! Built-in request signature schemes.
+//!
+//! See [`Ed25519Scheme`] and [`HmacSha256Scheme`] docs.
+
+use actix_web::{
+    http::{header::HeaderName, StatusCode},
+    web::{self, Bytes},
+    HttpRequest, ResponseError,
+};
+use async_trait::async_trait;
+#[cfg(feature = "ed25519")]
+use bytes::BytesMut;
+use derive_more::{Display, Error};
+#[cfg(feature = "ed25519")]
+use ed25519_dalek::{Signature, VerifyingKey};
+#[cfg(feature = "hmac-sha256")]
+use hmac::{digest::CtOutput, Mac as _, SimpleHmac};
+#[cfg(feature = "hmac-sha256")]
+use sha2::Sha256;
+use tracing::debug;
+
+use crate::extract::RequestSignatureScheme;
+
+/// Errors that can occur when verifying requests using the built-in signature schemes.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum SignatureSchemeError {
+    /// Request has no signature header, or is missing another header the scheme requires.
+    #[display(fmt = "Signature is missing.")]
+    Missing,
+
+    /// Signature header could not be decoded.
+    #[display(fmt = "Signature is malformed.")]
+    Malformed,
+
+    /// Signature does not match request.
+    #[display(fmt = "Signature is invalid.")]
+    Invalid,
+
+    /// Scheme's configuration is not registered as app data.
+    #[display(fmt = "Signature scheme is not configured.")]
+    NotConfigured,
+}
+
+impl ResponseError for SignatureSchemeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Missing | Self::Invalid => StatusCode::UNAUTHORIZED,
+            Self::Malformed => StatusCode::BAD_REQUEST,
+            Self::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Returns config of type `T` registered as app data, either directly or wrapped in `Data`.
+fn config<'a, T: 'static>(req: &'a HttpRequest, name: &str) -> Result<&'a T, SignatureSchemeError> {
+    req.app_data::<T>()
+        .or_else(|| req.app_data::<web::Data<T>>().map(|data| data.get_ref()))
+        .ok_or_else(|| {
+            debug!(
+                "Failed to verify `{name}` signature for `{}` handler. For the scheme to work \
+                correctly, register its config as app data.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            SignatureSchemeError::NotConfigured
+        })
+}
+
+/// Decodes hex-encoded signature from header `name`, after stripping `prefix`.
+fn hex_signature(
+    req: &HttpRequest,
+    name: &HeaderName,
+    prefix: &str,
+) -> Result<Vec<u8>, SignatureSchemeError> {
+    let val = req
+        .headers()
+        .get(name)
+        .ok_or(SignatureSchemeError::Missing)?;
+
+    val.as_bytes()
+        .strip_prefix(prefix.as_bytes())
+        .and_then(|hex| hex::decode(hex).ok())
+        .ok_or(SignatureSchemeError::Malformed)
+}
+
+/// Signature scheme which verifies Ed25519 signatures of request bodies.
+///
+/// Signatures are read, hex-encoded, from the `X-Signature-Ed25519` header by default. When a
+/// [timestamp header](Ed25519Config::timestamp_header) is configured, its value is prepended to
+/// the body before verifying, as done by Discord's interaction webhooks.
+///
+/// Requires an [`Ed25519Config`] to be registered as app data, either directly or wrapped in
+/// [`Data`](web::Data).
+///
+/// Signatures are checked using [strict verification](VerifyingKey::verify_strict). Checking that
+/// timestamps are recent, to prevent replay attacks, is left to handlers.
+///
+/// # Errors
+/// Extracting a [`RequestSignature`](crate::extract::RequestSignature) using this scheme fails
+/// with a [`SignatureSchemeError`] if the signature is missing, malformed, or does not match.
+///
+/// # Examples
+/// ```
+/// use actix_web::{http::header::HeaderName, post, web::Bytes, App, Responder};
+/// use actix_web_lab::extract::{Ed25519Config, Ed25519Scheme, RequestSignature};
+///
+/// #[post("/webhook")]
+/// async fn webhook(body: RequestSignature<Bytes, Ed25519Scheme>) -> impl Responder {
+///     let (body, _sig) = body.into_parts();
+///     format!("verified {} bytes", body.len())
+/// }
+///
+/// # let public_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]).verifying_key();
+/// App::new()
+///     .app_data(
+///         Ed25519Config::new(public_key)
+///             .timestamp_header(HeaderName::from_static("x-signature-timestamp")),
+///     )
+///     .service(webhook)
+/// # ;
+/// ```
+#[cfg(feature = "ed25519")]
+#[derive(Debug)]
+pub struct Ed25519Scheme {
+    key: VerifyingKey,
+    candidate: Signature,
+    message: BytesMut,
+}
+
+#[cfg(feature = "ed25519")]
+#[async_trait(?Send)]
+impl RequestSignatureScheme for Ed25519Scheme {
+    type Signature = Signature;
+    type Error = SignatureSchemeError;
+
+    async fn init(req: &HttpRequest) -> Result<Self, Self::Error> {
+        let config = config::<Ed25519Config>(req, "Ed25519")?;
+
+        let candidate = hex_signature(req, &config.signature_header, "")?;
+        let candidate = Signature::from_slice(&candidate).map_err(|_| Self::Error::Malformed)?;
+
+        let mut message = BytesMut::new();
+
+        if let Some(name) = &config.timestamp_header {
+            let timestamp = req.headers().get(name).ok_or(Self::Error::Missing)?;
+            message.extend_from_slice(timestamp.as_bytes());
+        }
+
+        Ok(Self {
+            key: config.key,
+            candidate,
+            message,
+        })
+    }
+
+    async fn consume_chunk(&mut self, _req: &HttpRequest, chunk: Bytes) -> Result<(), Self::Error> {
+        self.message.extend_from_slice(&chunk);
+        Ok(())
+    }
+
+    async fn finalize(self, _req: &HttpRequest) -> Result<Self::Signature, Self::Error> {
+        self.key
+            .verify_strict(&self.message, &self.candidate)
+            .map_err(|_| Self::Error::Invalid)?;
+
+        Ok(self.candidate)
+    }
+}
+
+/// Configuration for the [`Ed25519Scheme`] signature scheme.
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone)]
+pub struct Ed25519Config {
+    key: VerifyingKey,
+    signature_header: HeaderName,
+    timestamp_header: Option<HeaderName>,
+}
+
+#[cfg(feature = "ed25519")]
+impl Ed25519Config {
+    /// Constructs new config which verifies signatures using the public `key`.
+    pub fn new(key: VerifyingKey) -> Self {
+        Self {
+            key,
+            signature_header: HeaderName::from_static("x-signature-ed25519"),
+            timestamp_header: None,
+        }
+    }
+
+    /// Sets header from which hex-encoded signatures are read.
+    ///
+    /// Defaults to `X-Signature-Ed25519`.
+    pub fn signature_header(mut self, name: HeaderName) -> Self {
+        self.signature_header = name;
+        self
+    }
+
+    /// Sets header whose value is prepended to the body before verifying.
+    ///
+    /// Requests without this header are rejected. Not set by default.
+    pub fn timestamp_header(mut self, name: HeaderName) -> Self {
+        self.timestamp_header = Some(name);
+        self
+    }
+}
+
+/// Signature scheme which verifies HMAC-SHA256 signatures of request bodies.
+///
+/// Signatures are read, hex-encoded and prefixed with `sha256=`, from the `X-Hub-Signature-256`
+/// header by default; this is the format used by GitHub's webhooks. The computed HMAC is compared
+/// with the signature in constant time.
+///
+/// Requires an [`HmacSha256Config`] to be registered as app data, either directly or wrapped in
+/// [`Data`](web::Data).
+///
+/// # Errors
+/// Extracting a [`RequestSignature`](crate::extract::RequestSignature) using this scheme fails
+/// with a [`SignatureSchemeError`] if the signature is missing, malformed, or does not match.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web::Bytes, App, Responder};
+/// use actix_web_lab::extract::{HmacSha256Config, HmacSha256Scheme, RequestSignature};
+///
+/// #[post("/webhook")]
+/// async fn webhook(body: RequestSignature<Bytes, HmacSha256Scheme>) -> impl Responder {
+///     let (body, _sig) = body.into_parts();
+///     format!("verified {} bytes", body.len())
+/// }
+///
+/// App::new()
+///     .app_data(HmacSha256Config::new("webhook secret"))
+///     .service(webhook)
+/// # ;
+/// ```
+#[cfg(feature = "hmac-sha256")]
+pub struct HmacSha256Scheme {
+    hmac: SimpleHmac<Sha256>,
+    candidate: [u8; 32],
+}
+
+#[cfg(feature = "hmac-sha256")]
+#[async_trait(?Send)]
+impl RequestSignatureScheme for HmacSha256Scheme {
+    type Signature = CtOutput<SimpleHmac<Sha256>>;
+    type Error = SignatureSchemeError;
+
+    async fn init(req: &HttpRequest) -> Result<Self, Self::Error> {
+        let config = config::<HmacSha256Config>(req, "HMAC-SHA256")?;
+
+        let candidate = hex_signature(req, &config.header, &config.prefix)?
+            .try_into()
+            .map_err(|_| Self::Error::Malformed)?;
+
+        let hmac = SimpleHmac::<Sha256>::new_from_slice(&config.key)
+            .expect("HMAC can take key of any size");
+
+        Ok(Self { hmac, candidate })
+    }
+
+    async fn consume_chunk(&mut self, _req: &HttpRequest, chunk: Bytes) -> Result<(), Self::Error> {
+        self.hmac.update(&chunk);
+        Ok(())
+    }
+
+    async fn finalize(self, _req: &HttpRequest) -> Result<Self::Signature, Self::Error> {
+        let signature = self.hmac.finalize();
+
+        if signature == CtOutput::new(self.candidate.into()) {
+            Ok(signature)
+        } else {
+            Err(Self::Error::Invalid)
+        }
+    }
+}
+
+/// Configuration for the [`HmacSha256Scheme`] signature scheme.
+#[cfg(feature = "hmac-sha256")]
+#[derive(Clone)]
+pub struct HmacSha256Config {
+    key: Vec<u8>,
+    header: HeaderName,
+    prefix: String,
+}
+
+#[cfg(feature = "hmac-sha256")]
+impl HmacSha256Config {
+    /// Constructs new config which verifies signatures using the shared secret `key`.
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            header: HeaderName::from_static("x-hub-signature-256"),
+            prefix: "sha256=".to_owned(),
+        }
+    }
+
+    /// Sets header from which signatures are read.
+    ///
+    /// Defaults to `X-Hub-Signature-256`.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        self.header = name;
+        self
+    }
+
+    /// Sets prefix which precedes hex-encoded signatures in the header.
+    ///
+    /// Defaults to `sha256=`. Use an empty prefix for bare signatures.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[cfg(feature = "hmac-sha256")]
+impl std::fmt::Debug for HmacSha256Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HmacSha256Config")
+            .field("key", &"..")
+            .field("header", &self.header)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        test::{self, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::extract::RequestSignature;
+
+    #[cfg(feature = "ed25519")]
+    #[actix_web::test]
+    async fn ed25519() {
+        use ed25519_dalek::{Signer as _, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(
+                    Ed25519Config::new(signing_key.verifying_key())
+                        .timestamp_header(HeaderName::from_static("x-signature-timestamp")),
+                )
+                .route(
+                    "/",
+                    web::post().to(|body: RequestSignature<Bytes, Ed25519Scheme>| async move {
+                        body.into_parts().0
+                    }),
+                ),
+        )
+        .await;
+
+        let sig = hex::encode(signing_key.sign(b"1700000000hello").to_bytes());
+
+        let req = TestRequest::post()
+            .insert_header(("x-signature-ed25519", sig.as_str()))
+            .insert_header(("x-signature-timestamp", "1700000000"))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "hello");
+
+        let req = TestRequest::post()
+            .insert_header(("x-signature-ed25519", sig.as_str()))
+            .insert_header(("x-signature-timestamp", "1700000001"))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::post()
+            .insert_header(("x-signature-ed25519", "zz"))
+            .insert_header(("x-signature-timestamp", "1700000000"))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post()
+            .insert_header(("x-signature-ed25519", sig.as_str()))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[cfg(feature = "hmac-sha256")]
+    #[actix_web::test]
+    async fn hmac_sha256() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(HmacSha256Config::new("secret")))
+                .route(
+                    "/",
+                    web::post().to(
+                        |body: RequestSignature<Bytes, HmacSha256Scheme>| async move {
+                            body.into_parts().0
+                        },
+                    ),
+                ),
+        )
+        .await;
+
+        let mut hmac = SimpleHmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        hmac.update(b"hello");
+        let sig = format!("sha256={}", hex::encode(hmac.finalize().into_bytes()));
+
+        let req = TestRequest::post()
+            .insert_header(("x-hub-signature-256", sig.as_str()))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .insert_header(("x-hub-signature-256", sig.as_str()))
+            .set_payload("hellO")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = TestRequest::post()
+            .insert_header(("x-hub-signature-256", "sha256=abcd"))
+            .set_payload("hello")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post().set_payload("hello").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn not_configured() {
+        let req = TestRequest::default().to_http_request();
+        let err = config::<()>(&req, "test").unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
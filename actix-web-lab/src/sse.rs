@@ -47,22 +47,35 @@ use std::{
 
 use actix_web::{
     body::{BodySize, BoxBody, MessageBody},
-    http::header::ContentEncoding,
+    http::header::{self, ContentEncoding},
     HttpRequest, HttpResponse, Responder,
 };
 use bytes::{BufMut as _, Bytes, BytesMut};
 use bytestring::ByteString;
 use futures_core::Stream;
+use futures_util::{stream, StreamExt as _};
 use pin_project_lite::pin_project;
 use serde::Serialize;
 use tokio::{
-    sync::mpsc,
+    sync::{broadcast, mpsc, watch},
     time::{interval, Interval},
 };
 use tokio_stream::wrappers::ReceiverStream;
 
+#[cfg(feature = "postgres")]
+pub use crate::sse_postgres::from_pg_notifications;
+#[cfg(feature = "fs-watch")]
+pub use crate::sse_watch_path::{watch_path, watch_path_with_debounce};
+
+/// Server-sent events client, built on `awc`.
+#[cfg(feature = "awc")]
+pub mod client {
+    pub use crate::sse_client::{connect, ClientError, Decoder, EventSource, EventStream};
+}
 use crate::{
+    bus::{recv_with_lag_policy, LagPolicy},
     header::{CacheControl, CacheDirective},
+    sse_compress::SseCompressor,
     util::InfallibleStream,
     BoxError,
 };
@@ -98,7 +111,7 @@ use crate::{
 /// # }; test();
 /// ```
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Data {
     id: Option<ByteString>,
     event: Option<ByteString>,
@@ -168,6 +181,21 @@ impl Data {
     pub fn set_event(&mut self, event: impl Into<ByteString>) {
         self.event = Some(event.into());
     }
+
+    /// Returns `data` field.
+    pub fn get_data(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns `id` field, if set.
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns `event` name field, if set.
+    pub fn get_event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
 }
 
 impl From<Data> for Event {
@@ -178,7 +206,7 @@ impl From<Data> for Event {
 
 /// Server-sent events message containing one or more fields.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Event {
     /// A `data` message with optional ID and event name.
     ///
@@ -207,7 +235,9 @@ pub enum Event {
 
 impl Event {
     /// Splits data into lines and prepend each line with `prefix`.
-    fn line_split_with_prefix(buf: &mut BytesMut, prefix: &'static str, data: ByteString) {
+    fn line_split_with_prefix(buf: &mut BytesMut, prefix: &'static str, data: impl AsRef<str>) {
+        let data = data.as_ref();
+
         // initial buffer size guess is len(data) + 10 lines of prefix + EOLs + EOF
         buf.reserve(data.len() + (10 * (prefix.len() + 1)) + 1);
 
@@ -219,10 +249,8 @@ impl Event {
         }
     }
 
-    /// Serializes message into event-stream format.
-    fn into_bytes(self) -> Bytes {
-        let mut buf = BytesMut::new();
-
+    /// Serializes message into event-stream format, appending it to `buf`.
+    fn write_to(&self, buf: &mut BytesMut) {
         match self {
             Event::Data(Data { id, event, data }) => {
                 if let Some(text) = id {
@@ -237,21 +265,26 @@ impl Event {
                     buf.put_u8(b'\n');
                 }
 
-                Self::line_split_with_prefix(&mut buf, "data: ", data);
+                Self::line_split_with_prefix(buf, "data: ", data);
             }
 
-            Event::Comment(text) => Self::line_split_with_prefix(&mut buf, ": ", text),
+            Event::Comment(text) => Self::line_split_with_prefix(buf, ": ", text),
         }
 
         // final newline to mark end of message
         buf.put_u8(b'\n');
+    }
 
-        buf.freeze()
+    /// Serializes message into event-stream format.
+    #[cfg(test)]
+    fn into_bytes(self) -> Bytes {
+        Encoder::new().encode(&self)
     }
 
     /// Serializes retry message into event-stream format.
+    #[cfg(test)]
     fn retry_to_bytes(retry: Duration) -> Bytes {
-        Bytes::from(format!("retry: {}\n\n", retry.as_millis()))
+        Encoder::new().encode_retry(retry)
     }
 
     /// Serializes a keep-alive event-stream comment message into bytes.
@@ -260,6 +293,72 @@ impl Event {
     }
 }
 
+/// Reusable server-sent events encoder.
+///
+/// Events are serialized into a single growable buffer and split off as [`Bytes`] chunks. Before
+/// each event is written, space for the largest event seen so far (or the initial capacity, if
+/// larger) is reserved. If all previously yielded chunks have been dropped (i.e., written to the
+/// socket) by then, this reclaims their memory instead of allocating.
+///
+/// [`Sse`] uses an encoder internally; this type is useful when writing events into custom body
+/// types.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::sse;
+///
+/// let mut encoder = sse::Encoder::new();
+///
+/// let chunk = encoder.encode(&sse::Data::new("foo").event("bar").into());
+/// assert_eq!(chunk, "event: bar\ndata: foo\n\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: BytesMut,
+
+    /// Space reserved before writing each event.
+    capacity: usize,
+}
+
+impl Encoder {
+    /// Constructs a new encoder with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a new encoder with a buffer pre-allocated to hold `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Serializes `event` into event-stream format.
+    pub fn encode(&mut self, event: &Event) -> Bytes {
+        self.buf.reserve(self.capacity);
+        event.write_to(&mut self.buf);
+        self.split()
+    }
+
+    /// Serializes a retry message into event-stream format.
+    pub fn encode_retry(&mut self, retry: Duration) -> Bytes {
+        use std::fmt::Write as _;
+
+        self.buf.reserve(self.capacity);
+
+        // writing to BytesMut is infallible
+        let _ = write!(self.buf, "retry: {}\n\n", retry.as_millis());
+        self.split()
+    }
+
+    /// Splits off written bytes, remembering their length so that it can be reserved next time.
+    fn split(&mut self) -> Bytes {
+        self.capacity = self.capacity.max(self.buf.len());
+        self.buf.split().freeze()
+    }
+}
+
 pin_project! {
     /// Server-sent events (`text/event-stream`) responder.
     ///
@@ -272,6 +371,10 @@ pin_project! {
         stream: S,
         keep_alive: Option<Interval>,
         retry_interval: Option<Duration>,
+        encoder: Encoder,
+        compress: bool,
+        compressor: Option<SseCompressor>,
+        ended: bool,
     }
 }
 
@@ -286,6 +389,10 @@ where
             stream,
             keep_alive: None,
             retry_interval: None,
+            encoder: Encoder::new(),
+            compress: false,
+            compressor: None,
+            ended: false,
         }
     }
 }
@@ -336,6 +443,16 @@ impl<S> Sse<S> {
         self.retry_interval = Some(retry);
         self
     }
+
+    /// Enables gzip or deflate compression of the event stream, if accepted by the client.
+    ///
+    /// The compressed stream is flushed after every message so that events are delivered without
+    /// delay. Since the response sets its own `Content-Encoding`, it will not be compressed again by
+    /// the `Compress` middleware.
+    pub fn with_compression(mut self) -> Self {
+        self.compress = true;
+        self
+    }
 }
 
 impl<S, E> Responder for Sse<S>
@@ -345,12 +462,24 @@ where
 {
     type Body = BoxBody;
 
-    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok()
-            .content_type(mime::TEXT_EVENT_STREAM)
-            .insert_header(ContentEncoding::Identity)
-            .insert_header(CacheControl(vec![CacheDirective::NoCache]))
-            .body(self)
+    fn respond_to(mut self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let mut res = HttpResponse::Ok();
+        res.content_type(mime::TEXT_EVENT_STREAM)
+            .insert_header(CacheControl(vec![CacheDirective::NoCache]));
+
+        if self.compress {
+            res.insert_header((header::VARY, "accept-encoding"));
+            self.compressor = SseCompressor::negotiate(req);
+        }
+
+        match &self.compressor {
+            Some(compressor) => {
+                res.insert_header((header::CONTENT_ENCODING, compressor.content_encoding()))
+            }
+            None => res.insert_header(ContentEncoding::Identity),
+        };
+
+        res.body(self)
     }
 }
 
@@ -371,22 +500,35 @@ where
     ) -> Poll<Option<Result<Bytes, Self::Error>>> {
         let this = self.project();
 
+        if *this.ended {
+            return Poll::Ready(None);
+        }
+
         if let Some(retry) = this.retry_interval.take() {
             cx.waker().wake_by_ref();
-            return Poll::Ready(Some(Ok(Event::retry_to_bytes(retry))));
+            let chunk = this.encoder.encode_retry(retry);
+            return Poll::Ready(Some(Ok(compress(this.compressor, chunk))));
         }
 
         if let Poll::Ready(msg) = this.stream.poll_next(cx) {
             return match msg {
-                Some(Ok(msg)) => Poll::Ready(Some(Ok(msg.into_bytes()))),
+                Some(Ok(msg)) => {
+                    let chunk = this.encoder.encode(&msg);
+                    Poll::Ready(Some(Ok(compress(this.compressor, chunk))))
+                }
                 Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
-                None => Poll::Ready(None),
+                None => {
+                    // emit the compressed stream's trailer, if any, before ending
+                    *this.ended = true;
+                    Poll::Ready(this.compressor.take().map(|comp| Ok(comp.finish())))
+                }
             };
         }
 
         if let Some(ref mut keep_alive) = this.keep_alive {
             if keep_alive.poll_tick(cx).is_ready() {
-                return Poll::Ready(Some(Ok(Event::keep_alive_bytes())));
+                let chunk = Event::keep_alive_bytes();
+                return Poll::Ready(Some(Ok(compress(this.compressor, chunk))));
             }
         }
 
@@ -394,6 +536,98 @@ where
     }
 }
 
+/// Compresses `chunk` if compression is enabled for the response.
+fn compress(compressor: &mut Option<SseCompressor>, chunk: Bytes) -> Bytes {
+    match compressor {
+        Some(compressor) => compressor.compress(&chunk),
+        None => chunk,
+    }
+}
+
+/// Creates an SSE responder from a Tokio broadcast channel receiver.
+///
+/// Each received message is converted to an event using `map`; this is where serialization
+/// should happen, e.g., using [`Data::new_json()`]. The event stream ends when all senders have
+/// been dropped.
+///
+/// If the receiver falls behind and misses messages, `lag_policy` determines whether the missed
+/// messages are skipped or the event stream is closed.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, Responder};
+/// use actix_web_lab::{bus::LagPolicy, sse};
+/// use tokio::sync::broadcast;
+///
+/// #[get("/ticks")]
+/// async fn ticks(tx: web::Data<broadcast::Sender<u64>>) -> impl Responder {
+///     sse::from_broadcast(tx.subscribe(), LagPolicy::Skip, |tick| {
+///         sse::Data::new(tick.to_string()).event("tick").into()
+///     })
+/// }
+/// ```
+pub fn from_broadcast<T, F>(
+    rx: broadcast::Receiver<T>,
+    lag_policy: LagPolicy,
+    map: F,
+) -> Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>
+where
+    T: Clone + 'static,
+    F: FnMut(T) -> Event + 'static,
+{
+    let stream = stream::unfold(rx, move |mut rx| async move {
+        let msg = recv_with_lag_policy(&mut rx, lag_policy).await?;
+        Some((msg, rx))
+    });
+
+    Sse::from_infallible_stream(stream.map(map))
+}
+
+/// Creates an SSE responder from a Tokio watch channel receiver.
+///
+/// The current value is sent immediately, followed by a new event each time the value changes.
+/// Intermediate values may be skipped if the value changes faster than the client reads events;
+/// only the latest value is ever sent. The event stream ends when the sender is dropped.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, Responder};
+/// use actix_web_lab::sse;
+/// use tokio::sync::watch;
+///
+/// #[derive(serde::Serialize)]
+/// struct Status {
+///     healthy: bool,
+/// }
+///
+/// #[get("/status")]
+/// async fn status(rx: web::Data<watch::Receiver<Status>>) -> impl Responder {
+///     sse::from_watch((**rx).clone(), |status| {
+///         sse::Data::new_json(status).unwrap().event("status").into()
+///     })
+/// }
+/// ```
+pub fn from_watch<T, F>(
+    rx: watch::Receiver<T>,
+    map: F,
+) -> Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>
+where
+    T: 'static,
+    F: FnMut(&T) -> Event + 'static,
+{
+    let stream = stream::unfold((rx, map, true), |(mut rx, mut map, is_first)| async move {
+        if !is_first {
+            // sender was dropped
+            rx.changed().await.ok()?;
+        }
+
+        let event = map(&rx.borrow_and_update());
+        Some((event, (rx, map, false)))
+    });
+
+    Sse::from_infallible_stream(stream)
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::Infallible;
@@ -428,6 +662,33 @@ mod tests {
         assert_eq!(buf, "data: foo\ndata: bar\n");
     }
 
+    #[test]
+    fn encoder_reuses_buffer() {
+        let mut encoder = Encoder::with_capacity(64);
+
+        let chunk = encoder.encode(&Event::Comment("foo".into()));
+        assert_eq!(chunk, ": foo\n\n");
+        let ptr = chunk.as_ptr();
+        drop(chunk);
+
+        // memory of the dropped chunk is reclaimed
+        let chunk = encoder.encode(&Data::new("bar").into());
+        assert_eq!(chunk, "data: bar\n\n");
+        assert_eq!(chunk.as_ptr(), ptr);
+
+        // memory of a chunk that is still held is not reused
+        let held = encoder.encode(&Data::new("baz").into());
+        assert_eq!(held, "data: baz\n\n");
+        let chunk = encoder.encode(&Data::new("qux").into());
+        assert_ne!(chunk.as_ptr(), held.as_ptr());
+        drop(chunk);
+
+        assert_eq!(
+            encoder.encode_retry(Duration::from_secs(1)),
+            "retry: 1000\n\n"
+        );
+    }
+
     #[test]
     fn into_bytes_format() {
         assert_eq!(Event::Comment("foo".into()).into_bytes(), ": foo\n\n");
@@ -528,6 +789,40 @@ mod tests {
         );
     }
 
+    #[actix_web::test]
+    async fn compressed_when_enabled_and_accepted() {
+        use std::io::Read as _;
+
+        let st = stream::iter([
+            Ok::<_, Infallible>(Event::Data(Data::new("foo"))),
+            Ok(Event::Data(Data::new("bar"))),
+        ]);
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_http_request();
+        let res = Sse::from_stream(st).with_compression().respond_to(&req);
+
+        assert_response_matches!(res, OK;
+            "content-encoding" => "gzip"
+            "vary" => "accept-encoding"
+        );
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "data: foo\n\ndata: bar\n\n");
+
+        // not compressed unless accepted
+        let st = stream::empty::<Result<_, Infallible>>();
+        let res = Sse::from_stream(st)
+            .with_compression()
+            .respond_to(&TestRequest::default().to_http_request());
+        assert_response_matches!(res, OK; "content-encoding" => "identity");
+    }
+
     #[actix_web::test]
     async fn messages_are_received_from_sender() {
         let (sender, receiver) = tokio::sync::mpsc::channel(2);
@@ -548,6 +843,67 @@ mod tests {
         }
     }
 
+    #[actix_web::test]
+    async fn from_broadcast_channel() {
+        let (tx, rx) = broadcast::channel(4);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+
+        let sse = from_broadcast(rx, LagPolicy::Skip, |n: u32| {
+            Data::new(n.to_string()).into()
+        });
+        assert_eq!(body::to_bytes(sse).await.unwrap(), "data: 1\n\ndata: 2\n\n",);
+
+        let (tx, rx) = broadcast::channel(1);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+
+        let sse = from_broadcast(rx, LagPolicy::Skip, |n: u32| {
+            Data::new(n.to_string()).into()
+        });
+        assert_eq!(body::to_bytes(sse).await.unwrap(), "data: 2\n\n");
+
+        let (tx, rx) = broadcast::channel(1);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+
+        let sse = from_broadcast(rx, LagPolicy::Close, |n: u32| {
+            Data::new(n.to_string()).into()
+        });
+        assert_eq!(body::to_bytes(sse).await.unwrap(), "");
+    }
+
+    #[actix_web::test]
+    async fn from_watch_channel() {
+        let (tx, rx) = watch::channel(1_u32);
+        drop(tx);
+
+        let sse = from_watch(rx, |n| Data::new(n.to_string()).into());
+        assert_eq!(body::to_bytes(sse).await.unwrap(), "data: 1\n\n");
+
+        let (tx, rx) = watch::channel(1_u32);
+        let mut sse = Box::pin(from_watch(rx, |n| Data::new(n.to_string()).into()));
+
+        match poll_fn(|cx| sse.as_mut().poll_next(cx)).now_or_never() {
+            Some(Some(Ok(bytes))) => assert_eq!(bytes, "data: 1\n\n"),
+            res => panic!("poll should return data message, got {res:?}"),
+        }
+
+        assert!(poll_fn(|cx| sse.as_mut().poll_next(cx))
+            .now_or_never()
+            .is_none());
+
+        tx.send(2).unwrap();
+
+        match poll_fn(|cx| sse.as_mut().poll_next(cx)).now_or_never() {
+            Some(Some(Ok(bytes))) => assert_eq!(bytes, "data: 2\n\n"),
+            res => panic!("poll should return data message, got {res:?}"),
+        }
+    }
+
     #[actix_web::test]
     async fn keep_alive_is_sent() {
         let waker = noop_waker();
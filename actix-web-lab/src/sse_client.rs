@@ -0,0 +1,542 @@
+//! Server-sent events client.
+//!
+//! See [`EventSource`] docs.
+
+use std::{
+    collections::VecDeque,
+    fmt, mem,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_web::{
+    error::PayloadError,
+    http::{header, StatusCode},
+};
+use bytes::{Bytes, BytesMut};
+use derive_more::{Display, Error};
+use futures_core::{stream::LocalBoxStream, Stream};
+use futures_util::{stream, StreamExt as _};
+
+use crate::sse::{Data, Event};
+
+/// Default delay before reconnecting, as suggested by the SSE spec.
+const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// Errors produced by an [`EventStream`].
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// Request could not be sent. The event source will reconnect, if enabled.
+    #[display(fmt = "Failed to connect to event source: {_0}")]
+    Connect(awc::error::SendRequestError),
+
+    /// Response body could not be read. The event source will reconnect, if enabled.
+    #[display(fmt = "Failed to read event stream: {_0}")]
+    Payload(PayloadError),
+
+    /// Server responded with a non-200 status code. The event source will not reconnect.
+    #[display(fmt = "Event source responded with status {_0}.")]
+    Status(#[error(not(source))] StatusCode),
+
+    /// Server responded with a content type other than `text/event-stream`. The event source will
+    /// not reconnect.
+    #[display(fmt = "Event source responded with an invalid content type.")]
+    ContentType,
+}
+
+/// Incremental parser for `text/event-stream` bodies.
+///
+/// Chunks can be split at arbitrary points. Only messages with data fields and comments are
+/// produced as events, following the [event stream interpretation rules]; the latest event ID and
+/// retry period are tracked separately.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::sse::{self, client::Decoder};
+///
+/// let mut decoder = Decoder::new();
+/// decoder.decode(b"id: 1\ndata: fo");
+/// assert!(decoder.next_event().is_none());
+///
+/// decoder.decode(b"o\n\n");
+/// let event = decoder.next_event().unwrap();
+/// assert_eq!(event, sse::Data::new("foo").id("1").into());
+/// assert_eq!(decoder.last_event_id(), Some("1"));
+/// ```
+///
+/// [event stream interpretation rules]: https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation
+#[derive(Debug, Default)]
+pub struct Decoder {
+    line: BytesMut,
+    skip_lf: bool,
+    bom_checked: bool,
+
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+
+    events: VecDeque<Event>,
+}
+
+impl Decoder {
+    /// Constructs a new decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a chunk of the event stream into the decoder.
+    pub fn decode(&mut self, mut chunk: &[u8]) {
+        if !self.bom_checked {
+            let bom = b"\xEF\xBB\xBF";
+            let pending = self.line.len() + chunk.len();
+
+            if pending < bom.len() && bom.starts_with(&[&self.line[..], chunk].concat()) {
+                // not enough bytes to know whether the stream starts with a BOM yet
+                self.line.extend_from_slice(chunk);
+                return;
+            }
+
+            self.bom_checked = true;
+
+            let buffered = mem::take(&mut self.line);
+            let mut start = [&buffered[..], chunk].concat();
+            if start.starts_with(bom) {
+                start.drain(..bom.len());
+            }
+
+            return self.decode_lines(&start);
+        }
+
+        if self.skip_lf {
+            self.skip_lf = false;
+
+            if let Some(rest) = chunk.strip_prefix(b"\n") {
+                chunk = rest;
+            }
+        }
+
+        self.decode_lines(chunk);
+    }
+
+    fn decode_lines(&mut self, mut chunk: &[u8]) {
+        while let Some(idx) = chunk.iter().position(|&b| b == b'\n' || b == b'\r') {
+            self.line.extend_from_slice(&chunk[..idx]);
+            let line = self.line.split().freeze();
+            self.process_line(&line);
+
+            if chunk[idx] == b'\r' {
+                match chunk.get(idx + 1) {
+                    Some(b'\n') => chunk = &chunk[idx + 2..],
+                    Some(_) => chunk = &chunk[idx + 1..],
+                    None => {
+                        // the LF of a CRLF pair may arrive in the next chunk
+                        self.skip_lf = true;
+                        return;
+                    }
+                }
+            } else {
+                chunk = &chunk[idx + 1..];
+            }
+        }
+
+        self.line.extend_from_slice(chunk);
+    }
+
+    fn process_line(&mut self, line: &Bytes) {
+        let line = String::from_utf8_lossy(line);
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        if let Some(comment) = line.strip_prefix(':') {
+            let comment = comment.strip_prefix(' ').unwrap_or(comment);
+            self.events.push_back(Event::Comment(comment.into()));
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (&*line, ""),
+        };
+
+        match field {
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "event" => self.event = Some(value.to_owned()),
+            "id" if !value.contains('\0') => {
+                self.id = Some(value.to_owned());
+                self.last_event_id = Some(value.to_owned());
+            }
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let event = self.event.take();
+        let id = self.id.take();
+
+        if self.data.is_empty() {
+            return;
+        }
+
+        let mut data = mem::take(&mut self.data);
+        data.pop(); // trailing line feed
+
+        let mut msg = Data::new(data);
+
+        if let Some(event) = event {
+            msg.set_event(event);
+        }
+
+        if let Some(id) = id {
+            msg.set_id(id);
+        }
+
+        self.events.push_back(msg.into());
+    }
+
+    /// Removes and returns the next complete event, if any.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Returns the most recently received event ID, if any.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Returns the most recently received reconnection time, if any.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+}
+
+/// A server-sent events client, built on `awc`.
+///
+/// Streams events from an SSE endpoint, reconnecting when the connection fails or is closed by
+/// the server. Reconnection requests include a `Last-Event-ID` header with the ID of the last
+/// received event and wait for the reconnection time most recently sent by the server (3 seconds
+/// by default). Responses with a status other than `200 OK` or a content type other than
+/// `text/event-stream` end the stream, as specified by the [SSE spec].
+///
+/// # Examples
+/// ```no_run
+/// use actix_web_lab::sse::{self, client::EventSource};
+/// use futures_util::StreamExt as _;
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let mut events = EventSource::new(awc::Client::new(), "http://localhost:8080/events")
+///     .into_stream();
+///
+/// while let Some(event) = events.next().await {
+///     match event {
+///         Ok(sse::Event::Data(data)) => println!("received: {}", data.get_data()),
+///         Ok(sse::Event::Comment(_)) => {}
+///         Err(err) => eprintln!("{err}"),
+///     }
+/// }
+/// # });
+/// ```
+///
+/// [SSE spec]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+#[derive(Clone)]
+pub struct EventSource {
+    client: awc::Client,
+    url: String,
+    last_event_id: Option<String>,
+    retry: Duration,
+    reconnect: bool,
+}
+
+impl EventSource {
+    /// Constructs a new event source for `url`, using `client` to make requests.
+    pub fn new(client: awc::Client, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+            last_event_id: None,
+            retry: DEFAULT_RETRY,
+            reconnect: true,
+        }
+    }
+
+    /// Sets the `Last-Event-ID` sent with the first request, e.g., to resume a previous stream.
+    pub fn last_event_id(mut self, id: impl Into<String>) -> Self {
+        self.last_event_id = Some(id.into());
+        self
+    }
+
+    /// Sets the delay before reconnecting, until the server sends its own.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets whether to reconnect when the connection fails or is closed. Defaults to true.
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Connects to the event source, returning the stream of received events.
+    pub fn into_stream(self) -> EventStream {
+        let state = State {
+            source: self,
+            decoder: Decoder::new(),
+            body: None,
+            connected_once: false,
+            done: false,
+        };
+
+        EventStream {
+            inner: stream::unfold(state, State::next).boxed_local(),
+        }
+    }
+}
+
+impl fmt::Debug for EventSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSource")
+            .field("url", &self.url)
+            .field("last_event_id", &self.last_event_id)
+            .field("retry", &self.retry)
+            .field("reconnect", &self.reconnect)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Connects to the event source at `url` with default settings.
+///
+/// See [`EventSource`] docs.
+pub fn connect(client: &awc::Client, url: impl Into<String>) -> EventStream {
+    EventSource::new(client.clone(), url).into_stream()
+}
+
+/// Stream of events received from an [`EventSource`].
+pub struct EventStream {
+    inner: LocalBoxStream<'static, Result<Event, ClientError>>,
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream").finish_non_exhaustive()
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+struct State {
+    source: EventSource,
+    decoder: Decoder,
+    body: Option<LocalBoxStream<'static, Result<Bytes, PayloadError>>>,
+    connected_once: bool,
+    done: bool,
+}
+
+impl State {
+    async fn next(mut self) -> Option<(Result<Event, ClientError>, Self)> {
+        loop {
+            if let Some(event) = self.decoder.next_event() {
+                return Some((Ok(event), self));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            let Some(body) = &mut self.body else {
+                if let Err(err) = self.connect().await {
+                    return Some((Err(err), self));
+                }
+
+                continue;
+            };
+
+            match body.next().await {
+                Some(Ok(chunk)) => self.decoder.decode(&chunk),
+
+                Some(Err(err)) => {
+                    self.disconnect();
+                    return Some((Err(ClientError::Payload(err)), self));
+                }
+
+                None => self.disconnect(),
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> Result<(), ClientError> {
+        if self.connected_once {
+            let retry = self.decoder.retry().unwrap_or(self.source.retry);
+            actix_web::rt::time::sleep(retry).await;
+        }
+        self.connected_once = true;
+
+        let mut req = self
+            .source
+            .client
+            .get(&self.source.url)
+            .insert_header((header::ACCEPT, mime::TEXT_EVENT_STREAM.essence_str()))
+            .insert_header((header::CACHE_CONTROL, "no-cache"));
+
+        let last_event_id = self
+            .decoder
+            .last_event_id()
+            .or(self.source.last_event_id.as_deref());
+
+        if let Some(id) = last_event_id {
+            req = req.insert_header(("last-event-id", id));
+        }
+
+        let res = match req.send().await {
+            Ok(res) => res,
+            Err(err) => {
+                self.done = !self.source.reconnect;
+                return Err(ClientError::Connect(err));
+            }
+        };
+
+        if res.status() != StatusCode::OK {
+            self.done = true;
+            return Err(ClientError::Status(res.status()));
+        }
+
+        let is_event_stream = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .and_then(|ct| ct.parse::<mime::Mime>().ok())
+            .is_some_and(|ct| ct.essence_str() == mime::TEXT_EVENT_STREAM.essence_str());
+
+        if !is_event_stream {
+            self.done = true;
+            return Err(ClientError::ContentType);
+        }
+
+        self.body = Some(res.boxed_local());
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.body = None;
+        self.done = !self.source.reconnect;
+
+        // discard partially received message
+        self.decoder = Decoder {
+            last_event_id: self.decoder.last_event_id.take(),
+            retry: self.decoder.retry,
+            events: mem::take(&mut self.decoder.events),
+            ..Decoder::new()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::{web, App, HttpRequest, Responder};
+
+    use super::*;
+    use crate::sse::Sse;
+
+    #[test]
+    fn decodes_messages() {
+        let mut decoder = Decoder::new();
+
+        decoder.decode(
+            b"\xEF\xBB\xBF: hello\r\n\
+            retry: 10\r\n\
+            event: greeting\n\
+            data: a\n\
+            data\n\
+            data:  b\n\
+            id: 7\n\
+            \n\
+            id\n\
+            data: no id\r",
+        );
+        decoder.decode(b"\n\r\ndata: dropped unless dispatched");
+
+        assert_eq!(decoder.next_event(), Some(Event::Comment("hello".into())));
+        assert_eq!(
+            decoder.next_event(),
+            Some(Data::new("a\n\n b").event("greeting").id("7").into()),
+        );
+        assert_eq!(decoder.next_event(), Some(Data::new("no id").id("").into()));
+        assert_eq!(decoder.next_event(), None);
+
+        assert_eq!(decoder.last_event_id(), Some(""));
+        assert_eq!(decoder.retry(), Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn decodes_split_bom_and_crlf() {
+        let mut decoder = Decoder::new();
+
+        decoder.decode(b"\xEF");
+        decoder.decode(b"\xBB\xBFdata: x\r");
+        decoder.decode(b"\n\r");
+        decoder.decode(b"\n");
+
+        assert_eq!(decoder.next_event(), Some(Data::new("x").into()));
+        assert_eq!(decoder.next_event(), None);
+    }
+
+    #[actix_web::test]
+    async fn reconnects_with_last_event_id() {
+        let srv = actix_test::start(|| {
+            App::new().route(
+                "/",
+                web::get().to(|req: HttpRequest| async move {
+                    let next = req
+                        .headers()
+                        .get("last-event-id")
+                        .map_or(0, |id| id.to_str().unwrap().parse::<u32>().unwrap() + 1);
+
+                    let events = [Event::from(
+                        Data::new(format!("msg {next}")).id(next.to_string()),
+                    )];
+
+                    Sse::from_stream(stream::iter(events.map(Ok::<_, Infallible>)))
+                        .with_retry_duration(Duration::from_millis(1))
+                        .respond_to(&req)
+                }),
+            )
+        });
+
+        let events = connect(&awc::Client::new(), srv.url("/"))
+            .take(3)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            events,
+            [
+                Event::from(Data::new("msg 0").id("0")),
+                Event::from(Data::new("msg 1").id("1")),
+                Event::from(Data::new("msg 2").id("2")),
+            ],
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Per-event compression for SSE responses.
+
+use std::io::Write as _;
+
+use actix_web::{
+    http::header::{self, HeaderValue},
+    HttpRequest,
+};
+use bytes::Bytes;
+use flate2::{
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+/// Streaming compressor which flushes after every event.
+///
+/// Sync-flushing after each chunk means that every chunk written to the response can be fully
+/// decompressed by clients as soon as it arrives, while the compression context is still shared
+/// between events.
+#[derive(Debug)]
+pub(crate) enum SseCompressor {
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl SseCompressor {
+    /// Selects a compressor supported by the request's `Accept-Encoding` header, preferring gzip.
+    pub(crate) fn negotiate(req: &HttpRequest) -> Option<Self> {
+        let accept_encoding = req.headers().get(header::ACCEPT_ENCODING)?.to_str().ok()?;
+
+        let accepts = |coding: &str| {
+            accept_encoding.split(',').any(|item| {
+                let mut params = item.split(';').map(str::trim);
+
+                params
+                    .next()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(coding))
+                    && params.all(|param| !matches!(param, "q=0" | "q=0.0" | "q=0.00" | "q=0.000"))
+            })
+        };
+
+        if accepts("gzip") {
+            Some(Self::Gzip(GzEncoder::new(Vec::new(), Compression::fast())))
+        } else if accepts("deflate") {
+            Some(Self::Deflate(ZlibEncoder::new(
+                Vec::new(),
+                Compression::fast(),
+            )))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `Content-Encoding` header value for this compressor.
+    pub(crate) fn content_encoding(&self) -> HeaderValue {
+        match self {
+            Self::Gzip(_) => HeaderValue::from_static("gzip"),
+            Self::Deflate(_) => HeaderValue::from_static("deflate"),
+        }
+    }
+
+    /// Compresses `chunk` and flushes, returning all output produced so far.
+    pub(crate) fn compress(&mut self, chunk: &[u8]) -> Bytes {
+        // writing to a Vec is infallible
+        let buf = match self {
+            Self::Gzip(enc) => {
+                let _ = enc.write_all(chunk);
+                let _ = enc.flush();
+                enc.get_mut()
+            }
+            Self::Deflate(enc) => {
+                let _ = enc.write_all(chunk);
+                let _ = enc.flush();
+                enc.get_mut()
+            }
+        };
+
+        Bytes::from(std::mem::take(buf))
+    }
+
+    /// Finishes the compressed stream, returning any trailing output.
+    pub(crate) fn finish(self) -> Bytes {
+        let buf = match self {
+            Self::Gzip(enc) => enc.finish(),
+            Self::Deflate(enc) => enc.finish(),
+        };
+
+        Bytes::from(buf.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use actix_web::test::TestRequest;
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn negotiation() {
+        let negotiate = |accept_encoding: &str| {
+            let req = TestRequest::default()
+                .insert_header((header::ACCEPT_ENCODING, accept_encoding))
+                .to_http_request();
+            SseCompressor::negotiate(&req).map(|comp| comp.content_encoding())
+        };
+
+        assert_eq!(
+            negotiate("br, gzip"),
+            Some(HeaderValue::from_static("gzip"))
+        );
+        assert_eq!(
+            negotiate("deflate, gzip;q=0"),
+            Some(HeaderValue::from_static("deflate")),
+        );
+        assert_eq!(negotiate("br"), None);
+        assert_eq!(negotiate("identity"), None);
+    }
+
+    #[test]
+    fn flushes_each_chunk() {
+        let mut comp = SseCompressor::Gzip(GzEncoder::new(Vec::new(), Compression::fast()));
+
+        let first = comp.compress(b"data: foo\n\n");
+
+        // first chunk is decodable on its own, without the trailer
+        let mut decoded = Vec::new();
+        let _ = GzDecoder::new(&first[..]).read_to_end(&mut decoded);
+        assert_eq!(decoded, b"data: foo\n\n");
+
+        let mut all = first.to_vec();
+        all.extend_from_slice(&comp.compress(b"data: bar\n\n"));
+        all.extend_from_slice(&comp.finish());
+
+        let mut decoded = String::new();
+        GzDecoder::new(&all[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "data: foo\n\ndata: bar\n\n");
+    }
+}
@@ -0,0 +1,124 @@
+//! Postgres `LISTEN`/`NOTIFY` SSE adapter.
+//!
+//! See [`from_pg_notifications`] docs.
+
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_util::stream;
+use sqlx::postgres::{PgListener, PgNotification, PgPool};
+use tracing::{debug, warn};
+
+use crate::{
+    sse::{Event, Sse},
+    util::InfallibleStream,
+};
+
+/// Initial delay before attempting to reconnect after a connection failure.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Maximum delay between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Creates an SSE responder that streams Postgres notifications sent to `channel`.
+///
+/// A dedicated connection is acquired from `pool` and `LISTEN`s on `channel`. Each notification is
+/// passed to `map` which can transform its payload into an event or return `None` to skip it.
+///
+/// If the listener connection fails, it is re-established using exponential backoff (from 100ms,
+/// up to 30s). Notifications sent while disconnected are not received.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{get, web, Responder};
+/// use actix_web_lab::sse;
+/// use sqlx::PgPool;
+///
+/// #[get("/orders/events")]
+/// async fn order_events(pool: web::Data<PgPool>) -> impl Responder {
+///     sse::from_pg_notifications((**pool).clone(), "order_updates", |notification| {
+///         Some(sse::Data::new(notification.payload()).event("order").into())
+///     })
+/// }
+/// ```
+pub fn from_pg_notifications<F>(
+    pool: PgPool,
+    channel: impl Into<String>,
+    map: F,
+) -> Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>
+where
+    F: FnMut(PgNotification) -> Option<Event> + 'static,
+{
+    let state = PgListenState {
+        pool,
+        channel: channel.into(),
+        listener: None,
+        backoff: INITIAL_BACKOFF,
+        map,
+    };
+
+    Sse::from_infallible_stream(stream::unfold(state, |mut state| async move {
+        let ev = state.next_event().await;
+        Some((ev, state))
+    }))
+}
+
+struct PgListenState<F> {
+    pool: PgPool,
+    channel: String,
+    listener: Option<PgListener>,
+    backoff: Duration,
+    map: F,
+}
+
+impl<F> PgListenState<F>
+where
+    F: FnMut(PgNotification) -> Option<Event>,
+{
+    async fn connect(&self) -> Result<PgListener, sqlx::Error> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(&self.channel).await?;
+        Ok(listener)
+    }
+
+    async fn next_event(&mut self) -> Event {
+        loop {
+            if self.listener.is_none() {
+                match self.connect().await {
+                    Ok(listener) => {
+                        debug!("listening for notifications on channel {}", self.channel);
+                        self.listener = Some(listener);
+                    }
+
+                    Err(err) => {
+                        warn!(
+                            "failed to listen on channel {}; retrying in {:?}: {err}",
+                            self.channel, self.backoff,
+                        );
+
+                        actix_web::rt::time::sleep(self.backoff).await;
+                        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                        continue;
+                    }
+                }
+            }
+
+            let listener = self.listener.as_mut().expect("listener was just connected");
+
+            match listener.recv().await {
+                Ok(notification) => {
+                    self.backoff = INITIAL_BACKOFF;
+
+                    if let Some(ev) = (self.map)(notification) {
+                        return ev;
+                    }
+                }
+
+                Err(err) => {
+                    warn!("notification listener failed: {err}");
+                    self.listener = None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! File system watcher SSE adapter.
+//!
+//! See [`watch_path`] docs.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use futures_util::stream;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{
+    sse::{Data, Event, Sse},
+    util::InfallibleStream,
+};
+
+/// Default period over which file system events are collected and de-duplicated.
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Kind of change to a watched file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl Change {
+    fn from_kind(kind: &EventKind) -> Option<Self> {
+        match kind {
+            EventKind::Create(_) => Some(Self::Created),
+            EventKind::Modify(_) => Some(Self::Modified),
+            EventKind::Remove(_) => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+
+    fn event_name(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Modified => "modified",
+            Self::Deleted => "deleted",
+        }
+    }
+
+    /// Combines a previously seen change for a path with a newer one.
+    fn merge(prev: Option<Self>, next: Self) -> Option<Self> {
+        match (prev, next) {
+            // file did not exist before this batch; still report as created
+            (Some(Self::Created), Self::Modified) => Some(Self::Created),
+
+            // file was created and removed within the batch
+            (Some(Self::Created), Self::Deleted) => None,
+
+            (_, next) => Some(next),
+        }
+    }
+}
+
+/// Creates an SSE responder that streams file system changes under `path`.
+///
+/// Directories are watched recursively. Events are debounced for 100ms; use
+/// [`watch_path_with_debounce`] to configure this.
+///
+/// Each change is sent as a data message with the event name set to one of `created`,
+/// `modified`, or `deleted` and the data field set to the affected file path.
+///
+/// # Errors
+/// Returns an error if the watcher could not be set up; e.g., if `path` does not exist.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{error, get, Responder};
+/// use actix_web_lab::sse;
+///
+/// #[get("/dev/changes")]
+/// async fn changes() -> actix_web::Result<impl Responder> {
+///     sse::watch_path("./static").map_err(error::ErrorInternalServerError)
+/// }
+/// ```
+pub fn watch_path(
+    path: impl AsRef<Path>,
+) -> Result<Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>, notify::Error> {
+    watch_path_with_debounce(path, DEFAULT_DEBOUNCE)
+}
+
+/// Creates an SSE responder that streams file system changes under `path`, debounced over the
+/// given period.
+///
+/// See [`watch_path`] docs.
+pub fn watch_path_with_debounce(
+    path: impl AsRef<Path>,
+    debounce: Duration,
+) -> Result<Sse<InfallibleStream<impl Stream<Item = Event> + 'static>>, notify::Error> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        // receiver is dropped when client disconnects
+        let _ = tx.send(res);
+    })?;
+
+    watcher.watch(path.as_ref(), RecursiveMode::Recursive)?;
+
+    let state = WatchState {
+        _watcher: watcher,
+        rx,
+        pending: VecDeque::new(),
+        debounce,
+    };
+
+    Ok(Sse::from_infallible_stream(stream::unfold(
+        state,
+        |mut state| async move {
+            let ev = state.next_event().await?;
+            Some((ev, state))
+        },
+    )))
+}
+
+struct WatchState {
+    /// Kept alive for the duration of the event stream.
+    _watcher: RecommendedWatcher,
+    rx: mpsc::UnboundedReceiver<notify::Result<notify::Event>>,
+    pending: VecDeque<Event>,
+    debounce: Duration,
+}
+
+impl WatchState {
+    async fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(ev) = self.pending.pop_front() {
+                return Some(ev);
+            }
+
+            let mut batch = BTreeMap::new();
+            add_to_batch(&mut batch, self.rx.recv().await?);
+
+            let deadline = actix_web::rt::time::sleep(self.debounce);
+            tokio::pin!(deadline);
+
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+
+                    res = self.rx.recv() => match res {
+                        Some(res) => add_to_batch(&mut batch, res),
+                        None => break,
+                    },
+                }
+            }
+
+            self.pending
+                .extend(batch.into_iter().filter_map(|(path, change)| {
+                    let change: Change = change?;
+
+                    Some(Event::from(
+                        Data::new(path.to_string_lossy().into_owned()).event(change.event_name()),
+                    ))
+                }));
+        }
+    }
+}
+
+fn add_to_batch(batch: &mut BTreeMap<PathBuf, Option<Change>>, res: notify::Result<notify::Event>) {
+    let ev = match res {
+        Ok(ev) => ev,
+        Err(err) => {
+            warn!("file watcher error: {err}");
+            return;
+        }
+    };
+
+    let Some(change) = Change::from_kind(&ev.kind) else {
+        return;
+    };
+
+    for path in ev.paths {
+        let entry = batch.entry(path).or_insert(None);
+        *entry = Change::merge(*entry, change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+
+    use super::*;
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<notify::Event> {
+        Ok(notify::Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn batches_are_coalesced() {
+        let mut batch = BTreeMap::new();
+
+        add_to_batch(&mut batch, event(EventKind::Create(CreateKind::File), "/a"));
+        add_to_batch(&mut batch, event(EventKind::Modify(ModifyKind::Any), "/a"));
+        add_to_batch(&mut batch, event(EventKind::Modify(ModifyKind::Any), "/b"));
+        add_to_batch(&mut batch, event(EventKind::Create(CreateKind::File), "/c"));
+        add_to_batch(&mut batch, event(EventKind::Remove(RemoveKind::File), "/c"));
+        add_to_batch(&mut batch, event(EventKind::Access(AccessKind::Any), "/d"));
+
+        assert_eq!(batch.get(Path::new("/a")), Some(&Some(Change::Created)));
+        assert_eq!(batch.get(Path::new("/b")), Some(&Some(Change::Modified)));
+        assert_eq!(batch.get(Path::new("/c")), Some(&None));
+        assert_eq!(batch.get(Path::new("/d")), None);
+    }
+
+    #[test]
+    fn missing_path_errors() {
+        assert!(watch_path("/this/path/does/not/exist/hopefully").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! Bridges external message consumers to streaming responses.
+//!
+//! Implement [`MessageSource`] for a message consumer (e.g., a Kafka consumer or NATS
+//! subscription) and wrap it in a [`StreamBridge`] to respond with an NDJSON or SSE stream of its
+//! messages. When the client disconnects, the source is [closed](MessageSource::close) in the
+//! background.
+//!
+//! A NATS implementation is provided for [`async_nats::Subscriber`] when the `nats` crate feature
+//! is enabled.
+//!
+//! # Examples
+//! ```no_run
+//! # #[cfg(feature = "nats")] {
+//! use actix_web::{get, web, Responder};
+//! use actix_web_lab::{sse, stream_bridge::StreamBridge};
+//!
+//! #[get("/prices")]
+//! async fn prices(nats: web::Data<async_nats::Client>) -> actix_web::Result<impl Responder> {
+//!     let sub = nats
+//!         .subscribe("prices")
+//!         .await
+//!         .map_err(actix_web::error::ErrorInternalServerError)?;
+//!
+//!     Ok(StreamBridge::new(sub).into_sse(|msg| {
+//!         sse::Data::new(String::from_utf8_lossy(&msg.payload).into_owned()).into()
+//!     }))
+//! }
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use futures_core::Stream;
+use futures_util::stream;
+use serde::Serialize;
+use tracing::trace;
+
+use crate::{
+    ndjson::NdJson,
+    sse::{Event, Sse},
+    BoxError,
+};
+
+/// A source of messages that can be bridged to a streaming response.
+///
+/// Annotate your implementations with `#[async_trait(?Send)]`.
+#[async_trait(?Send)]
+pub trait MessageSource: 'static {
+    /// Message type yielded by the source.
+    type Message;
+
+    /// Error type yielded by the source.
+    type Error: Into<BoxError> + 'static;
+
+    /// Receives the next message, returning `None` if the source is exhausted.
+    async fn next(&mut self) -> Option<Result<Self::Message, Self::Error>>;
+
+    /// Acknowledges (or commits the offset of) a message.
+    ///
+    /// When this is called is determined by the bridge's [`AckStrategy`]. The default
+    /// implementation does nothing.
+    #[allow(unused_variables)]
+    async fn ack(&mut self, msg: &Self::Message) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Cleans up the source when the response stream ends or the client disconnects.
+    ///
+    /// The default implementation does nothing.
+    async fn close(&mut self) {}
+}
+
+/// Determines when messages are acknowledged by a [`StreamBridge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum AckStrategy {
+    /// Acknowledge each message as soon as it is received from the source (at-most-once).
+    BeforeSend,
+
+    /// Acknowledge each message once the response writer has taken its encoded chunk and asked
+    /// for the next one (at-least-once). Messages in flight when the client disconnects are not
+    /// acknowledged.
+    #[default]
+    AfterSend,
+
+    /// Never acknowledge messages.
+    Never,
+}
+
+/// Converts a [`MessageSource`] into NDJSON or SSE responders.
+///
+/// See [module docs](self) for more.
+#[derive(Debug)]
+#[must_use]
+pub struct StreamBridge<S> {
+    source: S,
+    ack_strategy: AckStrategy,
+}
+
+impl<S: MessageSource> StreamBridge<S> {
+    /// Constructs a new bridge for `source` using the default [`AckStrategy`].
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            ack_strategy: AckStrategy::default(),
+        }
+    }
+
+    /// Sets the strategy used for acknowledging messages.
+    pub fn ack_strategy(mut self, ack_strategy: AckStrategy) -> Self {
+        self.ack_strategy = ack_strategy;
+        self
+    }
+
+    /// Converts the bridge into a stream, mapping each message using `map`.
+    pub fn into_stream<T, F>(self, map: F) -> impl Stream<Item = Result<T, S::Error>> + 'static
+    where
+        T: 'static,
+        F: FnMut(&S::Message) -> T + 'static,
+    {
+        let state = BridgeState {
+            source: Some(self.source),
+            ack_strategy: self.ack_strategy,
+            pending_ack: None,
+            map,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let item = state.next_item().await?;
+            Some((item, state))
+        })
+    }
+
+    /// Converts the bridge into an SSE responder, mapping each message to an event using `map`.
+    pub fn into_sse<F>(self, map: F) -> Sse<impl Stream<Item = Result<Event, S::Error>> + 'static>
+    where
+        F: FnMut(&S::Message) -> Event + 'static,
+    {
+        Sse::from_stream(self.into_stream(map))
+    }
+
+    /// Converts the bridge into an NDJSON responder, mapping each message to a serializable item
+    /// using `map`.
+    pub fn into_ndjson<T, F>(self, map: F) -> impl actix_web::Responder
+    where
+        T: Serialize + 'static,
+        F: FnMut(&S::Message) -> T + 'static,
+    {
+        NdJson::new(self.into_stream(map)).into_responder()
+    }
+}
+
+struct BridgeState<S: MessageSource, F> {
+    /// Always `Some` until dropped.
+    source: Option<S>,
+    ack_strategy: AckStrategy,
+    pending_ack: Option<S::Message>,
+    map: F,
+}
+
+impl<S, F, T> BridgeState<S, F>
+where
+    S: MessageSource,
+    F: FnMut(&S::Message) -> T,
+{
+    async fn next_item(&mut self) -> Option<Result<T, S::Error>> {
+        let source = self.source.as_mut()?;
+
+        // previous chunk has been taken by response writer
+        if let Some(msg) = self.pending_ack.take() {
+            if let Err(err) = source.ack(&msg).await {
+                return Some(Err(err));
+            }
+        }
+
+        let msg = match source.next().await? {
+            Ok(msg) => msg,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let item = (self.map)(&msg);
+
+        match self.ack_strategy {
+            AckStrategy::BeforeSend => {
+                if let Err(err) = source.ack(&msg).await {
+                    return Some(Err(err));
+                }
+            }
+            AckStrategy::AfterSend => self.pending_ack = Some(msg),
+            AckStrategy::Never => {}
+        }
+
+        Some(Ok(item))
+    }
+}
+
+impl<S: MessageSource, F> Drop for BridgeState<S, F> {
+    fn drop(&mut self) {
+        if let Some(mut source) = self.source.take() {
+            trace!("closing message source");
+            actix_web::rt::spawn(async move { source.close().await });
+        }
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait(?Send)]
+impl MessageSource for async_nats::Subscriber {
+    type Message = async_nats::Message;
+    type Error = std::convert::Infallible;
+
+    async fn next(&mut self) -> Option<Result<Self::Message, Self::Error>> {
+        futures_util::StreamExt::next(self).await.map(Ok)
+    }
+
+    async fn close(&mut self) {
+        if let Err(err) = self.unsubscribe().await {
+            tracing::warn!("failed to unsubscribe from NATS subject: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, convert::Infallible, rc::Rc};
+
+    use actix_web::{body, test::TestRequest, Responder as _};
+    use futures_util::StreamExt as _;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Log {
+        acked: Vec<u32>,
+        closed: bool,
+    }
+
+    struct TestSource {
+        msgs: VecDeque<u32>,
+        log: Rc<RefCell<Log>>,
+    }
+
+    impl TestSource {
+        fn new(msgs: impl IntoIterator<Item = u32>) -> (Self, Rc<RefCell<Log>>) {
+            let log = Rc::new(RefCell::new(Log::default()));
+
+            let source = Self {
+                msgs: msgs.into_iter().collect(),
+                log: Rc::clone(&log),
+            };
+
+            (source, log)
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl MessageSource for TestSource {
+        type Message = u32;
+        type Error = Infallible;
+
+        async fn next(&mut self) -> Option<Result<u32, Infallible>> {
+            self.msgs.pop_front().map(Ok)
+        }
+
+        async fn ack(&mut self, msg: &u32) -> Result<(), Infallible> {
+            self.log.borrow_mut().acked.push(*msg);
+            Ok(())
+        }
+
+        async fn close(&mut self) {
+            self.log.borrow_mut().closed = true;
+        }
+    }
+
+    #[actix_web::test]
+    async fn ndjson_responder() {
+        let (source, log) = TestSource::new([1, 2, 3]);
+
+        let res = StreamBridge::new(source)
+            .into_ndjson(|n| serde_json::json!({ "n": n }))
+            .respond_to(&TestRequest::default().to_http_request());
+
+        assert_eq!(
+            body::to_bytes(res.into_body())
+                .await
+                .map_err(Into::<BoxError>::into)
+                .unwrap(),
+            "{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n",
+        );
+
+        actix_web::rt::task::yield_now().await;
+        assert_eq!(log.borrow().acked, [1, 2, 3]);
+        assert!(log.borrow().closed);
+    }
+
+    #[actix_web::test]
+    async fn sse_responder() {
+        let (source, _log) = TestSource::new([1, 2]);
+
+        let sse =
+            StreamBridge::new(source).into_sse(|n| crate::sse::Data::new(n.to_string()).into());
+
+        assert_eq!(body::to_bytes(sse).await.unwrap(), "data: 1\n\ndata: 2\n\n",);
+    }
+
+    #[actix_web::test]
+    async fn unacked_on_disconnect() {
+        let (source, log) = TestSource::new([1, 2, 3]);
+
+        let mut stream = Box::pin(StreamBridge::new(source).into_stream(|n| *n));
+        assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+        assert_eq!(stream.next().await.unwrap().unwrap(), 2);
+
+        // client disconnects
+        drop(stream);
+        actix_web::rt::task::yield_now().await;
+
+        assert_eq!(log.borrow().acked, [1]);
+        assert!(log.borrow().closed);
+    }
+
+    #[actix_web::test]
+    async fn ack_strategies() {
+        let (source, log) = TestSource::new([1, 2]);
+
+        let mut stream = Box::pin(
+            StreamBridge::new(source)
+                .ack_strategy(AckStrategy::BeforeSend)
+                .into_stream(|n| *n),
+        );
+        assert_eq!(stream.next().await.unwrap().unwrap(), 1);
+        assert_eq!(log.borrow().acked, [1]);
+
+        let (source, log) = TestSource::new([1, 2]);
+        let stream = StreamBridge::new(source)
+            .ack_strategy(AckStrategy::Never)
+            .into_stream(|n| *n);
+        assert_eq!(stream.collect::<Vec<_>>().await.len(), 2);
+        assert!(log.borrow().acked.is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+//! Generic streaming responder with pluggable formats.
+//!
+//! See [`Streamed`] docs.
+
+use std::{error::Error as StdError, marker::PhantomData};
+
+use actix_web::{
+    body::{BodyStream, BoxBody},
+    http::header::{self, Accept, CacheControl, CacheDirective, HeaderValue},
+    HttpMessage as _, HttpRequest, HttpResponse, Responder,
+};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::TryStreamExt as _;
+use mime::Mime;
+use serde::Serialize;
+
+use crate::{csv::serialize_csv_row, ndjson::serialize_json_line, sse, util::InfallibleStream};
+
+/// A serialization format for [`Streamed`] responses.
+pub trait StreamFormat {
+    /// Returns the media type of response bodies in this format.
+    fn mime() -> Mime;
+
+    /// Serializes a single stream item into a body chunk.
+    fn encode<T: Serialize>(item: &T) -> Bytes;
+}
+
+/// [NDJSON] stream format; one JSON document per line.
+///
+/// [NDJSON]: https://ndjson.org/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NdJsonFmt;
+
+impl StreamFormat for NdJsonFmt {
+    fn mime() -> Mime {
+        "application/x-ndjson".parse().unwrap()
+    }
+
+    fn encode<T: Serialize>(item: &T) -> Bytes {
+        serialize_json_line(item)
+    }
+}
+
+/// CSV stream format; one row per item.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvFmt;
+
+impl StreamFormat for CsvFmt {
+    fn mime() -> Mime {
+        mime::TEXT_CSV_UTF_8
+    }
+
+    fn encode<T: Serialize>(item: &T) -> Bytes {
+        serialize_csv_row(item)
+    }
+}
+
+/// Server-sent events stream format; one JSON data message per item.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SseFmt;
+
+impl StreamFormat for SseFmt {
+    fn mime() -> Mime {
+        mime::TEXT_EVENT_STREAM
+    }
+
+    fn encode<T: Serialize>(item: &T) -> Bytes {
+        let data = sse::Data::new_json(item).unwrap();
+        sse::Encoder::new().encode(&data.into())
+    }
+}
+
+/// Format marker which selects the format of [`Streamed`] responses using the request's `Accept`
+/// header.
+///
+/// Supports the formats in this module, preferring [`NdJsonFmt`] when the request has no `Accept`
+/// header or accepts any media type. Requests which accept none of the formats receive a
+/// `406 Not Acceptable` response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Negotiate;
+
+/// A streaming responder which serializes items using the format `F`.
+///
+/// Unifies the [`NdJson`](crate::respond::NdJson), [`Csv`](crate::respond::Csv), and
+/// [SSE](crate::sse) streaming responders behind one type, so that handlers can return it
+/// directly. Use [`Negotiate`] as the format to choose one based on the request.
+///
+/// # Examples
+/// ```
+/// use std::convert::Infallible;
+///
+/// use actix_web::get;
+/// use actix_web_lab::respond::{NdJsonFmt, Negotiate, Streamed};
+/// use futures_core::Stream;
+/// use futures_util::stream;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Reading {
+///     sensor: u32,
+///     value: f64,
+/// }
+///
+/// fn readings() -> impl Stream<Item = Reading> {
+///     stream::iter([Reading { sensor: 1, value: 0.5 }])
+/// }
+///
+/// #[get("/readings.ndjson")]
+/// async fn ndjson() -> Streamed<impl Stream<Item = Result<Reading, Infallible>>, NdJsonFmt> {
+///     Streamed::new_infallible(readings())
+/// }
+///
+/// #[get("/readings")]
+/// async fn negotiated() -> Streamed<impl Stream<Item = Result<Reading, Infallible>>, Negotiate> {
+///     Streamed::new_infallible(readings())
+/// }
+/// ```
+pub struct Streamed<S, F = NdJsonFmt> {
+    stream: S,
+    _fmt: PhantomData<F>,
+}
+
+impl<S, F> Streamed<S, F> {
+    /// Constructs a new `Streamed` responder from a fallible stream of items.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            _fmt: PhantomData,
+        }
+    }
+
+    /// Constructs a new `Streamed` responder from an infallible stream of items.
+    pub fn new_infallible(stream: S) -> Streamed<InfallibleStream<S>, F> {
+        Streamed::new(InfallibleStream::new(stream))
+    }
+
+    /// Changes the format of this responder.
+    pub fn format<F2>(self) -> Streamed<S, F2> {
+        Streamed::new(self.stream)
+    }
+}
+
+impl<S, T, E> Streamed<S, Negotiate>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Box<dyn StdError>> + 'static,
+{
+    fn respond_as<F: StreamFormat>(self) -> HttpResponse {
+        let mut res = self.format::<F>().into_response();
+        res.headers_mut()
+            .insert(header::VARY, HeaderValue::from_static("accept"));
+        res
+    }
+}
+
+impl<S, T, E, F> Streamed<S, F>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Box<dyn StdError>> + 'static,
+    F: StreamFormat,
+{
+    fn into_response(self) -> HttpResponse {
+        let body = BodyStream::new(self.stream.map_ok(|item| F::encode(&item)));
+        let mime = F::mime();
+
+        let mut res = HttpResponse::Ok();
+        res.content_type(mime.clone());
+
+        if mime == mime::TEXT_EVENT_STREAM {
+            res.insert_header(CacheControl(vec![CacheDirective::NoCache]));
+        }
+
+        res.body(body)
+    }
+}
+
+impl<S, T, E, F> Responder for Streamed<S, F>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Box<dyn StdError>> + 'static,
+    F: StreamFormat,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        self.into_response()
+    }
+}
+
+impl<S, T, E> Responder for Streamed<S, Negotiate>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize + 'static,
+    E: Into<Box<dyn StdError>> + 'static,
+{
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let Some(accept) = req.get_header::<Accept>() else {
+            return self.respond_as::<NdJsonFmt>();
+        };
+
+        for mime in accept.ranked() {
+            match (mime.type_(), mime.subtype()) {
+                (mime::STAR, mime::STAR) => return self.respond_as::<NdJsonFmt>(),
+                (mime::APPLICATION, sub) if sub == "x-ndjson" || sub == "jsonl" => {
+                    return self.respond_as::<NdJsonFmt>()
+                }
+                (mime::TEXT, mime::CSV) => return self.respond_as::<CsvFmt>(),
+                (mime::TEXT, mime::EVENT_STREAM) => return self.respond_as::<SseFmt>(),
+                _ => {}
+            }
+        }
+
+        HttpResponse::NotAcceptable()
+            .insert_header((header::VARY, "accept"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::{body, test::TestRequest};
+    use futures_util::stream;
+
+    use super::*;
+
+    fn items() -> Streamed<impl Stream<Item = Result<[u32; 2], Infallible>>, Negotiate> {
+        Streamed::new_infallible(stream::iter(vec![[1, 2], [3, 4]]))
+    }
+
+    #[actix_web::test]
+    async fn fixed_format() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = items().format::<CsvFmt>().respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/csv; charset=utf-8",
+        );
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "1,2\n3,4\n");
+    }
+
+    #[actix_web::test]
+    async fn negotiated_format() {
+        let cases = [
+            (None, "application/x-ndjson", "[1,2]\n[3,4]\n"),
+            (Some("*/*"), "application/x-ndjson", "[1,2]\n[3,4]\n"),
+            (
+                Some("text/html, text/event-stream;q=0.9, text/csv;q=0.5"),
+                "text/event-stream",
+                "data: [1,2]\n\ndata: [3,4]\n\n",
+            ),
+            (Some("text/csv"), "text/csv; charset=utf-8", "1,2\n3,4\n"),
+        ];
+
+        for (accept, content_type, expected) in cases {
+            let mut req = TestRequest::default();
+            if let Some(accept) = accept {
+                req = req.insert_header((header::ACCEPT, accept));
+            }
+            let req = req.to_http_request();
+
+            let res = items().respond_to(&req);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                content_type,
+            );
+            let body = body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, expected);
+        }
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, "text/html"))
+            .to_http_request();
+        let res = items().respond_to(&req);
+        assert_eq!(res.status(), actix_web::http::StatusCode::NOT_ACCEPTABLE);
+    }
+}
@@ -0,0 +1,203 @@
+//! Unknown field detection for extractors in strict mode.
+//!
+//! See [`LabConfig::strict()`](crate::extract::LabConfig::strict).
+
+use std::{borrow::Cow, cell::Cell, fmt};
+
+use serde::de::{self, value, DeserializeOwned, Visitor};
+
+use crate::uri::decode_query_component;
+
+/// Names of unknown fields, each with the most similar known field, if any is close.
+#[derive(Debug)]
+pub(crate) struct UnknownFields {
+    fields: Vec<(String, Option<&'static str>)>,
+}
+
+impl UnknownFields {
+    /// Returns unknown fields among `keys`, given the `known` field names of the target type.
+    pub(crate) fn find<K>(keys: impl IntoIterator<Item = K>, known: &[&'static str]) -> Option<Self>
+    where
+        K: AsRef<str>,
+    {
+        let mut fields = Vec::<(String, Option<&'static str>)>::new();
+
+        for key in keys {
+            let key = key.as_ref();
+
+            if known.contains(&key) || fields.iter().any(|(field, _)| field == key) {
+                continue;
+            }
+
+            fields.push((key.to_owned(), suggest(key, known)));
+        }
+
+        (!fields.is_empty()).then_some(Self { fields })
+    }
+
+    /// Converts into a deserialization error of type `E`, with this list as its message.
+    pub(crate) fn into_error<E: de::Error>(self) -> E {
+        E::custom(self)
+    }
+}
+
+impl fmt::Display for UnknownFields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.fields.len() == 1 { "" } else { "s" };
+        write!(f, "unknown field{plural} ")?;
+
+        for (idx, (field, suggestion)) in self.fields.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "`{field}`")?;
+
+            if let Some(suggestion) = suggestion {
+                write!(f, " (did you mean `{suggestion}`?)")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns field names, including aliases, of `T` if it deserializes from a struct.
+///
+/// Returns `None` for other types, including structs with flattened fields, which deserialize
+/// from maps.
+pub(crate) fn struct_fields<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let fields = Cell::new(None);
+    let _ = T::deserialize(FieldProbe(&fields));
+    fields.get()
+}
+
+/// Returns decoded keys of a URL-encoded query string or form body.
+pub(crate) fn urlencoded_keys(input: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+            decode_query_component(key).unwrap_or(Cow::Borrowed(key))
+        })
+}
+
+/// Returns the known field most similar to `key`, if it is within a small edit distance.
+fn suggest(key: &str, known: &[&'static str]) -> Option<&'static str> {
+    known
+        .iter()
+        .map(|field| (*field, edit_distance(key, field)))
+        .filter(|(field, distance)| *distance <= 2 && *distance < field.len())
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(field, _)| field)
+}
+
+/// Returns Levenshtein distance between `a` and `b`, ignoring ASCII case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, a_ch) in a.chars().enumerate() {
+        curr[0] = i + 1;
+
+        for (j, b_ch) in b.iter().enumerate() {
+            let cost = usize::from(!a_ch.eq_ignore_ascii_case(b_ch));
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// A deserializer that records the fields requested by `deserialize_struct` and then fails.
+struct FieldProbe<'a>(&'a Cell<Option<&'static [&'static str]>>);
+
+impl<'de> de::Deserializer<'de> for FieldProbe<'_> {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("field probe"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(Some(fields));
+        Err(de::Error::custom("field probe"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[test]
+    fn probes_struct_fields() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Params {
+            name: String,
+            #[serde(alias = "mail")]
+            email: String,
+        }
+
+        let fields = struct_fields::<Params>().unwrap();
+        assert!(fields.contains(&"name"));
+        assert!(fields.contains(&"email"));
+
+        assert_eq!(struct_fields::<HashMap<String, String>>(), None);
+        assert_eq!(struct_fields::<u32>(), None);
+    }
+
+    #[test]
+    fn lists_unknown_fields_with_suggestions() {
+        let known = &["name", "email", "age"];
+
+        assert!(UnknownFields::find(["name", "age"], known).is_none());
+
+        let unknown = UnknownFields::find(["nmae", "age", "extra", "Email", "extra"], known);
+        assert_eq!(
+            unknown.unwrap().to_string(),
+            "unknown fields `nmae` (did you mean `name`?), `extra`, `Email` (did you mean \
+            `email`?)",
+        );
+
+        let unknown = UnknownFields::find(["ag"], known);
+        assert_eq!(
+            unknown.unwrap().to_string(),
+            "unknown field `ag` (did you mean `age`?)"
+        );
+
+        let unknown = UnknownFields::find(["x"], known);
+        assert_eq!(unknown.unwrap().to_string(), "unknown field `x`");
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("NAME", "name"), 0);
+    }
+
+    #[test]
+    fn decodes_urlencoded_keys() {
+        let keys = urlencoded_keys("a=1&&b%20c=2&d+e&=3").collect::<Vec<_>>();
+        assert_eq!(keys, ["a", "b c", "d e", ""]);
+    }
+}
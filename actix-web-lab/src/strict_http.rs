@@ -0,0 +1,276 @@
+//! Strict request header validation middleware.
+//!
+//! See [`StrictHttp`] docs.
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, CONTENT_LENGTH, TRANSFER_ENCODING},
+    Error,
+};
+use futures_core::future::LocalBoxFuture;
+use tracing::warn;
+
+use crate::error::LabError;
+
+/// Default maximum number of request header lines.
+const DEFAULT_MAX_HEADERS: usize = 64;
+
+/// A middleware that rejects requests with ambiguous or malformed framing headers.
+///
+/// Request smuggling attacks rely on a front-end proxy and the application server disagreeing on
+/// where a request ends. Actix Web's own parser is strict, but when requests pass through other
+/// intermediaries first this middleware provides defense-in-depth by rejecting requests which any
+/// of them could plausibly have interpreted differently.
+///
+/// Requests are rejected with a `400 Bad Request` [`LabError::SuspiciousRequest`] response when:
+/// - both `Content-Length` and `Transfer-Encoding` are present;
+/// - `Content-Length` is repeated or is not a plain decimal number;
+/// - `Transfer-Encoding` is repeated or does not end with `chunked`;
+/// - any header value contains NUL, CR, or LF bytes (e.g., from obsolete line folding);
+/// - there are more header lines than the configured [maximum](Self::max_headers).
+///
+/// Each rejection is logged at warn level with the reason, method, and path.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::middleware::StrictHttp;
+///
+/// App::new().wrap(StrictHttp::new().max_headers(32))
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct StrictHttp {
+    max_headers: usize,
+}
+
+impl StrictHttp {
+    /// Constructs new strict header validation middleware with default limits.
+    pub fn new() -> Self {
+        Self {
+            max_headers: DEFAULT_MAX_HEADERS,
+        }
+    }
+
+    /// Sets maximum number of header lines allowed in a request.
+    ///
+    /// Repeated headers count once per occurrence. Defaults to 64.
+    pub fn max_headers(mut self, max_headers: usize) -> Self {
+        self.max_headers = max_headers;
+        self
+    }
+}
+
+impl Default for StrictHttp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for StrictHttp
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = StrictHttpMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(StrictHttpMiddleware {
+            service: Rc::new(service),
+            max_headers: self.max_headers,
+        }))
+    }
+}
+
+/// Service for the [`StrictHttp`] middleware.
+pub struct StrictHttpMiddleware<S> {
+    service: Rc<S>,
+    max_headers: usize,
+}
+
+impl<S, B> Service<ServiceRequest> for StrictHttpMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Err(reason) = validate(req.headers(), self.max_headers) {
+            warn!(
+                reason,
+                method = %req.method(),
+                path = req.path(),
+                "rejected suspicious request",
+            );
+
+            let res = req.error_response(LabError::SuspiciousRequest { reason });
+            return Box::pin(ready(Ok(res.map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// Returns reason that headers are rejected, if any.
+fn validate(headers: &HeaderMap, max_headers: usize) -> Result<(), &'static str> {
+    if headers.len() > max_headers {
+        return Err("too many headers");
+    }
+
+    let mut content_length = headers.get_all(CONTENT_LENGTH);
+    let mut transfer_encoding = headers.get_all(TRANSFER_ENCODING);
+
+    let cl = content_length.next();
+    let te = transfer_encoding.next();
+
+    if cl.is_some() && te.is_some() {
+        return Err("both Content-Length and Transfer-Encoding present");
+    }
+
+    if let Some(cl) = cl {
+        if content_length.next().is_some() {
+            return Err("repeated Content-Length");
+        }
+
+        let cl = cl.as_bytes();
+        if cl.is_empty() || !cl.iter().all(u8::is_ascii_digit) {
+            return Err("invalid Content-Length");
+        }
+    }
+
+    if let Some(te) = te {
+        if transfer_encoding.next().is_some() {
+            return Err("repeated Transfer-Encoding");
+        }
+
+        let last_coding = te
+            .to_str()
+            .ok()
+            .and_then(|te| te.rsplit(',').next())
+            .map(str::trim);
+
+        if !last_coding.is_some_and(|coding| coding.eq_ignore_ascii_case("chunked")) {
+            return Err("Transfer-Encoding does not end with chunked");
+        }
+    }
+
+    // the HTTP/1 decoder constructs header values without validating them
+    if headers
+        .iter()
+        .any(|(_, val)| has_control_bytes(val.as_bytes()))
+    {
+        return Err("control characters in header value");
+    }
+
+    Ok(())
+}
+
+/// Returns true if header value contains NUL, CR, or LF bytes.
+fn has_control_bytes(val: &[u8]) -> bool {
+    val.iter().any(|&b| matches!(b, b'\0' | b'\r' | b'\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{
+            header::{HeaderName, HeaderValue},
+            StatusCode,
+        },
+        test::{call_service, init_service, read_body_json, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static [u8])]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+
+        for (name, val) in pairs {
+            map.append(
+                HeaderName::from_static(*name),
+                HeaderValue::from_maybe_shared(bytes::Bytes::from_static(*val)).unwrap(),
+            );
+        }
+
+        map
+    }
+
+    #[test]
+    fn allows_well_formed_framing() {
+        assert!(validate(&headers(&[("content-length", b"42")]), 64).is_ok());
+        assert!(validate(&headers(&[("transfer-encoding", b"gzip, Chunked")]), 64).is_ok());
+        assert!(validate(&headers(&[]), 0).is_ok());
+    }
+
+    #[test]
+    fn rejects_ambiguous_framing() {
+        let cases: &[&[(&'static str, &'static [u8])]] = &[
+            &[("content-length", b"4"), ("transfer-encoding", b"chunked")],
+            &[("content-length", b"4"), ("content-length", b"4")],
+            &[("content-length", b"+4")],
+            &[("transfer-encoding", b"chunked, gzip")],
+            &[
+                ("transfer-encoding", b"chunked"),
+                ("transfer-encoding", b"chunked"),
+            ],
+        ];
+
+        for case in cases {
+            assert!(validate(&headers(case), 64).is_err(), "{case:?}");
+        }
+    }
+
+    #[test]
+    fn detects_control_bytes() {
+        assert!(!has_control_bytes(b"text/plain; charset=utf-8"));
+        assert!(has_control_bytes(b"a\0b"));
+        assert!(has_control_bytes(b"foo\r\n bar"));
+    }
+
+    #[test]
+    fn rejects_excess_headers() {
+        let map = headers(&[("x-foo", b"1"), ("x-foo", b"2"), ("x-bar", b"3")]);
+        assert_eq!(validate(&map, 2), Err("too many headers"));
+    }
+
+    #[actix_web::test]
+    async fn responds_bad_request() {
+        let app = init_service(
+            App::new()
+                .wrap(StrictHttp::new())
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .insert_header(("content-length", "0"))
+            .append_header(("transfer-encoding", "chunked"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(body["code"], "suspicious_request");
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,440 @@
+//! Minimal parsing and serialization of structured field values (RFC 8941).
+//!
+//! Only dictionaries are parsed, which is what the headers using structured fields in this crate
+//! need. Decimals are not supported and cause parsing to fail.
+
+use std::fmt::{self, Write as _};
+
+use base64::Engine as _;
+
+/// A bare item of a structured field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BareItem {
+    Integer(i64),
+    String(String),
+    Token(String),
+    ByteSeq(Vec<u8>),
+    Boolean(bool),
+}
+
+impl BareItem {
+    /// Returns string value, if this item is a string.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns integer value, if this item is an integer.
+    pub(crate) fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(val) => Some(*val),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BareItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(val) => write!(f, "{val}"),
+
+            Self::String(val) => {
+                f.write_char('"')?;
+
+                for ch in val.chars() {
+                    if matches!(ch, '"' | '\\') {
+                        f.write_char('\\')?;
+                    }
+
+                    f.write_char(ch)?;
+                }
+
+                f.write_char('"')
+            }
+
+            Self::Token(val) => f.write_str(val),
+
+            Self::ByteSeq(val) => {
+                write!(
+                    f,
+                    ":{}:",
+                    base64::engine::general_purpose::STANDARD.encode(val)
+                )
+            }
+
+            Self::Boolean(val) => write!(f, "?{}", u8::from(*val)),
+        }
+    }
+}
+
+/// Parameters of an item or inner list, in order.
+pub(crate) type Params = Vec<(String, BareItem)>;
+
+/// Returns value of parameter `key`, if present.
+pub(crate) fn param<'a>(params: &'a Params, key: &str) -> Option<&'a BareItem> {
+    params
+        .iter()
+        .find_map(|(name, val)| (name == key).then_some(val))
+}
+
+/// Writes parameters in their serialized form.
+pub(crate) fn write_params(buf: &mut String, params: &Params) {
+    for (key, val) in params {
+        buf.push(';');
+        buf.push_str(key);
+
+        if *val != BareItem::Boolean(true) {
+            let _ = write!(buf, "={val}");
+        }
+    }
+}
+
+/// An item of a structured field, with its parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Item {
+    pub(crate) bare: BareItem,
+    pub(crate) params: Params,
+}
+
+/// A dictionary member value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Member {
+    Item(Item),
+    InnerList(Vec<Item>, Params),
+}
+
+impl Member {
+    /// Returns member in its serialized form.
+    pub(crate) fn serialize(&self) -> String {
+        let mut buf = String::new();
+
+        match self {
+            Self::Item(item) => {
+                let _ = write!(buf, "{}", item.bare);
+                write_params(&mut buf, &item.params);
+            }
+
+            Self::InnerList(items, params) => {
+                buf.push('(');
+
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        buf.push(' ');
+                    }
+
+                    let _ = write!(buf, "{}", item.bare);
+                    write_params(&mut buf, &item.params);
+                }
+
+                buf.push(')');
+                write_params(&mut buf, params);
+            }
+        }
+
+        buf
+    }
+}
+
+/// Parses a structured field dictionary.
+///
+/// Returns `None` if `input` is not a valid dictionary. When keys are repeated, the last value is
+/// kept, in the position of the first.
+pub(crate) fn parse_dictionary(input: &str) -> Option<Vec<(String, Member)>> {
+    let mut parser = Parser {
+        input: input.trim_matches(' ').as_bytes(),
+        pos: 0,
+    };
+
+    let mut dict = Vec::<(String, Member)>::new();
+
+    while !parser.is_empty() {
+        let key = parser.parse_key()?;
+
+        let member = if parser.eat(b'=') {
+            parser.parse_member()?
+        } else {
+            Member::Item(Item {
+                bare: BareItem::Boolean(true),
+                params: parser.parse_params()?,
+            })
+        };
+
+        match dict.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, existing)) => *existing = member,
+            None => dict.push((key, member)),
+        }
+
+        parser.skip_ows();
+
+        if parser.is_empty() {
+            break;
+        }
+
+        if !parser.eat(b',') {
+            return None;
+        }
+
+        parser.skip_ows();
+
+        // trailing commas are not allowed
+        if parser.is_empty() {
+            return None;
+        }
+    }
+
+    Some(dict)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_sp(&mut self) {
+        while self.eat(b' ') {}
+    }
+
+    fn skip_ows(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let input = self.input;
+        let start = self.pos;
+
+        while self.peek().is_some_and(&pred) {
+            self.pos += 1;
+        }
+
+        // only ASCII bytes are accepted by the predicates used
+        std::str::from_utf8(&input[start..self.pos]).unwrap_or_default()
+    }
+
+    fn parse_key(&mut self) -> Option<String> {
+        if !matches!(self.peek()?, b'a'..=b'z' | b'*') {
+            return None;
+        }
+
+        let key =
+            self.take_while(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-' | b'.' | b'*'));
+
+        Some(key.to_owned())
+    }
+
+    fn parse_member(&mut self) -> Option<Member> {
+        if self.eat(b'(') {
+            let mut items = Vec::new();
+
+            loop {
+                self.skip_sp();
+
+                if self.eat(b')') {
+                    return Some(Member::InnerList(items, self.parse_params()?));
+                }
+
+                items.push(self.parse_item()?);
+
+                if !matches!(self.peek()?, b' ' | b')') {
+                    return None;
+                }
+            }
+        }
+
+        self.parse_item().map(Member::Item)
+    }
+
+    fn parse_item(&mut self) -> Option<Item> {
+        Some(Item {
+            bare: self.parse_bare_item()?,
+            params: self.parse_params()?,
+        })
+    }
+
+    fn parse_params(&mut self) -> Option<Params> {
+        let mut params = Params::new();
+
+        while self.eat(b';') {
+            self.skip_sp();
+
+            let key = self.parse_key()?;
+
+            let val = if self.eat(b'=') {
+                self.parse_bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+
+            match params.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, existing)) => *existing = val,
+                None => params.push((key, val)),
+            }
+        }
+
+        Some(params)
+    }
+
+    fn parse_bare_item(&mut self) -> Option<BareItem> {
+        match self.peek()? {
+            b'-' | b'0'..=b'9' => self.parse_integer(),
+            b'"' => self.parse_string(),
+            b':' => self.parse_byte_seq(),
+            b'?' => self.parse_boolean(),
+            b'A'..=b'Z' | b'a'..=b'z' | b'*' => self.parse_token(),
+            _ => None,
+        }
+    }
+
+    fn parse_integer(&mut self) -> Option<BareItem> {
+        let negative = self.eat(b'-');
+        let digits = self.take_while(|b| b.is_ascii_digit());
+
+        if digits.is_empty() || digits.len() > 15 || self.peek() == Some(b'.') {
+            return None;
+        }
+
+        let val = digits.parse::<i64>().ok()?;
+        Some(BareItem::Integer(if negative { -val } else { val }))
+    }
+
+    fn parse_string(&mut self) -> Option<BareItem> {
+        self.pos += 1;
+        let mut val = String::new();
+
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    return Some(BareItem::String(val));
+                }
+
+                b'\\' => {
+                    self.pos += 1;
+                    let escaped = self.peek().filter(|b| matches!(b, b'"' | b'\\'))?;
+                    val.push(char::from(escaped));
+                }
+
+                b @ 0x20..=0x7e => val.push(char::from(b)),
+
+                _ => return None,
+            }
+
+            self.pos += 1;
+        }
+    }
+
+    fn parse_byte_seq(&mut self) -> Option<BareItem> {
+        self.pos += 1;
+        let encoded =
+            self.take_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='));
+        let val = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+
+        self.eat(b':').then_some(BareItem::ByteSeq(val))
+    }
+
+    fn parse_boolean(&mut self) -> Option<BareItem> {
+        self.pos += 1;
+
+        let val = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+
+        self.pos += 1;
+        Some(BareItem::Boolean(val))
+    }
+
+    fn parse_token(&mut self) -> Option<BareItem> {
+        let token =
+            self.take_while(|b| b.is_ascii_alphanumeric() || b":/!#$%&'*+-.^_`|~".contains(&b));
+
+        Some(BareItem::Token(token.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = r#"sig1=("@method" "@path" "content-digest");created=1618884473;keyid="test-key", sig2=:aGVsbG8=:, flag;a=?0, tok=abc/def;b=-3"#;
+
+        let dict = parse_dictionary(input).unwrap();
+        let serialized = dict
+            .iter()
+            .map(|(key, member)| match member {
+                Member::Item(Item {
+                    bare: BareItem::Boolean(true),
+                    params,
+                }) => {
+                    let mut buf = key.clone();
+                    write_params(&mut buf, params);
+                    buf
+                }
+                member => format!("{key}={}", member.serialize()),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        assert_eq!(serialized, input);
+
+        let Member::InnerList(items, params) = &dict[0].1 else {
+            panic!("expected inner list");
+        };
+        assert_eq!(items[2].bare.as_str(), Some("content-digest"));
+        assert_eq!(
+            param(params, "created").and_then(BareItem::as_integer),
+            Some(1618884473)
+        );
+
+        assert_eq!(
+            dict[1].1,
+            Member::Item(Item {
+                bare: BareItem::ByteSeq(b"hello".to_vec()),
+                params: Params::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse_dictionary("").unwrap().is_empty());
+
+        for input in [
+            "a=1,",
+            "A=1",
+            "a=1.5",
+            "a=(1 2",
+            r#"a="unterminated"#,
+            "a=:not base64:",
+            "a=1 b=2",
+            "a=?2",
+        ] {
+            assert!(parse_dictionary(input).is_none(), "{input}");
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use std::sync::Arc;
 
 use actix_utils::future::{ready, Ready};
-use actix_web::{dev, error, Error, FromRequest, HttpRequest};
+use actix_web::{dev, Error, FromRequest, HttpRequest};
 use arc_swap::{ArcSwap, Guard};
 use tracing::debug;
 
+use crate::error::LabError;
+
 /// A wrapper around `ArcSwap` that can be used as an extractor.
 ///
 /// Can serve as a replacement for `Data<RwLock<T>>` in certain situations.
@@ -64,10 +66,9 @@ impl<T: 'static> FromRequest for SwapData<T> {
                 req.match_name().unwrap_or_else(|| req.path())
             );
 
-            ready(Err(error::ErrorInternalServerError(
-                "Requested application data is not configured correctly. \
-                View/enable debug logs for more details.",
-            )))
+            ready(Err(Error::from(LabError::AppDataNotConfigured {
+                type_name: core::any::type_name::<T>(),
+            })))
         }
     }
 }
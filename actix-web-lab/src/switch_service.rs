@@ -0,0 +1,328 @@
+//! Runtime-switchable service.
+//!
+//! See [`SwitchService`] docs.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use actix_service::{Service, ServiceFactory};
+use actix_utils::future::{ready, Ready};
+use actix_web::{
+    dev::{self, ServiceRequest, ServiceResponse},
+    Error, FromRequest, HttpRequest,
+};
+use futures_core::future::LocalBoxFuture;
+use tracing::debug;
+
+use crate::error::LabError;
+
+/// One of the two slots of a [`SwitchService`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwitchSlot {
+    /// The blue slot.
+    Blue,
+
+    /// The green slot.
+    Green,
+}
+
+impl SwitchSlot {
+    /// Returns the other slot.
+    pub fn other(self) -> Self {
+        match self {
+            Self::Blue => Self::Green,
+            Self::Green => Self::Blue,
+        }
+    }
+}
+
+/// A handle for switching the active slot of [`SwitchService`]s.
+///
+/// Handles are cheap to clone and all clones control the same switch, including across worker
+/// threads. Switching takes effect for the next request handled by any worker; requests already
+/// being handled complete on the slot they were dispatched to.
+///
+/// Can be used as an extractor when registered using `App::app_data()`, which is useful for
+/// writing admin endpoints that perform cutovers.
+#[derive(Clone)]
+pub struct SwitchHandle {
+    green: Arc<AtomicBool>,
+}
+
+impl SwitchHandle {
+    /// Constructs a new switch handle with `active` as the active slot.
+    pub fn new(active: SwitchSlot) -> Self {
+        Self {
+            green: Arc::new(AtomicBool::new(active == SwitchSlot::Green)),
+        }
+    }
+
+    /// Returns the active slot.
+    pub fn active(&self) -> SwitchSlot {
+        if self.green.load(Ordering::Acquire) {
+            SwitchSlot::Green
+        } else {
+            SwitchSlot::Blue
+        }
+    }
+
+    /// Makes `slot` the active slot, returning the previously active slot.
+    pub fn switch_to(&self, slot: SwitchSlot) -> SwitchSlot {
+        let prev = self.green.swap(slot == SwitchSlot::Green, Ordering::AcqRel);
+
+        if prev {
+            SwitchSlot::Green
+        } else {
+            SwitchSlot::Blue
+        }
+    }
+
+    /// Makes the inactive slot the active one, returning the newly active slot.
+    pub fn toggle(&self) -> SwitchSlot {
+        let prev = self.green.fetch_xor(true, Ordering::AcqRel);
+
+        if prev {
+            SwitchSlot::Blue
+        } else {
+            SwitchSlot::Green
+        }
+    }
+
+    /// Constructs a new switch service controlled by this handle.
+    ///
+    /// See [`SwitchService`] docs for more details.
+    pub fn service<A, B>(&self, blue: A, green: B) -> SwitchService<A, B> {
+        SwitchService {
+            handle: self.clone(),
+            blue,
+            green,
+        }
+    }
+}
+
+impl fmt::Debug for SwitchHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwitchHandle")
+            .field("active", &self.active())
+            .finish()
+    }
+}
+
+impl FromRequest for SwitchHandle {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _pl: &mut dev::Payload) -> Self::Future {
+        if let Some(handle) = req.app_data::<SwitchHandle>() {
+            ready(Ok(handle.clone()))
+        } else {
+            debug!(
+                "Failed to extract `SwitchHandle` for `{}` handler. For the SwitchHandle extractor \
+                to work correctly, pass the handle to `App::app_data()`.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            ready(Err(Error::from(LabError::AppDataNotConfigured {
+                type_name: core::any::type_name::<SwitchHandle>(),
+            })))
+        }
+    }
+}
+
+/// A service factory holding two inner services, one of which handles all requests.
+///
+/// Each request is dispatched to the service in the active slot of the [`SwitchHandle`] used to
+/// construct it. Switching slots at runtime, for example from an admin endpoint, enables
+/// in-process blue/green cutovers between two implementations of the same handler, scope, or
+/// default service, with an equally quick rollback.
+///
+/// Both services are constructed when the app starts, in every worker, so state held by either
+/// one is kept warm while it is inactive.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, App, HttpResponse, Responder};
+/// use actix_web_lab::web::{SwitchHandle, SwitchSlot};
+///
+/// async fn legacy() -> impl Responder {
+///     HttpResponse::Ok().body("v1")
+/// }
+///
+/// async fn rewrite() -> impl Responder {
+///     HttpResponse::Ok().body("v2")
+/// }
+///
+/// #[post("/admin/cutover")]
+/// async fn cutover(switch: SwitchHandle) -> impl Responder {
+///     format!("{:?} is now active", switch.toggle())
+/// }
+///
+/// // created outside the `HttpServer` factory closure so that all workers share it
+/// let switch = SwitchHandle::new(SwitchSlot::Blue);
+///
+/// App::new()
+///     .app_data(switch.clone())
+///     .service(cutover)
+///     .default_service(switch.service(web::to(legacy), web::to(rewrite)))
+/// # ;
+/// ```
+#[derive(Clone)]
+pub struct SwitchService<A, B> {
+    handle: SwitchHandle,
+    blue: A,
+    green: B,
+}
+
+impl<A, B> fmt::Debug for SwitchService<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SwitchService")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A, B> ServiceFactory<ServiceRequest> for SwitchService<A, B>
+where
+    A: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    B: ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse,
+            Error = Error,
+            InitError = (),
+        > + 'static,
+    A::Future: 'static,
+    <A::Service as Service<ServiceRequest>>::Future: 'static,
+    B::Future: 'static,
+    <B::Service as Service<ServiceRequest>>::Future: 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = SwitchedService<A::Service, B::Service>;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let handle = self.handle.clone();
+        let blue = self.blue.new_service(());
+        let green = self.green.new_service(());
+
+        Box::pin(async move {
+            Ok(SwitchedService {
+                handle,
+                blue: blue.await?,
+                green: green.await?,
+            })
+        })
+    }
+}
+
+/// Service for [`SwitchService`].
+pub struct SwitchedService<A, B> {
+    handle: SwitchHandle,
+    blue: A,
+    green: B,
+}
+
+impl<A, B> Service<ServiceRequest> for SwitchedService<A, B>
+where
+    A: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+    A::Future: 'static,
+    B: Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+    B::Future: 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    // the slot can be switched between readiness checks and calls so both services must be ready
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let blue = self.blue.poll_ready(cx)?;
+        let green = self.green.poll_ready(cx)?;
+
+        if blue.is_ready() && green.is_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        match self.handle.active() {
+            SwitchSlot::Blue => Box::pin(self.blue.call(req)),
+            SwitchSlot::Green => Box::pin(self.green.call(req)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        test::{self, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[test]
+    fn switching() {
+        let handle = SwitchHandle::new(SwitchSlot::Blue);
+        let clone = handle.clone();
+        assert_eq!(handle.active(), SwitchSlot::Blue);
+
+        assert_eq!(clone.toggle(), SwitchSlot::Green);
+        assert_eq!(handle.active(), SwitchSlot::Green);
+
+        assert_eq!(handle.switch_to(SwitchSlot::Green), SwitchSlot::Green);
+        assert_eq!(handle.switch_to(SwitchSlot::Blue), SwitchSlot::Green);
+        assert_eq!(clone.active(), SwitchSlot::Blue);
+        assert_eq!(SwitchSlot::Blue.other(), SwitchSlot::Green);
+    }
+
+    #[actix_web::test]
+    async fn cutover() {
+        let switch = SwitchHandle::new(SwitchSlot::Blue);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(switch.clone())
+                .route(
+                    "/cutover",
+                    web::post().to(|switch: SwitchHandle| async move {
+                        HttpResponse::Ok().body(format!("{:?}", switch.toggle()))
+                    }),
+                )
+                .default_service(switch.service(
+                    web::to(|| async { HttpResponse::Ok().body("blue") }),
+                    web::to(|| async { HttpResponse::Ok().body("green") }),
+                )),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/items").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "blue");
+
+        let req = TestRequest::post().uri("/cutover").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "Green");
+
+        let req = TestRequest::with_uri("/items").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "green");
+
+        switch.switch_to(SwitchSlot::Blue);
+        let req = TestRequest::with_uri("/items").to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await, "blue");
+    }
+}
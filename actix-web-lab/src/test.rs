@@ -3,8 +3,45 @@
 #[doc(inline)]
 #[cfg(test)]
 pub(crate) use crate::test_header_macros::{header_round_trip_test, header_test_module};
+pub use crate::test_http_file::{http_file, HttpFile, HttpFileError};
+pub use crate::test_multipart::MultipartBuilder;
 #[doc(inline)]
 pub use crate::test_request_macros::test_request;
 #[doc(inline)]
 pub use crate::test_response_macros::assert_response_matches;
 pub use crate::test_services::echo_path_service;
+pub use crate::test_streaming::{call_and_collect_sse, SseMessage, StreamReader};
+pub use crate::{
+    clock::MockClock,
+    entropy::SeededEntropy,
+    http_signatures::{sign_request, HttpSigningKey},
+};
+
+#[cfg(feature = "proptest")]
+pub mod arbitrary {
+    //! Property-testing strategies for this crate's typed headers.
+    //!
+    //! Each strategy only generates values which can be serialized to a header value and parsed
+    //! back into an equal value. Combine them with [`assert_header_round_trip()`] to check that
+    //! invariant, or use them as building blocks for generating requests.
+    //!
+    //! Requires the `proptest` crate feature.
+    //!
+    //! # Examples
+    //! ```
+    //! use actix_web_lab::test::arbitrary::{assert_header_round_trip, cache_control};
+    //! use proptest::prelude::*;
+    //!
+    //! proptest! {
+    //!     fn cache_control_round_trips(header in cache_control()) {
+    //!         assert_header_round_trip(header);
+    //!     }
+    //! }
+    //! # cache_control_round_trips();
+    //! ```
+
+    pub use crate::test_arbitrary::{
+        assert_header_round_trip, cache_control, cache_directive, content_length, forwarded,
+        strict_transport_security, x_forwarded_prefix,
+    };
+}
@@ -0,0 +1,435 @@
+//! Table-driven endpoint tests described in `.http` files.
+//!
+//! See [`http_file`] and [`HttpFile`] docs.
+
+use std::{fs, path::Path};
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    http::{
+        header::{HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+};
+use derive_more::{Display, Error};
+
+/// Runs the requests in the `.http` file at `path` against `app`, asserting that each response
+/// matches its expectations.
+///
+/// See [`HttpFile`] for a description of the file format.
+///
+/// # Panics
+/// Panics if the file cannot be read or parsed, or if any response does not match.
+///
+/// # Examples
+/// ```no_run
+/// use actix_web::{test, web, App, HttpResponse};
+/// use actix_web_lab::test::http_file;
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let app = test::init_service(App::new().route("/", web::get().to(HttpResponse::Ok))).await;
+/// http_file(&app, "tests/api.http").await;
+/// # });
+/// ```
+pub async fn http_file<S, B>(app: &S, path: impl AsRef<Path>)
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    let path = path.as_ref();
+
+    let input = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+
+    let file = HttpFile::parse(&input)
+        .unwrap_or_else(|err| panic!("failed to parse {}: {err}", path.display()));
+
+    file.run(app).await;
+}
+
+/// A parsed `.http` file: a list of requests, each with its expected response.
+///
+/// The format is a small subset of the `.http` files used by editor REST clients, with response
+/// expectations written in the style of [Hurl]:
+///
+/// ```text
+/// # comments are allowed before each request
+/// POST /items
+/// Content-Type: application/json
+///
+/// {"name": "widget"}
+///
+/// HTTP 201
+/// Location: /items/1
+///
+/// {"id": 1, "name": "widget"}
+///
+/// ###
+///
+/// GET /items/404
+///
+/// HTTP 404
+/// ```
+///
+/// Entries are separated by lines starting with `###`. Each entry has:
+/// - a request line, containing the method and path (an HTTP version may follow and is ignored);
+/// - request headers, one per line;
+/// - optionally, a blank line followed by the request body;
+/// - a response line, `HTTP` followed by the expected status code;
+/// - expected response headers, one per line, which must be present with exactly these values;
+/// - optionally, a blank line followed by the expected response body.
+///
+/// Trailing whitespace is ignored when comparing bodies. If both the expected and actual response
+/// bodies are valid JSON, they are compared as JSON values, so formatting and key order do not
+/// matter.
+///
+/// # Examples
+/// ```
+/// use actix_web::{test, web, App, HttpResponse};
+/// use actix_web_lab::test::HttpFile;
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let app = test::init_service(
+///     App::new().route("/", web::get().to(|| async { HttpResponse::Ok().body("hello") })),
+/// )
+/// .await;
+///
+/// let file = HttpFile::parse("GET /\n\nHTTP 200\n\nhello\n").unwrap();
+/// file.run(&app).await;
+/// # });
+/// ```
+///
+/// [Hurl]: https://hurl.dev
+#[derive(Debug, Clone)]
+pub struct HttpFile {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Line number of request line.
+    line: usize,
+    method: Method,
+    uri: String,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: String,
+    status: StatusCode,
+    expected_headers: Vec<(HeaderName, HeaderValue)>,
+    expected_body: Option<String>,
+}
+
+impl HttpFile {
+    /// Parses the contents of a `.http` file.
+    ///
+    /// # Errors
+    /// Returns an error, with the line number at which it occurred, if the input is malformed.
+    pub fn parse(input: &str) -> Result<Self, HttpFileError> {
+        let mut entries = Vec::new();
+        let mut block = Vec::new();
+
+        for (idx, line) in input.lines().enumerate() {
+            if line.starts_with("###") {
+                if let Some(entry) = Entry::parse(&block)? {
+                    entries.push(entry);
+                }
+
+                block.clear();
+            } else {
+                block.push((idx + 1, line));
+            }
+        }
+
+        if let Some(entry) = Entry::parse(&block)? {
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the number of requests in the file.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the file contains no requests.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sends each request to `app`, in order, asserting that each response matches its
+    /// expectations.
+    ///
+    /// # Panics
+    /// Panics if any response does not match, identifying the request by its line number.
+    pub async fn run<S, B>(&self, app: &S)
+    where
+        S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody,
+    {
+        for entry in &self.entries {
+            entry.run(app).await;
+        }
+    }
+}
+
+impl Entry {
+    /// Parses an entry from its lines, returning `None` if it contains no request.
+    fn parse(lines: &[(usize, &str)]) -> Result<Option<Self>, HttpFileError> {
+        let mut lines = lines
+            .iter()
+            .copied()
+            .skip_while(|(_, line)| line.trim().is_empty() || line.starts_with('#'));
+
+        let Some((line, request_line)) = lines.next() else {
+            return Ok(None);
+        };
+
+        let mut parts = request_line.split_whitespace();
+
+        let method = parts
+            .next()
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok())
+            .ok_or_else(|| HttpFileError::new(line, "expected request method"))?;
+
+        let uri = parts
+            .next()
+            .ok_or_else(|| HttpFileError::new(line, "expected request path"))?
+            .to_owned();
+
+        let mut headers = Vec::new();
+        let mut body = Vec::new();
+        let mut in_body = false;
+        let mut status = None;
+
+        for (line, text) in lines.by_ref() {
+            if let Some(code) = parse_response_line(line, text)? {
+                status = Some(code);
+                break;
+            }
+
+            if in_body {
+                body.push(text);
+            } else if text.trim().is_empty() {
+                in_body = true;
+            } else {
+                headers.push(parse_header(line, text)?);
+            }
+        }
+
+        let status = status
+            .ok_or_else(|| HttpFileError::new(line, "expected response line, e.g. `HTTP 200`"))?;
+
+        let mut expected_headers = Vec::new();
+        let mut expected_body = Vec::new();
+        let mut in_body = false;
+
+        for (line, text) in lines {
+            if in_body {
+                expected_body.push(text);
+            } else if text.trim().is_empty() {
+                in_body = true;
+            } else {
+                expected_headers.push(parse_header(line, text)?);
+            }
+        }
+
+        let expected_body = join_body(&expected_body);
+
+        Ok(Some(Self {
+            line,
+            method,
+            uri,
+            headers,
+            body: join_body(&body),
+            status,
+            expected_headers,
+            expected_body: (!expected_body.is_empty()).then_some(expected_body),
+        }))
+    }
+
+    async fn run<S, B>(&self, app: &S)
+    where
+        S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+        B: MessageBody,
+    {
+        let name = format!("{} {} (line {})", self.method, self.uri, self.line);
+
+        let mut req = actix_web::test::TestRequest::default()
+            .method(self.method.clone())
+            .uri(&self.uri);
+
+        for header in &self.headers {
+            req = req.append_header(header.clone());
+        }
+
+        let req = req.set_payload(self.body.clone()).to_request();
+        let res = actix_web::test::call_service(app, req).await;
+
+        assert_eq!(res.status(), self.status, "unexpected status for {name}");
+
+        for (header_name, value) in &self.expected_headers {
+            assert_eq!(
+                res.headers().get(header_name),
+                Some(value),
+                "unexpected `{header_name}` header for {name}",
+            );
+        }
+
+        let body = actix_web::test::read_body(res).await;
+
+        if let Some(expected) = &self.expected_body {
+            let actual = String::from_utf8_lossy(&body);
+            let actual = actual.trim_end();
+
+            match (
+                serde_json::from_str::<serde_json::Value>(expected),
+                serde_json::from_str::<serde_json::Value>(actual),
+            ) {
+                (Ok(expected), Ok(actual)) => {
+                    assert_eq!(actual, expected, "unexpected JSON body for {name}");
+                }
+                _ => assert_eq!(actual, expected, "unexpected body for {name}"),
+            }
+        }
+    }
+}
+
+/// Parses a response line (e.g., `HTTP 200` or `HTTP/1.1 200`), returning `None` if `text` is not
+/// a response line.
+fn parse_response_line(line: usize, text: &str) -> Result<Option<StatusCode>, HttpFileError> {
+    let mut parts = text.split_whitespace();
+
+    match parts.next() {
+        Some(version) if version == "HTTP" || version.starts_with("HTTP/") => {}
+        _ => return Ok(None),
+    }
+
+    parts
+        .next()
+        .and_then(|code| code.parse::<u16>().ok())
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .map(Some)
+        .ok_or_else(|| HttpFileError::new(line, "expected status code after `HTTP`"))
+}
+
+fn parse_header(line: usize, text: &str) -> Result<(HeaderName, HeaderValue), HttpFileError> {
+    let (name, value) = text
+        .split_once(':')
+        .ok_or_else(|| HttpFileError::new(line, "expected header, e.g. `Name: value`"))?;
+
+    let name = HeaderName::try_from(name.trim())
+        .map_err(|_| HttpFileError::new(line, "invalid header name"))?;
+
+    let value = HeaderValue::try_from(value.trim())
+        .map_err(|_| HttpFileError::new(line, "invalid header value"))?;
+
+    Ok((name, value))
+}
+
+/// Joins body lines, ignoring trailing whitespace.
+fn join_body(lines: &[&str]) -> String {
+    lines.join("\n").trim_end().to_owned()
+}
+
+/// Error returned when a `.http` file is malformed.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "line {line}: {message}")]
+pub struct HttpFileError {
+    line: usize,
+    message: String,
+}
+
+impl HttpFileError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+
+    /// Returns line number, starting at 1, at which the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{web, App, HttpResponse};
+
+    use super::*;
+
+    const FILE: &str = r#"
+# create an item
+POST /items
+Content-Type: application/json
+
+{"name": "widget"}
+
+HTTP 201
+Location: /items/widget
+
+{ "name": "widget",
+  "id": 1 }
+
+###
+
+GET /items/missing HTTP/1.1
+
+HTTP/1.1 404
+"#;
+
+    async fn create(body: web::Json<serde_json::Value>) -> HttpResponse {
+        let name = body["name"].as_str().unwrap();
+
+        HttpResponse::Created()
+            .insert_header(("location", format!("/items/{name}")))
+            .json(serde_json::json!({ "id": 1, "name": name }))
+    }
+
+    #[actix_web::test]
+    async fn runs_entries() {
+        let file = HttpFile::parse(FILE).unwrap();
+        assert_eq!(file.len(), 2);
+
+        let app = actix_web::test::init_service(
+            App::new()
+                .route("/items", web::post().to(create))
+                .route("/items/{name}", web::get().to(HttpResponse::NotFound)),
+        )
+        .await;
+
+        file.run(&app).await;
+    }
+
+    #[actix_web::test]
+    #[should_panic(expected = "unexpected status for GET / (line 1)")]
+    async fn reports_mismatch() {
+        let app = actix_web::test::init_service(App::new()).await;
+        HttpFile::parse("GET /\n\nHTTP 200\n")
+            .unwrap()
+            .run(&app)
+            .await;
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(HttpFile::parse("").unwrap().is_empty());
+        assert!(HttpFile::parse("###\n# nothing here\n###")
+            .unwrap()
+            .is_empty());
+
+        for (input, line) in [
+            ("GET", 1),
+            ("GET /\nnot a header\n\nHTTP 200", 2),
+            ("\nGET /\n", 2),
+            ("GET /\n\nHTTP abc", 3),
+            ("GET /\n\nHTTP 200\nbad header\n", 4),
+        ] {
+            let err = HttpFile::parse(input).unwrap_err();
+            assert_eq!(err.line(), line, "{input:?}");
+        }
+    }
+}
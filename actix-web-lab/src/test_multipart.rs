@@ -0,0 +1,124 @@
+use actix_web::{
+    http::header::{self, HeaderValue},
+    test::TestRequest,
+    web::{Bytes, BytesMut},
+};
+
+const BOUNDARY: &str = "actix-web-lab-4f0e8a3b9c7d2e61";
+const CONTENT_TYPE: &str = "multipart/form-data; boundary=actix-web-lab-4f0e8a3b9c7d2e61";
+
+/// Builder for `multipart/form-data` request bodies.
+///
+/// # Examples
+/// ```
+/// use actix_web::test::TestRequest;
+/// use actix_web_lab::test::MultipartBuilder;
+///
+/// let req = MultipartBuilder::new()
+///     .text("title", "Holiday")
+///     .file("photo", "beach.jpg", "image/jpeg", b"\xFF\xD8\xFF")
+///     .into_test_request(TestRequest::post().uri("/upload"))
+///     .to_request();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultipartBuilder {
+    body: BytesMut,
+}
+
+impl MultipartBuilder {
+    /// Constructs new, empty multipart body builder.
+    pub fn new() -> Self {
+        Self {
+            body: BytesMut::new(),
+        }
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: &str, value: impl AsRef<str>) -> Self {
+        let headers = format!("Content-Disposition: form-data; name=\"{name}\"\r\n");
+        self.part(&headers, value.as_ref().as_bytes())
+    }
+
+    /// Adds a file field.
+    pub fn file(
+        self,
+        name: &str,
+        file_name: &str,
+        content_type: &str,
+        contents: impl AsRef<[u8]>,
+    ) -> Self {
+        let headers = format!(
+            "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\n\
+            Content-Type: {content_type}\r\n"
+        );
+        self.part(&headers, contents.as_ref())
+    }
+
+    fn part(mut self, headers: &str, contents: &[u8]) -> Self {
+        self.body.extend_from_slice(b"--");
+        self.body.extend_from_slice(BOUNDARY.as_bytes());
+        self.body.extend_from_slice(b"\r\n");
+        self.body.extend_from_slice(headers.as_bytes());
+        self.body.extend_from_slice(b"\r\n");
+        self.body.extend_from_slice(contents);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// Returns the `Content-Type` header value, including boundary, for the body.
+    pub fn content_type(&self) -> HeaderValue {
+        HeaderValue::from_static(CONTENT_TYPE)
+    }
+
+    /// Finishes building the body.
+    pub fn finish(mut self) -> Bytes {
+        self.body.extend_from_slice(b"--");
+        self.body.extend_from_slice(BOUNDARY.as_bytes());
+        self.body.extend_from_slice(b"--\r\n");
+        self.body.freeze()
+    }
+
+    /// Sets the body and `Content-Type` header of `req`.
+    pub fn into_test_request(self, req: TestRequest) -> TestRequest {
+        req.insert_header((header::CONTENT_TYPE, self.content_type()))
+            .set_payload(self.finish())
+    }
+}
+
+impl Default for MultipartBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_body() {
+        let builder =
+            MultipartBuilder::new()
+                .text("title", "hi")
+                .file("doc", "a.txt", "text/plain", "abc");
+
+        assert_eq!(
+            builder.content_type(),
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        );
+
+        assert_eq!(
+            builder.finish(),
+            format!(
+                "--{BOUNDARY}\r\n\
+                Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+                hi\r\n\
+                --{BOUNDARY}\r\n\
+                Content-Disposition: form-data; name=\"doc\"; filename=\"a.txt\"\r\n\
+                Content-Type: text/plain\r\n\r\n\
+                abc\r\n\
+                --{BOUNDARY}--\r\n"
+            ),
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Helpers for testing streaming responses.
+//!
+//! See [`StreamReader`] docs.
+
+use std::{pin::Pin, str, time::Duration};
+
+use actix_http::Request;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceResponse},
+    rt::time::timeout,
+    test, Error,
+};
+use bytes::{Buf as _, Bytes, BytesMut};
+use futures_util::future::poll_fn;
+use serde::de::DeserializeOwned;
+
+use crate::BoxError;
+
+/// Reads items from a streaming response body as they arrive.
+///
+/// Every read takes a timeout so that tests of endpoints which stream indefinitely fail instead of
+/// hanging. Dropping the reader drops the body, which cancels the stream in the same way as a
+/// client disconnecting.
+///
+/// All methods panic if the body yields an error, if a read times out, or if the body cannot be
+/// parsed in the requested format.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{get, test, App, Responder};
+/// use actix_web_lab::{sse, test::StreamReader};
+/// use futures_util::stream;
+///
+/// #[get("/events")]
+/// async fn events() -> impl Responder {
+///     sse::Sse::from_infallible_stream(stream::repeat(sse::Data::new("ping").into()))
+/// }
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let app = test::init_service(App::new().service(events)).await;
+/// let req = test::TestRequest::with_uri("/events").to_request();
+/// let res = test::call_service(&app, req).await;
+///
+/// let mut reader = StreamReader::new(res.into_body());
+/// let sse_events = reader.take_sse(3, Duration::from_secs(1)).await;
+/// assert!(sse_events.iter().all(|ev| ev.data() == "ping"));
+/// # });
+/// ```
+pub struct StreamReader<B> {
+    body: Pin<Box<B>>,
+    buf: BytesMut,
+    done: bool,
+}
+
+impl<B: MessageBody> StreamReader<B> {
+    /// Constructs a new reader for `body`.
+    pub fn new(body: B) -> Self {
+        Self {
+            body: Box::pin(body),
+            buf: BytesMut::new(),
+            done: false,
+        }
+    }
+
+    /// Polls the body for its next chunk, returning `None` when the body has ended.
+    async fn poll_chunk(&mut self, dur: Duration) -> Option<Bytes> {
+        if self.done {
+            return None;
+        }
+
+        let body = &mut self.body;
+        let res = timeout(dur, poll_fn(|cx| body.as_mut().poll_next(cx)))
+            .await
+            .unwrap_or_else(|_| panic!("timed out after {dur:?} waiting for body chunk"));
+
+        match res {
+            Some(Ok(chunk)) => Some(chunk),
+            Some(Err(err)) => {
+                let err: BoxError = err.into();
+                panic!("body yielded an error: {err}");
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+
+    /// Returns the next chunk of the body, as yielded by the body, or `None` if the body has ended.
+    ///
+    /// Any data buffered by previous structured reads is returned first.
+    pub async fn next_chunk(&mut self, timeout: Duration) -> Option<Bytes> {
+        if !self.buf.is_empty() {
+            return Some(self.buf.split().freeze());
+        }
+
+        self.poll_chunk(timeout).await
+    }
+
+    /// Reads until `delim` is found, returning the bytes before it, or `None` if the body ended
+    /// cleanly before any more data was received.
+    async fn read_until(&mut self, delim: &[u8], timeout: Duration) -> Option<Bytes> {
+        loop {
+            if let Some(pos) = self
+                .buf
+                .windows(delim.len())
+                .position(|window| window == delim)
+            {
+                let item = self.buf.split_to(pos).freeze();
+                self.buf.advance(delim.len());
+                return Some(item);
+            }
+
+            match self.poll_chunk(timeout).await {
+                Some(chunk) => self.buf.extend_from_slice(&chunk),
+                None if self.buf.is_empty() => return None,
+                None => panic!(
+                    "body ended with incomplete item: {:?}",
+                    String::from_utf8_lossy(&self.buf),
+                ),
+            }
+        }
+    }
+
+    /// Returns the next server-sent event, or `None` if the body has ended.
+    ///
+    /// Comment-only messages, such as keep-alive pings, are skipped.
+    pub async fn next_sse(&mut self, timeout: Duration) -> Option<SseMessage> {
+        loop {
+            let frame = self.read_until(b"\n\n", timeout).await?;
+            let frame = str::from_utf8(&frame).expect("SSE message should be valid UTF-8");
+
+            if let Some(msg) = SseMessage::parse(frame) {
+                return Some(msg);
+            }
+        }
+    }
+
+    /// Returns the next line of an NDJSON body, deserialized as `T`, or `None` if the body has
+    /// ended.
+    ///
+    /// Empty lines are skipped.
+    pub async fn next_ndjson<T: DeserializeOwned>(&mut self, timeout: Duration) -> Option<T> {
+        loop {
+            let line = self.read_until(b"\n", timeout).await?;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            return Some(
+                serde_json::from_slice(&line)
+                    .unwrap_or_else(|err| panic!("NDJSON line should deserialize: {err}")),
+            );
+        }
+    }
+
+    /// Reads exactly `n` server-sent events, allowing `timeout` for each.
+    ///
+    /// # Panics
+    /// Also panics if the body ends before `n` events were read.
+    pub async fn take_sse(&mut self, n: usize, timeout: Duration) -> Vec<SseMessage> {
+        let mut events = Vec::with_capacity(n);
+
+        while events.len() < n {
+            match self.next_sse(timeout).await {
+                Some(msg) => events.push(msg),
+                None => panic!("body ended after {} of {n} SSE messages", events.len()),
+            }
+        }
+
+        events
+    }
+
+    /// Reads exactly `n` NDJSON items, allowing `timeout` for each.
+    ///
+    /// # Panics
+    /// Also panics if the body ends before `n` items were read.
+    pub async fn take_ndjson<T: DeserializeOwned>(
+        &mut self,
+        n: usize,
+        timeout: Duration,
+    ) -> Vec<T> {
+        let mut items = Vec::with_capacity(n);
+
+        while items.len() < n {
+            match self.next_ndjson(timeout).await {
+                Some(item) => items.push(item),
+                None => panic!("body ended after {} of {n} NDJSON items", items.len()),
+            }
+        }
+
+        items
+    }
+
+    /// Asserts that the body ends within `dur`, discarding any remaining data.
+    ///
+    /// Useful for checking that a stream is closed after triggering server-side shutdown of its
+    /// source.
+    pub async fn assert_ends(mut self, dur: Duration) {
+        self.buf.clear();
+
+        let body = &mut self.body;
+        let drain = async {
+            while let Some(res) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                if let Err(err) = res {
+                    let err: BoxError = err.into();
+                    panic!("body yielded an error: {err}");
+                }
+            }
+        };
+
+        if !self.done {
+            timeout(dur, drain)
+                .await
+                .unwrap_or_else(|_| panic!("body did not end within {dur:?}"));
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for StreamReader<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("buf", &self.buf)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A server-sent event read by [`StreamReader`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SseMessage {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseMessage {
+    /// Parses an event-stream message, returning `None` if it contains only comments.
+    fn parse(frame: &str) -> Option<Self> {
+        let mut msg = Self::default();
+        let mut data_lines = Vec::new();
+        let mut has_fields = false;
+
+        for line in frame.lines() {
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            has_fields = true;
+
+            match field {
+                "event" => msg.event = Some(value.to_owned()),
+                "data" => data_lines.push(value),
+                "id" => msg.id = Some(value.to_owned()),
+                "retry" => msg.retry = value.parse().ok().map(Duration::from_millis),
+                _ => {}
+            }
+        }
+
+        msg.data = data_lines.join("\n");
+        has_fields.then_some(msg)
+    }
+
+    /// Returns the event name, if set.
+    pub fn event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Returns the event data, with multiple data lines joined by newlines.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns the event ID, if set.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the reconnection time, if set.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+}
+
+/// Calls `app` with `req` and reads `n` server-sent events from the response, allowing `timeout`
+/// for each.
+///
+/// The response body is dropped afterwards, cancelling the stream.
+///
+/// # Panics
+/// Panics if the response is not successful or under the same conditions as
+/// [`StreamReader::take_sse()`].
+pub async fn call_and_collect_sse<S, B>(
+    app: &S,
+    req: Request,
+    n: usize,
+    timeout: Duration,
+) -> Vec<SseMessage>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let res = test::call_service(app, req).await;
+
+    assert!(
+        res.status().is_success(),
+        "SSE response has non-success status: {}",
+        res.status(),
+    );
+
+    StreamReader::new(res.into_body())
+        .take_sse(n, timeout)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_web::{web, App, Responder as _};
+    use futures_util::stream;
+    use serde_json::Value;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::{respond::NdJson, sse};
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[actix_web::test]
+    async fn collects_sse_messages() {
+        let app = test::init_service(App::new().route(
+            "/",
+            web::get().to(|| async {
+                sse::Sse::from_infallible_stream(stream::iter([
+                    sse::Data::new("foo").event("greeting").into(),
+                    sse::Event::Comment("keep-alive".into()),
+                    sse::Data::new("bar\nbaz").id("2").into(),
+                ]))
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::default().to_request();
+        let events = call_and_collect_sse(&app, req, 2, TIMEOUT).await;
+
+        assert_eq!(events[0].event(), Some("greeting"));
+        assert_eq!(events[0].data(), "foo");
+        assert_eq!(events[1].id(), Some("2"));
+        assert_eq!(events[1].data(), "bar\nbaz");
+    }
+
+    #[actix_web::test]
+    async fn reads_ndjson_items() {
+        let body = NdJson::new_infallible(stream::iter([
+            serde_json::json!({ "id": 1 }),
+            serde_json::json!({ "id": 2 }),
+        ]))
+        .into_body_stream();
+
+        let mut reader = StreamReader::new(body);
+        let items = reader.take_ndjson::<Value>(2, TIMEOUT).await;
+        assert_eq!(items[1]["id"], 2);
+
+        reader.assert_ends(TIMEOUT).await;
+    }
+
+    #[actix_web::test]
+    async fn assert_ends_after_source_closes() {
+        let (tx, rx) = mpsc::channel::<Result<sse::Event, Infallible>>(4);
+        let res =
+            sse::Sse::from_receiver(rx).respond_to(&test::TestRequest::default().to_http_request());
+
+        let mut reader = StreamReader::new(res.into_body());
+
+        tx.send(Ok(sse::Data::new("foo").into())).await.unwrap();
+        assert_eq!(reader.next_sse(TIMEOUT).await.unwrap().data(), "foo");
+
+        drop(tx);
+        reader.assert_ends(TIMEOUT).await;
+    }
+
+    #[actix_web::test]
+    #[should_panic = "timed out"]
+    async fn read_times_out() {
+        let mut reader = StreamReader::new(sse::Sse::from_infallible_stream(stream::pending()));
+        reader.next_chunk(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,467 @@
+//! Text extractor with charset decoding and const-generic payload size limit.
+//!
+//! See docs for [`Text`].
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{dev, http::StatusCode, web, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use derive_more::{Display, Error};
+use tracing::debug;
+
+use crate::bytes::{BytesBody, BytesPayloadError};
+
+/// Default text payload size limit of 4MiB.
+pub const DEFAULT_TEXT_LIMIT: usize = 4_194_304;
+
+/// Text extractor with charset decoding and const-generic payload size limit.
+///
+/// # Extractor
+/// Reads the request body and decodes it using the `charset` parameter of its `Content-Type`
+/// header. Requests without a charset are decoded as UTF-8. A leading byte order mark is removed.
+///
+/// Supported charsets are:
+/// - UTF-8 (and US-ASCII, which is a subset of it);
+/// - UTF-16, UTF-16LE, and UTF-16BE; UTF-16 without a byte order mark is decoded as big-endian;
+/// - ISO-8859-1 (Latin-1);
+/// - with the `encoding` crate feature, any other encoding defined by the [WHATWG Encoding
+///   Standard], such as Windows-1252 or Shift_JIS.
+///
+/// Requests with other charsets are rejected with a `415 Unsupported Media Type` response.
+///
+/// By default, bodies containing byte sequences which are invalid in their charset are rejected
+/// with a `400 Bad Request` response. Registering a [`TextConfig`] as app data can instead cause
+/// invalid sequences to be replaced with `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// Use the `LIMIT` const generic parameter to control the payload size limit. The default limit
+/// that is exported (`DEFAULT_TEXT_LIMIT`) is 4MiB. The limit applies to the encoded body.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::{InvalidSequences, Text, TextConfig};
+///
+/// #[post("/")]
+/// async fn index(text: Text) -> String {
+///     format!("Received {} characters.", text.chars().count())
+/// }
+///
+/// App::new()
+///     .app_data(TextConfig::default().invalid_sequences(InvalidSequences::Replace))
+///     .service(index)
+/// # ;
+/// ```
+///
+/// [WHATWG Encoding Standard]: https://encoding.spec.whatwg.org
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text<const LIMIT: usize = DEFAULT_TEXT_LIMIT>(pub String);
+
+impl<const LIMIT: usize> std::ops::Deref for Text<LIMIT> {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const LIMIT: usize> std::ops::DerefMut for Text<LIMIT> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const LIMIT: usize> Text<LIMIT> {
+    /// Unwraps into inner string.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+/// See [here](#extractor) for example of usage as an extractor.
+impl<const LIMIT: usize> FromRequest for Text<LIMIT> {
+    type Error = TextPayloadError;
+    type Future = TextExtractFut<LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let invalid_sequences = TextConfig::from_req(req).invalid_sequences;
+
+        let charset = req.mime_type().ok().flatten().and_then(|mime| {
+            mime.get_param(mime::CHARSET)
+                .map(|cs| cs.as_str().to_owned())
+        });
+
+        let state = match charset
+            .as_deref()
+            .map_or(Some(Charset::Utf8), Charset::from_label)
+        {
+            Some(charset) => TextState::Body {
+                body: BytesBody::new(req, payload),
+                charset,
+            },
+            None => TextState::Error(Some(TextPayloadError::UnsupportedCharset {
+                charset: charset.unwrap_or_default(),
+            })),
+        };
+
+        TextExtractFut {
+            req: Some(req.clone()),
+            invalid_sequences,
+            state,
+        }
+    }
+}
+
+/// Policy for byte sequences which are invalid in the charset of a [`Text`] body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum InvalidSequences {
+    /// Rejects the body with a `400 Bad Request` response.
+    #[default]
+    Reject,
+
+    /// Replaces each invalid sequence with `U+FFFD REPLACEMENT CHARACTER`.
+    Replace,
+}
+
+/// Configuration for the [`Text`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data).
+#[derive(Debug, Clone, Default)]
+pub struct TextConfig {
+    invalid_sequences: InvalidSequences,
+}
+
+const DEFAULT_CONFIG: TextConfig = TextConfig {
+    invalid_sequences: InvalidSequences::Reject,
+};
+
+impl TextConfig {
+    /// Sets policy for invalid byte sequences.
+    ///
+    /// Defaults to [`InvalidSequences::Reject`].
+    pub fn invalid_sequences(mut self, policy: InvalidSequences) -> Self {
+        self.invalid_sequences = policy;
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Charsets supported by the [`Text`] extractor.
+#[derive(Debug, Clone, Copy)]
+enum Charset {
+    Utf8,
+    Utf16(Option<Endian>),
+    Latin1,
+    #[cfg(feature = "encoding")]
+    Whatwg(&'static encoding_rs::Encoding),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Big,
+    Little,
+}
+
+impl Charset {
+    fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim().to_ascii_lowercase();
+
+        let charset = match label.as_str() {
+            "utf-8" | "utf8" | "us-ascii" | "ascii" => Self::Utf8,
+            "utf-16" => Self::Utf16(None),
+            "utf-16be" => Self::Utf16(Some(Endian::Big)),
+            "utf-16le" => Self::Utf16(Some(Endian::Little)),
+            "iso-8859-1" | "iso_8859-1" | "latin1" | "l1" => Self::Latin1,
+
+            #[cfg(feature = "encoding")]
+            label => Self::Whatwg(encoding_rs::Encoding::for_label(label.as_bytes())?),
+
+            #[cfg(not(feature = "encoding"))]
+            _ => return None,
+        };
+
+        Some(charset)
+    }
+
+    fn decode(self, bytes: &[u8], policy: InvalidSequences) -> Result<String, TextPayloadError> {
+        match self {
+            Self::Utf8 => {
+                let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+                match policy {
+                    InvalidSequences::Reject => std::str::from_utf8(bytes)
+                        .map(str::to_owned)
+                        .map_err(|_| TextPayloadError::InvalidSequence),
+                    InvalidSequences::Replace => Ok(String::from_utf8_lossy(bytes).into_owned()),
+                }
+            }
+
+            Self::Utf16(endian) => decode_utf16(bytes, endian, policy),
+
+            Self::Latin1 => Ok(bytes.iter().map(|&b| char::from(b)).collect()),
+
+            #[cfg(feature = "encoding")]
+            Self::Whatwg(encoding) => {
+                let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+
+                if had_errors && policy == InvalidSequences::Reject {
+                    Err(TextPayloadError::InvalidSequence)
+                } else {
+                    Ok(text.into_owned())
+                }
+            }
+        }
+    }
+}
+
+/// Decodes UTF-16, using the byte order mark, if present, when `endian` is not specified.
+fn decode_utf16(
+    bytes: &[u8],
+    endian: Option<Endian>,
+    policy: InvalidSequences,
+) -> Result<String, TextPayloadError> {
+    let (bytes, endian) = match (bytes, endian) {
+        ([0xFE, 0xFF, rest @ ..], None | Some(Endian::Big)) => (rest, Endian::Big),
+        ([0xFF, 0xFE, rest @ ..], None | Some(Endian::Little)) => (rest, Endian::Little),
+        (bytes, endian) => (bytes, endian.unwrap_or(Endian::Big)),
+    };
+
+    let chunks = bytes.chunks_exact(2);
+    let has_trailing_byte = !chunks.remainder().is_empty();
+
+    let units = chunks.map(|unit| match endian {
+        Endian::Big => u16::from_be_bytes([unit[0], unit[1]]),
+        Endian::Little => u16::from_le_bytes([unit[0], unit[1]]),
+    });
+
+    let mut text = String::with_capacity(bytes.len() / 2);
+
+    let chars = char::decode_utf16(units)
+        .map(|ch| ch.map_err(drop))
+        .chain(has_trailing_byte.then_some(Err(())));
+
+    for ch in chars {
+        match (ch, policy) {
+            (Ok(ch), _) => text.push(ch),
+            (Err(()), InvalidSequences::Replace) => text.push(char::REPLACEMENT_CHARACTER),
+            (Err(()), InvalidSequences::Reject) => return Err(TextPayloadError::InvalidSequence),
+        }
+    }
+
+    Ok(text)
+}
+
+enum TextState<const LIMIT: usize> {
+    Error(Option<TextPayloadError>),
+    Body {
+        body: BytesBody<LIMIT>,
+        charset: Charset,
+    },
+}
+
+/// Future returned by the [`Text`] extractor.
+pub struct TextExtractFut<const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    invalid_sequences: InvalidSequences,
+    state: TextState<LIMIT>,
+}
+
+impl<const LIMIT: usize> Future for TextExtractFut<LIMIT> {
+    type Output = Result<Text<LIMIT>, TextPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match &mut this.state {
+            TextState::Error(err) => Err(err.take().unwrap()),
+            TextState::Body { body, charset } => match ready!(Pin::new(body).poll(cx)) {
+                Ok(bytes) => charset.decode(&bytes, this.invalid_sequences),
+                Err(err) => Err(TextPayloadError::Payload(err)),
+            },
+        };
+
+        let res = res.map(Text).map_err(|err| {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to extract Text from payload in handler: {}",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            err
+        });
+
+        Poll::Ready(res)
+    }
+}
+
+/// A set of errors that can occur when extracting text payloads.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum TextPayloadError {
+    /// Charset of the request is not supported.
+    #[display(fmt = "Charset `{charset}` is not supported.")]
+    UnsupportedCharset {
+        /// Name of the unsupported charset.
+        #[error(not(source))]
+        charset: String,
+    },
+
+    /// Payload contains a byte sequence which is invalid in its charset.
+    #[display(fmt = "Payload contains a byte sequence which is invalid in its charset.")]
+    InvalidSequence,
+
+    /// Payload could not be read or exceeded the size limit.
+    #[display(fmt = "{_0}")]
+    Payload(BytesPayloadError),
+}
+
+impl ResponseError for TextPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedCharset { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::InvalidSequence => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::header, test::TestRequest};
+
+    use super::*;
+
+    async fn extract(
+        content_type: &str,
+        body: &'static [u8],
+        config: TextConfig,
+    ) -> Result<String, TextPayloadError> {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .app_data(config)
+            .set_payload(body)
+            .to_http_parts();
+
+        Text::<DEFAULT_TEXT_LIMIT>::from_request(&req, &mut pl)
+            .await
+            .map(Text::into_inner)
+    }
+
+    fn replace() -> TextConfig {
+        TextConfig::default().invalid_sequences(InvalidSequences::Replace)
+    }
+
+    #[actix_web::test]
+    async fn utf8() {
+        let text = extract("text/plain", "h\u{e9}llo".as_bytes(), TextConfig::default()).await;
+        assert_eq!(text.unwrap(), "h\u{e9}llo");
+
+        let text = extract(
+            "text/plain; charset=UTF-8",
+            b"\xEF\xBB\xBFhi",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "hi");
+
+        let err = extract("text/plain", b"a\xFFb", TextConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        let text = extract("text/plain", b"a\xFFb", replace()).await;
+        assert_eq!(text.unwrap(), "a\u{FFFD}b");
+    }
+
+    #[actix_web::test]
+    async fn utf16() {
+        let text = extract(
+            "text/plain; charset=utf-16le",
+            b"h\0i\0",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "hi");
+
+        let text = extract(
+            "text/plain; charset=utf-16",
+            b"\xFF\xFEh\0i\0",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "hi");
+
+        let text = extract(
+            "text/plain; charset=utf-16",
+            b"\0h\0i",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "hi");
+
+        // unpaired surrogate and trailing byte
+        let body = b"\0h\xD8\x00\0i\0";
+        let err = extract("text/plain; charset=utf-16be", body, TextConfig::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TextPayloadError::InvalidSequence));
+
+        let text = extract("text/plain; charset=utf-16be", body, replace()).await;
+        assert_eq!(text.unwrap(), "h\u{FFFD}i\u{FFFD}");
+    }
+
+    #[actix_web::test]
+    async fn latin1() {
+        let text = extract(
+            "text/plain; charset=ISO-8859-1",
+            b"caf\xE9",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "caf\u{e9}");
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[actix_web::test]
+    async fn unsupported_charset() {
+        let err = extract(
+            "text/plain; charset=shift_jis",
+            b"hi",
+            TextConfig::default(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(err.to_string(), "Charset `shift_jis` is not supported.");
+    }
+
+    #[cfg(feature = "encoding")]
+    #[actix_web::test]
+    async fn whatwg_encodings() {
+        let text = extract(
+            "text/plain; charset=windows-1252",
+            b"\x80",
+            TextConfig::default(),
+        );
+        assert_eq!(text.await.unwrap(), "\u{20AC}");
+
+        let err = extract("text/plain; charset=bogus", b"hi", TextConfig::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[actix_web::test]
+    async fn limit() {
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(&b"foo foo foo foo"[..])
+            .to_http_parts();
+
+        let err = Text::<10>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
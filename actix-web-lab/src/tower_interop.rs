@@ -0,0 +1,261 @@
+//! Interoperability with `tower` services and layers.
+//!
+//! See [`TowerLayer`], [`FromTowerService`], and [`IntoTowerService`] docs.
+
+use std::{
+    future::{poll_fn, ready, Ready},
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_service::always_ready;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_core::future::LocalBoxFuture;
+use tower_layer::Layer;
+
+/// A middleware that applies a `tower` layer to an Actix Web service.
+///
+/// The wrapped Actix Web service is exposed to the layer as an [`IntoTowerService`] and the service
+/// produced by the layer is driven as a [`FromTowerService`]. Layers therefore see requests and
+/// responses as [`ServiceRequest`]s and [`ServiceResponse`]s.
+///
+/// This is an experiment. It works with layers that are generic over request, response, and error
+/// types, such as those that only add behavior around calls or map requests and responses. Layers
+/// that require `http::Request` and `http::Response` types (e.g., most of `tower-http`) or `Send`
+/// services and errors are not supported. Errors from the layer's service must be convertible into
+/// Actix Web's [`Error`] type.
+///
+/// # Examples
+/// ```
+/// use actix_web::App;
+/// use actix_web_lab::middleware::TowerLayer;
+/// # let my_tower_layer = tower_layer::Identity::new();
+///
+/// App::new()
+///     .wrap(TowerLayer::new(my_tower_layer))
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct TowerLayer<L> {
+    layer: L,
+}
+
+impl<L> TowerLayer<L> {
+    /// Constructs new middleware which applies `layer` to the services it wraps.
+    pub fn new(layer: L) -> Self {
+        Self { layer }
+    }
+}
+
+impl<S, B, L, T> Transform<S, ServiceRequest> for TowerLayer<L>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    L: Layer<IntoTowerService<S>, Service = T>,
+    T: tower_service::Service<ServiceRequest, Response = ServiceResponse<B>> + Clone + 'static,
+    T::Error: Into<Error>,
+    T::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = FromTowerService<T>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let service = self.layer.layer(IntoTowerService::new(service));
+        ready(Ok(FromTowerService::new(service)))
+    }
+}
+
+/// Adapts a `tower` service into an Actix Web service.
+///
+/// Tower services take `&mut self` and must be polled for readiness before each call, whereas Actix
+/// Web services are shared. Each call is therefore made on a clone of the wrapped service, which
+/// is polled for readiness before the request is passed to it. The adapter itself is always ready.
+#[derive(Debug, Clone)]
+pub struct FromTowerService<T> {
+    service: T,
+}
+
+impl<T> FromTowerService<T> {
+    /// Constructs new Actix Web service which calls clones of the tower `service`.
+    pub fn new(service: T) -> Self {
+        Self { service }
+    }
+
+    /// Returns the wrapped tower service.
+    pub fn into_inner(self) -> T {
+        self.service
+    }
+}
+
+impl<Req, T> Service<Req> for FromTowerService<T>
+where
+    Req: 'static,
+    T: tower_service::Service<Req> + Clone + 'static,
+    T::Error: Into<Error>,
+    T::Future: 'static,
+{
+    type Response = T::Response;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    always_ready!();
+
+    fn call(&self, req: Req) -> Self::Future {
+        let mut service = self.service.clone();
+
+        Box::pin(async move {
+            poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(Into::into)?;
+
+            service.call(req).await.map_err(Into::into)
+        })
+    }
+}
+
+/// Adapts an Actix Web service into a `tower` service.
+///
+/// The Actix Web service is shared between clones of the adapter. Readiness and calls are forwarded
+/// to it unchanged.
+#[derive(Debug)]
+pub struct IntoTowerService<S> {
+    service: Rc<S>,
+}
+
+impl<S> IntoTowerService<S> {
+    /// Constructs new tower service which forwards calls to the Actix Web `service`.
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Rc::new(service),
+        }
+    }
+}
+
+impl<S> Clone for IntoTowerService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            service: Rc::clone(&self.service),
+        }
+    }
+}
+
+impl<Req, S> tower_service::Service<Req> for IntoTowerService<S>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, future::Future, pin::Pin};
+
+    use actix_web::{
+        http::header::{HeaderName, HeaderValue},
+        test, web, App, HttpResponse,
+    };
+
+    use super::*;
+
+    /// Tower service that counts calls and adds a header to responses.
+    #[derive(Clone)]
+    struct Counted<T> {
+        service: T,
+        calls: Rc<Cell<usize>>,
+    }
+
+    impl<T, B> tower_service::Service<ServiceRequest> for Counted<T>
+    where
+        T: tower_service::Service<ServiceRequest, Response = ServiceResponse<B>>,
+        T::Future: 'static,
+    {
+        type Response = ServiceResponse<B>;
+        type Error = T::Error;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.service.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: ServiceRequest) -> Self::Future {
+            self.calls.set(self.calls.get() + 1);
+            let calls = self.calls.get();
+            let fut = self.service.call(req);
+
+            Box::pin(async move {
+                let mut res = fut.await?;
+                res.headers_mut()
+                    .insert(HeaderName::from_static("x-calls"), HeaderValue::from(calls));
+                Ok(res)
+            })
+        }
+    }
+
+    #[actix_web::test]
+    async fn applies_tower_layer() {
+        let calls = Rc::new(Cell::new(0));
+
+        let layer = tower_layer::layer_fn({
+            let calls = Rc::clone(&calls);
+            move |service| Counted {
+                service,
+                calls: Rc::clone(&calls),
+            }
+        });
+
+        let app = test::init_service(
+            App::new()
+                .wrap(TowerLayer::new(layer))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for expected in 1..=2_usize {
+            let req = test::TestRequest::default().to_request();
+            let res = test::call_service(&app, req).await;
+            assert!(res.status().is_success());
+            assert_eq!(
+                res.headers().get("x-calls").unwrap(),
+                expected.to_string().as_str()
+            );
+        }
+
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[actix_web::test]
+    async fn round_trips_actix_service() {
+        let app =
+            test::init_service(App::new().route("/", web::get().to(HttpResponse::NoContent))).await;
+
+        let mut service = IntoTowerService::new(app);
+        poll_fn(|cx| tower_service::Service::poll_ready(&mut service, cx))
+            .await
+            .unwrap();
+        let req = test::TestRequest::default().to_request();
+        let res = tower_service::Service::call(&mut service, req)
+            .await
+            .unwrap();
+        assert_eq!(res.status(), actix_web::http::StatusCode::NO_CONTENT);
+
+        let service = FromTowerService::new(service);
+        let req = test::TestRequest::default().to_request();
+        let res = test::call_service(&service, req).await;
+        assert_eq!(res.status(), actix_web::http::StatusCode::NO_CONTENT);
+    }
+}
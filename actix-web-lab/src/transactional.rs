@@ -0,0 +1,388 @@
+//! Per-request transaction middleware and extractor.
+//!
+//! See [`Transactional`] and [`Tx`] for docs.
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    ops::DerefMut,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error, Error, FromRequest, HttpMessage as _, HttpRequest,
+};
+use async_trait::async_trait;
+use futures_core::future::LocalBoxFuture;
+use futures_util::FutureExt as _;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{debug, warn};
+
+use crate::error::LabError;
+
+/// A source of transactions used by the [`Transactional`] middleware.
+///
+/// Implement this for database pools or similar handles. An implementation for [`sqlx::PgPool`]
+/// is provided when the `postgres` crate feature is enabled.
+///
+/// [`sqlx::PgPool`]: https://docs.rs/sqlx/0.7/sqlx/type.PgPool.html
+#[async_trait(?Send)]
+pub trait TxProvider: 'static {
+    /// Transaction type exposed to handlers through the [`Tx`] extractor.
+    type Tx: 'static;
+
+    /// Error type returned when transactions cannot be started, committed, or rolled back.
+    type Error: fmt::Debug + fmt::Display + 'static;
+
+    /// Starts a new transaction.
+    async fn begin(&self) -> Result<Self::Tx, Self::Error>;
+
+    /// Commits `tx`.
+    async fn commit(&self, tx: Self::Tx) -> Result<(), Self::Error>;
+
+    /// Rolls back `tx`.
+    async fn rollback(&self, tx: Self::Tx) -> Result<(), Self::Error>;
+}
+
+/// A middleware that wraps each request in a transaction.
+///
+/// A transaction is started, using the [`TxProvider`], before the wrapped service is called and is
+/// made available to handlers through the [`Tx`] extractor. Once the response has been produced,
+/// the transaction is:
+/// - committed, if the response has a success (2xx) or redirection (3xx) status code;
+/// - rolled back, if the response has any other status code, if the service returns an error, or
+///   if the service panics.
+///
+/// Requests are rejected with a 500 Internal Server Error if the transaction cannot be started or
+/// committed; errors during rollback are only logged.
+///
+/// # Examples
+/// ```
+/// use std::convert::Infallible;
+///
+/// use actix_web::{post, App, HttpResponse};
+/// use actix_web_lab::{
+///     extract::Tx,
+///     middleware::{Transactional, TxProvider},
+/// };
+/// use async_trait::async_trait;
+///
+/// #[derive(Clone)]
+/// struct Db;
+///
+/// #[async_trait(?Send)]
+/// impl TxProvider for Db {
+///     /// Pending writes.
+///     type Tx = Vec<String>;
+///     type Error = Infallible;
+///
+///     async fn begin(&self) -> Result<Self::Tx, Self::Error> {
+///         Ok(Vec::new())
+///     }
+///
+///     async fn commit(&self, writes: Self::Tx) -> Result<(), Self::Error> {
+///         // ...apply writes...
+///         Ok(())
+///     }
+///
+///     async fn rollback(&self, _writes: Self::Tx) -> Result<(), Self::Error> {
+///         Ok(())
+///     }
+/// }
+///
+/// #[post("/items")]
+/// async fn create_item(tx: Tx<Vec<String>>) -> HttpResponse {
+///     tx.lock().await.push("INSERT ...".to_owned());
+///     HttpResponse::Created().finish()
+/// }
+///
+/// App::new()
+///     .wrap(Transactional::new(Db))
+///     .service(create_item)
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transactional<P> {
+    provider: P,
+}
+
+impl<P: TxProvider> Transactional<P> {
+    /// Constructs new transaction middleware which starts transactions using `provider`.
+    pub fn new(provider: P) -> Self {
+        Self { provider }
+    }
+}
+
+impl<S, B, P> Transform<S, ServiceRequest> for Transactional<P>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    P: TxProvider + Clone,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TransactionalMiddleware<S, P>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TransactionalMiddleware {
+            service: Rc::new(service),
+            provider: Rc::new(self.provider.clone()),
+        }))
+    }
+}
+
+/// Service for the [`Transactional`] middleware.
+pub struct TransactionalMiddleware<S, P> {
+    service: Rc<S>,
+    provider: Rc<P>,
+}
+
+impl<S, B, P> Service<ServiceRequest> for TransactionalMiddleware<S, P>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+    P: TxProvider,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let provider = Rc::clone(&self.provider);
+
+        Box::pin(async move {
+            let tx = provider.begin().await.map_err(|err| {
+                warn!("failed to begin transaction: {err}");
+                error::ErrorInternalServerError(err)
+            })?;
+
+            let tx = Tx {
+                inner: Rc::new(Mutex::new(Some(tx))),
+            };
+            req.extensions_mut().insert(tx.clone());
+
+            let res = AssertUnwindSafe(service.call(req)).catch_unwind().await;
+
+            let tx = tx
+                .inner
+                .lock()
+                .await
+                .take()
+                .expect("transaction is only taken by the middleware");
+
+            match res {
+                Ok(Ok(res)) if res.status().is_success() || res.status().is_redirection() => {
+                    provider.commit(tx).await.map_err(|err| {
+                        warn!("failed to commit transaction: {err}");
+                        error::ErrorInternalServerError(err)
+                    })?;
+
+                    Ok(res)
+                }
+
+                Ok(res) => {
+                    rollback(&*provider, tx).await;
+                    res
+                }
+
+                Err(panic_err) => {
+                    rollback(&*provider, tx).await;
+                    panic::resume_unwind(panic_err)
+                }
+            }
+        })
+    }
+}
+
+async fn rollback<P: TxProvider>(provider: &P, tx: P::Tx) {
+    if let Err(err) = provider.rollback(tx).await {
+        warn!("failed to roll back transaction: {err}");
+    }
+}
+
+/// Request-scoped transaction provided by the [`Transactional`] middleware.
+///
+/// Use [`lock()`](Self::lock) to access the transaction. Clones share the same transaction, which
+/// is committed or rolled back by the middleware once the response has been produced.
+///
+/// Extracting `Tx` without the middleware, or with a middleware whose provider uses a different
+/// transaction type, results in a [`LabError::MiddlewareNotRegistered`] error.
+///
+/// See [`Transactional`] docs for an example.
+pub struct Tx<T> {
+    inner: Rc<Mutex<Option<T>>>,
+}
+
+impl<T> Tx<T> {
+    /// Waits for exclusive access to the transaction and returns a guard which dereferences to it.
+    ///
+    /// # Panics
+    /// Panics if called after the middleware has completed the transaction; for example, from a
+    /// task spawned by the handler which outlives the request.
+    pub async fn lock(&self) -> impl DerefMut<Target = T> + '_ {
+        MutexGuard::map(self.inner.lock().await, |tx| {
+            tx.as_mut().expect("transaction has already been completed")
+        })
+    }
+}
+
+impl<T> Clone for Tx<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Tx<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tx").finish_non_exhaustive()
+    }
+}
+
+impl<T: 'static> FromRequest for Tx<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `Tx` for `{}` handler. For the Tx extractor to work \
+                correctly, wrap the app or scope with the `Transactional` middleware.",
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            LabError::MiddlewareNotRegistered {
+                middleware: "Transactional",
+            }
+            .into()
+        }))
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[async_trait(?Send)]
+impl TxProvider for sqlx::PgPool {
+    type Tx = sqlx::Transaction<'static, sqlx::Postgres>;
+    type Error = sqlx::Error;
+
+    async fn begin(&self) -> Result<Self::Tx, Self::Error> {
+        sqlx::PgPool::begin(self).await
+    }
+
+    async fn commit(&self, tx: Self::Tx) -> Result<(), Self::Error> {
+        tx.commit().await
+    }
+
+    async fn rollback(&self, tx: Self::Tx) -> Result<(), Self::Error> {
+        tx.rollback().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    use super::*;
+
+    #[derive(Debug, Clone, Default)]
+    struct Log(Rc<RefCell<Vec<String>>>);
+
+    #[async_trait(?Send)]
+    impl TxProvider for Log {
+        type Tx = Vec<String>;
+        type Error = String;
+
+        async fn begin(&self) -> Result<Self::Tx, Self::Error> {
+            Ok(Vec::new())
+        }
+
+        async fn commit(&self, tx: Self::Tx) -> Result<(), Self::Error> {
+            if tx.iter().any(|write| write == "fail") {
+                return Err("commit failed".to_owned());
+            }
+
+            self.0.borrow_mut().extend(tx);
+            Ok(())
+        }
+
+        async fn rollback(&self, _tx: Self::Tx) -> Result<(), Self::Error> {
+            self.0.borrow_mut().push("rollback".to_owned());
+            Ok(())
+        }
+    }
+
+    async fn handler(tx: Tx<Vec<String>>, path: web::Path<(u16, String)>) -> HttpResponse {
+        let (status, write) = path.into_inner();
+        tx.lock().await.push(write);
+        HttpResponse::build(StatusCode::from_u16(status).unwrap()).finish()
+    }
+
+    async fn panicking_handler(tx: Tx<Vec<String>>) -> HttpResponse {
+        tx.lock().await.push("panic".to_owned());
+        panic!("handler panicked")
+    }
+
+    #[actix_web::test]
+    async fn commits_or_rolls_back() {
+        let log = Log::default();
+
+        let app = test::init_service(
+            App::new()
+                .wrap(Transactional::new(log.clone()))
+                .route("/panic", web::get().to(panicking_handler))
+                .route("/{status}/{write}", web::get().to(handler)),
+        )
+        .await;
+
+        for (uri, status) in [
+            ("/200/a", StatusCode::OK),
+            ("/303/b", StatusCode::SEE_OTHER),
+            ("/404/c", StatusCode::NOT_FOUND),
+            ("/500/d", StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let req = test::TestRequest::with_uri(uri).to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), status, "{uri}");
+        }
+
+        let req = test::TestRequest::with_uri("/200/fail").to_request();
+        let err = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        assert_eq!(*log.0.borrow(), ["a", "b", "rollback", "rollback"]);
+
+        let req = test::TestRequest::with_uri("/panic").to_request();
+        let res = AssertUnwindSafe(test::call_service(&app, req))
+            .catch_unwind()
+            .await;
+        assert!(res.is_err());
+
+        assert_eq!(
+            *log.0.borrow(),
+            ["a", "b", "rollback", "rollback", "rollback"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn requires_middleware() {
+        let app =
+            test::init_service(App::new().route("/{status}/{write}", web::get().to(handler))).await;
+
+        let req = test::TestRequest::with_uri("/200/a").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
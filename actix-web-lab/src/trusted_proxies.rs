@@ -0,0 +1,209 @@
+//! Trusted proxy address ranges.
+//!
+//! See [`TrustedProxies`] docs.
+
+use std::{net::IpAddr, str::FromStr};
+
+use derive_more::{Display, Error};
+
+/// A set of trusted proxy address ranges.
+///
+/// Used to decide whether forwarding information, such as `Forwarded` headers, sent by a peer
+/// should be believed. Shared by [`ClientIpConfig`](crate::extract::ClientIpConfig) and
+/// [`HostConfig`](crate::extract::HostConfig).
+///
+/// # Examples
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// use actix_web_lab::util::TrustedProxies;
+///
+/// let proxies = TrustedProxies::parse(["10.0.0.0/8", "fd00::/8"]).unwrap();
+/// assert!(proxies.contains("10.1.2.3".parse().unwrap()));
+///
+/// let proxies = TrustedProxies::from_iter([IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))]);
+/// assert!(!proxies.contains("10.1.2.3".parse().unwrap()));
+///
+/// assert!(TrustedProxies::parse(["10.0.0.0/33"]).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    ranges: Vec<IpRange>,
+}
+
+impl TrustedProxies {
+    /// Constructs new set which trusts no proxies.
+    pub const fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Parses address ranges in CIDR notation (e.g., `10.0.0.0/8`).
+    ///
+    /// Single addresses without a prefix length are also accepted.
+    ///
+    /// # Errors
+    /// Returns an error for the first range that is not valid.
+    pub fn parse<S: AsRef<str>>(
+        ranges: impl IntoIterator<Item = S>,
+    ) -> Result<Self, InvalidIpRange> {
+        ranges
+            .into_iter()
+            .map(|range| range.as_ref().parse::<IpRange>())
+            .collect()
+    }
+
+    /// Returns true if `ip` is within one of the trusted ranges.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(ip))
+    }
+}
+
+impl<R: Into<IpRange>> FromIterator<R> for TrustedProxies {
+    fn from_iter<I: IntoIterator<Item = R>>(ranges: I) -> Self {
+        Self {
+            ranges: ranges.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<R: Into<IpRange>> Extend<R> for TrustedProxies {
+    fn extend<I: IntoIterator<Item = R>>(&mut self, ranges: I) {
+        self.ranges.extend(ranges.into_iter().map(Into::into));
+    }
+}
+
+/// An IP address range in CIDR notation.
+///
+/// Parsed from strings such as `10.0.0.0/8` or `fd00::/8`. Single addresses without a prefix
+/// length are also accepted and can be converted from [`IpAddr`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Returns true if `ip` is within this range.
+    ///
+    /// IPv4 ranges also contain the IPv4-mapped IPv6 forms of their addresses.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match (self.addr, ip) {
+            // match IPv4 ranges against IPv4-mapped IPv6 addresses too
+            (IpAddr::V4(_), IpAddr::V6(v6)) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            _ => ip,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                prefix_eq(&range.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                prefix_eq(&range.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+        Self { addr, prefix_len }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = InvalidIpRange;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidIpRange {
+            range: range.to_owned(),
+        };
+
+        let (addr, prefix_len) = match range.split_once('/') {
+            Some((addr, len)) => (addr, Some(len.parse::<u8>().map_err(|_| invalid())?)),
+            None => (range, None),
+        };
+
+        let addr = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+/// Error returned when an [`IpRange`] can not be parsed.
+#[derive(Debug, Clone, Display, Error)]
+#[display(fmt = "`{range}` is not a valid IP address range")]
+pub struct InvalidIpRange {
+    range: String,
+}
+
+/// Returns true if the first `len` bits of `a` and `b` are equal.
+fn prefix_eq(a: &[u8], b: &[u8], len: u8) -> bool {
+    let full = usize::from(len / 8);
+    let rem = len % 8;
+
+    if a[..full] != b[..full] {
+        return false;
+    }
+
+    if rem == 0 {
+        return true;
+    }
+
+    let mask = 0xFF_u8 << (8 - rem);
+    a[full] & mask == b[full] & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_ranges() {
+        let range = "10.0.0.0/8".parse::<IpRange>().unwrap();
+        assert!(range.contains("10.1.2.3".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!range.contains("11.0.0.1".parse().unwrap()));
+        assert!(!range.contains("fd00::1".parse().unwrap()));
+
+        let range = "192.168.1.128/25".parse::<IpRange>().unwrap();
+        assert!(range.contains("192.168.1.200".parse().unwrap()));
+        assert!(!range.contains("192.168.1.100".parse().unwrap()));
+
+        let range = "fd00::/8".parse::<IpRange>().unwrap();
+        assert!(range.contains("fd12::1".parse().unwrap()));
+        assert!(!range.contains("fe80::1".parse().unwrap()));
+
+        let range = "203.0.113.7".parse::<IpRange>().unwrap();
+        assert!(range.contains("203.0.113.7".parse().unwrap()));
+        assert!(!range.contains("203.0.113.8".parse().unwrap()));
+
+        assert!("0.0.0.0/0".parse::<IpRange>().is_ok());
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("10.0.0/8".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn trusted_proxies() {
+        let proxies = TrustedProxies::parse(["10.0.0.0/8", "2001:db8::1"]).unwrap();
+        assert!(proxies.contains("10.0.0.1".parse().unwrap()));
+        assert!(proxies.contains("2001:db8::1".parse().unwrap()));
+        assert!(!proxies.contains("2001:db8::2".parse().unwrap()));
+
+        let err = TrustedProxies::parse(["10.0.0.0/8", "proxy"]).unwrap_err();
+        assert_eq!(err.to_string(), "`proxy` is not a valid IP address range");
+
+        let proxies = TrustedProxies::from_iter(["192.0.2.1".parse::<IpAddr>().unwrap()]);
+        assert!(proxies.contains("192.0.2.1".parse().unwrap()));
+        assert!(!proxies.contains("192.0.2.2".parse().unwrap()));
+
+        assert!(!TrustedProxies::new().contains("10.0.0.1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,366 @@
+//! Upload progress tracking.
+//!
+//! Register a [`ProgressTracker`] as app data and have clients tag uploads with an
+//! [`X-Upload-Id`](UPLOAD_ID) header. Body extractors which are aware of the tracker (currently
+//! [`BodyLimit`](crate::extract::BodyLimit)) will report the number of bytes received so far, which
+//! can be streamed back to the browser from a companion SSE endpoint for use in progress bars.
+//!
+//! The tracker is `Send` and cheap to clone so a single instance can be shared between workers,
+//! which is necessary since the upload and progress requests may be handled by different workers.
+//!
+//! # Examples
+//! ```
+//! use actix_web::{get, post, web, App, HttpServer, Responder};
+//! use actix_web_lab::{extract::BodyLimit, uploads::ProgressTracker};
+//!
+//! #[post("/upload")]
+//! async fn upload(body: BodyLimit<web::Bytes, 10_485_760>) -> impl Responder {
+//!     format!("received {} bytes", body.into_inner().len())
+//! }
+//!
+//! #[get("/upload/{id}/progress")]
+//! async fn progress(tracker: ProgressTracker, id: web::Path<String>) -> impl Responder {
+//!     tracker.sse(&id)
+//! }
+//!
+//! # fn run() -> std::io::Result<()> {
+//! let tracker = ProgressTracker::new();
+//!
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .app_data(tracker.clone())
+//!         .service(upload)
+//!         .service(progress)
+//! })
+//! # ; Ok(()) }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use actix_utils::future::{ready, Ready};
+use actix_web::{dev, http::header::HeaderName, Error, FromRequest, HttpMessage as _, HttpRequest};
+use ahash::AHashMap;
+use futures_core::Stream;
+use tokio::sync::watch;
+use tracing::debug;
+
+use crate::{
+    error::LabError,
+    header::ContentLength,
+    sse::{self, Event, Sse},
+    util::InfallibleStream,
+};
+
+/// Header used by clients to identify an upload: `x-upload-id`.
+pub const UPLOAD_ID: HeaderName = HeaderName::from_static("x-upload-id");
+
+/// State of a tracked upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UploadStatus {
+    /// Progress has been requested but the upload has not started.
+    Pending,
+
+    /// Upload body is being received.
+    Uploading,
+
+    /// Upload body was received in full.
+    Completed,
+
+    /// Upload body was not received in full, either due to an error or the upload exceeding its
+    /// size limit.
+    Aborted,
+}
+
+impl UploadStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Uploading => "uploading",
+            Self::Completed => "completed",
+            Self::Aborted => "aborted",
+        }
+    }
+}
+
+/// Snapshot of an upload's progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+    received: u64,
+    total: Option<u64>,
+    status: UploadStatus,
+}
+
+impl UploadProgress {
+    const PENDING: Self = Self {
+        received: 0,
+        total: None,
+        status: UploadStatus::Pending,
+    };
+
+    /// Returns number of body bytes received so far.
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Returns total size of the upload, if the client sent a `Content-Length` header.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Returns state of the upload.
+    pub fn status(&self) -> UploadStatus {
+        self.status
+    }
+
+    /// Converts progress into an SSE event named `progress` with a JSON payload.
+    ///
+    /// For example: `{"received":1024,"status":"uploading","total":4096}`.
+    pub fn to_event(&self) -> Event {
+        let data = serde_json::json!({
+            "received": self.received,
+            "total": self.total,
+            "status": self.status.as_str(),
+        });
+
+        sse::Data::new(data.to_string()).event("progress").into()
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    tx: Arc<watch::Sender<UploadProgress>>,
+    active: bool,
+}
+
+/// Shared registry of upload progress, keyed by upload ID.
+///
+/// Can be used as an extractor when registered as app data. See [module docs](self) for usage.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressTracker {
+    uploads: Arc<Mutex<AHashMap<String, Entry>>>,
+}
+
+impl ProgressTracker {
+    /// Constructs new, empty progress tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns current progress of upload with `id`, if it is being tracked.
+    pub fn progress(&self, id: &str) -> Option<UploadProgress> {
+        let uploads = self.uploads.lock().unwrap();
+        uploads.get(id).map(|entry| *entry.tx.borrow())
+    }
+
+    /// Returns a receiver for progress updates of upload with `id`.
+    ///
+    /// Subscribing before the upload has started is supported; the status will be
+    /// [`Pending`](UploadStatus::Pending) until it does. The receiver is closed after the upload
+    /// has completed or aborted.
+    pub fn subscribe(&self, id: &str) -> watch::Receiver<UploadProgress> {
+        let mut uploads = self.uploads.lock().unwrap();
+        prune(&mut uploads);
+
+        uploads
+            .entry(id.to_owned())
+            .or_insert_with(|| Entry {
+                tx: Arc::new(watch::channel(UploadProgress::PENDING).0),
+                active: false,
+            })
+            .tx
+            .subscribe()
+    }
+
+    /// Creates an SSE responder which streams progress events for upload with `id`.
+    ///
+    /// See [`UploadProgress::to_event()`] for the event format.
+    pub fn sse(&self, id: &str) -> Sse<InfallibleStream<impl Stream<Item = Event> + 'static>> {
+        sse::from_watch(self.subscribe(id), UploadProgress::to_event)
+    }
+
+    /// Starts tracking an upload.
+    fn start(&self, id: String, total: Option<u64>) -> UploadReporter {
+        let mut uploads = self.uploads.lock().unwrap();
+        prune(&mut uploads);
+
+        let entry = uploads.entry(id.clone()).or_insert_with(|| Entry {
+            tx: Arc::new(watch::channel(UploadProgress::PENDING).0),
+            active: false,
+        });
+
+        entry.active = true;
+        entry.tx.send_replace(UploadProgress {
+            received: 0,
+            total,
+            status: UploadStatus::Uploading,
+        });
+
+        UploadReporter {
+            tracker: self.clone(),
+            tx: Arc::clone(&entry.tx),
+            id,
+            finished: false,
+        }
+    }
+}
+
+/// Removes finished or unstarted uploads which no one is waiting on.
+fn prune(uploads: &mut AHashMap<String, Entry>) {
+    uploads.retain(|_, entry| entry.active || entry.tx.receiver_count() > 0);
+}
+
+impl FromRequest for ProgressTracker {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut dev::Payload) -> Self::Future {
+        ready(req.app_data::<Self>().cloned().ok_or_else(|| {
+            debug!(
+                "Failed to extract `ProgressTracker` for `{}` handler. For the ProgressTracker \
+                extractor to work correctly, construct a `ProgressTracker::new()` and pass it to \
+                `App::app_data()`.",
+                req.match_name().unwrap_or_else(|| req.path()),
+            );
+
+            LabError::AppDataNotConfigured {
+                type_name: core::any::type_name::<Self>(),
+            }
+            .into()
+        }))
+    }
+}
+
+/// Handle used by body extractors to report progress of a single upload.
+///
+/// If dropped before [`complete()`](Self::complete) is called, the upload is marked as aborted.
+#[derive(Debug)]
+pub(crate) struct UploadReporter {
+    tracker: ProgressTracker,
+    tx: Arc<watch::Sender<UploadProgress>>,
+    id: String,
+    finished: bool,
+}
+
+impl UploadReporter {
+    /// Starts tracking an upload if a tracker is registered and the request has an upload ID.
+    pub(crate) fn for_request(req: &HttpRequest) -> Option<Self> {
+        let tracker = req.app_data::<ProgressTracker>()?;
+        let id = req.headers().get(UPLOAD_ID)?.to_str().ok()?;
+        let total = req
+            .get_header::<ContentLength>()
+            .map(|len| len.into_inner() as u64);
+
+        Some(tracker.start(id.to_owned(), total))
+    }
+
+    /// Updates number of bytes received so far.
+    pub(crate) fn update(&self, received: u64) {
+        self.tx.send_if_modified(|progress| {
+            let modified = progress.received != received;
+            progress.received = received;
+            modified
+        });
+    }
+
+    /// Marks upload as completed.
+    pub(crate) fn complete(mut self) {
+        self.finish(UploadStatus::Completed);
+    }
+
+    fn finish(&mut self, status: UploadStatus) {
+        self.finished = true;
+        self.tx.send_modify(|progress| progress.status = status);
+
+        // dropping the entry's sender, along with our own, closes subscribers' receivers
+        self.tracker.uploads.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl Drop for UploadReporter {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish(UploadStatus::Aborted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, test::TestRequest, web::Bytes, Responder as _};
+
+    use super::*;
+    use crate::extract::BodyLimit;
+
+    #[actix_web::test]
+    async fn body_limit_reports_progress() {
+        let tracker = ProgressTracker::new();
+        let mut rx = tracker.subscribe("abc");
+        assert_eq!(rx.borrow_and_update().status(), UploadStatus::Pending);
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(tracker.clone())
+            .insert_header((UPLOAD_ID, "abc"))
+            .set_payload("hello world")
+            .to_http_parts();
+
+        BodyLimit::<Bytes, 64>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+
+        let progress = *rx.borrow_and_update();
+        assert_eq!(progress.received(), 11);
+        assert_eq!(progress.total(), Some(11));
+        assert_eq!(progress.status(), UploadStatus::Completed);
+
+        // upload is no longer tracked once finished
+        assert!(tracker.progress("abc").is_none());
+        assert!(rx.changed().await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn body_limit_overflow_aborts_upload() {
+        let tracker = ProgressTracker::new();
+        let rx = tracker.subscribe("abc");
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(tracker.clone())
+            .insert_header((UPLOAD_ID, "abc"))
+            .set_payload("hello world")
+            .to_http_parts();
+
+        BodyLimit::<Bytes, 4>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+
+        assert_eq!(rx.borrow().status(), UploadStatus::Aborted);
+    }
+
+    #[actix_web::test]
+    async fn untracked_uploads_are_pruned() {
+        let tracker = ProgressTracker::new();
+
+        drop(tracker.subscribe("abc"));
+        assert!(tracker.progress("abc").is_some());
+
+        drop(tracker.subscribe("def"));
+        assert!(tracker.progress("abc").is_none());
+    }
+
+    #[actix_web::test]
+    async fn sse_stream_ends_after_upload() {
+        let tracker = ProgressTracker::new();
+        let sse = tracker.sse("abc");
+
+        tracker.start("abc".to_owned(), Some(3)).complete();
+
+        let res = sse.respond_to(&TestRequest::default().to_http_request());
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+
+        assert_eq!(
+            body,
+            "event: progress\n\
+            data: {\"received\":0,\"status\":\"completed\",\"total\":3}\n\n",
+        );
+    }
+}
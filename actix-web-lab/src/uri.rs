@@ -0,0 +1,264 @@
+use std::{
+    borrow::Cow,
+    ops::Deref,
+    path::{Path, PathBuf},
+    str::{FromStr, Utf8Error},
+};
+
+use derive_more::{Display, Error};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters which are percent-encoded in fragments.
+///
+/// See <https://url.spec.whatwg.org/#fragment-percent-encode-set>.
+const FRAGMENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'<').add(b'>').add(b'`');
+
+/// Characters which are percent-encoded in paths.
+///
+/// See <https://url.spec.whatwg.org/#path-percent-encode-set>.
+const PATH: &AsciiSet = &FRAGMENT.add(b'#').add(b'?').add(b'{').add(b'}');
+
+/// Characters which are percent-encoded in path segments; the path set plus `/` and `%`.
+const PATH_SEGMENT: &AsciiSet = &PATH.add(b'/').add(b'%');
+
+/// Characters which are percent-encoded in query keys and values; everything except unreserved
+/// characters.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc3986#section-2.3>.
+const QUERY_COMPONENT: &AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Sets of characters that are percent-encoded by [`encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncodeSet {
+    /// A whole path; slashes are preserved.
+    Path,
+
+    /// A single path segment; slashes and percent signs are encoded.
+    PathSegment,
+
+    /// A single query string key or value; all characters except unreserved ones are encoded.
+    QueryComponent,
+
+    /// A URL fragment.
+    Fragment,
+}
+
+impl EncodeSet {
+    fn ascii_set(self) -> &'static AsciiSet {
+        match self {
+            Self::Path => PATH,
+            Self::PathSegment => PATH_SEGMENT,
+            Self::QueryComponent => QUERY_COMPONENT,
+            Self::Fragment => FRAGMENT,
+        }
+    }
+}
+
+/// Percent-encodes `input` using the given character set.
+///
+/// Non-ASCII characters are always encoded as UTF-8. Input is only copied if any characters need
+/// encoding.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::util::uri::{encode, EncodeSet};
+///
+/// assert_eq!(encode("a b/c", EncodeSet::Path), "a%20b/c");
+/// assert_eq!(encode("a b/c", EncodeSet::PathSegment), "a%20b%2Fc");
+/// assert_eq!(encode("a&b=c", EncodeSet::QueryComponent), "a%26b%3Dc");
+/// ```
+pub fn encode(input: &str, set: EncodeSet) -> Cow<'_, str> {
+    utf8_percent_encode(input, set.ascii_set()).into()
+}
+
+/// Percent-decodes `input`, failing if the decoded bytes are not valid UTF-8.
+///
+/// Input is only copied if it contains any percent-encoded bytes. Note that `+` is not decoded as
+/// a space; use [`decode_query_component`] for `application/x-www-form-urlencoded` input.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::util::uri::decode;
+///
+/// assert_eq!(decode("a%20b%2Fc").unwrap(), "a b/c");
+/// assert!(decode("%FF").is_err());
+/// ```
+pub fn decode(input: &str) -> Result<Cow<'_, str>, Utf8Error> {
+    percent_decode_str(input).decode_utf8()
+}
+
+/// Percent-decodes a query string key or value, also decoding `+` as a space.
+///
+/// # Examples
+/// ```
+/// use actix_web_lab::util::uri::decode_query_component;
+///
+/// assert_eq!(decode_query_component("a+b%2Bc").unwrap(), "a b+c");
+/// ```
+pub fn decode_query_component(input: &str) -> Result<Cow<'_, str>, Utf8Error> {
+    if !input.contains('+') {
+        return decode(input);
+    }
+
+    let input = input.replace('+', " ");
+    Ok(Cow::Owned(decode(&input)?.into_owned()))
+}
+
+/// Errors that can occur when constructing a [`SafePathBuf`].
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsafePathError {
+    /// Path contains a percent-encoded sequence that is not valid UTF-8.
+    #[display(fmt = "Path is not valid UTF-8 when decoded.")]
+    InvalidUtf8,
+
+    /// Path contains a `..` segment.
+    #[display(fmt = "Path contains a parent directory segment.")]
+    ParentDir,
+
+    /// Path contains a segment which has special meaning on some platforms, such as a segment
+    /// containing a backslash or null byte, or a drive prefix.
+    #[display(fmt = "Path contains a disallowed segment.")]
+    DisallowedSegment,
+}
+
+/// A relative file system path, decoded from a URL path, that is guaranteed not to escape the
+/// directory it is joined to.
+///
+/// Empty and `.` segments are skipped. Paths containing `..` segments, percent-encoded or not, are
+/// rejected, as are segments which could be interpreted specially by the file system, such as
+/// those containing backslashes, null bytes, or Windows drive prefixes.
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+///
+/// use actix_web_lab::util::uri::SafePathBuf;
+///
+/// let path = SafePathBuf::from_url_path("/assets/./img%20one.png").unwrap();
+/// assert_eq!(path.as_path(), Path::new("assets/img one.png"));
+///
+/// assert!(SafePathBuf::from_url_path("/assets/../secret").is_err());
+/// assert!(SafePathBuf::from_url_path("/assets/%2e%2e/secret").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SafePathBuf(PathBuf);
+
+impl SafePathBuf {
+    /// Decodes and validates a URL path, or part of one.
+    pub fn from_url_path(path: &str) -> Result<Self, UnsafePathError> {
+        let mut buf = PathBuf::new();
+
+        for segment in path.split('/') {
+            let segment = decode(segment).map_err(|_| UnsafePathError::InvalidUtf8)?;
+
+            match &*segment {
+                "" | "." => continue,
+                ".." => return Err(UnsafePathError::ParentDir),
+                _ => {}
+            }
+
+            if segment.contains(['/', '\\', '\0'])
+                || segment.ends_with(':')
+                || (cfg!(windows) && segment.contains(['<', '>', '|', '*', '?']))
+            {
+                return Err(UnsafePathError::DisallowedSegment);
+            }
+
+            buf.push(&*segment);
+        }
+
+        Ok(Self(buf))
+    }
+
+    /// Returns path as a [`Path`].
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Returns full path of this relative path when joined to `base`.
+    pub fn join_to(&self, base: impl AsRef<Path>) -> PathBuf {
+        base.as_ref().join(&self.0)
+    }
+
+    /// Unwraps into inner path.
+    pub fn into_inner(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl FromStr for SafePathBuf {
+    type Err = UnsafePathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::from_url_path(path)
+    }
+}
+
+impl Deref for SafePathBuf {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for SafePathBuf {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let input = "ünïcödé /?#&=+%";
+
+        for set in [
+            EncodeSet::Path,
+            EncodeSet::PathSegment,
+            EncodeSet::QueryComponent,
+            EncodeSet::Fragment,
+        ] {
+            let encoded = encode(input, set);
+            assert!(encoded.is_ascii());
+            assert_eq!(decode(&encoded).unwrap(), input, "{set:?}");
+        }
+
+        assert!(matches!(encode("plain", EncodeSet::Path), Cow::Borrowed(_)));
+        assert_eq!(encode("a?b#c", EncodeSet::Path), "a%3Fb%23c");
+        assert_eq!(encode("a?b#c", EncodeSet::Fragment), "a?b#c");
+    }
+
+    #[test]
+    fn safe_paths() {
+        let path = |input| SafePathBuf::from_url_path(input).map(SafePathBuf::into_inner);
+
+        assert_eq!(path("").unwrap(), PathBuf::new());
+        assert_eq!(path("//a//b/").unwrap(), PathBuf::from("a/b"));
+        assert_eq!(path("/a/.../b").unwrap(), PathBuf::from("a/.../b"));
+        assert_eq!(
+            SafePathBuf::from_url_path("a/b").unwrap().join_to("/srv"),
+            PathBuf::from("/srv/a/b"),
+        );
+
+        assert_eq!(path("/a/.."), Err(UnsafePathError::ParentDir));
+        assert_eq!(path("/a/%2E%2E/b"), Err(UnsafePathError::ParentDir));
+        assert_eq!(
+            path("/a/b%2F..%2Fc"),
+            Err(UnsafePathError::DisallowedSegment)
+        );
+        assert_eq!(path("/a%5C..%5Cb"), Err(UnsafePathError::DisallowedSegment));
+        assert_eq!(path("/a%00"), Err(UnsafePathError::DisallowedSegment));
+        assert_eq!(path("/c:/windows"), Err(UnsafePathError::DisallowedSegment));
+        assert_eq!(path("/%FF"), Err(UnsafePathError::InvalidUtf8));
+    }
+}
@@ -15,6 +15,11 @@ use futures_core::Stream as _;
 use serde::de::DeserializeOwned;
 use tracing::debug;
 
+use crate::{
+    lab_config::LabConfig,
+    strict::{self, UnknownFields},
+};
+
 /// Default URL-encoded form payload size limit of 2MiB.
 pub const DEFAULT_URL_ENCODED_FORM_LIMIT: usize = 2_097_152;
 
@@ -142,11 +147,14 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for UrlEncodedFormExtractFu
 /// - `Content-Type` is not `application/x-www-form-urlencoded`.
 /// - `Content-Length` is greater than `LIMIT`.
 /// - The payload, when consumed, is not URL-encoded.
+/// - A [`LabConfig`] in strict mode is registered and the payload has unknown fields.
 pub enum UrlEncodedFormBody<T, const LIMIT: usize> {
     Error(Option<UrlencodedError>),
     Body {
         /// Length as reported by `Content-Length` header, if present.
         length: Option<usize>,
+        /// Known fields of `T`, if unknown fields should be rejected.
+        strict_fields: Option<&'static [&'static str]>,
         payload: Payload,
         buf: web::BytesMut,
         _res: PhantomData<T>,
@@ -190,8 +198,15 @@ impl<T: DeserializeOwned, const LIMIT: usize> UrlEncodedFormBody<T, LIMIT> {
             }
         }
 
+        let strict_fields = if LabConfig::from_req(req).is_strict() {
+            strict::struct_fields::<T>()
+        } else {
+            None
+        };
+
         UrlEncodedFormBody::Body {
             length,
+            strict_fields,
             payload,
             buf: web::BytesMut::with_capacity(8192),
             _res: PhantomData,
@@ -206,7 +221,12 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for UrlEncodedFormBody<T, L
         let this = self.get_mut();
 
         match this {
-            UrlEncodedFormBody::Body { buf, payload, .. } => loop {
+            UrlEncodedFormBody::Body {
+                buf,
+                payload,
+                strict_fields,
+                ..
+            } => loop {
                 let res = ready!(Pin::new(&mut *payload).poll_next(cx));
 
                 match res {
@@ -224,6 +244,13 @@ impl<T: DeserializeOwned, const LIMIT: usize> Future for UrlEncodedFormBody<T, L
                     }
 
                     None => {
+                        if let Some(unknown) = (*strict_fields).and_then(|fields| {
+                            let body = std::str::from_utf8(buf).ok()?;
+                            UnknownFields::find(strict::urlencoded_keys(body), fields)
+                        }) {
+                            return Poll::Ready(Err(UrlencodedError::Parse(unknown.into_error())));
+                        }
+
                         let form = serde_html_form::from_bytes::<T>(buf)
                             .map_err(UrlencodedError::Parse)?;
                         return Poll::Ready(Ok(form));
@@ -386,6 +413,31 @@ mod tests {
         );
     }
 
+    #[actix_web::test]
+    async fn strict_mode() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::form_url_encoded())
+            .app_data(web::Data::new(LabConfig::new().strict()))
+            .set_payload(Bytes::from_static(b"name=test&nam=1"))
+            .to_http_parts();
+        let err = UrlEncodedForm::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("unknown field `nam` (did you mean `name`?)."));
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::form_url_encoded())
+            .app_data(web::Data::new(LabConfig::new().strict()))
+            .set_payload(Bytes::from_static(b"name=test"))
+            .to_http_parts();
+        let form = UrlEncodedForm::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(form.name, "test");
+    }
+
     #[actix_web::test]
     async fn test_with_form_and_bad_content_type() {
         let (req, mut pl) = TestRequest::default()
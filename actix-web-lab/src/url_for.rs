@@ -0,0 +1,453 @@
+//! Typed URL generation from named routes.
+//!
+//! See [`LabUrl`] and [`RouteTable`] docs.
+
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use actix_web::{http::StatusCode, web, HttpRequest, ResponseError};
+use ahash::AHashMap;
+use derive_more::{Display, Error};
+
+use crate::util::uri::{encode, EncodeSet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Param { name: String, tail: bool },
+}
+
+/// Parses a resource pattern into literal and parameter parts.
+///
+/// Supports `{name}`, `{name:regex}`, and a trailing `{name}*` tail match.
+fn parse_pattern(pattern: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Literal(rest[..start].to_owned()));
+        }
+
+        // find matching close brace, allowing for braces in regex quantifiers
+        let mut depth = 0;
+        let end = rest[start..]
+            .char_indices()
+            .find_map(|(idx, ch)| {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+
+                (depth == 0).then_some(start + idx)
+            })
+            .ok_or_else(|| format!("unclosed parameter in pattern `{pattern}`"))?;
+
+        let inner = &rest[start + 1..end];
+        let name = inner.split_once(':').map_or(inner, |(name, _)| name);
+
+        if name.is_empty() {
+            return Err(format!("unnamed parameter in pattern `{pattern}`"));
+        }
+
+        rest = &rest[end + 1..];
+
+        let tail = rest == "*";
+        if tail {
+            rest = "";
+        }
+
+        parts.push(Part::Param {
+            name: name.to_owned(),
+            tail,
+        });
+    }
+
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_owned()));
+    }
+
+    Ok(parts)
+}
+
+/// Table of named route patterns used to generate URLs with [`LabUrl`].
+///
+/// Routes are registered with their full path pattern. Resources for routes in the table should
+/// be created using [`resource()`](Self::resource) so that the patterns used for routing and URL
+/// generation cannot diverge. The table must be registered as app data using
+/// [`App::app_data`](actix_web::App::app_data) for URLs to be generated from requests.
+///
+/// Cheap to clone; all clones share the same routes.
+///
+/// See [`LabUrl`] docs for an example.
+#[derive(Clone, Default)]
+pub struct RouteTable {
+    routes: Arc<AHashMap<String, Route>>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    pattern: String,
+    parts: Vec<Part>,
+}
+
+impl RouteTable {
+    /// Constructs new, empty route table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds named route pattern to the table.
+    ///
+    /// # Panics
+    /// Panics if the pattern is malformed or a route with the same name has already been added.
+    pub fn route(mut self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        let name = name.into();
+        let pattern = pattern.into();
+
+        let parts = parse_pattern(&pattern).unwrap_or_else(|err| panic!("{err}"));
+
+        let routes = Arc::make_mut(&mut self.routes);
+
+        assert!(
+            !routes.contains_key(&name),
+            "route `{name}` is already defined"
+        );
+
+        routes.insert(name, Route { pattern, parts });
+        self
+    }
+
+    /// Returns pattern of the named route, if it exists.
+    pub fn pattern(&self, name: &str) -> Option<&str> {
+        self.routes.get(name).map(|route| route.pattern.as_str())
+    }
+
+    /// Creates a named resource using the route's pattern.
+    ///
+    /// # Panics
+    /// Panics if no route with the given name exists.
+    pub fn resource(&self, name: &str) -> actix_web::Resource {
+        let pattern = self
+            .pattern(name)
+            .unwrap_or_else(|| panic!("route `{name}` is not defined in route table"));
+
+        web::resource(pattern).name(name)
+    }
+
+    fn path_for(&self, url: &LabUrl) -> Result<String, UrlForError> {
+        let route = self
+            .routes
+            .get(&url.route)
+            .ok_or_else(|| UrlForError::UnknownRoute {
+                name: url.route.clone(),
+            })?;
+
+        let mut path = String::new();
+        let mut used = 0;
+
+        for part in &route.parts {
+            match part {
+                Part::Literal(lit) => path.push_str(lit),
+                Part::Param { name, tail } => {
+                    let val = url
+                        .params
+                        .get(name)
+                        .ok_or_else(|| UrlForError::MissingParam { name: name.clone() })?;
+
+                    let set = if *tail {
+                        EncodeSet::Path
+                    } else {
+                        EncodeSet::PathSegment
+                    };
+
+                    path.push_str(&encode(val, set));
+                    used += 1;
+                }
+            }
+        }
+
+        if used < url.params.len() {
+            let name = url
+                .params
+                .keys()
+                .find(|name| {
+                    !route.parts.iter().any(
+                        |part| matches!(part, Part::Param { name: param, .. } if param == *name),
+                    )
+                })
+                .cloned()
+                .unwrap_or_default();
+
+            return Err(UrlForError::UnknownParam { name });
+        }
+
+        Ok(path)
+    }
+}
+
+impl fmt::Debug for RouteTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.routes
+                    .iter()
+                    .map(|(name, route)| (name, &route.pattern)),
+            )
+            .finish()
+    }
+}
+
+/// Typed URL builder for named routes.
+///
+/// URLs are generated using the patterns in a [`RouteTable`]. Parameters are percent-encoded and
+/// must exactly match the parameters in the route's pattern. Builders can be checked against the
+/// table when the app is constructed using [`validate()`](Self::validate) so that typos in route or
+/// parameter names are caught at startup rather than in a request.
+///
+/// Absolute URLs use the scheme and host from the request's
+/// [connection info](HttpRequest::connection_info), which takes `Forwarded` and `X-Forwarded-*`
+/// headers into account.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpRequest, HttpResponse};
+/// use actix_web_lab::web::{LabUrl, RouteTable};
+///
+/// async fn create_user(req: HttpRequest) -> actix_web::Result<HttpResponse> {
+///     let url = LabUrl::for_route("user_detail")
+///         .param("id", 42)
+///         .query("tab", "profile")
+///         .absolute(&req)?;
+///
+///     Ok(HttpResponse::Created()
+///         .insert_header(("location", url))
+///         .finish())
+/// }
+///
+/// let routes = RouteTable::new()
+///     .route("users", "/users")
+///     .route("user_detail", "/users/{id}");
+///
+/// // fail fast if the builder used in handlers does not match the table
+/// LabUrl::for_route("user_detail")
+///     .param("id", 0)
+///     .validate(&routes)
+///     .unwrap();
+///
+/// App::new()
+///     .app_data(routes.clone())
+///     .service(routes.resource("users").route(web::post().to(create_user)))
+///     .service(routes.resource("user_detail").to(HttpResponse::Ok))
+/// # ;
+/// ```
+#[derive(Debug, Clone)]
+pub struct LabUrl {
+    route: String,
+    params: BTreeMap<String, String>,
+    query: Vec<(String, String)>,
+    fragment: Option<String>,
+}
+
+impl LabUrl {
+    /// Starts building a URL for the named route.
+    pub fn for_route(name: impl Into<String>) -> Self {
+        Self {
+            route: name.into(),
+            params: BTreeMap::new(),
+            query: Vec::new(),
+            fragment: None,
+        }
+    }
+
+    /// Sets value of a path parameter.
+    pub fn param(mut self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.params.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Appends a query string parameter.
+    pub fn query(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.query.push((key.into(), value.to_string()));
+        self
+    }
+
+    /// Sets URL fragment.
+    pub fn fragment(mut self, fragment: impl Into<String>) -> Self {
+        self.fragment = Some(fragment.into());
+        self
+    }
+
+    /// Checks that the route exists in `routes` and that parameters match its pattern.
+    pub fn validate(&self, routes: &RouteTable) -> Result<(), UrlForError> {
+        routes.path_for(self).map(|_| ())
+    }
+
+    /// Returns path, query, and fragment of URL, generated using the given route table.
+    pub fn relative_with(&self, routes: &RouteTable) -> Result<String, UrlForError> {
+        let mut url = routes.path_for(self)?;
+
+        for (idx, (key, val)) in self.query.iter().enumerate() {
+            url.push(if idx == 0 { '?' } else { '&' });
+            url.push_str(&encode(key, EncodeSet::QueryComponent));
+            url.push('=');
+            url.push_str(&encode(val, EncodeSet::QueryComponent));
+        }
+
+        if let Some(fragment) = &self.fragment {
+            url.push('#');
+            url.push_str(&encode(fragment, EncodeSet::Fragment));
+        }
+
+        Ok(url)
+    }
+
+    /// Returns path, query, and fragment of URL, using the route table in the request's app data.
+    pub fn relative(&self, req: &HttpRequest) -> Result<String, UrlForError> {
+        let routes = req
+            .app_data::<RouteTable>()
+            .ok_or(UrlForError::RouteTableNotConfigured)?;
+
+        self.relative_with(routes)
+    }
+
+    /// Returns absolute URL, using the route table in the request's app data and the scheme and
+    /// host from the request's connection info.
+    pub fn absolute(&self, req: &HttpRequest) -> Result<String, UrlForError> {
+        let relative = self.relative(req)?;
+        let conn = req.connection_info();
+
+        Ok(format!("{}://{}{relative}", conn.scheme(), conn.host()))
+    }
+}
+
+/// Errors that can occur when generating URLs with [`LabUrl`].
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UrlForError {
+    /// No [`RouteTable`] was registered as app data.
+    #[display(fmt = "Route table is not configured.")]
+    RouteTableNotConfigured,
+
+    /// Route with the given name does not exist.
+    #[display(fmt = "Route `{name}` is not defined.")]
+    UnknownRoute {
+        /// Route name.
+        name: String,
+    },
+
+    /// Route pattern contains a parameter that was not given a value.
+    #[display(fmt = "Route parameter `{name}` is missing.")]
+    MissingParam {
+        /// Parameter name.
+        name: String,
+    },
+
+    /// Value was given for a parameter that is not in the route pattern.
+    #[display(fmt = "Route parameter `{name}` is not in the route pattern.")]
+    UnknownParam {
+        /// Parameter name.
+        name: String,
+    },
+}
+
+impl ResponseError for UrlForError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[test]
+    fn parses_patterns() {
+        assert_eq!(
+            parse_pattern("/a/{id:\\d{3}}/b/{tail}*").unwrap(),
+            [
+                Part::Literal("/a/".to_owned()),
+                Part::Param {
+                    name: "id".to_owned(),
+                    tail: false
+                },
+                Part::Literal("/b/".to_owned()),
+                Part::Param {
+                    name: "tail".to_owned(),
+                    tail: true
+                },
+            ],
+        );
+
+        assert!(parse_pattern("/a/{id").is_err());
+        assert!(parse_pattern("/a/{:x}").is_err());
+    }
+
+    #[test]
+    fn generates_urls() {
+        let routes = RouteTable::new()
+            .route("user", "/users/{id}")
+            .route("file", "/files/{path}*");
+
+        let url = LabUrl::for_route("user")
+            .param("id", "a b/c")
+            .query("q", "x&y")
+            .query("page", 2)
+            .fragment("top");
+        assert_eq!(
+            url.relative_with(&routes).unwrap(),
+            "/users/a%20b%2Fc?q=x%26y&page=2#top",
+        );
+
+        let url = LabUrl::for_route("file").param("path", "a/b c.txt");
+        assert_eq!(url.relative_with(&routes).unwrap(), "/files/a/b%20c.txt");
+
+        assert_eq!(
+            LabUrl::for_route("nope").validate(&routes),
+            Err(UrlForError::UnknownRoute {
+                name: "nope".to_owned()
+            }),
+        );
+        assert_eq!(
+            LabUrl::for_route("user").validate(&routes),
+            Err(UrlForError::MissingParam {
+                name: "id".to_owned()
+            }),
+        );
+        assert_eq!(
+            LabUrl::for_route("user")
+                .param("id", 1)
+                .param("idd", 1)
+                .validate(&routes),
+            Err(UrlForError::UnknownParam {
+                name: "idd".to_owned()
+            }),
+        );
+    }
+
+    #[test]
+    fn absolute_urls_use_forwarded_host() {
+        let routes = RouteTable::new().route("user", "/users/{id}");
+        let url = LabUrl::for_route("user").param("id", 1);
+
+        let req = TestRequest::default()
+            .app_data(routes)
+            .insert_header(("forwarded", "proto=https;host=example.com"))
+            .to_http_request();
+        assert_eq!(url.absolute(&req).unwrap(), "https://example.com/users/1");
+
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(
+            url.absolute(&req),
+            Err(UrlForError::RouteTableNotConfigured)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn resource_for_unknown_route() {
+        let _ = RouteTable::new().resource("nope");
+    }
+}
@@ -0,0 +1,384 @@
+//! User-Agent extractor.
+//!
+//! See [`UserAgent`] docs.
+
+use std::convert::Infallible;
+
+use actix_utils::future::{ok, Ready};
+use actix_web::{dev::Payload, http::header, FromRequest, HttpRequest};
+
+/// A product token from a [`UserAgent`] header, e.g., `Firefox/120.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Product {
+    name: String,
+    version: Option<String>,
+    comment: Option<String>,
+}
+
+impl Product {
+    /// Returns product name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns product version, if present.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns comment following the product, without parentheses, if present.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+}
+
+/// Request's `User-Agent` header, with lightweight parsing of product tokens.
+///
+/// # Extractor
+/// Extraction never fails. If the header is missing or is not valid UTF-8, [`raw()`](Self::raw)
+/// returns `None` and there are no products.
+///
+/// Browser and OS hints are based on common product tokens and comments, and are intended for
+/// analytics rather than content negotiation or access control; user agents can send any value.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::extract::UserAgent;
+///
+/// #[get("/")]
+/// async fn index(ua: UserAgent) -> impl Responder {
+///     format!(
+///         "browser: {:?}, os: {:?}, bot: {}",
+///         ua.browser(),
+///         ua.os(),
+///         ua.is_bot(),
+///     )
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserAgent {
+    raw: Option<String>,
+    products: Vec<Product>,
+}
+
+impl UserAgent {
+    /// Parses a `User-Agent` header value.
+    pub fn parse(raw: &str) -> Self {
+        Self {
+            raw: Some(raw.to_owned()),
+            products: parse_products(raw),
+        }
+    }
+
+    /// Returns header value, if present.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Returns product tokens, in the order they appear.
+    pub fn products(&self) -> &[Product] {
+        &self.products
+    }
+
+    /// Returns product with the given name, ignoring case.
+    pub fn product(&self, name: &str) -> Option<&Product> {
+        self.products
+            .iter()
+            .find(|product| product.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns name of the browser, if recognized.
+    ///
+    /// Recognizes Edge, Opera, Samsung Internet, Firefox, Chrome, and Safari.
+    pub fn browser(&self) -> Option<&'static str> {
+        // order matters since most browsers also include the products of those they derive from
+        const BROWSERS: &[(&str, &str)] = &[
+            ("Edg", "Edge"),
+            ("Edge", "Edge"),
+            ("OPR", "Opera"),
+            ("SamsungBrowser", "Samsung Internet"),
+            ("Firefox", "Firefox"),
+            ("FxiOS", "Firefox"),
+            ("CriOS", "Chrome"),
+            ("Chrome", "Chrome"),
+            ("Safari", "Safari"),
+        ];
+
+        BROWSERS
+            .iter()
+            .find(|(token, _)| self.product(token).is_some())
+            .map(|(_, browser)| *browser)
+    }
+
+    /// Returns version of the browser, if recognized.
+    pub fn browser_version(&self) -> Option<&str> {
+        let token = match self.browser()? {
+            "Edge" => self.product("Edg").or_else(|| self.product("Edge")),
+            "Opera" => self.product("OPR"),
+            "Samsung Internet" => self.product("SamsungBrowser"),
+            "Firefox" => self.product("Firefox").or_else(|| self.product("FxiOS")),
+            "Chrome" => self.product("Chrome").or_else(|| self.product("CriOS")),
+            // Safari's product version is its WebKit build; the marketing version is separate
+            _ => self.product("Version").or_else(|| self.product("Safari")),
+        };
+
+        token?.version()
+    }
+
+    /// Returns name of the operating system, if recognized.
+    ///
+    /// Recognizes Windows, Android, iOS, macOS, ChromeOS, and Linux.
+    pub fn os(&self) -> Option<&'static str> {
+        // order matters since e.g. Android comments also mention Linux
+        const SYSTEMS: &[(&str, &str)] = &[
+            ("Windows", "Windows"),
+            ("Android", "Android"),
+            ("iPhone", "iOS"),
+            ("iPad", "iOS"),
+            ("iPod", "iOS"),
+            ("Mac OS X", "macOS"),
+            ("Macintosh", "macOS"),
+            ("CrOS", "ChromeOS"),
+            ("Linux", "Linux"),
+        ];
+
+        let comments = self
+            .products
+            .iter()
+            .filter_map(Product::comment)
+            .collect::<Vec<_>>();
+
+        SYSTEMS
+            .iter()
+            .find(|(token, _)| comments.iter().any(|comment| comment.contains(token)))
+            .map(|(_, os)| *os)
+    }
+
+    /// Returns true if the user agent identifies itself as a bot, crawler, or spider.
+    pub fn is_bot(&self) -> bool {
+        self.raw.as_deref().is_some_and(|raw| {
+            let raw = raw.to_ascii_lowercase();
+            ["bot", "crawler", "spider"]
+                .iter()
+                .any(|token| raw.contains(token))
+        })
+    }
+
+    /// Returns true if the user agent hints that it is running on a mobile device.
+    pub fn is_mobile(&self) -> bool {
+        let tablet = self
+            .products
+            .iter()
+            .filter_map(Product::comment)
+            .any(|comment| comment.contains("iPad"));
+
+        self.product("Mobile").is_some()
+            || (matches!(self.os(), Some("iOS" | "Android")) && !tablet)
+    }
+}
+
+impl FromRequest for UserAgent {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let ua = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|val| val.to_str().ok())
+            .map(UserAgent::parse)
+            .unwrap_or_default();
+
+        ok(ua)
+    }
+}
+
+/// Parses product tokens and their comments, as defined in RFC 9110 §10.1.5.
+fn parse_products(raw: &str) -> Vec<Product> {
+    let mut products = Vec::<Product>::new();
+    let mut rest = raw.trim();
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('(') {
+            // comments can be nested
+            let mut depth = 1;
+            let end = after
+                .char_indices()
+                .find_map(|(idx, ch)| {
+                    match ch {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+
+                    (depth == 0).then_some(idx)
+                })
+                .unwrap_or(after.len());
+
+            let comment = after[..end].trim().to_owned();
+
+            // comments without a preceding product are ignored
+            if let Some(product) = products.last_mut() {
+                match &mut product.comment {
+                    Some(existing) => {
+                        existing.push_str("; ");
+                        existing.push_str(&comment);
+                    }
+                    None => product.comment = Some(comment),
+                }
+            }
+
+            rest = after.get(end + 1..).unwrap_or_default().trim_start();
+            continue;
+        }
+
+        let end = rest
+            .find(|ch: char| ch.is_whitespace() || ch == '(')
+            .unwrap_or(rest.len());
+        let (token, remaining) = rest.split_at(end);
+        rest = remaining.trim_start();
+
+        let (name, version) = match token.split_once('/') {
+            Some((name, version)) => (name, Some(version.to_owned())),
+            None => (token, None),
+        };
+
+        if !name.is_empty() {
+            products.push(Product {
+                name: name.to_owned(),
+                version,
+                comment: None,
+            });
+        }
+    }
+
+    products
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    const CHROME_WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+        (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    const EDGE: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like \
+        Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.2210.61";
+    const SAFARI_IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) \
+        AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1";
+    const FIREFOX_ANDROID: &str =
+        "Mozilla/5.0 (Android 14; Mobile; rv:120.0) Gecko/120.0 Firefox/120.0";
+    const FIREFOX_LINUX: &str =
+        "Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0";
+    const GOOGLEBOT: &str =
+        "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+    #[test]
+    fn parses_products() {
+        let ua = UserAgent::parse(CHROME_WINDOWS);
+        let products = ua
+            .products()
+            .iter()
+            .map(|product| (product.name(), product.version(), product.comment()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            products,
+            [
+                ("Mozilla", Some("5.0"), Some("Windows NT 10.0; Win64; x64")),
+                ("AppleWebKit", Some("537.36"), Some("KHTML, like Gecko")),
+                ("Chrome", Some("120.0.0.0"), None),
+                ("Safari", Some("537.36"), None),
+            ],
+        );
+
+        let ua = UserAgent::parse("curl/8.4.0");
+        assert_eq!(ua.products().len(), 1);
+        assert_eq!(ua.product("CURL").unwrap().version(), Some("8.4.0"));
+
+        let ua = UserAgent::parse("  app (a (nested) comment) (more) lib/1 (unterminated");
+        let products = ua
+            .products()
+            .iter()
+            .map(|product| (product.name(), product.version(), product.comment()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            products,
+            [
+                ("app", None, Some("a (nested) comment; more")),
+                ("lib", Some("1"), Some("unterminated")),
+            ],
+        );
+    }
+
+    #[test]
+    fn hints() {
+        let cases = [
+            (
+                CHROME_WINDOWS,
+                Some("Chrome"),
+                Some("120.0.0.0"),
+                Some("Windows"),
+                false,
+            ),
+            (
+                EDGE,
+                Some("Edge"),
+                Some("120.0.2210.61"),
+                Some("Windows"),
+                false,
+            ),
+            (
+                SAFARI_IPHONE,
+                Some("Safari"),
+                Some("17.1"),
+                Some("iOS"),
+                true,
+            ),
+            (
+                FIREFOX_ANDROID,
+                Some("Firefox"),
+                Some("120.0"),
+                Some("Android"),
+                true,
+            ),
+            (
+                FIREFOX_LINUX,
+                Some("Firefox"),
+                Some("120.0"),
+                Some("Linux"),
+                false,
+            ),
+            (GOOGLEBOT, None, None, None, false),
+        ];
+
+        for (raw, browser, version, os, mobile) in cases {
+            let ua = UserAgent::parse(raw);
+            assert_eq!(ua.browser(), browser, "{raw}");
+            assert_eq!(ua.browser_version(), version, "{raw}");
+            assert_eq!(ua.os(), os, "{raw}");
+            assert_eq!(ua.is_mobile(), mobile, "{raw}");
+            assert_eq!(ua.is_bot(), raw == GOOGLEBOT, "{raw}");
+        }
+    }
+
+    #[test]
+    fn extracts_infallibly() {
+        let req = TestRequest::default()
+            .insert_header((header::USER_AGENT, FIREFOX_LINUX))
+            .to_http_request();
+        let ua = UserAgent::extract(&req).into_inner().unwrap();
+        assert_eq!(ua.raw(), Some(FIREFOX_LINUX));
+        assert_eq!(ua.browser(), Some("Firefox"));
+
+        let req = TestRequest::default().to_http_request();
+        let ua = UserAgent::extract(&req).into_inner().unwrap();
+        assert_eq!(ua, UserAgent::default());
+        assert_eq!(ua.raw(), None);
+        assert_eq!(ua.browser(), None);
+        assert!(!ua.is_bot());
+    }
+}
@@ -16,6 +16,29 @@ use futures_core::Stream;
 use futures_util::StreamExt as _;
 use local_channel::mpsc;
 
+pub use crate::{
+    cache_aside::{cache_aside, CacheAside, CacheStore, CachedBytes},
+    clock::{Clock, SystemClock},
+    connect_data::ConnectInfoPlugin,
+    entropy::{Entropy, SystemEntropy},
+    forward_headers::{ForwardHeaders, NodeIdentifier},
+    response_ext::ServiceResponseExt,
+    sharded_map::ShardedMap,
+    trusted_proxies::{InvalidIpRange, IpRange, TrustedProxies},
+};
+
+/// Scrubbing of sensitive values before they are logged.
+pub mod scrub {
+    pub use crate::scrub::{Hash, Mask, ScrubPolicy, Scrubber};
+}
+
+/// Percent-encoding and URL path utilities.
+pub mod uri {
+    pub use crate::uri::{
+        decode, decode_query_component, encode, EncodeSet, SafePathBuf, UnsafePathError,
+    };
+}
+
 /// Returns an effectively cloned payload that supports streaming efficiently.
 ///
 /// The cloned payload:
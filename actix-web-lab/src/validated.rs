@@ -0,0 +1,332 @@
+//! Validation wrapper for extractors, using `garde`.
+//!
+//! See [`Validated`] docs.
+
+use std::collections::BTreeMap;
+
+use actix_web::{
+    dev::Payload, http::StatusCode, web, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+use derive_more::{Display, Error};
+use futures_core::future::LocalBoxFuture;
+use garde::{Report, Validate};
+use serde_json::json;
+use tracing::debug;
+
+use crate::extract::{Json, JsonOrForm, Query, UrlEncodedForm};
+
+/// An extractor whose extracted value can be validated by [`Validated`].
+pub trait Validatable: FromRequest {
+    /// Type of the extracted value.
+    type Inner;
+
+    /// Returns a reference to the extracted value.
+    fn inner(&self) -> &Self::Inner;
+}
+
+impl<T, const LIMIT: usize> Validatable for Json<T, LIMIT>
+where
+    Self: FromRequest,
+{
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Validatable for Query<T>
+where
+    Self: FromRequest,
+{
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, const LIMIT: usize> Validatable for UrlEncodedForm<T, LIMIT>
+where
+    Self: FromRequest,
+{
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, const LIMIT: usize> Validatable for JsonOrForm<T, LIMIT>
+where
+    Self: FromRequest,
+{
+    type Inner = T;
+
+    fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Extractor wrapper that validates extracted values using [`garde`].
+///
+/// Wraps the [`Json`], [`Query`], [`UrlEncodedForm`], and [`JsonOrForm`] extractors, or any other
+/// extractor implementing [`Validatable`]. After the inner extractor succeeds, its value is
+/// validated and the handler is only called if validation passes.
+///
+/// Validation failures respond with `422 Unprocessable Entity` and a JSON body listing messages
+/// for each invalid field, keyed by paths such as `address.city` and `items[1].sku`. Errors from
+/// the inner extractor respond as they would without this wrapper.
+///
+/// # Validation Context
+/// Types with a custom [`Validate::Context`] receive the context registered as app data, either
+/// directly or wrapped in [`Data`](web::Data). If no context is registered, its default is used.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::{Json, Validated};
+/// use garde::Validate;
+/// use serde::Deserialize;
+///
+/// struct Limits {
+///     max_quantity: u32,
+/// }
+///
+/// impl Default for Limits {
+///     fn default() -> Self {
+///         Self { max_quantity: 10 }
+///     }
+/// }
+///
+/// #[derive(Deserialize, Validate)]
+/// #[garde(context(Limits))]
+/// struct Order {
+///     #[garde(length(min = 1))]
+///     sku: String,
+///
+///     #[garde(custom(within_limit))]
+///     quantity: u32,
+/// }
+///
+/// fn within_limit(quantity: &u32, limits: &Limits) -> garde::Result {
+///     if *quantity > limits.max_quantity {
+///         return Err(garde::Error::new("quantity is too large"));
+///     }
+///
+///     Ok(())
+/// }
+///
+/// #[post("/orders")]
+/// async fn create_order(order: Validated<Json<Order>>) -> String {
+///     format!("ordered {} of {}", order.quantity, order.sku)
+/// }
+///
+/// App::new()
+///     .app_data(Limits { max_quantity: 100 })
+///     .service(create_order)
+/// # ;
+/// ```
+#[derive(Debug)]
+pub struct Validated<E>(pub E);
+
+impl_more::impl_deref_and_mut!(<E> in Validated<E> => E);
+
+impl<E> Validated<E> {
+    /// Unwraps into inner extractor.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E> FromRequest for Validated<E>
+where
+    E: Validatable + 'static,
+    E::Future: 'static,
+    E::Inner: Validate,
+    <E::Inner as Validate>::Context: Default + 'static,
+{
+    type Error = ValidatedError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let fut = E::from_request(&req, payload);
+
+        Box::pin(async move {
+            let extracted = fut
+                .await
+                .map_err(|err| ValidatedError::Extract(err.into()))?;
+
+            let ctx = req
+                .app_data::<<E::Inner as Validate>::Context>()
+                .or_else(|| {
+                    req.app_data::<web::Data<<E::Inner as Validate>::Context>>()
+                        .map(|data| data.get_ref())
+                });
+
+            let res = match ctx {
+                Some(ctx) => extracted.inner().validate(ctx),
+                None => extracted.inner().validate(&Default::default()),
+            };
+
+            match res {
+                Ok(()) => Ok(Validated(extracted)),
+
+                Err(report) => {
+                    debug!(
+                        "Failed to validate {} in handler: {}: {report}",
+                        core::any::type_name::<E::Inner>(),
+                        req.match_name().unwrap_or_else(|| req.path())
+                    );
+
+                    Err(ValidatedError::Validation(report))
+                }
+            }
+        })
+    }
+}
+
+/// Errors that can occur when extracting a [`Validated`] value.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ValidatedError {
+    /// Inner extractor failed.
+    #[display(fmt = "{_0}")]
+    Extract(#[error(not(source))] actix_web::Error),
+
+    /// Extracted value failed validation.
+    #[display(fmt = "Validation error: {_0}")]
+    Validation(Report),
+}
+
+impl ValidatedError {
+    /// Returns validation messages keyed by field path, if extracted value failed validation.
+    pub fn field_errors(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        match self {
+            Self::Validation(report) => {
+                let mut fields = BTreeMap::<_, Vec<_>>::new();
+
+                for (path, err) in report.iter() {
+                    fields
+                        .entry(path.to_string())
+                        .or_default()
+                        .push(err.message().to_owned());
+                }
+
+                Some(fields)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ResponseError for ValidatedError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Extract(err) => err.as_response_error().status_code(),
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::Extract(err) => err.error_response(),
+            Self::Validation(_) => HttpResponse::UnprocessableEntity()
+                .json(json!({ "errors": self.field_errors().unwrap_or_default() })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use super::*;
+
+    struct Limits {
+        max_quantity: u32,
+    }
+
+    impl Default for Limits {
+        fn default() -> Self {
+            Self { max_quantity: 10 }
+        }
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    #[garde(context(Limits))]
+    struct Order {
+        #[garde(length(min = 1))]
+        sku: String,
+
+        #[garde(custom(within_limit))]
+        quantity: u32,
+    }
+
+    fn within_limit(quantity: &u32, limits: &Limits) -> garde::Result {
+        if *quantity > limits.max_quantity {
+            return Err(garde::Error::new("quantity is too large"));
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn validates_query() {
+        let (req, mut pl) = TestRequest::with_uri("/?sku=a&quantity=5").to_http_parts();
+        let order = Validated::<Query<Order>>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(order.quantity, 5);
+
+        let (req, mut pl) = TestRequest::with_uri("/?sku=&quantity=50").to_http_parts();
+        let err = Validated::<Query<Order>>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let fields = err.field_errors().unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields["quantity"], ["quantity is too large"]);
+
+        let res = err.error_response();
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let body = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(body["errors"]["quantity"][0], "quantity is too large");
+    }
+
+    #[actix_web::test]
+    async fn uses_registered_context() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::Data::new(Limits { max_quantity: 100 }))
+            .insert_header(header::ContentType::json())
+            .set_payload(r#"{ "sku": "a", "quantity": 50 }"#)
+            .to_http_parts();
+        let order = Validated::<Json<Order>>::from_request(&req, &mut pl)
+            .await
+            .unwrap()
+            .into_inner()
+            .into_inner();
+        assert_eq!(order.quantity, 50);
+    }
+
+    #[actix_web::test]
+    async fn extractor_errors() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::plaintext())
+            .set_payload(r#"{ "sku": "a", "quantity": 5 }"#)
+            .to_http_parts();
+        let err = Validated::<Json<Order>>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ValidatedError::Extract(_)));
+        assert_eq!(
+            err.status_code(),
+            actix_web::error::JsonPayloadError::ContentType.status_code(),
+        );
+        assert!(err.field_errors().is_none());
+    }
+}
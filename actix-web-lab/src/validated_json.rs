@@ -0,0 +1,308 @@
+//! Validated JSON extractor.
+//!
+//! See [`ValidatedJson`] docs.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{
+    dev::Payload, error::JsonPayloadError, http::StatusCode, FromRequest, HttpRequest,
+    HttpResponse, ResponseError,
+};
+use derive_more::{Display, Error};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use tracing::debug;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use crate::json::{JsonBody, DEFAULT_JSON_LIMIT};
+
+/// JSON extractor that validates payloads using the [`validator`] crate.
+///
+/// Payloads are read and deserialized exactly as with the [`Json`](crate::extract::Json)
+/// extractor, including the `LIMIT` const generic parameter, after which
+/// [`Validate::validate()`] is called on the inner value.
+///
+/// Payload errors respond in the same way as the `Json` extractor. Validation failures respond
+/// with `422 Unprocessable Entity` and a JSON body listing messages for each invalid field.
+/// Fields of nested structs and lists are identified by paths such as `address.city` and
+/// `items[1].sku`; messages fall back to the validator's error code when no message is set.
+///
+/// ```json
+/// { "errors": { "email": ["email"], "name": ["name must not be empty"] } }
+/// ```
+///
+/// As with `Json`, use `Result<ValidatedJson<T>, ValidatedJsonError>` as the extractor type to
+/// handle errors in the handler instead.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, App};
+/// use actix_web_lab::extract::ValidatedJson;
+/// use serde::Deserialize;
+/// use validator::Validate;
+///
+/// #[derive(Deserialize, Validate)]
+/// struct Signup {
+///     #[validate(email)]
+///     email: String,
+///
+///     #[validate(length(min = 1, message = "name must not be empty"))]
+///     name: String,
+/// }
+///
+/// #[post("/signup")]
+/// async fn signup(signup: ValidatedJson<Signup>) -> String {
+///     format!("Welcome {}!", signup.name)
+/// }
+/// # App::new().service(signup);
+/// ```
+#[derive(Debug)]
+pub struct ValidatedJson<T, const LIMIT: usize = DEFAULT_JSON_LIMIT>(pub T);
+
+mod waiting_on_derive_more_to_start_using_syn_2_due_to_proc_macro_panic {
+    use super::*;
+
+    impl<T, const LIMIT: usize> std::ops::Deref for ValidatedJson<T, LIMIT> {
+        type Target = T;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T, const LIMIT: usize> std::ops::DerefMut for ValidatedJson<T, LIMIT> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl<T: fmt::Display, const LIMIT: usize> fmt::Display for ValidatedJson<T, LIMIT> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T, const LIMIT: usize> ValidatedJson<T, LIMIT> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate, const LIMIT: usize> FromRequest for ValidatedJson<T, LIMIT> {
+    type Error = ValidatedJsonError;
+    type Future = ValidatedJsonExtractFut<T, LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        ValidatedJsonExtractFut {
+            req: Some(req.clone()),
+            fut: JsonBody::new(req, payload),
+        }
+    }
+}
+
+/// Future for the [`ValidatedJson`] extractor.
+pub struct ValidatedJsonExtractFut<T, const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    fut: JsonBody<T, LIMIT>,
+}
+
+impl<T: DeserializeOwned + Validate, const LIMIT: usize> Future
+    for ValidatedJsonExtractFut<T, LIMIT>
+{
+    type Output = Result<ValidatedJson<T, LIMIT>, ValidatedJsonError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = ready!(Pin::new(&mut this.fut).poll(cx))
+            .map_err(ValidatedJsonError::Json)
+            .and_then(|data| match data.validate() {
+                Ok(()) => Ok(data),
+                Err(err) => Err(ValidatedJsonError::Validation(err)),
+            });
+
+        if let Err(err) = &res {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to extract ValidatedJson<{}> from payload in handler: {}: {err}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+        }
+
+        Poll::Ready(res.map(ValidatedJson))
+    }
+}
+
+/// Errors that can occur when extracting a [`ValidatedJson`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ValidatedJsonError {
+    /// Payload could not be read or deserialized.
+    #[display(fmt = "{_0}")]
+    Json(JsonPayloadError),
+
+    /// Payload was deserialized but failed validation.
+    #[display(fmt = "Validation error: {_0}")]
+    Validation(ValidationErrors),
+}
+
+impl ValidatedJsonError {
+    /// Returns validation messages keyed by field path, if payload failed validation.
+    pub fn field_errors(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        match self {
+            Self::Validation(errors) => {
+                let mut fields = BTreeMap::new();
+                collect_field_errors(errors, "", &mut fields);
+                Some(fields)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn collect_field_errors(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut BTreeMap<String, Vec<String>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            (*field).to_owned()
+        } else {
+            format!("{prefix}.{field}")
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = errors.iter().map(|err| match &err.message {
+                    Some(message) => message.to_string(),
+                    None => err.code.to_string(),
+                });
+
+                fields.entry(path).or_default().extend(messages);
+            }
+
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, fields),
+
+            ValidationErrorsKind::List(items) => {
+                for (idx, errors) in items {
+                    collect_field_errors(errors, &format!("{path}[{idx}]"), fields);
+                }
+            }
+        }
+    }
+}
+
+impl ResponseError for ValidatedJsonError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Json(err) => err.status_code(),
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::Json(err) => err.error_response(),
+            Self::Validation(_) => HttpResponse::UnprocessableEntity()
+                .json(json!({ "errors": self.field_errors().unwrap_or_default() })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Item {
+        #[validate(length(min = 1))]
+        sku: String,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Order {
+        #[validate(email)]
+        email: String,
+
+        #[validate(range(min = 1, message = "must order at least one"))]
+        quantity: u32,
+
+        #[validate]
+        items: Vec<Item>,
+    }
+
+    async fn extract<const LIMIT: usize>(
+        payload: &'static str,
+    ) -> Result<ValidatedJson<Order, LIMIT>, ValidatedJsonError> {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload(payload)
+            .to_http_parts();
+
+        ValidatedJson::<Order, LIMIT>::from_request(&req, &mut pl).await
+    }
+
+    #[actix_web::test]
+    async fn valid_payload() {
+        let order = extract::<DEFAULT_JSON_LIMIT>(
+            r#"{ "email": "ana@example.com", "quantity": 2, "items": [{ "sku": "a" }] }"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(order.quantity, 2);
+    }
+
+    #[actix_web::test]
+    async fn invalid_payload() {
+        let err = extract::<DEFAULT_JSON_LIMIT>(
+            r#"{ "email": "nope", "quantity": 0, "items": [{ "sku": "a" }, { "sku": "" }] }"#,
+        )
+        .await
+        .unwrap_err();
+
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({
+                "errors": {
+                    "email": ["email"],
+                    "items[1].sku": ["length"],
+                    "quantity": ["must order at least one"],
+                },
+            }),
+        );
+    }
+
+    #[actix_web::test]
+    async fn payload_errors() {
+        let err = extract::<DEFAULT_JSON_LIMIT>(r#"{ "email": "#)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ValidatedJsonError::Json(_)));
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert!(err.field_errors().is_none());
+
+        let err = extract::<8>(r#"{ "email": "ana@example.com" }"#)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,348 @@
+//! Warm-up and readiness gating middleware.
+//!
+//! See [`Warmup`] docs.
+
+use std::{
+    fmt,
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    Error, HttpResponse,
+};
+use futures_core::future::LocalBoxFuture;
+use serde_json::json;
+use tokio::sync::watch;
+use tracing::debug;
+
+type WarmupTask = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+struct Inner {
+    tasks: Mutex<Vec<(String, WarmupTask)>>,
+    pending: Mutex<Vec<String>>,
+    started: AtomicBool,
+    ready: watch::Sender<bool>,
+}
+
+/// A middleware that responds with `503 Service Unavailable` until warm-up tasks complete.
+///
+/// Warm-up tasks, such as priming caches or building lookup tables, are registered using
+/// [`task()`](Self::task) and are spawned when the first worker starts. Until all of them have
+/// completed, requests are rejected with a `503 Service Unavailable` response and a `Retry-After`
+/// header, except those to paths registered using [`allow()`](Self::allow), such as liveness
+/// checks.
+///
+/// A readiness check endpoint, responding with `200 OK` once warm-up has completed and
+/// `503 Service Unavailable` before then, can be served using
+/// [`readiness_path()`](Self::readiness_path). Its JSON body lists the tasks still pending, e.g., `{"ready":false,"pending":["prime-cache"]}`.
+/// Readiness can also be queried directly using [`is_ready()`](Self::is_ready) and
+/// [`ready()`](Self::ready), for integration with other health checks.
+///
+/// The middleware is cheap to clone and should be constructed outside the `HttpServer` factory
+/// closure so that all workers share its warm-up state.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpResponse, HttpServer};
+/// use actix_web_lab::middleware::Warmup;
+///
+/// # fn run() -> std::io::Result<()> {
+/// let warmup = Warmup::new()
+///     .task("prime-cache", async {
+///         // load frequently accessed items ...
+///     })
+///     .readiness_path("/readyz")
+///     .allow("/livez");
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .wrap(warmup.clone())
+///         .route("/livez", web::get().to(HttpResponse::Ok))
+/// })
+/// # ; Ok(()) }
+/// ```
+#[derive(Clone)]
+pub struct Warmup {
+    inner: Arc<Inner>,
+    readiness_path: Option<String>,
+    allowed_paths: Vec<String>,
+}
+
+impl Warmup {
+    /// Constructs new warm-up middleware with no warm-up tasks.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                tasks: Mutex::new(Vec::new()),
+                pending: Mutex::new(Vec::new()),
+                started: AtomicBool::new(false),
+                ready: watch::channel(true).0,
+            }),
+            readiness_path: None,
+            allowed_paths: Vec::new(),
+        }
+    }
+
+    /// Registers a warm-up task, identified by `name`.
+    pub fn task(
+        self,
+        name: impl Into<String>,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) -> Self {
+        let name = name.into();
+
+        self.inner.pending.lock().unwrap().push(name.clone());
+        self.inner
+            .tasks
+            .lock()
+            .unwrap()
+            .push((name, Box::pin(task)));
+        self.inner.ready.send_replace(false);
+
+        self
+    }
+
+    /// Serves readiness checks at `path`.
+    pub fn readiness_path(mut self, path: impl Into<String>) -> Self {
+        self.readiness_path = Some(path.into());
+        self
+    }
+
+    /// Allows requests to `path` during warm-up.
+    pub fn allow(mut self, path: impl Into<String>) -> Self {
+        self.allowed_paths.push(path.into());
+        self
+    }
+
+    /// Returns true if all warm-up tasks have completed.
+    pub fn is_ready(&self) -> bool {
+        *self.inner.ready.borrow()
+    }
+
+    /// Returns names of warm-up tasks which have not yet completed.
+    pub fn pending(&self) -> Vec<String> {
+        self.inner.pending.lock().unwrap().clone()
+    }
+
+    /// Waits until all warm-up tasks have completed.
+    pub async fn ready(&self) {
+        let mut rx = self.inner.ready.subscribe();
+
+        while !*rx.borrow_and_update() {
+            // sender is owned by `self` so it can not be dropped while waiting
+            let _ = rx.changed().await;
+        }
+    }
+
+    /// Spawns warm-up tasks, if they have not been spawned already.
+    fn start(&self) {
+        if self.inner.started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let tasks = std::mem::take(&mut *self.inner.tasks.lock().unwrap());
+
+        for (name, task) in tasks {
+            let inner = Arc::clone(&self.inner);
+
+            actix_web::rt::spawn(async move {
+                task.await;
+                debug!("warm-up task {name} completed");
+
+                let mut pending = inner.pending.lock().unwrap();
+                pending.retain(|pending| *pending != name);
+
+                if pending.is_empty() {
+                    inner.ready.send_replace(true);
+                }
+            });
+        }
+    }
+
+    fn readiness_response(&self) -> HttpResponse {
+        let ready = self.is_ready();
+
+        let mut res = if ready {
+            HttpResponse::Ok()
+        } else {
+            HttpResponse::ServiceUnavailable()
+        };
+
+        res.insert_header(header::CacheControl(vec![header::CacheDirective::NoStore]))
+            .json(json!({ "ready": ready, "pending": self.pending() }))
+    }
+}
+
+impl Default for Warmup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Warmup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Warmup")
+            .field("ready", &self.is_ready())
+            .field("pending", &self.pending())
+            .field("readiness_path", &self.readiness_path)
+            .field("allowed_paths", &self.allowed_paths)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Warmup
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = WarmupMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        self.start();
+
+        ready(Ok(WarmupMiddleware {
+            service: Rc::new(service),
+            warmup: self.clone(),
+        }))
+    }
+}
+
+/// Service for the [`Warmup`] middleware.
+pub struct WarmupMiddleware<S> {
+    service: Rc<S>,
+    warmup: Warmup,
+}
+
+impl<S, B> Service<ServiceRequest> for WarmupMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let warmup = &self.warmup;
+
+        if warmup.readiness_path.as_deref() == Some(req.path()) {
+            let res = warmup.readiness_response();
+            return Box::pin(ready(Ok(req.into_response(res).map_into_right_body())));
+        }
+
+        let allowed = warmup.allowed_paths.iter().any(|path| path == req.path());
+
+        if !warmup.is_ready() && !allowed {
+            let res = HttpResponse::ServiceUnavailable()
+                .insert_header((header::RETRY_AFTER, "1"))
+                .finish();
+            return Box::pin(ready(Ok(req.into_response(res).map_into_right_body())));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+    use tokio::sync::oneshot;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn gates_until_ready() {
+        let (tx, rx) = oneshot::channel::<()>();
+
+        let warmup = Warmup::new()
+            .task("slow", async {
+                rx.await.unwrap();
+            })
+            .task("fast", async {})
+            .readiness_path("/readyz")
+            .allow("/livez");
+
+        let app = test::init_service(
+            App::new()
+                .wrap(warmup.clone())
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route("/livez", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // let fast task complete
+        actix_web::rt::task::yield_now().await;
+        assert!(!warmup.is_ready());
+        assert_eq!(warmup.pending(), ["slow"]);
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "1");
+
+        let req = TestRequest::with_uri("/livez").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = test::read_body(res).await;
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({ "ready": false, "pending": ["slow"] }),
+        );
+
+        tx.send(()).unwrap();
+        warmup.ready().await;
+        assert!(warmup.is_ready());
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/readyz").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = test::read_body(res).await;
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({ "ready": true, "pending": [] }),
+        );
+    }
+
+    #[actix_web::test]
+    async fn ready_without_tasks() {
+        let warmup = Warmup::new();
+        assert!(warmup.is_ready());
+        warmup.ready().await;
+
+        let app = test::init_service(
+            App::new()
+                .wrap(warmup)
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let res = test::call_service(&app, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -4,6 +4,21 @@
 
 #[cfg(feature = "spa")]
 pub use crate::spa::Spa;
+pub use crate::{
+    block_stream::{block_stream, block_stream_with_buffer, BlockStreamSender},
+    checked_routes::{CheckedRoutes, RouteConflict, RouteConflictError},
+    fallback::{Fallback, FallbackKind},
+    switch_service::{SwitchHandle, SwitchService, SwitchSlot, SwitchedService},
+    url_for::{LabUrl, RouteTable, UrlForError},
+    with_cleanup::{with_cleanup, WithCleanup, WithCleanupFut},
+};
+
+/// Constructs a new fallback service builder.
+///
+/// See [`Fallback`] docs for more details.
+pub fn fallback() -> Fallback {
+    Fallback::new()
+}
 
 /// Constructs a new Single-page Application (SPA) builder.
 ///
@@ -0,0 +1,168 @@
+//! Handler wrapper which runs async cleanup when requests are canceled.
+//!
+//! See [`with_cleanup()`] docs.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::Handler;
+use pin_project_lite::pin_project;
+
+/// Wraps `handler` so that `on_cancel` is run if the handler does not complete.
+///
+/// Handler futures are dropped when the client disconnects before a response is produced (or if
+/// the handler panics). Since `Drop` implementations cannot await, this wrapper is useful for
+/// cleanup which must be async, such as releasing distributed locks or aborting downstream work.
+///
+/// When the handler's future is dropped before completing, the future returned by `on_cancel` is
+/// spawned onto the current Actix runtime. It is not run when the handler completes, even if the
+/// response is never fully sent.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+/// use actix_web_lab::web as web_lab;
+///
+/// async fn long_job() -> &'static str {
+///     // ...acquire lock and do some work...
+///     "done"
+/// }
+///
+/// async fn release_lock() {
+///     // ...release lock...
+/// }
+///
+/// App::new().route(
+///     "/job",
+///     web::post().to(web_lab::with_cleanup(long_job, release_lock)),
+/// )
+/// # ;
+/// ```
+pub fn with_cleanup<F, C>(handler: F, on_cancel: C) -> WithCleanup<F, C> {
+    WithCleanup { handler, on_cancel }
+}
+
+/// A handler which runs a cleanup future if it does not complete.
+///
+/// See [`with_cleanup()`] docs.
+#[derive(Debug, Clone)]
+pub struct WithCleanup<F, C> {
+    handler: F,
+    on_cancel: C,
+}
+
+impl<F, Args, C, Fut> Handler<Args> for WithCleanup<F, C>
+where
+    F: Handler<Args>,
+    C: Fn() -> Fut + Clone + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    type Output = F::Output;
+    type Future = WithCleanupFut<F::Future, C, Fut>;
+
+    fn call(&self, args: Args) -> Self::Future {
+        WithCleanupFut {
+            fut: self.handler.call(args),
+            on_cancel: Some(self.on_cancel.clone()),
+        }
+    }
+}
+
+pin_project! {
+    /// Future returned by [`WithCleanup`] handlers.
+    pub struct WithCleanupFut<H, C, Fut>
+    where
+        C: Fn() -> Fut,
+        Fut: Future<Output = ()>,
+        Fut: 'static,
+    {
+        #[pin]
+        fut: H,
+        on_cancel: Option<C>,
+    }
+
+    impl<H, C, Fut> PinnedDrop for WithCleanupFut<H, C, Fut>
+    where
+        C: Fn() -> Fut,
+        Fut: Future<Output = ()>,
+        Fut: 'static,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            if let Some(on_cancel) = this.project().on_cancel.take() {
+                actix_web::rt::spawn(on_cancel());
+            }
+        }
+    }
+}
+
+impl<H, C, Fut> Future for WithCleanupFut<H, C, Fut>
+where
+    H: Future,
+    C: Fn() -> Fut,
+    Fut: Future<Output = ()> + 'static,
+{
+    type Output = H::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.fut.poll(cx));
+
+        // handler completed, so there is nothing to clean up
+        this.on_cancel.take();
+
+        Poll::Ready(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use actix_web::rt::{task::yield_now, time::timeout};
+    use futures_core::future::LocalBoxFuture;
+
+    use super::*;
+
+    fn tracked_cleanup() -> (
+        Rc<Cell<bool>>,
+        impl Fn() -> LocalBoxFuture<'static, ()> + Clone,
+    ) {
+        let cleaned_up = Rc::new(Cell::new(false));
+
+        let on_cancel = {
+            let cleaned_up = Rc::clone(&cleaned_up);
+            move || {
+                let cleaned_up = Rc::clone(&cleaned_up);
+                Box::pin(async move { cleaned_up.set(true) }) as LocalBoxFuture<'static, ()>
+            }
+        };
+
+        (cleaned_up, on_cancel)
+    }
+
+    #[actix_web::test]
+    async fn runs_cleanup_when_canceled() {
+        let (cleaned_up, on_cancel) = tracked_cleanup();
+        let handler = with_cleanup(std::future::pending::<&'static str>, on_cancel);
+
+        let res = timeout(Duration::from_millis(10), handler.call(())).await;
+        assert!(res.is_err());
+
+        yield_now().await;
+        assert!(cleaned_up.get());
+    }
+
+    #[actix_web::test]
+    async fn skips_cleanup_when_completed() {
+        let (cleaned_up, on_cancel) = tracked_cleanup();
+        let handler = with_cleanup(|| async { "done" }, on_cancel);
+
+        assert_eq!(handler.call(()).await, "done");
+
+        yield_now().await;
+        assert!(!cleaned_up.get());
+    }
+}
@@ -0,0 +1,394 @@
+//! XML extractor and responder.
+
+use std::{fmt, sync::Arc};
+
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, web, FromRequest, HttpMessage as _,
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use bytes::Bytes;
+use derive_more::{Deref, DerefMut, Display, Error};
+use futures_core::future::LocalBoxFuture;
+use futures_util::StreamExt as _;
+use mime::Mime;
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::debug;
+
+static XML_MIME: Lazy<Mime> = Lazy::new(|| "application/xml".parse().unwrap());
+
+/// Default XML payload size limit of 2MiB.
+pub const DEFAULT_XML_LIMIT: usize = 2_097_152;
+
+type ErrorHandler = Arc<dyn Fn(XmlPayloadError, &HttpRequest) -> actix_web::Error + Send + Sync>;
+
+/// XML extractor and responder, using [`quick_xml`]'s serde support.
+///
+/// # Extractor
+/// To extract typed data from a request body, the inner type `T` must implement the
+/// [`serde::Deserialize`] trait. The request must have a `Content-Type` of `application/xml`,
+/// `text/xml`, or a type with a `+xml` suffix, and the payload must be UTF-8 encoded.
+///
+/// The payload size limit and error handler are configured by registering an [`XmlConfig`] as app
+/// data. The default limit is 2MiB.
+///
+/// ```
+/// use actix_web::{error, post, App, HttpResponse};
+/// use actix_web_lab::extract::{Xml, XmlConfig};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Order {
+///     #[serde(rename = "@id")]
+///     id: u64,
+///     sku: String,
+/// }
+///
+/// #[post("/orders")]
+/// async fn create_order(order: Xml<Order>) -> String {
+///     format!("order {} for {}", order.id, order.sku)
+/// }
+///
+/// let config = XmlConfig::default()
+///     .limit(65_536)
+///     .error_handler(|err, _req| {
+///         let res = HttpResponse::BadRequest().body(format!("<error>{err}</error>"));
+///         error::InternalError::from_response(err, res).into()
+///     });
+///
+/// App::new().app_data(config).service(create_order)
+/// # ;
+/// ```
+///
+/// # Responder
+/// Serializes the inner value as XML, using the type's name as the root element name, with a
+/// `Content-Type` of `application/xml`. Responds with an empty `500 Internal Server Error`
+/// response if serialization fails.
+///
+/// ```
+/// use actix_web::{get, Responder};
+/// use actix_web_lab::respond::Xml;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Status {
+///     online: bool,
+/// }
+///
+/// #[get("/status")]
+/// async fn index() -> impl Responder {
+///     Xml(Status { online: true })
+/// }
+/// ```
+#[derive(Debug, Deref, DerefMut, Display)]
+pub struct Xml<T>(pub T);
+
+impl<T> Xml<T> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Responder for Xml<T> {
+    type Body = Bytes;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        match quick_xml::se::to_string(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(XML_MIME.clone())
+                .message_body(Bytes::from(body))
+                .unwrap(),
+
+            Err(err) => {
+                debug!("Failed to serialize XML response: {err}");
+
+                HttpResponse::InternalServerError()
+                    .message_body(Bytes::new())
+                    .unwrap()
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Xml<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let config = XmlConfig::from_req(&req).clone();
+        let payload = payload.take();
+
+        Box::pin(async move {
+            match read_xml(&req, payload, config.limit).await {
+                Ok(data) => Ok(Xml(data)),
+
+                Err(err) => {
+                    debug!(
+                        "Failed to deserialize Xml<{}> from payload in handler: {}",
+                        core::any::type_name::<T>(),
+                        req.match_name().unwrap_or_else(|| req.path())
+                    );
+
+                    Err(match &config.err_handler {
+                        Some(err_handler) => (err_handler)(err, &req),
+                        None => err.into(),
+                    })
+                }
+            }
+        })
+    }
+}
+
+async fn read_xml<T: DeserializeOwned>(
+    req: &HttpRequest,
+    mut payload: Payload,
+    limit: usize,
+) -> Result<T, XmlPayloadError> {
+    let can_parse_xml = match req.mime_type() {
+        Ok(Some(mime)) => {
+            (matches!(mime.type_().as_str(), "application" | "text") && mime.subtype() == mime::XML)
+                || mime.suffix() == Some(mime::XML)
+        }
+        _ => false,
+    };
+
+    if !can_parse_xml {
+        return Err(XmlPayloadError::ContentType);
+    }
+
+    let length = req
+        .get_header::<crate::header::ContentLength>()
+        .map(|cl| cl.into_inner());
+
+    if let Some(length) = length {
+        if length > limit {
+            return Err(XmlPayloadError::OverflowKnownLength { length, limit });
+        }
+    }
+
+    let mut buf = web::BytesMut::with_capacity(length.unwrap_or(8192).min(limit));
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if buf.len() + chunk.len() > limit {
+            return Err(XmlPayloadError::Overflow { limit });
+        }
+
+        buf.extend_from_slice(&chunk);
+    }
+
+    let xml = std::str::from_utf8(&buf).map_err(|_| XmlPayloadError::Encoding)?;
+
+    quick_xml::de::from_str(xml).map_err(XmlPayloadError::Deserialize)
+}
+
+/// Configuration for the [`Xml`] extractor.
+///
+/// Register as app data, either directly or wrapped in [`Data`](web::Data). Extractors used
+/// without a registered config use the defaults.
+#[derive(Clone)]
+pub struct XmlConfig {
+    limit: usize,
+    err_handler: Option<ErrorHandler>,
+}
+
+const DEFAULT_CONFIG: XmlConfig = XmlConfig {
+    limit: DEFAULT_XML_LIMIT,
+    err_handler: None,
+};
+
+impl XmlConfig {
+    /// Sets maximum accepted payload size, in bytes.
+    ///
+    /// The default limit is 2MiB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets custom error handler, used to convert extraction errors into responses.
+    pub fn error_handler<F>(mut self, err_handler: F) -> Self
+    where
+        F: Fn(XmlPayloadError, &HttpRequest) -> actix_web::Error + Send + Sync + 'static,
+    {
+        self.err_handler = Some(Arc::new(err_handler));
+        self
+    }
+
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|data| data.get_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+impl Default for XmlConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+impl fmt::Debug for XmlConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XmlConfig")
+            .field("limit", &self.limit)
+            .field("err_handler", &self.err_handler.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// Errors that can occur when extracting an [`Xml`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum XmlPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(fmt = "XML payload ({length} bytes) is larger than allowed (limit: {limit} bytes).")]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "XML payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not XML.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload is not valid UTF-8.
+    #[display(fmt = "XML payload is not valid UTF-8.")]
+    Encoding,
+
+    /// Payload could not be deserialized.
+    #[display(fmt = "XML deserialize error: {_0}")]
+    Deserialize(quick_xml::DeError),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl From<PayloadError> for XmlPayloadError {
+    fn from(err: PayloadError) -> Self {
+        Self::Payload(err)
+    }
+}
+
+impl ResponseError for XmlPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Encoding | Self::Deserialize(_) => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, error::InternalError, http::header, test::TestRequest};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        sku: String,
+        quantity: u32,
+    }
+
+    fn order() -> Order {
+        Order {
+            sku: "abc".to_owned(),
+            quantity: 2,
+        }
+    }
+
+    #[actix_web::test]
+    async fn round_trip() {
+        let req = TestRequest::default().to_http_request();
+        let res = Xml(order()).respond_to(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/xml",
+        );
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "<Order><sku>abc</sku><quantity>2</quantity></Order>");
+
+        for content_type in ["application/xml", "text/xml", "application/atom+xml"] {
+            let (req, mut pl) = TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload(body.clone())
+                .to_http_parts();
+            let Xml(extracted) = Xml::<Order>::from_request(&req, &mut pl).await.unwrap();
+            assert_eq!(extracted, order());
+        }
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload("<Order/>")
+            .to_http_parts();
+        let err = Xml::<Order>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(XmlConfig::default().limit(4))
+            .insert_header((header::CONTENT_TYPE, "text/xml"))
+            .set_payload("<Order/>")
+            .to_http_parts();
+        let err = Xml::<Order>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "text/xml"))
+            .set_payload("<Order><sku>abc</sku></Order>")
+            .to_http_parts();
+        let err = Xml::<Order>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn custom_error_handler() {
+        let config = XmlConfig::default().error_handler(|err, _req| {
+            let res = HttpResponse::UnprocessableEntity().body("<error/>");
+            InternalError::from_response(err, res).into()
+        });
+
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::Data::new(config))
+            .insert_header((header::CONTENT_TYPE, "text/xml"))
+            .set_payload("not xml")
+            .to_http_parts();
+        let err = Xml::<Order>::from_request(&req, &mut pl).await.unwrap_err();
+
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "<error/>");
+    }
+}
@@ -0,0 +1,311 @@
+//! YAML extractor.
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use actix_web::{
+    dev::Payload, error::PayloadError, http::StatusCode, FromRequest, HttpMessage as _,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use derive_more::{Display, Error};
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+use crate::bytes::{BytesBody, BytesPayloadError};
+
+/// Default YAML payload size limit of 2MiB.
+pub const DEFAULT_YAML_LIMIT: usize = 2_097_152;
+
+/// YAML extractor.
+///
+/// To extract typed data from a request body, the inner type `T` must implement the
+/// [`serde::Deserialize`] trait. The request must have a `Content-Type` of `application/yaml`,
+/// `application/x-yaml`, `text/yaml`, `text/x-yaml`, or a type with a `+yaml` suffix.
+///
+/// Use the `LIMIT` const generic parameter to control the payload size limit. The default limit
+/// that is exported (`DEFAULT_YAML_LIMIT`) is 2MiB.
+///
+/// Deserialization errors result in a `400 Bad Request` response. In debug builds, the response
+/// body includes the error and its line and column in the payload; release builds omit these
+/// details since they can reveal the expected structure of the document.
+///
+/// # Examples
+/// ```
+/// use actix_web::{put, App};
+/// use actix_web_lab::extract::Yaml;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+///     replicas: u32,
+/// }
+///
+/// #[put("/config")]
+/// async fn upload_config(config: Yaml<Config, 65_536>) -> String {
+///     format!("{} will run {} replicas", config.name, config.replicas)
+/// }
+/// # App::new().service(upload_config);
+/// ```
+#[derive(Debug)]
+pub struct Yaml<T, const LIMIT: usize = DEFAULT_YAML_LIMIT>(pub T);
+
+mod waiting_on_derive_more_to_start_using_syn_2_due_to_proc_macro_panic {
+    use super::*;
+
+    impl<T, const LIMIT: usize> std::ops::Deref for Yaml<T, LIMIT> {
+        type Target = T;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T, const LIMIT: usize> std::ops::DerefMut for Yaml<T, LIMIT> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl<T: fmt::Display, const LIMIT: usize> fmt::Display for Yaml<T, LIMIT> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+impl<T, const LIMIT: usize> Yaml<T, LIMIT> {
+    /// Unwraps into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned, const LIMIT: usize> FromRequest for Yaml<T, LIMIT> {
+    type Error = YamlPayloadError;
+    type Future = YamlExtractFut<T, LIMIT>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let can_parse_yaml = match req.mime_type() {
+            Ok(Some(mime)) => {
+                (matches!(mime.type_().as_str(), "application" | "text")
+                    && matches!(mime.subtype().as_str(), "yaml" | "x-yaml"))
+                    || mime.suffix().map(|s| s.as_str()) == Some("yaml")
+            }
+            _ => false,
+        };
+
+        YamlExtractFut {
+            req: Some(req.clone()),
+            fut: can_parse_yaml.then(|| BytesBody::new(req, payload)),
+            _res: PhantomData,
+        }
+    }
+}
+
+/// Future for the [`Yaml`] extractor.
+pub struct YamlExtractFut<T, const LIMIT: usize> {
+    req: Option<HttpRequest>,
+    fut: Option<BytesBody<LIMIT>>,
+    _res: PhantomData<T>,
+}
+
+impl<T, const LIMIT: usize> Unpin for YamlExtractFut<T, LIMIT> {}
+
+impl<T: DeserializeOwned, const LIMIT: usize> Future for YamlExtractFut<T, LIMIT> {
+    type Output = Result<Yaml<T, LIMIT>, YamlPayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        let res = match &mut this.fut {
+            None => Err(YamlPayloadError::ContentType),
+            Some(fut) => ready!(Pin::new(fut).poll(cx))
+                .map_err(YamlPayloadError::from)
+                .and_then(|body| {
+                    serde_yaml::from_slice(&body).map_err(YamlPayloadError::Deserialize)
+                }),
+        };
+
+        if let Err(err) = &res {
+            let req = this.req.take().unwrap();
+
+            debug!(
+                "Failed to deserialize Yaml<{}> from payload in handler: {}",
+                core::any::type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            if let Some((line, column)) = err.location() {
+                debug!("YAML payload is invalid at line {line}, column {column}");
+            }
+        }
+
+        Poll::Ready(res.map(Yaml))
+    }
+}
+
+/// Errors that can occur when extracting a [`Yaml`] payload.
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum YamlPayloadError {
+    /// Payload size is bigger than allowed and content length header set.
+    #[display(
+        fmt = "YAML payload ({length} bytes) is larger than allowed (limit: {limit} bytes)."
+    )]
+    OverflowKnownLength {
+        /// Length reported by the content length header.
+        length: usize,
+
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Payload size is bigger than allowed but no content length header set.
+    #[display(fmt = "YAML payload has exceeded limit ({limit} bytes).")]
+    Overflow {
+        /// Configured payload size limit.
+        limit: usize,
+    },
+
+    /// Content type of the request is not YAML.
+    #[display(fmt = "Content type error")]
+    ContentType,
+
+    /// Payload could not be deserialized.
+    #[display(fmt = "YAML deserialize error: {_0}")]
+    Deserialize(serde_yaml::Error),
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {_0}")]
+    Payload(PayloadError),
+}
+
+impl YamlPayloadError {
+    /// Returns 1-based line and column in the payload at which deserialization failed, if known.
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Deserialize(err) => err.location().map(|loc| (loc.line(), loc.column())),
+            _ => None,
+        }
+    }
+}
+
+impl From<BytesPayloadError> for YamlPayloadError {
+    fn from(err: BytesPayloadError) -> Self {
+        match err {
+            BytesPayloadError::OverflowKnownLength { length, limit } => {
+                Self::OverflowKnownLength { length, limit }
+            }
+            BytesPayloadError::Overflow { limit } => Self::Overflow { limit },
+            BytesPayloadError::ContentType => Self::ContentType,
+            BytesPayloadError::Payload(err) => Self::Payload(err),
+        }
+    }
+}
+
+impl ResponseError for YamlPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::OverflowKnownLength { .. } | Self::Overflow { .. } => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            Self::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Deserialize(_) => StatusCode::BAD_REQUEST,
+            Self::Payload(err) => err.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+
+        match self {
+            // serde_yaml errors include the location in their display output
+            Self::Deserialize(_) if cfg!(debug_assertions) => res.body(self.to_string()),
+            Self::Deserialize(_) => res.body("YAML deserialize error"),
+            _ => res.body(self.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{body, http::header, test::TestRequest, web::Bytes};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        replicas: u32,
+    }
+
+    #[actix_web::test]
+    async fn extracts() {
+        for content_type in [
+            "application/yaml",
+            "text/x-yaml",
+            "application/vnd.app+yaml",
+        ] {
+            let (req, mut pl) = TestRequest::default()
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .set_payload("name: web\nreplicas: 3\n")
+                .to_http_parts();
+
+            let Yaml(config) = Yaml::<Config>::from_request(&req, &mut pl).await.unwrap();
+            assert_eq!(
+                config,
+                Config {
+                    name: "web".to_owned(),
+                    replicas: 3,
+                },
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn errors() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header(header::ContentType::json())
+            .set_payload("name: web")
+            .to_http_parts();
+        let err = Yaml::<Config>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/yaml"))
+            .set_payload(Bytes::from_static(&[b'a'; 100]))
+            .to_http_parts();
+        let err = Yaml::<Config, 10>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/yaml"))
+            .set_payload("name: web\nreplicas: many\n")
+            .to_http_parts();
+        let err = Yaml::<Config>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.location().map(|(line, _)| line), Some(2));
+
+        // tests are built with debug assertions so details are included
+        let body = body::to_bytes(err.error_response().into_body())
+            .await
+            .unwrap();
+        assert!(
+            String::from_utf8_lossy(&body).contains("line 2"),
+            "unexpected body: {body:?}",
+        );
+    }
+}